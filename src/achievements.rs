@@ -0,0 +1,296 @@
+//! Achievements: milestones a colony can reach.
+//!
+//! Once a second in play the colony is checked against each milestone: 100
+//! ants alive at once, 1000 leaves harvested, coming through a flood with the
+//! queen alive, and digging all the way down to the bottom of the world. A
+//! newly reached one pops up a toast and is kept in the player's profile (see
+//! `profile.rs`), so it stays unlocked from game to game. Nothing unlocks in a
+//! run where sandbox mode has been on or the console has been used.
+//!
+//! Achievements (A) on the title screen lists them all, locked and unlocked.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+use serde::{Deserialize, Serialize};
+
+use crate::ants::{Ant, Caste};
+use crate::notifications::{Toast, ToastStack};
+use crate::profile::Profile;
+use crate::sandbox::Sandbox;
+use crate::sprites;
+use crate::water::Groundwater;
+use crate::world::{FungusGarden, WorldGrid};
+use crate::{GameState, ResetRun, in_game, reset_resource};
+
+pub struct AchievementsPlugin;
+
+impl Plugin for AchievementsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AchievementRun>()
+            .add_systems(OnEnter(GameState::Achievements), enter_achievements)
+            .add_systems(OnExit(GameState::Achievements), exit_achievements)
+            .add_systems(
+                Update,
+                check_achievements.run_if(in_game.and(on_timer(Duration::from_secs(1)))),
+            )
+            .add_systems(
+                Update,
+                achievements_input.run_if(in_state(GameState::Achievements)),
+            )
+            .add_systems(ResetRun, reset_resource::<AchievementRun>);
+    }
+}
+
+/// Ants alive at once for Hundred Strong
+const POPULATION_GOAL: usize = 100;
+/// Leaves harvested in one run for Leaf Hoard
+const LEAVES_GOAL: u32 = 1000;
+/// Color of unlock toasts
+const TOAST_BACKGROUND: Color = Color::srgba(0.45, 0.35, 0.05, 0.9);
+
+/// A milestone a colony can reach
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum Achievement {
+    HundredAnts,
+    ThousandLeaves,
+    WeatheredFlood,
+    Bedrock,
+}
+
+impl Achievement {
+    pub const ALL: [Achievement; 4] = [
+        Achievement::HundredAnts,
+        Achievement::ThousandLeaves,
+        Achievement::WeatheredFlood,
+        Achievement::Bedrock,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Achievement::HundredAnts => "Hundred Strong",
+            Achievement::ThousandLeaves => "Leaf Hoard",
+            Achievement::WeatheredFlood => "High and Dry",
+            Achievement::Bedrock => "Bedrock",
+        }
+    }
+
+    /// What it takes to unlock
+    pub fn description(&self) -> &'static str {
+        match self {
+            Achievement::HundredAnts => "Have 100 ants alive at once",
+            Achievement::ThousandLeaves => "Harvest 1000 leaves in one game",
+            Achievement::WeatheredFlood => "Come through a flood with the queen alive",
+            Achievement::Bedrock => "Dig down to the bottom of the world",
+        }
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// How this run has gone, for achievements that depend on more than the
+/// colony as it stands
+#[derive(Resource, Default)]
+pub struct AchievementRun {
+    /// Water has flooded the tunnels and not yet drained
+    pub flooded: bool,
+    /// Sandbox mode has been on at some point
+    pub sandboxed: bool,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the achievements screen root
+#[derive(Component)]
+struct AchievementsScreen;
+
+/// Marker for the button back to the title screen
+#[derive(Component)]
+struct BackButton;
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Check the colony against each achievement still locked, unlocking and
+/// announcing any it has reached
+#[allow(clippy::too_many_arguments)]
+fn check_achievements(
+    mut commands: Commands,
+    caste_query: Query<&Caste, With<Ant>>,
+    stack_query: Query<(Entity, &ToastStack)>,
+    fungus_garden: Res<FungusGarden>,
+    groundwater: Res<Groundwater>,
+    world_grid: Res<WorldGrid>,
+    sandbox: Res<Sandbox>,
+    mut run: ResMut<AchievementRun>,
+    mut profile: ResMut<Profile>,
+) {
+    run.sandboxed |= sandbox.enabled;
+    if run.sandboxed {
+        return;
+    }
+
+    let queen_alive = caste_query.iter().any(|caste| *caste == Caste::Queen);
+    let mut weathered_flood = false;
+    if !groundwater.flooded.is_empty() {
+        run.flooded = true;
+    } else if run.flooded {
+        run.flooded = false;
+        weathered_flood = queen_alive;
+    }
+
+    let reached = |achievement: &Achievement| match achievement {
+        Achievement::HundredAnts => caste_query.iter().count() >= POPULATION_GOAL,
+        Achievement::ThousandLeaves => fungus_garden.harvested >= LEAVES_GOAL,
+        Achievement::WeatheredFlood => weathered_flood,
+        Achievement::Bedrock => world_grid.tiles[0]
+            .iter()
+            .flatten()
+            .any(|tile| tile.is_excavated()),
+    };
+    let unlocked: Vec<Achievement> = Achievement::ALL
+        .into_iter()
+        .filter(|achievement| !profile.achievements.contains(achievement))
+        .filter(reached)
+        .collect();
+    if unlocked.is_empty() {
+        return;
+    }
+
+    profile.achievements.extend(&unlocked);
+    profile.save();
+    let Some(stack) = ToastStack::Achievements.find(&stack_query) else {
+        return;
+    };
+    for achievement in unlocked {
+        info!("Achievement unlocked: {}", achievement.name());
+        commands.entity(stack).with_child((
+            Toast::default(),
+            Node {
+                flex_direction: FlexDirection::Column,
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(TOAST_BACKGROUND),
+            children![
+                (
+                    Text::new(format!("Achievement unlocked: {}", achievement.name())),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(sprites::ui::HIGHLIGHT),
+                ),
+                (
+                    Text::new(achievement.description()),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                )
+            ],
+        ));
+    }
+}
+
+/// List every achievement, unlocked ones lit up
+fn enter_achievements(mut commands: Commands, profile: Res<Profile>) {
+    let unlocked = profile.achievements.len();
+    commands
+        .spawn((
+            AchievementsScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(30.0),
+                top: Val::Percent(15.0),
+                max_width: Val::Px(520.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(format!(
+                    "Achievements ({} of {})",
+                    unlocked,
+                    Achievement::ALL.len()
+                )),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            for achievement in Achievement::ALL {
+                let (mark, color) = if profile.achievements.contains(&achievement) {
+                    ("[x]", sprites::ui::HIGHLIGHT)
+                } else {
+                    ("[ ]", Color::srgba(0.6, 0.6, 0.6, 1.0))
+                };
+                parent.spawn((
+                    Text::new(format!(
+                        "{} {} - {}",
+                        mark,
+                        achievement.name(),
+                        achievement.description()
+                    )),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(color),
+                ));
+            }
+            parent
+                .spawn((
+                    BackButton,
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.25, 0.3, 0.25)),
+                ))
+                .with_child((
+                    Text::new("Back (Esc)"),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+        });
+}
+
+/// Tear down the achievements screen
+fn exit_achievements(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<AchievementsScreen>>,
+) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Go back to the title screen with Esc or the Back button
+fn achievements_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape)
+        || back_query.iter().any(|i| *i == Interaction::Pressed)
+    {
+        next_state.set(GameState::MainMenu);
+    }
+}
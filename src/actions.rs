@@ -0,0 +1,247 @@
+//! Player actions that affect the simulation.
+//!
+//! Input systems translate keys and clicks into `PlayerAction` messages instead
+//! of mutating the simulation directly, so every action can be recorded and
+//! replayed against the same world. Systems that write actions run in
+//! `ActionSystems::Input` and those that apply them in `ActionSystems::Apply`,
+//! so anything that needs to see a frame's actions before they take effect
+//! (like co-op lockstep) can run between the two.
+
+use bevy::prelude::*;
+
+use crate::ants::Caste;
+use crate::designations::DesignationKind;
+use crate::pheromones::PheromoneType;
+use crate::world::TileKind;
+
+pub struct ActionsPlugin;
+
+impl Plugin for ActionsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<PlayerAction>()
+            .configure_sets(Update, (ActionSystems::Input, ActionSystems::Apply).chain());
+    }
+}
+
+/// Where a system stands in the flow of player actions each frame
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub enum ActionSystems {
+    /// Turning input into actions
+    Input,
+    /// Carrying actions out on the simulation
+    Apply,
+}
+
+/// Something the player did to the simulation
+#[derive(Message, Debug, Clone, PartialEq)]
+pub enum PlayerAction {
+    /// Add pheromone at a tile
+    PlacePheromone {
+        ptype: PheromoneType,
+        x: usize,
+        y: usize,
+        z: usize,
+        amount: f32,
+    },
+    /// Debug-spawn a newly hatched ant
+    SpawnAnt {
+        caste: Caste,
+        x: usize,
+        y: usize,
+        z: usize,
+    },
+    /// Mark a tile for excavation
+    Designate {
+        kind: DesignationKind,
+        x: usize,
+        y: usize,
+        z: usize,
+    },
+    /// Remove the excavation mark from a tile
+    ClearDesignation { x: usize, y: usize, z: usize },
+    /// Move the nest to the entrance at a tile
+    MoveNest { x: usize, y: usize, z: usize },
+    /// Sandbox: turn a tile into another kind
+    PaintTile {
+        kind: TileKind,
+        x: usize,
+        y: usize,
+        z: usize,
+    },
+    /// Sandbox: take an ant out of the world
+    RemoveAnt { x: usize, y: usize, z: usize },
+}
+
+impl PlayerAction {
+    /// The tile the action targets
+    pub fn position(&self) -> (usize, usize, usize) {
+        match *self {
+            PlayerAction::PlacePheromone { x, y, z, .. }
+            | PlayerAction::SpawnAnt { x, y, z, .. }
+            | PlayerAction::Designate { x, y, z, .. }
+            | PlayerAction::ClearDesignation { x, y, z }
+            | PlayerAction::MoveNest { x, y, z }
+            | PlayerAction::PaintTile { x, y, z, .. }
+            | PlayerAction::RemoveAnt { x, y, z } => (x, y, z),
+        }
+    }
+
+    /// The same action aimed at another tile
+    pub fn moved_to(&self, x: usize, y: usize, z: usize) -> Self {
+        let mut action = self.clone();
+        match &mut action {
+            PlayerAction::PlacePheromone {
+                x: ax,
+                y: ay,
+                z: az,
+                ..
+            }
+            | PlayerAction::SpawnAnt {
+                x: ax,
+                y: ay,
+                z: az,
+                ..
+            }
+            | PlayerAction::Designate {
+                x: ax,
+                y: ay,
+                z: az,
+                ..
+            }
+            | PlayerAction::ClearDesignation {
+                x: ax,
+                y: ay,
+                z: az,
+            }
+            | PlayerAction::MoveNest {
+                x: ax,
+                y: ay,
+                z: az,
+            }
+            | PlayerAction::PaintTile {
+                x: ax,
+                y: ay,
+                z: az,
+                ..
+            }
+            | PlayerAction::RemoveAnt {
+                x: ax,
+                y: ay,
+                z: az,
+            } => (*ax, *ay, *az) = (x, y, z),
+        }
+        action
+    }
+
+    /// Serialize to a single whitespace-separated line
+    pub fn to_line(&self) -> String {
+        match self {
+            PlayerAction::PlacePheromone {
+                ptype,
+                x,
+                y,
+                z,
+                amount,
+            } => format!("pheromone {} {} {} {} {}", ptype.name(), x, y, z, amount),
+            PlayerAction::SpawnAnt { caste, x, y, z } => {
+                format!("spawn {} {} {} {}", caste.name(), x, y, z)
+            }
+            PlayerAction::Designate { kind, x, y, z } => {
+                format!("designate {} {} {} {}", kind.name(), x, y, z)
+            }
+            PlayerAction::ClearDesignation { x, y, z } => {
+                format!("undesignate {} {} {}", x, y, z)
+            }
+            PlayerAction::MoveNest { x, y, z } => format!("movenest {} {} {}", x, y, z),
+            PlayerAction::PaintTile { kind, x, y, z } => {
+                format!("paint {} {} {} {}", kind.name(), x, y, z)
+            }
+            PlayerAction::RemoveAnt { x, y, z } => format!("removeant {} {} {}", x, y, z),
+        }
+    }
+
+    /// Parse a line written by `to_line`
+    pub fn from_line(line: &str) -> Result<Self, String> {
+        let fields: Vec<&str> = line.split_whitespace().collect();
+        let coord = |i: usize| -> Result<usize, String> {
+            fields
+                .get(i)
+                .ok_or_else(|| format!("missing field {} in '{}'", i, line))?
+                .parse()
+                .map_err(|e| format!("bad coordinate in '{}': {}", line, e))
+        };
+
+        match fields.first() {
+            Some(&"pheromone") => {
+                let ptype = fields
+                    .get(1)
+                    .and_then(|name| PheromoneType::from_name(name))
+                    .ok_or_else(|| format!("unknown pheromone in '{}'", line))?;
+                let amount = fields
+                    .get(5)
+                    .ok_or_else(|| format!("missing amount in '{}'", line))?
+                    .parse()
+                    .map_err(|e| format!("bad amount in '{}': {}", line, e))?;
+                Ok(PlayerAction::PlacePheromone {
+                    ptype,
+                    x: coord(2)?,
+                    y: coord(3)?,
+                    z: coord(4)?,
+                    amount,
+                })
+            }
+            Some(&"spawn") => {
+                let caste = fields
+                    .get(1)
+                    .and_then(|name| Caste::from_name(name))
+                    .ok_or_else(|| format!("unknown caste in '{}'", line))?;
+                Ok(PlayerAction::SpawnAnt {
+                    caste,
+                    x: coord(2)?,
+                    y: coord(3)?,
+                    z: coord(4)?,
+                })
+            }
+            Some(&"designate") => {
+                let kind = fields
+                    .get(1)
+                    .and_then(|name| DesignationKind::from_name(name))
+                    .ok_or_else(|| format!("unknown designation in '{}'", line))?;
+                Ok(PlayerAction::Designate {
+                    kind,
+                    x: coord(2)?,
+                    y: coord(3)?,
+                    z: coord(4)?,
+                })
+            }
+            Some(&"undesignate") => Ok(PlayerAction::ClearDesignation {
+                x: coord(1)?,
+                y: coord(2)?,
+                z: coord(3)?,
+            }),
+            Some(&"movenest") => Ok(PlayerAction::MoveNest {
+                x: coord(1)?,
+                y: coord(2)?,
+                z: coord(3)?,
+            }),
+            Some(&"paint") => {
+                let kind = fields
+                    .get(1)
+                    .and_then(|name| TileKind::from_name(name))
+                    .ok_or_else(|| format!("unknown tile in '{}'", line))?;
+                Ok(PlayerAction::PaintTile {
+                    kind,
+                    x: coord(2)?,
+                    y: coord(3)?,
+                    z: coord(4)?,
+                })
+            }
+            Some(&"removeant") => Ok(PlayerAction::RemoveAnt {
+                x: coord(1)?,
+                y: coord(2)?,
+                z: coord(3)?,
+            }),
+            _ => Err(format!("unknown action '{}'", line)),
+        }
+    }
+}
@@ -0,0 +1,327 @@
+//! Air quality in the nest.
+//!
+//! Ants and fungus gardens breathe out CO2, which spreads through the open
+//! tiles of the tunnel network and only escapes where tunnels reach the
+//! surface. Deep chambers far from any shaft go stale, slowing the ants
+//! working in them and the brood developing there; digging another shaft
+//! toward the surface airs them out. Air draws up vertical shafts faster than
+//! it drifts along level tunnels, and a nest with several separate entrances
+//! gets a through-draft that flushes every tunnel still connected to the
+//! surface. V shows the CO2 level as an overlay.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ants::{Ant, GridPosition};
+use crate::low_spec::cosmetic_frame;
+use crate::sprites;
+use crate::time_controls::{SimulationSet, SimulationTick};
+use crate::world::{
+    CurrentZLevel, SURFACE_LEVEL, TILE_SIZE, TileKind, WORLD_SIZE, WorldGrid, filled_grid,
+};
+use crate::{ResetRun, reset_resource};
+
+pub struct AirPlugin;
+
+impl Plugin for AirPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<AirQuality>()
+            .init_resource::<AirOverlaySettings>()
+            .add_systems(Startup, spawn_air_overlay)
+            .add_systems(Update, update_air_overlay.run_if(cosmetic_frame))
+            .add_systems(FixedUpdate, air_circulation.in_set(SimulationSet))
+            .add_systems(ResetRun, reset_resource::<AirQuality>);
+    }
+}
+
+/// Ticks between air updates
+const AIR_UPDATE_INTERVAL: u64 = 4;
+/// CO2 each ant breathes out per update
+const ANT_BREATH: f32 = 0.0015;
+/// CO2 each fungus garden tile gives off per update
+const GARDEN_RESPIRATION: f32 = 0.0005;
+/// Share of the difference with each open neighbour exchanged per update
+const DIFFUSION_RATE: f32 = 0.12;
+/// How much faster air moves up and down shafts than along tunnels
+const SHAFT_DRAFT: f32 = 1.5;
+/// Fraction of CO2 flushed from connected tunnels per update for each
+/// entrance beyond the first
+const CROSS_VENTILATION: f32 = 0.01;
+/// Most extra entrances that add to the through-draft
+const MAX_CROSS_ENTRANCES: usize = 4;
+/// Fraction of CO2 lost through the soil per update
+const SOIL_SEEPAGE: f32 = 0.002;
+/// CO2 level above which air counts as stale
+pub const STALE_THRESHOLD: f32 = 0.4;
+/// Chance per tick that work stalls in completely stale air
+const MAX_STALL_CHANCE: f64 = 0.5;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// CO2 concentration (0.0 - 1.0) in every open underground tile
+#[derive(Resource)]
+pub struct AirQuality {
+    pub co2: Box<[[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+    /// Separate openings from the tunnels to the surface
+    pub entrances: usize,
+    /// Open tiles with a path up to an entrance, reached by the through-draft
+    connected: Box<[[[bool; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+    /// Next-step buffer reused by each diffusion pass
+    scratch: Box<[[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+}
+
+impl Default for AirQuality {
+    fn default() -> Self {
+        Self {
+            co2: filled_grid(0.0),
+            entrances: 0,
+            connected: filled_grid(false),
+            scratch: filled_grid(0.0),
+        }
+    }
+}
+
+impl AirQuality {
+    /// How stale the air is at a tile, from 0.0 (fine) to 1.0 (unbreathable)
+    pub fn staleness(&self, x: usize, y: usize, z: usize) -> f32 {
+        ((self.co2[z][y][x] - STALE_THRESHOLD) / (1.0 - STALE_THRESHOLD)).clamp(0.0, 1.0)
+    }
+
+    /// Roll whether work at a tile stalls this tick for lack of air
+    pub fn stalls(&self, rng: &mut impl Rng, x: usize, y: usize, z: usize) -> bool {
+        let staleness = self.staleness(x, y, z);
+        staleness > 0.0 && rng.random_bool(staleness as f64 * MAX_STALL_CHANCE)
+    }
+}
+
+/// Whether the air quality overlay is drawn
+#[derive(Resource, Default)]
+pub struct AirOverlaySettings {
+    pub visible: bool,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for air quality overlay sprites
+#[derive(Component)]
+pub struct AirOverlay {
+    pub x: usize,
+    pub y: usize,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Tiles air can fill
+fn is_open(tile: TileKind) -> bool {
+    tile.is_excavated()
+}
+
+/// Breathe CO2 into the tunnels and let it spread, venting at the surface
+fn air_circulation(
+    tick: Res<SimulationTick>,
+    world_grid: Res<WorldGrid>,
+    mut air: ResMut<AirQuality>,
+    ant_query: Query<&GridPosition, With<Ant>>,
+) {
+    if !tick.0.is_multiple_of(AIR_UPDATE_INTERVAL) {
+        return;
+    }
+
+    let air = &mut *air;
+    let tiles = &world_grid.tiles;
+
+    for pos in &ant_query {
+        if pos.z < SURFACE_LEVEL && is_open(tiles[pos.z][pos.y][pos.x]) {
+            air.co2[pos.z][pos.y][pos.x] += ANT_BREATH;
+        }
+    }
+
+    let entrances = trace_ventilation(tiles, &mut air.connected);
+    if entrances != air.entrances {
+        info!("The nest now has {} entrances", entrances);
+        air.entrances = entrances;
+    }
+    let flush = CROSS_VENTILATION * entrances.saturating_sub(1).min(MAX_CROSS_ENTRANCES) as f32;
+
+    for z in 0..SURFACE_LEVEL {
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                let tile = tiles[z][y][x];
+                if !is_open(tile) {
+                    air.scratch[z][y][x] = 0.0;
+                    continue;
+                }
+
+                let here = air.co2[z][y][x];
+                let mut flow = 0.0;
+                let neighbors = [
+                    (x.wrapping_sub(1), y, z),
+                    (x + 1, y, z),
+                    (x, y.wrapping_sub(1), z),
+                    (x, y + 1, z),
+                    (x, y, z.wrapping_sub(1)),
+                    (x, y, z + 1),
+                ];
+                for (nx, ny, nz) in neighbors {
+                    let draft = if nz == z { 1.0 } else { SHAFT_DRAFT };
+                    if nz == SURFACE_LEVEL {
+                        // Open to the sky: exchange with fresh air
+                        flow -= here * draft;
+                    } else if nx < WORLD_SIZE
+                        && ny < WORLD_SIZE
+                        && nz < SURFACE_LEVEL
+                        && is_open(tiles[nz][ny][nx])
+                    {
+                        flow += (air.co2[nz][ny][nx] - here) * draft;
+                    }
+                }
+
+                let mut next = here + flow * DIFFUSION_RATE;
+                if tile == TileKind::FungusGarden {
+                    next += GARDEN_RESPIRATION;
+                }
+                if air.connected[z][y][x] {
+                    next *= 1.0 - flush;
+                }
+                air.scratch[z][y][x] = (next * (1.0 - SOIL_SEEPAGE)).clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    std::mem::swap(&mut air.co2, &mut air.scratch);
+}
+
+/// Mark every open tile with a path up to the surface, returning how many
+/// separate entrances (clusters of open tiles in the top layer) there are
+fn trace_ventilation(
+    tiles: &[[[TileKind; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE],
+    connected: &mut [[[bool; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE],
+) -> usize {
+    connected
+        .iter_mut()
+        .flatten()
+        .flatten()
+        .for_each(|tile| *tile = false);
+    let top = SURFACE_LEVEL - 1;
+
+    // Count the openings, treating touching tiles as one wide entrance
+    let mut entrances = 0;
+    let mut seen = [[false; WORLD_SIZE]; WORLD_SIZE];
+    let mut stack = Vec::new();
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            if !is_open(tiles[top][y][x]) || seen[y][x] {
+                continue;
+            }
+            entrances += 1;
+            seen[y][x] = true;
+            stack.push((x, y));
+            while let Some((cx, cy)) = stack.pop() {
+                for (nx, ny) in [
+                    (cx.wrapping_sub(1), cy),
+                    (cx + 1, cy),
+                    (cx, cy.wrapping_sub(1)),
+                    (cx, cy + 1),
+                ] {
+                    if nx < WORLD_SIZE
+                        && ny < WORLD_SIZE
+                        && is_open(tiles[top][ny][nx])
+                        && !seen[ny][nx]
+                    {
+                        seen[ny][nx] = true;
+                        stack.push((nx, ny));
+                    }
+                }
+            }
+        }
+    }
+
+    // Follow the tunnels down from every opening
+    let mut stack: Vec<_> = (0..WORLD_SIZE)
+        .flat_map(|y| (0..WORLD_SIZE).map(move |x| (x, y, top)))
+        .filter(|&(x, y, z)| is_open(tiles[z][y][x]))
+        .collect();
+    for &(x, y, z) in &stack {
+        connected[z][y][x] = true;
+    }
+    while let Some((x, y, z)) = stack.pop() {
+        for (nx, ny, nz) in [
+            (x.wrapping_sub(1), y, z),
+            (x + 1, y, z),
+            (x, y.wrapping_sub(1), z),
+            (x, y + 1, z),
+            (x, y, z.wrapping_sub(1)),
+            (x, y, z + 1),
+        ] {
+            if nx < WORLD_SIZE
+                && ny < WORLD_SIZE
+                && nz <= top
+                && is_open(tiles[nz][ny][nx])
+                && !connected[nz][ny][nx]
+            {
+                connected[nz][ny][nx] = true;
+                stack.push((nx, ny, nz));
+            }
+        }
+    }
+
+    entrances
+}
+
+/// Spawn overlay sprites for air quality visualization
+fn spawn_air_overlay(mut commands: Commands) {
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let world_x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+            let world_y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+
+            commands.spawn((
+                Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(world_x, world_y, 0.45), // Just under pheromones
+                AirOverlay { x, y },
+                Visibility::Hidden,
+            ));
+        }
+    }
+}
+
+/// Tint open tiles on the current z-level by their CO2 level
+fn update_air_overlay(
+    air: Res<AirQuality>,
+    current_z: Res<CurrentZLevel>,
+    settings: Res<AirOverlaySettings>,
+    world_grid: Res<WorldGrid>,
+    mut query: Query<(&AirOverlay, &mut Sprite, &mut Visibility)>,
+) {
+    let z = current_z.0;
+    if !settings.visible || z >= SURFACE_LEVEL {
+        if settings.is_changed() || current_z.is_changed() {
+            for (_, _, mut visibility) in &mut query {
+                *visibility = Visibility::Hidden;
+            }
+        }
+        return;
+    }
+
+    for (overlay, mut sprite, mut visibility) in &mut query {
+        let (x, y) = (overlay.x, overlay.y);
+        if !is_open(world_grid.tiles[z][y][x]) {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        *visibility = Visibility::Visible;
+        let level = air.co2[z][y][x].clamp(0.0, 1.0);
+        sprite.color = sprites::air::FRESH.mix(&sprites::air::STALE, level);
+    }
+}
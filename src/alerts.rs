@@ -0,0 +1,264 @@
+//! Alerts for critical colony states.
+//!
+//! Once a second the colony is checked for trouble: the stores running low,
+//! the queen going hungry or dying, water flooding the tunnels, and spiders
+//! raiding the surface. Each new alert pops up as a toast for a few seconds,
+//! and while any alert lasts an icon at the top counts them. Clicking a
+//! toast jumps the camera to where the trouble is; clicking the icon cycles
+//! through every active alert.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
+
+use crate::ants::{Ant, Caste, GridPosition, Hunger, NestLocation};
+use crate::camera::{MainCamera, focus_tile};
+use crate::migrations::{Migrant, MigrantKind};
+use crate::notifications::{Toast, ToastStack};
+use crate::sprites;
+use crate::summary::RunStats;
+use crate::water::Groundwater;
+use crate::world::{CurrentZLevel, FoodStores, SURFACE_LEVEL};
+use crate::{ResetRun, in_game, reset_resource};
+
+pub struct AlertsPlugin;
+
+impl Plugin for AlertsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ActiveAlerts>()
+            .add_systems(Startup, setup_alert_icon)
+            .add_systems(
+                Update,
+                (
+                    check_alerts.run_if(on_timer(Duration::from_secs(1))),
+                    click_alerts,
+                    update_alert_icon,
+                )
+                    .chain()
+                    .run_if(in_game),
+            )
+            .add_systems(ResetRun, reset_resource::<ActiveAlerts>);
+    }
+}
+
+/// Food, honeydew and seeds in store below which the stores count as low
+const LOW_FOOD: u32 = 10;
+/// Share of the queen's hunger limit at which she counts as hungry
+const QUEEN_HUNGRY: f32 = 0.7;
+/// Color of the alert icon and toasts
+const ALERT_BACKGROUND: Color = Color::srgba(0.5, 0.1, 0.05, 0.9);
+
+/// A critical state of the colony
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum AlertKind {
+    LowFood,
+    QueenHungry,
+    QueenDead,
+    Flood,
+    Raid,
+}
+
+impl AlertKind {
+    fn text(&self) -> &'static str {
+        match self {
+            AlertKind::LowFood => "Food stores are running low",
+            AlertKind::QueenHungry => "The queen is hungry",
+            AlertKind::QueenDead => "The queen is dead",
+            AlertKind::Flood => "Water is flooding the tunnels",
+            AlertKind::Raid => "Spiders are raiding the surface",
+        }
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Alerts in effect, each with where the trouble is, oldest first
+#[derive(Resource, Default)]
+pub struct ActiveAlerts {
+    pub alerts: Vec<(AlertKind, (usize, usize, usize))>,
+    /// Which alert the icon jumps to next
+    next: usize,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// A pop-up for a newly raised alert, with where the trouble is
+#[derive(Component)]
+struct AlertToast {
+    location: (usize, usize, usize),
+}
+
+/// Marker for the persistent alert icon
+#[derive(Component)]
+struct AlertIcon;
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Spawn the (initially hidden) alert icon
+fn setup_alert_icon(mut commands: Commands) {
+    commands
+        .spawn((
+            AlertIcon,
+            Button,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(35.0),
+                top: Val::Px(10.0),
+                padding: UiRect::axes(Val::Px(10.0), Val::Px(4.0)),
+                ..default()
+            },
+            BackgroundColor(ALERT_BACKGROUND),
+            Visibility::Hidden,
+        ))
+        .with_child((
+            Text::new(""),
+            TextFont {
+                font_size: 14.0,
+                ..default()
+            },
+            TextColor(sprites::ui::HIGHLIGHT),
+        ));
+}
+
+/// Check the colony for trouble, raising a toast for each new alert
+#[allow(clippy::too_many_arguments)]
+fn check_alerts(
+    mut commands: Commands,
+    food_stores: Res<FoodStores>,
+    nest: Res<NestLocation>,
+    stats: Res<RunStats>,
+    groundwater: Res<Groundwater>,
+    queen_query: Query<(&Caste, &Hunger, &GridPosition), With<Ant>>,
+    migrant_query: Query<&Migrant>,
+    stack_query: Query<(Entity, &ToastStack)>,
+    mut active: ResMut<ActiveAlerts>,
+) {
+    let nest = (nest.x, nest.y, nest.z);
+    let mut alerts = Vec::new();
+
+    if food_stores.food + food_stores.honeydew + food_stores.seeds < LOW_FOOD {
+        alerts.push((AlertKind::LowFood, nest));
+    }
+    let queen = queen_query
+        .iter()
+        .find(|(caste, ..)| **caste == Caste::Queen);
+    match queen {
+        Some((_, hunger, pos)) if hunger.current >= hunger.max * QUEEN_HUNGRY => {
+            alerts.push((AlertKind::QueenHungry, (pos.x, pos.y, pos.z)));
+        }
+        None if stats.queen_death.is_some() => alerts.push((AlertKind::QueenDead, nest)),
+        _ => {}
+    }
+    if let Some(&tile) = groundwater.flooded.first() {
+        alerts.push((AlertKind::Flood, tile));
+    }
+    if let Some(spider) = migrant_query
+        .iter()
+        .find(|m| m.kind == MigrantKind::Spider && !m.leaving)
+    {
+        alerts.push((AlertKind::Raid, (spider.x, spider.y, SURFACE_LEVEL)));
+    }
+
+    let Some(stack) = ToastStack::Alerts.find(&stack_query) else {
+        return;
+    };
+    for &(kind, location) in &alerts {
+        if active.alerts.iter().any(|(active, _)| *active == kind) {
+            continue;
+        }
+        commands.entity(stack).with_child((
+            Toast::default(),
+            AlertToast { location },
+            Button,
+            Node {
+                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                ..default()
+            },
+            BackgroundColor(ALERT_BACKGROUND),
+            children![(
+                Text::new(format!("{} (click to look)", kind.text())),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            )],
+        ));
+        warn!("{}", kind.text());
+    }
+
+    // Keep the same order so the icon cycles through alerts predictably
+    let kinds: Vec<AlertKind> = active.alerts.iter().map(|(kind, _)| *kind).collect();
+    let mut updated: Vec<_> = kinds
+        .iter()
+        .filter_map(|kind| alerts.iter().find(|(k, _)| k == kind).copied())
+        .collect();
+    updated.extend(alerts.iter().filter(|(kind, _)| !kinds.contains(kind)));
+    if updated != active.alerts {
+        active.alerts = updated;
+    }
+}
+
+/// Jump the camera to a clicked toast's trouble, or to the next alert when
+/// the icon is clicked
+fn click_alerts(
+    mut commands: Commands,
+    toast_query: Query<(Entity, &Interaction, &AlertToast), Changed<Interaction>>,
+    icon_query: Query<&Interaction, (Changed<Interaction>, With<AlertIcon>)>,
+    mut active: ResMut<ActiveAlerts>,
+    mut current_z: ResMut<CurrentZLevel>,
+    mut camera_query: Query<&mut Transform, With<MainCamera>>,
+) {
+    let mut target = None;
+    for (entity, interaction, toast) in &toast_query {
+        if *interaction == Interaction::Pressed {
+            target = Some(toast.location);
+            commands.entity(entity).despawn();
+        }
+    }
+    if icon_query.iter().any(|i| *i == Interaction::Pressed) && !active.alerts.is_empty() {
+        let index = active.next % active.alerts.len();
+        target = Some(active.alerts[index].1);
+        active.next = index + 1;
+    }
+
+    if let (Some(target), Ok(mut transform)) = (target, camera_query.single_mut()) {
+        focus_tile(&mut transform, &mut current_z, target);
+    }
+}
+
+/// Show the icon with a count while any alert lasts
+fn update_alert_icon(
+    active: Res<ActiveAlerts>,
+    mut icon_query: Query<(&mut Visibility, &Children), With<AlertIcon>>,
+    mut text_query: Query<&mut Text>,
+) {
+    if !active.is_changed() {
+        return;
+    }
+    let Ok((mut visibility, children)) = icon_query.single_mut() else {
+        return;
+    };
+
+    *visibility = if active.alerts.is_empty() {
+        Visibility::Hidden
+    } else {
+        Visibility::Visible
+    };
+    if let Some(mut text) = children
+        .first()
+        .and_then(|child| text_query.get_mut(*child).ok())
+    {
+        **text = match active.alerts.len() {
+            1 => String::from("! 1 alert"),
+            n => format!("! {} alerts", n),
+        };
+    }
+}
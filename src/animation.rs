@@ -0,0 +1,124 @@
+//! Ant animations.
+//!
+//! Each ant picks a pose from what it's doing: walking, working at a tile or
+//! plant, carrying a load, or standing still. A timer steps through the pose's
+//! frames along the caste's row of the sprite atlas (see `sprites.rs`). The
+//! animation follows the game clock, so it freezes while paused, and without
+//! an atlas it has nothing to show.
+
+use bevy::prelude::*;
+
+use crate::ants::{Ant, Caste, GridPosition, Inventory, PreviousPosition, Task};
+use crate::atlas::AtlasSprite;
+use crate::sprites;
+
+pub struct AnimationPlugin;
+
+impl Plugin for AnimationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, animate_ants);
+    }
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// What an ant looks like it's doing
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Pose {
+    #[default]
+    Standing,
+    Walking,
+    /// Digging, cutting leaves, scrubbing the garden or bailing water
+    Working,
+    /// Walking with a load
+    Carrying,
+    /// Standing with a load
+    Holding,
+}
+
+impl Pose {
+    /// First column and number of frames along the caste's atlas row
+    fn frames(&self) -> (usize, usize) {
+        match self {
+            Pose::Standing => (0, 1),
+            Pose::Walking => sprites::ants::WALK_FRAMES,
+            Pose::Working => sprites::ants::WORK_FRAMES,
+            Pose::Carrying => sprites::ants::CARRY_FRAMES,
+            Pose::Holding => (sprites::ants::HOLD_FRAME, 1),
+        }
+    }
+
+    fn frame_secs(&self) -> f32 {
+        match self {
+            Pose::Working => sprites::ants::WORK_FRAME_SECS,
+            _ => sprites::ants::WALK_FRAME_SECS,
+        }
+    }
+}
+
+/// An ant's current pose and how far through it the animation is
+#[derive(Component, Default)]
+pub struct AntAnimation {
+    pub pose: Pose,
+    pub frame: usize,
+    /// Seconds the current frame has shown for
+    pub elapsed: f32,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// What an ant is doing and how it's drawn doing it
+type AnimatedAntQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static Caste,
+        &'static Task,
+        &'static Inventory,
+        &'static GridPosition,
+        &'static PreviousPosition,
+        &'static mut AntAnimation,
+        &'static mut AtlasSprite,
+    ),
+    With<Ant>,
+>;
+
+/// Pick each ant's pose and step through its frames
+fn animate_ants(time: Res<Time>, mut query: AnimatedAntQuery) {
+    for (caste, task, inventory, pos, previous, mut animation, mut cell) in &mut query {
+        let moving = (previous.0.x, previous.0.y, previous.0.z) != (pos.x, pos.y, pos.z);
+        let working = matches!(
+            task,
+            Task::Digging { .. }
+                | Task::Cutting { .. }
+                | Task::Cleaning { .. }
+                | Task::Bailing { .. }
+        );
+        let pose = match (inventory.is_empty(), moving) {
+            (false, true) => Pose::Carrying,
+            (false, false) => Pose::Holding,
+            (true, true) => Pose::Walking,
+            (true, false) if working => Pose::Working,
+            (true, false) => Pose::Standing,
+        };
+
+        if pose != animation.pose {
+            *animation = AntAnimation { pose, ..default() };
+        } else {
+            animation.elapsed += time.delta_secs();
+            if animation.elapsed >= pose.frame_secs() {
+                animation.elapsed -= pose.frame_secs();
+                animation.frame += 1;
+            }
+        }
+
+        let (first, count) = pose.frames();
+        cell.set_if_neq(AtlasSprite(
+            caste.atlas_index() + first + animation.frame % count,
+        ));
+    }
+}
@@ -1,40 +1,89 @@
 //! Ant entities, components, and behaviors.
 
 use bevy::prelude::*;
-
+use serde::Deserialize;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::air::AirQuality;
+use crate::animation::AntAnimation;
+use crate::aphids::AphidColony;
+use crate::atlas::AtlasSprite;
+use crate::brood::{Brood, Nursery, feed_larva};
+use crate::config::SimulationConfig;
+use crate::content::Content;
+use crate::designations::{Designations, dig_approach};
+use crate::entrances::Entrances;
+use crate::excavation::DigProgress;
+use crate::founding::{FoundingQueen, spawn_founding_queen};
+use crate::low_spec::cosmetic_frame;
+use crate::migrations::{Migrant, MigrantKind};
+use crate::moisture::SoilMoisture;
+use crate::mushrooms::{
+    MUSHROOM_CONTAMINATION, MUSHROOM_SPORE_RADIUS, MUSHROOM_TAINT_CHANCE, WildMushroom,
+};
+use crate::notifications::Notification;
+use crate::pathfinding::{self, Route};
 use crate::pheromones::{PheromoneGrids, PheromoneType};
-use crate::sprites;
+use crate::rng::SimRng;
+use crate::scouting::ColonyKnowledge;
+use crate::seeds::{SEED_NUTRITION, SeedItem};
+use crate::sprites::{self, Palette};
+use crate::stuck::Stillness;
+use crate::time_controls::{SimulationSet, SimulationTick, TICKS_PER_DAY};
+use crate::water::{BAIL_THRESHOLD, Groundwater};
 use crate::world::{
-    CurrentZLevel, FungusGarden, LeafSource, SURFACE_LEVEL, TILE_SIZE, TileKind, Tree, WORLD_SIZE,
-    WorldGrid,
+    CurrentZLevel, FoodStores, FungusGarden, LeafSource, Plant, SURFACE_LEVEL, TILE_SIZE, TileDug,
+    TileKind, WORLD_SIZE, WorldGrid, WorldOptions, tile_to_world,
 };
+use crate::{ResetRun, despawn_all, reset_resource};
 
 pub struct AntPlugin;
 
 impl Plugin for AntPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<NestLocation>()
+            .add_message::<AntDied>()
             .add_systems(Startup, spawn_founding_colony)
-            .add_systems(Update, (update_ant_sprites, debug_spawn_ant))
+            .add_systems(FixedFirst, record_previous_positions.in_set(SimulationSet))
+            .add_systems(
+                Update,
+                (
+                    update_ant_sprites.run_if(cosmetic_frame),
+                    apply_spawn_actions.in_set(ActionSystems::Apply),
+                ),
+            )
             .add_systems(
                 FixedUpdate,
                 (
+                    ant_gravity,
+                    ant_nursing,
                     ant_behavior,
                     ant_digging,
                     ant_foraging,
+                    ant_tending_aphids,
+                    ant_gathering_seeds,
+                    ant_harvesting_mushrooms,
+                    ant_bailing,
                     ant_carrying,
                     ant_gardening,
                     ant_hunger,
                     ant_feeding,
+                    ant_trophallaxis,
                     ant_starvation,
+                    ant_aging,
                 )
-                    .chain(),
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(
+                ResetRun,
+                (reset_resource::<NestLocation>, despawn_all::<Ant>),
             );
     }
 }
 
 /// The location of the nest (where ants bring resources)
-#[derive(Resource)]
+#[derive(Resource, Clone)]
 pub struct NestLocation {
     pub x: usize,
     pub y: usize,
@@ -52,12 +101,55 @@ impl Default for NestLocation {
     }
 }
 
+/// Why an ant died
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum DeathCause {
+    Starvation,
+    /// Caught by a predator on the surface
+    Predation,
+    /// Buried by collapsing soil
+    Crushed,
+    /// Caught in a flooded tunnel
+    Drowned,
+    /// Dropped from too high
+    Fall,
+}
+
+impl DeathCause {
+    pub const ALL: [DeathCause; 5] = [
+        DeathCause::Starvation,
+        DeathCause::Predation,
+        DeathCause::Crushed,
+        DeathCause::Drowned,
+        DeathCause::Fall,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DeathCause::Starvation => "starvation",
+            DeathCause::Predation => "predation",
+            DeathCause::Crushed => "crushed",
+            DeathCause::Drowned => "drowned",
+            DeathCause::Fall => "fall",
+        }
+    }
+}
+
+/// Sent whenever an adult ant dies
+#[derive(Message, Debug, Clone, Copy)]
+pub struct AntDied {
+    pub caste: Caste,
+    pub cause: DeathCause,
+}
+
 // ============================================================================
 // Components
 // ============================================================================
 
-/// Marker component for all ants
+/// Marker component for all ants, each of which keeps the route it's
+/// walking
 #[derive(Component)]
+#[require(Route)]
 pub struct Ant;
 
 /// Position in the world grid (tile coordinates)
@@ -68,37 +160,82 @@ pub struct GridPosition {
     pub z: usize,
 }
 
+/// Where an ant was at the start of the current tick, so its sprite can glide
+/// from there to its `GridPosition` between ticks
+#[derive(Component, Clone, Copy)]
+pub struct PreviousPosition(pub GridPosition);
+
+/// Which way an ant is heading, in radians counterclockwise from east; kept
+/// from its last step while it stands still
+#[derive(Component, Clone, Copy, Default)]
+pub struct Facing(pub f32);
+
 /// The caste/role of an ant
-#[derive(Component, Clone, Copy, PartialEq, Eq, Debug)]
+#[derive(Component, Clone, Copy, PartialEq, Eq, Debug, Deserialize)]
 pub enum Caste {
     Queen,
     Forager,
     Gardener,
+    /// Small workers who raise the brood
+    Nurse,
     Soldier,
+    /// Winged princesses and males raised for the nuptial flight
+    Alate,
 }
 
 impl Caste {
-    pub fn color(&self) -> Color {
+    /// The caste's color in a palette
+    pub fn color(&self, palette: Palette) -> Color {
+        let [queen, forager, gardener, nurse, soldier, alate] = sprites::ants::colors(palette);
+        match self {
+            Caste::Queen => queen,
+            Caste::Forager => forager,
+            Caste::Gardener => gardener,
+            Caste::Nurse => nurse,
+            Caste::Soldier => soldier,
+            Caste::Alate => alate,
+        }
+    }
+
+    /// Cell of the caste's sprite in the atlas
+    pub fn atlas_index(&self) -> usize {
         match self {
-            Caste::Queen => sprites::ants::QUEEN,
-            Caste::Forager => sprites::ants::FORAGER,
-            Caste::Gardener => sprites::ants::GARDENER,
-            Caste::Soldier => sprites::ants::SOLDIER,
+            Caste::Queen => sprites::ants::QUEEN_INDEX,
+            Caste::Forager => sprites::ants::FORAGER_INDEX,
+            Caste::Gardener => sprites::ants::GARDENER_INDEX,
+            Caste::Nurse => sprites::ants::NURSE_INDEX,
+            Caste::Soldier => sprites::ants::SOLDIER_INDEX,
+            Caste::Alate => sprites::ants::ALATE_INDEX,
         }
     }
 
-    pub fn size(&self) -> f32 {
+    pub fn name(&self) -> &'static str {
         match self {
-            Caste::Queen => sprites::ants::QUEEN_SIZE,
-            Caste::Forager => sprites::ants::FORAGER_SIZE,
-            Caste::Gardener => sprites::ants::GARDENER_SIZE,
-            Caste::Soldier => sprites::ants::SOLDIER_SIZE,
+            Caste::Queen => "Queen",
+            Caste::Forager => "Forager",
+            Caste::Gardener => "Gardener",
+            Caste::Nurse => "Nurse",
+            Caste::Soldier => "Soldier",
+            Caste::Alate => "Alate",
+        }
+    }
+
+    /// Look up a caste by its display name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Queen" => Some(Caste::Queen),
+            "Forager" => Some(Caste::Forager),
+            "Gardener" => Some(Caste::Gardener),
+            "Nurse" => Some(Caste::Nurse),
+            "Soldier" => Some(Caste::Soldier),
+            "Alate" => Some(Caste::Alate),
+            _ => None,
         }
     }
 }
 
 /// Hunger level - ants die if this reaches max
-#[derive(Component)]
+#[derive(Component, Clone)]
 pub struct Hunger {
     pub current: f32,
     pub max: f32,
@@ -113,22 +250,93 @@ impl Default for Hunger {
     }
 }
 
+/// Food held in an ant's crop (its social stomach) to share with hungry
+/// nestmates, in units of hunger it can relieve
+#[derive(Component, Clone, Default)]
+pub struct Crop {
+    pub food: f32,
+}
+
+/// An ant dropping through open air, and how many levels it has fallen
+#[derive(Component, Clone)]
+pub struct Falling {
+    pub height: usize,
+}
+
 /// Age in simulation ticks
-#[derive(Component, Default)]
+#[derive(Component, Clone, Default)]
 pub struct Age(pub u32);
 
-/// What the ant is currently carrying
-#[derive(Component, Default)]
+/// How long a newly hatched ant stays pale and small (its first day)
+pub const CALLOW_TICKS: u32 = TICKS_PER_DAY;
+
+impl Age {
+    /// How far the ant has darkened from callow to adult (0.0 - 1.0)
+    pub fn maturity(&self) -> f32 {
+        (self.0 as f32 / CALLOW_TICKS as f32).min(1.0)
+    }
+}
+
+/// What kind of load an ant is carrying
+#[derive(Clone, Copy, Default)]
 pub enum Carrying {
     #[default]
     Nothing,
-    Leaf,
+    /// A leaf fragment worth `value` leaves to the garden
+    Leaf {
+        value: u32,
+    },
     Mulch,
+    /// Fungus from the stores, being moved to a new nest
     FungusFood,
+    Honeydew,
+    Seed,
+    /// Wild fungus harvested on the surface, possibly carrying mold spores
+    WildFungus {
+        tainted: bool,
+    },
+    /// A mouthful of water bailed out of a flooded tunnel
+    Water,
+}
+
+/// What an ant is carrying and how much of it
+#[derive(Component, Clone, Default)]
+pub struct Inventory {
+    pub load: Carrying,
+    pub quantity: u32,
+}
+
+impl Inventory {
+    pub fn is_empty(&self) -> bool {
+        matches!(self.load, Carrying::Nothing)
+    }
+
+    /// Pick up `quantity` of a load, replacing whatever was carried
+    pub fn hold(&mut self, load: Carrying, quantity: u32) {
+        self.load = load;
+        self.quantity = quantity;
+    }
+
+    /// Add more of a load to what's already carried
+    pub fn add(&mut self, load: Carrying, quantity: u32) {
+        self.load = load;
+        self.quantity += quantity;
+    }
+
+    /// Put the whole load down
+    pub fn drop_all(&mut self) {
+        *self = Self::default();
+    }
+
+    /// Whether a heavy load holds the ant back this tick: a second item
+    /// slows it to three quarters pace, a third to half
+    pub fn weighs_down(&self, tick: u64, drag: u64) -> bool {
+        tick % drag < self.quantity.saturating_sub(1) as u64
+    }
 }
 
 /// Current task/behavior
-#[derive(Component, Default)]
+#[derive(Component, Clone, Default)]
 pub enum Task {
     #[default]
     Idle,
@@ -138,9 +346,15 @@ pub enum Task {
         target_y: usize,
         target_z: usize,
     },
-    /// Moving toward a leaf source to cut leaves
+    /// Moving toward a plant to cut leaves
     Foraging {
-        target_tree: Entity,
+        target_plant: Entity,
+    },
+    /// Stationed at a plant, cutting leaf fragments loose
+    Cutting {
+        target_plant: Entity,
+        /// Ticks of work put into the fragment being cut
+        progress: u32,
     },
     /// Carrying a leaf back to the nest/garden
     CarryingHome {
@@ -148,9 +362,109 @@ pub enum Task {
         home_y: usize,
         home_z: usize,
     },
+    /// Picking up a leaf at the nest to chew into mulch
     Gardening,
+    /// Carrying mulch to a garden tile (or a chamber to plant)
+    TendingGarden {
+        target_x: usize,
+        target_y: usize,
+        target_z: usize,
+    },
+    /// Scrubbing mold and waste off a contaminated garden tile
+    Cleaning {
+        target_x: usize,
+        target_y: usize,
+        target_z: usize,
+    },
     /// Going to nest to eat
     SeekingFood,
+    /// Tending an aphid colony and collecting its honeydew
+    TendingAphids {
+        colony: Entity,
+    },
+    /// Fetching a fallen seed as an emergency ration
+    GatheringSeed {
+        seed: Entity,
+    },
+    /// Harvesting a wild mushroom patch
+    HarvestingMushroom {
+        patch: Entity,
+    },
+    /// Scooping water out of a flooded tunnel to carry to the surface
+    Bailing {
+        target_x: usize,
+        target_y: usize,
+        target_z: usize,
+    },
+    /// Standing guard over a nest entrance
+    Guarding {
+        x: usize,
+        y: usize,
+    },
+    /// Heading out to a far part of the surface to look for plants
+    Scouting {
+        target_x: usize,
+        target_y: usize,
+    },
+    /// Going to pick up brood lying outside the nursery
+    FetchingBrood {
+        brood: Entity,
+    },
+    /// Carrying brood down to the nursery
+    CarryingBrood {
+        brood: Entity,
+    },
+    /// Going to feed, groom and warm a brood item
+    Nursing {
+        brood: Entity,
+    },
+    /// Keeping the queen company on her way to a new nest
+    Escorting,
+    /// Going back to the old nest for a load of stores to move
+    HaulingStores,
+}
+
+impl Task {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Task::Idle => "Idle",
+            Task::Wandering => "Wandering",
+            Task::Digging { .. } => "Digging",
+            Task::Foraging { .. } => "Foraging",
+            Task::Cutting { .. } => "Cutting",
+            Task::CarryingHome { .. } => "CarryingHome",
+            Task::Gardening => "Gardening",
+            Task::TendingGarden { .. } => "TendingGarden",
+            Task::Cleaning { .. } => "Cleaning",
+            Task::SeekingFood => "SeekingFood",
+            Task::TendingAphids { .. } => "TendingAphids",
+            Task::GatheringSeed { .. } => "GatheringSeed",
+            Task::HarvestingMushroom { .. } => "HarvestingMushroom",
+            Task::Bailing { .. } => "Bailing",
+            Task::Guarding { .. } => "Guarding",
+            Task::Scouting { .. } => "Scouting",
+            Task::FetchingBrood { .. } => "FetchingBrood",
+            Task::CarryingBrood { .. } => "CarryingBrood",
+            Task::Nursing { .. } => "Nursing",
+            Task::Escorting => "Escorting",
+            Task::HaulingStores => "HaulingStores",
+        }
+    }
+
+    /// Whether the task can keep an ant on one tile for a long time without
+    /// anything being wrong
+    pub fn stays_put(&self) -> bool {
+        matches!(
+            self,
+            Task::Idle
+                | Task::Gardening
+                | Task::SeekingFood
+                | Task::TendingAphids { .. }
+                | Task::Guarding { .. }
+                | Task::Nursing { .. }
+                | Task::Escorting
+        )
+    }
 }
 
 // ============================================================================
@@ -158,93 +472,180 @@ pub enum Task {
 // ============================================================================
 
 /// Spawn the founding queen and initial workers at the center of the surface
-fn spawn_founding_colony(mut commands: Commands) {
-    let center = WORLD_SIZE / 2;
+fn spawn_founding_colony(
+    mut commands: Commands,
+    options: Res<WorldOptions>,
+    nest: Res<NestLocation>,
+    mut food_stores: ResMut<FoodStores>,
+) {
+    spawn_start(&mut commands, &options, (nest.x, nest.y));
+    if options.founding {
+        // The founding queen lives off her body until the first workers forage
+        food_stores.food = 0;
+    }
+}
+
+/// Spawn whoever the game starts with at the nest: a lone founding queen,
+/// or a queen with her first workers
+pub fn spawn_start(commands: &mut Commands, options: &WorldOptions, nest: (usize, usize)) {
+    if options.founding {
+        spawn_founding_queen(commands, nest);
+    } else {
+        spawn_colony(commands, options.starting_workers, nest);
+    }
+}
+
+/// Spawn a queen at the nest with `workers` adult workers beside her, about
+/// two foragers for every gardener and a nurse for every five workers
+pub fn spawn_colony(commands: &mut Commands, workers: usize, (nest_x, nest_y): (usize, usize)) {
     let surface_z = crate::world::SURFACE_LEVEL;
 
+    // The founding colony starts out as mature adults
+    let adult = CALLOW_TICKS;
+
     // Spawn queen
-    spawn_ant(&mut commands, center, center, surface_z, Caste::Queen);
+    spawn_ant(commands, nest_x, nest_y, surface_z, Caste::Queen, adult);
     info!(
         "Founding queen spawned at ({}, {}, {})",
-        center, center, surface_z
+        nest_x, nest_y, surface_z
     );
 
-    // Spawn foragers
-    for i in 0..3 {
-        spawn_ant(
-            &mut commands,
-            center + i + 1,
-            center,
-            surface_z,
-            Caste::Forager,
-        );
+    let gardeners = workers * 2 / 5;
+    let nurses = workers / 5;
+    let foragers = workers - gardeners - nurses;
+
+    // Foragers line up east of the queen, gardeners west
+    for i in 0..foragers {
+        let x = (nest_x + i + 1).min(WORLD_SIZE - 1);
+        spawn_ant(commands, x, nest_y, surface_z, Caste::Forager, adult);
     }
-    info!("Spawned 3 initial forager workers");
+    info!("Spawned {} initial forager workers", foragers);
 
-    // Spawn gardeners
-    for i in 0..2 {
-        spawn_ant(
-            &mut commands,
-            center - i - 1,
-            center,
-            surface_z,
-            Caste::Gardener,
-        );
+    for i in 0..gardeners {
+        let x = nest_x.saturating_sub(i + 1);
+        spawn_ant(commands, x, nest_y, surface_z, Caste::Gardener, adult);
     }
-    info!("Spawned 2 initial gardener workers");
+    info!("Spawned {} initial gardener workers", gardeners);
+
+    // Nurses wait beside the queen for her first eggs
+    for i in 0..nurses {
+        let y = (nest_y + i + 1).min(WORLD_SIZE - 1);
+        spawn_ant(commands, nest_x, y, surface_z, Caste::Nurse, adult);
+    }
+    info!("Spawned {} initial nurse workers", nurses);
 }
 
-/// Spawn a single ant at the given grid position
-fn spawn_ant(commands: &mut Commands, x: usize, y: usize, z: usize, caste: Caste) {
+/// Spawn a single ant at the given grid position, `age` ticks old
+pub fn spawn_ant(
+    commands: &mut Commands,
+    x: usize,
+    y: usize,
+    z: usize,
+    caste: Caste,
+    age: u32,
+) -> Entity {
     let world_x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
     let world_y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+    let age = Age(age);
+    let maturity = age.maturity();
+
+    commands
+        .spawn((
+            Ant,
+            GridPosition { x, y, z },
+            PreviousPosition(GridPosition { x, y, z }),
+            Facing::default(),
+            caste,
+            Hunger::default(),
+            Crop::default(),
+            age,
+            Inventory::default(),
+            Task::Idle,
+            Sprite {
+                // Recolored for the active palette and sized for the caste on
+                // the first sprite update
+                color: sprites::ants::callow_color(caste.color(Palette::default()), maturity),
+                ..default()
+            },
+            Transform::from_xyz(world_x, world_y, 1.0),
+            AtlasSprite(caste.atlas_index()),
+            AntAnimation::default(),
+            Stillness::default(),
+        ))
+        .id()
+}
 
-    commands.spawn((
-        Ant,
-        GridPosition { x, y, z },
-        caste,
-        Hunger::default(),
-        Age::default(),
-        Carrying::Nothing,
-        Task::Idle,
-        Sprite {
-            color: caste.color(),
-            custom_size: Some(Vec2::splat(caste.size())),
-            ..default()
-        },
-        Transform::from_xyz(world_x, world_y, 1.0),
-    ));
-}
-
-/// Debug: spawn workers with F key
-fn debug_spawn_ant(
-    mut commands: Commands,
-    keyboard: Res<ButtonInput<KeyCode>>,
-    queen_query: Query<&GridPosition, With<Ant>>,
-) {
-    if keyboard.just_pressed(KeyCode::KeyF) {
-        // Find queen position (or any ant if no queen)
-        if let Some(pos) = queen_query.iter().next() {
-            spawn_ant(&mut commands, pos.x, pos.y, pos.z, Caste::Forager);
-            info!(
-                "Debug: Spawned forager at ({}, {}, {})",
-                pos.x, pos.y, pos.z
-            );
+/// Spawn ants requested by live or replayed input
+fn apply_spawn_actions(mut commands: Commands, mut actions: MessageReader<PlayerAction>) {
+    for action in actions.read() {
+        if let PlayerAction::SpawnAnt { caste, x, y, z } = *action {
+            spawn_ant(&mut commands, x, y, z, caste, 0);
+            info!("Debug: Spawned {:?} at ({}, {}, {})", caste, x, y, z);
         }
     }
 }
 
-/// Update ant sprite visibility and position based on current z-level
+/// Remember where every ant is before the tick moves them
+fn record_previous_positions(mut query: Query<(&GridPosition, &mut PreviousPosition), With<Ant>>) {
+    for (grid_pos, mut previous) in &mut query {
+        previous.0 = *grid_pos;
+    }
+}
+
+/// What it takes to draw an ant
+type AntSpriteQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static GridPosition,
+        &'static PreviousPosition,
+        &'static Caste,
+        &'static Age,
+        &'static mut Facing,
+        &'static mut Sprite,
+        &'static mut Transform,
+        &'static mut Visibility,
+    ),
+    With<Ant>,
+>;
+
+/// Update ant sprite visibility, position, heading, and callow coloring based
+/// on current z-level
 fn update_ant_sprites(
     current_z: Res<CurrentZLevel>,
-    mut query: Query<(&GridPosition, &mut Transform, &mut Visibility), With<Ant>>,
+    fixed_time: Res<Time<Fixed>>,
+    palette: Res<Palette>,
+    content: Res<Content>,
+    mut query: AntSpriteQuery,
 ) {
-    for (grid_pos, mut transform, mut visibility) in &mut query {
+    // How far the next tick has got, so ants move smoothly between tiles
+    // rather than a whole tile at a time, at any speed
+    let progress = fixed_time.overstep_fraction();
+
+    for (grid_pos, previous, caste, age, mut facing, mut sprite, mut transform, mut visibility) in
+        &mut query
+    {
         // Update world position from grid position
-        let world_x = (grid_pos.x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
-        let world_y = (grid_pos.y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
-        transform.translation.x = world_x;
-        transform.translation.y = world_y;
+        let current = tile_to_world(grid_pos.x, grid_pos.y);
+        // Ants that changed level or were moved more than a step jump there
+        let from = previous.0;
+        let stepped = from.z == grid_pos.z
+            && from.x.abs_diff(grid_pos.x) <= 1
+            && from.y.abs_diff(grid_pos.y) <= 1;
+        let position = if stepped {
+            tile_to_world(from.x, from.y).lerp(current, progress)
+        } else {
+            current
+        };
+        transform.translation.x = position.x;
+        transform.translation.y = position.y;
+
+        // Turn to face the way the ant last stepped
+        if stepped && (from.x, from.y) != (grid_pos.x, grid_pos.y) {
+            let step = current - tile_to_world(from.x, from.y);
+            facing.0 = step.y.atan2(step.x);
+        }
+        transform.rotation = Quat::from_rotation_z(facing.0);
 
         // Only visible if on current z-level
         *visibility = if grid_pos.z == current_z.0 {
@@ -252,31 +653,150 @@ fn update_ant_sprites(
         } else {
             Visibility::Hidden
         };
+
+        // Callow ants darken and grow to full size over their first day
+        let maturity = age.maturity();
+        if age.0 <= CALLOW_TICKS || palette.is_changed() || sprite.is_added() {
+            sprite.color = sprites::ants::callow_color(caste.color(*palette), maturity);
+        }
+        if age.0 <= CALLOW_TICKS || content.is_changed() || sprite.is_added() {
+            sprite.custom_size = Some(sprites::ants::body_size(
+                content.caste(*caste).size * sprites::ants::callow_scale(maturity),
+            ));
+        }
+    }
+}
+
+/// Ants that might fall, and what a fall does to them
+type GravityQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static mut GridPosition,
+        &'static mut Hunger,
+        &'static Caste,
+        Option<&'static mut Falling>,
+    ),
+    With<Ant>,
+>;
+
+/// System that drops ants with nothing under them one level per tick, hurting
+/// them when they land from high up. Ants never walk off into open air, but
+/// they can be spawned there or have the ground change under them.
+fn ant_gravity(
+    mut commands: Commands,
+    mut query: GravityQuery,
+    world_grid: Res<WorldGrid>,
+    mut deaths: MessageWriter<AntDied>,
+    mut notifications: MessageWriter<Notification>,
+    config: Res<SimulationConfig>,
+) {
+    for (entity, mut grid_pos, mut hunger, caste, falling) in &mut query {
+        let tile = world_grid.tiles[grid_pos.z][grid_pos.y][grid_pos.x];
+        if tile.is_open_air() && grid_pos.z > 0 {
+            grid_pos.z -= 1;
+            match falling {
+                Some(mut falling) => falling.height += 1,
+                None => {
+                    commands.entity(entity).insert(Falling { height: 1 });
+                }
+            }
+            continue;
+        }
+
+        // Landed, maybe hard
+        let Some(falling) = falling else {
+            continue;
+        };
+        commands.entity(entity).remove::<Falling>();
+        let injury = falling.height.saturating_sub(config.safe_fall_height);
+        if injury == 0 {
+            continue;
+        }
+
+        hunger.current += config.fall_injury * injury as f32;
+        if hunger.current >= hunger.max {
+            commands.entity(entity).despawn();
+            deaths.write(AntDied {
+                caste: *caste,
+                cause: DeathCause::Fall,
+            });
+            notifications.write(Notification::warning(format!(
+                "A {} died falling {} levels",
+                caste.name().to_lowercase(),
+                falling.height
+            )));
+        }
     }
 }
 
+/// Ants on their feet, choosing what to do next
+type BehaviorQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut GridPosition,
+        &'static mut Route,
+        &'static Caste,
+        &'static mut Task,
+        &'static Inventory,
+    ),
+    (With<Ant>, Without<Falling>),
+>;
+
 /// Basic ant movement - wander randomly for now
+#[allow(clippy::too_many_arguments)]
 fn ant_behavior(
-    mut query: Query<(&mut GridPosition, &Caste, &mut Task, &Carrying), With<Ant>>,
+    mut query: BehaviorQuery,
     world_grid: Res<WorldGrid>,
     mut pheromones: ResMut<PheromoneGrids>,
-    tree_query: Query<(Entity, &Tree, &LeafSource)>,
+    plant_query: Query<(Entity, &Plant, &LeafSource)>,
     fungus_garden: Res<FungusGarden>,
     nest_location: Res<NestLocation>,
+    designations: Res<Designations>,
+    colony_query: Query<(Entity, &AphidColony)>,
+    seed_query: Query<(Entity, &SeedItem)>,
+    mushroom_query: Query<(Entity, &WildMushroom)>,
+    food_stores: Res<FoodStores>,
+    water: Res<Groundwater>,
+    entrances: Res<Entrances>,
+    knowledge: Res<ColonyKnowledge>,
+    mut rng: ResMut<SimRng>,
+    config: Res<SimulationConfig>,
 ) {
-    for (mut grid_pos, caste, mut task, carrying) in &mut query {
-        // Queen doesn't move (for now)
-        if *caste == Caste::Queen {
+    for (mut grid_pos, mut route, caste, mut task, inventory) in &mut query {
+        // The queen only moves when the nest does, and alates wait for
+        // their flight
+        if matches!(caste, Caste::Queen | Caste::Alate) {
             continue;
         }
 
         // Skip ants that are carrying things or already foraging/carrying home
-        if !matches!(*carrying, Carrying::Nothing) {
+        if !inventory.is_empty() {
             continue;
         }
 
         match *task {
             Task::Idle => {
+                // Gardeners drop everything for a badly contaminated garden tile,
+                // and otherwise clean between leaf deliveries
+                let clean_threshold = if fungus_garden.leaves > 0 {
+                    config.urgent_clean_threshold
+                } else {
+                    config.clean_threshold
+                };
+                if *caste == Caste::Gardener
+                    && let Some((x, y, z)) = fungus_garden.dirtiest_plot(clean_threshold)
+                {
+                    *task = Task::Cleaning {
+                        target_x: x,
+                        target_y: y,
+                        target_z: z,
+                    };
+                    continue;
+                }
+
                 // Gardeners prioritize processing leaves at the nest
                 if *caste == Caste::Gardener && fungus_garden.leaves > 0 {
                     // Check if at nest
@@ -297,13 +817,74 @@ fn ant_behavior(
                     }
                 }
 
-                // Foragers prioritize finding trees when there are Forage pheromones
+                // Foragers prioritize finding plants when there are Forage pheromones
                 if *caste == Caste::Forager
-                    && let Some(tree_entity) =
-                        find_forage_target(&grid_pos, &pheromones, &tree_query)
+                    && let Some(plant_entity) =
+                        find_forage_target(&grid_pos, &pheromones, &plant_query, &knowledge)
                 {
                     *task = Task::Foraging {
-                        target_tree: tree_entity,
+                        target_plant: plant_entity,
+                    };
+                    continue;
+                }
+
+                // Wild mushrooms are quick food while the stores are thin
+                if *caste == Caste::Forager
+                    && food_stores.total() < food_stores.capacity / 2
+                    && let Some(patch) = find_nearest_mushroom(&grid_pos, &mushroom_query)
+                {
+                    *task = Task::HarvestingMushroom { patch };
+                    continue;
+                }
+
+                // When the stores run low, foragers fall back on fallen seeds
+                if *caste == Caste::Forager
+                    && food_stores.food + food_stores.honeydew < config.emergency_food_level
+                    && let Some(seed) = find_nearest_seed(&grid_pos, &seed_query)
+                {
+                    *task = Task::GatheringSeed { seed };
+                    continue;
+                }
+
+                // Foragers look after aphids that have honeydew ready
+                if *caste == Caste::Forager
+                    && let Some(colony) =
+                        find_honeydew(&grid_pos, &colony_query, config.honeydew_load)
+                {
+                    *task = Task::TendingAphids { colony };
+                    continue;
+                }
+
+                // Soldiers man the entrances before anything else
+                if *caste == Caste::Soldier
+                    && let Some((x, y)) = entrances.short_handed()
+                {
+                    *task = Task::Guarding { x, y };
+                    continue;
+                }
+
+                // Flooded tunnels need bailing before any more digging
+                if *caste != Caste::Gardener
+                    && rng.random_ratio(1, 3)
+                    && let Some((tx, ty, tz)) =
+                        water.bail_target((grid_pos.x, grid_pos.y, grid_pos.z))
+                {
+                    *task = Task::Bailing {
+                        target_x: tx,
+                        target_y: ty,
+                        target_z: tz,
+                    };
+                    continue;
+                }
+
+                // Player designations take priority over pheromone hints
+                if let Some((tx, ty, tz)) =
+                    designations.nearest_workable(&world_grid, (grid_pos.x, grid_pos.y, grid_pos.z))
+                {
+                    *task = Task::Digging {
+                        target_x: tx,
+                        target_y: ty,
+                        target_z: tz,
                     };
                     continue;
                 }
@@ -322,19 +903,24 @@ fn ant_behavior(
 
                 // Decide what to do randomly
                 use rand::Rng;
-                let mut rng = rand::rng();
 
-                // Foragers: 30% forage, 10% dig, 60% wander
+                // Foragers: 30% forage (or scout if no plants are known), 10%
+                // dig, 60% wander
                 // Gardeners: 50% go to garden (if leaves), 10% dig, 40% wander
                 // Others: 10% dig, 90% wander
                 if *caste == Caste::Forager && rng.random_ratio(3, 10) {
-                    // Try to find a tree to forage
-                    if let Some(tree_entity) = find_nearest_tree(&grid_pos, &tree_query) {
+                    // Try to find a plant to forage
+                    if let Some(plant_entity) = find_best_plant(&grid_pos, &plant_query, &knowledge)
+                    {
                         *task = Task::Foraging {
-                            target_tree: tree_entity,
+                            target_plant: plant_entity,
                         };
                     } else {
-                        *task = Task::Wandering;
+                        // Nothing worth cutting that we know of; go looking
+                        *task = Task::Scouting {
+                            target_x: rng.random_range(0..WORLD_SIZE),
+                            target_y: rng.random_range(0..WORLD_SIZE),
+                        };
                     }
                 } else if *caste == Caste::Gardener && rng.random_ratio(5, 10) {
                     // Gardeners go to nest to work
@@ -359,11 +945,10 @@ fn ant_behavior(
             }
             Task::Wandering => {
                 // Check for pheromones to follow and reinforce trails
-                try_pheromone_biased_move(&mut grid_pos, &world_grid, &mut pheromones);
+                try_pheromone_biased_move(&mut grid_pos, &world_grid, &mut pheromones, &mut *rng);
 
                 // Small chance to go idle and reconsider
                 use rand::Rng;
-                let mut rng = rand::rng();
                 if rng.random_ratio(1, 30) {
                     *task = Task::Idle;
                 }
@@ -373,11 +958,6 @@ fn ant_behavior(
                 target_y,
                 target_z,
             } => {
-                // Move towards target if not adjacent
-                let dx = (target_x as i32 - grid_pos.x as i32).signum();
-                let dy = (target_y as i32 - grid_pos.y as i32).signum();
-                let dz = (target_z as i32 - grid_pos.z as i32).signum();
-
                 // Check if we're adjacent to the target (including z)
                 let dist_x = (target_x as i32 - grid_pos.x as i32).abs();
                 let dist_y = (target_y as i32 - grid_pos.y as i32).abs();
@@ -389,33 +969,74 @@ fn ant_behavior(
                 if is_adjacent {
                     // We're adjacent - digging happens in ant_digging system
                     // Stay in Digging state
+                } else if let Some(approach) =
+                    dig_approach(&world_grid, (target_x, target_y, target_z))
+                {
+                    // Walk to a tile bordering the target
+                    step_toward(&mut grid_pos, &mut route, approach, &world_grid);
                 } else {
-                    // Move towards target on same z-level first
-                    if dist_x > 0 || dist_y > 0 {
-                        let new_x =
-                            (grid_pos.x as i32 + dx).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                        let new_y =
-                            (grid_pos.y as i32 + dy).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                        let tile = world_grid.tiles[grid_pos.z][new_y][new_x];
-                        if is_passable(tile) {
-                            grid_pos.x = new_x;
-                            grid_pos.y = new_y;
-                        }
-                    } else if dist_z > 0 && dz < 0 {
-                        // Need to go down - check if tile below is passable
-                        let new_z =
-                            (grid_pos.z as i32 + dz).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                        let tile = world_grid.tiles[new_z][grid_pos.y][grid_pos.x];
-                        if is_passable(tile) {
-                            grid_pos.z = new_z;
-                        }
-                    }
+                    // Nowhere to stand next to it (yet)
+                    *task = Task::Idle;
                 }
             }
-            Task::Foraging { .. } | Task::CarryingHome { .. } | Task::SeekingFood => {
+            Task::Foraging { .. }
+            | Task::Cutting { .. }
+            | Task::CarryingHome { .. }
+            | Task::SeekingFood => {
                 // Handled by ant_foraging, ant_carrying, and ant_feeding systems
             }
-            Task::Gardening => {
+            Task::TendingAphids { .. } => {
+                // Handled by ant_tending_aphids system
+            }
+            Task::GatheringSeed { .. } => {
+                // Handled by ant_gathering_seeds system
+            }
+            Task::HarvestingMushroom { .. } => {
+                // Handled by ant_harvesting_mushrooms system
+            }
+            Task::Bailing { .. } => {
+                // Handled by ant_bailing system
+            }
+            Task::Scouting { target_x, target_y } => {
+                use rand::Rng;
+
+                // Plants are spotted along the way; give up now and then
+                // in case the spot can't be reached
+                if (grid_pos.x, grid_pos.y, grid_pos.z) == (target_x, target_y, SURFACE_LEVEL)
+                    || rng.random_ratio(1, config.scout_give_up_odds)
+                {
+                    *task = Task::Idle;
+                } else {
+                    step_toward(
+                        &mut grid_pos,
+                        &mut route,
+                        (target_x, target_y, SURFACE_LEVEL),
+                        &world_grid,
+                    );
+                }
+            }
+            Task::FetchingBrood { .. } | Task::CarryingBrood { .. } | Task::Nursing { .. } => {
+                // Handled by ant_nursing system
+            }
+            Task::Escorting | Task::HaulingStores => {
+                // Handled by the relocation systems
+            }
+            Task::Guarding { x, y } => {
+                if !entrances.contains(x, y) {
+                    // The entrance was filled in; find other work
+                    *task = Task::Idle;
+                } else if grid_pos.x.abs_diff(x) + grid_pos.y.abs_diff(y) > 1
+                    || grid_pos.z != SURFACE_LEVEL
+                {
+                    step_toward(
+                        &mut grid_pos,
+                        &mut route,
+                        (x, y, SURFACE_LEVEL),
+                        &world_grid,
+                    );
+                }
+            }
+            Task::Gardening | Task::TendingGarden { .. } | Task::Cleaning { .. } => {
                 // Handled by ant_gardening system
             }
         }
@@ -448,8 +1069,13 @@ fn find_diggable_tile(pos: &GridPosition, world_grid: &WorldGrid) -> Option<(usi
             continue;
         }
 
+        // New entrances are only opened where the player designates them
+        if pos.z == SURFACE_LEVEL && dz < 0 {
+            continue;
+        }
+
         let tile = world_grid.tiles[nz as usize][ny as usize][nx as usize];
-        if tile == TileKind::Dirt {
+        if tile.is_diggable() {
             return Some((nx as usize, ny as usize, nz as usize));
         }
     }
@@ -458,11 +1084,20 @@ fn find_diggable_tile(pos: &GridPosition, world_grid: &WorldGrid) -> Option<(usi
 }
 
 /// System that performs actual digging
+#[allow(clippy::too_many_arguments)]
 fn ant_digging(
-    mut query: Query<(&GridPosition, &mut Task), With<Ant>>,
+    mut query: Query<(&GridPosition, &Caste, &mut Task), With<Ant>>,
     mut world_grid: ResMut<WorldGrid>,
+    mut designations: ResMut<Designations>,
+    air: Res<AirQuality>,
+    moisture: Res<SoilMoisture>,
+    mut progress: ResMut<DigProgress>,
+    mut dug_tiles: MessageWriter<TileDug>,
+    mut rng: ResMut<SimRng>,
+    content: Res<Content>,
+    config: Res<SimulationConfig>,
 ) {
-    for (grid_pos, mut task) in &mut query {
+    for (grid_pos, caste, mut task) in &mut query {
         if let Task::Digging {
             target_x,
             target_y,
@@ -478,13 +1113,50 @@ fn ant_digging(
                 (dist_x <= 1 && dist_y <= 1 && dist_z <= 1) && (dist_x + dist_y + dist_z > 0);
 
             if is_adjacent {
-                // Check if target is still dirt
-                if world_grid.tiles[target_z][target_y][target_x] == TileKind::Dirt {
-                    // Dig it!
-                    world_grid.tiles[target_z][target_y][target_x] = TileKind::Tunnel;
+                // Stale air leaves the digger sluggish
+                if air.stalls(&mut *rng, grid_pos.x, grid_pos.y, grid_pos.z) {
+                    continue;
+                }
+
+                let target = world_grid.tiles[target_z][target_y][target_x];
+
+                // Roots take many ticks of gnawing before they give way, and
+                // harder or drier soils take longer than soft, wet ones
+                let dig_speed = match target {
+                    TileKind::Root => config.root_dig_speed,
+                    _ => moisture.dig_chance(
+                        world_grid.soil[target_z][target_y][target_x],
+                        target_x,
+                        target_y,
+                        target_z,
+                    ),
+                };
+                let work = dig_speed as f32 * content.caste(*caste).dig_strength * config.dig_rate;
+                if target.is_diggable() && !progress.advance(target_x, target_y, target_z, work) {
+                    continue;
+                }
+
+                // Check if target is still diggable
+                if target.is_diggable() {
+                    // Dig it! Designations decide the shape
+                    let dug =
+                        if let Some(kind) = designations.complete(target_x, target_y, target_z) {
+                            kind.tile()
+                        } else if target_x == grid_pos.x && target_y == grid_pos.y {
+                            // Digging straight up or down cuts a ramp to climb
+                            TileKind::Ramp
+                        } else {
+                            TileKind::Tunnel
+                        };
+                    world_grid.tiles[target_z][target_y][target_x] = dug;
+                    dug_tiles.write(TileDug {
+                        x: target_x,
+                        y: target_y,
+                        z: target_z,
+                    });
                     info!(
-                        "Ant dug tunnel at ({}, {}, {})",
-                        target_x, target_y, target_z
+                        "Ant dug {:?} at ({}, {}, {})",
+                        dug, target_x, target_y, target_z
                     );
                 }
                 // Task complete - go idle
@@ -494,41 +1166,109 @@ fn ant_digging(
     }
 }
 
-/// System that handles ants foraging for leaves from trees
+/// System that handles ants walking to plants and cutting leaves from them
+#[allow(clippy::too_many_arguments)]
 fn ant_foraging(
-    mut ant_query: Query<(&mut GridPosition, &mut Task, &mut Carrying), With<Ant>>,
-    mut tree_query: Query<(&Tree, &mut LeafSource)>,
+    mut ant_query: Query<
+        (
+            &mut GridPosition,
+            &mut Route,
+            &Caste,
+            &mut Task,
+            &mut Inventory,
+        ),
+        With<Ant>,
+    >,
+    mut plant_query: Query<(&Plant, &mut LeafSource)>,
+    migrant_query: Query<&Migrant>,
     world_grid: Res<WorldGrid>,
-    nest_location: Res<NestLocation>,
+    entrances: Res<Entrances>,
     mut pheromones: ResMut<PheromoneGrids>,
+    content: Res<Content>,
+    config: Res<SimulationConfig>,
 ) {
-    for (mut grid_pos, mut task, mut carrying) in &mut ant_query {
-        if let Task::Foraging { target_tree } = *task {
-            // Get the tree's position
-            let Some((tree, mut leaf_source)) = tree_query.get_mut(target_tree).ok() else {
-                // Tree no longer exists, go idle
-                *task = Task::Idle;
-                continue;
-            };
+    for (mut grid_pos, mut route, caste, mut task, mut inventory) in &mut ant_query {
+        match *task {
+            Task::Foraging { target_plant } => {
+                // Get the plant's position
+                let Some((plant, leaf_source)) = plant_query.get(target_plant).ok() else {
+                    // Plant no longer exists, go idle
+                    *task = Task::Idle;
+                    continue;
+                };
 
-            // Check if tree still has leaves
-            if leaf_source.leaves_remaining == 0 {
-                *task = Task::Idle;
-                continue;
+                // Check if plant still has leaves
+                if leaf_source.leaves_remaining == 0 {
+                    *task = Task::Idle;
+                    continue;
+                }
+
+                let spot = plant.forage_spot();
+
+                // Check if we're next to (or right at) the leaves: beside smaller
+                // plants on the surface, or up in a tree's canopy
+                let dist_x = (plant.x as i32 - grid_pos.x as i32).abs();
+                let dist_y = (plant.y as i32 - grid_pos.y as i32).abs();
+                let is_adjacent = dist_x <= 1 && dist_y <= 1;
+
+                if is_adjacent && grid_pos.z == spot.2 {
+                    // We're next to the plant - set to work
+                    *task = Task::Cutting {
+                        target_plant,
+                        progress: 0,
+                    };
+                } else {
+                    // Walk to the plant, climbing the trunk for a tree
+                    step_toward(&mut grid_pos, &mut route, spot, &world_grid);
+                }
             }
+            Task::Cutting {
+                target_plant,
+                progress,
+            } => {
+                // A spider coming close sends the ant running for the nest
+                // with whatever it has cut so far
+                let alarmed = migrant_query.iter().any(|migrant| {
+                    migrant.kind == MigrantKind::Spider
+                        && migrant.x.abs_diff(grid_pos.x) + migrant.y.abs_diff(grid_pos.y)
+                            <= config.alarm_range
+                });
+
+                let Some((plant, mut leaf_source)) = plant_query.get_mut(target_plant).ok() else {
+                    // Plant no longer exists, go home with what we have
+                    carry_cut_home(&grid_pos, &mut task, &inventory, &entrances);
+                    continue;
+                };
 
-            let tree_x = tree.x;
-            let tree_y = tree.y;
+                if alarmed || leaf_source.leaves_remaining == 0 {
+                    if alarmed {
+                        info!(
+                            "Ant fled a spider while cutting {} at ({}, {})",
+                            plant.kind.name(),
+                            plant.x,
+                            plant.y
+                        );
+                    }
+                    carry_cut_home(&grid_pos, &mut task, &inventory, &entrances);
+                    continue;
+                }
 
-            // Check if we're adjacent to the tree (on the surface level)
-            let dist_x = (tree_x as i32 - grid_pos.x as i32).abs();
-            let dist_y = (tree_y as i32 - grid_pos.y as i32).abs();
-            let is_adjacent = dist_x <= 1 && dist_y <= 1 && (dist_x + dist_y > 0);
+                if progress + 1 < config.leaf_cut_ticks {
+                    *task = Task::Cutting {
+                        target_plant,
+                        progress: progress + 1,
+                    };
+                    continue;
+                }
 
-            if is_adjacent && grid_pos.z == SURFACE_LEVEL {
-                // We're next to the tree - cut a leaf!
-                leaf_source.leaves_remaining = leaf_source.leaves_remaining.saturating_sub(1);
-                *carrying = Carrying::Leaf;
+                // A fragment comes loose
+                leaf_source.leaves_remaining -= 1;
+                inventory.add(
+                    Carrying::Leaf {
+                        value: leaf_source.leaf_value,
+                    },
+                    1,
+                );
 
                 // Deposit strong Forage pheromone at this successful foraging location
                 pheromones.add(
@@ -540,59 +1280,390 @@ fn ant_foraging(
                 );
 
                 info!(
-                    "Ant cut leaf from tree at ({}, {}). {} leaves remaining.",
-                    tree_x, tree_y, leaf_source.leaves_remaining
+                    "Ant cut a leaf from {} at ({}, {}). {} leaves remaining.",
+                    plant.kind.name(),
+                    plant.x,
+                    plant.y,
+                    leaf_source.leaves_remaining
                 );
 
-                // Now carry the leaf home
-                *task = Task::CarryingHome {
-                    home_x: nest_location.x,
-                    home_y: nest_location.y,
-                    home_z: nest_location.z,
-                };
-            } else {
-                // Move towards the tree on the surface level
-                if grid_pos.z != SURFACE_LEVEL {
-                    // Need to get to surface first - move up if possible
-                    let new_z = grid_pos.z + 1;
-                    if new_z < WORLD_SIZE
-                        && is_passable(world_grid.tiles[new_z][grid_pos.y][grid_pos.x])
-                    {
-                        grid_pos.z = new_z;
-                    }
+                if inventory.quantity >= content.caste(*caste).load_capacity
+                    || leaf_source.leaves_remaining == 0
+                {
+                    carry_cut_home(&grid_pos, &mut task, &inventory, &entrances);
                 } else {
-                    // Move towards tree on surface
-                    let dx = (tree_x as i32 - grid_pos.x as i32).signum();
-                    let dy = (tree_y as i32 - grid_pos.y as i32).signum();
-
-                    let new_x = (grid_pos.x as i32 + dx).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                    let new_y = (grid_pos.y as i32 + dy).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-
-                    if is_passable(world_grid.tiles[grid_pos.z][new_y][new_x]) {
-                        grid_pos.x = new_x;
-                        grid_pos.y = new_y;
-                    } else if is_passable(world_grid.tiles[grid_pos.z][grid_pos.y][new_x]) {
-                        // Try just x movement
-                        grid_pos.x = new_x;
-                    } else if is_passable(world_grid.tiles[grid_pos.z][new_y][grid_pos.x]) {
-                        // Try just y movement
-                        grid_pos.y = new_y;
-                    }
+                    *task = Task::Cutting {
+                        target_plant,
+                        progress: 0,
+                    };
                 }
             }
+            _ => {}
         }
     }
 }
 
-/// System that handles ants carrying resources back to the nest
-fn ant_carrying(
-    mut query: Query<(&mut GridPosition, &mut Task, &mut Carrying), With<Ant>>,
-    world_grid: Res<WorldGrid>,
-    mut fungus_garden: ResMut<FungusGarden>,
-    mut pheromones: ResMut<PheromoneGrids>,
+/// Head for the nearest entrance with the leaves cut so far, climbing back
+/// down the trunk if they came from a tree, or give up empty-handed
+fn carry_cut_home(
+    grid_pos: &GridPosition,
+    task: &mut Task,
+    inventory: &Inventory,
+    entrances: &Entrances,
 ) {
-    for (mut grid_pos, mut task, mut carrying) in &mut query {
-        if let Task::CarryingHome {
+    if inventory.is_empty() {
+        *task = Task::Idle;
+        return;
+    }
+    let (home_x, home_y, home_z) = entrances.nearest(grid_pos.x, grid_pos.y);
+    *task = Task::CarryingHome {
+        home_x,
+        home_y,
+        home_z,
+    };
+}
+
+/// System that handles foragers picking up fallen seeds
+fn ant_gathering_seeds(
+    mut commands: Commands,
+    mut ant_query: Query<(&mut GridPosition, &mut Route, &mut Task, &mut Inventory), With<Ant>>,
+    seed_query: Query<&SeedItem>,
+    world_grid: Res<WorldGrid>,
+    entrances: Res<Entrances>,
+) {
+    for (mut grid_pos, mut route, mut task, mut inventory) in &mut ant_query {
+        let Task::GatheringSeed { seed } = *task else {
+            continue;
+        };
+        let Ok(item) = seed_query.get(seed) else {
+            // Someone else got it first, or it sprouted or rotted
+            *task = Task::Idle;
+            continue;
+        };
+
+        if (grid_pos.x, grid_pos.y, grid_pos.z) != (item.x, item.y, SURFACE_LEVEL) {
+            step_toward(
+                &mut grid_pos,
+                &mut route,
+                (item.x, item.y, SURFACE_LEVEL),
+                &world_grid,
+            );
+            continue;
+        }
+
+        commands.entity(seed).despawn();
+        inventory.hold(Carrying::Seed, 1);
+        let (home_x, home_y, home_z) = entrances.nearest(grid_pos.x, grid_pos.y);
+        *task = Task::CarryingHome {
+            home_x,
+            home_y,
+            home_z,
+        };
+    }
+}
+
+/// System that handles foragers harvesting wild mushroom patches
+fn ant_harvesting_mushrooms(
+    mut commands: Commands,
+    mut ant_query: Query<(&mut GridPosition, &mut Route, &mut Task, &mut Inventory), With<Ant>>,
+    mut mushroom_query: Query<&mut WildMushroom>,
+    world_grid: Res<WorldGrid>,
+    entrances: Res<Entrances>,
+    mut rng: ResMut<SimRng>,
+) {
+    use rand::Rng;
+
+    for (mut grid_pos, mut route, mut task, mut inventory) in &mut ant_query {
+        let Task::HarvestingMushroom { patch } = *task else {
+            continue;
+        };
+        let Ok(mut mushroom) = mushroom_query.get_mut(patch) else {
+            // Already picked clean
+            *task = Task::Idle;
+            continue;
+        };
+
+        if (grid_pos.x, grid_pos.y, grid_pos.z) != (mushroom.x, mushroom.y, SURFACE_LEVEL) {
+            step_toward(
+                &mut grid_pos,
+                &mut route,
+                (mushroom.x, mushroom.y, SURFACE_LEVEL),
+                &world_grid,
+            );
+            continue;
+        }
+
+        if mushroom.harvest() {
+            commands.entity(patch).despawn();
+        }
+        inventory.hold(
+            Carrying::WildFungus {
+                tainted: rng.random_bool(MUSHROOM_TAINT_CHANCE),
+            },
+            1,
+        );
+        let (home_x, home_y, home_z) = entrances.nearest(grid_pos.x, grid_pos.y);
+        *task = Task::CarryingHome {
+            home_x,
+            home_y,
+            home_z,
+        };
+    }
+}
+
+/// System that handles ants tending aphids on tree canopies and collecting
+/// their honeydew
+fn ant_tending_aphids(
+    mut ant_query: Query<
+        (
+            &mut GridPosition,
+            &mut Route,
+            &mut Task,
+            &mut Inventory,
+            &mut Hunger,
+        ),
+        With<Ant>,
+    >,
+    mut colony_query: Query<&mut AphidColony>,
+    world_grid: Res<WorldGrid>,
+    entrances: Res<Entrances>,
+    config: Res<SimulationConfig>,
+) {
+    for (mut grid_pos, mut route, mut task, mut inventory, mut hunger) in &mut ant_query {
+        let Task::TendingAphids { colony } = *task else {
+            continue;
+        };
+        let Ok(mut colony) = colony_query.get_mut(colony) else {
+            // The colony died out
+            *task = Task::Idle;
+            continue;
+        };
+
+        let at_tree = grid_pos.z == SURFACE_LEVEL
+            && grid_pos.x.abs_diff(colony.x) <= 1
+            && grid_pos.y.abs_diff(colony.y) <= 1;
+        if !at_tree {
+            step_toward(
+                &mut grid_pos,
+                &mut route,
+                (colony.x, colony.y, SURFACE_LEVEL),
+                &world_grid,
+            );
+            continue;
+        }
+
+        // Stroke the aphids until a full drop of honeydew is ready
+        if colony.honeydew < config.honeydew_load {
+            continue;
+        }
+        colony.collect(config.honeydew_load);
+
+        // Honeydew needs no processing, so a hungry tender just drinks it
+        if hunger.current >= config.honeydew_drink_hunger {
+            hunger.current = 0.0;
+            info!("Ant drank honeydew at ({}, {})", colony.x, colony.y);
+            continue;
+        }
+
+        inventory.hold(Carrying::Honeydew, 1);
+        let (home_x, home_y, home_z) = entrances.nearest(grid_pos.x, grid_pos.y);
+        *task = Task::CarryingHome {
+            home_x,
+            home_y,
+            home_z,
+        };
+    }
+}
+
+/// Ants on their feet, for the nurses among them to see to the brood
+type NurseQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        &'static mut GridPosition,
+        &'static mut Route,
+        &'static Caste,
+        &'static mut Task,
+    ),
+    (With<Ant>, Without<Falling>),
+>;
+
+/// System that handles nurses: finding brood that needs them, carrying it
+/// down to the nursery, and feeding and tending it there
+fn ant_nursing(
+    mut ant_query: NurseQuery,
+    mut brood_query: Query<(Entity, &mut Brood, &mut GridPosition), Without<Ant>>,
+    world_grid: Res<WorldGrid>,
+    nursery: Res<Nursery>,
+    mut food_stores: ResMut<FoodStores>,
+) {
+    for (mut grid_pos, mut route, caste, mut task) in &mut ant_query {
+        if *caste == Caste::Nurse
+            && matches!(*task, Task::Idle)
+            && let Some(work) = find_brood_work(&grid_pos, &brood_query, &nursery, &food_stores)
+        {
+            *task = work;
+        }
+
+        match *task {
+            Task::FetchingBrood { brood } => {
+                let Ok((_, _, brood_pos)) = brood_query.get(brood) else {
+                    // It hatched, died, or someone else moved it
+                    *task = Task::Idle;
+                    continue;
+                };
+                let at = (brood_pos.x, brood_pos.y, brood_pos.z);
+                if (grid_pos.x, grid_pos.y, grid_pos.z) == at {
+                    *task = Task::CarryingBrood { brood };
+                } else {
+                    step_toward(&mut grid_pos, &mut route, at, &world_grid);
+                }
+            }
+            Task::CarryingBrood { brood } => {
+                let (Some(tile), Ok((_, mut item, mut brood_pos))) =
+                    (nursery.tile, brood_query.get_mut(brood))
+                else {
+                    *task = Task::Idle;
+                    continue;
+                };
+                if (grid_pos.x, grid_pos.y, grid_pos.z) != tile {
+                    step_toward(&mut grid_pos, &mut route, tile, &world_grid);
+                }
+
+                // The brood comes along in the nurse's mandibles
+                brood_pos.x = grid_pos.x;
+                brood_pos.y = grid_pos.y;
+                brood_pos.z = grid_pos.z;
+                if (grid_pos.x, grid_pos.y, grid_pos.z) == tile {
+                    item.since_tended = 0;
+                    *task = Task::Idle;
+                }
+            }
+            Task::Nursing { brood } => {
+                let Ok((_, mut item, brood_pos)) = brood_query.get_mut(brood) else {
+                    *task = Task::Idle;
+                    continue;
+                };
+                let at = (brood_pos.x, brood_pos.y, brood_pos.z);
+                if (grid_pos.x, grid_pos.y, grid_pos.z) != at {
+                    step_toward(&mut grid_pos, &mut route, at, &world_grid);
+                    continue;
+                }
+
+                item.since_tended = 0;
+                if item.is_hungry() && !feed_larva(&mut item, &mut food_stores) {
+                    info!("A nurse found nothing in the stores to feed a larva");
+                }
+                *task = Task::Idle;
+            }
+            _ => {}
+        }
+    }
+}
+
+/// The most pressing brood care for a nurse: brood lying outside the
+/// nursery first, then hungry larvae while there's food to give them, then
+/// whatever has gone longest without tending
+fn find_brood_work(
+    pos: &GridPosition,
+    brood_query: &Query<(Entity, &mut Brood, &mut GridPosition), Without<Ant>>,
+    nursery: &Nursery,
+    food_stores: &FoodStores,
+) -> Option<Task> {
+    let distance = |other: &GridPosition| {
+        other.x.abs_diff(pos.x) + other.y.abs_diff(pos.y) + other.z.abs_diff(pos.z)
+    };
+
+    if let Some(tile) = nursery.tile
+        && let Some((brood, ..)) = brood_query
+            .iter()
+            .filter(|(_, _, brood_pos)| (brood_pos.x, brood_pos.y, brood_pos.z) != tile)
+            .min_by_key(|(_, _, brood_pos)| distance(brood_pos))
+    {
+        return Some(Task::FetchingBrood { brood });
+    }
+
+    if food_stores.food + food_stores.seeds > 0
+        && let Some((brood, ..)) = brood_query
+            .iter()
+            .filter(|(_, item, _)| item.is_hungry())
+            .min_by_key(|(_, _, brood_pos)| distance(brood_pos))
+    {
+        return Some(Task::Nursing { brood });
+    }
+
+    brood_query
+        .iter()
+        .filter(|(_, item, _)| item.needs_tending())
+        .max_by_key(|(_, item, _)| item.since_tended)
+        .map(|(brood, ..)| Task::Nursing { brood })
+}
+
+/// System that handles ants bailing out flooded tunnels, one mouthful at a
+/// time carried up to the nest entrance
+fn ant_bailing(
+    mut ant_query: Query<(&mut GridPosition, &mut Route, &mut Task, &mut Inventory), With<Ant>>,
+    mut water: ResMut<Groundwater>,
+    world_grid: Res<WorldGrid>,
+    entrances: Res<Entrances>,
+    config: Res<SimulationConfig>,
+) {
+    for (mut grid_pos, mut route, mut task, mut inventory) in &mut ant_query {
+        let Task::Bailing {
+            target_x,
+            target_y,
+            target_z,
+        } = *task
+        else {
+            continue;
+        };
+
+        if water.level[target_z][target_y][target_x] < BAIL_THRESHOLD {
+            // Someone else bailed it out first; move on to the next one
+            *task = match water.bail_target((grid_pos.x, grid_pos.y, grid_pos.z)) {
+                Some((x, y, z)) => Task::Bailing {
+                    target_x: x,
+                    target_y: y,
+                    target_z: z,
+                },
+                None => Task::Idle,
+            };
+            continue;
+        }
+
+        if (grid_pos.x, grid_pos.y, grid_pos.z) != (target_x, target_y, target_z) {
+            step_toward(
+                &mut grid_pos,
+                &mut route,
+                (target_x, target_y, target_z),
+                &world_grid,
+            );
+            continue;
+        }
+
+        water.bail(target_x, target_y, target_z, config.bail_amount);
+        inventory.hold(Carrying::Water, 1);
+        let (home_x, home_y, home_z) = entrances.nearest(grid_pos.x, grid_pos.y);
+        *task = Task::CarryingHome {
+            home_x,
+            home_y,
+            home_z,
+        };
+    }
+}
+
+/// System that handles ants carrying resources back to the nest
+fn ant_carrying(
+    mut query: Query<(&mut GridPosition, &mut Route, &mut Task, &mut Inventory), With<Ant>>,
+    world_grid: Res<WorldGrid>,
+    tick: Res<SimulationTick>,
+    mut fungus_garden: ResMut<FungusGarden>,
+    mut food_stores: ResMut<FoodStores>,
+    mut pheromones: ResMut<PheromoneGrids>,
+    config: Res<SimulationConfig>,
+) {
+    for (mut grid_pos, mut route, mut task, mut inventory) in &mut query {
+        if let Task::CarryingHome {
             home_x,
             home_y,
             home_z,
@@ -601,19 +1672,70 @@ fn ant_carrying(
             // Check if we're at the nest
             if grid_pos.x == home_x && grid_pos.y == home_y && grid_pos.z == home_z {
                 // Drop the resource into the fungus garden
-                if let Carrying::Leaf = *carrying {
-                    fungus_garden.add_leaf();
-                    info!(
-                        "Ant delivered leaf to garden. Total: {} leaves, {} mulch, {} food",
-                        fungus_garden.leaves, fungus_garden.mulch, fungus_garden.food
-                    );
+                match inventory.load {
+                    Carrying::Leaf { value } => {
+                        fungus_garden.add_leaves(value * inventory.quantity);
+                        info!(
+                            "Ant delivered {} leaves to garden. {} leaves waiting.",
+                            inventory.quantity, fungus_garden.leaves
+                        );
+                    }
+                    Carrying::WildFungus { tainted } => {
+                        if food_stores.store_food() {
+                            info!(
+                                "Ant delivered wild fungus. {} food in stores.",
+                                food_stores.food
+                            );
+                        }
+                        if tainted {
+                            fungus_garden.contaminate_near(
+                                (home_x, home_y, home_z),
+                                MUSHROOM_SPORE_RADIUS,
+                                MUSHROOM_CONTAMINATION,
+                            );
+                            info!("Wild fungus brought mold spores into the garden");
+                        }
+                    }
+                    Carrying::FungusFood => {
+                        // Stores moved over from the old nest
+                        let stored = (0..inventory.quantity)
+                            .filter(|_| food_stores.store_food())
+                            .count();
+                        info!(
+                            "Ant moved {} food to the new nest. {} food in stores.",
+                            stored, food_stores.food
+                        );
+                    }
+                    Carrying::Seed if food_stores.store_seed() => {
+                        info!("Ant stored a seed. {} seeds in stores.", food_stores.seeds);
+                    }
+                    Carrying::Water => {
+                        // Poured out on the surface, well away from the tunnels
+                    }
+                    Carrying::Honeydew => {
+                        if food_stores.store_honeydew() {
+                            info!(
+                                "Ant delivered honeydew. {} honeydew in stores.",
+                                food_stores.honeydew
+                            );
+                        } else {
+                            info!("Stores are full; delivered honeydew was wasted");
+                        }
+                    }
+                    _ => {}
                 }
-                *carrying = Carrying::Nothing;
+                inventory.drop_all();
                 *task = Task::Idle;
             } else {
                 // Deposit Home pheromone while carrying resources back
                 // This creates a trail for other ants to follow home
-                if matches!(*carrying, Carrying::Leaf) {
+                if matches!(
+                    inventory.load,
+                    Carrying::Leaf { .. }
+                        | Carrying::Honeydew
+                        | Carrying::Seed
+                        | Carrying::WildFungus { .. }
+                ) {
                     pheromones.add(
                         PheromoneType::Home,
                         grid_pos.x,
@@ -623,96 +1745,182 @@ fn ant_carrying(
                     );
                 }
 
-                // Move towards home
-                let dx = (home_x as i32 - grid_pos.x as i32).signum();
-                let dy = (home_y as i32 - grid_pos.y as i32).signum();
-                let dz = (home_z as i32 - grid_pos.z as i32).signum();
-
-                // Try to move on the same z-level first
-                if grid_pos.z == home_z || dz == 0 {
-                    let new_x = (grid_pos.x as i32 + dx).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                    let new_y = (grid_pos.y as i32 + dy).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-
-                    if is_passable(world_grid.tiles[grid_pos.z][new_y][new_x]) {
-                        grid_pos.x = new_x;
-                        grid_pos.y = new_y;
-                    } else if dx != 0
-                        && is_passable(world_grid.tiles[grid_pos.z][grid_pos.y][new_x])
-                    {
-                        grid_pos.x = new_x;
-                    } else if dy != 0
-                        && is_passable(world_grid.tiles[grid_pos.z][new_y][grid_pos.x])
-                    {
-                        grid_pos.y = new_y;
-                    }
-                } else {
-                    // Need to change z-level
-                    let new_z = (grid_pos.z as i32 + dz).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                    if is_passable(world_grid.tiles[new_z][grid_pos.y][grid_pos.x]) {
-                        grid_pos.z = new_z;
-                    }
+                // A heavy load slows the walk home
+                if inventory.weighs_down(tick.0, config.load_drag) {
+                    continue;
                 }
+                step_toward(
+                    &mut grid_pos,
+                    &mut route,
+                    (home_x, home_y, home_z),
+                    &world_grid,
+                );
             }
         }
     }
 }
 
-/// System that handles gardener ants processing leaves into mulch
+/// System that handles gardener ants chewing leaves into mulch at the nest,
+/// spreading it on garden tiles, and keeping those tiles clean
 fn ant_gardening(
-    mut query: Query<(&GridPosition, &mut Task), With<Ant>>,
+    mut query: Query<(&mut GridPosition, &mut Route, &mut Task, &mut Inventory), With<Ant>>,
     mut fungus_garden: ResMut<FungusGarden>,
+    mut world_grid: ResMut<WorldGrid>,
     nest_location: Res<NestLocation>,
+    air: Res<AirQuality>,
+    mut rng: ResMut<SimRng>,
+    config: Res<SimulationConfig>,
 ) {
-    for (grid_pos, mut task) in &mut query {
-        if let Task::Gardening = *task {
-            // Must be at the nest to garden
-            if grid_pos.x == nest_location.x
-                && grid_pos.y == nest_location.y
-                && grid_pos.z == nest_location.z
-            {
-                // Try to process a leaf into mulch
-                if fungus_garden.process_leaf() {
+    for (mut grid_pos, mut route, mut task, mut inventory) in &mut query {
+        match *task {
+            Task::Gardening => {
+                // Must be at the nest to pick up leaves
+                if grid_pos.x != nest_location.x
+                    || grid_pos.y != nest_location.y
+                    || grid_pos.z != nest_location.z
+                {
+                    // Not at nest, go idle (ant_behavior will redirect us)
+                    *task = Task::Idle;
+                    continue;
+                }
+
+                let nest = (nest_location.x, nest_location.y, nest_location.z);
+                let Some((site_x, site_y, site_z)) = fungus_garden.find_site(&world_grid, nest)
+                else {
+                    // Nowhere to plant - leaves wait until a chamber is dug
+                    *task = Task::Idle;
+                    continue;
+                };
+
+                if fungus_garden.take_leaf() {
+                    inventory.hold(Carrying::Mulch, 1);
+                    *task = Task::TendingGarden {
+                        target_x: site_x,
+                        target_y: site_y,
+                        target_z: site_z,
+                    };
+                } else {
+                    *task = Task::Idle;
+                }
+            }
+            Task::TendingGarden {
+                target_x,
+                target_y,
+                target_z,
+            } => {
+                if (grid_pos.x, grid_pos.y, grid_pos.z) != (target_x, target_y, target_z) {
+                    step_toward(
+                        &mut grid_pos,
+                        &mut route,
+                        (target_x, target_y, target_z),
+                        &world_grid,
+                    );
+                    continue;
+                }
+                if air.stalls(&mut *rng, target_x, target_y, target_z) {
+                    continue;
+                }
+
+                // Plant the chamber tile if this is a new garden
+                if world_grid.tiles[target_z][target_y][target_x] == TileKind::Chamber
+                    && fungus_garden.plant(&mut world_grid, target_x, target_y, target_z)
+                {
                     info!(
-                        "Gardener processed leaf into mulch. Garden: {} leaves, {} mulch, {} food",
-                        fungus_garden.leaves, fungus_garden.mulch, fungus_garden.food
+                        "Gardener planted fungus garden at ({}, {}, {}). {} garden tiles.",
+                        target_x,
+                        target_y,
+                        target_z,
+                        fungus_garden.plots.len()
                     );
                 }
 
-                // If no more leaves, go idle
-                if fungus_garden.leaves == 0 {
+                if fungus_garden.add_mulch(target_x, target_y, target_z) {
+                    inventory.drop_all();
+                    *task = Task::Idle;
+                    info!(
+                        "Gardener spread mulch. Garden: {} mulch on {} tiles, {} leaves waiting",
+                        fungus_garden.total_mulch(),
+                        fungus_garden.plots.len(),
+                        fungus_garden.leaves
+                    );
+                } else if let Some((x, y, z)) =
+                    fungus_garden.find_site(&world_grid, (target_x, target_y, target_z))
+                {
+                    // Tile filled up or vanished on the way - find another
+                    *task = Task::TendingGarden {
+                        target_x: x,
+                        target_y: y,
+                        target_z: z,
+                    };
+                } else {
+                    // Nowhere left to spread it; return the leaf to the pile
+                    fungus_garden.return_leaf();
+                    inventory.drop_all();
+                    *task = Task::Idle;
+                }
+            }
+            Task::Cleaning {
+                target_x,
+                target_y,
+                target_z,
+            } => {
+                if (grid_pos.x, grid_pos.y, grid_pos.z) != (target_x, target_y, target_z) {
+                    step_toward(
+                        &mut grid_pos,
+                        &mut route,
+                        (target_x, target_y, target_z),
+                        &world_grid,
+                    );
+                    continue;
+                }
+                if air.stalls(&mut *rng, target_x, target_y, target_z) {
+                    continue;
+                }
+
+                let remaining =
+                    fungus_garden.clean(target_x, target_y, target_z, config.clean_rate);
+                if remaining <= 0.0 {
+                    info!(
+                        "Gardener finished cleaning garden tile ({}, {}, {})",
+                        target_x, target_y, target_z
+                    );
                     *task = Task::Idle;
                 }
-                // Otherwise stay gardening
-            } else {
-                // Not at nest, go idle (ant_behavior will redirect us)
-                *task = Task::Idle;
             }
+            _ => {}
         }
     }
 }
 
-/// Hunger constant: how much hunger increases per tick
-const HUNGER_RATE: f32 = 0.15;
-/// Hunger threshold at which ants will seek food
-const HUNGER_THRESHOLD: f32 = 50.0;
-
 /// System that increases ant hunger over time
-fn ant_hunger(mut query: Query<(&mut Hunger, &mut Task, &Caste), With<Ant>>) {
-    for (mut hunger, mut task, caste) in &mut query {
+fn ant_hunger(
+    mut query: Query<(&mut Hunger, &mut Task, &Caste, Has<FoundingQueen>), With<Ant>>,
+    config: Res<SimulationConfig>,
+) {
+    for (mut hunger, mut task, caste, founding) in &mut query {
+        // A founding queen lives off her body reserves
+        if founding {
+            continue;
+        }
+
         // Queen gets hungry slower
         let rate = if *caste == Caste::Queen {
-            HUNGER_RATE * 0.5
+            config.hunger_per_tick * 0.5
         } else {
-            HUNGER_RATE
-        };
+            config.hunger_per_tick
+        } * config.hunger_rate;
 
         hunger.current += rate;
 
         // If very hungry and not already seeking food or doing critical task, go eat
-        if hunger.current >= HUNGER_THRESHOLD {
+        if hunger.current >= config.hunger_threshold {
             match *task {
-                Task::SeekingFood | Task::CarryingHome { .. } => {
-                    // Already heading home or seeking food
+                Task::SeekingFood
+                | Task::CarryingHome { .. }
+                | Task::TendingGarden { .. }
+                | Task::Cutting { .. } => {
+                    // Already heading home, seeking food, or finishing a delivery
+                    // or a cut
                 }
                 _ => {
                     // Drop everything and go eat
@@ -723,75 +1931,188 @@ fn ant_hunger(mut query: Query<(&mut Hunger, &mut Task, &Caste), With<Ant>>) {
     }
 }
 
-/// System that handles ants eating at the nest
+/// System that handles ants eating at the nest, and filling their crops to
+/// share while the stores can spare it
 fn ant_feeding(
-    mut query: Query<(&mut GridPosition, &mut Hunger, &mut Task), With<Ant>>,
-    mut fungus_garden: ResMut<FungusGarden>,
+    mut query: Query<
+        (
+            &mut GridPosition,
+            &mut Route,
+            &mut Hunger,
+            &mut Crop,
+            &mut Task,
+        ),
+        With<Ant>,
+    >,
+    mut food_stores: ResMut<FoodStores>,
     nest_location: Res<NestLocation>,
     world_grid: Res<WorldGrid>,
+    config: Res<SimulationConfig>,
 ) {
-    for (mut grid_pos, mut hunger, mut task) in &mut query {
+    for (mut grid_pos, mut route, mut hunger, mut crop, mut task) in &mut query {
         if let Task::SeekingFood = *task {
+            // A nestmate fed it on the way
+            if hunger.current < config.beg_threshold {
+                *task = Task::Idle;
+                continue;
+            }
+
             // Check if at nest
             if grid_pos.x == nest_location.x
                 && grid_pos.y == nest_location.y
                 && grid_pos.z == nest_location.z
             {
                 // Try to eat
-                if fungus_garden.consume_food() {
+                if food_stores.consume_food() {
                     hunger.current = 0.0;
                     info!(
-                        "Ant ate food. {} food remaining in garden.",
-                        fungus_garden.food
+                        "Ant ate food. {} food remaining in stores.",
+                        food_stores.total()
+                    );
+                    if crop.food < config.crop_capacity
+                        && food_stores.food > config.crop_reserve
+                        && food_stores.consume_food()
+                    {
+                        crop.food = config.crop_capacity;
+                    }
+                    *task = Task::Idle;
+                } else if food_stores.consume_seed() {
+                    // Seeds are a poor substitute for fungus
+                    hunger.current *= 1.0 - SEED_NUTRITION;
+                    info!(
+                        "Ant ate a seed. {} seeds remaining in stores.",
+                        food_stores.seeds
                     );
                     *task = Task::Idle;
                 }
                 // If no food, stay seeking (will starve if too long)
             } else {
                 // Move toward nest
-                let home_x = nest_location.x;
-                let home_y = nest_location.y;
-                let home_z = nest_location.z;
-
-                let dx = (home_x as i32 - grid_pos.x as i32).signum();
-                let dy = (home_y as i32 - grid_pos.y as i32).signum();
-                let dz = (home_z as i32 - grid_pos.z as i32).signum();
-
-                // Try to move on the same z-level first
-                if grid_pos.z == home_z || dz == 0 {
-                    let new_x = (grid_pos.x as i32 + dx).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                    let new_y = (grid_pos.y as i32 + dy).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-
-                    if is_passable(world_grid.tiles[grid_pos.z][new_y][new_x]) {
-                        grid_pos.x = new_x;
-                        grid_pos.y = new_y;
-                    } else if dx != 0
-                        && is_passable(world_grid.tiles[grid_pos.z][grid_pos.y][new_x])
-                    {
-                        grid_pos.x = new_x;
-                    } else if dy != 0
-                        && is_passable(world_grid.tiles[grid_pos.z][new_y][grid_pos.x])
-                    {
-                        grid_pos.y = new_y;
-                    }
-                } else {
-                    // Need to change z-level
-                    let new_z = (grid_pos.z as i32 + dz).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                    if is_passable(world_grid.tiles[new_z][grid_pos.y][grid_pos.x]) {
-                        grid_pos.z = new_z;
-                    }
-                }
+                let nest = (nest_location.x, nest_location.y, nest_location.z);
+                step_toward(&mut grid_pos, &mut route, nest, &world_grid);
             }
         }
     }
 }
 
+/// System that lets hungry ants beg food from well-fed nestmates next to
+/// them, who pass it over from their crops
+fn ant_trophallaxis(
+    mut query: Query<(Entity, &GridPosition, &mut Hunger, &mut Crop), With<Ant>>,
+    config: Res<SimulationConfig>,
+) {
+    let mut donors: Vec<(Entity, GridPosition, f32)> = query
+        .iter()
+        .filter(|(_, _, hunger, crop)| crop.food > 0.0 && hunger.current < config.beg_threshold)
+        .map(|(entity, pos, _, crop)| (entity, *pos, crop.food))
+        .collect();
+    if donors.is_empty() {
+        return;
+    }
+
+    let mut transfers = Vec::new();
+    for (entity, pos, hunger, _) in &query {
+        if hunger.current < config.beg_threshold {
+            continue;
+        }
+        let donor = donors.iter_mut().find(|(donor, donor_pos, food)| {
+            *donor != entity
+                && *food > 0.0
+                && donor_pos.z == pos.z
+                && donor_pos.x.abs_diff(pos.x) <= 1
+                && donor_pos.y.abs_diff(pos.y) <= 1
+        });
+        if let Some((donor, _, food)) = donor {
+            let amount = food.min(hunger.current);
+            *food -= amount;
+            transfers.push((*donor, entity, amount));
+        }
+    }
+
+    for (donor, recipient, amount) in transfers {
+        if let Ok((_, _, _, mut crop)) = query.get_mut(donor) {
+            crop.food -= amount;
+        }
+        if let Ok((_, _, mut hunger, _)) = query.get_mut(recipient) {
+            hunger.current -= amount;
+        }
+        info!("Ant shared {:.0} food with a hungry nestmate", amount);
+    }
+}
+
 /// System that kills ants that have starved
-fn ant_starvation(mut commands: Commands, query: Query<(Entity, &Hunger, &Caste), With<Ant>>) {
-    for (entity, hunger, caste) in &query {
+fn ant_starvation(
+    mut commands: Commands,
+    query: Query<(Entity, &GridPosition, &Hunger, &Caste), With<Ant>>,
+    mut fungus_garden: ResMut<FungusGarden>,
+    mut deaths: MessageWriter<AntDied>,
+    config: Res<SimulationConfig>,
+) {
+    for (entity, grid_pos, hunger, caste) in &query {
         if hunger.current >= hunger.max {
             info!("A {:?} ant has starved to death!", caste);
+            // The rotting corpse fouls any garden it died near
+            fungus_garden.contaminate_near(
+                (grid_pos.x, grid_pos.y, grid_pos.z),
+                config.corpse_radius,
+                config.corpse_contamination,
+            );
             commands.entity(entity).despawn();
+            deaths.write(AntDied {
+                caste: *caste,
+                cause: DeathCause::Starvation,
+            });
+        }
+    }
+}
+
+/// System that ages every ant by one tick
+fn ant_aging(mut query: Query<&mut Age, With<Ant>>) {
+    for mut age in &mut query {
+        age.0 = age.0.saturating_add(1);
+    }
+}
+
+/// Take one step toward a target along the shortest passable path, kept on
+/// the ant's route between ticks, falling back to a direct step if no path is
+/// found
+pub fn step_toward(
+    grid_pos: &mut GridPosition,
+    route: &mut Route,
+    target: (usize, usize, usize),
+    world_grid: &WorldGrid,
+) {
+    let from = (grid_pos.x, grid_pos.y, grid_pos.z);
+    if let Some((x, y, z)) = route.next_step(world_grid, from, target) {
+        grid_pos.x = x;
+        grid_pos.y = y;
+        grid_pos.z = z;
+        return;
+    }
+
+    let (target_x, target_y, target_z) = target;
+    let dx = (target_x as i32 - grid_pos.x as i32).signum();
+    let dy = (target_y as i32 - grid_pos.y as i32).signum();
+    let dz = (target_z as i32 - grid_pos.z as i32).signum();
+
+    // Try to move on the same z-level first
+    if dz == 0 {
+        let new_x = (grid_pos.x as i32 + dx).clamp(0, WORLD_SIZE as i32 - 1) as usize;
+        let new_y = (grid_pos.y as i32 + dy).clamp(0, WORLD_SIZE as i32 - 1) as usize;
+
+        if world_grid.tiles[grid_pos.z][new_y][new_x].is_passable() {
+            grid_pos.x = new_x;
+            grid_pos.y = new_y;
+        } else if dx != 0 && world_grid.tiles[grid_pos.z][grid_pos.y][new_x].is_passable() {
+            grid_pos.x = new_x;
+        } else if dy != 0 && world_grid.tiles[grid_pos.z][new_y][grid_pos.x].is_passable() {
+            grid_pos.y = new_y;
+        }
+    } else {
+        // Need to change z-level
+        let new_z = (grid_pos.z as i32 + dz).clamp(0, WORLD_SIZE as i32 - 1) as usize;
+        if pathfinding::can_step(world_grid, from, (grid_pos.x, grid_pos.y, new_z)) {
+            grid_pos.z = new_z;
         }
     }
 }
@@ -802,10 +2123,8 @@ fn try_pheromone_biased_move(
     grid_pos: &mut GridPosition,
     world_grid: &WorldGrid,
     pheromones: &mut PheromoneGrids,
+    rng: &mut impl rand::Rng,
 ) {
-    use rand::Rng;
-
-    let mut rng = rand::rng();
     let directions: [(i32, i32); 4] = [(0, 1), (0, -1), (1, 0), (-1, 0)];
 
     // Calculate weights for each direction based on pheromones
@@ -828,7 +2147,7 @@ fn try_pheromone_biased_move(
         let z = grid_pos.z;
 
         // Check passability
-        if !is_passable(world_grid.tiles[z][ny][nx]) {
+        if !world_grid.tiles[z][ny][nx].is_passable() {
             weights[i] = 0.0;
             continue;
         }
@@ -923,8 +2242,8 @@ fn find_pheromone_dig_target(
                 let y = ny as usize;
                 let z = nz as usize;
 
-                // Must be a dirt tile
-                if world_grid.tiles[z][y][x] != TileKind::Dirt {
+                // Must be a diggable tile
+                if !world_grid.tiles[z][y][x].is_diggable() {
                     continue;
                 }
 
@@ -946,19 +2265,12 @@ fn find_pheromone_dig_target(
     best_target
 }
 
-/// Check if a tile can be walked on
-fn is_passable(tile: TileKind) -> bool {
-    matches!(
-        tile,
-        TileKind::Surface | TileKind::Tunnel | TileKind::Chamber | TileKind::FungusGarden
-    )
-}
-
-/// Find a tree to forage based on Forage pheromone presence
+/// Find a plant to forage based on Forage pheromone presence
 fn find_forage_target(
     pos: &GridPosition,
     pheromones: &PheromoneGrids,
-    tree_query: &Query<(Entity, &Tree, &LeafSource)>,
+    plant_query: &Query<(Entity, &Plant, &LeafSource)>,
+    knowledge: &ColonyKnowledge,
 ) -> Option<Entity> {
     // Check if there's significant Forage pheromone nearby
     let search_radius: i32 = 5;
@@ -989,30 +2301,68 @@ fn find_forage_target(
         return None;
     }
 
-    // Find the nearest tree with leaves
-    find_nearest_tree(pos, tree_query)
+    // Find the most worthwhile plant with leaves
+    find_best_plant(pos, plant_query, knowledge)
 }
 
-/// Find the nearest tree that has leaves remaining
-fn find_nearest_tree(
+/// Find the known plant with leaves remaining that's worth the most per step
+/// of travel, so a nearby tuft of grass can beat a distant tree
+fn find_best_plant(
     pos: &GridPosition,
-    tree_query: &Query<(Entity, &Tree, &LeafSource)>,
+    plant_query: &Query<(Entity, &Plant, &LeafSource)>,
+    knowledge: &ColonyKnowledge,
 ) -> Option<Entity> {
-    let mut best_tree: Option<Entity> = None;
-    let mut best_distance = i32::MAX;
+    let mut best_plant: Option<Entity> = None;
+    let mut best_score = 0.0;
 
-    for (entity, tree, leaf_source) in tree_query.iter() {
-        // Skip trees with no leaves
-        if leaf_source.leaves_remaining == 0 {
+    for (entity, plant, leaf_source) in plant_query.iter() {
+        // Skip plants with no leaves, or that no scout has found yet
+        if leaf_source.leaves_remaining == 0 || !knowledge.knows(entity) {
             continue;
         }
 
-        let dist = (tree.x as i32 - pos.x as i32).abs() + (tree.y as i32 - pos.y as i32).abs();
-        if dist < best_distance {
-            best_distance = dist;
-            best_tree = Some(entity);
+        let dist = (plant.x as i32 - pos.x as i32).abs() + (plant.y as i32 - pos.y as i32).abs();
+        let score = leaf_source.leaf_value as f32 / (dist + 1) as f32;
+        if score > best_score {
+            best_score = score;
+            best_plant = Some(entity);
         }
     }
 
-    best_tree
+    best_plant
+}
+
+/// Find the nearest aphid colony with a load of honeydew ready
+fn find_honeydew(
+    pos: &GridPosition,
+    colony_query: &Query<(Entity, &AphidColony)>,
+    load: f32,
+) -> Option<Entity> {
+    colony_query
+        .iter()
+        .filter(|(_, colony)| colony.honeydew >= load)
+        .min_by_key(|(_, colony)| colony.x.abs_diff(pos.x) + colony.y.abs_diff(pos.y))
+        .map(|(entity, _)| entity)
+}
+
+/// Find the nearest wild mushroom patch
+fn find_nearest_mushroom(
+    pos: &GridPosition,
+    mushroom_query: &Query<(Entity, &WildMushroom)>,
+) -> Option<Entity> {
+    mushroom_query
+        .iter()
+        .min_by_key(|(_, mushroom)| mushroom.x.abs_diff(pos.x) + mushroom.y.abs_diff(pos.y))
+        .map(|(entity, _)| entity)
+}
+
+/// Find the nearest seed lying on the surface
+fn find_nearest_seed(
+    pos: &GridPosition,
+    seed_query: &Query<(Entity, &SeedItem)>,
+) -> Option<Entity> {
+    seed_query
+        .iter()
+        .min_by_key(|(_, seed)| seed.x.abs_diff(pos.x) + seed.y.abs_diff(pos.y))
+        .map(|(entity, _)| entity)
 }
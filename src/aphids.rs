@@ -0,0 +1,249 @@
+//! Aphid herding: a second food source alongside the fungus garden.
+//!
+//! Aphid colonies settle on tree canopies and grow toward what the tree can
+//! support, producing honeydew as they go. Foragers tend them and carry the
+//! honeydew home, where ants can drink it without any fungus processing.
+//! Ladybugs prey on the aphids unless tending ants chase them off.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ants::{Ant, GridPosition, Task};
+use crate::atlas::AtlasSprite;
+use crate::low_spec::cosmetic_frame;
+use crate::rng::SimRng;
+use crate::sprites;
+use crate::time_controls::SimulationSet;
+use crate::world::{CurrentZLevel, SURFACE_LEVEL, TILE_SIZE, Tree, tile_to_world};
+use crate::{ResetRun, despawn_all};
+
+pub struct AphidPlugin;
+
+impl Plugin for AphidPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_aphid_sprites.run_if(cosmetic_frame))
+            .add_systems(
+                FixedUpdate,
+                (
+                    aphid_colonization,
+                    aphid_growth,
+                    ladybug_arrival,
+                    ladybug_predation,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(
+                ResetRun,
+                (despawn_all::<AphidColony>, despawn_all::<Ladybug>),
+            );
+    }
+}
+
+/// Per-tick chance that aphids settle on a tree without a colony
+const COLONIZATION_CHANCE: f64 = 1.0 / 4000.0;
+/// Aphids in a newly settled colony
+const FOUNDING_APHIDS: f32 = 3.0;
+/// Most aphids a single tree can support
+const APHID_CAPACITY: f32 = 40.0;
+/// Per-tick logistic growth rate of an aphid colony
+const APHID_GROWTH_RATE: f32 = 0.0015;
+/// Honeydew produced per aphid per tick
+const HONEYDEW_PER_APHID: f32 = 0.0004;
+/// Most honeydew that pools on a colony before it's wasted
+const MAX_HONEYDEW: f32 = 10.0;
+/// Aphids a colony needs before it attracts ladybugs
+const LADYBUG_ATTRACTION: f32 = 15.0;
+/// Per-tick chance a ladybug arrives at an attractive colony
+const LADYBUG_ARRIVAL_CHANCE: f64 = 1.0 / 1500.0;
+/// Aphids a ladybug eats per tick
+const LADYBUG_APPETITE: f32 = 0.03;
+/// Ticks a ladybug feeds before flying off
+const LADYBUG_STAY_TICKS: u32 = 1500;
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// A colony of aphids living on a tree's canopy
+#[derive(Component)]
+pub struct AphidColony {
+    /// The tree the aphids live on
+    pub tree: Entity,
+    pub x: usize,
+    pub y: usize,
+    pub population: f32,
+    /// Honeydew waiting to be collected
+    pub honeydew: f32,
+}
+
+impl AphidColony {
+    /// Collect up to `amount` honeydew, returning how much was taken
+    pub fn collect(&mut self, amount: f32) -> f32 {
+        let taken = self.honeydew.min(amount);
+        self.honeydew -= taken;
+        taken
+    }
+}
+
+/// A ladybug preying on an aphid colony
+#[derive(Component)]
+pub struct Ladybug {
+    pub colony: Entity,
+    /// Ticks spent feeding so far
+    pub ticks: u32,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Aphids occasionally settle on trees that don't have a colony yet
+fn aphid_colonization(
+    mut commands: Commands,
+    tree_query: Query<(Entity, &Tree)>,
+    colony_query: Query<&AphidColony>,
+    mut rng: ResMut<SimRng>,
+) {
+    for (entity, tree) in &tree_query {
+        if colony_query.iter().any(|colony| colony.tree == entity) {
+            continue;
+        }
+        if !rng.random_bool(COLONIZATION_CHANCE) {
+            continue;
+        }
+
+        commands.spawn((
+            AphidColony {
+                tree: entity,
+                x: tree.x,
+                y: tree.y,
+                population: FOUNDING_APHIDS,
+                honeydew: 0.0,
+            },
+            Sprite {
+                color: sprites::objects::APHID,
+                custom_size: Some(Vec2::splat(sprites::objects::APHID_SIZE)),
+                ..default()
+            },
+            AtlasSprite(sprites::objects::APHID_INDEX),
+            Transform::from_translation(
+                (tile_to_world(tree.x, tree.y) + Vec2::splat(TILE_SIZE * 0.25)).extend(0.85),
+            ),
+        ));
+        info!("Aphids settled on the tree at ({}, {})", tree.x, tree.y);
+    }
+}
+
+/// Colonies grow toward the tree's capacity and produce honeydew; colonies
+/// whose tree has died, or that have been eaten out, disappear
+fn aphid_growth(
+    mut commands: Commands,
+    mut colony_query: Query<(Entity, &mut AphidColony)>,
+    tree_query: Query<(), With<Tree>>,
+) {
+    for (entity, mut colony) in &mut colony_query {
+        if !tree_query.contains(colony.tree) || colony.population < 0.5 {
+            commands.entity(entity).despawn();
+            info!("The aphid colony at ({}, {}) died out", colony.x, colony.y);
+            continue;
+        }
+
+        let growth =
+            APHID_GROWTH_RATE * colony.population * (1.0 - colony.population / APHID_CAPACITY);
+        colony.population = (colony.population + growth).max(0.0);
+        colony.honeydew =
+            (colony.honeydew + colony.population * HONEYDEW_PER_APHID).min(MAX_HONEYDEW);
+    }
+}
+
+/// Large colonies attract ladybugs
+fn ladybug_arrival(
+    mut commands: Commands,
+    colony_query: Query<(Entity, &AphidColony)>,
+    ladybug_query: Query<&Ladybug>,
+    mut rng: ResMut<SimRng>,
+) {
+    for (entity, colony) in &colony_query {
+        if colony.population < LADYBUG_ATTRACTION
+            || ladybug_query.iter().any(|ladybug| ladybug.colony == entity)
+            || !rng.random_bool(LADYBUG_ARRIVAL_CHANCE)
+        {
+            continue;
+        }
+
+        commands.spawn((
+            Ladybug {
+                colony: entity,
+                ticks: 0,
+            },
+            Sprite {
+                color: sprites::objects::LADYBUG,
+                custom_size: Some(Vec2::splat(sprites::objects::LADYBUG_SIZE)),
+                ..default()
+            },
+            AtlasSprite(sprites::objects::LADYBUG_INDEX),
+            Transform::from_translation(
+                (tile_to_world(colony.x, colony.y) + Vec2::new(-0.25, 0.25) * TILE_SIZE)
+                    .extend(0.86),
+            ),
+        ));
+        info!(
+            "A ladybug landed on the aphids at ({}, {})",
+            colony.x, colony.y
+        );
+    }
+}
+
+/// Ladybugs eat aphids until they're full or chased off by tending ants
+fn ladybug_predation(
+    mut commands: Commands,
+    mut ladybug_query: Query<(Entity, &mut Ladybug)>,
+    mut colony_query: Query<&mut AphidColony>,
+    ant_query: Query<(&GridPosition, &Task), With<Ant>>,
+) {
+    for (entity, mut ladybug) in &mut ladybug_query {
+        let Ok(mut colony) = colony_query.get_mut(ladybug.colony) else {
+            commands.entity(entity).despawn();
+            continue;
+        };
+
+        // An ant tending this colony right at the tree drives the ladybug away
+        let guarded = ant_query.iter().any(|(pos, task)| {
+            matches!(*task, Task::TendingAphids { colony: target } if target == ladybug.colony)
+                && pos.z == SURFACE_LEVEL
+                && pos.x.abs_diff(colony.x) <= 1
+                && pos.y.abs_diff(colony.y) <= 1
+        });
+        if guarded {
+            commands.entity(entity).despawn();
+            info!(
+                "Ants chased a ladybug off the aphids at ({}, {})",
+                colony.x, colony.y
+            );
+            continue;
+        }
+
+        colony.population = (colony.population - LADYBUG_APPETITE).max(0.0);
+        ladybug.ticks += 1;
+        if ladybug.ticks >= LADYBUG_STAY_TICKS {
+            commands.entity(entity).despawn();
+        }
+    }
+}
+
+/// Aphid colonies and the ladybugs after them, to show or hide
+type AphidSpriteQuery<'w, 's> =
+    Query<'w, 's, &'static mut Visibility, Or<(With<AphidColony>, With<Ladybug>)>>;
+
+/// Aphids and ladybugs live up in the canopy, so they're shown from the surface up
+fn update_aphid_sprites(current_z: Res<CurrentZLevel>, mut query: AphidSpriteQuery) {
+    let visibility = if current_z.0 >= SURFACE_LEVEL {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for mut sprite_visibility in &mut query {
+        *sprite_visibility = visibility;
+    }
+}
@@ -0,0 +1,128 @@
+//! Texture atlas sprites.
+//!
+//! Tiles, ants, brood and items carry an `AtlasSprite` naming their cell in
+//! `assets/sprites/atlas.png`, a grid of 16x16 cells laid out as listed in
+//! `sprites.rs`. Once the atlas has loaded, every `AtlasSprite` draws its cell
+//! instead of a plain square. The art is drawn in light tones and tinted by the
+//! sprite's color, so biome palettes, soil colors and callow ants still show.
+//! Without the file, or if it fails to load, sprites stay colored squares.
+
+use std::path::Path;
+
+use bevy::prelude::*;
+
+use crate::sprites;
+
+pub struct AtlasPlugin;
+
+impl Plugin for AtlasPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, load_atlas).add_systems(
+            Update,
+            (
+                watch_atlas,
+                apply_atlas_sprites.run_if(resource_exists::<SpriteAtlas>),
+            )
+                .chain(),
+        );
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// The sprite atlas, once requested
+#[derive(Resource)]
+pub struct SpriteAtlas {
+    pub image: Handle<Image>,
+    pub layout: Handle<TextureAtlasLayout>,
+    /// Whether the image has finished loading and sprites can use it
+    pub ready: bool,
+    /// Whether loading failed, leaving the colored squares in place
+    pub failed: bool,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// The atlas cell a sprite draws once the atlas is loaded
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub struct AtlasSprite(pub usize);
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start loading the atlas, if there is one
+fn load_atlas(
+    mut commands: Commands,
+    asset_server: Res<AssetServer>,
+    mut layouts: ResMut<Assets<TextureAtlasLayout>>,
+) {
+    // Native builds can check for the file first; the web has to ask for it
+    let on_disk = Path::new("assets").join(sprites::atlas::PATH);
+    if !cfg!(target_arch = "wasm32") && !on_disk.exists() {
+        info!(
+            "No sprite atlas at {}, using colored sprites",
+            on_disk.display()
+        );
+        return;
+    }
+
+    let layout = TextureAtlasLayout::from_grid(
+        UVec2::splat(sprites::atlas::CELL_SIZE),
+        sprites::atlas::COLUMNS,
+        sprites::atlas::ROWS,
+        None,
+        None,
+    );
+    commands.insert_resource(SpriteAtlas {
+        image: asset_server.load(sprites::atlas::PATH),
+        layout: layouts.add(layout),
+        ready: false,
+        failed: false,
+    });
+}
+
+/// Mark the atlas ready when it finishes loading, or give up on it if it fails
+fn watch_atlas(asset_server: Res<AssetServer>, atlas: Option<ResMut<SpriteAtlas>>) {
+    let Some(mut atlas) = atlas else {
+        return;
+    };
+    if atlas.ready || atlas.failed {
+        return;
+    }
+
+    if asset_server.is_loaded_with_dependencies(&atlas.image) {
+        atlas.ready = true;
+        info!("Loaded sprite atlas {}", sprites::atlas::PATH);
+    } else if asset_server.load_state(&atlas.image).is_failed() {
+        atlas.failed = true;
+        warn!(
+            "Couldn't load sprite atlas {}, using colored sprites",
+            sprites::atlas::PATH
+        );
+    }
+}
+
+/// Point sprites at their atlas cells, all of them when the atlas first
+/// becomes ready and then whichever change cell
+fn apply_atlas_sprites(atlas: Res<SpriteAtlas>, mut query: Query<(Ref<AtlasSprite>, &mut Sprite)>) {
+    if !atlas.ready {
+        return;
+    }
+    let everything = atlas.is_changed();
+
+    for (cell, mut sprite) in &mut query {
+        if !everything && !cell.is_changed() {
+            continue;
+        }
+        sprite.image = atlas.image.clone();
+        sprite.texture_atlas = Some(TextureAtlas {
+            layout: atlas.layout.clone(),
+            index: cell.0,
+        });
+    }
+}
@@ -0,0 +1,229 @@
+//! Headless task audit for balance tuning.
+//!
+//! `--audit <ticks>` runs the simulation without a window as fast as it can,
+//! then prints aggregate behavior metrics: how ant time splits across tasks
+//! and how long each stint lasts, forage round-trip times, and dig
+//! throughput. Useful for checking the effect of tweaking the probability
+//! constants in `ant_behavior`.
+
+use std::collections::HashMap;
+use std::time::Duration;
+
+use bevy::app::{PluginGroupBuilder, ScheduleRunnerPlugin};
+use bevy::prelude::*;
+use bevy::render::RenderPlugin;
+use bevy::render::settings::WgpuSettings;
+use bevy::time::TimeUpdateStrategy;
+use bevy::window::ExitCondition;
+use bevy::winit::WinitPlugin;
+
+use crate::ants::{Ant, Carrying, Caste, Inventory, Task};
+use crate::cli::CliArgs;
+use crate::rng::SimRng;
+use crate::summary::RunStats;
+use crate::time_controls::{BASE_TICKS_PER_SECOND, SimulationTick, TICKS_PER_DAY};
+use crate::world::{SURFACE_LEVEL, WorldGrid};
+
+pub struct AuditPlugin;
+
+impl Plugin for AuditPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_audit)
+            .add_systems(
+                FixedPostUpdate,
+                audit_tasks.run_if(resource_exists::<TaskAudit>),
+            )
+            .add_systems(Update, finish_audit.run_if(resource_exists::<TaskAudit>));
+    }
+}
+
+/// Default plugins with no window, renderer backend, or event loop
+pub fn headless_plugins() -> PluginGroupBuilder {
+    DefaultPlugins
+        .set(WindowPlugin {
+            primary_window: None,
+            exit_condition: ExitCondition::DontExit,
+            ..default()
+        })
+        .set(RenderPlugin {
+            render_creation: WgpuSettings {
+                backends: None,
+                ..default()
+            }
+            .into(),
+            ..default()
+        })
+        .disable::<WinitPlugin>()
+        .add(ScheduleRunnerPlugin::run_loop(Duration::ZERO))
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Metrics gathered during an audit run
+#[derive(Resource, Default)]
+pub struct TaskAudit {
+    /// Tick the audit stops at
+    pub target: u64,
+    /// Ant-ticks spent in each task
+    task_ticks: HashMap<&'static str, u64>,
+    /// Separate stints of each task (an ant switching into it)
+    task_stints: HashMap<&'static str, u64>,
+    /// The task each ant was on last tick
+    last_task: HashMap<Entity, &'static str>,
+    /// Forage trips in progress: start tick and whether a leaf was cut yet
+    trips: HashMap<Entity, (u64, bool)>,
+    /// Lengths of completed forage round trips
+    trip_ticks: Vec<u64>,
+    /// Excavated tiles on the first audited tick
+    excavated_at_start: Option<usize>,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Set up the audit when `--audit` was passed, stepping time one tick per frame
+fn start_audit(mut commands: Commands, cli: Option<Res<CliArgs>>) {
+    let Some(target) = cli.and_then(|cli| cli.audit) else {
+        return;
+    };
+
+    commands.insert_resource(TaskAudit {
+        target,
+        ..default()
+    });
+    commands.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / BASE_TICKS_PER_SECOND,
+    )));
+    info!("Auditing {} ticks", target);
+}
+
+/// Tally what every worker is doing this tick
+fn audit_tasks(
+    tick: Res<SimulationTick>,
+    world_grid: Res<WorldGrid>,
+    mut audit: ResMut<TaskAudit>,
+    query: Query<(Entity, &Caste, &Task, &Inventory), With<Ant>>,
+) {
+    if audit.excavated_at_start.is_none() {
+        audit.excavated_at_start = Some(excavated_tiles(&world_grid));
+    }
+
+    for (entity, caste, task, inventory) in &query {
+        if matches!(caste, Caste::Queen | Caste::Alate) {
+            continue;
+        }
+
+        let name = task.name();
+        *audit.task_ticks.entry(name).or_default() += 1;
+        if audit.last_task.insert(entity, name) != Some(name) {
+            *audit.task_stints.entry(name).or_default() += 1;
+        }
+
+        // A round trip runs from setting out to forage until the leaf is dropped off
+        match audit.trips.get(&entity).copied() {
+            None => {
+                if matches!(task, Task::Foraging { .. }) {
+                    audit.trips.insert(entity, (tick.0, false));
+                }
+            }
+            Some((start, _)) if matches!(inventory.load, Carrying::Leaf { .. }) => {
+                audit.trips.insert(entity, (start, true));
+            }
+            Some((start, true)) => {
+                audit.trip_ticks.push(tick.0 - start);
+                audit.trips.remove(&entity);
+            }
+            Some((_, false)) => {
+                if !matches!(task, Task::Foraging { .. } | Task::Cutting { .. }) {
+                    // Gave up before cutting anything
+                    audit.trips.remove(&entity);
+                }
+            }
+        }
+    }
+}
+
+/// Print the report and quit once the target tick is reached, or early if the
+/// colony collapses (which pauses the simulation)
+fn finish_audit(
+    tick: Res<SimulationTick>,
+    audit: Res<TaskAudit>,
+    stats: Res<RunStats>,
+    rng: Res<SimRng>,
+    world_grid: Res<WorldGrid>,
+    ant_query: Query<(), With<Ant>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    if tick.0 < audit.target && !stats.collapsed {
+        return;
+    }
+
+    let mut report = audit_report(&audit, tick.0, &world_grid, ant_query.iter().count());
+    report.push_str(&format!("Seed: {}\n", rng.seed));
+    if stats.collapsed {
+        report.push_str(&format!("Colony collapsed at tick {}\n", tick.0));
+    }
+    println!("{}", report);
+    exit.write(AppExit::Success);
+}
+
+/// Format the collected metrics
+fn audit_report(audit: &TaskAudit, ticks: u64, world_grid: &WorldGrid, ants: usize) -> String {
+    let days = ticks as f64 / TICKS_PER_DAY as f64;
+    let total: u64 = audit.task_ticks.values().sum();
+
+    let mut report = String::new();
+    report.push_str(&format!(
+        "=== ACRE task audit: {} ticks ({:.1} days) ===\n",
+        ticks, days
+    ));
+    report.push_str(&format!("Ants alive at end: {}\n\n", ants));
+
+    report.push_str("Task            share   avg ticks/stint\n");
+    let mut tasks: Vec<_> = audit.task_ticks.iter().collect();
+    tasks.sort_by(|a, b| b.1.cmp(a.1));
+    for (name, &task_ticks) in tasks {
+        let stints = audit.task_stints.get(name).copied().unwrap_or(1).max(1);
+        report.push_str(&format!(
+            "{:<15} {:>5.1}%  {:>8.1}\n",
+            name,
+            task_ticks as f64 * 100.0 / total.max(1) as f64,
+            task_ticks as f64 / stints as f64
+        ));
+    }
+
+    report.push('\n');
+    if audit.trip_ticks.is_empty() {
+        report.push_str("Forage round trips: none completed\n");
+    } else {
+        let trips = audit.trip_ticks.len();
+        let mean = audit.trip_ticks.iter().sum::<u64>() as f64 / trips as f64;
+        let longest = audit.trip_ticks.iter().max().copied().unwrap_or_default();
+        report.push_str(&format!(
+            "Forage round trips: {} (avg {:.1} ticks, longest {})\n",
+            trips, mean, longest
+        ));
+    }
+
+    let dug =
+        excavated_tiles(world_grid).saturating_sub(audit.excavated_at_start.unwrap_or_default());
+    report.push_str(&format!(
+        "Tiles excavated: {} ({:.1} per day)\n",
+        dug,
+        dug as f64 / days.max(f64::EPSILON)
+    ));
+
+    report
+}
+
+/// Underground tiles the colony has dug out
+fn excavated_tiles(world_grid: &WorldGrid) -> usize {
+    world_grid.tiles[..SURFACE_LEVEL]
+        .iter()
+        .flat_map(|level| level.iter().flatten())
+        .filter(|tile| tile.is_excavated())
+        .count()
+}
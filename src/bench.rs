@@ -0,0 +1,258 @@
+//! Headless benchmark for catching performance regressions.
+//!
+//! `--bench` runs the same world (seed 1, unless `--seed` says otherwise) for
+//! five in-game days without a window, one tick per frame as fast as the
+//! machine allows, then prints ticks per second, peak memory, and how long the
+//! busiest systems took per tick: ant behavior, pheromone decay, the overlays
+//! and the rest. Per-system timing comes from Bevy's system spans, so build
+//! with `--features bench` to get it; without the feature only the totals are
+//! reported.
+//!
+//! `--bench-large` runs the same benchmark starting with a colony of
+//! `LARGE_COLONY_WORKERS`, to show how the per-ant systems, pathfinding
+//! above all, hold up as the colony grows.
+
+use std::cmp::Reverse;
+use std::collections::HashMap;
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+
+use bevy::log::BoxedLayer;
+use bevy::log::tracing::Subscriber;
+use bevy::log::tracing::field::{Field, Visit};
+use bevy::log::tracing::span::{Attributes, Id};
+use bevy::log::tracing_subscriber::Layer;
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::registry::LookupSpan;
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use crate::cli::CliArgs;
+use crate::rng::SimRng;
+use crate::summary::RunStats;
+use crate::time_controls::{BASE_TICKS_PER_SECOND, SimulationTick, TICKS_PER_DAY};
+
+pub struct BenchPlugin;
+
+impl Plugin for BenchPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_bench)
+            .add_systems(Update, finish_bench.run_if(resource_exists::<BenchRun>));
+    }
+}
+
+/// Seed benchmarked unless `--seed` picks another, so runs compare like for like
+pub const BENCH_SEED: u64 = 1;
+/// Workers the colony starts with under `--bench-large`
+pub const LARGE_COLONY_WORKERS: usize = 300;
+/// Ticks benchmarked: five in-game days
+const BENCH_TICKS: u64 = 5 * TICKS_PER_DAY as u64;
+/// Systems listed in the report, slowest first
+const REPORTED_SYSTEMS: usize = 20;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// A benchmark in progress
+#[derive(Resource)]
+struct BenchRun {
+    /// When the first tick ran, once it has
+    started: Option<(Instant, u64)>,
+}
+
+/// Time spent in each system, by name, and how many times it ran
+#[derive(Resource, Clone, Default)]
+pub struct SystemTimings(Arc<Mutex<HashMap<String, (Duration, u64)>>>);
+
+// ============================================================================
+// System timing
+// ============================================================================
+
+/// Logging layer for `LogPlugin::custom_layer` that adds up how long each
+/// system span is entered for. Bevy only opens spans for systems when built
+/// with its `trace` feature, which `--features bench` turns on.
+pub fn system_timing_layer(app: &mut App) -> Option<BoxedLayer> {
+    let timings = SystemTimings::default();
+    app.insert_resource(timings.clone());
+    Some(Box::new(SystemTimingLayer(timings)))
+}
+
+struct SystemTimingLayer(SystemTimings);
+
+/// The system a span belongs to, and when it was last entered
+struct SystemSpan {
+    name: String,
+    entered: Option<Instant>,
+}
+
+/// Pulls the `name` field out of a system span
+struct NameVisitor(Option<String>);
+
+impl Visit for NameVisitor {
+    fn record_str(&mut self, field: &Field, value: &str) {
+        if field.name() == "name" {
+            self.0 = Some(value.to_string());
+        }
+    }
+
+    fn record_debug(&mut self, field: &Field, value: &dyn std::fmt::Debug) {
+        if field.name() == "name" {
+            self.0 = Some(format!("{:?}", value));
+        }
+    }
+}
+
+impl<S: Subscriber + for<'a> LookupSpan<'a>> Layer<S> for SystemTimingLayer {
+    fn on_new_span(&self, attrs: &Attributes<'_>, id: &Id, ctx: Context<'_, S>) {
+        if attrs.metadata().name() != "system" {
+            return;
+        }
+        let mut visitor = NameVisitor(None);
+        attrs.record(&mut visitor);
+        if let (Some(name), Some(span)) = (visitor.0, ctx.span(id)) {
+            span.extensions_mut().insert(SystemSpan {
+                name,
+                entered: None,
+            });
+        }
+    }
+
+    fn on_enter(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        if let Some(system) = span.extensions_mut().get_mut::<SystemSpan>() {
+            system.entered = Some(Instant::now());
+        }
+    }
+
+    fn on_exit(&self, id: &Id, ctx: Context<'_, S>) {
+        let Some(span) = ctx.span(id) else {
+            return;
+        };
+        let mut extensions = span.extensions_mut();
+        let Some(system) = extensions.get_mut::<SystemSpan>() else {
+            return;
+        };
+        let Some(entered) = system.entered.take() else {
+            return;
+        };
+        if let Ok(mut timings) = self.0.0.lock() {
+            let timing = timings.entry(system.name.clone()).or_default();
+            timing.0 += entered.elapsed();
+            timing.1 += 1;
+        }
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Set up the benchmark when `--bench` was passed, stepping time one tick per
+/// frame
+fn start_bench(mut commands: Commands, cli: Option<Res<CliArgs>>) {
+    if !cli.is_some_and(|cli| cli.bench) {
+        return;
+    }
+
+    commands.insert_resource(BenchRun { started: None });
+    commands.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+        1.0 / BASE_TICKS_PER_SECOND,
+    )));
+    if !cfg!(feature = "bench") {
+        warn!("Built without --features bench: per-system timings won't be reported");
+    }
+    info!("Benchmarking {} ticks", BENCH_TICKS);
+}
+
+/// Print the report and quit once the last tick has run, or early if the
+/// colony collapses
+fn finish_bench(
+    tick: Res<SimulationTick>,
+    mut run: ResMut<BenchRun>,
+    stats: Res<RunStats>,
+    rng: Res<SimRng>,
+    timings: Option<Res<SystemTimings>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    // Time from the first tick, leaving world generation out
+    let Some((started, first_tick)) = run.started else {
+        run.started = Some((Instant::now(), tick.0));
+        if let Some(timings) = &timings
+            && let Ok(mut timings) = timings.0.lock()
+        {
+            timings.clear();
+        }
+        return;
+    };
+    if tick.0 < BENCH_TICKS && !stats.collapsed {
+        return;
+    }
+
+    let ticks = tick.0 - first_tick;
+    let elapsed = started.elapsed();
+    let mut report = format!(
+        "=== ACRE bench: {} ticks in {:.2}s ===\n",
+        ticks,
+        elapsed.as_secs_f64()
+    );
+    report.push_str(&format!(
+        "Ticks/second: {:.1}\n",
+        ticks as f64 / elapsed.as_secs_f64().max(f64::EPSILON)
+    ));
+    report.push_str(&format!(
+        "Peak memory: {}\n",
+        peak_memory_kb().map_or(String::from("n/a"), |kb| format!(
+            "{:.1} MB",
+            kb as f64 / 1024.0
+        ))
+    ));
+
+    match timings.as_ref().and_then(|timings| timings.0.lock().ok()) {
+        Some(timings) if !timings.is_empty() => {
+            report.push_str(&system_report(&timings, ticks));
+        }
+        _ => report.push_str("\nPer-system timings need a build with --features bench\n"),
+    }
+
+    report.push_str(&format!("Seed: {}\n", rng.seed));
+    if stats.collapsed {
+        report.push_str(&format!("Colony collapsed at tick {}\n", tick.0));
+    }
+    println!("{}", report);
+    exit.write(AppExit::Success);
+}
+
+/// Format the slowest systems by total time
+fn system_report(timings: &HashMap<String, (Duration, u64)>, ticks: u64) -> String {
+    let mut systems: Vec<_> = timings.iter().collect();
+    systems.sort_by_key(|(_, (time, _))| Reverse(*time));
+    let total: Duration = systems.iter().map(|(_, (time, _))| *time).sum();
+
+    let mut report =
+        String::from("\nSystem                                    share   us/tick     runs\n");
+    for (name, (time, runs)) in systems.into_iter().take(REPORTED_SYSTEMS) {
+        // Module paths make names long without telling systems apart
+        let short = name.rsplit("::").next().unwrap_or(name);
+        report.push_str(&format!(
+            "{:<40} {:>5.1}%  {:>8.1}  {:>7}\n",
+            short,
+            time.as_secs_f64() / total.as_secs_f64().max(f64::EPSILON) * 100.0,
+            time.as_secs_f64() * 1_000_000.0 / ticks.max(1) as f64,
+            runs
+        ));
+    }
+    report
+}
+
+/// Peak resident memory of this process in kilobytes (Linux only)
+fn peak_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmHWM:"))
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|kb| kb.parse().ok())
+}
@@ -0,0 +1,98 @@
+//! Biomes chosen when the world is created.
+//!
+//! The biome sets how densely the surface is planted, how fast plants regrow
+//! their leaves, how moist the soil is, and the colors of the ground. It's a
+//! resource so world generation and later systems (weather, moisture) can
+//! consult it.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::sprites;
+
+/// The kind of land the colony is founded in
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Biome {
+    /// Dense trees, lush undergrowth, damp soil
+    #[default]
+    Forest,
+    /// Few trees but plenty of fast-growing grass
+    Grassland,
+    /// Sparse, slow-growing plants in dry sand
+    Desert,
+}
+
+impl Biome {
+    pub const ALL: [Biome; 3] = [Biome::Forest, Biome::Grassland, Biome::Desert];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Biome::Forest => "Forest",
+            Biome::Grassland => "Grassland",
+            Biome::Desert => "Desert",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|biome| biome.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The next biome in the list, wrapping around
+    pub fn next(&self) -> Self {
+        match self {
+            Biome::Forest => Biome::Grassland,
+            Biome::Grassland => Biome::Desert,
+            Biome::Desert => Biome::Forest,
+        }
+    }
+
+    /// Trees planted when the world is generated
+    pub fn tree_count(&self) -> usize {
+        match self {
+            Biome::Forest => 12,
+            Biome::Grassland => 5,
+            Biome::Desert => 2,
+        }
+    }
+
+    /// Bushes and grass tufts planted when the world is generated
+    pub fn undergrowth_counts(&self) -> (usize, usize) {
+        match self {
+            Biome::Forest => (12, 24),
+            Biome::Grassland => (6, 48),
+            Biome::Desert => (4, 8),
+        }
+    }
+
+    /// Leaf regrowth speed relative to a forest
+    pub fn regrowth_rate(&self) -> f32 {
+        match self {
+            Biome::Forest => 1.0,
+            Biome::Grassland => 1.25,
+            Biome::Desert => 0.5,
+        }
+    }
+
+    /// How much water the soil holds, scaling humidity underground
+    pub fn moisture(&self) -> f32 {
+        match self {
+            Biome::Forest => 1.0,
+            Biome::Grassland => 0.7,
+            Biome::Desert => 0.3,
+        }
+    }
+
+    /// Ground colors for this biome: (surface, dirt)
+    pub fn soil_colors(&self) -> (Color, Color) {
+        match self {
+            Biome::Forest => (sprites::tiles::SURFACE, sprites::tiles::DIRT),
+            Biome::Grassland => (
+                sprites::tiles::GRASSLAND_SURFACE,
+                sprites::tiles::GRASSLAND_DIRT,
+            ),
+            Biome::Desert => (sprites::tiles::DESERT_SURFACE, sprites::tiles::DESERT_DIRT),
+        }
+    }
+}
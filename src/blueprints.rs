@@ -0,0 +1,329 @@
+//! Nest blueprints: reusable dig layouts.
+//!
+//! Ctrl+B saves the colony's excavation and pending designations, relative to
+//! the nest entrance, as a blueprint under `blueprints/`. Shift+B cycles the
+//! saved blueprints and B stamps the selected one as designations with its
+//! entrance at the cursor. Tiles that are already dug or can't be dug are
+//! skipped, so stamping over an existing nest only adds what's missing.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::ants::NestLocation;
+use crate::cursor::CursorTile;
+use crate::designations::{DesignationKind, Designations};
+use crate::notifications::Notification;
+use crate::world::{SURFACE_LEVEL, TileKind, WORLD_SIZE, WorldGrid};
+
+pub struct BlueprintsPlugin;
+
+impl Plugin for BlueprintsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BlueprintLibrary>()
+            .add_systems(Startup, load_blueprints)
+            .add_systems(
+                Update,
+                (save_blueprint, cycle_blueprint, stamp_blueprint)
+                    .chain()
+                    .in_set(ActionSystems::Input),
+            );
+    }
+}
+
+/// Directory blueprints are saved to and loaded from
+const BLUEPRINTS_DIR: &str = "blueprints";
+
+// ============================================================================
+// Blueprints
+// ============================================================================
+
+/// A dig layout relative to the nest entrance
+#[derive(Debug, Clone)]
+pub struct Blueprint {
+    pub name: String,
+    /// Offsets from the entrance and what to dig there
+    pub cells: Vec<((i32, i32, i32), DesignationKind)>,
+}
+
+impl Blueprint {
+    /// Capture the nest below `entrance`: dug tunnels and chambers plus any
+    /// designations still waiting to be dug
+    pub fn capture(
+        name: String,
+        world_grid: &WorldGrid,
+        designations: &Designations,
+        entrance: (usize, usize, usize),
+    ) -> Self {
+        let offset = |x: usize, y: usize, z: usize| {
+            (
+                x as i32 - entrance.0 as i32,
+                y as i32 - entrance.1 as i32,
+                z as i32 - entrance.2 as i32,
+            )
+        };
+
+        let mut cells = Vec::new();
+        for z in 0..SURFACE_LEVEL {
+            for y in 0..WORLD_SIZE {
+                for x in 0..WORLD_SIZE {
+                    let kind = match world_grid.tiles[z][y][x] {
+                        TileKind::Tunnel => DesignationKind::Tunnel,
+                        TileKind::Ramp => DesignationKind::Ramp,
+                        TileKind::Chamber | TileKind::FungusGarden => DesignationKind::Chamber,
+                        _ => match designations.get(x, y, z) {
+                            Some(kind) => kind,
+                            None => continue,
+                        },
+                    };
+                    cells.push((offset(x, y, z), kind));
+                }
+            }
+        }
+
+        Self { name, cells }
+    }
+
+    /// Serialize as one `<kind> <dx> <dy> <dz>` line per cell
+    fn to_text(&self) -> String {
+        let mut contents = String::from("# acre nest blueprint\n");
+        for ((dx, dy, dz), kind) in &self.cells {
+            contents.push_str(&format!("{} {} {} {}\n", kind.name(), dx, dy, dz));
+        }
+        contents
+    }
+
+    /// Parse text written by `to_text`
+    fn from_text(name: String, contents: &str) -> Result<Self, String> {
+        let mut cells = Vec::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let fields: Vec<&str> = line.split_whitespace().collect();
+            let [kind, dx, dy, dz] = fields[..] else {
+                return Err(format!("malformed line '{}'", line));
+            };
+            let kind = DesignationKind::from_name(kind)
+                .ok_or_else(|| format!("unknown designation in '{}'", line))?;
+            let parse = |field: &str| -> Result<i32, String> {
+                field
+                    .parse()
+                    .map_err(|e| format!("bad offset in '{}': {}", line, e))
+            };
+            cells.push(((parse(dx)?, parse(dy)?, parse(dz)?), kind));
+        }
+
+        Ok(Self { name, cells })
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Saved blueprints and which one B will stamp
+#[derive(Resource, Default)]
+pub struct BlueprintLibrary {
+    pub blueprints: Vec<Blueprint>,
+    pub selected: usize,
+}
+
+impl BlueprintLibrary {
+    pub fn selected(&self) -> Option<&Blueprint> {
+        self.blueprints.get(self.selected)
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Load every blueprint in the blueprints directory
+fn load_blueprints(mut library: ResMut<BlueprintLibrary>) {
+    let Ok(entries) = fs::read_dir(BLUEPRINTS_DIR) else {
+        return;
+    };
+
+    let mut paths: Vec<PathBuf> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "txt"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        match read_blueprint(&path) {
+            Ok(blueprint) => library.blueprints.push(blueprint),
+            Err(e) => error!("Failed to load blueprint {}: {}", path.display(), e),
+        }
+    }
+
+    info!("Loaded {} nest blueprints", library.blueprints.len());
+}
+
+/// Read a blueprint file, naming it after the file
+fn read_blueprint(path: &Path) -> Result<Blueprint, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let name = path
+        .file_stem()
+        .map(|stem| stem.to_string_lossy().into_owned())
+        .unwrap_or_default();
+    Blueprint::from_text(name, &contents)
+}
+
+/// Save the current nest layout as a new blueprint with Ctrl+B
+fn save_blueprint(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    world_grid: Res<WorldGrid>,
+    designations: Res<Designations>,
+    nest_location: Res<NestLocation>,
+    mut library: ResMut<BlueprintLibrary>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyB)
+        || !keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight])
+    {
+        return;
+    }
+    if cfg!(target_arch = "wasm32") {
+        notifications.write(Notification::warning(
+            "Blueprints can't be saved in a web build",
+        ));
+        return;
+    }
+
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let name = format!("nest-{}", timestamp);
+    let entrance = (nest_location.x, nest_location.y, nest_location.z);
+    let blueprint = Blueprint::capture(name, &world_grid, &designations, entrance);
+
+    if blueprint.cells.is_empty() {
+        notifications.write(Notification::warning(
+            "Nothing to save: the nest has no tunnels or designations yet",
+        ));
+        return;
+    }
+
+    let path = PathBuf::from(BLUEPRINTS_DIR).join(format!("{}.txt", blueprint.name));
+    let result =
+        fs::create_dir_all(BLUEPRINTS_DIR).and_then(|()| fs::write(&path, blueprint.to_text()));
+    match result {
+        Ok(()) => {
+            info!(
+                "Saved blueprint with {} tiles to {}",
+                blueprint.cells.len(),
+                path.display()
+            );
+            notifications.write(Notification::info(format!(
+                "Saved blueprint {} ({} tiles)",
+                blueprint.name,
+                blueprint.cells.len()
+            )));
+            library.blueprints.push(blueprint);
+            library.selected = library.blueprints.len() - 1;
+        }
+        Err(e) => error!("Failed to save blueprint {}: {}", path.display(), e),
+    }
+}
+
+/// Select the next saved blueprint with Shift+B
+fn cycle_blueprint(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut library: ResMut<BlueprintLibrary>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyB)
+        || !keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight])
+        || library.blueprints.is_empty()
+    {
+        return;
+    }
+
+    library.selected = (library.selected + 1) % library.blueprints.len();
+    if let Some(blueprint) = library.selected() {
+        notifications.write(Notification::info(format!(
+            "Selected blueprint {} ({} tiles)",
+            blueprint.name,
+            blueprint.cells.len()
+        )));
+    }
+}
+
+/// Stamp the selected blueprint with B, its entrance at the cursor
+fn stamp_blueprint(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    cursor_tile: Res<CursorTile>,
+    world_grid: Res<WorldGrid>,
+    library: Res<BlueprintLibrary>,
+    mut actions: MessageWriter<PlayerAction>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyB)
+        || keyboard.any_pressed([
+            KeyCode::ControlLeft,
+            KeyCode::ControlRight,
+            KeyCode::ShiftLeft,
+            KeyCode::ShiftRight,
+        ])
+    {
+        return;
+    }
+
+    let Some(blueprint) = library.selected() else {
+        notifications.write(Notification::info(
+            "No blueprints saved yet (Ctrl+B saves the current nest)",
+        ));
+        return;
+    };
+    let Some((ax, ay, az)) = cursor_tile.0 else {
+        return;
+    };
+
+    // Resolve every cell first so a blueprint that doesn't fit is refused whole
+    let mut targets = Vec::with_capacity(blueprint.cells.len());
+    for &((dx, dy, dz), kind) in &blueprint.cells {
+        let x = ax as i32 + dx;
+        let y = ay as i32 + dy;
+        let z = az as i32 + dz;
+        let range = 0..WORLD_SIZE as i32;
+        if !(range.contains(&x) && range.contains(&y) && range.contains(&z)) {
+            notifications.write(Notification::warning(format!(
+                "Blueprint {} doesn't fit here",
+                blueprint.name
+            )));
+            return;
+        }
+        targets.push(((x as usize, y as usize, z as usize), kind));
+    }
+
+    // Collision check: only soil gets designated; existing excavation and
+    // anything else solid is left alone
+    let mut placed = 0;
+    let mut skipped = 0;
+    for ((x, y, z), kind) in targets {
+        if world_grid.tiles[z][y][x].is_diggable() {
+            actions.write(PlayerAction::Designate { kind, x, y, z });
+            placed += 1;
+        } else {
+            skipped += 1;
+        }
+    }
+
+    info!(
+        "Stamped blueprint {} at ({}, {}, {}): {} designated, {} skipped",
+        blueprint.name, ax, ay, az, placed, skipped
+    );
+    notifications.write(Notification::info(format!(
+        "Stamped {}: {} tiles designated, {} already dug or blocked",
+        blueprint.name, placed, skipped
+    )));
+}
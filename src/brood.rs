@@ -0,0 +1,463 @@
+//! Brood development: the queen lays eggs that grow into new workers.
+//!
+//! Eggs hatch into larvae, which must be fed from the colony's food stores to
+//! keep developing. Fed larvae pupate and emerge as callow adults. How much
+//! brood the queen keeps going is limited by the garden area that can feed it.
+//!
+//! Nurses do the brood care: they carry eggs down from the queen to the
+//! nursery chamber, feed the larvae, and keep every stage groomed and warm.
+//! Brood left without a nurse goes cold and develops slowly, and larvae
+//! nobody feeds starve.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::air::AirQuality;
+use crate::ants::{Ant, Caste, GridPosition, NestLocation, spawn_ant};
+use crate::atlas::AtlasSprite;
+use crate::config::SimulationConfig;
+use crate::founding::{FoundingQueen, FoundingStage};
+use crate::low_spec::cosmetic_frame;
+use crate::notifications::Notification;
+use crate::nuptial::NuptialFlight;
+use crate::pathfinding::Coord;
+use crate::relocation::NestMove;
+use crate::rng::SimRng;
+use crate::sprites;
+use crate::summary::RunStats;
+use crate::time_controls::SimulationSet;
+use crate::world::{
+    CurrentZLevel, FoodStores, FungusGarden, SURFACE_LEVEL, TILE_SIZE, TileKind, WORLD_SIZE,
+    WorldGrid,
+};
+use crate::{ResetRun, despawn_all, reset_resource};
+
+pub struct BroodPlugin;
+
+impl Plugin for BroodPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Nursery>()
+            .add_systems(Update, update_brood_sprites.run_if(cosmetic_frame))
+            .add_systems(
+                FixedUpdate,
+                (
+                    find_nursery.run_if(
+                        resource_changed::<WorldGrid>
+                            .or(resource_changed::<NestLocation>)
+                            .or(resource_changed::<NestMove>),
+                    ),
+                    queen_laying,
+                    brood_feeding,
+                    brood_development,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, (reset_resource::<Nursery>, despawn_all::<Brood>));
+    }
+}
+
+/// Colony members (adults plus brood) supported without any garden
+const BASE_POPULATION: usize = 8;
+/// Extra colony members each garden tile can support
+const POPULATION_PER_PLOT: usize = 3;
+/// Ticks a larva can go without food before it's considered unfed
+pub const LARVA_FEED_INTERVAL: u32 = 300;
+/// Ticks a larva can go without food before it dies
+const LARVA_STARVE_TICKS: u32 = 1200;
+/// Ticks brood stays warm after a nurse last tended it
+pub const TEND_INTERVAL: u32 = 400;
+/// Cold brood develops only one tick in this many
+const COLD_SLOWDOWN: u32 = 4;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Where nurses keep the brood: the chamber tile closest to the nest
+/// entrance, or nowhere until one has been dug
+#[derive(Resource, Default)]
+pub struct Nursery {
+    pub tile: Option<Coord>,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Development stage of a brood item
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum BroodStage {
+    Egg,
+    Larva,
+    Pupa,
+}
+
+impl BroodStage {
+    /// Ticks of development needed to finish this stage
+    pub fn duration(&self) -> u32 {
+        match self {
+            BroodStage::Egg => 600,
+            BroodStage::Larva => 1200,
+            BroodStage::Pupa => 900,
+        }
+    }
+
+    /// The stage that follows, or `None` once the adult emerges
+    pub fn next(&self) -> Option<Self> {
+        match self {
+            BroodStage::Egg => Some(BroodStage::Larva),
+            BroodStage::Larva => Some(BroodStage::Pupa),
+            BroodStage::Pupa => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            BroodStage::Egg => "Egg",
+            BroodStage::Larva => "Larva",
+            BroodStage::Pupa => "Pupa",
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            BroodStage::Egg => sprites::brood::EGG,
+            BroodStage::Larva => sprites::brood::LARVA,
+            BroodStage::Pupa => sprites::brood::PUPA,
+        }
+    }
+
+    /// Cell of the stage's sprite in the atlas
+    pub fn atlas_index(&self) -> usize {
+        match self {
+            BroodStage::Egg => sprites::brood::EGG_INDEX,
+            BroodStage::Larva => sprites::brood::LARVA_INDEX,
+            BroodStage::Pupa => sprites::brood::PUPA_INDEX,
+        }
+    }
+
+    pub fn size(&self) -> f32 {
+        match self {
+            BroodStage::Egg => sprites::brood::EGG_SIZE,
+            BroodStage::Larva => sprites::brood::LARVA_SIZE,
+            BroodStage::Pupa => sprites::brood::PUPA_SIZE,
+        }
+    }
+}
+
+/// A developing egg, larva, or pupa
+#[derive(Component, Clone)]
+pub struct Brood {
+    pub stage: BroodStage,
+    /// Ticks of development in the current stage
+    pub progress: u32,
+    /// The caste this brood will emerge as
+    pub caste: Caste,
+    /// Ticks since this larva was last fed
+    pub since_fed: u32,
+    /// Ticks since a nurse last tended it
+    pub since_tended: u32,
+}
+
+impl Brood {
+    /// Fraction of the current stage completed (0.0 to 1.0)
+    pub fn stage_progress(&self) -> f32 {
+        self.progress as f32 / self.stage.duration() as f32
+    }
+
+    /// Development ticks left until the adult emerges, assuming it stays fed
+    pub fn ticks_to_emerge(&self) -> u32 {
+        let mut remaining = self.stage.duration().saturating_sub(self.progress);
+        let mut stage = self.stage.next();
+        while let Some(next) = stage {
+            remaining += next.duration();
+            stage = next.next();
+        }
+        remaining
+    }
+
+    /// Larvae that haven't eaten recently stop developing
+    pub fn is_unfed(&self) -> bool {
+        self.stage == BroodStage::Larva && self.since_fed >= LARVA_FEED_INTERVAL
+    }
+
+    /// Larvae halfway to going unfed, which nurses feed before it's too late
+    pub fn is_hungry(&self) -> bool {
+        self.stage == BroodStage::Larva && self.since_fed >= LARVA_FEED_INTERVAL / 2
+    }
+
+    /// Brood no nurse has tended recently develops slowly
+    pub fn is_cold(&self) -> bool {
+        self.since_tended >= TEND_INTERVAL
+    }
+
+    /// Brood halfway to going cold, which nurses tend before it does
+    pub fn needs_tending(&self) -> bool {
+        self.since_tended >= TEND_INTERVAL / 2
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Colony members the current garden can support
+pub fn population_cap(fungus_garden: &FungusGarden) -> usize {
+    BASE_POPULATION + fungus_garden.plots.len() * POPULATION_PER_PLOT
+}
+
+/// Pick the caste of a new egg, weighted by the configured odds
+fn random_caste(config: &SimulationConfig, rng: &mut impl Rng) -> Caste {
+    let odds = [
+        (Caste::Forager, config.forager_odds),
+        (Caste::Gardener, config.gardener_odds),
+        (Caste::Nurse, config.nurse_odds),
+        (Caste::Soldier, config.soldier_odds),
+    ];
+    let total: u32 = odds.iter().map(|(_, weight)| weight).sum();
+    if total == 0 {
+        return Caste::Forager;
+    }
+
+    let mut roll = rng.random_range(0..total);
+    for (caste, weight) in odds {
+        if roll < weight {
+            return caste;
+        }
+        roll -= weight;
+    }
+    Caste::Soldier
+}
+
+/// Pick the chamber tile closest to the nest entrance as the nursery, or to
+/// the new site while the colony is moving
+fn find_nursery(
+    world_grid: Res<WorldGrid>,
+    nest_location: Res<NestLocation>,
+    nest_move: Res<NestMove>,
+    mut nursery: ResMut<Nursery>,
+) {
+    let (site_x, site_y, _) =
+        nest_move
+            .target
+            .unwrap_or((nest_location.x, nest_location.y, nest_location.z));
+    let mut best: Option<(usize, Coord)> = None;
+    for z in 0..SURFACE_LEVEL {
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                if world_grid.tiles[z][y][x] != TileKind::Chamber {
+                    continue;
+                }
+                let distance = x.abs_diff(site_x) + y.abs_diff(site_y) + (SURFACE_LEVEL - z);
+                if best.is_none_or(|(closest, _)| distance < closest) {
+                    best = Some((distance, (x, y, z)));
+                }
+            }
+        }
+    }
+
+    let tile = best.map(|(_, tile)| tile);
+    if tile != nursery.tile {
+        match tile {
+            Some((x, y, z)) => info!("Nursery set up at ({}, {}, {})", x, y, z),
+            None => info!("No chamber left for a nursery"),
+        }
+        nursery.tile = tile;
+    }
+}
+
+/// The queen lays an egg at regular intervals while the colony has room
+#[allow(clippy::too_many_arguments)]
+fn queen_laying(
+    mut commands: Commands,
+    mut timer: Local<u32>,
+    fungus_garden: Res<FungusGarden>,
+    flight: Res<NuptialFlight>,
+    ant_query: Query<(&GridPosition, &Caste, Option<&FoundingQueen>), With<Ant>>,
+    brood_query: Query<&Brood>,
+    config: Res<SimulationConfig>,
+    mut rng: ResMut<SimRng>,
+) {
+    *timer += 1;
+    if *timer < config.lay_interval {
+        return;
+    }
+    *timer = 0;
+
+    let Some((queen_pos, _, founding)) = ant_query
+        .iter()
+        .find(|(_, caste, _)| **caste == Caste::Queen)
+    else {
+        return;
+    };
+    // A founding queen waits until she's shut in her chamber
+    if founding.is_some_and(|founding| founding.stage == FoundingStage::Digging) {
+        return;
+    }
+
+    let population = ant_query.iter().count() + brood_query.iter().count();
+    if population >= population_cap(&fungus_garden) {
+        return;
+    }
+
+    // A mature colony raises alates for its nuptial flight first
+    let alates = ant_query
+        .iter()
+        .filter(|(_, caste, _)| **caste == Caste::Alate)
+        .count()
+        + brood_query
+            .iter()
+            .filter(|brood| brood.caste == Caste::Alate)
+            .count();
+    let caste = if flight.wants_alate(alates) {
+        Caste::Alate
+    } else {
+        random_caste(&config, &mut *rng)
+    };
+    spawn_brood(&mut commands, queen_pos, caste);
+    info!("Queen laid a {} egg", caste.name());
+}
+
+/// Spawn a new egg at the given position
+pub fn spawn_brood(commands: &mut Commands, pos: &GridPosition, caste: Caste) -> Entity {
+    let world_x = (pos.x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+    let world_y = (pos.y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+    let stage = BroodStage::Egg;
+
+    commands
+        .spawn((
+            Brood {
+                stage,
+                progress: 0,
+                caste,
+                since_fed: 0,
+                since_tended: 0,
+            },
+            GridPosition {
+                x: pos.x,
+                y: pos.y,
+                z: pos.z,
+            },
+            Sprite {
+                color: stage.color(),
+                custom_size: Some(Vec2::splat(stage.size())),
+                ..default()
+            },
+            Transform::from_xyz(world_x, world_y, 0.9),
+            AtlasSprite(stage.atlas_index()),
+        ))
+        .id()
+}
+
+/// Feed a larva from the food stores, returning false if they're empty
+pub fn feed_larva(brood: &mut Brood, food_stores: &mut FoodStores) -> bool {
+    if !(food_stores.consume_food() || food_stores.consume_seed()) {
+        return false;
+    }
+    brood.since_fed = 0;
+    true
+}
+
+/// Brood cools and larvae get hungry between nurse visits; larvae left
+/// unfed too long die
+fn brood_feeding(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Brood)>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let mut newly_unfed = 0;
+
+    for (entity, mut brood) in &mut query {
+        brood.since_tended = brood.since_tended.saturating_add(1);
+        if brood.stage != BroodStage::Larva {
+            continue;
+        }
+
+        brood.since_fed += 1;
+        if brood.since_fed == LARVA_FEED_INTERVAL {
+            newly_unfed += 1;
+        }
+        if brood.since_fed >= LARVA_STARVE_TICKS {
+            commands.entity(entity).despawn();
+            info!("A {} larva starved", brood.caste.name());
+        }
+    }
+
+    if newly_unfed > 0 {
+        notifications.write(Notification::warning(format!(
+            "{} larvae are going unfed - the colony needs food and nurses",
+            newly_unfed
+        )));
+    }
+}
+
+/// Advance brood through its stages and emerge finished pupae as adults
+fn brood_development(
+    mut commands: Commands,
+    mut query: Query<(Entity, &mut Brood, &GridPosition)>,
+    air: Res<AirQuality>,
+    mut rng: ResMut<SimRng>,
+    mut stats: ResMut<RunStats>,
+) {
+    for (entity, mut brood, pos) in &mut query {
+        if brood.is_unfed() {
+            continue;
+        }
+        // Cold brood develops slowly
+        if brood.is_cold() && !rng.random_ratio(1, COLD_SLOWDOWN) {
+            continue;
+        }
+        // Brood develops slowly in stale air
+        if air.stalls(&mut *rng, pos.x, pos.y, pos.z) {
+            continue;
+        }
+
+        brood.progress += 1;
+        if brood.progress < brood.stage.duration() {
+            continue;
+        }
+
+        match brood.stage.next() {
+            Some(next) => {
+                brood.stage = next;
+                brood.progress = 0;
+                brood.since_fed = 0;
+            }
+            None => {
+                commands.entity(entity).despawn();
+                spawn_ant(&mut commands, pos.x, pos.y, pos.z, brood.caste, 0);
+                stats.raised += 1;
+                info!("A new {} emerged", brood.caste.name());
+            }
+        }
+    }
+}
+
+/// Update brood sprite position, stage look, and visibility on the current z-level
+fn update_brood_sprites(
+    current_z: Res<CurrentZLevel>,
+    mut query: Query<(
+        &Brood,
+        &GridPosition,
+        &mut Sprite,
+        &mut AtlasSprite,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    for (brood, grid_pos, mut sprite, mut cell, mut transform, mut visibility) in &mut query {
+        transform.translation.x = (grid_pos.x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+        transform.translation.y = (grid_pos.y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+
+        *visibility = if grid_pos.z == current_z.0 {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+
+        sprite.color = brood.stage.color();
+        sprite.custom_size = Some(Vec2::splat(brood.stage.size()));
+        cell.set_if_neq(AtlasSprite(brood.stage.atlas_index()));
+    }
+}
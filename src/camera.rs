@@ -1,33 +1,121 @@
 use bevy::prelude::*;
+use bevy::transform::TransformSystems;
 
-use crate::world::{CurrentZLevel, SURFACE_LEVEL, WORLD_SIZE};
+use crate::ants::NestLocation;
+use crate::notifications::Notification;
+use crate::world::{CurrentZLevel, SURFACE_LEVEL, TILE_SIZE, WORLD_SIZE};
+use crate::{ResetRun, in_game, reset_resource};
 
 pub struct CameraPlugin;
 
 impl Plugin for CameraPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, spawn_camera)
-            .add_systems(Update, camera_pan)
+        app.init_resource::<EdgeScroll>()
+            .init_resource::<CameraBookmarks>()
+            .add_systems(Startup, spawn_camera)
+            .add_systems(Update, (toggle_edge_scroll, camera_pan).chain())
             .add_systems(Update, camera_zoom)
-            .add_systems(Update, camera_z_level);
+            .add_systems(Update, camera_z_level)
+            // Digits type a seed on the setup screen
+            .add_systems(Update, (jump_to_nest, camera_bookmarks).run_if(in_game))
+            // After every system that moves or zooms the camera
+            .add_systems(PostUpdate, clamp_camera.before(TransformSystems::Propagate))
+            .add_systems(ResetRun, reset_resource::<CameraBookmarks>);
     }
 }
 
 const PAN_SPEED: f32 = 500.0;
 const ZOOM_SPEED: f32 = 0.1;
-const MIN_SCALE: f32 = 0.5;
-const MAX_SCALE: f32 = 5.0;
+/// Zoom per second with a gamepad trigger fully pulled
+const TRIGGER_ZOOM_SPEED: f32 = 1.5;
+/// Stick deflection below which a gamepad stick is ignored
+pub const STICK_DEADZONE: f32 = 0.2;
+pub const MIN_SCALE: f32 = 0.5;
+pub const MAX_SCALE: f32 = 5.0;
+/// Pixels from the window edge where the resting cursor scrolls the view
+const EDGE_MARGIN: f32 = 16.0;
+/// World units the view may scroll past the world's edge, leaving room for the
+/// UI panels along the top and bottom of the screen
+const BOUNDS_MARGIN: f32 = 4.0 * TILE_SIZE;
+/// Edge scroll speeds Shift+E steps through, as fractions of the key pan speed
+const EDGE_SPEEDS: [f32; 3] = [0.5, 1.0, 2.0];
+/// Keys for the camera bookmark slots, Ctrl to save and bare to recall
+const BOOKMARK_KEYS: [KeyCode; 4] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+];
 
 #[derive(Component)]
-struct MainCamera;
+pub struct MainCamera;
+
+/// Scrolling the view by resting the mouse at the window edge (E toggles,
+/// Shift+E changes speed)
+#[derive(Resource)]
+pub struct EdgeScroll {
+    pub enabled: bool,
+    /// Pan speed as a fraction of the arrow keys'
+    pub speed: f32,
+}
+
+impl Default for EdgeScroll {
+    fn default() -> Self {
+        Self {
+            enabled: false,
+            speed: 1.0,
+        }
+    }
+}
+
+/// A saved view: where the camera was, how far it was zoomed, and which
+/// z-level was showing
+#[derive(Debug, Clone, Copy)]
+pub struct Bookmark {
+    pub position: Vec2,
+    pub scale: f32,
+    pub z: usize,
+}
+
+/// Views saved with Ctrl+1..4 and recalled with 1..4
+#[derive(Resource, Default)]
+pub struct CameraBookmarks(pub [Option<Bookmark>; BOOKMARK_KEYS.len()]);
 
 fn spawn_camera(mut commands: Commands) {
     commands.spawn((Camera2d, MainCamera));
 }
 
+/// Toggle edge scrolling with E, or step its speed with Shift+E
+fn toggle_edge_scroll(keyboard: Res<ButtonInput<KeyCode>>, mut edge: ResMut<EdgeScroll>) {
+    if !keyboard.just_pressed(KeyCode::KeyE) {
+        return;
+    }
+
+    if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        let next = EDGE_SPEEDS
+            .iter()
+            .position(|&speed| speed > edge.speed)
+            .unwrap_or(0);
+        edge.speed = EDGE_SPEEDS[next];
+        edge.enabled = true;
+        info!("Edge scrolling at {}x", edge.speed);
+    } else {
+        edge.enabled = !edge.enabled;
+        info!(
+            "Edge scrolling {}",
+            if edge.enabled { "enabled" } else { "disabled" }
+        );
+    }
+}
+
+/// Pan with the arrow keys, a gamepad's right stick, or the cursor resting at
+/// the window edge
 fn camera_pan(
     time: Res<Time>,
     keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    windows: Query<&Window>,
+    edge: Res<EdgeScroll>,
     mut query: Query<(&mut Transform, &Projection), With<MainCamera>>,
 ) {
     let Ok((mut transform, projection)) = query.single_mut() else {
@@ -56,43 +144,165 @@ fn camera_pan(
 
     if direction != Vec2::ZERO {
         direction = direction.normalize();
+    }
+    // The stick pans slower the less it's pushed
+    for gamepad in &gamepads {
+        let stick = gamepad.right_stick();
+        if stick.length() > STICK_DEADZONE {
+            direction = (direction + stick).clamp_length_max(1.0);
+        }
+    }
+
+    if edge.enabled
+        && let Ok(window) = windows.single()
+        && let Some(cursor) = window.cursor_position()
+    {
+        // Screen y runs down, world y runs up
+        let mut toward = Vec2::ZERO;
+        if cursor.x < EDGE_MARGIN {
+            toward.x -= 1.0;
+        } else if cursor.x > window.width() - EDGE_MARGIN {
+            toward.x += 1.0;
+        }
+        if cursor.y < EDGE_MARGIN {
+            toward.y += 1.0;
+        } else if cursor.y > window.height() - EDGE_MARGIN {
+            toward.y -= 1.0;
+        }
+        if toward != Vec2::ZERO {
+            direction =
+                (direction + toward.normalize() * edge.speed).clamp_length_max(edge.speed.max(1.0));
+        }
+    }
+
+    if direction != Vec2::ZERO {
         let movement = direction * PAN_SPEED * scale * time.delta_secs();
         transform.translation.x += movement.x;
         transform.translation.y += movement.y;
     }
 }
 
+/// Zoom with the mouse wheel, toward whatever is under the cursor, or a
+/// gamepad's right trigger (in) and left trigger (out), toward the middle of
+/// the screen
 fn camera_zoom(
+    time: Res<Time>,
     mut scroll_events: MessageReader<bevy::input::mouse::MouseWheel>,
-    mut query: Query<&mut Projection, With<MainCamera>>,
+    gamepads: Query<&Gamepad>,
+    windows: Query<&Window>,
+    mut query: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
 ) {
-    let Ok(mut projection) = query.single_mut() else {
+    let Ok((mut transform, mut projection)) = query.single_mut() else {
         return;
     };
+    let Projection::Orthographic(ref mut ortho) = *projection else {
+        return;
+    };
+
+    let wheel_delta: f32 = scroll_events
+        .read()
+        .map(|event| -event.y * ZOOM_SPEED)
+        .sum();
+    if wheel_delta != 0.0 {
+        let old_scale = ortho.scale;
+        ortho.scale = (ortho.scale + wheel_delta).clamp(MIN_SCALE, MAX_SCALE);
 
-    for event in scroll_events.read() {
-        if let Projection::Orthographic(ref mut ortho) = *projection {
-            let zoom_delta = -event.y * ZOOM_SPEED;
-            ortho.scale = (ortho.scale + zoom_delta).clamp(MIN_SCALE, MAX_SCALE);
+        // Move the camera so the world point under the cursor stays put
+        if let Ok(window) = windows.single()
+            && let Some(cursor) = window.cursor_position()
+        {
+            // Screen y runs down, world y runs up
+            let offset = cursor - window.size() / 2.0;
+            let shift = offset * (old_scale - ortho.scale);
+            transform.translation.x += shift.x;
+            transform.translation.y -= shift.y;
         }
     }
+
+    let mut trigger_delta = 0.0;
+    for gamepad in &gamepads {
+        let zoom_in = gamepad.get(GamepadButton::RightTrigger2).unwrap_or(0.0);
+        let zoom_out = gamepad.get(GamepadButton::LeftTrigger2).unwrap_or(0.0);
+        trigger_delta += (zoom_out - zoom_in) * TRIGGER_ZOOM_SPEED * time.delta_secs();
+    }
+    if trigger_delta != 0.0 {
+        ortho.scale = (ortho.scale + trigger_delta).clamp(MIN_SCALE, MAX_SCALE);
+    }
 }
 
-fn camera_z_level(keyboard: Res<ButtonInput<KeyCode>>, mut current_z: ResMut<CurrentZLevel>) {
-    let go_up =
-        keyboard.just_pressed(KeyCode::BracketRight) || keyboard.just_pressed(KeyCode::Period);
-    let go_down =
-        keyboard.just_pressed(KeyCode::BracketLeft) || keyboard.just_pressed(KeyCode::Comma);
+/// Keep the view over the world, so it can't drift off into empty space. A view
+/// wider than the world (zoomed far out) stays centered on it instead.
+fn clamp_camera(
+    windows: Query<&Window>,
+    mut query: Query<(&mut Transform, &Projection), With<MainCamera>>,
+) {
+    let Ok(window) = windows.single() else {
+        return;
+    };
+    let Ok((mut transform, projection)) = query.single_mut() else {
+        return;
+    };
+    let Projection::Orthographic(ortho) = projection else {
+        return;
+    };
 
-    if go_up && current_z.0 < WORLD_SIZE - 1 {
-        current_z.0 += 1;
-        info!("Z-level: {} {}", current_z.0, z_level_label(current_z.0));
+    // Tile sprites are centered on their positions, so the world runs half a
+    // tile further on the low side than the high side
+    let half_world = WORLD_SIZE as f32 * TILE_SIZE / 2.0 + BOUNDS_MARGIN;
+    let center = -TILE_SIZE / 2.0;
+    let half_view = window.size() / 2.0 * ortho.scale;
+
+    let clamp_axis = |position: f32, half_view: f32| {
+        let room = half_world - half_view;
+        if room <= 0.0 {
+            center
+        } else {
+            position.clamp(center - room, center + room)
+        }
+    };
+    let x = clamp_axis(transform.translation.x, half_view.x);
+    let y = clamp_axis(transform.translation.y, half_view.y);
+    // Only write when needed so change detection stays quiet
+    if x != transform.translation.x || y != transform.translation.y {
+        transform.translation.x = x;
+        transform.translation.y = y;
     }
+}
+
+/// Step between z-levels with [ and ] (or , and .), or a gamepad's bumpers
+fn camera_z_level(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut current_z: ResMut<CurrentZLevel>,
+) {
+    let bumper = |button| gamepads.iter().any(|gamepad| gamepad.just_pressed(button));
+    // Shift+. steps a paused simulation instead
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    let go_up = keyboard.just_pressed(KeyCode::BracketRight)
+        || (keyboard.just_pressed(KeyCode::Period) && !shift)
+        || bumper(GamepadButton::RightTrigger);
+    let go_down = keyboard.just_pressed(KeyCode::BracketLeft)
+        || keyboard.just_pressed(KeyCode::Comma)
+        || bumper(GamepadButton::LeftTrigger);
 
-    if go_down && current_z.0 > 0 {
+    if go_up {
+        step_z_level(&mut current_z, true);
+    }
+    if go_down {
+        step_z_level(&mut current_z, false);
+    }
+}
+
+/// Go up or down a z-level, stopping at the top and bottom of the world
+pub fn step_z_level(current_z: &mut CurrentZLevel, up: bool) {
+    if up && current_z.0 < WORLD_SIZE - 1 {
+        current_z.0 += 1;
+    } else if !up && current_z.0 > 0 {
         current_z.0 -= 1;
-        info!("Z-level: {} {}", current_z.0, z_level_label(current_z.0));
+    } else {
+        return;
     }
+    info!("Z-level: {} {}", current_z.0, z_level_label(current_z.0));
 }
 
 fn z_level_label(z: usize) -> &'static str {
@@ -104,3 +314,89 @@ fn z_level_label(z: usize) -> &'static str {
         "(underground)"
     }
 }
+
+/// Center the camera on a tile and switch to its z-level
+pub fn focus_tile(
+    transform: &mut Transform,
+    current_z: &mut CurrentZLevel,
+    (x, y, z): (usize, usize, usize),
+) {
+    transform.translation.x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+    transform.translation.y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+    current_z.0 = z;
+}
+
+/// Snap the camera and z-level to the nest with Home
+fn jump_to_nest(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    nest_location: Res<NestLocation>,
+    mut current_z: ResMut<CurrentZLevel>,
+    mut query: Query<&mut Transform, With<MainCamera>>,
+) {
+    if !keyboard.just_pressed(KeyCode::Home) {
+        return;
+    }
+    let Ok(mut transform) = query.single_mut() else {
+        return;
+    };
+
+    let nest = (nest_location.x, nest_location.y, nest_location.z);
+    focus_tile(&mut transform, &mut current_z, nest);
+    info!(
+        "Camera to nest at ({}, {}, {})",
+        nest_location.x, nest_location.y, nest_location.z
+    );
+}
+
+/// Save the view to a bookmark with Ctrl+1..4 and go back to it with 1..4
+fn camera_bookmarks(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut bookmarks: ResMut<CameraBookmarks>,
+    mut current_z: ResMut<CurrentZLevel>,
+    mut query: Query<(&mut Transform, &mut Projection), With<MainCamera>>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    // Shift+digits pick the speed
+    if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+    let Some(slot) = BOOKMARK_KEYS
+        .iter()
+        .position(|key| keyboard.just_pressed(*key))
+    else {
+        return;
+    };
+    let Ok((mut transform, mut projection)) = query.single_mut() else {
+        return;
+    };
+    let Projection::Orthographic(ref mut ortho) = *projection else {
+        return;
+    };
+
+    if keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        bookmarks.0[slot] = Some(Bookmark {
+            position: transform.translation.truncate(),
+            scale: ortho.scale,
+            z: current_z.0,
+        });
+        notifications.write(Notification::info(format!(
+            "Saved camera bookmark {}",
+            slot + 1
+        )));
+        return;
+    }
+
+    let Some(bookmark) = bookmarks.0[slot] else {
+        notifications.write(Notification::info(format!(
+            "No camera bookmark {} yet (Ctrl+{} saves one)",
+            slot + 1,
+            slot + 1
+        )));
+        return;
+    };
+    transform.translation.x = bookmark.position.x;
+    transform.translation.y = bookmark.position.y;
+    ortho.scale = bookmark.scale;
+    current_z.0 = bookmark.z;
+    info!("Camera to bookmark {}", slot + 1);
+}
@@ -0,0 +1,320 @@
+//! Tunnel structural integrity.
+//!
+//! Every open tile underground is held up by the solid tiles around it. The
+//! support pass measures how far each one is from the nearest wall or pillar
+//! on its level and compares that span with what the soil overhead can
+//! bridge: clay roofs hold wide rooms, sand barely covers a corridor, and rock
+//! never gives way. Tiles spanning too far are at risk and every so often cave
+//! in, filling back up with the soil from above and crushing anything inside.
+//! I shows the at-risk tiles as an overlay. Leaving a pillar of dirt in the
+//! middle of a big room is enough to shore it up.
+
+use std::collections::VecDeque;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ants::{Ant, AntDied, Caste, DeathCause, GridPosition};
+use crate::brood::Brood;
+use crate::low_spec::cosmetic_frame;
+use crate::moisture::SoilMoisture;
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::sprites;
+use crate::time_controls::SimulationSet;
+use crate::world::{
+    CurrentZLevel, SURFACE_LEVEL, TILE_SIZE, TileKind, WORLD_SIZE, WorldGrid, filled_grid,
+};
+use crate::{ResetRun, reset_resource};
+
+pub struct CaveInPlugin;
+
+impl Plugin for CaveInPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Supports>()
+            .init_resource::<SupportOverlaySettings>()
+            .add_systems(Startup, spawn_support_overlay)
+            .add_systems(Update, update_support_overlay.run_if(cosmetic_frame))
+            .add_systems(
+                FixedUpdate,
+                (
+                    calculate_supports
+                        .run_if(resource_changed::<WorldGrid>.or(resource_changed::<SoilMoisture>)),
+                    cave_ins,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, reset_resource::<Supports>);
+    }
+}
+
+/// Span every roof can bridge, however weak its soil
+const BASE_SPAN: f32 = 1.0;
+/// Extra span a fully stable roof can bridge
+const STABLE_SPAN: f32 = 3.0;
+/// Per-tick chance that a fully overstrained tile caves in
+const CAVE_IN_CHANCE: f64 = 0.0005;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// How overstrained each open tile's roof is, recalculated whenever the world
+/// grid changes
+#[derive(Resource)]
+pub struct Supports {
+    /// Span to the nearest support divided by the span the roof can bridge;
+    /// above 1.0 the tile is at risk
+    pub strain: Box<[[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+    /// Tiles that may cave in, in grid order
+    pub at_risk: Vec<(usize, usize, usize)>,
+}
+
+impl Default for Supports {
+    fn default() -> Self {
+        Self {
+            strain: filled_grid(0.0),
+            at_risk: Vec::new(),
+        }
+    }
+}
+
+/// Whether the at-risk overlay is drawn
+#[derive(Resource, Default)]
+pub struct SupportOverlaySettings {
+    pub visible: bool,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for at-risk overlay sprites
+#[derive(Component)]
+pub struct SupportOverlay {
+    pub x: usize,
+    pub y: usize,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Tiles that need a roof held over them
+fn is_open(tile: TileKind) -> bool {
+    tile.is_excavated()
+}
+
+/// Span the roof over an open tile can bridge, or `None` if it can't fall:
+/// rock never gives way, and a tile under another open tile has no roof
+fn bridgeable_span(
+    world_grid: &WorldGrid,
+    moisture: &SoilMoisture,
+    x: usize,
+    y: usize,
+    z: usize,
+) -> Option<f32> {
+    // The topmost layer is roofed by the surface crust, made of its own soil
+    let roof_z = if z + 1 < SURFACE_LEVEL { z + 1 } else { z };
+    let stability = match world_grid.tiles[roof_z][y][x] {
+        TileKind::Rock => return None,
+        // Roots bind the soil around them
+        TileKind::Root => 1.0,
+        tile if roof_z != z && is_open(tile) => return None,
+        _ => moisture.stability(world_grid.soil[roof_z][y][x], x, y, roof_z),
+    };
+    Some(BASE_SPAN + STABLE_SPAN * stability)
+}
+
+/// Measure every open tile's distance to the nearest solid tile on its level
+/// and how that compares with what its roof can bridge
+fn calculate_supports(
+    world_grid: Res<WorldGrid>,
+    moisture: Res<SoilMoisture>,
+    mut supports: ResMut<Supports>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let was_at_risk = !supports.at_risk.is_empty();
+    supports.at_risk.clear();
+
+    let mut span = [[0u32; WORLD_SIZE]; WORLD_SIZE];
+    let mut queue = VecDeque::new();
+
+    for z in 0..SURFACE_LEVEL {
+        let level = &world_grid.tiles[z];
+
+        // Breadth-first out from every wall and pillar at once
+        queue.clear();
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                if is_open(level[y][x]) {
+                    span[y][x] = u32::MAX;
+                } else {
+                    span[y][x] = 0;
+                    queue.push_back((x, y));
+                }
+            }
+        }
+        while let Some((x, y)) = queue.pop_front() {
+            let next = span[y][x] + 1;
+            for (nx, ny) in [
+                (x.wrapping_sub(1), y),
+                (x + 1, y),
+                (x, y.wrapping_sub(1)),
+                (x, y + 1),
+            ] {
+                if nx < WORLD_SIZE && ny < WORLD_SIZE && span[ny][nx] > next {
+                    span[ny][nx] = next;
+                    queue.push_back((nx, ny));
+                }
+            }
+        }
+
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                // Gardens count toward a room's span, but the fungus comb
+                // filling them props their own roof up
+                let hollow = matches!(
+                    level[y][x],
+                    TileKind::Tunnel | TileKind::Ramp | TileKind::Chamber
+                );
+                let strain = match bridgeable_span(&world_grid, &moisture, x, y, z) {
+                    Some(bridgeable) if hollow => span[y][x] as f32 / bridgeable,
+                    _ => 0.0,
+                };
+                supports.strain[z][y][x] = strain;
+                if strain > 1.0 {
+                    supports.at_risk.push((x, y, z));
+                }
+            }
+        }
+    }
+
+    if !was_at_risk && !supports.at_risk.is_empty() {
+        notifications.write(Notification::warning(
+            "Part of the nest is too wide to hold its roof up (I shows where)",
+        ));
+    }
+}
+
+/// Let overstrained tiles cave in, taking their at-risk neighbors with them
+/// and crushing ants and brood underneath
+#[allow(clippy::too_many_arguments)]
+fn cave_ins(
+    mut commands: Commands,
+    supports: Res<Supports>,
+    mut world_grid: ResMut<WorldGrid>,
+    ant_query: Query<(Entity, &GridPosition, &Caste), With<Ant>>,
+    brood_query: Query<(Entity, &GridPosition), With<Brood>>,
+    mut deaths: MessageWriter<AntDied>,
+    mut notifications: MessageWriter<Notification>,
+    mut rng: ResMut<SimRng>,
+) {
+    let mut fallen = Vec::new();
+
+    for &(x, y, z) in &supports.at_risk {
+        let overstrain = (supports.strain[z][y][x] - 1.0).min(1.0) as f64;
+        if !rng.random_bool(CAVE_IN_CHANCE * overstrain) {
+            continue;
+        }
+
+        fallen.push((x, y, z));
+        fallen.extend(
+            supports
+                .at_risk
+                .iter()
+                .filter(|&&(nx, ny, nz)| nz == z && nx.abs_diff(x) + ny.abs_diff(y) == 1),
+        );
+    }
+
+    if fallen.is_empty() {
+        return;
+    }
+    fallen.sort();
+    fallen.dedup();
+
+    // The roof comes down, filling the space with its own soil
+    for &(x, y, z) in &fallen {
+        let roof_z = (z + 1).min(SURFACE_LEVEL - 1);
+        world_grid.soil[z][y][x] = world_grid.soil[roof_z][y][x];
+        world_grid.tiles[z][y][x] = TileKind::Dirt;
+    }
+    info!("Cave-in filled {} tiles", fallen.len());
+
+    let buried = |pos: &GridPosition| fallen.contains(&(pos.x, pos.y, pos.z));
+    let mut crushed = 0;
+    for (entity, pos, caste) in &ant_query {
+        if buried(pos) {
+            commands.entity(entity).despawn();
+            deaths.write(AntDied {
+                caste: *caste,
+                cause: DeathCause::Crushed,
+            });
+            crushed += 1;
+        }
+    }
+    for (entity, pos) in &brood_query {
+        if buried(pos) {
+            commands.entity(entity).despawn();
+            crushed += 1;
+        }
+    }
+
+    notifications.write(Notification::warning(if crushed > 0 {
+        format!("A tunnel caved in, crushing {} of the colony", crushed)
+    } else {
+        String::from("A tunnel caved in")
+    }));
+}
+
+/// Spawn overlay sprites for at-risk tiles
+fn spawn_support_overlay(mut commands: Commands) {
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let world_x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+            let world_y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+
+            commands.spawn((
+                Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(world_x, world_y, 0.46), // Over the air overlay
+                SupportOverlay { x, y },
+                Visibility::Hidden,
+            ));
+        }
+    }
+}
+
+/// Tint at-risk tiles on the current z-level, deeper red the more strained
+fn update_support_overlay(
+    supports: Res<Supports>,
+    current_z: Res<CurrentZLevel>,
+    settings: Res<SupportOverlaySettings>,
+    mut query: Query<(&SupportOverlay, &mut Sprite, &mut Visibility)>,
+) {
+    let z = current_z.0;
+    if !settings.visible || z >= SURFACE_LEVEL {
+        if settings.is_changed() || current_z.is_changed() {
+            for (_, _, mut visibility) in &mut query {
+                *visibility = Visibility::Hidden;
+            }
+        }
+        return;
+    }
+
+    for (overlay, mut sprite, mut visibility) in &mut query {
+        let strain = supports.strain[z][overlay.y][overlay.x];
+        if strain <= 1.0 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        *visibility = Visibility::Visible;
+        sprite.color =
+            sprites::cave_ins::STRAINED.mix(&sprites::cave_ins::FAILING, (strain - 1.0).min(1.0));
+    }
+}
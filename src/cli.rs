@@ -0,0 +1,200 @@
+//! Command-line arguments.
+
+use std::path::PathBuf;
+
+use bevy::prelude::*;
+
+use crate::biome::Biome;
+use crate::metrics::MetricsFormat;
+use crate::multiplayer::{self, CoopRole};
+use crate::sprites::Palette;
+
+/// Options passed on the command line
+#[derive(Resource, Debug, Default, Clone)]
+pub struct CliArgs {
+    /// Input recording to replay (`--replay <file>`)
+    pub replay: Option<PathBuf>,
+    /// Start in low-spec rendering mode (`--low-spec`)
+    pub low_spec: bool,
+    /// Run this many ticks headless and print task metrics (`--audit <ticks>`)
+    pub audit: Option<u64>,
+    /// Biome to generate the world in (`--biome <forest|grassland|desert>`)
+    pub biome: Biome,
+    /// Seed for world generation and the simulation (`--seed <n>`)
+    pub seed: Option<u64>,
+    /// Run this many ticks logging memory and entity counts (`--soak <ticks>`)
+    pub soak: Option<u64>,
+    /// Run a soak without a window (`--headless`)
+    pub headless: bool,
+    /// Depth of the water table below the surface (`--water-table <depth>`)
+    pub water_table: Option<usize>,
+    /// Start with a lone queen founding the nest (`--founding`)
+    pub founding: bool,
+    /// Map file to play on instead of a generated world (`--map <file>`)
+    pub map: Option<PathBuf>,
+    /// Tuning file to read instead of `acre.ron` (`--config <file>`)
+    pub config: Option<PathBuf>,
+    /// Color palette for castes and pheromones (`--palette <name>`)
+    pub palette: Palette,
+    /// UI size in percent, 75 to 200 (`--ui-scale <percent>`)
+    pub ui_scale: Option<f32>,
+    /// Record a timelapse from the start, a frame every so many ticks
+    /// (`--timelapse <ticks>`)
+    pub timelapse: Option<u64>,
+    /// Run the fixed benchmark headless and print timings (`--bench`)
+    pub bench: bool,
+    /// Benchmark a large colony rather than the usual starting one
+    /// (`--bench-large`)
+    pub bench_large: bool,
+    /// Write the colony's numbers to a file each in-game day
+    /// (`--export-metrics <csv|json>`)
+    pub export_metrics: Option<MetricsFormat>,
+    /// What the console shows, as `RUST_LOG` directives (`--log <filter>`)
+    pub log: Option<String>,
+    /// Share the colony with another player, hosting on localhost
+    /// (`--host <port>`) or another address (`--host <address:port>`), or
+    /// joining (`--join <address>`)
+    pub coop: Option<CoopRole>,
+    /// Serve the colony to remote viewers over WebSocket, in builds with the
+    /// `spectator` feature (`--spectate <port>`)
+    pub spectate: Option<u16>,
+    /// Serve an HTTP API on localhost for scripts to drive the simulation, in
+    /// builds with the `remote` feature (`--remote <port>`)
+    pub remote: Option<u16>,
+    /// Serve metrics for Prometheus to scrape, in builds with the `prometheus`
+    /// feature (`--prometheus <port>`)
+    pub prometheus: Option<u16>,
+}
+
+impl CliArgs {
+    /// Parse the process arguments, warning about anything unrecognized.
+    /// Runs before logging is set up, so problems go straight to stderr.
+    pub fn parse() -> Self {
+        let mut args = CliArgs::default();
+        let mut iter = std::env::args().skip(1);
+
+        while let Some(arg) = iter.next() {
+            match arg.as_str() {
+                "--replay" => match iter.next() {
+                    Some(path) => args.replay = Some(PathBuf::from(path)),
+                    None => eprintln!("--replay needs a file path"),
+                },
+                "--low-spec" => args.low_spec = true,
+                "--biome" => match iter.next().as_deref().and_then(Biome::from_name) {
+                    Some(biome) => args.biome = biome,
+                    None => eprintln!("--biome needs one of: forest, grassland, desert"),
+                },
+                "--seed" => match iter.next().map(|seed| seed.parse()) {
+                    Some(Ok(seed)) => args.seed = Some(seed),
+                    _ => eprintln!("--seed needs a number"),
+                },
+                "--audit" => match iter.next().map(|ticks| ticks.parse()) {
+                    Some(Ok(ticks)) => args.audit = Some(ticks),
+                    _ => eprintln!("--audit needs a number of ticks"),
+                },
+                "--soak" => match iter.next().map(|ticks| ticks.parse()) {
+                    Some(Ok(ticks)) => args.soak = Some(ticks),
+                    _ => eprintln!("--soak needs a number of ticks"),
+                },
+                "--headless" => args.headless = true,
+                "--water-table" => match iter.next().map(|depth| depth.parse()) {
+                    Some(Ok(depth)) => args.water_table = Some(depth),
+                    _ => eprintln!("--water-table needs a depth in tiles"),
+                },
+                "--founding" => args.founding = true,
+                "--map" => match iter.next() {
+                    Some(path) => args.map = Some(PathBuf::from(path)),
+                    None => eprintln!("--map needs a file path"),
+                },
+                "--config" => match iter.next() {
+                    Some(path) => args.config = Some(PathBuf::from(path)),
+                    None => eprintln!("--config needs a file path"),
+                },
+                "--palette" => match iter.next().as_deref().and_then(Palette::from_name) {
+                    Some(palette) => args.palette = palette,
+                    None => eprintln!(
+                        "--palette needs one of: standard, deuteranopia, protanopia, tritanopia"
+                    ),
+                },
+                "--ui-scale" => match iter.next().map(|percent| percent.parse()) {
+                    Some(Ok(percent)) => args.ui_scale = Some(percent),
+                    _ => eprintln!("--ui-scale needs a percentage, 75 to 200"),
+                },
+                "--timelapse" => match iter.next().map(|ticks| ticks.parse()) {
+                    Some(Ok(ticks)) => args.timelapse = Some(ticks),
+                    _ => eprintln!("--timelapse needs a number of ticks between frames"),
+                },
+                "--bench" => args.bench = true,
+                "--bench-large" => {
+                    args.bench = true;
+                    args.bench_large = true;
+                }
+                "--log" => match iter.next() {
+                    Some(filter) => args.log = Some(filter),
+                    None => eprintln!("--log needs a filter, e.g. acre=info"),
+                },
+                "--host" => match iter.next().as_deref().and_then(multiplayer::host_address) {
+                    Some(address) => args.coop = Some(CoopRole::Host(address)),
+                    None => eprintln!(
+                        "--host needs a port number, or an address and port, e.g. 0.0.0.0:7777"
+                    ),
+                },
+                "--join" => match iter.next() {
+                    Some(address) => args.coop = Some(CoopRole::Join(address)),
+                    None => eprintln!("--join needs the host's address, e.g. 192.168.1.5:7777"),
+                },
+                "--spectate" => match iter.next().map(|port| port.parse()) {
+                    Some(Ok(port)) => args.spectate = Some(port),
+                    _ => eprintln!("--spectate needs a port number"),
+                },
+                "--remote" => match iter.next().map(|port| port.parse()) {
+                    Some(Ok(port)) => args.remote = Some(port),
+                    _ => eprintln!("--remote needs a port number"),
+                },
+                "--prometheus" => match iter.next().map(|port| port.parse()) {
+                    Some(Ok(port)) => args.prometheus = Some(port),
+                    _ => eprintln!("--prometheus needs a port number"),
+                },
+                "--export-metrics" => {
+                    match iter.next().as_deref().and_then(MetricsFormat::from_name) {
+                        Some(format) => args.export_metrics = Some(format),
+                        None => eprintln!("--export-metrics needs one of: csv, json"),
+                    }
+                }
+                other => eprintln!("Ignoring unknown argument: {}", other),
+            }
+        }
+
+        if args.headless && args.soak.is_none() && args.audit.is_none() && args.remote.is_none() {
+            eprintln!("--headless only applies to --soak and --remote runs");
+        }
+
+        if args.spectate.is_some() && !cfg!(feature = "spectator") {
+            eprintln!("--spectate needs a build with the spectator feature");
+        }
+        if args.remote.is_some() && !cfg!(feature = "remote") {
+            eprintln!("--remote needs a build with the remote feature");
+        }
+        if args.prometheus.is_some() && !cfg!(feature = "prometheus") {
+            eprintln!("--prometheus needs a build with the prometheus feature");
+        }
+        if args.coop.is_some() && args.is_headless() {
+            eprintln!("Co-op games need a window; ignoring --host and --join");
+            args.coop = None;
+        }
+        if args.coop.is_some() && args.map.is_some() {
+            eprintln!("Co-op games start on a generated world; ignoring --map");
+            args.map = None;
+        }
+
+        args
+    }
+
+    /// Whether this run has no window: audits and benchmarks always, soaks and
+    /// remote-controlled runs on request
+    pub fn is_headless(&self) -> bool {
+        self.audit.is_some()
+            || self.bench
+            || (self.headless && (self.soak.is_some() || self.remote.is_some()))
+    }
+}
@@ -0,0 +1,282 @@
+//! Simulation tuning.
+//!
+//! `SimulationConfig` holds the rates and thresholds the simulation runs on.
+//! The tuning half is read at startup from `acre.ron` in the working directory
+//! (or the file given with `--config <path>`), and read again whenever the
+//! file changes, so numbers can be tweaked while the game runs. The file is a
+//! RON struct of whichever fields should change; fields left out keep their
+//! defaults, and a file with unknown fields is rejected with a warning:
+//!
+//! ```ron
+//! (
+//!     hunger_per_tick: 0.2,
+//!     lay_interval: 300,
+//! )
+//! ```
+//!
+//! The difficulty multipliers are set by the difficulty preset instead (see
+//! `difficulty.rs`).
+
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::SystemTime;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::cli::CliArgs;
+use crate::notifications::Notification;
+use crate::world::BLIGHT_THRESHOLD;
+
+pub struct ConfigPlugin;
+
+impl Plugin for ConfigPlugin {
+    fn build(&self, app: &mut App) {
+        let path = app
+            .world()
+            .get_resource::<CliArgs>()
+            .and_then(|cli| cli.config.clone())
+            .unwrap_or_else(|| PathBuf::from(DEFAULT_CONFIG_PATH));
+
+        let mut config = SimulationConfig::default();
+        let modified = modified_time(&path);
+        if modified.is_some() {
+            load_tuning(&mut config, &path);
+        }
+
+        app.insert_resource(config)
+            .insert_resource(ConfigFile { path, modified })
+            .add_systems(Update, reload_config);
+    }
+}
+
+/// Config file read when `--config` isn't given
+const DEFAULT_CONFIG_PATH: &str = "acre.ron";
+/// Seconds between checks for changes to the config file
+const RELOAD_CHECK_SECS: f32 = 1.0;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Rates and thresholds the simulation runs on
+#[derive(Resource, Debug, Clone, PartialEq, Deserialize)]
+#[serde(default, deny_unknown_fields)]
+pub struct SimulationConfig {
+    /// Hunger an ant gains each tick (the queen gains half)
+    pub hunger_per_tick: f32,
+    /// Pheromone lost from each tile each tick
+    pub pheromone_decay: f32,
+    /// Growth per tick of a garden tile with one unit of mulch
+    pub fungus_growth_rate: f32,
+    /// Ticks between the queen's eggs
+    pub lay_interval: u32,
+    /// Fewest beetles in an arriving herd
+    pub herd_min: usize,
+    /// Most beetles in an arriving herd
+    pub herd_max: usize,
+    /// Relative odds of a new egg being each worker caste
+    pub forager_odds: u32,
+    pub gardener_odds: u32,
+    pub nurse_odds: u32,
+    pub soldier_odds: u32,
+    /// Ticks a busy ant can stand on one tile before it drops its task
+    pub stuck_ticks: u32,
+    /// Avoid pheromone laid where an ant got stuck; zero lays none
+    pub stuck_avoid: f32,
+
+    /// Hunger at which ants will seek food
+    pub hunger_threshold: f32,
+    /// Hunger at which an ant starts begging food from nestmates it passes,
+    /// early enough that it needn't drop what it's doing
+    pub beg_threshold: f32,
+    /// Most hunger a full crop can relieve
+    pub crop_capacity: f32,
+    /// Food left in the stores below which ants don't fill their crops
+    pub crop_reserve: u32,
+    /// Food and honeydew left in the stores below which foragers gather seeds
+    pub emergency_food_level: u32,
+    /// Ticks out of every this many that each item beyond the first costs an
+    /// ant carrying a load home
+    pub load_drag: u64,
+    /// Levels an ant can drop without getting hurt
+    pub safe_fall_height: usize,
+    /// Hunger each level fallen beyond the safe height costs the ant to recover
+    pub fall_injury: f32,
+    /// A scout gives up on an unreachable spot with 1-in-this odds each tick
+    pub scout_give_up_odds: u32,
+    /// Share of a tile of soft soil a forager digs out per tick; harder soils
+    /// and smaller castes take proportionally longer
+    pub dig_rate: f32,
+    /// How fast roots are gnawed through, on the same scale as soil dig chances
+    pub root_dig_speed: f64,
+    /// Ticks of work to cut one leaf fragment loose
+    pub leaf_cut_ticks: u32,
+    /// How close a spider can get before cutting ants abandon the plant
+    pub alarm_range: usize,
+    /// Honeydew a tending ant carries home in one trip
+    pub honeydew_load: f32,
+    /// Hunger at which a tending ant drinks the honeydew itself
+    pub honeydew_drink_hunger: f32,
+    /// Water scooped out of a tile per bailing trip
+    pub bail_amount: f32,
+    /// Contamination at which idle gardeners go clean a garden tile
+    pub clean_threshold: f32,
+    /// Contamination at which gardeners clean even with leaves waiting
+    pub urgent_clean_threshold: f32,
+    /// Contamination removed per tick of cleaning
+    pub clean_rate: f32,
+    /// How far from a corpse garden tiles are contaminated by it
+    pub corpse_radius: usize,
+    /// Contamination a corpse adds to nearby garden tiles
+    pub corpse_contamination: f32,
+
+    /// Multiplier on how fast ants get hungry, from the difficulty
+    #[serde(skip)]
+    pub hunger_rate: f32,
+    /// Multiplier on how often predators wander in, from the difficulty
+    #[serde(skip)]
+    pub predator_frequency: f64,
+    /// Multiplier on how fast the fungus garden grows food, from the difficulty
+    #[serde(skip)]
+    pub fungus_yield: f32,
+    /// Most predators that can be prowling the surface at once, from the
+    /// difficulty
+    #[serde(skip)]
+    pub raid_strength: usize,
+}
+
+impl Default for SimulationConfig {
+    fn default() -> Self {
+        let hunger_threshold = 50.0;
+        Self {
+            hunger_per_tick: 0.15,
+            pheromone_decay: 0.0005,
+            fungus_growth_rate: 0.005,
+            lay_interval: 400,
+            herd_min: 3,
+            herd_max: 5,
+            forager_odds: 45,
+            gardener_odds: 30,
+            nurse_odds: 15,
+            soldier_odds: 10,
+            stuck_ticks: 300,
+            stuck_avoid: 0.5,
+
+            hunger_threshold,
+            beg_threshold: hunger_threshold * 0.6,
+            crop_capacity: 50.0,
+            crop_reserve: 5,
+            emergency_food_level: 5,
+            load_drag: 4,
+            safe_fall_height: 3,
+            fall_injury: 20.0,
+            scout_give_up_odds: 200,
+            dig_rate: 0.25,
+            root_dig_speed: 0.1,
+            leaf_cut_ticks: 15,
+            alarm_range: 4,
+            honeydew_load: 1.0,
+            honeydew_drink_hunger: hunger_threshold * 0.5,
+            bail_amount: 0.25,
+            clean_threshold: 0.2,
+            urgent_clean_threshold: BLIGHT_THRESHOLD * 0.8,
+            clean_rate: 0.02,
+            corpse_radius: 3,
+            corpse_contamination: 0.25,
+
+            hunger_rate: 1.0,
+            predator_frequency: 1.0,
+            fungus_yield: 1.0,
+            raid_strength: 2,
+        }
+    }
+}
+
+impl SimulationConfig {
+    /// This config retuned from a tuning file: the file's fields over the
+    /// defaults, keeping the difficulty multipliers
+    pub fn tuned(&self, text: &str) -> Result<Self, String> {
+        let mut tuned: SimulationConfig = ron::from_str(text).map_err(|e| e.to_string())?;
+        tuned.hunger_rate = self.hunger_rate;
+        tuned.predator_frequency = self.predator_frequency;
+        tuned.fungus_yield = self.fungus_yield;
+        tuned.raid_strength = self.raid_strength;
+
+        // Keep the ranges and odds the simulation samples from usable
+        tuned.herd_max = tuned.herd_max.max(tuned.herd_min);
+        tuned.lay_interval = tuned.lay_interval.max(1);
+        tuned.stuck_ticks = tuned.stuck_ticks.max(1);
+        tuned.load_drag = tuned.load_drag.max(1);
+        tuned.scout_give_up_odds = tuned.scout_give_up_odds.max(1);
+
+        Ok(tuned)
+    }
+}
+
+/// The tuning file and when it last changed
+#[derive(Resource)]
+pub struct ConfigFile {
+    pub path: PathBuf,
+    /// Modification time when last read, None if there's no file
+    pub modified: Option<SystemTime>,
+}
+
+/// When a file was last modified, None if it can't be read
+fn modified_time(path: &Path) -> Option<SystemTime> {
+    fs::metadata(path).and_then(|meta| meta.modified()).ok()
+}
+
+/// Retune the config from the tuning file, leaving it as it was if the file
+/// can't be read or used
+fn load_tuning(config: &mut SimulationConfig, path: &Path) -> bool {
+    let tuned = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| config.tuned(&text));
+    match tuned {
+        Ok(tuned) => {
+            *config = tuned;
+            info!("Loaded config from {}", path.display());
+            true
+        }
+        Err(e) => {
+            warn!("Couldn't load config {}: {}", path.display(), e);
+            false
+        }
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Re-read the tuning file whenever it changes on disk
+fn reload_config(
+    time: Res<Time<Real>>,
+    mut timer: Local<Option<Timer>>,
+    mut file: ResMut<ConfigFile>,
+    mut config: ResMut<SimulationConfig>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let timer =
+        timer.get_or_insert_with(|| Timer::from_seconds(RELOAD_CHECK_SECS, TimerMode::Repeating));
+    if !timer.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let modified = modified_time(&file.path);
+    if modified.is_none() || modified == file.modified {
+        return;
+    }
+    file.modified = modified;
+
+    // Fields removed from the file go back to their defaults
+    let mut tuned = config.clone();
+    if load_tuning(&mut tuned, &file.path) {
+        *config = tuned;
+        notifications.write(Notification::info(format!(
+            "Reloaded tuning from {}",
+            file.path.display()
+        )));
+    }
+}
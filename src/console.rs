@@ -0,0 +1,482 @@
+//! Developer console.
+//!
+//! The backquote key (~) drops a console down over the game. Type a command
+//! and press Enter to run it; Up and Down step through earlier commands, and
+//! Esc or ~ again closes it. While it's open it takes all the keyboard input,
+//! so typing doesn't set off the game's own keys.
+//!
+//! Commands live in a registry, and any plugin can add its own with
+//! `app.add_console_command(...)`, giving a name, a usage line, a line of
+//! help and a function that gets the world and the command's arguments. The
+//! built-in ones:
+//!
+//! - `help` lists every command
+//! - `spawn <caste> [count]` hatches ants at the cursor (or the nest)
+//! - `set <food|honeydew|seeds|leaves> <amount>` fills a store
+//! - `reveal` uncovers every underground tile
+//! - `tp camera <nest|cursor|x y z>` moves the camera
+//! - `tick <count>` runs the simulation forward that many ticks
+//!
+//! Spawned ants go through `PlayerAction` like the rest of the player's
+//! input, so recordings replay them; everything else is changed directly.
+//! Using any command but `help` counts as cheating for achievements.
+
+use std::collections::VecDeque;
+
+use bevy::input::InputSystems;
+use bevy::input::keyboard::{Key, KeyboardInput};
+use bevy::prelude::*;
+
+use crate::achievements::AchievementRun;
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::ants::NestLocation;
+use crate::camera::{MainCamera, focus_tile};
+use crate::cursor::CursorTile;
+use crate::exploration::Explored;
+use crate::history::CASTES;
+use crate::sprites;
+use crate::world::{CurrentZLevel, FoodStores, FungusGarden, WORLD_SIZE};
+use crate::{in_game, step};
+
+pub struct ConsolePlugin;
+
+impl Plugin for ConsolePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Console>()
+            .add_console_command("help", "help", "List every command", help)
+            .add_console_command(
+                "spawn",
+                "spawn <caste> [count]",
+                "Hatch ants at the cursor, or the nest",
+                spawn,
+            )
+            .add_console_command(
+                "set",
+                "set <food|honeydew|seeds|leaves> <amount>",
+                "Fill a store",
+                set,
+            )
+            .add_console_command("reveal", "reveal", "Uncover every tile", reveal)
+            .add_console_command(
+                "tp",
+                "tp camera <nest|cursor|x y z>",
+                "Move the camera",
+                teleport,
+            )
+            .add_console_command("tick", "tick <count>", "Run the simulation forward", tick)
+            .add_systems(Startup, setup_console_panel)
+            // Before anything else reads the keyboard, so it can be kept
+            // from the game while the console is open
+            .add_systems(PreUpdate, console_input.after(InputSystems).run_if(in_game))
+            .add_systems(
+                Update,
+                (
+                    run_console_commands.in_set(ActionSystems::Input),
+                    update_console_panel,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Lines of output kept before the oldest are dropped
+const MAX_OUTPUT: usize = 100;
+/// Lines of output shown at once
+const VISIBLE_LINES: usize = 12;
+/// Most ticks `tick` will run in one go
+const MAX_TICKS: u64 = 10_000;
+/// Most ants `spawn` will hatch in one go
+const MAX_SPAWN: usize = 500;
+
+/// A console command: the world, and the words after the command's name, in;
+/// a line to show, or what went wrong, out
+pub type CommandFn = fn(&mut World, &[&str]) -> Result<String, String>;
+
+/// A command the console can run
+#[derive(Clone)]
+pub struct ConsoleCommand {
+    pub name: &'static str,
+    /// How it's typed, e.g. `set <store> <amount>`
+    pub usage: &'static str,
+    pub help: &'static str,
+    pub run: CommandFn,
+}
+
+/// Registering console commands from any plugin
+pub trait ConsoleAppExt {
+    fn add_console_command(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        run: CommandFn,
+    ) -> &mut Self;
+}
+
+impl ConsoleAppExt for App {
+    fn add_console_command(
+        &mut self,
+        name: &'static str,
+        usage: &'static str,
+        help: &'static str,
+        run: CommandFn,
+    ) -> &mut Self {
+        self.init_resource::<ConsoleCommands>();
+        self.world_mut()
+            .resource_mut::<ConsoleCommands>()
+            .register(ConsoleCommand {
+                name,
+                usage,
+                help,
+                run,
+            });
+        self
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Every command the console knows, in the order they were registered
+#[derive(Resource, Default)]
+pub struct ConsoleCommands {
+    list: Vec<ConsoleCommand>,
+}
+
+impl ConsoleCommands {
+    /// Add a command, replacing any of the same name
+    pub fn register(&mut self, command: ConsoleCommand) {
+        self.list.retain(|existing| existing.name != command.name);
+        self.list.push(command);
+    }
+
+    pub fn get(&self, name: &str) -> Option<&ConsoleCommand> {
+        self.list.iter().find(|command| command.name == name)
+    }
+}
+
+/// The console's state: whether it's down, what's being typed, and what it
+/// has said
+#[derive(Resource, Default)]
+pub struct Console {
+    pub open: bool,
+    input: String,
+    /// Lines entered and not yet run
+    pending: Vec<String>,
+    /// Lines entered before, oldest first
+    history: Vec<String>,
+    /// Place in the history while stepping through it with Up and Down
+    history_index: Option<usize>,
+    output: VecDeque<String>,
+}
+
+impl Console {
+    fn print(&mut self, line: impl Into<String>) {
+        self.output.push_back(line.into());
+        while self.output.len() > MAX_OUTPUT {
+            self.output.pop_front();
+        }
+    }
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the console panel
+#[derive(Component)]
+struct ConsolePanel;
+
+/// Marker for the console's text
+#[derive(Component)]
+struct ConsoleText;
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Spawn the (initially hidden) console across the top of the screen
+fn setup_console_panel(mut commands: Commands) {
+    commands.spawn((
+        ConsolePanel,
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(0.0),
+            right: Val::Px(0.0),
+            top: Val::Px(0.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.9)),
+        GlobalZIndex(10),
+        Visibility::Hidden,
+        children![(
+            ConsoleText,
+            Text::new(""),
+            TextFont {
+                font_size: 13.0,
+                ..default()
+            },
+            TextColor(sprites::ui::TEXT),
+        )],
+    ));
+}
+
+/// Open and close the console, and type into it while it's open
+fn console_input(
+    mut keyboard: ResMut<ButtonInput<KeyCode>>,
+    mut key_events: MessageReader<KeyboardInput>,
+    mut console: ResMut<Console>,
+) {
+    if keyboard.just_pressed(KeyCode::Backquote) {
+        console.open = !console.open;
+        key_events.clear();
+        keyboard.reset_all();
+        return;
+    }
+    if !console.open {
+        key_events.clear();
+        return;
+    }
+
+    for event in key_events.read() {
+        if !event.state.is_pressed() {
+            continue;
+        }
+        match &event.logical_key {
+            Key::Enter => {
+                let line = std::mem::take(&mut console.input);
+                if !line.trim().is_empty() {
+                    console.history.push(line.clone());
+                    console.pending.push(line);
+                }
+                console.history_index = None;
+            }
+            Key::Backspace => {
+                console.input.pop();
+            }
+            Key::Escape => console.open = false,
+            Key::ArrowUp | Key::ArrowDown => {
+                let last = console.history.len().checked_sub(1);
+                let index = match (&event.logical_key, console.history_index) {
+                    (Key::ArrowUp, None) => last,
+                    (Key::ArrowUp, Some(i)) => Some(i.saturating_sub(1)),
+                    (_, Some(i)) if Some(i) < last => Some(i + 1),
+                    _ => None,
+                };
+                console.history_index = index;
+                console.input = index
+                    .and_then(|i| console.history.get(i).cloned())
+                    .unwrap_or_default();
+            }
+            Key::Space => console.input.push(' '),
+            Key::Character(text) => console.input.push_str(text),
+            _ => {}
+        }
+    }
+
+    // Nothing else gets to see the keys while the console has them
+    keyboard.reset_all();
+}
+
+/// Run the commands entered since the last frame
+fn run_console_commands(world: &mut World) {
+    let pending = std::mem::take(&mut world.resource_mut::<Console>().pending);
+
+    for line in pending {
+        let words: Vec<&str> = line.split_whitespace().collect();
+        let Some((name, args)) = words.split_first() else {
+            continue;
+        };
+        let command = world.resource::<ConsoleCommands>().get(name).cloned();
+
+        let result = match command {
+            Some(command) => {
+                if command.name != "help" {
+                    world.resource_mut::<AchievementRun>().sandboxed = true;
+                }
+                (command.run)(world, args)
+            }
+            None => Err(format!("Unknown command '{}', try 'help'", name)),
+        };
+
+        let mut console = world.resource_mut::<Console>();
+        console.print(format!("> {}", line));
+        match result {
+            Ok(output) => {
+                info!("Console: {}: {}", line, output);
+                console.print(output);
+            }
+            Err(e) => {
+                warn!("Console: {}: {}", line, e);
+                console.print(format!("Error: {}", e));
+            }
+        }
+    }
+}
+
+/// Show the console while it's open, with its latest output and the line
+/// being typed
+fn update_console_panel(
+    console: Res<Console>,
+    mut panel_query: Query<&mut Visibility, With<ConsolePanel>>,
+    mut text_query: Query<&mut Text, With<ConsoleText>>,
+) {
+    if !console.is_changed() {
+        return;
+    }
+    if let Ok(mut visibility) = panel_query.single_mut() {
+        *visibility = if console.open {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+
+    let skip = console.output.len().saturating_sub(VISIBLE_LINES);
+    let mut text: String = console
+        .output
+        .iter()
+        .skip(skip)
+        .map(|line| format!("{}\n", line))
+        .collect();
+    text.push_str(&format!("] {}_", console.input));
+    for mut panel_text in &mut text_query {
+        **panel_text = text.clone();
+    }
+}
+
+// ============================================================================
+// Commands
+// ============================================================================
+
+/// Parse an argument, naming it if it's missing or wrong
+fn parse_arg<T: std::str::FromStr>(args: &[&str], index: usize, what: &str) -> Result<T, String> {
+    let arg = args.get(index).ok_or_else(|| format!("missing {}", what))?;
+    arg.parse()
+        .map_err(|_| format!("'{}' isn't a valid {}", arg, what))
+}
+
+fn help(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let lines: Vec<String> = world
+        .resource::<ConsoleCommands>()
+        .list
+        .iter()
+        .map(|command| format!("{} - {}", command.usage, command.help))
+        .collect();
+    Ok(lines.join("\n"))
+}
+
+fn spawn(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let name = args.first().ok_or("missing caste")?;
+    let caste = CASTES
+        .into_iter()
+        .find(|caste| caste.name().eq_ignore_ascii_case(name))
+        .ok_or_else(|| format!("unknown caste '{}'", name))?;
+    let count = if args.len() > 1 {
+        parse_arg(args, 1, "count")?
+    } else {
+        1
+    };
+    if count > MAX_SPAWN {
+        return Err(format!("at most {} at a time", MAX_SPAWN));
+    }
+
+    let nest = world.resource::<NestLocation>();
+    let (x, y, z) = world
+        .resource::<CursorTile>()
+        .0
+        .unwrap_or((nest.x, nest.y, nest.z));
+    for _ in 0..count {
+        world.write_message(PlayerAction::SpawnAnt { caste, x, y, z });
+    }
+    Ok(format!(
+        "Spawned {} {} at ({}, {}, {})",
+        count,
+        caste.name(),
+        x,
+        y,
+        z
+    ))
+}
+
+fn set(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let store = *args.first().ok_or("missing store")?;
+    let amount: u32 = parse_arg(args, 1, "amount")?;
+    match store {
+        "food" => world.resource_mut::<FoodStores>().food = amount,
+        "honeydew" => world.resource_mut::<FoodStores>().honeydew = amount,
+        "seeds" => world.resource_mut::<FoodStores>().seeds = amount,
+        "leaves" => world.resource_mut::<FungusGarden>().leaves = amount,
+        _ => return Err(format!("unknown store '{}'", store)),
+    }
+    Ok(format!("Set {} to {}", store, amount))
+}
+
+fn reveal(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    for level in world.resource_mut::<Explored>().tiles.iter_mut() {
+        for row in level.iter_mut() {
+            row.fill(true);
+        }
+    }
+    Ok(String::from("Revealed every tile"))
+}
+
+fn teleport(world: &mut World, args: &[&str]) -> Result<String, String> {
+    if args.first() != Some(&"camera") {
+        return Err(String::from("only the camera can be moved: tp camera ..."));
+    }
+    let target = match args.get(1..).unwrap_or_default() {
+        ["nest"] => {
+            let nest = world.resource::<NestLocation>();
+            (nest.x, nest.y, nest.z)
+        }
+        ["cursor"] => world
+            .resource::<CursorTile>()
+            .0
+            .ok_or("the cursor isn't over the world")?,
+        [_, _, _] => {
+            let coords = &args[1..];
+            let target = (
+                parse_arg(coords, 0, "x")?,
+                parse_arg(coords, 1, "y")?,
+                parse_arg(coords, 2, "z")?,
+            );
+            if [target.0, target.1, target.2]
+                .iter()
+                .any(|c| *c >= WORLD_SIZE)
+            {
+                return Err(format!("coordinates run from 0 to {}", WORLD_SIZE - 1));
+            }
+            target
+        }
+        _ => return Err(String::from("tp camera <nest|cursor|x y z>")),
+    };
+
+    world.resource_scope(|world, mut current_z: Mut<CurrentZLevel>| {
+        let mut camera_query = world.query_filtered::<&mut Transform, With<MainCamera>>();
+        match camera_query.single_mut(world) {
+            Ok(mut transform) => {
+                focus_tile(&mut transform, &mut current_z, target);
+                Ok(format!(
+                    "Camera at ({}, {}, {})",
+                    target.0, target.1, target.2
+                ))
+            }
+            Err(_) => Err(String::from("there's no camera")),
+        }
+    })
+}
+
+fn tick(world: &mut World, args: &[&str]) -> Result<String, String> {
+    let count: u64 = parse_arg(args, 0, "count")?;
+    if count > MAX_TICKS {
+        return Err(format!("at most {} ticks at a time", MAX_TICKS));
+    }
+
+    for _ in 0..count {
+        step(world);
+    }
+    Ok(format!("Ran {} ticks", count))
+}
@@ -0,0 +1,307 @@
+//! Content definitions: the numbers behind castes, tiles, plants and
+//! migrants.
+//!
+//! Each caste's size, digging strength and load, each tile kind's color and
+//! atlas cell, each plant's leaves and regrowth, and each migrant's seasons
+//! and pace live in `assets/content.ron` rather than in code. The file is
+//! built into the game, so it always has a full set; at startup the copy in
+//! `assets/` is loaded through the asset system over the top of it, and
+//! reloaded if it changes while the game runs (with Bevy's file watcher on).
+//! A file that's missing a kind, or doesn't parse, is warned about and the
+//! definitions already in use are kept.
+//!
+//! The kinds themselves are still enums in code, since what each one does
+//! is, so a new caste or tile needs its variant adding as well as its entry
+//! here.
+
+use bevy::asset::io::Reader;
+use bevy::asset::{AssetLoader, LoadContext};
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::ants::Caste;
+use crate::biome::Biome;
+use crate::history::CASTES;
+use crate::migrations::MigrantKind;
+use crate::time_controls::Season;
+use crate::world::{LeafSource, PlantKind, TileKind};
+
+pub struct ContentPlugin;
+
+impl Plugin for ContentPlugin {
+    fn build(&self, app: &mut App) {
+        let content = match Content::parse(BUILT_IN.as_bytes()) {
+            Ok(content) => content,
+            Err(e) => panic!("The built-in content definitions are broken: {}", e),
+        };
+
+        app.insert_resource(content);
+
+        // Without an asset server, as in a bare headless app, the built-in
+        // definitions are the only ones
+        if app.world().contains_resource::<AssetServer>() {
+            app.init_asset::<Content>()
+                .register_asset_loader(ContentLoader)
+                .add_systems(Startup, load_content)
+                .add_systems(Update, watch_content);
+        }
+    }
+}
+
+/// The definitions, relative to `assets/`
+pub const PATH: &str = "content.ron";
+/// The definitions the game ships with
+const BUILT_IN: &str = include_str!("../assets/content.ron");
+
+// ============================================================================
+// Definitions
+// ============================================================================
+
+/// How a caste is built
+#[derive(Debug, Clone, Deserialize)]
+pub struct CasteStats {
+    pub caste: Caste,
+    /// Body length of an adult, in pixels
+    pub size: f32,
+    /// How fast it digs compared with a forager
+    pub dig_strength: f32,
+    /// How many items it can carry at once
+    pub load_capacity: u32,
+}
+
+/// How a tile kind is drawn
+#[derive(Debug, Clone, Deserialize)]
+pub struct TileStats {
+    pub kind: TileKind,
+    /// sRGB, 0 to 1
+    pub color: (f32, f32, f32),
+    /// Cell in the sprite atlas
+    pub atlas_index: usize,
+}
+
+/// How much a plant gives and how fast it grows back
+#[derive(Debug, Clone, Deserialize)]
+pub struct PlantStats {
+    pub kind: PlantKind,
+    /// Leaf units a single cut fragment adds to the garden
+    pub leaf_value: u32,
+    /// Leaves on a plant in full leaf
+    pub max_leaves: u32,
+    /// Ticks to regrow a leaf, before the biome's pace
+    pub regrow_ticks: f32,
+}
+
+/// When an animal passes through and how fast
+#[derive(Debug, Clone, Deserialize)]
+pub struct MigrantStats {
+    pub kind: MigrantKind,
+    /// Seasons it's around in
+    pub seasons: Vec<Season>,
+    /// Ticks between steps across the surface
+    pub step_ticks: u32,
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Every definition, one per kind, in the order the kinds are declared
+#[derive(Resource, Asset, TypePath, Debug, Clone, Deserialize)]
+pub struct Content {
+    castes: Vec<CasteStats>,
+    tiles: Vec<TileStats>,
+    plants: Vec<PlantStats>,
+    migrants: Vec<MigrantStats>,
+}
+
+impl Content {
+    /// Parse definitions, checking there's exactly one for every kind and
+    /// putting them in order
+    fn parse(bytes: &[u8]) -> Result<Self, String> {
+        let content: Content = ron::de::from_bytes(bytes).map_err(|e| e.to_string())?;
+        Ok(Content {
+            castes: complete(content.castes, &CASTES, |stats| stats.caste, Caste::name)?,
+            tiles: complete(
+                content.tiles,
+                &TileKind::ALL,
+                |stats| stats.kind,
+                TileKind::name,
+            )?,
+            plants: complete(
+                content.plants,
+                &PlantKind::ALL,
+                |stats| stats.kind,
+                PlantKind::name,
+            )?,
+            migrants: complete(
+                content.migrants,
+                &MigrantKind::ALL,
+                |stats| stats.kind,
+                MigrantKind::name,
+            )?,
+        })
+    }
+
+    pub fn caste(&self, caste: Caste) -> &CasteStats {
+        &self.castes[caste as usize]
+    }
+
+    pub fn tile(&self, kind: TileKind) -> &TileStats {
+        &self.tiles[kind as usize]
+    }
+
+    pub fn plant(&self, kind: PlantKind) -> &PlantStats {
+        &self.plants[kind as usize]
+    }
+
+    pub fn migrant(&self, kind: MigrantKind) -> &MigrantStats {
+        &self.migrants[kind as usize]
+    }
+
+    pub fn tile_color(&self, kind: TileKind) -> Color {
+        let (r, g, b) = self.tile(kind).color;
+        Color::srgb(r, g, b)
+    }
+
+    /// Color of a tile, with the ground tinted for the biome
+    pub fn biome_color(&self, kind: TileKind, biome: Biome) -> Color {
+        let (surface, dirt) = biome.soil_colors();
+        match kind {
+            TileKind::Surface => surface,
+            TileKind::Dirt => dirt,
+            _ => self.tile_color(kind),
+        }
+    }
+
+    /// A fully leafed source for a plant, regrowing at the biome's pace
+    pub fn leaf_source(&self, kind: PlantKind, biome: Biome) -> LeafSource {
+        let stats = self.plant(kind);
+        LeafSource {
+            leaves_remaining: stats.max_leaves,
+            leaf_value: stats.leaf_value,
+            max_leaves: stats.max_leaves,
+            regrow_ticks: stats.regrow_ticks / biome.regrowth_rate(),
+            ..default()
+        }
+    }
+
+    /// Whether a migrant is around during a season
+    pub fn in_season(&self, kind: MigrantKind, season: Season) -> bool {
+        self.migrant(kind).seasons.contains(&season)
+    }
+}
+
+/// Order definitions by kind, one for each in `kinds`, naming any kind
+/// that's missing or defined twice
+fn complete<T, K: Copy + PartialEq>(
+    mut definitions: Vec<T>,
+    kinds: &[K],
+    kind_of: impl Fn(&T) -> K,
+    name: impl Fn(&K) -> &'static str,
+) -> Result<Vec<T>, String> {
+    let mut ordered = Vec::with_capacity(kinds.len());
+    for kind in kinds {
+        let found: Vec<usize> = (0..definitions.len())
+            .filter(|&i| kind_of(&definitions[i]) == *kind)
+            .collect();
+        match found.as_slice() {
+            [] => return Err(format!("no definition for {}", name(kind))),
+            [_] => {}
+            _ => return Err(format!("{} is defined more than once", name(kind))),
+        }
+        ordered.push(definitions.remove(found[0]));
+    }
+    Ok(ordered)
+}
+
+/// The content file as loaded through the asset system
+#[derive(Resource)]
+pub struct ContentFile {
+    handle: Handle<Content>,
+    /// Whether it has loaded and is in use
+    ready: bool,
+    /// Whether loading failed, leaving the built-in definitions in place
+    failed: bool,
+}
+
+/// Reads definitions files into `Content`
+#[derive(TypePath)]
+struct ContentLoader;
+
+impl AssetLoader for ContentLoader {
+    type Asset = Content;
+    type Settings = ();
+    type Error = Box<dyn std::error::Error + Send + Sync>;
+
+    async fn load(
+        &self,
+        reader: &mut dyn Reader,
+        _settings: &(),
+        _load_context: &mut LoadContext<'_>,
+    ) -> Result<Content, Self::Error> {
+        let mut bytes = Vec::new();
+        reader.read_to_end(&mut bytes).await?;
+        Ok(Content::parse(&bytes)?)
+    }
+
+    fn extensions(&self) -> &[&str] {
+        &["ron"]
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start loading the definitions in `assets/`, if there are any
+fn load_content(mut commands: Commands, asset_server: Res<AssetServer>) {
+    // Native builds can check for the file first; the web has to ask for it
+    if !cfg!(target_arch = "wasm32") && !std::path::Path::new("assets").join(PATH).exists() {
+        info!("No content definitions in assets/, using the built-in ones");
+        return;
+    }
+    commands.insert_resource(ContentFile {
+        handle: asset_server.load(PATH),
+        ready: false,
+        failed: false,
+    });
+}
+
+/// Put the definitions to use whenever they (re)load, or keep the ones in
+/// use if they fail to
+fn watch_content(
+    mut events: MessageReader<AssetEvent<Content>>,
+    asset_server: Res<AssetServer>,
+    assets: Res<Assets<Content>>,
+    file: Option<ResMut<ContentFile>>,
+    mut content: ResMut<Content>,
+) {
+    let Some(mut file) = file else {
+        return;
+    };
+
+    for event in events.read() {
+        if let AssetEvent::LoadedWithDependencies { id } | AssetEvent::Modified { id } = event
+            && *id == file.handle.id()
+            && let Some(loaded) = assets.get(*id)
+        {
+            *content = loaded.clone();
+            file.ready = true;
+            info!("Loaded content definitions {}", PATH);
+        }
+    }
+
+    if !file.ready && !file.failed && asset_server.load_state(&file.handle).is_failed() {
+        file.failed = true;
+        warn!(
+            "Couldn't load content definitions {}, keeping the ones in use",
+            PATH
+        );
+    }
+}
+
+/// Whether the definitions in `assets/` have loaded, or failed to, so the
+/// game can start
+pub fn content_settled(file: Option<Res<ContentFile>>) -> bool {
+    file.is_none_or(|file| file.ready || file.failed)
+}
@@ -0,0 +1,324 @@
+//! Keyboard, mouse and gamepad controls for the simulation.
+//!
+//! The simulation only takes player input as `PlayerAction`s and settings
+//! resources, so it can run headless with nothing pressing keys. This is
+//! where the graphical game turns what the player presses into those: pause
+//! and speed, single steps, painting pheromones, designating tiles, moving
+//! the nest and toggling the overlays.
+
+use bevy::prelude::*;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::air::AirOverlaySettings;
+use crate::ants::{Ant, Caste, GridPosition};
+use crate::cave_ins::SupportOverlaySettings;
+use crate::cursor::CursorTile;
+use crate::designations::DesignationKind;
+use crate::moisture::MoistureOverlaySettings;
+use crate::pheromones::{
+    PLACE_AMOUNT, PheromoneOverlaySettings, SelectedPheromoneType, TrailPruning,
+};
+use crate::sandbox::sandbox_tool_active;
+use crate::time_controls::{SPEED_PRESETS, SimulationSpeed, toggle_paused, tune_ultra};
+use crate::{GameState, in_game, step};
+
+pub struct ControlsPlugin;
+
+impl Plugin for ControlsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                toggle_pause,
+                step_tick.run_if(in_state(GameState::Paused)),
+                change_speed.before(tune_ultra),
+            ),
+        )
+        .add_systems(
+            Update,
+            (
+                pheromone_input.run_if(in_game.and(not(sandbox_tool_active))),
+                designation_input.run_if(in_game),
+                relocation_input.run_if(in_game),
+                debug_spawn_ant,
+            )
+                .in_set(ActionSystems::Input),
+        )
+        .add_systems(
+            Update,
+            (
+                cycle_pheromone_type,
+                toggle_pheromone_overlay,
+                toggle_trail_pruning,
+                toggle_air_overlay,
+                toggle_moisture_overlay,
+                toggle_support_overlay,
+            ),
+        );
+    }
+}
+
+/// Keys for the speed presets, in order
+const PRESET_KEYS: [KeyCode; 5] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+];
+
+// ============================================================================
+// Time
+// ============================================================================
+
+/// Pause or resume with Space
+fn toggle_pause(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    if keyboard.just_pressed(KeyCode::Space) {
+        toggle_paused(current_state.get(), &mut next_state, &mut time);
+    }
+}
+
+/// Run exactly one simulation tick while paused with Shift+. (">")
+fn step_tick(world: &mut World) {
+    let keyboard = world.resource::<ButtonInput<KeyCode>>();
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    if !shift || !keyboard.just_pressed(KeyCode::Period) {
+        return;
+    }
+
+    // Virtual time stays paused, so run the fixed schedule directly rather
+    // than waiting for time to pass
+    step(world);
+    info!("Stepped one tick");
+}
+
+fn change_speed(keyboard: Res<ButtonInput<KeyCode>>, mut speed: ResMut<SimulationSpeed>) {
+    let old_speed = (speed.multiplier, speed.ultra);
+
+    // Minus key (-) to slow down
+    if keyboard.just_pressed(KeyCode::Minus) {
+        speed.slower();
+    }
+
+    // Equals key (=) to speed up
+    if keyboard.just_pressed(KeyCode::Equal) {
+        speed.faster();
+    }
+
+    // Shift+1..5 for the presets, Shift+0 for ultra mode
+    if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        if let Some(preset) = PRESET_KEYS
+            .iter()
+            .position(|key| keyboard.just_pressed(*key))
+        {
+            speed.ultra = false;
+            speed.multiplier = SPEED_PRESETS[preset];
+        }
+        if keyboard.just_pressed(KeyCode::Digit0) {
+            speed.ultra = !speed.ultra;
+            // Ultra climbs from the fastest preset; leaving it drops back there
+            speed.multiplier = SPEED_PRESETS[SPEED_PRESETS.len() - 1];
+        }
+    }
+
+    if (speed.multiplier, speed.ultra) != old_speed {
+        info!("Speed: {}", speed.label());
+    }
+}
+
+// ============================================================================
+// Actions
+// ============================================================================
+
+/// Handle player pheromone placement via mouse click, Enter, or a gamepad's
+/// south button (A on Xbox pads)
+fn pheromone_input(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    cursor_tile: Res<CursorTile>,
+    selected_type: Res<SelectedPheromoneType>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    let gamepad_held = gamepads
+        .iter()
+        .any(|gamepad| gamepad.pressed(GamepadButton::South));
+    if !mouse_button.pressed(MouseButton::Left)
+        && !keyboard.pressed(KeyCode::Enter)
+        && !gamepad_held
+    {
+        return;
+    }
+
+    let Some((x, y, z)) = cursor_tile.0 else {
+        return;
+    };
+
+    // Add pheromone at this location
+    actions.write(PlayerAction::PlacePheromone {
+        ptype: selected_type.0,
+        x,
+        y,
+        z,
+        amount: PLACE_AMOUNT,
+    });
+}
+
+/// Mark or clear tiles under the cursor with the right mouse button or X
+fn designation_input(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    cursor_tile: Res<CursorTile>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    if !mouse_button.pressed(MouseButton::Right) && !keyboard.pressed(KeyCode::KeyX) {
+        return;
+    }
+
+    let Some((x, y, z)) = cursor_tile.0 else {
+        return;
+    };
+
+    if keyboard.any_pressed([KeyCode::ControlLeft, KeyCode::ControlRight]) {
+        actions.write(PlayerAction::ClearDesignation { x, y, z });
+        return;
+    }
+
+    let kind = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        DesignationKind::Chamber
+    } else if keyboard.any_pressed([KeyCode::AltLeft, KeyCode::AltRight]) {
+        DesignationKind::Ramp
+    } else {
+        DesignationKind::Tunnel
+    };
+    actions.write(PlayerAction::Designate { kind, x, y, z });
+}
+
+/// Pick the entrance under the cursor as the new nest with L
+fn relocation_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    cursor_tile: Res<CursorTile>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyL) {
+        return;
+    }
+
+    if let Some((x, y, z)) = cursor_tile.0 {
+        actions.write(PlayerAction::MoveNest { x, y, z });
+    }
+}
+
+/// Debug: spawn workers with F key
+fn debug_spawn_ant(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    queen_query: Query<&GridPosition, With<Ant>>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyF) {
+        // Find queen position (or any ant if no queen)
+        if let Some(pos) = queen_query.iter().next() {
+            actions.write(PlayerAction::SpawnAnt {
+                caste: Caste::Forager,
+                x: pos.x,
+                y: pos.y,
+                z: pos.z,
+            });
+        }
+    }
+}
+
+// ============================================================================
+// Settings
+// ============================================================================
+
+/// Cycle through pheromone types with Tab key or a gamepad's north button
+fn cycle_pheromone_type(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut selected: ResMut<SelectedPheromoneType>,
+) {
+    let gamepad_pressed = gamepads
+        .iter()
+        .any(|gamepad| gamepad.just_pressed(GamepadButton::North));
+    if keyboard.just_pressed(KeyCode::Tab) || gamepad_pressed {
+        selected.0 = selected.0.next();
+        info!("Selected pheromone: {}", selected.0.name());
+    }
+}
+
+/// Show or hide the pheromone overlay with the O key
+fn toggle_pheromone_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<PheromoneOverlaySettings>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyO) {
+        settings.visible = !settings.visible;
+        info!(
+            "Pheromone overlay {}",
+            if settings.visible { "shown" } else { "hidden" }
+        );
+    }
+}
+
+/// Toggle trail pruning with the P key
+fn toggle_trail_pruning(keyboard: Res<ButtonInput<KeyCode>>, mut pruning: ResMut<TrailPruning>) {
+    if keyboard.just_pressed(KeyCode::KeyP) {
+        pruning.enabled = !pruning.enabled;
+        info!(
+            "Trail pruning {}",
+            if pruning.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+}
+
+/// Show or hide the air quality overlay with the V key
+fn toggle_air_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<AirOverlaySettings>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyV) {
+        settings.visible = !settings.visible;
+        info!(
+            "Air quality overlay {}",
+            if settings.visible { "shown" } else { "hidden" }
+        );
+    }
+}
+
+/// Show or hide the moisture overlay with the M key
+fn toggle_moisture_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<MoistureOverlaySettings>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyM) {
+        settings.visible = !settings.visible;
+        info!(
+            "Moisture overlay {}",
+            if settings.visible { "shown" } else { "hidden" }
+        );
+    }
+}
+
+/// Show or hide the at-risk overlay with the I key
+fn toggle_support_overlay(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut settings: ResMut<SupportOverlaySettings>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyI) {
+        settings.visible = !settings.visible;
+        info!(
+            "Cave-in risk overlay {}",
+            if settings.visible { "shown" } else { "hidden" }
+        );
+    }
+}
@@ -0,0 +1,223 @@
+//! Tracks which world tile the player is pointing at.
+//!
+//! Input systems that act on a tile read `CursorTile` instead of converting
+//! screen coordinates themselves. The tile normally follows the mouse, but
+//! WASD (or a gamepad's left stick or D-pad) moves a grid cursor instead so the
+//! game can be played without a mouse; moving the mouse hands control back to
+//! it.
+
+use bevy::input::InputSystems;
+use bevy::prelude::*;
+use bevy::window::CursorMoved;
+
+use crate::camera::STICK_DEADZONE;
+use crate::sprites;
+use crate::world::{CurrentZLevel, TILE_SIZE, WORLD_SIZE};
+
+pub struct CursorPlugin;
+
+impl Plugin for CursorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<CursorTile>()
+            .init_resource::<KeyboardCursor>()
+            .add_systems(Startup, spawn_cursor_highlight)
+            .add_systems(
+                PreUpdate,
+                (move_keyboard_cursor, update_cursor_tile)
+                    .chain()
+                    .after(InputSystems),
+            )
+            .add_systems(Update, update_cursor_highlight);
+    }
+}
+
+/// Seconds a WASD key must be held before the cursor starts repeating
+const KEY_REPEAT_DELAY: f32 = 0.3;
+/// Seconds between repeated cursor steps while a key is held
+const KEY_REPEAT_INTERVAL: f32 = 0.06;
+
+/// The grid tile under the cursor on the current z-level, if any
+#[derive(Resource, Default)]
+pub struct CursorTile(pub Option<(usize, usize, usize)>);
+
+/// A grid cursor driven by the keyboard
+#[derive(Resource)]
+pub struct KeyboardCursor {
+    pub x: usize,
+    pub y: usize,
+    /// Whether the keyboard cursor (rather than the mouse) picks the tile
+    pub active: bool,
+    /// Seconds the current movement keys have been held
+    held: f32,
+}
+
+impl Default for KeyboardCursor {
+    fn default() -> Self {
+        Self {
+            x: WORLD_SIZE / 2,
+            y: WORLD_SIZE / 2,
+            active: false,
+            held: 0.0,
+        }
+    }
+}
+
+/// Marker for the sprite outlining the tile under the cursor
+#[derive(Component)]
+struct CursorHighlight;
+
+/// Move the keyboard cursor with WASD or a gamepad, repeating while held
+fn move_keyboard_cursor(
+    time: Res<Time<Real>>,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    gamepads: Query<&Gamepad>,
+    mut cursor: ResMut<KeyboardCursor>,
+) {
+    let mut direction = IVec2::ZERO;
+    if keyboard.pressed(KeyCode::KeyW) {
+        direction.y += 1;
+    }
+    if keyboard.pressed(KeyCode::KeyS) {
+        direction.y -= 1;
+    }
+    if keyboard.pressed(KeyCode::KeyA) {
+        direction.x -= 1;
+    }
+    if keyboard.pressed(KeyCode::KeyD) {
+        direction.x += 1;
+    }
+    for gamepad in &gamepads {
+        let stick = gamepad.left_stick() + gamepad.dpad();
+        if stick.x.abs() > STICK_DEADZONE {
+            direction.x += stick.x.signum() as i32;
+        }
+        if stick.y.abs() > STICK_DEADZONE {
+            direction.y += stick.y.signum() as i32;
+        }
+    }
+    let direction = direction.clamp(IVec2::NEG_ONE, IVec2::ONE);
+
+    if direction == IVec2::ZERO {
+        cursor.held = 0.0;
+        return;
+    }
+
+    // A stick has no press, so any start of movement steps at once
+    let just_pressed = cursor.held == 0.0
+        || keyboard.any_just_pressed([KeyCode::KeyW, KeyCode::KeyS, KeyCode::KeyA, KeyCode::KeyD]);
+    let previous = cursor.held;
+    cursor.held += time.delta_secs();
+
+    // Step once on press, then repeat after a short delay
+    let repeat_steps = |held: f32| ((held - KEY_REPEAT_DELAY) / KEY_REPEAT_INTERVAL).floor();
+    let should_step = just_pressed
+        || (cursor.held >= KEY_REPEAT_DELAY
+            && (previous < KEY_REPEAT_DELAY || repeat_steps(cursor.held) > repeat_steps(previous)));
+    if !should_step {
+        return;
+    }
+
+    cursor.active = true;
+    let max = WORLD_SIZE as i32 - 1;
+    cursor.x = (cursor.x as i32 + direction.x).clamp(0, max) as usize;
+    cursor.y = (cursor.y as i32 + direction.y).clamp(0, max) as usize;
+}
+
+/// Work out which tile is being pointed at, from the keyboard cursor or the mouse
+fn update_cursor_tile(
+    windows: Query<&Window>,
+    camera_query: Query<(&Camera, &GlobalTransform)>,
+    current_z: Res<CurrentZLevel>,
+    mut mouse_moves: MessageReader<CursorMoved>,
+    mut keyboard_cursor: ResMut<KeyboardCursor>,
+    mut cursor_tile: ResMut<CursorTile>,
+) {
+    // Touching the mouse hands control back to it
+    if mouse_moves.read().count() > 0 {
+        keyboard_cursor.active = false;
+    }
+
+    if keyboard_cursor.active {
+        cursor_tile.0 = Some((keyboard_cursor.x, keyboard_cursor.y, current_z.0));
+        return;
+    }
+
+    cursor_tile.0 = None;
+
+    let Ok(window) = windows.single() else {
+        return;
+    };
+
+    let Ok((camera, camera_transform)) = camera_query.single() else {
+        return;
+    };
+
+    let Some(cursor_pos) = window.cursor_position() else {
+        return;
+    };
+
+    let Some((x, y)) = screen_to_tile(camera, camera_transform, cursor_pos) else {
+        return;
+    };
+
+    // Keep the keyboard cursor where the mouse left off
+    keyboard_cursor.x = x;
+    keyboard_cursor.y = y;
+
+    cursor_tile.0 = Some((x, y, current_z.0));
+}
+
+/// The grid column under a point on the screen, if it's inside the world
+pub fn screen_to_tile(
+    camera: &Camera,
+    camera_transform: &GlobalTransform,
+    screen_pos: Vec2,
+) -> Option<(usize, usize)> {
+    // Convert screen position to world position
+    let world_pos = camera
+        .viewport_to_world_2d(camera_transform, screen_pos)
+        .ok()?;
+
+    // Convert world position to grid position
+    let grid_x = ((world_pos.x / TILE_SIZE) + (WORLD_SIZE as f32 / 2.0)).floor() as i32;
+    let grid_y = ((world_pos.y / TILE_SIZE) + (WORLD_SIZE as f32 / 2.0)).floor() as i32;
+
+    // Bounds check
+    if grid_x < 0 || grid_x >= WORLD_SIZE as i32 || grid_y < 0 || grid_y >= WORLD_SIZE as i32 {
+        return None;
+    }
+    Some((grid_x as usize, grid_y as usize))
+}
+
+/// Spawn the (initially hidden) tile highlight
+fn spawn_cursor_highlight(mut commands: Commands) {
+    commands.spawn((
+        CursorHighlight,
+        Sprite {
+            color: sprites::ui::CURSOR,
+            custom_size: Some(Vec2::splat(TILE_SIZE)),
+            ..default()
+        },
+        Transform::from_xyz(0.0, 0.0, 1.5), // Above ants
+        Visibility::Hidden,
+    ));
+}
+
+/// Keep the highlight on the tile being pointed at
+fn update_cursor_highlight(
+    cursor_tile: Res<CursorTile>,
+    mut query: Query<(&mut Transform, &mut Visibility), With<CursorHighlight>>,
+) {
+    let Ok((mut transform, mut visibility)) = query.single_mut() else {
+        return;
+    };
+
+    match cursor_tile.0 {
+        Some((x, y, _)) => {
+            transform.translation.x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+            transform.translation.y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+            *visibility = Visibility::Visible;
+        }
+        None => *visibility = Visibility::Hidden,
+    }
+}
@@ -0,0 +1,219 @@
+//! Dig designations: tiles the player has marked for excavation.
+//!
+//! Pheromones nudge ants toward an area; designations say exactly which tiles
+//! to dig and whether they become tunnels, ramps or chambers. Right-click (or
+//! X) marks a tunnel, adding Shift marks a chamber, adding Alt marks a ramp
+//! between z-levels, and adding Ctrl clears a mark.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::pathfinding::Coord;
+use crate::sprites;
+use crate::world::{CurrentZLevel, TILE_SIZE, TileKind, WORLD_SIZE, WorldGrid};
+use crate::{ResetRun, reset_resource};
+
+pub struct DesignationsPlugin;
+
+impl Plugin for DesignationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Designations>()
+            .add_systems(Startup, spawn_designation_overlay)
+            .add_systems(
+                Update,
+                (
+                    apply_designation_actions.in_set(ActionSystems::Apply),
+                    update_designation_overlay,
+                )
+                    .chain(),
+            )
+            .add_systems(ResetRun, reset_resource::<Designations>);
+    }
+}
+
+// ============================================================================
+// Designation Types
+// ============================================================================
+
+/// What a designated tile should be dug into
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum DesignationKind {
+    Tunnel,
+    Ramp,
+    Chamber,
+}
+
+impl DesignationKind {
+    /// The tile this designation leaves behind once dug
+    pub fn tile(&self) -> TileKind {
+        match self {
+            DesignationKind::Tunnel => TileKind::Tunnel,
+            DesignationKind::Ramp => TileKind::Ramp,
+            DesignationKind::Chamber => TileKind::Chamber,
+        }
+    }
+
+    pub fn color(&self) -> Color {
+        match self {
+            DesignationKind::Tunnel => sprites::designations::TUNNEL,
+            DesignationKind::Ramp => sprites::designations::RAMP,
+            DesignationKind::Chamber => sprites::designations::CHAMBER,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            DesignationKind::Tunnel => "Tunnel",
+            DesignationKind::Ramp => "Ramp",
+            DesignationKind::Chamber => "Chamber",
+        }
+    }
+
+    /// Look up a designation kind by its display name
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "Tunnel" => Some(DesignationKind::Tunnel),
+            "Ramp" => Some(DesignationKind::Ramp),
+            "Chamber" => Some(DesignationKind::Chamber),
+            _ => None,
+        }
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Tiles marked for excavation
+#[derive(Resource, Clone, Default)]
+pub struct Designations {
+    pub tiles: BTreeMap<Coord, DesignationKind>,
+}
+
+impl Designations {
+    /// Designated kind at a tile, if any
+    pub fn get(&self, x: usize, y: usize, z: usize) -> Option<DesignationKind> {
+        self.tiles.get(&(x, y, z)).copied()
+    }
+
+    /// Take a designation off a tile once it's been dug
+    pub fn complete(&mut self, x: usize, y: usize, z: usize) -> Option<DesignationKind> {
+        self.tiles.remove(&(x, y, z))
+    }
+
+    /// Nearest designation an ant could start digging: the tile must still be
+    /// diggable and border a tile ants can stand on
+    pub fn nearest_workable(&self, world_grid: &WorldGrid, from: Coord) -> Option<Coord> {
+        self.tiles
+            .keys()
+            .copied()
+            .filter(|&(x, y, z)| world_grid.tiles[z][y][x].is_diggable())
+            .filter(|&coord| dig_approach(world_grid, coord).is_some())
+            .min_by_key(|&(x, y, z)| x.abs_diff(from.0) + y.abs_diff(from.1) + z.abs_diff(from.2))
+    }
+}
+
+/// A passable tile next to `target` that a digger can stand on
+pub fn dig_approach(world_grid: &WorldGrid, target: Coord) -> Option<Coord> {
+    const FACES: [(i32, i32, i32); 6] = [
+        (0, 0, 1),
+        (1, 0, 0),
+        (-1, 0, 0),
+        (0, 1, 0),
+        (0, -1, 0),
+        (0, 0, -1),
+    ];
+
+    FACES.iter().find_map(|&(dx, dy, dz)| {
+        let x = target.0 as i32 + dx;
+        let y = target.1 as i32 + dy;
+        let z = target.2 as i32 + dz;
+        let range = 0..WORLD_SIZE as i32;
+        if !(range.contains(&x) && range.contains(&y) && range.contains(&z)) {
+            return None;
+        }
+        let (x, y, z) = (x as usize, y as usize, z as usize);
+        world_grid.tiles[z][y][x].is_passable().then_some((x, y, z))
+    })
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for designation overlay sprites
+#[derive(Component)]
+struct DesignationOverlay {
+    x: usize,
+    y: usize,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Apply designations from live or replayed input. Only soil can be designated.
+fn apply_designation_actions(
+    mut actions: MessageReader<PlayerAction>,
+    world_grid: Res<WorldGrid>,
+    mut designations: ResMut<Designations>,
+) {
+    for action in actions.read() {
+        match *action {
+            PlayerAction::Designate { kind, x, y, z }
+                if world_grid.tiles[z][y][x].is_diggable() =>
+            {
+                designations.tiles.insert((x, y, z), kind);
+            }
+            PlayerAction::ClearDesignation { x, y, z } => {
+                designations.tiles.remove(&(x, y, z));
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Spawn overlay sprites for designated tiles
+fn spawn_designation_overlay(mut commands: Commands) {
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let world_x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+            let world_y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+
+            commands.spawn((
+                Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::splat(TILE_SIZE * 0.6)),
+                    ..default()
+                },
+                Transform::from_xyz(world_x, world_y, 0.6), // Above pheromones, below ants
+                DesignationOverlay { x, y },
+                Visibility::Hidden,
+            ));
+        }
+    }
+}
+
+/// Show designations on the current z-level
+fn update_designation_overlay(
+    designations: Res<Designations>,
+    current_z: Res<CurrentZLevel>,
+    mut query: Query<(&DesignationOverlay, &mut Sprite, &mut Visibility)>,
+) {
+    if !designations.is_changed() && !current_z.is_changed() {
+        return;
+    }
+
+    let z = current_z.0;
+    for (overlay, mut sprite, mut visibility) in &mut query {
+        match designations.get(overlay.x, overlay.y, z) {
+            Some(kind) => {
+                sprite.color = kind.color();
+                *visibility = Visibility::Visible;
+            }
+            None => *visibility = Visibility::Hidden,
+        }
+    }
+}
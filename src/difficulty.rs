@@ -0,0 +1,120 @@
+//! Difficulty presets.
+//!
+//! The difficulty is picked on the setup screen (H) and can be changed
+//! mid-game with the same key for sandbox play. Besides the starting food, each
+//! preset sets the multipliers in `SimulationConfig` that scale how fast ants
+//! get hungry, how often predators turn up and how many prowl at once, and how
+//! much food the fungus gives.
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::config::SimulationConfig;
+use crate::in_game;
+use crate::notifications::Notification;
+
+pub struct DifficultyPlugin;
+
+impl Plugin for DifficultyPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Difficulty>().add_systems(
+            Update,
+            (
+                difficulty_input.run_if(in_game),
+                apply_difficulty.run_if(resource_changed::<Difficulty>),
+            )
+                .chain(),
+        );
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// How forgiving the game is
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
+pub enum Difficulty {
+    /// A full larder and a gentle world to learn the ropes with
+    Easy,
+    #[default]
+    Normal,
+    /// Barely enough food to get the first garden going, and hungry spiders
+    Hard,
+}
+
+impl Difficulty {
+    pub const ALL: [Difficulty; 3] = [Difficulty::Easy, Difficulty::Normal, Difficulty::Hard];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Difficulty::Easy => "Easy",
+            Difficulty::Normal => "Normal",
+            Difficulty::Hard => "Hard",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|difficulty| difficulty.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The next difficulty in the list, wrapping around
+    pub fn next(&self) -> Self {
+        match self {
+            Difficulty::Easy => Difficulty::Normal,
+            Difficulty::Normal => Difficulty::Hard,
+            Difficulty::Hard => Difficulty::Easy,
+        }
+    }
+
+    /// Food in the stores when the colony is founded
+    pub fn starting_food(&self) -> u32 {
+        match self {
+            Difficulty::Easy => 20,
+            Difficulty::Normal => 10,
+            Difficulty::Hard => 4,
+        }
+    }
+
+    /// Set the difficulty multipliers in the simulation config
+    pub fn apply(&self, config: &mut SimulationConfig) {
+        let (hunger_rate, predator_frequency, fungus_yield, raid_strength) = match self {
+            Difficulty::Easy => (0.75, 0.5, 1.25, 1),
+            Difficulty::Normal => (1.0, 1.0, 1.0, 2),
+            Difficulty::Hard => (1.25, 2.0, 0.8, 4),
+        };
+        config.hunger_rate = hunger_rate;
+        config.predator_frequency = predator_frequency;
+        config.fungus_yield = fungus_yield;
+        config.raid_strength = raid_strength;
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Cycle the difficulty mid-game with H
+fn difficulty_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut difficulty: ResMut<Difficulty>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyH) {
+        return;
+    }
+
+    *difficulty = difficulty.next();
+    notifications.write(Notification::info(format!(
+        "Difficulty set to {}",
+        difficulty.name()
+    )));
+}
+
+/// Retune the simulation whenever the difficulty changes
+fn apply_difficulty(difficulty: Res<Difficulty>, mut config: ResMut<SimulationConfig>) {
+    difficulty.apply(&mut config);
+    info!("Difficulty: {} ({:?})", difficulty.name(), *config);
+}
@@ -0,0 +1,303 @@
+//! World editor: sculpt a map to play on.
+//!
+//! Map editor (M) on the title screen opens the world editor over a freshly
+//! generated world, with time stopped and the ants cleared away. Pick a tool
+//! with the number keys and left click on the current z-level (the usual keys
+//! move between levels): 1-6 paint dirt, tunnel, chamber, ramp, rock or bare
+//! surface (hold the button to paint a stroke), 7-9 place a tree, bush or tuft
+//! of grass on the surface (click one again to clear it), and 0 sets where the
+//! colony starts. F5 saves the world to `maps/` (see `maps.rs`), Enter saves
+//! it and goes on to the setup screen to play it, and Esc goes back to the
+//! title screen.
+//!
+//! Edits change the world directly rather than going through `PlayerAction`:
+//! nothing is being simulated, so there's nothing to record.
+
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::ants::{Ant, NestLocation};
+use crate::biome::Biome;
+use crate::content::Content;
+use crate::cursor::CursorTile;
+use crate::maps::{save_map, write_map};
+use crate::menu::{GameSession, new_world};
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::world::{
+    FungusGarden, Plant, PlantKind, SURFACE_LEVEL, TileKind, WorldGrid, WorldOptions, clear_tree,
+    plant_tree,
+};
+
+pub struct EditorPlugin;
+
+impl Plugin for EditorPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EditorTool>()
+            .add_systems(
+                OnEnter(GameState::Editor),
+                (
+                    new_world.run_if(|session: Res<GameSession>| session.played),
+                    enter_editor,
+                )
+                    .chain(),
+            )
+            .add_systems(OnExit(GameState::Editor), exit_editor)
+            .add_systems(
+                Update,
+                (editor_input, editor_paint, update_editor_panel)
+                    .chain()
+                    .run_if(in_state(GameState::Editor)),
+            );
+    }
+}
+
+/// Tools on the number keys, 1 through 9 then 0
+const TOOLS: [EditorTool; 10] = [
+    EditorTool::Paint(TileKind::Dirt),
+    EditorTool::Paint(TileKind::Tunnel),
+    EditorTool::Paint(TileKind::Chamber),
+    EditorTool::Paint(TileKind::Ramp),
+    EditorTool::Paint(TileKind::Rock),
+    EditorTool::Paint(TileKind::Surface),
+    EditorTool::Plant(PlantKind::Tree),
+    EditorTool::Plant(PlantKind::Bush),
+    EditorTool::Plant(PlantKind::Grass),
+    EditorTool::Nest,
+];
+const TOOL_KEYS: [KeyCode; 10] = [
+    KeyCode::Digit1,
+    KeyCode::Digit2,
+    KeyCode::Digit3,
+    KeyCode::Digit4,
+    KeyCode::Digit5,
+    KeyCode::Digit6,
+    KeyCode::Digit7,
+    KeyCode::Digit8,
+    KeyCode::Digit9,
+    KeyCode::Digit0,
+];
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// What a left click does in the editor
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq)]
+enum EditorTool {
+    /// Turn the tile under the cursor into this kind
+    Paint(TileKind),
+    /// Place or clear a plant on the surface
+    Plant(PlantKind),
+    /// Start the colony here
+    Nest,
+}
+
+impl Default for EditorTool {
+    fn default() -> Self {
+        TOOLS[0]
+    }
+}
+
+impl EditorTool {
+    fn name(&self) -> &'static str {
+        match self {
+            EditorTool::Paint(kind) => kind.name(),
+            EditorTool::Plant(kind) => kind.name(),
+            EditorTool::Nest => "nest",
+        }
+    }
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the editor's help panel
+#[derive(Component)]
+struct EditorPanel;
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Stop time, clear the ants away and show the help panel
+fn enter_editor(
+    mut commands: Commands,
+    ant_query: Query<Entity, With<Ant>>,
+    mut session: ResMut<GameSession>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    time.pause();
+    for entity in &ant_query {
+        commands.entity(entity).despawn();
+    }
+    // Whatever's made here, the next game starts over
+    session.resume = None;
+    session.played = true;
+
+    commands.spawn((
+        EditorPanel,
+        Text::new(""),
+        TextFont {
+            font_size: 14.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Px(10.0),
+            bottom: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+    ));
+}
+
+fn exit_editor(mut commands: Commands, panel_query: Query<Entity, With<EditorPanel>>) {
+    for entity in &panel_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Pick tools, save, play or leave
+#[allow(clippy::too_many_arguments)]
+fn editor_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    world_grid: Res<WorldGrid>,
+    biome: Res<Biome>,
+    nest: Res<NestLocation>,
+    mut tool: ResMut<EditorTool>,
+    mut options: ResMut<WorldOptions>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if let Some(index) = TOOL_KEYS.iter().position(|key| keyboard.just_pressed(*key)) {
+        *tool = TOOLS[index];
+    }
+
+    if keyboard.just_pressed(KeyCode::Escape) {
+        next_state.set(GameState::MainMenu);
+        return;
+    }
+
+    let play = keyboard.just_pressed(KeyCode::Enter);
+    if !(play || keyboard.just_pressed(KeyCode::F5)) {
+        return;
+    }
+    match save_map(&write_map(&world_grid, *biome, (nest.x, nest.y))) {
+        Ok(path) => {
+            notifications.write(Notification::info(format!(
+                "Saved map to {}",
+                path.display()
+            )));
+            if play {
+                // The setup screen loads it from the file like any other map
+                options.map = Some(path);
+                next_state.set(GameState::Setup);
+            }
+        }
+        Err(e) => {
+            warn!("Failed to save map: {}", e);
+            notifications.write(Notification::warning("Couldn't save the map"));
+        }
+    }
+}
+
+/// Use the tool in hand on the tile under the cursor: paint while the button
+/// is held, place plants and the nest once per click
+#[allow(clippy::too_many_arguments)]
+fn editor_paint(
+    mut commands: Commands,
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    cursor_tile: Res<CursorTile>,
+    tool: Res<EditorTool>,
+    plant_query: Query<(Entity, &Plant)>,
+    biome: Res<Biome>,
+    content: Res<Content>,
+    mut world_grid: ResMut<WorldGrid>,
+    mut fungus_garden: ResMut<FungusGarden>,
+    mut nest: ResMut<NestLocation>,
+    mut rng: ResMut<SimRng>,
+) {
+    let Some((x, y, z)) = cursor_tile.0 else {
+        return;
+    };
+
+    match *tool {
+        EditorTool::Paint(kind) if mouse_button.pressed(MouseButton::Left) => {
+            // Plants own their tiles; clear them with their own tool
+            let tile = world_grid.tiles[z][y][x];
+            if tile != kind && !tile.is_plant() {
+                world_grid.tiles[z][y][x] = kind;
+                fungus_garden.plots.remove(&(x, y, z));
+            }
+        }
+        EditorTool::Plant(kind) if mouse_button.just_pressed(MouseButton::Left) => {
+            let existing = plant_query
+                .iter()
+                .find(|(_, plant)| (plant.x, plant.y) == (x, y));
+            if let Some((entity, plant)) = existing {
+                if plant.kind == PlantKind::Tree {
+                    clear_tree(&mut world_grid, x, y);
+                } else {
+                    world_grid.tiles[SURFACE_LEVEL + 1][y][x] = TileKind::Air;
+                }
+                commands.entity(entity).despawn();
+                return;
+            }
+
+            // Plants need open ground to stand on
+            if world_grid.tiles[SURFACE_LEVEL][y][x] != TileKind::Surface
+                || world_grid.tiles[SURFACE_LEVEL + 1][y][x] != TileKind::Air
+            {
+                return;
+            }
+            if kind == PlantKind::Tree {
+                let leaves = content.leaf_source(PlantKind::Tree, *biome);
+                plant_tree(&mut commands, &mut world_grid, &mut *rng, x, y, leaves);
+            } else {
+                world_grid.tiles[SURFACE_LEVEL + 1][y][x] = kind.tile();
+                commands.spawn((Plant { kind, x, y }, content.leaf_source(kind, *biome)));
+            }
+        }
+        EditorTool::Nest if mouse_button.just_pressed(MouseButton::Left) => {
+            nest.x = x;
+            nest.y = y;
+        }
+        _ => {}
+    }
+}
+
+/// Show the tool in hand, the nest and the keys
+fn update_editor_panel(
+    tool: Res<EditorTool>,
+    nest: Res<NestLocation>,
+    mut panel_query: Query<&mut Text, With<EditorPanel>>,
+) {
+    if !(tool.is_changed() || nest.is_changed()) {
+        return;
+    }
+
+    let tools: Vec<String> = TOOLS
+        .iter()
+        .enumerate()
+        .map(|(index, t)| {
+            let key = (index + 1) % 10;
+            if t == &*tool {
+                format!("[{}: {}]", key, t.name())
+            } else {
+                format!("{}: {}", key, t.name())
+            }
+        })
+        .collect();
+    for mut text in &mut panel_query {
+        **text = format!(
+            "=== Map editor ===\n{}\nNest: ({}, {})\nLeft click: use tool | F5: save | Enter: save and play | Esc: title screen",
+            tools.join("  "),
+            nest.x,
+            nest.y
+        );
+    }
+}
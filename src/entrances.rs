@@ -0,0 +1,183 @@
+//! Nest entrances.
+//!
+//! Wherever a ramp climbs all the way up to the surface, the surface tile
+//! above it becomes an entrance. Foragers head for the nearest one with their
+//! loads, so a second entrance out by the trees shortens every trip, but each
+//! entrance is another hole to defend: soldiers take up posts at them, and
+//! spiders go for the ones left unguarded. New entrances are only opened
+//! where the player marks a ramp just under the surface.
+
+use bevy::prelude::*;
+
+use crate::ants::{Ant, Caste, GridPosition, Task};
+use crate::notifications::Notification;
+use crate::pathfinding::Coord;
+use crate::time_controls::SimulationSet;
+use crate::world::{SURFACE_LEVEL, TileKind, WORLD_SIZE, WorldGrid};
+use crate::{ResetRun, reset_resource};
+
+pub struct EntrancePlugin;
+
+impl Plugin for EntrancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Entrances>()
+            .add_systems(
+                FixedUpdate,
+                (
+                    find_entrances.run_if(resource_changed::<WorldGrid>),
+                    count_guards,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, reset_resource::<Entrances>);
+    }
+}
+
+/// Soldiers posted to each entrance before the rest go about other work
+const GUARDS_PER_ENTRANCE: usize = 2;
+/// How close a guard has to be to an entrance to be at their post
+const POST_RADIUS: usize = 1;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// A hole in the surface leading down into the nest
+#[derive(Debug, Clone, Copy)]
+pub struct Entrance {
+    pub x: usize,
+    pub y: usize,
+    /// Soldiers assigned to guard it
+    pub guards: usize,
+    /// Whether any of them is at their post right now
+    pub manned: bool,
+}
+
+/// Every entrance to the nest, in grid order
+#[derive(Resource, Default)]
+pub struct Entrances {
+    pub list: Vec<Entrance>,
+}
+
+impl Entrances {
+    /// Surface tile of the entrance closest to a column, or the founding
+    /// spot at the center if every entrance has been filled in
+    pub fn nearest(&self, x: usize, y: usize) -> Coord {
+        self.list
+            .iter()
+            .min_by_key(|entrance| entrance.x.abs_diff(x) + entrance.y.abs_diff(y))
+            .map(|entrance| (entrance.x, entrance.y, SURFACE_LEVEL))
+            .unwrap_or((WORLD_SIZE / 2, WORLD_SIZE / 2, SURFACE_LEVEL))
+    }
+
+    /// The entrance with the fewest guards, if any still needs one
+    pub fn short_handed(&self) -> Option<(usize, usize)> {
+        self.list
+            .iter()
+            .filter(|entrance| entrance.guards < GUARDS_PER_ENTRANCE)
+            .min_by_key(|entrance| entrance.guards)
+            .map(|entrance| (entrance.x, entrance.y))
+    }
+
+    /// Whether there's an entrance at a surface column
+    pub fn contains(&self, x: usize, y: usize) -> bool {
+        self.list
+            .iter()
+            .any(|entrance| (entrance.x, entrance.y) == (x, y))
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Open an entrance over every ramp reaching the top underground layer, and
+/// close any whose ramp has been filled in
+fn find_entrances(
+    mut world_grid: ResMut<WorldGrid>,
+    mut entrances: ResMut<Entrances>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let top = SURFACE_LEVEL - 1;
+    let mut changes = Vec::new();
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let ramp_below = world_grid.tiles[top][y][x] == TileKind::Ramp;
+            match world_grid.tiles[SURFACE_LEVEL][y][x] {
+                TileKind::Surface if ramp_below => changes.push((x, y, TileKind::Entrance)),
+                TileKind::Entrance if !ramp_below => changes.push((x, y, TileKind::Surface)),
+                _ => {}
+            }
+        }
+    }
+
+    // Only touch the grid when something changed, so this doesn't retrigger
+    // itself every tick
+    for &(x, y, tile) in &changes {
+        world_grid.tiles[SURFACE_LEVEL][y][x] = tile;
+    }
+
+    let previous = entrances.list.len();
+    entrances.list.clear();
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            if world_grid.tiles[SURFACE_LEVEL][y][x] == TileKind::Entrance {
+                entrances.list.push(Entrance {
+                    x,
+                    y,
+                    guards: 0,
+                    manned: false,
+                });
+            }
+        }
+    }
+
+    let count = entrances.list.len();
+    if count != previous {
+        info!("The nest has {} entrances", count);
+    }
+    if previous > 0 && count > previous {
+        notifications.write(Notification::info(format!(
+            "A new entrance has opened - the nest now has {}",
+            count
+        )));
+    } else if count < previous {
+        notifications.write(Notification::warning("A nest entrance has been filled in"));
+    }
+}
+
+/// Tally the soldiers assigned to each entrance and whether any are at
+/// their post
+fn count_guards(
+    ant_query: Query<(&GridPosition, &Caste, &Task), With<Ant>>,
+    mut entrances: ResMut<Entrances>,
+) {
+    for entrance in &mut entrances.list {
+        entrance.guards = 0;
+        entrance.manned = false;
+    }
+
+    for (pos, caste, task) in &ant_query {
+        let Task::Guarding { x, y } = *task else {
+            continue;
+        };
+        if *caste != Caste::Soldier {
+            continue;
+        }
+        let Some(entrance) = entrances
+            .list
+            .iter_mut()
+            .find(|entrance| (entrance.x, entrance.y) == (x, y))
+        else {
+            continue;
+        };
+        entrance.guards += 1;
+        if pos.z == SURFACE_LEVEL
+            && pos.x.abs_diff(x) <= POST_RADIUS
+            && pos.y.abs_diff(y) <= POST_RADIUS
+        {
+            entrance.manned = true;
+        }
+    }
+}
@@ -0,0 +1,410 @@
+//! In-game event log.
+//!
+//! Everything the simulation logs (a tunnel dug, a leaf delivered, an egg
+//! laid, a warning about the stores) is caught on its way to the console and
+//! kept in a scrollable panel, stamped with the in-game day and time and
+//! labelled with the part of the game it came from. J shows the panel,
+//! Shift+J cycles between all events, warnings and up, and errors only, and
+//! PageUp/PageDown scroll back through it.
+//!
+//! The console itself only shows the game's warnings and errors now, so it
+//! stays readable; `--log <filter>` sets what it shows using the usual
+//! `RUST_LOG` directives, e.g. `--log acre=info` for everything as before.
+
+use std::collections::VecDeque;
+use std::fmt;
+use std::sync::{Arc, Mutex};
+
+use bevy::log::tracing::field::{Field, Visit};
+use bevy::log::tracing::{Event, Level, Subscriber};
+use bevy::log::tracing_subscriber::layer::Context;
+use bevy::log::tracing_subscriber::{EnvFilter, Layer, fmt as tracing_fmt};
+use bevy::log::{BoxedFmtLayer, BoxedLayer};
+use bevy::prelude::*;
+
+use crate::cli::CliArgs;
+use crate::sprites;
+use crate::time_controls::{SimulationTick, TICKS_PER_DAY};
+use crate::{ResetRun, in_game};
+
+pub struct EventLogPlugin;
+
+impl Plugin for EventLogPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<EventLog>()
+            .add_systems(Startup, setup_event_log_panel)
+            .add_systems(
+                Update,
+                (
+                    collect_events,
+                    event_log_input.run_if(in_game),
+                    update_event_log_panel,
+                )
+                    .chain(),
+            )
+            .add_systems(ResetRun, clear_log);
+    }
+}
+
+/// Events kept before the oldest are dropped
+const MAX_EVENTS: usize = 500;
+/// Rows shown in the panel at once
+const VISIBLE_ROWS: usize = 18;
+/// Console filter unless `--log` says otherwise: the game's own warnings and
+/// errors, and Bevy's usual startup information
+const DEFAULT_CONSOLE_FILTER: &str = "info,wgpu=error,naga=warn,acre=warn";
+/// Console filter for a soak with a window, which logs its samples
+const SOAK_CONSOLE_FILTER: &str = "info,wgpu=error,naga=warn";
+
+/// How serious an event is
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord)]
+pub enum Severity {
+    Info,
+    Warning,
+    Error,
+}
+
+impl Severity {
+    fn from_level(level: &Level) -> Option<Self> {
+        match *level {
+            Level::INFO => Some(Severity::Info),
+            Level::WARN => Some(Severity::Warning),
+            Level::ERROR => Some(Severity::Error),
+            _ => None,
+        }
+    }
+
+    fn color(self) -> Color {
+        match self {
+            Severity::Info => sprites::ui::TEXT,
+            Severity::Warning => sprites::ui::HIGHLIGHT,
+            Severity::Error => Color::srgb(1.0, 0.4, 0.4),
+        }
+    }
+
+    /// Label for the panel's filter
+    fn filter_name(self) -> &'static str {
+        match self {
+            Severity::Info => "all events",
+            Severity::Warning => "warnings and errors",
+            Severity::Error => "errors only",
+        }
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// An event as it arrives from the logger, not yet stamped with a tick
+struct RawEvent {
+    severity: Severity,
+    category: String,
+    text: String,
+}
+
+/// Events caught by the logging layer, waiting to be stamped and stored
+#[derive(Resource, Clone, Default)]
+struct EventQueue(Arc<Mutex<Vec<RawEvent>>>);
+
+/// One entry in the log
+pub struct LoggedEvent {
+    pub tick: u64,
+    pub severity: Severity,
+    /// The part of the game it came from, e.g. "ants" or "brood"
+    pub category: String,
+    pub text: String,
+}
+
+/// The event log, oldest first, and how the panel is showing it
+#[derive(Resource)]
+pub struct EventLog {
+    pub events: VecDeque<LoggedEvent>,
+    /// Least serious events shown
+    pub min_severity: Severity,
+    /// Rows scrolled back from the newest
+    scroll: usize,
+}
+
+impl Default for EventLog {
+    fn default() -> Self {
+        Self {
+            events: VecDeque::with_capacity(MAX_EVENTS),
+            min_severity: Severity::Info,
+            scroll: 0,
+        }
+    }
+}
+
+// ============================================================================
+// Logging layers
+// ============================================================================
+
+/// Logging layer for `LogPlugin::custom_layer` that catches the game's own
+/// events for the log
+pub fn event_log_layer(app: &mut App) -> Option<BoxedLayer> {
+    let queue = EventQueue::default();
+    app.insert_resource(queue.clone());
+    Some(Box::new(EventLogLayer(queue)))
+}
+
+/// Console layer for `LogPlugin::fmt_layer`, filtered by `--log`
+pub fn console_layer(app: &mut App) -> Option<BoxedFmtLayer> {
+    let cli = app.world().get_resource::<CliArgs>();
+    // A soak's daily samples are logged, so keep the game's own information
+    let default = if cli.is_some_and(|cli| cli.soak.is_some()) {
+        SOAK_CONSOLE_FILTER
+    } else {
+        DEFAULT_CONSOLE_FILTER
+    };
+    let directives = cli
+        .and_then(|cli| cli.log.clone())
+        .unwrap_or_else(|| default.to_string());
+    let filter = EnvFilter::try_new(&directives).unwrap_or_else(|e| {
+        eprintln!("Ignoring --log {}: {}", directives, e);
+        EnvFilter::new(default)
+    });
+    Some(Box::new(
+        tracing_fmt::Layer::default()
+            .with_writer(std::io::stderr)
+            .with_filter(filter),
+    ))
+}
+
+struct EventLogLayer(EventQueue);
+
+/// Pulls the message out of a log event
+struct MessageVisitor(String);
+
+impl Visit for MessageVisitor {
+    fn record_debug(&mut self, field: &Field, value: &dyn fmt::Debug) {
+        if field.name() == "message" {
+            self.0 = format!("{:?}", value);
+        }
+    }
+}
+
+impl<S: Subscriber> Layer<S> for EventLogLayer {
+    fn on_event(&self, event: &Event<'_>, _ctx: Context<'_, S>) {
+        let metadata = event.metadata();
+        let Some(module) = metadata.target().strip_prefix("acre::") else {
+            return;
+        };
+        let Some(severity) = Severity::from_level(metadata.level()) else {
+            return;
+        };
+
+        let mut visitor = MessageVisitor(String::new());
+        event.record(&mut visitor);
+        let category = module.split("::").next().unwrap_or(module).to_string();
+        if let Ok(mut queue) = self.0.0.lock() {
+            queue.push(RawEvent {
+                severity,
+                category,
+                text: visitor.0,
+            });
+        }
+    }
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the event log panel root
+#[derive(Component)]
+struct EventLogPanel;
+
+/// Marker for the panel's title line
+#[derive(Component)]
+struct EventLogTitle;
+
+/// Marker for the container holding one row per event
+#[derive(Component)]
+struct EventLogRows;
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Stamp newly caught events with the tick and add them to the log
+fn collect_events(
+    queue: Option<Res<EventQueue>>,
+    tick: Res<SimulationTick>,
+    mut log: ResMut<EventLog>,
+) {
+    let Some(queue) = queue else {
+        return;
+    };
+    let Ok(mut raw) = queue.0.lock() else {
+        return;
+    };
+    if raw.is_empty() {
+        return;
+    }
+
+    for event in raw.drain(..) {
+        if log.events.len() >= MAX_EVENTS {
+            log.events.pop_front();
+        }
+        log.events.push_back(LoggedEvent {
+            tick: tick.0,
+            severity: event.severity,
+            category: event.category,
+            text: event.text,
+        });
+    }
+}
+
+/// Empty the log for a new run, keeping the severity filter
+fn clear_log(mut log: ResMut<EventLog>) {
+    log.events.clear();
+    log.scroll = 0;
+}
+
+/// Spawn the (initially hidden) event log panel on the left
+fn setup_event_log_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            EventLogPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Px(10.0),
+                bottom: Val::Px(60.0),
+                width: Val::Px(460.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                EventLogTitle,
+                Text::new("Event log"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent.spawn((
+                EventLogRows,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(2.0),
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// J shows or hides the log, Shift+J changes the severity shown, and
+/// PageUp/PageDown scroll it
+fn event_log_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut log: ResMut<EventLog>,
+    mut panel_query: Query<&mut Visibility, With<EventLogPanel>>,
+) {
+    if keyboard.just_pressed(KeyCode::KeyJ) {
+        if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+            log.min_severity = match log.min_severity {
+                Severity::Info => Severity::Warning,
+                Severity::Warning => Severity::Error,
+                Severity::Error => Severity::Info,
+            };
+            log.scroll = 0;
+        } else if let Ok(mut visibility) = panel_query.single_mut() {
+            *visibility = match *visibility {
+                Visibility::Hidden => Visibility::Visible,
+                _ => Visibility::Hidden,
+            };
+        }
+    }
+
+    if keyboard.just_pressed(KeyCode::PageUp) {
+        log.scroll += VISIBLE_ROWS / 2;
+    }
+    if keyboard.just_pressed(KeyCode::PageDown) {
+        log.scroll = log.scroll.saturating_sub(VISIBLE_ROWS / 2);
+    }
+}
+
+/// Rebuild the visible rows when the log changes while the panel is showing
+fn update_event_log_panel(
+    mut commands: Commands,
+    mut log: ResMut<EventLog>,
+    panel_query: Query<Ref<Visibility>, With<EventLogPanel>>,
+    mut title_query: Query<&mut Text, With<EventLogTitle>>,
+    rows_query: Query<Entity, With<EventLogRows>>,
+) {
+    let Ok(visibility) = panel_query.single() else {
+        return;
+    };
+    if *visibility != Visibility::Visible || !(log.is_changed() || visibility.is_changed()) {
+        return;
+    }
+
+    let shown: Vec<&LoggedEvent> = log
+        .events
+        .iter()
+        .filter(|event| event.severity >= log.min_severity)
+        .collect();
+    // Don't scroll back past the oldest event
+    let max_scroll = shown.len().saturating_sub(VISIBLE_ROWS);
+    let scroll = log.scroll.min(max_scroll);
+    let end = shown.len() - scroll;
+    let start = end.saturating_sub(VISIBLE_ROWS);
+
+    if let Ok(mut text) = title_query.single_mut() {
+        **text = format!(
+            "Event log: {} ({} of {}){}",
+            log.min_severity.filter_name(),
+            end,
+            shown.len(),
+            if scroll > 0 {
+                "  [PageDown: newer]"
+            } else {
+                ""
+            }
+        );
+    }
+
+    if let Ok(rows) = rows_query.single() {
+        commands
+            .entity(rows)
+            .despawn_related::<Children>()
+            .with_children(|parent| {
+                for event in &shown[start..end] {
+                    spawn_event_row(parent, event);
+                }
+            });
+    }
+
+    if log.scroll != scroll {
+        log.scroll = scroll;
+    }
+}
+
+/// One event: when, where from, and what happened
+fn spawn_event_row(parent: &mut ChildSpawnerCommands, event: &LoggedEvent) {
+    let day = event.tick / TICKS_PER_DAY as u64 + 1;
+    let minutes = (event.tick % TICKS_PER_DAY as u64) * 24 * 60 / TICKS_PER_DAY as u64;
+    parent.spawn((
+        Text::new(format!(
+            "D{} {:02}:{:02}  {}: {}",
+            day,
+            minutes / 60,
+            minutes % 60,
+            event.category,
+            event.text
+        )),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(event.severity.color()),
+    ));
+}
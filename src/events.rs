@@ -0,0 +1,313 @@
+//! Random events.
+//!
+//! Every couple of days something happens to the colony: a storm, a heat
+//! wave, a wave of spiders, a blight on the leaves, or a bumper crop. Which
+//! one is a weighted draw, and the weights depend on the season, so heat waves
+//! come in summer and spiders in summer and autumn.
+//!
+//! Each event is a `RandomEvent`. New ones only need to implement the trait
+//! and be added to `RandomEvents` with `register`; the scheduler does the
+//! rest.
+
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ResetRun;
+use crate::config::SimulationConfig;
+use crate::content::Content;
+use crate::migrations::{MigrantKind, random_edge_tile, random_interior_tile, spawn_migrant};
+use crate::moisture::{SoilMoisture, Weather};
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::time_controls::{Season, SimulationSet, SimulationTick, TICKS_PER_DAY};
+use crate::world::{LeafSource, SURFACE_LEVEL};
+
+pub struct EventsPlugin;
+
+impl Plugin for EventsPlugin {
+    fn build(&self, app: &mut App) {
+        let mut events = RandomEvents::default();
+        events.register(Storm);
+        events.register(HeatWave);
+        events.register(PredatorWave);
+        events.register(LeafBlight);
+        events.register(BumperCrop);
+
+        app.insert_resource(events)
+            .add_systems(FixedUpdate, run_random_events.in_set(SimulationSet))
+            .add_systems(ResetRun, reset_schedule);
+    }
+}
+
+/// Ticks between one event and the next
+const EVENT_INTERVAL: RangeInclusive<u64> = TICKS_PER_DAY as u64..=3 * TICKS_PER_DAY as u64;
+/// How long a storm rains, in ticks
+const STORM_TICKS: u32 = 1500;
+/// Layers of topsoil a heat wave dries out
+const HEAT_WAVE_DEPTH: usize = 3;
+/// Share of its moisture the topsoil keeps through a heat wave
+const HEAT_WAVE_DRYING: f32 = 0.3;
+/// Share of its leaves each plant keeps through a blight
+const BLIGHT_SURVIVAL: f32 = 0.5;
+
+// ============================================================================
+// Events
+// ============================================================================
+
+/// Something that can happen to the colony at random
+pub trait RandomEvent: Send + Sync {
+    fn name(&self) -> &'static str;
+
+    /// How likely the event is to be drawn in a season, relative to the
+    /// others; zero means it can't happen then
+    fn weight(&self, season: Season, content: &Content) -> u32;
+
+    /// Make the event happen and say what happened
+    fn fire(&self, world: &mut World) -> Notification;
+}
+
+/// Hours of heavy rain
+struct Storm;
+
+impl RandomEvent for Storm {
+    fn name(&self) -> &'static str {
+        "Storm"
+    }
+
+    fn weight(&self, season: Season, _content: &Content) -> u32 {
+        match season {
+            Season::Spring | Season::Autumn => 3,
+            Season::Summer => 2,
+            Season::Winter => 1,
+        }
+    }
+
+    fn fire(&self, world: &mut World) -> Notification {
+        let mut weather = world.resource_mut::<Weather>();
+        weather.rain_ticks = weather.rain_ticks.max(STORM_TICKS);
+        Notification::warning("A storm has broken - watch the tunnels for flooding")
+    }
+}
+
+/// A dry spell that bakes the topsoil
+struct HeatWave;
+
+impl RandomEvent for HeatWave {
+    fn name(&self) -> &'static str {
+        "Heat wave"
+    }
+
+    fn weight(&self, season: Season, _content: &Content) -> u32 {
+        match season {
+            Season::Summer => 3,
+            Season::Spring => 1,
+            Season::Autumn | Season::Winter => 0,
+        }
+    }
+
+    fn fire(&self, world: &mut World) -> Notification {
+        world.resource_mut::<Weather>().rain_ticks = 0;
+        let mut moisture = world.resource_mut::<SoilMoisture>();
+        for z in SURFACE_LEVEL - HEAT_WAVE_DEPTH..SURFACE_LEVEL {
+            for row in moisture.level[z].iter_mut() {
+                for level in row.iter_mut() {
+                    *level *= HEAT_WAVE_DRYING;
+                }
+            }
+        }
+        Notification::warning("A heat wave is drying out the topsoil")
+    }
+}
+
+/// A pack of spiders all at once, as many as the difficulty allows
+struct PredatorWave;
+
+impl RandomEvent for PredatorWave {
+    fn name(&self) -> &'static str {
+        "Predator wave"
+    }
+
+    fn weight(&self, season: Season, content: &Content) -> u32 {
+        if content.in_season(MigrantKind::Spider, season) {
+            2
+        } else {
+            0
+        }
+    }
+
+    fn fire(&self, world: &mut World) -> Notification {
+        let spiders = world.resource::<SimulationConfig>().raid_strength;
+        let mut rng = world.resource_mut::<SimRng>();
+        let (x, y) = random_edge_tile(&mut *rng);
+        let target = random_interior_tile(&mut *rng);
+
+        let mut commands = world.commands();
+        for _ in 0..spiders {
+            spawn_migrant(&mut commands, MigrantKind::Spider, x, y, target);
+        }
+        Notification::warning(format!(
+            "A pack of {} wolf spiders is closing in - keep the foragers near home",
+            spiders
+        ))
+    }
+}
+
+/// Disease strips leaves from every plant
+struct LeafBlight;
+
+impl RandomEvent for LeafBlight {
+    fn name(&self) -> &'static str {
+        "Leaf blight"
+    }
+
+    fn weight(&self, season: Season, _content: &Content) -> u32 {
+        match season {
+            Season::Spring | Season::Summer | Season::Autumn => 1,
+            Season::Winter => 0,
+        }
+    }
+
+    fn fire(&self, world: &mut World) -> Notification {
+        let mut sources = world.query::<&mut LeafSource>();
+        for mut source in sources.iter_mut(world) {
+            source.leaves_remaining = (source.leaves_remaining as f32 * BLIGHT_SURVIVAL) as u32;
+        }
+        Notification::warning("A blight has withered leaves across the surface")
+    }
+}
+
+/// Every plant leafs out in full at once
+struct BumperCrop;
+
+impl RandomEvent for BumperCrop {
+    fn name(&self) -> &'static str {
+        "Bumper crop"
+    }
+
+    fn weight(&self, season: Season, _content: &Content) -> u32 {
+        match season {
+            Season::Spring => 2,
+            Season::Summer => 1,
+            Season::Autumn | Season::Winter => 0,
+        }
+    }
+
+    fn fire(&self, world: &mut World) -> Notification {
+        let mut sources = world.query::<&mut LeafSource>();
+        for mut source in sources.iter_mut(world) {
+            source.leaves_remaining = source.max_leaves;
+        }
+        Notification::info("A bumper crop - every plant is in full leaf")
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// The events that can happen and when the next one is due
+#[derive(Resource, Default)]
+pub struct RandomEvents {
+    events: Vec<Box<dyn RandomEvent>>,
+    /// Tick the next event fires on
+    pub next_tick: u64,
+    /// Events asked for out of turn, by place in the list, to fire on the
+    /// next tick
+    triggered: Vec<usize>,
+}
+
+impl RandomEvents {
+    /// Add an event to the draw
+    pub fn register(&mut self, event: impl RandomEvent + 'static) {
+        self.events.push(Box::new(event));
+    }
+
+    /// Names of the events, in the order they were registered
+    pub fn names(&self) -> Vec<&'static str> {
+        self.events.iter().map(|event| event.name()).collect()
+    }
+
+    /// Fire an event on the next tick whatever the season, without moving
+    /// the schedule
+    pub fn trigger(&mut self, index: usize) {
+        self.triggered.push(index);
+    }
+
+    /// Draw an event for the season, weighted; None if nothing can happen
+    fn draw(
+        &self,
+        season: Season,
+        content: &Content,
+        rng: &mut impl Rng,
+    ) -> Option<&dyn RandomEvent> {
+        let total: u32 = self
+            .events
+            .iter()
+            .map(|event| event.weight(season, content))
+            .sum();
+        if total == 0 {
+            return None;
+        }
+
+        let mut roll = rng.random_range(0..total);
+        for event in &self.events {
+            let weight = event.weight(season, content);
+            if roll < weight {
+                return Some(event.as_ref());
+            }
+            roll -= weight;
+        }
+        None
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Forget the last run's schedule, so a new run's first event is scheduled
+/// from its own start
+fn reset_schedule(mut events: ResMut<RandomEvents>) {
+    events.next_tick = 0;
+    events.triggered.clear();
+}
+
+/// Fire a random event whenever one is due and schedule the next
+fn run_random_events(world: &mut World) {
+    let tick = world.resource::<SimulationTick>();
+    let (now, season) = (tick.0, tick.season());
+
+    world.resource_scope(|world, mut events: Mut<RandomEvents>| {
+        for index in std::mem::take(&mut events.triggered) {
+            let Some(event) = events.events.get(index) else {
+                continue;
+            };
+            info!("Triggered event: {}", event.name());
+            let notification = event.fire(world);
+            world.write_message(notification);
+        }
+
+        if now < events.next_tick {
+            return;
+        }
+
+        // The first time round only schedules, so a game never opens on one
+        let first = events.next_tick == 0;
+        let content = world.resource::<Content>().clone();
+        let mut rng = world.resource_mut::<SimRng>();
+        events.next_tick = now + rng.random_range(EVENT_INTERVAL);
+        if first {
+            return;
+        }
+        let Some(event) = events.draw(season, &content, &mut *rng) else {
+            return;
+        };
+
+        info!("Random event: {}", event.name());
+        let notification = event.fire(world);
+        world.write_message(notification);
+    });
+    world.flush();
+}
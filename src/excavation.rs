@@ -0,0 +1,131 @@
+//! Dig progress on tiles being excavated.
+//!
+//! Digging a tile takes a number of ticks of work rather than happening at
+//! once: each tick of digging adds progress scaled by how soft the soil is
+//! and how strong the digger's mandibles are, and the tile opens up when the
+//! progress is complete. Work left half-done stays put for the next ant.
+//! Tiles being dug show a crack that grows as the work goes on.
+
+use std::collections::BTreeMap;
+
+use bevy::prelude::*;
+
+use crate::low_spec::cosmetic_frame;
+use crate::sprites;
+use crate::time_controls::SimulationSet;
+use crate::world::{CurrentZLevel, TILE_SIZE, WORLD_SIZE, WorldGrid};
+use crate::{ResetRun, reset_resource};
+
+pub struct ExcavationPlugin;
+
+impl Plugin for ExcavationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<DigProgress>()
+            .add_systems(Startup, spawn_crack_overlay)
+            .add_systems(Update, update_crack_overlay.run_if(cosmetic_frame))
+            .add_systems(
+                FixedUpdate,
+                forget_filled_digs
+                    .run_if(resource_changed::<WorldGrid>)
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, reset_resource::<DigProgress>);
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Work done so far (0.0 - 1.0) on each partly dug tile
+#[derive(Resource, Clone, Default)]
+pub struct DigProgress {
+    pub tiles: BTreeMap<(usize, usize, usize), f32>,
+}
+
+impl DigProgress {
+    /// Progress on a tile, zero if nobody has started on it
+    pub fn get(&self, x: usize, y: usize, z: usize) -> f32 {
+        self.tiles.get(&(x, y, z)).copied().unwrap_or(0.0)
+    }
+
+    /// Put some work into a tile, returning true once it's dug through
+    pub fn advance(&mut self, x: usize, y: usize, z: usize, work: f32) -> bool {
+        let progress = self.tiles.entry((x, y, z)).or_insert(0.0);
+        *progress += work;
+        if *progress < 1.0 {
+            return false;
+        }
+        self.tiles.remove(&(x, y, z));
+        true
+    }
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the crack sprite drawn over a tile being dug
+#[derive(Component)]
+pub struct CrackOverlay {
+    pub x: usize,
+    pub y: usize,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Drop progress on tiles that are no longer soil, whether dug out some other
+/// way or filled with something undiggable
+fn forget_filled_digs(world_grid: Res<WorldGrid>, mut progress: ResMut<DigProgress>) {
+    progress
+        .tiles
+        .retain(|&(x, y, z), _| world_grid.tiles[z][y][x].is_diggable());
+}
+
+/// Spawn crack sprites over every tile of the view
+fn spawn_crack_overlay(mut commands: Commands) {
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let world_x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+            let world_y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+
+            commands.spawn((
+                Sprite {
+                    color: sprites::tiles::CRACK,
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(world_x, world_y, 0.35), // Over tiles, under water
+                CrackOverlay { x, y },
+                Visibility::Hidden,
+            ));
+        }
+    }
+}
+
+/// Show a crack on tiles being dug on the current z-level, widening as the
+/// work goes on
+fn update_crack_overlay(
+    progress: Res<DigProgress>,
+    current_z: Res<CurrentZLevel>,
+    mut query: Query<(&CrackOverlay, &mut Sprite, &mut Visibility)>,
+) {
+    if !progress.is_changed() && !current_z.is_changed() {
+        return;
+    }
+
+    let z = current_z.0;
+    for (overlay, mut sprite, mut visibility) in &mut query {
+        let done = progress.get(overlay.x, overlay.y, z);
+        if done <= 0.0 {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        *visibility = Visibility::Visible;
+        sprite.custom_size = Some(Vec2::splat(TILE_SIZE * (0.2 + 0.6 * done)));
+        sprite.color = sprites::tiles::CRACK.with_alpha(0.3 + 0.5 * done);
+    }
+}
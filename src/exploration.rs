@@ -0,0 +1,85 @@
+//! Underground fog of war.
+//!
+//! The colony only knows the soil its ants have been next to. Everything
+//! above ground is in plain sight and anything dug out is known by
+//! definition, but solid ground stays unexplored, drawn dark and hiding its
+//! soil, roots and rock, until an ant passes right beside it.
+
+use bevy::prelude::*;
+
+use crate::ants::{Ant, GridPosition};
+use crate::time_controls::SimulationSet;
+use crate::world::{SURFACE_LEVEL, TileKind, WORLD_SIZE, filled_grid};
+use crate::{ResetRun, reset_resource};
+
+pub struct ExplorationPlugin;
+
+impl Plugin for ExplorationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Explored>()
+            .add_systems(FixedUpdate, explore.in_set(SimulationSet))
+            .add_systems(ResetRun, reset_resource::<Explored>);
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Underground tiles an ant has been next to
+#[derive(Resource, Clone)]
+pub struct Explored {
+    pub tiles: Box<[[[bool; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+}
+
+impl Default for Explored {
+    fn default() -> Self {
+        Self {
+            tiles: filled_grid(false),
+        }
+    }
+}
+
+impl Explored {
+    /// Whether the colony knows what a tile is: anything above ground,
+    /// anything dug out, and solid ground an ant has been next to
+    pub fn shows(&self, tile: TileKind, x: usize, y: usize, z: usize) -> bool {
+        z >= SURFACE_LEVEL || tile.is_excavated() || self.tiles[z][y][x]
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Reveal every tile an ant is in or right next to
+fn explore(ant_query: Query<&GridPosition, With<Ant>>, mut explored: ResMut<Explored>) {
+    let mut revealed = false;
+    // Most ticks reveal nothing, so only flag a change when something's new
+    let tiles = &mut explored.bypass_change_detection().tiles;
+
+    for pos in &ant_query {
+        let neighbors = [
+            (pos.x, pos.y, pos.z),
+            (pos.x.wrapping_sub(1), pos.y, pos.z),
+            (pos.x + 1, pos.y, pos.z),
+            (pos.x, pos.y.wrapping_sub(1), pos.z),
+            (pos.x, pos.y + 1, pos.z),
+            (pos.x, pos.y, pos.z.wrapping_sub(1)),
+            (pos.x, pos.y, pos.z + 1),
+        ];
+        for (x, y, z) in neighbors {
+            if x >= WORLD_SIZE || y >= WORLD_SIZE || z >= SURFACE_LEVEL {
+                continue;
+            }
+            if !tiles[z][y][x] {
+                tiles[z][y][x] = true;
+                revealed = true;
+            }
+        }
+    }
+
+    if revealed {
+        explored.set_changed();
+    }
+}
@@ -0,0 +1,245 @@
+//! Claustral founding, an optional start (Q on the setup screen or
+//! `--founding`).
+//!
+//! Instead of an established nest, the game begins with a lone mated queen on
+//! the surface. She digs her own shaft and first chamber, seals the shaft
+//! behind her, and plants the scrap of fungus she carried from her mother's
+//! nest. Living off her body reserves, she lays, feeds and warms the first
+//! brood herself. When the first workers emerge they dig the shaft back open
+//! and the colony carries on as usual. A queen who runs out of reserves before
+//! then has to eat like anyone else, and there's nothing in the stores.
+
+use bevy::prelude::*;
+
+use crate::ants::{Ant, CALLOW_TICKS, Caste, GridPosition, spawn_ant};
+use crate::brood::Brood;
+use crate::content::Content;
+use crate::excavation::DigProgress;
+use crate::moisture::SoilMoisture;
+use crate::notifications::Notification;
+use crate::pathfinding::Coord;
+use crate::time_controls::SimulationSet;
+use crate::world::{
+    FOUNDING_SHAFT_DEPTH, FungusGarden, SURFACE_LEVEL, TileDug, TileKind, WORLD_SIZE, WorldGrid,
+};
+
+pub struct FoundingPlugin;
+
+impl Plugin for FoundingPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            FixedUpdate,
+            (
+                burn_reserves,
+                queen_digging,
+                queen_brood_care,
+                open_founding_nest,
+            )
+                .chain()
+                .in_set(SimulationSet),
+        );
+    }
+}
+
+/// Food's worth of body reserves a founding queen starts with
+const QUEEN_RESERVES: f32 = 60.0;
+/// Reserves the queen burns each tick just staying alive
+const RESERVE_UPKEEP: f32 = 0.002;
+/// Reserves spent feeding a hungry larva, the same as a unit of food
+const LARVA_MEAL: f32 = 1.0;
+/// Share of a tile of soft soil the queen digs out per tick
+const QUEEN_DIG_RATE: f32 = 0.1;
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// How far a founding queen has got
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum FoundingStage {
+    /// Digging her shaft and chamber
+    Digging,
+    /// Shut in her chamber raising the first brood
+    Sealed,
+}
+
+/// A queen founding the nest on her own
+#[derive(Component, Clone)]
+pub struct FoundingQueen {
+    /// Food's worth of wing muscle and fat left to live on
+    pub reserves: f32,
+    pub stage: FoundingStage,
+}
+
+impl Default for FoundingQueen {
+    fn default() -> Self {
+        Self {
+            reserves: QUEEN_RESERVES,
+            stage: FoundingStage::Digging,
+        }
+    }
+}
+
+/// Spawn a lone mated queen on the surface over the nest
+pub fn spawn_founding_queen(commands: &mut Commands, (x, y): (usize, usize)) {
+    let queen = spawn_ant(commands, x, y, SURFACE_LEVEL, Caste::Queen, CALLOW_TICKS);
+    commands.entity(queen).insert(FoundingQueen::default());
+    info!("Founding queen landed at ({}, {}, {})", x, y, SURFACE_LEVEL);
+}
+
+/// The tiles a founding queen digs, in order: a shaft of ramps straight down
+/// from where she landed, her chamber at the bottom, and a second chamber
+/// tile beside it for the fungus
+fn founding_plan(x: usize, y: usize) -> Vec<(Coord, TileKind)> {
+    let chamber_z = SURFACE_LEVEL - FOUNDING_SHAFT_DEPTH - 1;
+    let beside = if x + 1 < WORLD_SIZE { x + 1 } else { x - 1 };
+
+    let mut plan: Vec<(Coord, TileKind)> = (1..=FOUNDING_SHAFT_DEPTH)
+        .map(|depth| ((x, y, SURFACE_LEVEL - depth), TileKind::Ramp))
+        .collect();
+    plan.push(((x, y, chamber_z), TileKind::Chamber));
+    plan.push(((beside, y, chamber_z), TileKind::Chamber));
+    plan
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Founding queens live off their bodies until the first workers arrive
+fn burn_reserves(
+    mut commands: Commands,
+    mut queen_query: Query<(Entity, &mut FoundingQueen)>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    for (entity, mut founding) in &mut queen_query {
+        founding.reserves -= RESERVE_UPKEEP;
+        if founding.reserves <= 0.0 {
+            commands.entity(entity).remove::<FoundingQueen>();
+            notifications.write(Notification::warning(
+                "The founding queen has used up her body reserves",
+            ));
+        }
+    }
+}
+
+/// The queen digs her shaft and chamber a tile at a time, climbing down as
+/// she goes, then seals the shaft and plants her fungus
+#[allow(clippy::too_many_arguments)]
+fn queen_digging(
+    mut queen_query: Query<(&mut GridPosition, &mut FoundingQueen), With<Ant>>,
+    mut world_grid: ResMut<WorldGrid>,
+    mut garden: ResMut<FungusGarden>,
+    mut progress: ResMut<DigProgress>,
+    moisture: Res<SoilMoisture>,
+    mut dug_tiles: MessageWriter<TileDug>,
+    mut notifications: MessageWriter<Notification>,
+    content: Res<Content>,
+) {
+    for (mut pos, mut founding) in &mut queen_query {
+        if founding.stage != FoundingStage::Digging {
+            continue;
+        }
+
+        let plan = founding_plan(pos.x, pos.y);
+        let next = plan
+            .iter()
+            .copied()
+            .find(|&((x, y, z), kind)| world_grid.tiles[z][y][x] != kind);
+
+        // Keep digging while there's soil left in the plan; stop short at
+        // anything she can't get through
+        if let Some(((x, y, z), kind)) = next
+            && world_grid.tiles[z][y][x].is_diggable()
+        {
+            let work = moisture.dig_chance(world_grid.soil[z][y][x], x, y, z) as f32
+                * content.caste(Caste::Queen).dig_strength
+                * QUEEN_DIG_RATE;
+            if !progress.advance(x, y, z, work) {
+                continue;
+            }
+            world_grid.tiles[z][y][x] = kind;
+            dug_tiles.write(TileDug { x, y, z });
+            if (x, y) == (pos.x, pos.y) {
+                pos.z = z;
+            }
+            continue;
+        }
+
+        // Nowhere to hide on the surface itself
+        if pos.z >= SURFACE_LEVEL {
+            continue;
+        }
+
+        let top = SURFACE_LEVEL - 1;
+        if pos.z < top {
+            world_grid.tiles[top][pos.y][pos.x] = TileKind::Dirt;
+        }
+        if let Some(&((x, y, z), _)) = plan.last()
+            && (x, y, z) != (pos.x, pos.y, pos.z)
+        {
+            garden.plant(&mut world_grid, x, y, z);
+        }
+        founding.stage = FoundingStage::Sealed;
+        info!("Founding queen sealed in at depth {}", pos.z);
+        notifications.write(Notification::info(
+            "The queen has sealed herself into her founding chamber",
+        ));
+    }
+}
+
+/// A sealed queen keeps her brood warm and feeds the larvae from her own body
+fn queen_brood_care(
+    mut queen_query: Query<(&GridPosition, &mut FoundingQueen), With<Ant>>,
+    mut brood_query: Query<(&mut Brood, &GridPosition), Without<Ant>>,
+) {
+    for (queen_pos, mut founding) in &mut queen_query {
+        if founding.stage != FoundingStage::Sealed {
+            continue;
+        }
+
+        for (mut brood, pos) in &mut brood_query {
+            let within_reach = pos.z == queen_pos.z
+                && pos.x.abs_diff(queen_pos.x) <= 1
+                && pos.y.abs_diff(queen_pos.y) <= 1;
+            if !within_reach {
+                continue;
+            }
+            brood.since_tended = 0;
+            if brood.is_hungry() && founding.reserves >= LARVA_MEAL {
+                brood.since_fed = 0;
+                founding.reserves -= LARVA_MEAL;
+            }
+        }
+    }
+}
+
+/// Once the first workers emerge, they dig the shaft open and the founding
+/// is over
+fn open_founding_nest(
+    mut commands: Commands,
+    queen_query: Query<(Entity, &GridPosition, &FoundingQueen), With<Ant>>,
+    caste_query: Query<&Caste, With<Ant>>,
+    mut world_grid: ResMut<WorldGrid>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !caste_query.iter().any(|caste| *caste != Caste::Queen) {
+        return;
+    }
+
+    for (entity, pos, founding) in &queen_query {
+        if founding.stage != FoundingStage::Sealed {
+            continue;
+        }
+
+        let top = SURFACE_LEVEL - 1;
+        if world_grid.tiles[top][pos.y][pos.x] == TileKind::Dirt {
+            world_grid.tiles[top][pos.y][pos.x] = TileKind::Ramp;
+        }
+        commands.entity(entity).remove::<FoundingQueen>();
+        info!("Founding over with {:.1} reserves left", founding.reserves);
+        notifications.write(Notification::info(
+            "The first workers have emerged and opened the nest",
+        ));
+    }
+}
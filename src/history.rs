@@ -0,0 +1,394 @@
+//! Colony history graphs.
+//!
+//! Every few ticks the colony's vital numbers are sampled into
+//! `ColonyHistory`: ants of each caste, food, leaves, mulch, deaths so far by
+//! cause, and how much of each pheromone is laid. G shows a panel of line graphs of the whole run, so it's easy to see
+//! whether the food stores are draining or the foragers dying off rather than
+//! reading counters at one moment. To keep long runs cheap, once the history
+//! fills up every other sample is dropped and sampling slows to match.
+
+use bevy::asset::RenderAssetUsages;
+use bevy::color::ColorToPacked;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::ants::{Ant, Caste, DeathCause};
+use crate::pheromones::PheromoneGrids;
+use crate::sprites::{self, Palette};
+use crate::summary::RunStats;
+use crate::time_controls::SimulationTick;
+use crate::world::{FoodStores, FungusGarden};
+use crate::{ResetRun, in_game, reset_resource};
+
+pub struct HistoryPlugin;
+
+impl Plugin for HistoryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ColonyHistory>()
+            .add_systems(Startup, setup_history_panel)
+            .add_systems(
+                Update,
+                (
+                    record_history,
+                    toggle_history_panel.run_if(in_game),
+                    update_history_panel,
+                )
+                    .chain(),
+            )
+            .add_systems(ResetRun, reset_resource::<ColonyHistory>);
+    }
+}
+
+/// Ticks between samples at the start of a run
+const SAMPLE_INTERVAL: u64 = 10;
+/// Samples kept before the history is thinned out
+const MAX_SAMPLES: usize = 256;
+/// Size of each graph in pixels
+const GRAPH_WIDTH: usize = 256;
+const GRAPH_HEIGHT: usize = 56;
+/// Background of each graph
+const GRAPH_BACKGROUND: [u8; 4] = [20, 20, 20, 255];
+
+/// Castes in the order they're counted
+pub const CASTES: [Caste; 6] = [
+    Caste::Queen,
+    Caste::Forager,
+    Caste::Gardener,
+    Caste::Nurse,
+    Caste::Soldier,
+    Caste::Alate,
+];
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// The colony's numbers at one tick
+#[derive(Debug, Clone, Copy, Default)]
+pub struct HistorySample {
+    pub tick: u64,
+    /// Adult ants of each caste, in `CASTES` order
+    pub castes: [u32; 6],
+    pub food: u32,
+    pub honeydew: u32,
+    pub seeds: u32,
+    pub leaves: u32,
+    pub mulch: u32,
+    /// Adult deaths since the run began, in `DeathCause::ALL` order
+    pub deaths: [u32; 5],
+    /// Summed intensity of each pheromone: dig, forage, home, avoid
+    pub pheromones: [f32; 4],
+}
+
+impl HistorySample {
+    /// The colony's numbers at a tick
+    pub fn take<'a>(
+        tick: u64,
+        ants: impl Iterator<Item = &'a Caste>,
+        food_stores: &FoodStores,
+        fungus_garden: &FungusGarden,
+        stats: &RunStats,
+        pheromones: &PheromoneGrids,
+    ) -> Self {
+        let mut castes = [0; 6];
+        for caste in ants {
+            if let Some(index) = CASTES.iter().position(|c| c == caste) {
+                castes[index] += 1;
+            }
+        }
+        HistorySample {
+            tick,
+            castes,
+            food: food_stores.food,
+            honeydew: food_stores.honeydew,
+            seeds: food_stores.seeds,
+            leaves: fungus_garden.leaves,
+            mulch: fungus_garden.total_mulch(),
+            deaths: DeathCause::ALL.map(|cause| stats.deaths.get(&cause).copied().unwrap_or(0)),
+            pheromones: pheromones.totals(),
+        }
+    }
+
+    pub fn population(&self) -> u32 {
+        self.castes.iter().sum()
+    }
+
+    pub fn total_deaths(&self) -> u32 {
+        self.deaths.iter().sum()
+    }
+}
+
+/// Samples of the colony's numbers over the run, oldest first
+#[derive(Resource)]
+pub struct ColonyHistory {
+    pub samples: Vec<HistorySample>,
+    /// Ticks between samples, doubling each time the history is thinned
+    pub interval: u64,
+}
+
+impl Default for ColonyHistory {
+    fn default() -> Self {
+        Self {
+            samples: Vec::with_capacity(MAX_SAMPLES),
+            interval: SAMPLE_INTERVAL,
+        }
+    }
+}
+
+impl ColonyHistory {
+    /// Add a sample, halving the resolution of the whole history once full
+    fn push(&mut self, sample: HistorySample) {
+        if self.samples.len() >= MAX_SAMPLES {
+            let mut index = 0;
+            self.samples.retain(|_| {
+                index += 1;
+                index % 2 == 1
+            });
+            self.interval *= 2;
+        }
+        self.samples.push(sample);
+    }
+}
+
+// ============================================================================
+// Graphs
+// ============================================================================
+
+/// One graph in the panel
+#[derive(Component, Clone, Copy)]
+enum Graph {
+    Population,
+    Food,
+    Garden,
+    Deaths,
+}
+
+impl Graph {
+    const ALL: [Graph; 4] = [Graph::Population, Graph::Food, Graph::Garden, Graph::Deaths];
+
+    /// Each line's name and color
+    fn lines(self, palette: Palette) -> Vec<(&'static str, Color)> {
+        match self {
+            Graph::Population => CASTES
+                .iter()
+                .map(|caste| (caste.name(), caste.color(palette)))
+                .collect(),
+            Graph::Food => vec![
+                ("Food", sprites::ui::HIGHLIGHT),
+                ("Honeydew", Color::srgb(0.6, 0.9, 0.5)),
+                ("Seeds", Color::srgb(0.8, 0.6, 0.4)),
+            ],
+            Graph::Garden => vec![
+                ("Leaves", Color::srgb(0.3, 0.8, 0.3)),
+                ("Mulch", Color::srgb(0.6, 0.45, 0.3)),
+            ],
+            Graph::Deaths => vec![("Deaths", Color::srgb(0.9, 0.3, 0.3))],
+        }
+    }
+
+    /// Each line's value in a sample, in `lines` order
+    fn values(self, sample: &HistorySample) -> Vec<u32> {
+        match self {
+            Graph::Population => sample.castes.to_vec(),
+            Graph::Food => vec![sample.food, sample.honeydew, sample.seeds],
+            Graph::Garden => vec![sample.leaves, sample.mulch],
+            Graph::Deaths => vec![sample.total_deaths()],
+        }
+    }
+
+    /// The caption above the graph, with the latest values
+    fn caption(self, latest: &HistorySample) -> String {
+        match self {
+            Graph::Population => format!("Population: {}", latest.population()),
+            Graph::Food => format!(
+                "Food: {}  Honeydew: {}  Seeds: {}",
+                latest.food, latest.honeydew, latest.seeds
+            ),
+            Graph::Garden => format!("Leaves: {}  Mulch: {}", latest.leaves, latest.mulch),
+            Graph::Deaths => format!("Deaths: {}", latest.total_deaths()),
+        }
+    }
+}
+
+/// Draw a graph's lines over the history, scaled to the largest value shown
+fn draw_graph(graph: Graph, samples: &[HistorySample], palette: Palette) -> Vec<u8> {
+    let mut pixels = GRAPH_BACKGROUND.repeat(GRAPH_WIDTH * GRAPH_HEIGHT);
+    let values: Vec<Vec<u32>> = samples.iter().map(|s| graph.values(s)).collect();
+    let max = values.iter().flatten().copied().max().unwrap_or(0).max(1);
+
+    let x_of = |i: usize| i * (GRAPH_WIDTH - 1) / samples.len().saturating_sub(1).max(1);
+    let y_of = |v: u32| (GRAPH_HEIGHT - 1) - (v as usize * (GRAPH_HEIGHT - 1) / max as usize);
+
+    for (line, (_, color)) in graph.lines(palette).into_iter().enumerate() {
+        let color = color.to_srgba().to_u8_array();
+        for i in 1..values.len() {
+            let from = (x_of(i - 1), y_of(values[i - 1][line]));
+            let to = (x_of(i), y_of(values[i][line]));
+            draw_line(&mut pixels, from, to, color);
+        }
+    }
+    pixels
+}
+
+/// Plot a straight line between two pixels
+fn draw_line(pixels: &mut [u8], from: (usize, usize), to: (usize, usize), color: [u8; 4]) {
+    let steps = from.0.abs_diff(to.0).max(from.1.abs_diff(to.1)).max(1);
+    for step in 0..=steps {
+        let lerp = |a: usize, b: usize| {
+            (a as f32 + (b as f32 - a as f32) * step as f32 / steps as f32).round() as usize
+        };
+        let (x, y) = (lerp(from.0, to.0), lerp(from.1, to.1));
+        let index = (y * GRAPH_WIDTH + x) * 4;
+        pixels[index..index + 4].copy_from_slice(&color);
+    }
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the history panel root
+#[derive(Component)]
+struct HistoryPanel;
+
+/// The caption above a graph
+#[derive(Component)]
+struct GraphCaption(Graph);
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Sample the colony every `interval` ticks
+fn record_history(
+    tick: Res<SimulationTick>,
+    food_stores: Res<FoodStores>,
+    fungus_garden: Res<FungusGarden>,
+    stats: Res<RunStats>,
+    pheromones: Res<PheromoneGrids>,
+    ant_query: Query<&Caste, With<Ant>>,
+    mut history: ResMut<ColonyHistory>,
+) {
+    // Rewinding or a new run takes the tick back: forget what hasn't happened
+    if history
+        .samples
+        .last()
+        .is_some_and(|last| last.tick > tick.0)
+    {
+        history.samples.retain(|sample| sample.tick <= tick.0);
+    }
+    let due = history
+        .samples
+        .last()
+        .is_none_or(|last| tick.0 >= last.tick + history.interval);
+    if !due || tick.0 == 0 {
+        return;
+    }
+
+    history.push(HistorySample::take(
+        tick.0,
+        ant_query.iter(),
+        &food_stores,
+        &fungus_garden,
+        &stats,
+        &pheromones,
+    ));
+}
+
+/// Spawn the (initially hidden) history panel in the bottom-right corner
+fn setup_history_panel(mut commands: Commands, mut images: ResMut<Assets<Image>>) {
+    commands
+        .spawn((
+            HistoryPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                bottom: Val::Px(60.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            for graph in Graph::ALL {
+                parent.spawn((
+                    GraphCaption(graph),
+                    Text::new(""),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(sprites::ui::TEXT),
+                ));
+                let image = Image::new(
+                    Extent3d {
+                        width: GRAPH_WIDTH as u32,
+                        height: GRAPH_HEIGHT as u32,
+                        depth_or_array_layers: 1,
+                    },
+                    TextureDimension::D2,
+                    GRAPH_BACKGROUND.repeat(GRAPH_WIDTH * GRAPH_HEIGHT),
+                    TextureFormat::Rgba8UnormSrgb,
+                    RenderAssetUsages::default(),
+                );
+                parent.spawn((
+                    graph,
+                    ImageNode::new(images.add(image)),
+                    Node {
+                        width: Val::Px(GRAPH_WIDTH as f32),
+                        height: Val::Px(GRAPH_HEIGHT as f32),
+                        ..default()
+                    },
+                ));
+            }
+        });
+}
+
+/// Show or hide the history panel with the G key
+fn toggle_history_panel(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut query: Query<&mut Visibility, With<HistoryPanel>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyG) {
+        return;
+    }
+
+    if let Ok(mut visibility) = query.single_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Redraw the graphs when the history changes while the panel is showing
+fn update_history_panel(
+    history: Res<ColonyHistory>,
+    palette: Res<Palette>,
+    panel_query: Query<Ref<Visibility>, With<HistoryPanel>>,
+    graph_query: Query<(&Graph, &ImageNode)>,
+    mut caption_query: Query<(&GraphCaption, &mut Text)>,
+    mut images: ResMut<Assets<Image>>,
+) {
+    let Ok(visibility) = panel_query.single() else {
+        return;
+    };
+    if *visibility != Visibility::Visible
+        || !(history.is_changed() || palette.is_changed() || visibility.is_changed())
+    {
+        return;
+    }
+
+    let latest = history.samples.last().copied().unwrap_or_default();
+    for (caption, mut text) in &mut caption_query {
+        **text = caption.0.caption(&latest);
+    }
+    for (graph, node) in &graph_query {
+        if let Some(image) = images.get_mut(&node.image) {
+            image.data = Some(draw_graph(*graph, &history.samples, *palette));
+        }
+    }
+}
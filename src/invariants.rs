@@ -0,0 +1,197 @@
+//! Simulation invariant checks, in debug builds.
+//!
+//! After every tick the simulation is checked for states it should never
+//! reach: an ant off the grid or standing inside solid dirt, a NaN or
+//! infinite progress value (digging, garden growth, plant regrowth,
+//! spoilage), or garden contamination outside 0 to its cap. The pheromone
+//! fields are too big to scan every tick, so they're checked for values
+//! outside 0 to 1 every `PHEROMONE_CHECK_INTERVAL` ticks. Each broken
+//! invariant is logged as an error, with the tick and what broke, the first
+//! tick it's seen. With `InvariantChecks::panic` set, as the integration tests
+//! do, the first one panics instead. Release builds skip the checks entirely.
+
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+
+use crate::ants::{Ant, Caste, GridPosition, Task};
+use crate::excavation::DigProgress;
+use crate::pheromones::{PheromoneGrids, PheromoneType};
+use crate::time_controls::{SimulationSet, SimulationTick, advance_tick};
+use crate::world::{
+    FoodStores, FungusGarden, LeafSource, MAX_CONTAMINATION, Plant, TileKind, WORLD_SIZE, WorldGrid,
+};
+
+pub struct InvariantsPlugin;
+
+impl Plugin for InvariantsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InvariantChecks>();
+        if cfg!(debug_assertions) {
+            // Before the tick is counted, so reports name the tick that broke
+            app.add_systems(
+                FixedLast,
+                (check_invariants, check_pheromones)
+                    .before(advance_tick)
+                    .in_set(SimulationSet),
+            );
+        }
+    }
+}
+
+/// Ticks between scans of the pheromone fields
+const PHEROMONE_CHECK_INTERVAL: u64 = 50;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// How broken invariants are reported
+#[derive(Resource, Default)]
+pub struct InvariantChecks {
+    /// Panic on the first broken invariant rather than logging it
+    pub panic: bool,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Check the invariants cheap enough to run every tick
+#[allow(clippy::too_many_arguments)]
+fn check_invariants(
+    checks: Res<InvariantChecks>,
+    tick: Res<SimulationTick>,
+    world_grid: Res<WorldGrid>,
+    progress: Res<DigProgress>,
+    fungus_garden: Res<FungusGarden>,
+    food_stores: Res<FoodStores>,
+    ant_query: Query<(Entity, &GridPosition, &Caste, &Task), With<Ant>>,
+    plant_query: Query<(&Plant, &LeafSource)>,
+    mut reported: Local<BTreeSet<String>>,
+) {
+    let mut broken = BTreeSet::new();
+
+    for (entity, pos, caste, task) in &ant_query {
+        let (x, y, z) = (pos.x, pos.y, pos.z);
+        if x >= WORLD_SIZE || y >= WORLD_SIZE || z >= WORLD_SIZE {
+            broken.insert(format!(
+                "{} {:?} is off the grid at ({}, {}, {}) while {}",
+                caste.name(),
+                entity,
+                x,
+                y,
+                z,
+                task.name()
+            ));
+        } else if world_grid.tiles[z][y][x] == TileKind::Dirt {
+            broken.insert(format!(
+                "{} {:?} is inside dirt at ({}, {}, {}) while {}",
+                caste.name(),
+                entity,
+                x,
+                y,
+                z,
+                task.name()
+            ));
+        }
+    }
+
+    for (&(x, y, z), &value) in &progress.tiles {
+        if !value.is_finite() {
+            broken.insert(format!(
+                "Dig progress is {} at ({}, {}, {})",
+                value, x, y, z
+            ));
+        }
+    }
+    for (&(x, y, z), plot) in &fungus_garden.plots {
+        if !plot.growth_progress.is_finite()
+            || !(0.0..=MAX_CONTAMINATION).contains(&plot.contamination)
+        {
+            broken.insert(format!(
+                "Garden plot at ({}, {}, {}) has growth {} and contamination {}",
+                x, y, z, plot.growth_progress, plot.contamination
+            ));
+        }
+    }
+    for (plant, leaves) in &plant_query {
+        if !leaves.regrow_timer.is_finite() || !leaves.regrow_ticks.is_finite() {
+            broken.insert(format!(
+                "{} at ({}, {}) has regrowth {} of {} ticks",
+                plant.kind.name(),
+                plant.x,
+                plant.y,
+                leaves.regrow_timer,
+                leaves.regrow_ticks
+            ));
+        }
+    }
+    if !food_stores.spoilage_progress.is_finite() {
+        broken.insert(format!(
+            "Food spoilage progress is {}",
+            food_stores.spoilage_progress
+        ));
+    }
+
+    report(&checks, tick.0, broken, &mut reported);
+}
+
+/// Check every pheromone stays within 0 to 1, every few ticks
+fn check_pheromones(
+    checks: Res<InvariantChecks>,
+    tick: Res<SimulationTick>,
+    pheromones: Res<PheromoneGrids>,
+    mut reported: Local<BTreeSet<String>>,
+) {
+    if !tick.0.is_multiple_of(PHEROMONE_CHECK_INTERVAL) {
+        return;
+    }
+
+    // One report per field, or a bad diffusion step would flood the log
+    let mut broken = BTreeSet::new();
+    for (ptype, grid) in PheromoneType::ALL.into_iter().zip([
+        &pheromones.dig,
+        &pheromones.forage,
+        &pheromones.home,
+        &pheromones.avoid,
+    ]) {
+        let mut first = None;
+        let mut count = 0;
+        for (z, layer) in grid.iter().enumerate() {
+            for (y, row) in layer.iter().enumerate() {
+                for (x, &value) in row.iter().enumerate() {
+                    if !(0.0..=1.0).contains(&value) {
+                        first.get_or_insert((x, y, z, value));
+                        count += 1;
+                    }
+                }
+            }
+        }
+        if let Some((x, y, z, value)) = first {
+            broken.insert(format!(
+                "{:?} pheromone is {} at ({}, {}, {}), one of {} tiles outside 0 to 1",
+                ptype, value, x, y, z, count
+            ));
+        }
+    }
+
+    report(&checks, tick.0, broken, &mut reported);
+}
+
+/// Report invariants that have newly broken, and forget ones that have
+/// recovered so they're reported if they break again
+fn report(
+    checks: &InvariantChecks,
+    tick: u64,
+    broken: BTreeSet<String>,
+    reported: &mut BTreeSet<String>,
+) {
+    for invariant in broken.difference(reported) {
+        if checks.panic {
+            panic!("Invariant broken on tick {}: {}", tick, invariant);
+        }
+        error!("Invariant broken on tick {}: {}", tick, invariant);
+    }
+    *reported = broken;
+}
@@ -0,0 +1,328 @@
+//! Acre's leafcutter colony simulation, as a library.
+//!
+//! `SimulationPlugin` adds everything that advances the colony: the world
+//! grid, ants and brood, pheromones, plants, water, weather and events. Each
+//! of those plugins also spawns and updates the sprites and overlays for what
+//! it simulates, next to the state they draw, so the `sprites`, `atlas` and
+//! `animation` modules are always built; without a window those systems just
+//! keep components up to date that nothing renders. The headless tools that
+//! drive the simulation from the command line (`cli`, `audit`, `bench` and
+//! `soak`) are always built too.
+//!
+//! The game in `main.rs` is a frontend over it, adding the window, camera,
+//! menus, controls and the rest of the interface from the modules behind the
+//! `ui` feature. Headless tools and tests can build an app with just the
+//! simulation and drive it one tick at a time with `step()`:
+//!
+//! ```no_run
+//! use bevy::prelude::*;
+//! use bevy::state::app::StatesPlugin;
+//!
+//! let mut app = App::new();
+//! app.add_plugins((MinimalPlugins, StatesPlugin))
+//!     .insert_state(acre::GameState::Playing)
+//!     .add_plugins(acre::SimulationPlugin { seed: 1 });
+//! app.finish();
+//! app.cleanup();
+//! app.update();
+//! for _ in 0..100 {
+//!     acre::step(app.world_mut());
+//! }
+//! let ants = app
+//!     .world_mut()
+//!     .query::<&acre::Ant>()
+//!     .iter(app.world())
+//!     .count();
+//! println!("{} ants after 100 ticks", ants);
+//! ```
+
+use bevy::app::FixedMain;
+use bevy::ecs::schedule::{ExecutorKind, ScheduleLabel};
+use bevy::prelude::*;
+
+#[cfg(feature = "ui")]
+pub mod achievements;
+pub mod actions;
+pub mod air;
+#[cfg(feature = "ui")]
+pub mod alerts;
+pub mod animation;
+pub mod ants;
+pub mod aphids;
+pub mod atlas;
+pub mod audit;
+pub mod bench;
+pub mod biome;
+#[cfg(feature = "ui")]
+pub mod blueprints;
+pub mod brood;
+#[cfg(feature = "ui")]
+pub mod camera;
+pub mod cave_ins;
+pub mod cli;
+pub mod config;
+#[cfg(feature = "ui")]
+pub mod console;
+pub mod content;
+#[cfg(feature = "ui")]
+pub mod controls;
+#[cfg(feature = "ui")]
+pub mod cursor;
+pub mod designations;
+#[cfg(feature = "ui")]
+pub mod difficulty;
+#[cfg(feature = "ui")]
+pub mod editor;
+pub mod entrances;
+#[cfg(feature = "ui")]
+pub mod event_log;
+pub mod events;
+pub mod excavation;
+pub mod exploration;
+pub mod founding;
+pub mod history;
+pub mod invariants;
+pub mod low_spec;
+#[cfg(feature = "ui")]
+pub mod macros;
+#[cfg(feature = "ui")]
+pub mod maps;
+#[cfg(feature = "ui")]
+pub mod menu;
+pub mod metrics;
+pub mod migrations;
+pub mod moisture;
+pub mod multiplayer;
+pub mod mushrooms;
+#[cfg(feature = "ui")]
+pub mod music;
+pub mod notifications;
+pub mod nuptial;
+#[cfg(feature = "ui")]
+pub mod palette;
+#[cfg(feature = "ui")]
+pub mod particles;
+pub mod pathfinding;
+pub mod performance;
+pub mod pheromones;
+#[cfg(feature = "ui")]
+pub mod poster;
+#[cfg(feature = "ui")]
+pub mod profile;
+#[cfg(feature = "prometheus")]
+pub mod prometheus;
+#[cfg(feature = "ui")]
+pub mod recording;
+pub mod relocation;
+#[cfg(feature = "remote")]
+pub mod remote;
+#[cfg(feature = "ui")]
+pub mod rewind;
+pub mod rng;
+pub mod sand;
+#[cfg(feature = "ui")]
+pub mod sandbox;
+#[cfg(feature = "ui")]
+pub mod scenarios;
+pub mod scouting;
+#[cfg(feature = "ui")]
+pub mod screenshots;
+#[cfg(feature = "ui")]
+pub mod scripting;
+pub mod seeds;
+#[cfg(feature = "ui")]
+pub mod setup;
+pub mod soak;
+#[cfg(feature = "spectator")]
+pub mod spectator;
+pub mod sprites;
+#[cfg(feature = "ui")]
+pub mod statistics;
+pub mod stuck;
+pub mod summary;
+pub mod time_controls;
+#[cfg(feature = "ui")]
+pub mod timelapse;
+#[cfg(feature = "ui")]
+pub mod touch;
+#[cfg(feature = "ui")]
+pub mod ui;
+pub mod water;
+pub mod world;
+
+pub use ants::{Ant, Caste, GridPosition, NestLocation, Task};
+pub use pheromones::PheromoneGrids;
+pub use time_controls::SimulationTick;
+pub use world::{TileKind, WORLD_SIZE, WorldGrid};
+
+use actions::ActionsPlugin;
+use air::AirPlugin;
+use ants::AntPlugin;
+use aphids::AphidPlugin;
+use brood::BroodPlugin;
+use cave_ins::CaveInPlugin;
+use config::ConfigPlugin;
+use content::ContentPlugin;
+use designations::DesignationsPlugin;
+use entrances::EntrancePlugin;
+use events::EventsPlugin;
+use excavation::ExcavationPlugin;
+use exploration::ExplorationPlugin;
+use founding::FoundingPlugin;
+use invariants::InvariantsPlugin;
+use low_spec::LowSpecMode;
+use migrations::MigrationPlugin;
+use moisture::MoisturePlugin;
+use mushrooms::MushroomPlugin;
+use notifications::Notification;
+use nuptial::NuptialPlugin;
+use pheromones::PheromonePlugin;
+use relocation::RelocationPlugin;
+use rng::SimRng;
+use sand::SandPlugin;
+use scouting::ScoutingPlugin;
+use seeds::SeedPlugin;
+use sprites::Palette;
+use stuck::StuckPlugin;
+use summary::RunStats;
+use time_controls::{SingleStep, TimeControlsPlugin};
+use water::WaterPlugin;
+use world::WorldPlugin;
+
+/// Everything that advances the colony, seeded so a run can be repeated.
+///
+/// The app decides where the game starts by inserting a `GameState` first;
+/// the simulation only runs while it's `Playing`, or a tick at a time
+/// through `step()`
+pub struct SimulationPlugin {
+    pub seed: u64,
+}
+
+impl Plugin for SimulationPlugin {
+    fn build(&self, app: &mut App) {
+        // Owned by the frontend's plugins when it adds them; a headless app
+        // runs with their defaults
+        app.init_resource::<LowSpecMode>()
+            .init_resource::<Palette>()
+            .init_resource::<RunStats>()
+            .add_message::<Notification>();
+
+        app.insert_resource(SimRng::new(self.seed))
+            // Definitions and tuning the simulation draws its numbers from
+            .add_plugins((ContentPlugin, ConfigPlugin))
+            // Colony
+            .add_plugins((
+                ActionsPlugin,
+                WorldPlugin,
+                TimeControlsPlugin,
+                AntPlugin,
+                BroodPlugin,
+                AphidPlugin,
+                SeedPlugin,
+                MushroomPlugin,
+                MigrationPlugin,
+                PheromonePlugin,
+                DesignationsPlugin,
+                EntrancePlugin,
+                ScoutingPlugin,
+                RelocationPlugin,
+                NuptialPlugin,
+            ))
+            // Underground environment
+            .add_plugins((
+                ExcavationPlugin,
+                ExplorationPlugin,
+                FoundingPlugin,
+                AirPlugin,
+                SandPlugin,
+                WaterPlugin,
+                MoisturePlugin,
+                EventsPlugin,
+                CaveInPlugin,
+            ))
+            // Recovery for stuck ants, and sanity checks after every tick in
+            // debug builds
+            .add_plugins((StuckPlugin, InvariantsPlugin));
+
+        // Systems that share `SimRng` have no order between them, so left to
+        // the multi-threaded executor they'd draw from it in a different order
+        // each run. Running the schedules that generate and advance the world
+        // one system at a time keeps a seed giving the same colony
+        for label in [
+            Startup.intern(),
+            PostStartup.intern(),
+            FixedFirst.intern(),
+            FixedPreUpdate.intern(),
+            FixedUpdate.intern(),
+            FixedPostUpdate.intern(),
+            FixedLast.intern(),
+        ] {
+            app.edit_schedule(label, |schedule| {
+                schedule.set_executor_kind(ExecutorKind::SingleThreaded);
+            });
+        }
+    }
+}
+
+/// Run exactly one simulation tick, whatever the game state or clock, as if
+/// stepped by hand while paused
+pub fn step(world: &mut World) {
+    world.resource_mut::<SingleStep>().0 = true;
+    world.run_schedule(FixedMain);
+}
+
+/// Where the game is: title screen (and scenario select, the map editor,
+/// achievements or statistics), then setup, loading, play (with pauses), and
+/// game over, then back to the title screen for another run
+#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
+pub enum GameState {
+    /// The title screen
+    #[default]
+    MainMenu,
+    /// Choosing a scenario to play
+    ScenarioSelect,
+    /// Sculpting a map in the world editor
+    Editor,
+    /// Looking over the achievements unlocked so far
+    Achievements,
+    /// Looking over the lifetime statistics
+    Statistics,
+    /// Previewing the generated world before the simulation starts
+    Setup,
+    /// Waiting for sprites, content definitions and music to finish loading
+    Loading,
+    Playing,
+    Paused,
+    /// The colony has collapsed or its flight got away
+    GameOver,
+}
+
+impl GameState {
+    /// Whether a colony is in play, running or paused
+    pub fn in_game(&self) -> bool {
+        matches!(self, GameState::Playing | GameState::Paused)
+    }
+}
+
+/// Run condition for in-game input: while playing or paused
+pub fn in_game(state: Res<State<GameState>>) -> bool {
+    state.get().in_game()
+}
+
+/// Systems that clear the last run away before a new world is generated over
+/// it. Each plugin resets what it owns here, so nothing carries over from one
+/// colony to the next
+#[derive(ScheduleLabel, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct ResetRun;
+
+/// Put a resource back how a run starts, in the `ResetRun` schedule
+pub fn reset_resource<R: Resource + Default>(mut resource: ResMut<R>) {
+    *resource = R::default();
+}
+
+/// Despawn everything with a component, in the `ResetRun` schedule
+pub fn despawn_all<C: Component>(mut commands: Commands, query: Query<Entity, With<C>>) {
+    for entity in &query {
+        commands.entity(entity).despawn();
+    }
+}
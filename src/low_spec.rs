@@ -0,0 +1,82 @@
+//! Low-spec rendering preset for weak integrated GPUs.
+//!
+//! Toggled with F8 or enabled at launch with `--low-spec`. While on, the
+//! pheromone overlay draws only the strongest signal per tile instead of
+//! blending, cosmetic systems update every few frames instead of every frame,
+//! and HiDPI displays render at a scale factor of 1. The simulation itself is
+//! unaffected.
+
+use bevy::prelude::*;
+use bevy::window::PrimaryWindow;
+
+use crate::cli::CliArgs;
+
+pub struct LowSpecPlugin;
+
+impl Plugin for LowSpecPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<LowSpecMode>()
+            .add_systems(Startup, apply_cli_low_spec)
+            .add_systems(Update, (toggle_low_spec, apply_resolution_cap).chain());
+    }
+}
+
+/// Frames between cosmetic updates in low-spec mode
+const COSMETIC_FRAME_INTERVAL: u32 = 4;
+
+/// Whether the low-spec preset is active
+#[derive(Resource, Default)]
+pub struct LowSpecMode {
+    pub enabled: bool,
+}
+
+/// Run condition for purely cosmetic systems: every frame normally, every few
+/// frames in low-spec mode
+pub fn cosmetic_frame(low_spec: Res<LowSpecMode>, mut frame: Local<u32>) -> bool {
+    if !low_spec.enabled {
+        return true;
+    }
+
+    *frame = (*frame + 1) % COSMETIC_FRAME_INTERVAL;
+    *frame == 0
+}
+
+/// Turn the preset on at launch when `--low-spec` was passed
+fn apply_cli_low_spec(cli: Option<Res<CliArgs>>, mut low_spec: ResMut<LowSpecMode>) {
+    if cli.is_some_and(|cli| cli.low_spec) {
+        low_spec.enabled = true;
+        info!("Low-spec mode enabled");
+    }
+}
+
+/// Toggle the preset with F8
+fn toggle_low_spec(keyboard: Res<ButtonInput<KeyCode>>, mut low_spec: ResMut<LowSpecMode>) {
+    if keyboard.just_pressed(KeyCode::F8) {
+        low_spec.enabled = !low_spec.enabled;
+        info!(
+            "Low-spec mode {}",
+            if low_spec.enabled {
+                "enabled"
+            } else {
+                "disabled"
+            }
+        );
+    }
+}
+
+/// Cap the window's render scale at 1 while low-spec mode is on
+fn apply_resolution_cap(
+    low_spec: Res<LowSpecMode>,
+    mut windows: Query<&mut Window, With<PrimaryWindow>>,
+) {
+    if !low_spec.is_changed() {
+        return;
+    }
+
+    let Ok(mut window) = windows.single_mut() else {
+        return;
+    };
+
+    let scale_override = low_spec.enabled.then_some(1.0);
+    window.resolution.set_scale_factor_override(scale_override);
+}
@@ -0,0 +1,283 @@
+//! Hotkey macros for repeating pheromone and designation patterns.
+//!
+//! Shift+F1..F4 starts recording into a slot; every pheromone stroke and
+//! designation made until the same key is pressed again is captured relative
+//! to the first one. F1..F4 plays the slot back with that first tile at the
+//! cursor, so an avoid ring drawn around one entrance can be dropped around
+//! the next with a single key. Slots are saved under `macros/`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::cursor::CursorTile;
+use crate::in_game;
+use crate::notifications::Notification;
+use crate::world::WORLD_SIZE;
+
+pub struct MacrosPlugin;
+
+impl Plugin for MacrosPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<MacroLibrary>()
+            .add_systems(Startup, load_macros)
+            .add_systems(
+                Update,
+                (toggle_macro_recording, record_macro_actions, play_macro)
+                    .chain()
+                    .run_if(in_game)
+                    .in_set(ActionSystems::Input),
+            );
+    }
+}
+
+/// Directory macros are saved to and loaded from
+const MACROS_DIR: &str = "macros";
+
+/// Keys that play (and with Shift, record) each macro slot
+const SLOT_KEYS: [KeyCode; 4] = [KeyCode::F1, KeyCode::F2, KeyCode::F3, KeyCode::F4];
+
+// ============================================================================
+// Macros
+// ============================================================================
+
+/// A recorded sequence of actions, each offset from the first one's tile
+#[derive(Debug, Clone, Default)]
+pub struct Macro {
+    pub steps: Vec<((i32, i32, i32), PlayerAction)>,
+}
+
+impl Macro {
+    /// Add an action, anchoring the macro on the first one
+    fn push(&mut self, action: &PlayerAction) {
+        let (x, y, z) = action.position();
+        let (ox, oy, oz) = self
+            .steps
+            .first()
+            .map_or((x, y, z), |(_, first)| first.position());
+        let offset = (
+            x as i32 - ox as i32,
+            y as i32 - oy as i32,
+            z as i32 - oz as i32,
+        );
+        self.steps.push((offset, action.clone()));
+    }
+
+    /// The actions with the anchor moved to `(ax, ay, az)`, dropping any that
+    /// would land off the map
+    fn at(&self, ax: usize, ay: usize, az: usize) -> Vec<PlayerAction> {
+        let range = 0..WORLD_SIZE as i32;
+        self.steps
+            .iter()
+            .filter_map(|((dx, dy, dz), action)| {
+                let (x, y, z) = (ax as i32 + dx, ay as i32 + dy, az as i32 + dz);
+                (range.contains(&x) && range.contains(&y) && range.contains(&z))
+                    .then(|| action.moved_to(x as usize, y as usize, z as usize))
+            })
+            .collect()
+    }
+
+    /// Serialize as one `<dx> <dy> <dz> <action>` line per step
+    fn to_text(&self) -> String {
+        let mut contents = String::from("# acre pheromone macro\n");
+        for ((dx, dy, dz), action) in &self.steps {
+            contents.push_str(&format!("{} {} {} {}\n", dx, dy, dz, action.to_line()));
+        }
+        contents
+    }
+
+    /// Parse text written by `to_text`
+    fn from_text(contents: &str) -> Result<Self, String> {
+        let mut steps = Vec::new();
+
+        for line in contents.lines() {
+            let line = line.trim();
+            if line.is_empty() || line.starts_with('#') {
+                continue;
+            }
+
+            let mut fields = line.splitn(4, ' ');
+            let mut offset = || -> Result<i32, String> {
+                fields
+                    .next()
+                    .ok_or_else(|| format!("malformed line '{}'", line))?
+                    .parse()
+                    .map_err(|e| format!("bad offset in '{}': {}", line, e))
+            };
+            let (dx, dy, dz) = (offset()?, offset()?, offset()?);
+            let action = fields
+                .next()
+                .ok_or_else(|| format!("missing action in '{}'", line))?;
+            steps.push(((dx, dy, dz), PlayerAction::from_line(action)?));
+        }
+
+        Ok(Self { steps })
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// The macro slots and which one, if any, is recording
+#[derive(Resource, Default)]
+pub struct MacroLibrary {
+    pub slots: [Macro; SLOT_KEYS.len()],
+    pub recording: Option<usize>,
+}
+
+/// Where a slot is saved
+fn slot_path(slot: usize) -> PathBuf {
+    PathBuf::from(MACROS_DIR).join(format!("slot-{}.txt", slot + 1))
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Load any macros saved by earlier sessions
+fn load_macros(mut library: ResMut<MacroLibrary>) {
+    let mut loaded = 0;
+    for (slot, recorded) in library.slots.iter_mut().enumerate() {
+        let path = slot_path(slot);
+        if !path.exists() {
+            continue;
+        }
+        match read_macro(&path) {
+            Ok(found) => {
+                *recorded = found;
+                loaded += 1;
+            }
+            Err(e) => error!("Failed to load macro {}: {}", path.display(), e),
+        }
+    }
+
+    if loaded > 0 {
+        info!("Loaded {} pheromone macros", loaded);
+    }
+}
+
+/// Read a macro file
+fn read_macro(path: &Path) -> Result<Macro, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    Macro::from_text(&contents)
+}
+
+/// Start recording a slot with Shift+F1..F4, and save it when pressed again
+fn toggle_macro_recording(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut library: ResMut<MacroLibrary>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+    let Some(slot) = SLOT_KEYS.iter().position(|key| keyboard.just_pressed(*key)) else {
+        return;
+    };
+
+    match library.recording {
+        Some(recording) if recording == slot => {
+            library.recording = None;
+            let recorded = &library.slots[slot];
+            if recorded.steps.is_empty() {
+                notifications.write(Notification::info(format!(
+                    "Macro F{} cleared (nothing recorded)",
+                    slot + 1
+                )));
+                // An empty slot shouldn't come back on the next launch
+                let _ = fs::remove_file(slot_path(slot));
+                return;
+            }
+
+            let path = slot_path(slot);
+            let result =
+                fs::create_dir_all(MACROS_DIR).and_then(|()| fs::write(&path, recorded.to_text()));
+            if let Err(e) = result {
+                error!("Failed to save macro {}: {}", path.display(), e);
+            }
+            notifications.write(Notification::info(format!(
+                "Recorded macro F{} ({} steps)",
+                slot + 1,
+                recorded.steps.len()
+            )));
+        }
+        Some(recording) => {
+            notifications.write(Notification::warning(format!(
+                "Already recording macro F{} (Shift+F{} to finish)",
+                recording + 1,
+                recording + 1
+            )));
+        }
+        None => {
+            library.recording = Some(slot);
+            library.slots[slot] = Macro::default();
+            notifications.write(Notification::info(format!(
+                "Recording macro F{}: draw pheromones and designations, Shift+F{} to finish",
+                slot + 1,
+                slot + 1
+            )));
+        }
+    }
+}
+
+/// Capture the player's pheromone strokes and designations into the slot
+/// being recorded
+fn record_macro_actions(
+    mut actions: MessageReader<PlayerAction>,
+    mut library: ResMut<MacroLibrary>,
+) {
+    let Some(slot) = library.recording else {
+        actions.clear();
+        return;
+    };
+
+    for action in actions.read() {
+        // Debug spawns and removals aren't part of a pattern
+        if matches!(
+            action,
+            PlayerAction::SpawnAnt { .. } | PlayerAction::RemoveAnt { .. }
+        ) {
+            continue;
+        }
+        library.slots[slot].push(action);
+    }
+}
+
+/// Play a slot back with F1..F4, its first tile at the cursor
+fn play_macro(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    cursor_tile: Res<CursorTile>,
+    library: Res<MacroLibrary>,
+    mut actions: MessageWriter<PlayerAction>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        return;
+    }
+    let Some(slot) = SLOT_KEYS.iter().position(|key| keyboard.just_pressed(*key)) else {
+        return;
+    };
+    if library.recording == Some(slot) {
+        return;
+    }
+
+    let recorded = &library.slots[slot];
+    if recorded.steps.is_empty() {
+        notifications.write(Notification::info(format!(
+            "Macro F{} is empty (Shift+F{} records it)",
+            slot + 1,
+            slot + 1
+        )));
+        return;
+    }
+    let Some((x, y, z)) = cursor_tile.0 else {
+        return;
+    };
+
+    let steps = recorded.at(x, y, z);
+    info!("Playing macro F{}: {} steps", slot + 1, steps.len());
+    actions.write_batch(steps);
+}
@@ -1,45 +1,223 @@
-use bevy::prelude::*;
+//! The graphical game: a window, camera, menus and interface over the
+//! simulation in the `acre` library.
 
-mod ants;
-mod camera;
-mod pheromones;
-mod sprites;
-mod time_controls;
-mod ui;
-mod world;
+use bevy::log::LogPlugin;
+use bevy::prelude::*;
 
-use ants::AntPlugin;
-use camera::CameraPlugin;
-use pheromones::PheromonePlugin;
-use time_controls::TimeControlsPlugin;
-use ui::UiPlugin;
-use world::WorldPlugin;
+use acre::achievements::AchievementsPlugin;
+use acre::alerts::AlertsPlugin;
+use acre::animation::AnimationPlugin;
+use acre::atlas::AtlasPlugin;
+use acre::audit::{self, AuditPlugin};
+use acre::bench::{self, BenchPlugin};
+use acre::blueprints::BlueprintsPlugin;
+use acre::camera::CameraPlugin;
+use acre::cli::CliArgs;
+use acre::console::ConsolePlugin;
+use acre::controls::ControlsPlugin;
+use acre::cursor::CursorPlugin;
+use acre::difficulty::DifficultyPlugin;
+use acre::editor::EditorPlugin;
+use acre::event_log::{self, EventLogPlugin};
+use acre::history::HistoryPlugin;
+use acre::low_spec::LowSpecPlugin;
+use acre::macros::MacrosPlugin;
+use acre::maps::MapsPlugin;
+use acre::menu::MenuPlugin;
+use acre::metrics::MetricsPlugin;
+use acre::multiplayer::{self, MultiplayerPlugin, SharedStart};
+use acre::music::MusicPlugin;
+use acre::notifications::NotificationsPlugin;
+use acre::palette::PalettePlugin;
+use acre::particles::ParticlesPlugin;
+use acre::performance::PerformancePlugin;
+use acre::poster::PosterPlugin;
+use acre::profile::ProfilePlugin;
+#[cfg(feature = "prometheus")]
+use acre::prometheus::PrometheusPlugin;
+use acre::recording::{self, RecordingPlugin};
+#[cfg(feature = "remote")]
+use acre::remote::RemotePlugin;
+use acre::rewind::RewindPlugin;
+use acre::sandbox::SandboxPlugin;
+use acre::scenarios::ScenarioPlugin;
+use acre::screenshots::ScreenshotPlugin;
+use acre::scripting::ScriptingPlugin;
+use acre::setup::SetupPlugin;
+use acre::soak::SoakPlugin;
+#[cfg(feature = "spectator")]
+use acre::spectator::SpectatorPlugin;
+use acre::statistics::StatisticsPlugin;
+use acre::summary::SummaryPlugin;
+use acre::timelapse::TimelapsePlugin;
+use acre::touch::TouchPlugin;
+use acre::ui::UiPlugin;
+use acre::world::{FoodStores, WorldOptions};
+use acre::{GameState, SimulationPlugin};
 
 fn main() {
-    App::new()
-        .add_plugins(DefaultPlugins.set(WindowPlugin {
-            primary_window: Some(Window {
-                title: "Acre - Ant Colony Simulation".to_string(),
-                resolution: (1280, 720).into(),
+    let cli = CliArgs::parse();
+
+    let mut app = App::new();
+    // The logging layers read the arguments while the plugins are built
+    app.insert_resource(cli.clone());
+    if cli.is_headless() {
+        // Audits, benchmarks, and headless soaks and remote runs skip the menus
+        let mut plugins = audit::headless_plugins();
+        if cli.bench {
+            plugins = plugins.set(LogPlugin {
+                custom_layer: bench::system_timing_layer,
                 ..default()
-            }),
-            ..default()
-        }))
-        .init_state::<GameState>()
+            });
+        }
+        // A remote-controlled run only moves when its scripts step it
+        let state = if cli.remote.is_some() {
+            GameState::Paused
+        } else {
+            GameState::Playing
+        };
+        app.add_plugins(plugins).insert_state(state);
+    } else {
+        app.add_plugins(
+            DefaultPlugins
+                .set(WindowPlugin {
+                    primary_window: Some(Window {
+                        title: "Acre - Ant Colony Simulation".to_string(),
+                        resolution: (1280, 720).into(),
+                        // Fill the page in a web build
+                        fit_canvas_to_parent: true,
+                        ..default()
+                    }),
+                    ..default()
+                })
+                .set(LogPlugin {
+                    custom_layer: event_log::event_log_layer,
+                    fmt_layer: event_log::console_layer,
+                    ..default()
+                }),
+        );
+        if cli.soak.is_some() || cli.coop.is_some() || cli.replay.is_some() {
+            // Soaks, co-op games and replays skip the menus even with a window
+            app.insert_state(GameState::Playing);
+        } else {
+            app.init_state::<GameState>();
+        }
+    }
+
+    let mut seed = cli
+        .seed
+        .or(cli.bench.then_some(bench::BENCH_SEED))
+        .unwrap_or_else(rand::random);
+    let mut options = WorldOptions::default();
+    if let Some(depth) = cli.water_table {
+        options.water_table_depth = depth;
+    }
+    options.founding = cli.founding;
+    if cli.bench_large {
+        options.starting_workers = bench::LARGE_COLONY_WORKERS;
+    }
+    options.map = cli.map.clone();
+    let mut biome = cli.biome;
+    if let Some(path) = &cli.replay {
+        // A replay starts in the world it was recorded in
+        match recording::read_start(path) {
+            Ok(start) => {
+                seed = start.seed;
+                biome = start.biome;
+                options = start.options;
+                app.insert_resource(start.difficulty)
+                    .insert_resource(FoodStores {
+                        food: start.difficulty.starting_food(),
+                        ..default()
+                    });
+            }
+            Err(error) => {
+                eprintln!("Failed to read recording {}: {}", path.display(), error);
+                std::process::exit(1);
+            }
+        }
+    }
+    if let Some(role) = &cli.coop {
+        // Both players generate the host's world
+        let start = SharedStart {
+            seed,
+            biome,
+            water_table_depth: options.water_table_depth,
+            founding: options.founding,
+        };
+        match multiplayer::connect(role, start) {
+            Ok((start, lockstep)) => {
+                seed = start.seed;
+                biome = start.biome;
+                options.water_table_depth = start.water_table_depth;
+                options.founding = start.founding;
+                app.insert_resource(lockstep);
+            }
+            Err(error) => {
+                eprintln!("{}", error);
+                std::process::exit(1);
+            }
+        }
+    }
+    app.insert_resource(biome)
+        .insert_resource(options)
+        .add_plugins(SimulationPlugin { seed })
+        // Input
+        .add_plugins((CameraPlugin, CursorPlugin, TouchPlugin, ControlsPlugin))
+        // Interface and tooling
         .add_plugins((
-            WorldPlugin,
-            CameraPlugin,
-            TimeControlsPlugin,
-            AntPlugin,
-            PheromonePlugin,
+            BlueprintsPlugin,
+            MacrosPlugin,
+            RecordingPlugin,
+            RewindPlugin,
+            NotificationsPlugin,
+            AlertsPlugin,
+            EventLogPlugin,
+            LowSpecPlugin,
+            SummaryPlugin,
+            HistoryPlugin,
+            MenuPlugin,
+            SetupPlugin,
+            DifficultyPlugin,
             UiPlugin,
         ))
-        .run();
-}
-
-#[derive(States, Debug, Clone, PartialEq, Eq, Hash, Default)]
-pub enum GameState {
-    #[default]
-    Running,
-    Paused,
+        // Game modes
+        .add_plugins((
+            ScenarioPlugin,
+            SandboxPlugin,
+            MapsPlugin,
+            EditorPlugin,
+            ScriptingPlugin,
+            MultiplayerPlugin,
+        ))
+        // Player profile
+        .add_plugins((ProfilePlugin, AchievementsPlugin, StatisticsPlugin))
+        // Diagnostics
+        .add_plugins((
+            PerformancePlugin,
+            AuditPlugin,
+            SoakPlugin,
+            BenchPlugin,
+            MetricsPlugin,
+            ConsolePlugin,
+        ))
+        // Presentation
+        .add_plugins((
+            AtlasPlugin,
+            AnimationPlugin,
+            ParticlesPlugin,
+            MusicPlugin,
+            PalettePlugin,
+            ScreenshotPlugin,
+            TimelapsePlugin,
+            PosterPlugin,
+        ));
+    // Remote viewers, control and monitoring, in builds that include them
+    #[cfg(feature = "spectator")]
+    app.add_plugins(SpectatorPlugin);
+    #[cfg(feature = "remote")]
+    app.add_plugins(RemotePlugin);
+    #[cfg(feature = "prometheus")]
+    app.add_plugins(PrometheusPlugin);
+    app.run();
 }
@@ -0,0 +1,344 @@
+//! Map files: hand-made worlds to play on instead of generated ones.
+//!
+//! The world editor (see `editor.rs`) saves maps to `maps/`, and `--map
+//! <file>` or a scenario's `map` starts the game on one. A map is plain text:
+//! a header giving the format version, world size, biome and nest position,
+//! then one block of rows per z-level with a character for each tile, then
+//! the soil beneath the surface the same way:
+//!
+//! ```text
+//! acre-map 1
+//! size 64
+//! biome Forest
+//! nest 32 32
+//! tiles 0
+//! ################...
+//! ...
+//! soil 0
+//! llllccccsssslll...
+//! ```
+//!
+//! Only the tiles are stored. Trees, bushes and grass come back from their
+//! tiles when a map is loaded (fresh, with new lifespans), and garden tiles
+//! are replanted empty. Saplings aren't kept.
+
+use std::fs;
+use std::io;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ants::{Ant, NestLocation, spawn_start};
+use crate::biome::Biome;
+use crate::content::Content;
+use crate::rng::SimRng;
+use crate::world::{
+    FungusGarden, Plant, PlantKind, SURFACE_LEVEL, Sapling, Soil, TileKind, WORLD_SIZE, WorldGrid,
+    WorldOptions, plant_tree,
+};
+
+pub struct MapsPlugin;
+
+impl Plugin for MapsPlugin {
+    fn build(&self, app: &mut App) {
+        // After the startup world is generated, so the map replaces it
+        app.add_systems(PostStartup, load_start_map);
+    }
+}
+
+/// Directory the editor saves maps to
+pub const MAPS_DIR: &str = "maps";
+/// Version written in the header; maps from newer versions are refused
+const MAP_VERSION: u32 = 1;
+
+/// Tile kinds and the characters they're written as
+const TILE_CHARS: [(TileKind, char); 15] = [
+    (TileKind::Air, '.'),
+    (TileKind::Surface, '_'),
+    (TileKind::Entrance, 'E'),
+    (TileKind::Dirt, '#'),
+    (TileKind::Tunnel, 't'),
+    (TileKind::Ramp, 'r'),
+    (TileKind::Chamber, 'c'),
+    (TileKind::FungusGarden, 'f'),
+    (TileKind::TreeTrunk, 'T'),
+    (TileKind::TreeCanopy, 'C'),
+    (TileKind::Deadwood, 'd'),
+    (TileKind::Bush, 'b'),
+    (TileKind::Grass, 'g'),
+    (TileKind::Root, 'R'),
+    (TileKind::Rock, 'X'),
+];
+/// Soil layers and the characters they're written as
+const SOIL_CHARS: [(Soil, char); 3] = [(Soil::Loam, 'l'), (Soil::Clay, 'c'), (Soil::Sand, 's')];
+
+/// A world read from a map file
+pub struct MapFile {
+    pub biome: Biome,
+    /// Surface position the colony starts at
+    pub nest: (usize, usize),
+    pub grid: WorldGrid,
+}
+
+fn tile_char(kind: TileKind) -> char {
+    TILE_CHARS
+        .iter()
+        .find(|(k, _)| *k == kind)
+        .map_or('.', |(_, c)| *c)
+}
+
+fn char_tile(c: char) -> Option<TileKind> {
+    TILE_CHARS.iter().find(|(_, ch)| *ch == c).map(|(k, _)| *k)
+}
+
+fn soil_char(soil: Soil) -> char {
+    SOIL_CHARS
+        .iter()
+        .find(|(s, _)| *s == soil)
+        .map_or('l', |(_, c)| *c)
+}
+
+fn char_soil(c: char) -> Option<Soil> {
+    SOIL_CHARS.iter().find(|(_, ch)| *ch == c).map(|(s, _)| *s)
+}
+
+/// Write a world out as map text
+pub fn write_map(world_grid: &WorldGrid, biome: Biome, (nest_x, nest_y): (usize, usize)) -> String {
+    let mut text = format!(
+        "acre-map {}\nsize {}\nbiome {}\nnest {} {}\n",
+        MAP_VERSION,
+        WORLD_SIZE,
+        biome.name(),
+        nest_x,
+        nest_y
+    );
+    for (z, level) in world_grid.tiles.iter().enumerate() {
+        text.push_str(&format!("tiles {}\n", z));
+        for row in level.iter() {
+            text.extend(row.iter().map(|tile| tile_char(*tile)));
+            text.push('\n');
+        }
+    }
+    for (z, level) in world_grid.soil[..SURFACE_LEVEL].iter().enumerate() {
+        text.push_str(&format!("soil {}\n", z));
+        for row in level.iter() {
+            text.extend(row.iter().map(|soil| soil_char(*soil)));
+            text.push('\n');
+        }
+    }
+    text
+}
+
+/// The value on a `key value` header line
+fn header<'a>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    key: &str,
+) -> Result<String, String> {
+    let (number, line) = lines.next().ok_or("unexpected end of file")?;
+    line.strip_prefix(key)
+        .and_then(|rest| rest.strip_prefix(' '))
+        .map(str::to_string)
+        .ok_or_else(|| format!("line {}: expected '{} ...'", number, key))
+}
+
+/// A row of a z-level, one character per tile
+fn row<'a, T>(
+    lines: &mut impl Iterator<Item = (usize, &'a str)>,
+    parse: fn(char) -> Option<T>,
+) -> Result<Vec<T>, String> {
+    let (number, line) = lines.next().ok_or("unexpected end of file")?;
+    let row: Vec<T> = line
+        .chars()
+        .map(|c| parse(c).ok_or_else(|| format!("line {}: unknown character '{}'", number, c)))
+        .collect::<Result<_, _>>()?;
+    if row.len() != WORLD_SIZE {
+        return Err(format!("line {}: expected {} tiles", number, WORLD_SIZE));
+    }
+    Ok(row)
+}
+
+/// Read a world from map text
+pub fn read_map(text: &str) -> Result<MapFile, String> {
+    let lines = &mut text
+        .lines()
+        .enumerate()
+        .map(|(i, line)| (i + 1, line.trim_end()));
+
+    let version: u32 = header(lines, "acre-map")?
+        .parse()
+        .map_err(|_| "bad format version".to_string())?;
+    if version > MAP_VERSION {
+        return Err(format!("map version {} is newer than this game", version));
+    }
+    let size: usize = header(lines, "size")?
+        .parse()
+        .map_err(|_| "bad world size".to_string())?;
+    if size != WORLD_SIZE {
+        return Err(format!(
+            "map is {} tiles across, but the world is {}",
+            size, WORLD_SIZE
+        ));
+    }
+    let biome_name = header(lines, "biome")?;
+    let biome =
+        Biome::from_name(&biome_name).ok_or_else(|| format!("unknown biome '{}'", biome_name))?;
+    let nest = header(lines, "nest")?;
+    let nest = match nest
+        .split_whitespace()
+        .map(str::parse::<usize>)
+        .collect::<Result<Vec<_>, _>>()
+        .as_deref()
+    {
+        Ok([x, y]) if *x < WORLD_SIZE && *y < WORLD_SIZE => (*x, *y),
+        _ => return Err(format!("bad nest position '{}'", nest)),
+    };
+
+    let mut grid = WorldGrid::default();
+    for z in 0..WORLD_SIZE {
+        let level = header(lines, "tiles")?;
+        if level != z.to_string() {
+            return Err(format!("expected tiles for z-level {}, found {}", z, level));
+        }
+        for y in 0..WORLD_SIZE {
+            grid.tiles[z][y].copy_from_slice(&row(lines, char_tile)?);
+        }
+    }
+    for z in 0..SURFACE_LEVEL {
+        let level = header(lines, "soil")?;
+        if level != z.to_string() {
+            return Err(format!("expected soil for z-level {}, found {}", z, level));
+        }
+        for y in 0..WORLD_SIZE {
+            grid.soil[z][y].copy_from_slice(&row(lines, char_soil)?);
+        }
+    }
+
+    Ok(MapFile { biome, nest, grid })
+}
+
+/// Read a map file from disk
+pub fn load_map(path: &Path) -> Result<MapFile, String> {
+    if cfg!(target_arch = "wasm32") {
+        return Err("can't read files in a web build".to_string());
+    }
+    let text = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    read_map(&text)
+}
+
+/// Save map text to a new file in `maps/`, returning where it went
+pub fn save_map(text: &str) -> io::Result<PathBuf> {
+    // There's no filesystem (or wall clock) in a web build
+    if cfg!(target_arch = "wasm32") {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "can't save files in a web build",
+        ));
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = PathBuf::from(MAPS_DIR).join(format!("map-{}.map", timestamp));
+    fs::create_dir_all(MAPS_DIR)?;
+    fs::write(&path, text)?;
+    Ok(path)
+}
+
+/// Put a map's world in place: its tiles, biome and nest, with the plants
+/// and garden plots its tiles call for. Callers are responsible for
+/// despawning the plants that stood in the old world.
+#[allow(clippy::too_many_arguments)]
+pub fn install_map(
+    commands: &mut Commands,
+    map: MapFile,
+    world_grid: &mut WorldGrid,
+    fungus_garden: &mut FungusGarden,
+    biome: &mut Biome,
+    nest: &mut NestLocation,
+    rng: &mut impl Rng,
+    content: &Content,
+) {
+    *world_grid = map.grid;
+    *biome = map.biome;
+    *nest = NestLocation {
+        x: map.nest.0,
+        y: map.nest.1,
+        z: SURFACE_LEVEL,
+    };
+
+    fungus_garden.plots.clear();
+    for z in 0..SURFACE_LEVEL {
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                if world_grid.tiles[z][y][x] == TileKind::FungusGarden {
+                    // Plant it afresh through the chamber it was dug from
+                    world_grid.tiles[z][y][x] = TileKind::Chamber;
+                    fungus_garden.plant(world_grid, x, y, z);
+                }
+            }
+        }
+    }
+
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let kind = match world_grid.tiles[SURFACE_LEVEL + 1][y][x] {
+                TileKind::TreeTrunk => {
+                    let leaves = content.leaf_source(PlantKind::Tree, *biome);
+                    plant_tree(commands, world_grid, rng, x, y, leaves);
+                    continue;
+                }
+                TileKind::Bush => PlantKind::Bush,
+                TileKind::Grass => PlantKind::Grass,
+                _ => continue,
+            };
+            commands.spawn((Plant { kind, x, y }, content.leaf_source(kind, *biome)));
+        }
+    }
+}
+
+/// Trees, saplings and ants from the generated world a map replaces
+type LeftoverQuery<'w, 's> = Query<'w, 's, Entity, Or<(With<Plant>, With<Sapling>, With<Ant>)>>;
+
+/// Start on the map given with `--map`, in place of the generated world
+#[allow(clippy::too_many_arguments)]
+fn load_start_map(
+    mut commands: Commands,
+    leftover_query: LeftoverQuery,
+    mut world_grid: ResMut<WorldGrid>,
+    mut fungus_garden: ResMut<FungusGarden>,
+    mut biome: ResMut<Biome>,
+    mut nest: ResMut<NestLocation>,
+    mut options: ResMut<WorldOptions>,
+    mut rng: ResMut<SimRng>,
+    content: Res<Content>,
+) {
+    let Some(path) = options.map.clone() else {
+        return;
+    };
+    let map = match load_map(&path) {
+        Ok(map) => map,
+        Err(e) => {
+            error!("Failed to load map {}: {}", path.display(), e);
+            options.map = None;
+            return;
+        }
+    };
+
+    for entity in &leftover_query {
+        commands.entity(entity).despawn();
+    }
+    install_map(
+        &mut commands,
+        map,
+        &mut world_grid,
+        &mut fungus_garden,
+        &mut biome,
+        &mut nest,
+        &mut *rng,
+        &content,
+    );
+    spawn_start(&mut commands, &options, (nest.x, nest.y));
+    info!("Loaded map {}", path.display());
+}
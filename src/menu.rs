@@ -0,0 +1,816 @@
+//! Title screen and the flow between games.
+//!
+//! The game opens on a title screen offering New Game, Scenarios, Map editor,
+//! Achievements, Statistics, Continue (while a colony is in play), Settings
+//! and Quit. New Game goes to the setup screen for free play, Scenarios to a
+//! list of challenges (see `scenarios.rs`) that set the world up before the
+//! setup screen, Map editor to the world editor (see `editor.rs`), and
+//! Achievements and Statistics to what the player has done across all their
+//! games (see `achievements.rs` and `statistics.rs`). Start there
+//! goes through loading (waiting on the sprite atlas and music) into play, and
+//! a collapse or successful flight ends the game. Esc brings the
+//! title screen back from play or a finished game, and again returns to a game
+//! still in progress. Starting a new game after playing one clears the old
+//! colony away and generates a fresh world, so there's no need to relaunch.
+//!
+//! Pausing a game brings up a pause menu over it: Resume, Settings, Restart
+//! (the same seed, from the setup screen) and Quit. Restart and Quit throw the
+//! colony away, so they ask for confirmation first, which Enter gives and Esc
+//! takes back.
+//!
+//! Once a game is over, buttons under the summary card start again on the
+//! same seed (R) or a new one (N), both from the setup screen.
+
+use bevy::app::AppExit;
+use bevy::ecs::system::{SystemParam, SystemState};
+use bevy::prelude::*;
+
+use crate::ants::{NestLocation, spawn_start};
+use crate::atlas::SpriteAtlas;
+use crate::biome::Biome;
+use crate::camera::EdgeScroll;
+use crate::content::{Content, ContentFile, content_settled};
+use crate::difficulty::Difficulty;
+use crate::maps::{install_map, load_map};
+use crate::music::{Music, MusicLayer};
+use crate::rewind::RewindHistory;
+use crate::rng::SimRng;
+use crate::scenarios::ActiveScenario;
+use crate::sprites::Palette;
+use crate::time_controls::toggle_paused;
+use crate::ui::next_ui_scale;
+use crate::world::{
+    FoodStores, FungusGarden, WorldGrid, WorldOptions, dig_founding_chamber, generate_surface,
+    generate_underground,
+};
+use crate::{GameState, ResetRun, in_game};
+
+pub struct MenuPlugin;
+
+impl Plugin for MenuPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<GameSession>()
+            .add_systems(OnEnter(GameState::MainMenu), enter_main_menu)
+            .add_systems(OnExit(GameState::MainMenu), exit_main_menu)
+            .add_systems(
+                OnEnter(GameState::Setup),
+                new_world.run_if(|session: Res<GameSession>| session.played),
+            )
+            .add_systems(OnEnter(GameState::Loading), enter_loading)
+            .add_systems(OnExit(GameState::Loading), exit_loading)
+            .add_systems(OnEnter(GameState::Playing), start_session)
+            .add_systems(OnEnter(GameState::GameOver), end_session)
+            .add_systems(OnExit(GameState::GameOver), exit_game_over)
+            .add_systems(OnEnter(GameState::Paused), enter_pause_menu)
+            .add_systems(OnExit(GameState::Paused), exit_pause_menu)
+            .add_systems(Update, menu_input.run_if(in_state(GameState::MainMenu)))
+            .add_systems(Update, pause_menu_input.run_if(in_state(GameState::Paused)))
+            .add_systems(
+                Update,
+                (settings_input, update_settings_text)
+                    .chain()
+                    .run_if(in_state(GameState::MainMenu).or(in_state(GameState::Paused))),
+            )
+            .add_systems(Update, finish_loading.run_if(in_state(GameState::Loading)))
+            .add_systems(
+                Update,
+                game_over_input.run_if(in_state(GameState::GameOver)),
+            )
+            .add_systems(
+                Update,
+                // Before the dialog it might be answering is torn down
+                open_main_menu
+                    .before(pause_menu_input)
+                    .run_if(in_game.or(in_state(GameState::GameOver))),
+            );
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Where the current game stands, between trips to the title screen
+#[derive(Resource, Default)]
+pub struct GameSession {
+    /// State to go back to with Continue, while a game is in progress
+    pub resume: Option<GameState>,
+    /// Whether the world has been played on and has to be generated afresh
+    /// for the next game
+    pub played: bool,
+    /// Seed to generate the next world from when restarting, instead of a
+    /// random one
+    pub restart_seed: Option<u64>,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the title screen root
+#[derive(Component)]
+struct MainMenuScreen;
+
+/// Marker for the settings panel, hidden until Settings is pressed
+#[derive(Component)]
+struct SettingsPanel;
+
+/// Marker for the current settings line
+#[derive(Component)]
+struct SettingsText;
+
+/// Marker for the loading screen
+#[derive(Component)]
+struct LoadingScreen;
+
+/// Marker for the pause menu, and any dialog open over it
+#[derive(Component)]
+struct PauseMenuScreen;
+
+/// Marker for the buttons shown once a game is over
+#[derive(Component)]
+struct GameOverScreen;
+
+/// A dialog asking to confirm a pause menu button
+#[derive(Component)]
+struct ConfirmDialog(MenuButton);
+
+/// Buttons on the title screen, the pause menu, the game over screen and
+/// their settings panels
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum MenuButton {
+    NewGame,
+    Scenarios,
+    Editor,
+    Achievements,
+    Statistics,
+    Continue,
+    Resume,
+    Settings,
+    Restart,
+    Quit,
+    Palette,
+    UiScale,
+    Music,
+    EdgeScroll,
+    Confirm,
+    Cancel,
+    SameSeed,
+    NewSeed,
+}
+
+impl MenuButton {
+    const MAIN: [MenuButton; 8] = [
+        MenuButton::NewGame,
+        MenuButton::Scenarios,
+        MenuButton::Editor,
+        MenuButton::Achievements,
+        MenuButton::Statistics,
+        MenuButton::Continue,
+        MenuButton::Settings,
+        MenuButton::Quit,
+    ];
+    const PAUSE: [MenuButton; 4] = [
+        MenuButton::Resume,
+        MenuButton::Settings,
+        MenuButton::Restart,
+        MenuButton::Quit,
+    ];
+    const SETTINGS: [MenuButton; 4] = [
+        MenuButton::Palette,
+        MenuButton::UiScale,
+        MenuButton::Music,
+        MenuButton::EdgeScroll,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            MenuButton::NewGame => "New Game (Enter)",
+            MenuButton::Scenarios => "Scenarios (S)",
+            MenuButton::Editor => "Map editor (M)",
+            MenuButton::Achievements => "Achievements (A)",
+            MenuButton::Statistics => "Statistics (T)",
+            MenuButton::Continue => "Continue (Esc)",
+            MenuButton::Resume => "Resume (Space)",
+            MenuButton::Settings => "Settings (Z)",
+            MenuButton::Restart => "Restart (R)",
+            MenuButton::Quit => "Quit (Q)",
+            MenuButton::Palette => "Palette (Y)",
+            MenuButton::UiScale => "UI size (U)",
+            MenuButton::Music => "Music (K)",
+            MenuButton::EdgeScroll => "Edge scroll (E)",
+            MenuButton::Confirm => "Yes (Enter)",
+            MenuButton::Cancel => "No (Esc)",
+            MenuButton::SameSeed => "Same seed (R)",
+            MenuButton::NewSeed => "New seed (N)",
+        }
+    }
+
+    /// What a confirmation dialog asks before going ahead
+    fn confirmation(&self) -> &'static str {
+        match self {
+            MenuButton::Restart => "Restart from the setup screen? This colony will be lost.",
+            _ => "Quit the game? This colony will be lost.",
+        }
+    }
+}
+
+/// Whether a button belongs in this build; there's nothing to quit to in a
+/// web build
+fn available(button: &MenuButton) -> bool {
+    *button != MenuButton::Quit || !cfg!(target_arch = "wasm32")
+}
+
+/// Spawn a column of menu buttons
+fn spawn_buttons(parent: &mut ChildSpawnerCommands, buttons: &[MenuButton]) {
+    for &button in buttons {
+        parent
+            .spawn((
+                button,
+                Button,
+                Node {
+                    padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                    ..default()
+                },
+                BackgroundColor(Color::srgb(0.25, 0.3, 0.25)),
+            ))
+            .with_child((
+                Text::new(button.label()),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+    }
+}
+
+/// Spawn the settings panel, hidden until Settings is pressed
+fn spawn_settings_panel(parent: &mut ChildSpawnerCommands) {
+    parent
+        .spawn((
+            SettingsPanel,
+            Node {
+                display: Display::None,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                margin: UiRect::top(Val::Px(8.0)),
+                ..default()
+            },
+        ))
+        .with_children(|panel| {
+            panel.spawn((
+                SettingsText,
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.8, 0.9, 0.8, 1.0)),
+            ));
+            spawn_buttons(panel, &MenuButton::SETTINGS);
+        });
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Hold the simulation and show the title screen
+fn enter_main_menu(
+    mut commands: Commands,
+    session: Res<GameSession>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    time.pause();
+
+    let buttons: Vec<MenuButton> = MenuButton::MAIN
+        .into_iter()
+        .filter(|button| *button != MenuButton::Continue || session.resume.is_some())
+        .filter(available)
+        .collect();
+
+    commands
+        .spawn((
+            MainMenuScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(35.0),
+                top: Val::Percent(20.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Acre"),
+                TextFont {
+                    font_size: 32.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent.spawn((
+                Text::new("An ant colony simulation"),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.8, 0.9, 0.8, 1.0)),
+            ));
+            spawn_buttons(parent, &buttons);
+            spawn_settings_panel(parent);
+        });
+}
+
+/// Tear down the title screen
+fn exit_main_menu(mut commands: Commands, screen_query: Query<Entity, With<MainMenuScreen>>) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Start, continue or quit from keys or buttons
+#[allow(clippy::too_many_arguments)]
+fn menu_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    button_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut session: ResMut<GameSession>,
+    mut scenario: ResMut<ActiveScenario>,
+    mut options: ResMut<WorldOptions>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let pressed = |target: MenuButton| {
+        button_query
+            .iter()
+            .any(|(interaction, button)| *interaction == Interaction::Pressed && *button == target)
+    };
+
+    if keyboard.just_pressed(KeyCode::Enter) || pressed(MenuButton::NewGame) {
+        session.resume = None;
+        scenario.0 = None;
+        // A map only carries over to the world it was loaded into
+        if session.played {
+            options.map = None;
+        }
+        next_state.set(GameState::Setup);
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyS) || pressed(MenuButton::Scenarios) {
+        next_state.set(GameState::ScenarioSelect);
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyM) || pressed(MenuButton::Editor) {
+        scenario.0 = None;
+        next_state.set(GameState::Editor);
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyA) || pressed(MenuButton::Achievements) {
+        next_state.set(GameState::Achievements);
+        return;
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyT) || pressed(MenuButton::Statistics) {
+        next_state.set(GameState::Statistics);
+        return;
+    }
+
+    if (keyboard.just_pressed(KeyCode::Escape) || pressed(MenuButton::Continue))
+        && let Some(state) = session.resume.take()
+    {
+        if state == GameState::Playing {
+            time.unpause();
+        }
+        next_state.set(state);
+        return;
+    }
+
+    if (keyboard.just_pressed(KeyCode::KeyQ) || pressed(MenuButton::Quit))
+        && available(&MenuButton::Quit)
+    {
+        exit.write(AppExit::Success);
+    }
+}
+
+/// Open and close the settings panel with Z or its button, and change
+/// settings from its buttons
+fn settings_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    button_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    mut panel_query: Query<&mut Node, With<SettingsPanel>>,
+    mut palette: ResMut<Palette>,
+    mut ui_scale: ResMut<UiScale>,
+    mut music: ResMut<Music>,
+    mut edge: ResMut<EdgeScroll>,
+) {
+    let pressed = |target: MenuButton| {
+        button_query
+            .iter()
+            .any(|(interaction, button)| *interaction == Interaction::Pressed && *button == target)
+    };
+
+    if keyboard.just_pressed(KeyCode::KeyZ) || pressed(MenuButton::Settings) {
+        for mut node in &mut panel_query {
+            node.display = match node.display {
+                Display::None => Display::Flex,
+                _ => Display::None,
+            };
+        }
+    }
+
+    // The keys for these already work everywhere; the buttons are for mice
+    if pressed(MenuButton::Palette) {
+        *palette = palette.next();
+    }
+    if pressed(MenuButton::UiScale) {
+        ui_scale.0 = next_ui_scale(ui_scale.0);
+    }
+    if pressed(MenuButton::Music) {
+        music.muted = !music.muted;
+    }
+    if pressed(MenuButton::EdgeScroll) {
+        edge.enabled = !edge.enabled;
+    }
+}
+
+/// Show the current settings
+fn update_settings_text(
+    palette: Res<Palette>,
+    ui_scale: Res<UiScale>,
+    music: Res<Music>,
+    edge: Res<EdgeScroll>,
+    mut query: Query<&mut Text, With<SettingsText>>,
+) {
+    let on_off = |on: bool| if on { "on" } else { "off" };
+    for mut text in &mut query {
+        **text = format!(
+            "Palette: {} | UI size: {:.0}%\nMusic: {} | Edge scroll: {}",
+            palette.name(),
+            ui_scale.0 * 100.0,
+            on_off(!music.muted),
+            on_off(edge.enabled)
+        );
+    }
+}
+
+/// Everything generating a new world reads and writes
+#[derive(SystemParam)]
+struct WorldSetup<'w, 's> {
+    commands: Commands<'w, 's>,
+    world_grid: ResMut<'w, WorldGrid>,
+    fungus_garden: ResMut<'w, FungusGarden>,
+    food_stores: ResMut<'w, FoodStores>,
+    rng: ResMut<'w, SimRng>,
+    biome: ResMut<'w, Biome>,
+    nest: ResMut<'w, NestLocation>,
+    options: ResMut<'w, WorldOptions>,
+    difficulty: Res<'w, Difficulty>,
+    content: Res<'w, Content>,
+}
+
+/// Clear away the last colony (see `ResetRun`) and generate a fresh world to
+/// set up, or load the chosen map
+pub fn new_world(world: &mut World) {
+    world.run_schedule(ResetRun);
+
+    let mut session = world.resource_mut::<GameSession>();
+    session.played = false;
+    let seed = session.restart_seed.take().unwrap_or_else(rand::random);
+
+    let mut state: SystemState<WorldSetup> = SystemState::new(world);
+    let WorldSetup {
+        mut commands,
+        mut world_grid,
+        mut fungus_garden,
+        mut food_stores,
+        mut rng,
+        mut biome,
+        mut nest,
+        mut options,
+        difficulty,
+        content,
+    } = state.get_mut(world);
+
+    rng.reseed(seed);
+    let map = options.map.clone().and_then(|path| match load_map(&path) {
+        Ok(map) => Some(map),
+        Err(e) => {
+            error!("Failed to load map {}: {}", path.display(), e);
+            None
+        }
+    });
+    match map {
+        Some(map) => install_map(
+            &mut commands,
+            map,
+            &mut world_grid,
+            &mut fungus_garden,
+            &mut biome,
+            &mut nest,
+            &mut *rng,
+            &content,
+        ),
+        None => {
+            options.map = None;
+            // Same order as at startup, so the seed reproduces this map
+            generate_surface(
+                &mut commands,
+                &mut world_grid,
+                &mut *rng,
+                *biome,
+                &content,
+                options.tree_count(*biome),
+            );
+            generate_underground(&mut world_grid, &mut *rng, *biome);
+            if !options.founding {
+                dig_founding_chamber(&mut world_grid, &mut fungus_garden);
+            }
+        }
+    }
+    // A founding queen starts with nothing in the stores
+    food_stores.food = if options.founding {
+        0
+    } else {
+        difficulty.starting_food()
+    };
+    spawn_start(&mut commands, &options, (nest.x, nest.y));
+
+    state.apply(world);
+    info!("Generated a new world");
+}
+
+/// Show a loading screen while the assets come in
+fn enter_loading(mut commands: Commands) {
+    commands
+        .spawn((
+            LoadingScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(45.0),
+                top: Val::Percent(45.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_child((
+            Text::new("Loading..."),
+            TextFont {
+                font_size: 18.0,
+                ..default()
+            },
+            TextColor(Color::WHITE),
+        ));
+}
+
+/// Tear down the loading screen and let the simulation run
+fn exit_loading(
+    mut commands: Commands,
+    screen_query: Query<Entity, With<LoadingScreen>>,
+    mut time: ResMut<Time<Virtual>>,
+) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn();
+    }
+    time.unpause();
+    info!("Starting simulation");
+}
+
+/// Start playing once the atlas, content definitions and music have loaded,
+/// or failed to
+fn finish_loading(
+    asset_server: Res<AssetServer>,
+    atlas: Option<Res<SpriteAtlas>>,
+    content_file: Option<Res<ContentFile>>,
+    music_query: Query<&AudioPlayer, With<MusicLayer>>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let atlas_done = atlas.is_none_or(|atlas| atlas.ready || atlas.failed);
+    let content_done = content_settled(content_file);
+    let music_done = music_query.iter().all(|player| {
+        asset_server.is_loaded_with_dependencies(&player.0)
+            || asset_server.load_state(&player.0).is_failed()
+    });
+    if atlas_done && content_done && music_done {
+        next_state.set(GameState::Playing);
+    }
+}
+
+/// Show the pause menu over the frozen game
+fn enter_pause_menu(mut commands: Commands, history: Res<RewindHistory>) {
+    // Rewinding pauses the game, but shows its own banner instead
+    if history.rewound() {
+        return;
+    }
+    let buttons: Vec<MenuButton> = MenuButton::PAUSE.into_iter().filter(available).collect();
+
+    commands
+        .spawn((
+            PauseMenuScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(40.0),
+                top: Val::Percent(20.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Paused"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            spawn_buttons(parent, &buttons);
+            spawn_settings_panel(parent);
+        });
+}
+
+/// Tear down the pause menu and any open dialog
+fn exit_pause_menu(mut commands: Commands, screen_query: Query<Entity, With<PauseMenuScreen>>) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Resume, restart or quit from the pause menu, confirming the last two
+#[allow(clippy::too_many_arguments)]
+fn pause_menu_input(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    button_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    dialog_query: Query<(Entity, &ConfirmDialog)>,
+    state: Res<State<GameState>>,
+    rng: Res<SimRng>,
+    mut session: ResMut<GameSession>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    let pressed = |target: MenuButton| {
+        button_query
+            .iter()
+            .any(|(interaction, button)| *interaction == Interaction::Pressed && *button == target)
+    };
+
+    // An open dialog takes the answer before anything else
+    if let Ok((dialog, confirm)) = dialog_query.single() {
+        if keyboard.just_pressed(KeyCode::Escape) || pressed(MenuButton::Cancel) {
+            commands.entity(dialog).despawn();
+        } else if keyboard.just_pressed(KeyCode::Enter) || pressed(MenuButton::Confirm) {
+            commands.entity(dialog).despawn();
+            match confirm.0 {
+                MenuButton::Restart => {
+                    session.resume = None;
+                    session.restart_seed = Some(rng.seed);
+                    next_state.set(GameState::Setup);
+                    info!("Restarting seed {}", rng.seed);
+                }
+                _ => {
+                    exit.write(AppExit::Success);
+                }
+            }
+        }
+        return;
+    }
+
+    if pressed(MenuButton::Resume) {
+        toggle_paused(state.get(), &mut next_state, &mut time);
+    }
+    for (key, action) in [
+        (KeyCode::KeyR, MenuButton::Restart),
+        (KeyCode::KeyQ, MenuButton::Quit),
+    ] {
+        if (keyboard.just_pressed(key) || pressed(action)) && available(&action) {
+            spawn_confirm_dialog(&mut commands, action);
+            return;
+        }
+    }
+}
+
+/// Ask before a pause menu button throws the colony away
+fn spawn_confirm_dialog(commands: &mut Commands, action: MenuButton) {
+    commands
+        .spawn((
+            PauseMenuScreen,
+            ConfirmDialog(action),
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(30.0),
+                top: Val::Percent(40.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.1, 0.0, 0.0, 0.95)),
+            // Over the pause menu
+            GlobalZIndex(1),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(action.confirmation()),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(8.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    spawn_buttons(row, &[MenuButton::Confirm, MenuButton::Cancel]);
+                });
+        });
+}
+
+/// Note that a game is under way, so the next new game starts from scratch
+fn start_session(mut session: ResMut<GameSession>) {
+    session.played = true;
+}
+
+/// A finished game can't be continued, only started again
+fn end_session(mut commands: Commands, mut session: ResMut<GameSession>) {
+    session.resume = None;
+
+    commands
+        .spawn((
+            GameOverScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(30.0),
+                bottom: Val::Percent(8.0),
+                column_gap: Val::Px(8.0),
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            spawn_buttons(parent, &[MenuButton::SameSeed, MenuButton::NewSeed]);
+        });
+}
+
+/// Tear down the game over buttons
+fn exit_game_over(mut commands: Commands, screen_query: Query<Entity, With<GameOverScreen>>) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Start again from the setup screen, on the same seed or a new one
+fn game_over_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    button_query: Query<(&Interaction, &MenuButton), Changed<Interaction>>,
+    rng: Res<SimRng>,
+    mut session: ResMut<GameSession>,
+    mut options: ResMut<WorldOptions>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pressed = |target: MenuButton| {
+        button_query
+            .iter()
+            .any(|(interaction, button)| *interaction == Interaction::Pressed && *button == target)
+    };
+
+    if keyboard.just_pressed(KeyCode::KeyR) || pressed(MenuButton::SameSeed) {
+        session.restart_seed = Some(rng.seed);
+        next_state.set(GameState::Setup);
+        info!("Restarting seed {}", rng.seed);
+    } else if keyboard.just_pressed(KeyCode::KeyN) || pressed(MenuButton::NewSeed) {
+        session.restart_seed = None;
+        options.map = None;
+        next_state.set(GameState::Setup);
+    }
+}
+
+/// Go back to the title screen with Esc, unless it's answering a dialog
+fn open_main_menu(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    dialog_query: Query<(), With<ConfirmDialog>>,
+    state: Res<State<GameState>>,
+    mut session: ResMut<GameSession>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if !keyboard.just_pressed(KeyCode::Escape) || !dialog_query.is_empty() {
+        return;
+    }
+
+    session.resume = state.get().in_game().then(|| state.get().clone());
+    next_state.set(GameState::MainMenu);
+}
@@ -0,0 +1,195 @@
+//! Metrics export for analysis outside the game.
+//!
+//! `--export-metrics <csv|json>` streams the colony history to a file under
+//! `metrics/` as the game runs: once per in-game day it appends a row with the
+//! population by caste, the stores, the garden, deaths by cause, and the
+//! pheromone totals. CSV opens straight in a spreadsheet; JSON is written one
+//! object per line, ready for a notebook to read as it grows.
+
+use std::fs::{self, File};
+use std::io::Write;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::ants::DeathCause;
+use crate::cli::CliArgs;
+use crate::history::{CASTES, ColonyHistory, HistorySample};
+use crate::pheromones::PheromoneType;
+use crate::time_controls::TICKS_PER_DAY;
+
+pub struct MetricsPlugin;
+
+impl Plugin for MetricsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_export).add_systems(
+            Update,
+            export_metrics.run_if(resource_exists::<MetricsExport>),
+        );
+    }
+}
+
+/// Directory metrics files are written to
+const METRICS_DIR: &str = "metrics";
+/// File format for exported metrics
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum MetricsFormat {
+    Csv,
+    /// One JSON object per line
+    Json,
+}
+
+impl MetricsFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name {
+            "csv" => Some(MetricsFormat::Csv),
+            "json" => Some(MetricsFormat::Json),
+            _ => None,
+        }
+    }
+
+    fn extension(self) -> &'static str {
+        match self {
+            MetricsFormat::Csv => "csv",
+            MetricsFormat::Json => "jsonl",
+        }
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// The metrics file being written
+#[derive(Resource)]
+struct MetricsExport {
+    format: MetricsFormat,
+    file: File,
+    path: PathBuf,
+    /// Day the last row was written for
+    last_day: Option<u64>,
+}
+
+/// Column names and values for a sample, in export order
+pub fn fields(sample: &HistorySample) -> Vec<(String, String)> {
+    let mut fields = vec![
+        (
+            String::from("day"),
+            (sample.tick / TICKS_PER_DAY as u64).to_string(),
+        ),
+        (String::from("tick"), sample.tick.to_string()),
+        (String::from("population"), sample.population().to_string()),
+    ];
+    for (caste, count) in CASTES.iter().zip(sample.castes) {
+        fields.push((caste.name().to_lowercase(), count.to_string()));
+    }
+    fields.extend([
+        (String::from("food"), sample.food.to_string()),
+        (String::from("honeydew"), sample.honeydew.to_string()),
+        (String::from("seeds"), sample.seeds.to_string()),
+        (String::from("leaves"), sample.leaves.to_string()),
+        (String::from("mulch"), sample.mulch.to_string()),
+    ]);
+    for (cause, count) in DeathCause::ALL.iter().zip(sample.deaths) {
+        fields.push((format!("deaths_{}", cause.name()), count.to_string()));
+    }
+    for (ptype, total) in PheromoneType::ALL.iter().zip(sample.pheromones) {
+        fields.push((
+            format!("pheromone_{}", ptype.name().to_lowercase()),
+            format!("{:.1}", total),
+        ));
+    }
+    fields
+}
+
+/// Fields as a single-line JSON object
+pub fn to_json(fields: Vec<(String, String)>) -> String {
+    let pairs: Vec<String> = fields
+        .into_iter()
+        .map(|(name, value)| format!("\"{}\":{}", name, value))
+        .collect();
+    format!("{{{}}}", pairs.join(","))
+}
+
+/// Create the metrics file, writing the CSV header
+fn create_file(format: MetricsFormat) -> std::io::Result<(File, PathBuf)> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    fs::create_dir_all(METRICS_DIR)?;
+    let path =
+        PathBuf::from(METRICS_DIR).join(format!("metrics-{}.{}", timestamp, format.extension()));
+    let mut file = File::create(&path)?;
+    if format == MetricsFormat::Csv {
+        let header: Vec<String> = fields(&HistorySample::default())
+            .into_iter()
+            .map(|(name, _)| name)
+            .collect();
+        writeln!(file, "{}", header.join(","))?;
+    }
+    Ok((file, path))
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Open the metrics file when `--export-metrics` was passed
+fn start_export(mut commands: Commands, cli: Option<Res<CliArgs>>) {
+    let Some(format) = cli.and_then(|cli| cli.export_metrics) else {
+        return;
+    };
+
+    // There's no filesystem in a web build
+    if cfg!(target_arch = "wasm32") {
+        warn!("Metrics can't be exported in a web build");
+        return;
+    }
+
+    match create_file(format) {
+        Ok((file, path)) => {
+            info!("Exporting metrics to {}", path.display());
+            commands.insert_resource(MetricsExport {
+                format,
+                file,
+                path,
+                last_day: None,
+            });
+        }
+        Err(e) => error!("Failed to create a metrics file: {}", e),
+    }
+}
+
+/// Append the latest sample once each new in-game day has been sampled
+fn export_metrics(history: Res<ColonyHistory>, mut export: ResMut<MetricsExport>) {
+    if !history.is_changed() {
+        return;
+    }
+    let Some(sample) = history.samples.last() else {
+        return;
+    };
+
+    // A new game or a rewind takes the day back, and rows carry on from there
+    let day = sample.tick / TICKS_PER_DAY as u64;
+    if export.last_day == Some(day) {
+        return;
+    }
+
+    let fields = fields(sample);
+    let line = match export.format {
+        MetricsFormat::Csv => fields
+            .into_iter()
+            .map(|(_, value)| value)
+            .collect::<Vec<_>>()
+            .join(","),
+        MetricsFormat::Json => to_json(fields),
+    };
+
+    export.last_day = Some(day);
+    let written = writeln!(export.file, "{}", line).and_then(|()| export.file.flush());
+    if let Err(e) = written {
+        error!("Failed to write to {}: {}", export.path.display(), e);
+    }
+}
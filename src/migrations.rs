@@ -0,0 +1,462 @@
+//! Seasonal migrations across the surface.
+//!
+//! Herds of leaf beetles wander in from the map edge in spring and summer,
+//! grazing the same plants the foragers cut. Wolf spiders follow them in
+//! summer and autumn, hunting beetles first and lone foragers when the herd is
+//! gone, lurking at unguarded nest entrances when there's nothing else to
+//! catch. Soldiers at their posts drive spiders off. Everyone heads back out
+//! of the map when their season ends, so the surface keeps changing over a
+//! long game.
+
+use bevy::prelude::*;
+use rand::Rng;
+use serde::Deserialize;
+
+use crate::ants::{Ant, AntDied, Caste, DeathCause, GridPosition};
+use crate::atlas::AtlasSprite;
+use crate::config::SimulationConfig;
+use crate::content::Content;
+use crate::entrances::Entrances;
+use crate::low_spec::cosmetic_frame;
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::sprites;
+use crate::summary::RunStats;
+use crate::time_controls::{SimulationSet, SimulationTick, TICKS_PER_DAY};
+use crate::world::{CurrentZLevel, LeafSource, Plant, SURFACE_LEVEL, WORLD_SIZE, tile_to_world};
+use crate::{ResetRun, despawn_all};
+
+pub struct MigrationPlugin;
+
+impl Plugin for MigrationPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<CombatHit>()
+            .add_systems(Update, update_migrant_sprites.run_if(cosmetic_frame))
+            .add_systems(
+                FixedUpdate,
+                (
+                    migrant_arrival,
+                    migrant_departure,
+                    migrant_movement,
+                    beetle_grazing,
+                    spider_hunting,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, despawn_all::<Migrant>);
+    }
+}
+
+/// Per-tick chance a beetle herd arrives while they're in season
+const HERD_ARRIVAL_CHANCE: f64 = 1.0 / 3000.0;
+/// Most beetles on the map at once
+const MAX_BEETLES: usize = 10;
+/// Per-tick chance a spider arrives while they're in season, on Normal
+const SPIDER_ARRIVAL_CHANCE: f64 = 1.0 / 5000.0;
+/// Longest a migrant stays before moving on, even in season
+const MAX_STAY_TICKS: u32 = 3 * TICKS_PER_DAY;
+/// Ticks a beetle spends on each leaf it eats
+const GRAZE_TICKS: u32 = 40;
+/// Ticks a spider rests after a kill before hunting again
+const SPIDER_DIGEST_TICKS: u32 = 600;
+/// How far a spider notices prey
+const SPIDER_SIGHT: usize = 8;
+/// How close to a manned entrance a spider can get before the guards drive
+/// it off
+const GUARD_REACH: usize = 3;
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// The kinds of animal that migrate through
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum MigrantKind {
+    /// Leaf beetles: grazers competing with foragers for leaves
+    Beetle,
+    /// Wolf spiders: predators of beetles and ants
+    Spider,
+}
+
+impl MigrantKind {
+    pub const ALL: [MigrantKind; 2] = [MigrantKind::Beetle, MigrantKind::Spider];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            MigrantKind::Beetle => "beetle",
+            MigrantKind::Spider => "spider",
+        }
+    }
+}
+
+/// An animal passing through the surface
+#[derive(Component)]
+pub struct Migrant {
+    pub kind: MigrantKind,
+    pub x: usize,
+    pub y: usize,
+    /// Where it's heading next
+    pub target: (usize, usize),
+    /// Heading for the map edge to leave
+    pub leaving: bool,
+    /// Ticks spent on the map
+    pub ticks: u32,
+    /// Ticks left resting (grazing or digesting) before it moves on
+    pub rest: u32,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Herds and predators wander in from the map edge in their seasons
+fn migrant_arrival(
+    mut commands: Commands,
+    tick: Res<SimulationTick>,
+    migrant_query: Query<&Migrant>,
+    mut notifications: MessageWriter<Notification>,
+    config: Res<SimulationConfig>,
+    content: Res<Content>,
+    mut rng: ResMut<SimRng>,
+) {
+    let season = tick.season();
+    let count = |kind| migrant_query.iter().filter(|m| m.kind == kind).count();
+
+    if content.in_season(MigrantKind::Beetle, season)
+        && count(MigrantKind::Beetle) < MAX_BEETLES
+        && rng.random_bool(HERD_ARRIVAL_CHANCE)
+    {
+        let (x, y) = random_edge_tile(&mut *rng);
+        let target = random_interior_tile(&mut *rng);
+        for _ in 0..rng.random_range(config.herd_min..=config.herd_max) {
+            spawn_migrant(&mut commands, MigrantKind::Beetle, x, y, target);
+        }
+        notifications.write(Notification::info(
+            "A herd of leaf beetles has wandered in to graze",
+        ));
+    }
+
+    if content.in_season(MigrantKind::Spider, season)
+        && count(MigrantKind::Spider) < config.raid_strength
+        && rng.random_bool((SPIDER_ARRIVAL_CHANCE * config.predator_frequency).min(1.0))
+    {
+        let (x, y) = random_edge_tile(&mut *rng);
+        let target = random_interior_tile(&mut *rng);
+        spawn_migrant(&mut commands, MigrantKind::Spider, x, y, target);
+        notifications.write(Notification::warning(
+            "A wolf spider is prowling the surface - foragers beware",
+        ));
+    }
+}
+
+/// Out-of-season or long-staying migrants head for the nearest edge, and
+/// leave once they reach it
+fn migrant_departure(
+    mut commands: Commands,
+    tick: Res<SimulationTick>,
+    mut migrant_query: Query<(Entity, &mut Migrant)>,
+    content: Res<Content>,
+) {
+    let season = tick.season();
+
+    for (entity, mut migrant) in &mut migrant_query {
+        migrant.ticks += 1;
+
+        if !migrant.leaving
+            && (!content.in_season(migrant.kind, season) || migrant.ticks >= MAX_STAY_TICKS)
+        {
+            migrant.leaving = true;
+            migrant.target = nearest_edge_tile(migrant.x, migrant.y);
+        }
+
+        if migrant.leaving && (migrant.x, migrant.y) == migrant.target {
+            commands.entity(entity).despawn();
+            info!("A {} left the map", migrant.kind.name());
+        }
+    }
+}
+
+/// Migrants walk toward their target, picking a new one when they arrive
+fn migrant_movement(
+    mut migrant_query: Query<&mut Migrant>,
+    content: Res<Content>,
+    mut rng: ResMut<SimRng>,
+) {
+    for mut migrant in &mut migrant_query {
+        if migrant.rest > 0 {
+            migrant.rest -= 1;
+            continue;
+        }
+        if !migrant
+            .ticks
+            .is_multiple_of(content.migrant(migrant.kind).step_ticks)
+        {
+            continue;
+        }
+
+        if (migrant.x, migrant.y) == migrant.target {
+            if !migrant.leaving {
+                migrant.target = random_interior_tile(&mut *rng);
+            }
+            continue;
+        }
+
+        let (tx, ty) = migrant.target;
+        migrant.x = step(migrant.x, tx);
+        migrant.y = step(migrant.y, ty);
+    }
+}
+
+/// Beetles strip leaves off any plant they pass, staying to eat while there
+/// are leaves left
+fn beetle_grazing(
+    mut migrant_query: Query<&mut Migrant>,
+    mut plant_query: Query<(&Plant, &mut LeafSource)>,
+) {
+    for mut migrant in &mut migrant_query {
+        if migrant.kind != MigrantKind::Beetle || migrant.leaving || migrant.rest > 0 {
+            continue;
+        }
+
+        let plant = plant_query.iter_mut().find(|(plant, leaves)| {
+            leaves.leaves_remaining > 0
+                && plant.x.abs_diff(migrant.x) <= 1
+                && plant.y.abs_diff(migrant.y) <= 1
+        });
+        if let Some((plant, mut leaves)) = plant {
+            leaves.leaves_remaining -= 1;
+            // Linger at the plant while munching
+            migrant.target = (plant.x, plant.y);
+            migrant.rest = GRAZE_TICKS;
+        }
+    }
+}
+
+/// Spiders stalk the nearest beetle or surface ant and eat what they catch,
+/// staking out an unguarded entrance when nothing is in sight
+#[allow(clippy::too_many_arguments)]
+fn spider_hunting(
+    mut commands: Commands,
+    mut migrant_query: Query<(Entity, &mut Migrant)>,
+    ant_query: Query<(Entity, &GridPosition, &Caste), With<Ant>>,
+    entrances: Res<Entrances>,
+    mut stats: ResMut<RunStats>,
+    mut deaths: MessageWriter<AntDied>,
+    mut hits: MessageWriter<CombatHit>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let beetles: Vec<(Entity, usize, usize)> = migrant_query
+        .iter()
+        .filter(|(_, m)| m.kind == MigrantKind::Beetle)
+        .map(|(entity, m)| (entity, m.x, m.y))
+        .collect();
+    let mut eaten = Vec::new();
+
+    for (_, mut spider) in &mut migrant_query {
+        // Spiders don't hunt while digesting or on their way out
+        if spider.kind != MigrantKind::Spider || spider.leaving || spider.rest > 0 {
+            continue;
+        }
+
+        let distance = |x: usize, y: usize| x.abs_diff(spider.x) + y.abs_diff(spider.y);
+
+        // Soldiers at their posts see spiders off
+        let guarded = entrances
+            .list
+            .iter()
+            .any(|entrance| entrance.manned && distance(entrance.x, entrance.y) <= GUARD_REACH);
+        if guarded {
+            hits.write(CombatHit {
+                x: spider.x,
+                y: spider.y,
+                z: SURFACE_LEVEL,
+            });
+            spider.leaving = true;
+            spider.target = nearest_edge_tile(spider.x, spider.y);
+            stats.raids_repelled += 1;
+            notifications.write(Notification::info(
+                "Soldiers drove a spider away from the nest entrance",
+            ));
+            continue;
+        }
+
+        // Beetles are the preferred prey
+        let beetle = beetles
+            .iter()
+            .filter(|(entity, ..)| !eaten.contains(entity))
+            .min_by_key(|(_, x, y)| distance(*x, *y))
+            .filter(|(_, x, y)| distance(*x, *y) <= SPIDER_SIGHT);
+        if let Some(&(entity, x, y)) = beetle {
+            if distance(x, y) == 0 {
+                hits.write(CombatHit {
+                    x,
+                    y,
+                    z: SURFACE_LEVEL,
+                });
+                eaten.push(entity);
+                spider.rest = SPIDER_DIGEST_TICKS;
+                info!("A spider ate a beetle at ({}, {})", x, y);
+            } else {
+                spider.target = (x, y);
+            }
+            continue;
+        }
+
+        let ant = ant_query
+            .iter()
+            .filter(|(entity, pos, caste)| {
+                pos.z == SURFACE_LEVEL && **caste != Caste::Queen && !eaten.contains(entity)
+            })
+            .min_by_key(|(_, pos, _)| distance(pos.x, pos.y))
+            .filter(|(_, pos, _)| distance(pos.x, pos.y) <= SPIDER_SIGHT);
+        if let Some((entity, pos, caste)) = ant {
+            if distance(pos.x, pos.y) == 0 {
+                hits.write(CombatHit {
+                    x: pos.x,
+                    y: pos.y,
+                    z: pos.z,
+                });
+                eaten.push(entity);
+                spider.rest = SPIDER_DIGEST_TICKS;
+                deaths.write(AntDied {
+                    caste: *caste,
+                    cause: DeathCause::Predation,
+                });
+                notifications.write(Notification::warning(format!(
+                    "A spider caught a {} on the surface",
+                    caste.name().to_lowercase()
+                )));
+            } else {
+                spider.target = (pos.x, pos.y);
+            }
+            continue;
+        }
+
+        // Wait where the ants come and go
+        let lurk = entrances
+            .list
+            .iter()
+            .filter(|entrance| !entrance.manned)
+            .min_by_key(|entrance| distance(entrance.x, entrance.y))
+            .filter(|entrance| distance(entrance.x, entrance.y) <= SPIDER_SIGHT);
+        if let Some(entrance) = lurk {
+            spider.target = (entrance.x, entrance.y);
+        }
+    }
+
+    for entity in eaten {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Migrants walk the surface, so they're only shown on the surface level
+fn update_migrant_sprites(
+    current_z: Res<CurrentZLevel>,
+    mut query: Query<(&Migrant, &mut Transform, &mut Visibility)>,
+) {
+    let visibility = if current_z.0 == SURFACE_LEVEL {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for (migrant, mut transform, mut migrant_visibility) in &mut query {
+        transform.translation = tile_to_world(migrant.x, migrant.y).extend(transform.translation.z);
+        *migrant_visibility = visibility;
+    }
+}
+
+// ============================================================================
+// Messages
+// ============================================================================
+
+/// Sent when a predator strikes or is fought off, for the hit flash
+#[derive(Message, Debug, Clone, Copy)]
+pub struct CombatHit {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+}
+
+// ============================================================================
+// Helpers
+// ============================================================================
+
+pub fn spawn_migrant(
+    commands: &mut Commands,
+    kind: MigrantKind,
+    x: usize,
+    y: usize,
+    target: (usize, usize),
+) {
+    let (color, size, index) = match kind {
+        MigrantKind::Beetle => (
+            sprites::objects::BEETLE,
+            sprites::objects::BEETLE_SIZE,
+            sprites::objects::BEETLE_INDEX,
+        ),
+        MigrantKind::Spider => (
+            sprites::objects::SPIDER,
+            sprites::objects::SPIDER_SIZE,
+            sprites::objects::SPIDER_INDEX,
+        ),
+    };
+
+    commands.spawn((
+        Migrant {
+            kind,
+            x,
+            y,
+            target,
+            leaving: false,
+            ticks: 0,
+            rest: 0,
+        },
+        Sprite {
+            color,
+            custom_size: Some(Vec2::splat(size)),
+            ..default()
+        },
+        Transform::from_translation(tile_to_world(x, y).extend(0.9)),
+        AtlasSprite(index),
+    ));
+    info!("A {} arrived at ({}, {})", kind.name(), x, y);
+}
+
+/// One step from `from` toward `to` along a single axis
+fn step(from: usize, to: usize) -> usize {
+    match from.cmp(&to) {
+        std::cmp::Ordering::Less => from + 1,
+        std::cmp::Ordering::Greater => from - 1,
+        std::cmp::Ordering::Equal => from,
+    }
+}
+
+/// A random tile on the edge of the map
+pub fn random_edge_tile(rng: &mut impl Rng) -> (usize, usize) {
+    let along = rng.random_range(0..WORLD_SIZE);
+    let last = WORLD_SIZE - 1;
+    match rng.random_range(0..4) {
+        0 => (along, 0),
+        1 => (along, last),
+        2 => (0, along),
+        _ => (last, along),
+    }
+}
+
+/// A random tile away from the map edge
+pub fn random_interior_tile(rng: &mut impl Rng) -> (usize, usize) {
+    (
+        rng.random_range(4..WORLD_SIZE - 4),
+        rng.random_range(4..WORLD_SIZE - 4),
+    )
+}
+
+/// The edge tile closest to a position
+fn nearest_edge_tile(x: usize, y: usize) -> (usize, usize) {
+    let last = WORLD_SIZE - 1;
+    [(x, 0), (x, last), (0, y), (last, y)]
+        .into_iter()
+        .min_by_key(|&(ex, ey)| ex.abs_diff(x) + ey.abs_diff(y))
+        .unwrap_or((x, 0))
+}
@@ -0,0 +1,335 @@
+//! Soil moisture and rain.
+//!
+//! Showers come and go with the seasons, more often in wet biomes, soaking
+//! into the topsoil and percolating down. Standing groundwater wets the soil
+//! around it and everything below the water table stays saturated. Between
+//! showers the soil dries back toward its usual dampness, which grows with
+//! depth. Wet soil digs faster but slumps, so rooms dug in it hold less
+//! roof, and the fungus garden grows best within a band of moisture: too dry
+//! and it withers, too wet and it drowns. M shows moisture as an overlay.
+
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::biome::Biome;
+use crate::exploration::Explored;
+use crate::low_spec::cosmetic_frame;
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::sprites;
+use crate::time_controls::{SimulationSet, SimulationTick};
+use crate::water::Groundwater;
+use crate::world::{
+    CurrentZLevel, SURFACE_LEVEL, Soil, TILE_SIZE, TileKind, WORLD_SIZE, WorldGrid, WorldOptions,
+    filled_grid, humidity,
+};
+use crate::{ResetRun, reset_resource};
+
+pub struct MoisturePlugin;
+
+impl Plugin for MoisturePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SoilMoisture>()
+            .init_resource::<Weather>()
+            .init_resource::<MoistureOverlaySettings>()
+            .add_systems(Startup, spawn_moisture_overlay)
+            .add_systems(Update, update_moisture_overlay.run_if(cosmetic_frame))
+            .add_systems(
+                FixedUpdate,
+                (
+                    reset_moisture.run_if(resource_changed::<Biome>),
+                    weather,
+                    soil_moisture,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(
+                ResetRun,
+                (reset_resource::<SoilMoisture>, reset_resource::<Weather>),
+            );
+    }
+}
+
+/// Ticks between moisture updates
+const MOISTURE_UPDATE_INTERVAL: u64 = 8;
+/// Per-tick chance of a shower starting in a normal season and biome
+const RAIN_CHANCE: f64 = 0.0005;
+/// How long a shower lasts, in ticks
+const RAIN_DURATION: RangeInclusive<u32> = 200..=800;
+/// Moisture a shower adds to the topsoil per update
+const RAIN_SOAK: f32 = 0.05;
+/// Share of the difference with each neighbor exchanged per update
+const DIFFUSION_RATE: f32 = 0.1;
+/// Share of the difference with the tile below that drips down per update
+const PERCOLATION_RATE: f32 = 0.1;
+/// Share of the way back to its usual dampness soil dries per update
+const DRAIN_RATE: f32 = 0.01;
+/// Usual moisture of the deepest soil in the wettest biome
+const BASE_MOISTURE: f32 = 0.5;
+/// Moisture of soil below the water table
+const SATURATED: f32 = 0.9;
+/// Moisture standing water adds to each neighboring tile per update
+const WATER_SOAK: f32 = 0.02;
+/// How much faster fully soaked soil digs
+const WET_DIG_BONUS: f64 = 0.5;
+/// Share of its stability fully soaked soil loses
+const WET_INSTABILITY: f32 = 0.4;
+/// Moisture the fungus garden grows best in
+const GARDEN_MOISTURE_BAND: RangeInclusive<f32> = 0.3..=0.7;
+/// Growth of a garden tile at the driest or wettest extreme
+const OFF_BAND_GROWTH: f32 = 0.25;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Water held in every underground tile (0.0 - 1.0)
+#[derive(Resource)]
+pub struct SoilMoisture {
+    pub level: Box<[[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+    /// Next-step buffer reused by each update
+    scratch: Box<[[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+}
+
+impl Default for SoilMoisture {
+    fn default() -> Self {
+        Self {
+            level: filled_grid(0.0),
+            scratch: filled_grid(0.0),
+        }
+    }
+}
+
+impl SoilMoisture {
+    /// Chance per tick of digging through `soil` at a tile, wet soil giving
+    /// way sooner
+    pub fn dig_chance(&self, soil: Soil, x: usize, y: usize, z: usize) -> f64 {
+        let wetness = self.level[z][y][x] as f64;
+        (soil.dig_chance() * (1.0 + WET_DIG_BONUS * wetness)).min(1.0)
+    }
+
+    /// How well `soil` at a tile holds up, wet soil slumping
+    pub fn stability(&self, soil: Soil, x: usize, y: usize, z: usize) -> f32 {
+        soil.stability() * (1.0 - WET_INSTABILITY * self.level[z][y][x])
+    }
+
+    /// How well fungus grows on a garden tile (0.0 - 1.0), best inside the
+    /// moisture band and falling off toward bone dry or waterlogged
+    pub fn garden_growth(&self, x: usize, y: usize, z: usize) -> f32 {
+        let level = self.level[z][y][x];
+        let (low, high) = (*GARDEN_MOISTURE_BAND.start(), *GARDEN_MOISTURE_BAND.end());
+        let off_band = if level < low {
+            (low - level) / low
+        } else if level > high {
+            (level - high) / (1.0 - high)
+        } else {
+            0.0
+        };
+        1.0 - (1.0 - OFF_BAND_GROWTH) * off_band.min(1.0)
+    }
+}
+
+/// Usual moisture of soil at a depth, which it dries back toward
+fn base_moisture(z: usize, biome: Biome) -> f32 {
+    BASE_MOISTURE * humidity(z) * biome.moisture()
+}
+
+/// The weather on the surface
+#[derive(Resource, Default)]
+pub struct Weather {
+    /// Ticks left in the current shower, zero when dry
+    pub rain_ticks: u32,
+    /// No showers at all, for a drought scenario
+    pub drought: bool,
+}
+
+impl Weather {
+    pub fn raining(&self) -> bool {
+        self.rain_ticks > 0
+    }
+}
+
+/// Whether the moisture overlay is drawn
+#[derive(Resource, Default)]
+pub struct MoistureOverlaySettings {
+    pub visible: bool,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for moisture overlay sprites
+#[derive(Component)]
+pub struct MoistureOverlay {
+    pub x: usize,
+    pub y: usize,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start the soil at its usual dampness for the biome
+fn reset_moisture(biome: Res<Biome>, mut moisture: ResMut<SoilMoisture>) {
+    for z in 0..SURFACE_LEVEL {
+        moisture.level[z] = [[base_moisture(z, *biome); WORLD_SIZE]; WORLD_SIZE];
+    }
+}
+
+/// Start and stop showers, more often in wet seasons and biomes
+fn weather(
+    tick: Res<SimulationTick>,
+    biome: Res<Biome>,
+    mut weather: ResMut<Weather>,
+    mut notifications: MessageWriter<Notification>,
+    mut rng: ResMut<SimRng>,
+) {
+    if weather.raining() {
+        weather.rain_ticks -= 1;
+        if !weather.raining() {
+            info!("The rain has stopped");
+        }
+        return;
+    }
+    if weather.drought {
+        return;
+    }
+
+    let chance = RAIN_CHANCE * tick.season().wetness() as f64 * biome.moisture() as f64;
+    if rng.random_bool(chance) {
+        weather.rain_ticks = rng.random_range(RAIN_DURATION);
+        notifications.write(Notification::info("Rain is soaking into the soil"));
+    }
+}
+
+/// Soak rain and groundwater into the soil, spread it around, and let it
+/// drain back toward its usual dampness
+fn soil_moisture(
+    tick: Res<SimulationTick>,
+    world_grid: Res<WorldGrid>,
+    biome: Res<Biome>,
+    weather: Res<Weather>,
+    water: Res<Groundwater>,
+    options: Res<WorldOptions>,
+    mut moisture: ResMut<SoilMoisture>,
+) {
+    if !tick.0.is_multiple_of(MOISTURE_UPDATE_INTERVAL) {
+        return;
+    }
+
+    let moisture = &mut *moisture;
+    let tiles = &world_grid.tiles;
+    let table_z = options.water_table_z();
+    // Rock holds no water and lets none through
+    let holds =
+        |x: usize, y: usize, z: usize| z < SURFACE_LEVEL && tiles[z][y][x] != TileKind::Rock;
+
+    for z in 0..SURFACE_LEVEL {
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                if !holds(x, y, z) {
+                    moisture.scratch[z][y][x] = 0.0;
+                    continue;
+                }
+
+                let here = moisture.level[z][y][x];
+                let mut next = here + (base_moisture(z, *biome) - here) * DRAIN_RATE;
+
+                let neighbors = [
+                    (x.wrapping_sub(1), y, z),
+                    (x + 1, y, z),
+                    (x, y.wrapping_sub(1), z),
+                    (x, y + 1, z),
+                    (x, y, z.wrapping_sub(1)),
+                    (x, y, z + 1),
+                ];
+                for (nx, ny, nz) in neighbors {
+                    if nx >= WORLD_SIZE || ny >= WORLD_SIZE || nz >= WORLD_SIZE {
+                        continue;
+                    }
+                    next += water.level[nz][ny][nx] * WATER_SOAK;
+                    if holds(nx, ny, nz) {
+                        next += (moisture.level[nz][ny][nx] - here) * DIFFUSION_RATE / 6.0;
+                    }
+                }
+
+                // Water drips down out of wetter soil above into drier below
+                if z + 1 < SURFACE_LEVEL && holds(x, y, z + 1) {
+                    next += (moisture.level[z + 1][y][x] - here).max(0.0) * PERCOLATION_RATE;
+                }
+                if z > 0 && holds(x, y, z - 1) {
+                    next -= (here - moisture.level[z - 1][y][x]).max(0.0) * PERCOLATION_RATE;
+                }
+
+                if weather.raining() && z + 1 == SURFACE_LEVEL {
+                    next += RAIN_SOAK;
+                }
+                if z <= table_z {
+                    next = next.max(SATURATED);
+                }
+
+                moisture.scratch[z][y][x] = next.clamp(0.0, 1.0);
+            }
+        }
+    }
+
+    std::mem::swap(&mut moisture.level, &mut moisture.scratch);
+}
+
+/// Spawn overlay sprites for moisture visualization
+fn spawn_moisture_overlay(mut commands: Commands) {
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let world_x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+            let world_y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+
+            commands.spawn((
+                Sprite {
+                    color: Color::NONE,
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(world_x, world_y, 0.44), // Just under the air overlay
+                MoistureOverlay { x, y },
+                Visibility::Hidden,
+            ));
+        }
+    }
+}
+
+/// Tint every tile on the current z-level from dry to soaked
+fn update_moisture_overlay(
+    moisture: Res<SoilMoisture>,
+    current_z: Res<CurrentZLevel>,
+    settings: Res<MoistureOverlaySettings>,
+    world_grid: Res<WorldGrid>,
+    explored: Res<Explored>,
+    mut query: Query<(&MoistureOverlay, &mut Sprite, &mut Visibility)>,
+) {
+    let z = current_z.0;
+    if !settings.visible || z >= SURFACE_LEVEL {
+        if settings.is_changed() || current_z.is_changed() {
+            for (_, _, mut visibility) in &mut query {
+                *visibility = Visibility::Hidden;
+            }
+        }
+        return;
+    }
+
+    for (overlay, mut sprite, mut visibility) in &mut query {
+        let (x, y) = (overlay.x, overlay.y);
+        let tile = world_grid.tiles[z][y][x];
+        if tile == TileKind::Rock || !explored.shows(tile, x, y, z) {
+            *visibility = Visibility::Hidden;
+            continue;
+        }
+
+        *visibility = Visibility::Visible;
+        let level = moisture.level[z][y][x];
+        sprite.color = sprites::moisture::DRY.mix(&sprites::moisture::WET, level);
+    }
+}
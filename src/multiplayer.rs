@@ -0,0 +1,455 @@
+//! Cooperative multiplayer: two players sharing one colony.
+//!
+//! One player hosts (`--host <port>`) and the other joins (`--join
+//! <address>`). A host listens on localhost unless given an address to listen
+//! on as well (`--host 0.0.0.0:<port>`), since anyone who can reach it can
+//! join. When the partner connects the host sends its seed and world
+//! options, so both games generate the same world, and from then on they run
+//! in lockstep. The simulation is deterministic (its schedules run one system
+//! at a time from one seeded generator), so only player actions cross the
+//! network: each one is scheduled `INPUT_DELAY` ticks ahead and sent to
+//! the partner, and a tick only runs once the partner has said it has nothing
+//! more for it. Both games then apply the same actions before the same tick,
+//! the host's first, and stay in step without ever sending the world.
+//!
+//! Every tick the two sides also swap a checksum of the colony, so a desync is
+//! reported on the tick it happens rather than left to grow unnoticed.
+//! Anything that changes the world other than through player actions (console
+//! commands, rewinding, different `acre.ron` tuning) will cause one. Pausing
+//! or running at a higher speed holds the partner back to match, and ticks
+//! are limited to one per frame.
+//!
+//! If the partner disconnects, the colony carries on as a single-player game.
+
+use std::collections::{BTreeMap, BTreeSet};
+use std::io::{ErrorKind, Read, Write};
+use std::net::{Ipv4Addr, SocketAddr, TcpListener, TcpStream};
+
+use bevy::ecs::message::MessageCursor;
+use bevy::prelude::*;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::ants::{Ant, GridPosition};
+use crate::biome::Biome;
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::time_controls::{SimulationSet, SimulationTick};
+use crate::world::FoodStores;
+
+pub struct MultiplayerPlugin;
+
+impl Plugin for MultiplayerPlugin {
+    fn build(&self, app: &mut App) {
+        app.configure_sets(FixedFirst, SimulationSet.run_if(tick_released))
+            .configure_sets(FixedUpdate, SimulationSet.run_if(tick_released))
+            .configure_sets(FixedLast, SimulationSet.run_if(tick_released))
+            .add_systems(
+                Update,
+                exchange_actions
+                    .after(ActionSystems::Input)
+                    .before(ActionSystems::Apply)
+                    .run_if(resource_exists::<Lockstep>),
+            );
+    }
+}
+
+/// Ticks between taking an action and the tick it's applied before, to give
+/// it time to reach the partner
+const INPUT_DELAY: u64 = 3;
+
+// ============================================================================
+// Connection
+// ============================================================================
+
+/// Which side of a co-op game this is
+#[derive(Debug, Clone)]
+pub enum CoopRole {
+    /// Wait for a partner on an address
+    Host(SocketAddr),
+    /// Connect to a host at an address
+    Join(String),
+}
+
+/// Where `--host` listens: a bare port on localhost only, or an address
+/// given with the port (like `0.0.0.0:7777`) to let other machines join
+pub fn host_address(arg: &str) -> Option<SocketAddr> {
+    match arg.parse::<u16>() {
+        Ok(port) => Some(SocketAddr::from((Ipv4Addr::LOCALHOST, port))),
+        Err(_) => arg.parse().ok(),
+    }
+}
+
+/// What both worlds are generated from, chosen by the host
+#[derive(Debug, Clone)]
+pub struct SharedStart {
+    pub seed: u64,
+    pub biome: Biome,
+    pub water_table_depth: usize,
+    pub founding: bool,
+}
+
+/// Connect to the other player before the game starts, blocking until they
+/// arrive. The host's start is returned to both sides, along with the
+/// connection to play over
+pub fn connect(role: &CoopRole, start: SharedStart) -> Result<(SharedStart, Lockstep), String> {
+    let version = env!("CARGO_PKG_VERSION");
+
+    let (stream, start) = match role {
+        CoopRole::Host(address) => {
+            let listener = TcpListener::bind(address)
+                .map_err(|e| format!("Couldn't listen on {}: {}", address, e))?;
+            if address.ip().is_loopback() {
+                eprintln!(
+                    "Only this machine can join; host on 0.0.0.0:{} to let others in",
+                    address.port()
+                );
+            }
+            eprintln!("Waiting for a partner to join on {}...", address);
+            let (mut stream, address) = listener
+                .accept()
+                .map_err(|e| format!("Couldn't accept a partner: {}", e))?;
+            eprintln!("{} joined", address);
+
+            let hello = format!(
+                "acre {} {} {} {} {}\n",
+                version,
+                start.seed,
+                start.biome.name(),
+                start.water_table_depth,
+                start.founding
+            );
+            stream
+                .write_all(hello.as_bytes())
+                .map_err(|e| format!("Couldn't greet the partner: {}", e))?;
+            (stream, start)
+        }
+        CoopRole::Join(address) => {
+            let mut stream = TcpStream::connect(address)
+                .map_err(|e| format!("Couldn't reach a host at {}: {}", address, e))?;
+            let hello =
+                read_hello(&mut stream).map_err(|e| format!("The host didn't say hello: {}", e))?;
+            let fields: Vec<&str> = hello.split_whitespace().collect();
+            let host_version = fields.get(1).copied().unwrap_or("?");
+            if fields.first() != Some(&"acre") || host_version != version {
+                return Err(format!(
+                    "The host is running Acre {}, and this is {}",
+                    host_version, version
+                ));
+            }
+            let start = parse_start(&fields)
+                .ok_or_else(|| format!("Couldn't read the host's world from '{}'", hello))?;
+            eprintln!("Joined {}", address);
+            (stream, start)
+        }
+    };
+
+    // Actions are small and latency matters more than throughput
+    stream
+        .set_nodelay(true)
+        .and_then(|_| stream.set_nonblocking(true))
+        .map_err(|e| format!("Couldn't set up the connection: {}", e))?;
+
+    let host = matches!(role, CoopRole::Host(_));
+    Ok((start, Lockstep::new(stream, host)))
+}
+
+/// The host's world, from the fields of its greeting
+fn parse_start(fields: &[&str]) -> Option<SharedStart> {
+    Some(SharedStart {
+        seed: fields.get(2)?.parse().ok()?,
+        biome: Biome::from_name(fields.get(3)?)?,
+        water_table_depth: fields.get(4)?.parse().ok()?,
+        founding: fields.get(5)?.parse().ok()?,
+    })
+}
+
+/// Read the host's greeting a byte at a time, so nothing sent after it is
+/// swallowed by a buffer
+fn read_hello(stream: &mut TcpStream) -> std::io::Result<String> {
+    let mut line = Vec::new();
+    let mut byte = [0u8];
+    loop {
+        stream.read_exact(&mut byte)?;
+        if byte[0] == b'\n' {
+            return Ok(String::from_utf8_lossy(&line).into_owned());
+        }
+        line.push(byte[0]);
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// A co-op game in progress: the connection to the partner and the actions
+/// waiting for their tick
+#[derive(Resource)]
+pub struct Lockstep {
+    stream: TcpStream,
+    /// Whether this side is the host, whose actions go first
+    host: bool,
+    /// Bytes received that don't make a full line yet
+    incoming: Vec<u8>,
+    /// Bytes the connection hasn't taken yet
+    outgoing: Vec<u8>,
+    /// This player's actions, by the tick they're applied before
+    local: BTreeMap<u64, Vec<PlayerAction>>,
+    /// The partner's actions, likewise
+    remote: BTreeMap<u64, Vec<PlayerAction>>,
+    /// The last tick this side has sent every action for
+    sent_through: Option<u64>,
+    /// The last tick the partner has sent every action for
+    remote_through: Option<u64>,
+    /// The tick whose actions were last handed to the simulation
+    released: Option<u64>,
+    /// The last tick a checksum was taken on
+    checked: Option<u64>,
+    /// Checksums waiting for the other side's, by tick
+    checksums: BTreeMap<u64, u64>,
+    remote_checksums: BTreeMap<u64, u64>,
+    /// Whether the colonies have already been found out of sync
+    desynced: bool,
+}
+
+impl Lockstep {
+    fn new(stream: TcpStream, host: bool) -> Self {
+        Self {
+            stream,
+            host,
+            incoming: Vec::new(),
+            outgoing: Vec::new(),
+            local: BTreeMap::new(),
+            remote: BTreeMap::new(),
+            sent_through: None,
+            remote_through: None,
+            released: None,
+            checked: None,
+            checksums: BTreeMap::new(),
+            remote_checksums: BTreeMap::new(),
+            desynced: false,
+        }
+    }
+
+    /// Queue a line for the partner
+    fn send(&mut self, line: &str) {
+        self.outgoing.extend_from_slice(line.as_bytes());
+        self.outgoing.push(b'\n');
+    }
+
+    /// Send what the connection will take and collect the full lines that have
+    /// arrived, failing once the partner is gone
+    fn exchange(&mut self) -> std::io::Result<Vec<String>> {
+        while !self.outgoing.is_empty() {
+            match self.stream.write(&self.outgoing) {
+                Ok(0) => return Err(ErrorKind::WriteZero.into()),
+                Ok(written) => {
+                    self.outgoing.drain(..written);
+                }
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut buffer = [0u8; 4096];
+        loop {
+            match self.stream.read(&mut buffer) {
+                Ok(0) => return Err(ErrorKind::UnexpectedEof.into()),
+                Ok(read) => self.incoming.extend_from_slice(&buffer[..read]),
+                Err(e) if e.kind() == ErrorKind::WouldBlock => break,
+                Err(e) => return Err(e),
+            }
+        }
+
+        let mut lines = Vec::new();
+        while let Some(end) = self.incoming.iter().position(|&byte| byte == b'\n') {
+            let line: Vec<u8> = self.incoming.drain(..=end).collect();
+            lines.push(String::from_utf8_lossy(&line).trim().to_string());
+        }
+        Ok(lines)
+    }
+
+    /// Take in a line from the partner: an action for a tick, the last tick
+    /// they've sent everything for, or a checksum
+    fn receive(&mut self, line: &str) -> Result<(), String> {
+        let (first, rest) = line
+            .split_once(' ')
+            .ok_or_else(|| format!("nothing after '{}'", line))?;
+        let parse_tick = |field: &str| -> Result<u64, String> {
+            field
+                .parse()
+                .map_err(|e| format!("bad tick in '{}': {}", line, e))
+        };
+
+        match first {
+            "done" => self.remote_through = Some(parse_tick(rest)?),
+            "sum" => {
+                let (tick, sum) = rest
+                    .split_once(' ')
+                    .ok_or_else(|| format!("missing checksum in '{}'", line))?;
+                let sum = sum
+                    .parse()
+                    .map_err(|e| format!("bad checksum in '{}': {}", line, e))?;
+                self.remote_checksums.insert(parse_tick(tick)?, sum);
+            }
+            tick => {
+                let action = PlayerAction::from_line(rest)?;
+                self.remote
+                    .entry(parse_tick(tick)?)
+                    .or_default()
+                    .push(action);
+            }
+        }
+        Ok(())
+    }
+
+    /// Both players' actions for a tick, the host's first, so each side
+    /// applies them in the same order
+    fn take_tick(&mut self, tick: u64) -> Vec<PlayerAction> {
+        let local = self.local.remove(&tick).unwrap_or_default();
+        let remote = self.remote.remove(&tick).unwrap_or_default();
+        if self.host {
+            local.into_iter().chain(remote).collect()
+        } else {
+            remote.into_iter().chain(local).collect()
+        }
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Run condition for `SimulationSet`: outside co-op always, and in co-op only
+/// once both players' actions for the coming tick have been applied
+fn tick_released(lockstep: Option<Res<Lockstep>>, tick: Res<SimulationTick>) -> bool {
+    lockstep.is_none_or(|lockstep| lockstep.released == Some(tick.0))
+}
+
+/// Hold back this frame's actions and send them to the partner, take in
+/// theirs, and hand the simulation both players' actions for the coming tick
+/// once the partner has sent all of theirs
+#[allow(clippy::too_many_arguments)]
+fn exchange_actions(
+    mut commands: Commands,
+    mut lockstep: ResMut<Lockstep>,
+    mut actions: ResMut<Messages<PlayerAction>>,
+    mut cursor: Local<MessageCursor<PlayerAction>>,
+    tick: Res<SimulationTick>,
+    ant_query: Query<&GridPosition, With<Ant>>,
+    food_stores: Res<FoodStores>,
+    rng: Res<SimRng>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    // Nothing reaches the simulation until its tick comes round
+    let taken: Vec<PlayerAction> = cursor.read(&actions).cloned().collect();
+    actions.clear();
+    let target = tick.0 + INPUT_DELAY;
+    for action in taken {
+        lockstep.send(&format!("{} {}", target, action.to_line()));
+        lockstep.local.entry(target).or_default().push(action);
+    }
+    if lockstep.sent_through != Some(target) {
+        lockstep.send(&format!("done {}", target));
+        lockstep.sent_through = Some(target);
+    }
+
+    // Taken before this tick's actions are applied, the same point on both sides
+    if lockstep.checked != Some(tick.0) {
+        let sum = checksum(tick.0, &ant_query, &food_stores, &rng);
+        lockstep.send(&format!("sum {} {}", tick.0, sum));
+        lockstep.checksums.insert(tick.0, sum);
+        lockstep.checked = Some(tick.0);
+    }
+
+    let lines = match lockstep.exchange() {
+        Ok(lines) => lines,
+        Err(error) => {
+            warn!("Lost the connection to the co-op partner: {}", error);
+            notifications.write(Notification::warning(
+                "Your partner has left; the colony is yours alone now",
+            ));
+            // Whatever was still waiting goes through, and the game runs alone
+            // from here
+            let ticks: BTreeSet<u64> = lockstep
+                .local
+                .keys()
+                .chain(lockstep.remote.keys())
+                .copied()
+                .collect();
+            let mut pending = Vec::new();
+            for tick in ticks {
+                pending.extend(lockstep.take_tick(tick));
+            }
+            actions.write_batch(pending);
+            cursor.clear(&actions);
+            commands.remove_resource::<Lockstep>();
+            return;
+        }
+    };
+    for line in lines {
+        if let Err(error) = lockstep.receive(&line) {
+            warn!("Ignoring a bad line from the co-op partner: {}", error);
+        }
+    }
+
+    let matched: Vec<u64> = lockstep
+        .checksums
+        .keys()
+        .filter(|tick| lockstep.remote_checksums.contains_key(tick))
+        .copied()
+        .collect();
+    for checked_tick in matched {
+        let ours = lockstep.checksums.remove(&checked_tick);
+        let theirs = lockstep.remote_checksums.remove(&checked_tick);
+        // Once apart the colonies stay apart, so only the first is reported
+        if ours != theirs && !lockstep.desynced {
+            lockstep.desynced = true;
+            error!(
+                "Co-op desync on tick {}: checksum {:?} here, {:?} for the partner",
+                checked_tick, ours, theirs
+            );
+            notifications.write(Notification::warning(
+                "Your colony has drifted out of sync with your partner's",
+            ));
+        }
+    }
+
+    if lockstep.released != Some(tick.0)
+        && lockstep
+            .remote_through
+            .is_some_and(|through| through >= tick.0)
+    {
+        let released = lockstep.take_tick(tick.0);
+        actions.write_batch(released);
+        lockstep.released = Some(tick.0);
+    }
+    // What was just handed over isn't this player's to take next frame
+    cursor.clear(&actions);
+}
+
+/// A fingerprint of the colony: where every ant is, what's in the stores and
+/// where the random number generator has got to
+fn checksum(
+    tick: u64,
+    ant_query: &Query<&GridPosition, With<Ant>>,
+    food_stores: &FoodStores,
+    rng: &SimRng,
+) -> u64 {
+    let mut positions: Vec<_> = ant_query.iter().map(|pos| (pos.x, pos.y, pos.z)).collect();
+    positions.sort_unstable();
+
+    // FNV-1a, which unlike std's hasher is the same in every build
+    positions
+        .into_iter()
+        .flat_map(|(x, y, z)| [x as u64, y as u64, z as u64])
+        .chain([
+            tick,
+            food_stores.food as u64,
+            food_stores.honeydew as u64,
+            food_stores.seeds as u64,
+            rng.fingerprint(),
+        ])
+        .fold(0xcbf2_9ce4_8422_2325, |hash, value| {
+            (hash ^ value).wrapping_mul(0x0100_0000_01b3)
+        })
+}
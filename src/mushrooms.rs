@@ -0,0 +1,121 @@
+//! Wild mushrooms: fungus patches that pop up on the surface.
+//!
+//! Foragers can harvest them straight into the food stores, skipping the
+//! garden entirely, which helps a young colony before its garden is producing.
+//! Wild fungus carries mold spores, though, and some loads bring
+//! contamination back into the garden.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::atlas::AtlasSprite;
+use crate::low_spec::cosmetic_frame;
+use crate::rng::SimRng;
+use crate::sprites;
+use crate::time_controls::SimulationSet;
+use crate::world::{CurrentZLevel, SURFACE_LEVEL, TILE_SIZE, TileKind, WORLD_SIZE, WorldGrid};
+use crate::{ResetRun, despawn_all};
+
+pub struct MushroomPlugin;
+
+impl Plugin for MushroomPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_mushroom_sprites.run_if(cosmetic_frame))
+            .add_systems(FixedUpdate, mushroom_spawning.in_set(SimulationSet))
+            .add_systems(ResetRun, despawn_all::<WildMushroom>);
+    }
+}
+
+/// Per-tick chance a new mushroom patch appears
+const MUSHROOM_SPAWN_CHANCE: f64 = 1.0 / 1500.0;
+/// Most patches growing on the surface at once
+const MAX_MUSHROOMS: usize = 4;
+/// Food units in a fresh patch
+const MUSHROOM_FOOD: u32 = 3;
+
+/// Chance that a harvested load carries spores into the garden
+pub const MUSHROOM_TAINT_CHANCE: f64 = 0.25;
+/// Contamination a tainted load spreads over nearby garden tiles
+pub const MUSHROOM_CONTAMINATION: f32 = 0.15;
+/// How far from the nest entrance spores from a tainted load reach
+pub const MUSHROOM_SPORE_RADIUS: usize = 4;
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// A patch of wild fungus on the surface
+#[derive(Component)]
+pub struct WildMushroom {
+    pub x: usize,
+    pub y: usize,
+    /// Food units left to harvest
+    pub food: u32,
+}
+
+impl WildMushroom {
+    /// Harvest one unit of food, returning whether the patch is now used up
+    pub fn harvest(&mut self) -> bool {
+        self.food = self.food.saturating_sub(1);
+        self.food == 0
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Occasionally sprout a new mushroom patch on open ground
+fn mushroom_spawning(
+    mut commands: Commands,
+    world_grid: Res<WorldGrid>,
+    mushroom_query: Query<&WildMushroom>,
+    mut rng: ResMut<SimRng>,
+) {
+    if !rng.random_bool(MUSHROOM_SPAWN_CHANCE) || mushroom_query.iter().count() >= MAX_MUSHROOMS {
+        return;
+    }
+
+    let x = rng.random_range(0..WORLD_SIZE);
+    let y = rng.random_range(0..WORLD_SIZE);
+    if world_grid.tiles[SURFACE_LEVEL][y][x] != TileKind::Surface
+        || mushroom_query.iter().any(|m| m.x == x && m.y == y)
+    {
+        return;
+    }
+
+    commands.spawn((
+        WildMushroom {
+            x,
+            y,
+            food: MUSHROOM_FOOD,
+        },
+        Sprite {
+            color: sprites::objects::MUSHROOM,
+            custom_size: Some(Vec2::splat(sprites::objects::MUSHROOM_SIZE)),
+            ..default()
+        },
+        AtlasSprite(sprites::objects::MUSHROOM_INDEX),
+        Transform::from_xyz(
+            (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE,
+            (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE,
+            0.7,
+        ),
+    ));
+    info!("Wild mushrooms sprouted at ({}, {})", x, y);
+}
+
+/// Mushrooms grow on the surface, so they're only shown on the surface level
+fn update_mushroom_sprites(
+    current_z: Res<CurrentZLevel>,
+    mut query: Query<&mut Visibility, With<WildMushroom>>,
+) {
+    let visibility = if current_z.0 == SURFACE_LEVEL {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for mut mushroom_visibility in &mut query {
+        *mushroom_visibility = visibility;
+    }
+}
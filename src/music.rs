@@ -0,0 +1,162 @@
+//! Ambient music.
+//!
+//! Two looping layers play in step: a calm one for ordinary foraging and a
+//! tense one for when the colony is under threat. While a spider is hunting on
+//! the surface, or shortly after one has caught an ant, the music crossfades
+//! toward the tense layer, and back again once things settle. K mutes and
+//! unmutes it. The layers are `assets/music/calm.ogg` and
+//! `assets/music/alarm.ogg`; without them the game is silent.
+
+use std::path::Path;
+
+use bevy::audio::{AudioSinkPlayback, Volume};
+use bevy::prelude::*;
+
+use crate::ants::{AntDied, DeathCause};
+use crate::migrations::{Migrant, MigrantKind};
+use crate::notifications::Notification;
+
+pub struct MusicPlugin;
+
+impl Plugin for MusicPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Music>()
+            .add_systems(Startup, start_music)
+            .add_systems(Update, (toggle_music, colony_mood, crossfade_music).chain());
+    }
+}
+
+/// Volume of a layer playing on its own
+const MUSIC_VOLUME: f32 = 0.5;
+/// Seconds to crossfade fully from one layer to the other, or to fade in and
+/// out when muting
+const CROSSFADE_SECS: f32 = 4.0;
+/// Seconds the tense layer keeps playing after a spider catches an ant
+const ALARM_HOLD_SECS: f32 = 30.0;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// How the music is playing
+#[derive(Resource, Default)]
+pub struct Music {
+    pub muted: bool,
+    /// Whether the colony is under threat and wants the tense layer
+    pub alarmed: bool,
+    /// Mix between the layers, from 0.0 (calm) to 1.0 (tense)
+    pub intensity: f32,
+    /// Overall loudness while fading in and out of mute, 0.0 to 1.0
+    pub fade: f32,
+    /// Seconds left holding the alarm after a predator kill
+    alarm_hold: f32,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// One of the looping music layers
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+pub enum MusicLayer {
+    Calm,
+    Alarm,
+}
+
+impl MusicLayer {
+    fn path(&self) -> &'static str {
+        match self {
+            MusicLayer::Calm => "music/calm.ogg",
+            MusicLayer::Alarm => "music/alarm.ogg",
+        }
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start both layers looping together, silent until the fade brings them in
+fn start_music(mut commands: Commands, asset_server: Res<AssetServer>) {
+    let layers = [MusicLayer::Calm, MusicLayer::Alarm];
+
+    // Native builds can check for the files first; the web has to ask for them
+    let missing = layers
+        .iter()
+        .find(|layer| !Path::new("assets").join(layer.path()).exists());
+    if !cfg!(target_arch = "wasm32")
+        && let Some(layer) = missing
+    {
+        info!("No music at assets/{}, playing without it", layer.path());
+        return;
+    }
+
+    for layer in layers {
+        commands.spawn((
+            layer,
+            AudioPlayer::new(asset_server.load(layer.path())),
+            PlaybackSettings::LOOP.with_volume(Volume::Linear(0.0)),
+        ));
+    }
+}
+
+/// Mute or unmute the music with K
+fn toggle_music(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut music: ResMut<Music>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyK) {
+        return;
+    }
+
+    music.muted = !music.muted;
+    notifications.write(Notification::info(if music.muted {
+        "Music muted"
+    } else {
+        "Music on"
+    }));
+}
+
+/// Sound the alarm while spiders hunt on the surface and for a while after
+/// one catches an ant
+fn colony_mood(
+    time: Res<Time<Real>>,
+    migrant_query: Query<&Migrant>,
+    mut deaths: MessageReader<AntDied>,
+    mut music: ResMut<Music>,
+) {
+    if deaths
+        .read()
+        .any(|death| death.cause == DeathCause::Predation)
+    {
+        music.alarm_hold = ALARM_HOLD_SECS;
+    }
+    music.alarm_hold = (music.alarm_hold - time.delta_secs()).max(0.0);
+
+    let hunting = migrant_query
+        .iter()
+        .any(|migrant| migrant.kind == MigrantKind::Spider && !migrant.leaving);
+    music.alarmed = hunting || music.alarm_hold > 0.0;
+}
+
+/// Ease the layers toward the colony's mood and the mute setting
+fn crossfade_music(
+    time: Res<Time<Real>>,
+    mut music: ResMut<Music>,
+    mut sink_query: Query<(&MusicLayer, &mut AudioSink)>,
+) {
+    let step = time.delta_secs() / CROSSFADE_SECS;
+    let intensity_target = if music.alarmed { 1.0 } else { 0.0 };
+    let fade_target = if music.muted { 0.0 } else { 1.0 };
+    music.intensity += (intensity_target - music.intensity).clamp(-step, step);
+    music.fade += (fade_target - music.fade).clamp(-step, step);
+
+    for (layer, mut sink) in &mut sink_query {
+        let mix = match layer {
+            MusicLayer::Calm => 1.0 - music.intensity,
+            MusicLayer::Alarm => music.intensity,
+        };
+        sink.set_volume(Volume::Linear(MUSIC_VOLUME * mix * music.fade));
+    }
+}
@@ -0,0 +1,192 @@
+//! Player-facing notifications.
+//!
+//! Systems post a `Notification` message; the most recent one is shown in the
+//! UI for a few seconds, and warnings play a short alert tone. Alerts and
+//! achievements pop up toasts instead, each in its own stack, which come down
+//! on their own after a few seconds.
+
+use std::time::Duration;
+
+use bevy::audio::Pitch;
+use bevy::prelude::*;
+
+use crate::{ResetRun, reset_resource};
+
+pub struct NotificationsPlugin;
+
+impl Plugin for NotificationsPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_message::<Notification>()
+            .init_resource::<NotificationFeed>()
+            .add_systems(Startup, setup_toast_stacks)
+            .add_systems(
+                Update,
+                (receive_notifications, expire_notifications, expire_toasts).chain(),
+            )
+            .add_systems(ResetRun, reset_resource::<NotificationFeed>);
+    }
+}
+
+/// How long a notification stays on screen
+const NOTIFICATION_SECONDS: f32 = 8.0;
+/// Frequency of the warning tone (Hz)
+const WARNING_TONE_HZ: f32 = 660.0;
+/// Length of the warning tone
+const WARNING_TONE_MILLIS: u64 = 250;
+/// How long a toast stays up
+const TOAST_SECONDS: f32 = 6.0;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum NotificationLevel {
+    Info,
+    Warning,
+}
+
+/// A message for the player
+#[derive(Message, Debug, Clone)]
+pub struct Notification {
+    pub level: NotificationLevel,
+    pub text: String,
+}
+
+impl Notification {
+    pub fn info(text: impl Into<String>) -> Self {
+        Self {
+            level: NotificationLevel::Info,
+            text: text.into(),
+        }
+    }
+
+    pub fn warning(text: impl Into<String>) -> Self {
+        Self {
+            level: NotificationLevel::Warning,
+            text: text.into(),
+        }
+    }
+}
+
+/// The notification currently on screen
+#[derive(Resource, Default)]
+pub struct NotificationFeed {
+    pub current: Option<(Notification, Timer)>,
+}
+
+// ============================================================================
+// Toasts
+// ============================================================================
+
+/// A container toasts stack up in, by what they're about
+#[derive(Component, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ToastStack {
+    /// Colony alerts, under the alert icon at the top
+    Alerts,
+    /// Unlocked achievements, near the bottom
+    Achievements,
+}
+
+impl ToastStack {
+    pub const ALL: [ToastStack; 2] = [ToastStack::Alerts, ToastStack::Achievements];
+
+    /// Find this stack's entity
+    pub fn find(self, stack_query: &Query<(Entity, &ToastStack)>) -> Option<Entity> {
+        stack_query
+            .iter()
+            .find(|(_, stack)| **stack == self)
+            .map(|(entity, _)| entity)
+    }
+}
+
+/// A pop-up in a toast stack, taken down once it's been up long enough
+#[derive(Component)]
+pub struct Toast(Timer);
+
+impl Default for Toast {
+    fn default() -> Self {
+        Self(Timer::from_seconds(TOAST_SECONDS, TimerMode::Once))
+    }
+}
+
+/// Spawn the (empty) stacks toasts go in
+fn setup_toast_stacks(mut commands: Commands) {
+    for stack in ToastStack::ALL {
+        let node = match stack {
+            ToastStack::Alerts => Node {
+                left: Val::Percent(35.0),
+                top: Val::Px(44.0),
+                ..default()
+            },
+            ToastStack::Achievements => Node {
+                right: Val::Percent(35.0),
+                bottom: Val::Px(60.0),
+                ..default()
+            },
+        };
+        commands.spawn((
+            stack,
+            Node {
+                position_type: PositionType::Absolute,
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                ..node
+            },
+        ));
+    }
+}
+
+/// Show incoming notifications and sound warnings
+fn receive_notifications(
+    mut commands: Commands,
+    mut notifications: MessageReader<Notification>,
+    mut feed: ResMut<NotificationFeed>,
+    pitch_assets: Option<ResMut<Assets<Pitch>>>,
+) {
+    let mut play_tone = false;
+
+    for notification in notifications.read() {
+        match notification.level {
+            NotificationLevel::Info => info!("{}", notification.text),
+            NotificationLevel::Warning => {
+                warn!("{}", notification.text);
+                play_tone = true;
+            }
+        }
+        feed.current = Some((
+            notification.clone(),
+            Timer::from_seconds(NOTIFICATION_SECONDS, TimerMode::Once),
+        ));
+    }
+
+    if play_tone && let Some(mut pitch_assets) = pitch_assets {
+        commands.spawn((
+            AudioPlayer(pitch_assets.add(Pitch::new(
+                WARNING_TONE_HZ,
+                Duration::from_millis(WARNING_TONE_MILLIS),
+            ))),
+            PlaybackSettings::DESPAWN,
+        ));
+    }
+}
+
+/// Clear the on-screen notification once it has been shown long enough
+fn expire_notifications(time: Res<Time<Real>>, mut feed: ResMut<NotificationFeed>) {
+    let expired = match feed.current.as_mut() {
+        Some((_, timer)) => timer.tick(time.delta()).is_finished(),
+        None => false,
+    };
+    if expired {
+        feed.current = None;
+    }
+}
+
+/// Take toasts down once they've been up long enough
+fn expire_toasts(
+    mut commands: Commands,
+    time: Res<Time<Real>>,
+    mut toast_query: Query<(Entity, &mut Toast)>,
+) {
+    for (entity, mut toast) in &mut toast_query {
+        if toast.0.tick(time.delta()).is_finished() {
+            commands.entity(entity).despawn();
+        }
+    }
+}
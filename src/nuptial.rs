@@ -0,0 +1,207 @@
+//! The nuptial flight, the colony's goal.
+//!
+//! Once the colony is large and well fed, the queen starts laying alates:
+//! winged princesses and males who do no work. When enough of them have grown
+//! up, on a spring or summer day they gather at the nest entrance and take off
+//! together. Spiders near the nest catch what they can, and if enough alates
+//! get away to found colonies of their own, the run is won.
+
+use bevy::prelude::*;
+
+use crate::ants::{Age, Ant, Caste, GridPosition, NestLocation, Task, step_toward};
+use crate::migrations::{Migrant, MigrantKind};
+use crate::notifications::Notification;
+use crate::pathfinding::Route;
+use crate::time_controls::{Season, SimulationSet, SimulationTick};
+use crate::world::{FoodStores, WorldGrid};
+use crate::{ResetRun, reset_resource};
+
+pub struct NuptialPlugin;
+
+impl Plugin for NuptialPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NuptialFlight>()
+            .add_message::<FlightSucceeded>()
+            .add_systems(
+                FixedUpdate,
+                (check_maturity, gather_alates, launch_flight)
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, reset_resource::<NuptialFlight>);
+    }
+}
+
+/// Grown alates the colony raises for a flight
+pub const ALATES_FOR_FLIGHT: usize = 6;
+/// Workers the colony needs before the queen lays alates
+const MATURE_POPULATION: usize = 30;
+/// Food in the stores the colony needs before the queen lays alates
+const MATURE_FOOD: u32 = 30;
+/// Alates that have to get away for the flight to count
+const MIN_SURVIVORS: usize = 3;
+/// How close to the nest a spider has to be to catch an alate taking off
+const SPIDER_REACH: usize = 6;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// How far the colony is toward its nuptial flight
+#[derive(Resource, Default)]
+pub struct NuptialFlight {
+    /// Big and well fed enough to raise alates
+    pub mature: bool,
+    /// The grown alates are heading for the entrance to take off
+    pub gathering: bool,
+}
+
+impl NuptialFlight {
+    /// Whether the queen's next egg should be an alate, given how many alates
+    /// (grown or still brood) the colony already has
+    pub fn wants_alate(&self, alates: usize) -> bool {
+        self.mature && alates < ALATES_FOR_FLIGHT
+    }
+}
+
+// ============================================================================
+// Messages
+// ============================================================================
+
+/// Sent when enough alates got away on a nuptial flight to win the run
+#[derive(Message, Debug, Clone, Copy)]
+pub struct FlightSucceeded {
+    /// Alates that took off
+    pub alates: usize,
+    /// Alates that got past the spiders
+    pub survivors: usize,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start raising alates once the colony is big and well fed
+fn check_maturity(
+    caste_query: Query<&Caste, With<Ant>>,
+    food_stores: Res<FoodStores>,
+    mut flight: ResMut<NuptialFlight>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if flight.mature {
+        return;
+    }
+
+    let workers = caste_query
+        .iter()
+        .filter(|caste| !matches!(caste, Caste::Queen | Caste::Alate))
+        .count();
+    if workers >= MATURE_POPULATION && food_stores.food >= MATURE_FOOD {
+        flight.mature = true;
+        info!("Colony mature with {} workers", workers);
+        notifications.write(Notification::info(
+            "The colony has matured - the queen has begun laying alates",
+        ));
+    }
+}
+
+/// Once enough alates have grown up in a flying season, send them to the
+/// nest entrance
+fn gather_alates(
+    mut ant_query: Query<(&mut GridPosition, &mut Route, &Caste, &Age, &Task), With<Ant>>,
+    world_grid: Res<WorldGrid>,
+    nest_location: Res<NestLocation>,
+    tick: Res<SimulationTick>,
+    mut flight: ResMut<NuptialFlight>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !flight.mature {
+        return;
+    }
+
+    if !flight.gathering {
+        let grown = ant_query
+            .iter()
+            .filter(|(_, _, caste, age, _)| **caste == Caste::Alate && age.maturity() >= 1.0)
+            .count();
+        if grown < ALATES_FOR_FLIGHT || !matches!(tick.season(), Season::Spring | Season::Summer) {
+            return;
+        }
+        flight.gathering = true;
+        notifications.write(Notification::info(
+            "The alates are gathering at the nest entrance for their flight",
+        ));
+    }
+
+    let nest = (nest_location.x, nest_location.y, nest_location.z);
+    for (mut pos, mut route, caste, age, task) in &mut ant_query {
+        // Hungry alates eat before they go
+        if *caste != Caste::Alate || age.maturity() < 1.0 || matches!(task, Task::SeekingFood) {
+            continue;
+        }
+        if (pos.x, pos.y, pos.z) != nest {
+            step_toward(&mut pos, &mut route, nest, &world_grid);
+        }
+    }
+}
+
+/// Take off once every grown alate is at the entrance, losing one to each
+/// spider lying in wait nearby
+fn launch_flight(
+    mut commands: Commands,
+    ant_query: Query<(Entity, &GridPosition, &Caste, &Age), With<Ant>>,
+    migrant_query: Query<&Migrant>,
+    nest_location: Res<NestLocation>,
+    mut flight: ResMut<NuptialFlight>,
+    mut succeeded: MessageWriter<FlightSucceeded>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !flight.gathering {
+        return;
+    }
+
+    let nest = (nest_location.x, nest_location.y, nest_location.z);
+    let alates: Vec<(Entity, bool)> = ant_query
+        .iter()
+        .filter(|(_, _, caste, age)| **caste == Caste::Alate && age.maturity() >= 1.0)
+        .map(|(entity, pos, ..)| (entity, (pos.x, pos.y, pos.z) == nest))
+        .collect();
+    if alates.iter().any(|&(_, ready)| !ready) {
+        return;
+    }
+    flight.gathering = false;
+    if alates.is_empty() {
+        return;
+    }
+
+    let spiders = migrant_query
+        .iter()
+        .filter(|migrant| {
+            migrant.kind == MigrantKind::Spider
+                && !migrant.leaving
+                && migrant.x.abs_diff(nest.0) + migrant.y.abs_diff(nest.1) <= SPIDER_REACH
+        })
+        .count();
+    let survivors = alates.len().saturating_sub(spiders);
+
+    for &(entity, _) in &alates {
+        commands.entity(entity).despawn();
+    }
+    info!(
+        "Nuptial flight: {} alates took off, {} got away",
+        alates.len(),
+        survivors
+    );
+
+    if survivors >= MIN_SURVIVORS {
+        succeeded.write(FlightSucceeded {
+            alates: alates.len(),
+            survivors,
+        });
+    } else {
+        notifications.write(Notification::warning(format!(
+            "Spiders caught the alates as they took off - only {} got away",
+            survivors
+        )));
+    }
+}
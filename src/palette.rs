@@ -0,0 +1,48 @@
+//! Colorblind-friendly palettes.
+//!
+//! Y cycles the palette used for the colors that tell castes and pheromones
+//! apart (or launch with `--palette <name>`). The colors themselves live in
+//! `sprites.rs`; anything drawn in a caste or pheromone color looks up the
+//! active `Palette`.
+
+use bevy::prelude::*;
+
+use crate::cli::CliArgs;
+use crate::notifications::Notification;
+use crate::sprites::Palette;
+
+pub struct PalettePlugin;
+
+impl Plugin for PalettePlugin {
+    fn build(&self, app: &mut App) {
+        let palette = app
+            .world()
+            .get_resource::<CliArgs>()
+            .map(|cli| cli.palette)
+            .unwrap_or_default();
+
+        app.insert_resource(palette)
+            .add_systems(Update, cycle_palette);
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Switch to the next palette with Y
+fn cycle_palette(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut palette: ResMut<Palette>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyY) {
+        return;
+    }
+
+    *palette = palette.next();
+    notifications.write(Notification::info(format!(
+        "Color palette: {}",
+        palette.name()
+    )));
+}
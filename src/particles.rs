@@ -0,0 +1,209 @@
+//! Particle effects.
+//!
+//! Short-lived sprites for a little life on screen: a puff of dirt when a tile
+//! is dug out, rain streaks over the surface while it rains, and a flash where
+//! a spider strikes or is fought off. Each particle belongs to a z-level and,
+//! like everything else, only shows while that level is in view. Particles are
+//! purely cosmetic, so they draw on their own random numbers rather than the
+//! simulation's.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::low_spec::cosmetic_frame;
+use crate::migrations::CombatHit;
+use crate::moisture::Weather;
+use crate::sprites;
+use crate::world::{CurrentZLevel, SURFACE_LEVEL, TILE_SIZE, TileDug, WORLD_SIZE, tile_to_world};
+use crate::{ResetRun, despawn_all};
+
+pub struct ParticlesPlugin;
+
+impl Plugin for ParticlesPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(
+            Update,
+            (
+                spawn_dirt_puffs,
+                spawn_hit_flashes,
+                spawn_rain.run_if(cosmetic_frame),
+                update_particles,
+            )
+                .chain(),
+        )
+        .add_systems(ResetRun, despawn_all::<Particle>);
+    }
+}
+
+/// Most particles alive at once; rain stops adding more past this
+const MAX_PARTICLES: usize = 600;
+/// Specks of dirt thrown up by each dug tile
+const DIRT_SPECKS: usize = 6;
+/// Rain streaks started each frame while it rains
+const RAIN_PER_FRAME: usize = 8;
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// A short-lived cosmetic sprite
+#[derive(Component)]
+pub struct Particle {
+    /// Z-level it shows on
+    pub z: usize,
+    /// World units per second
+    pub velocity: Vec2,
+    /// Seconds alive so far
+    pub age: f32,
+    /// Seconds it lives for, fading out as it goes
+    pub lifetime: f32,
+    /// Scale gained per second
+    pub growth: f32,
+    /// Opacity when it was spawned
+    pub alpha: f32,
+}
+
+/// Spawn a particle at a world position
+#[allow(clippy::too_many_arguments)]
+fn spawn_particle(
+    commands: &mut Commands,
+    position: Vec2,
+    z: usize,
+    color: Color,
+    size: Vec2,
+    velocity: Vec2,
+    lifetime: f32,
+    growth: f32,
+) {
+    commands.spawn((
+        Particle {
+            z,
+            velocity,
+            age: 0.0,
+            lifetime,
+            growth,
+            alpha: color.alpha(),
+        },
+        Sprite {
+            color,
+            custom_size: Some(size),
+            ..default()
+        },
+        // Over ants and brood, under the overlays
+        Transform::from_xyz(position.x, position.y, 1.5),
+    ));
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Throw up a puff of dirt from every tile dug out
+fn spawn_dirt_puffs(mut commands: Commands, mut dug_tiles: MessageReader<TileDug>) {
+    let mut rng = rand::rng();
+    for dug in dug_tiles.read() {
+        let center = tile_to_world(dug.x, dug.y);
+        for _ in 0..DIRT_SPECKS {
+            let angle = rng.random_range(0.0..std::f32::consts::TAU);
+            let speed = rng.random_range(10.0..30.0);
+            spawn_particle(
+                &mut commands,
+                center,
+                dug.z,
+                sprites::particles::DIRT,
+                Vec2::splat(sprites::particles::DIRT_SIZE),
+                Vec2::from_angle(angle) * speed,
+                rng.random_range(0.3..0.6),
+                0.0,
+            );
+        }
+    }
+}
+
+/// Flash where a spider strikes or is driven off
+fn spawn_hit_flashes(mut commands: Commands, mut hits: MessageReader<CombatHit>) {
+    for hit in hits.read() {
+        spawn_particle(
+            &mut commands,
+            tile_to_world(hit.x, hit.y),
+            hit.z,
+            sprites::particles::HIT,
+            Vec2::splat(sprites::particles::HIT_SIZE),
+            Vec2::ZERO,
+            0.25,
+            4.0,
+        );
+    }
+}
+
+/// Streak rain across the surface while it's raining and the sky is in view
+fn spawn_rain(
+    mut commands: Commands,
+    time: Res<Time>,
+    weather: Res<Weather>,
+    current_z: Res<CurrentZLevel>,
+    particle_query: Query<(), With<Particle>>,
+) {
+    // Frozen streaks would only pile up while paused
+    if time.delta_secs() == 0.0
+        || !weather.raining()
+        || current_z.0 < SURFACE_LEVEL
+        || particle_query.iter().count() >= MAX_PARTICLES
+    {
+        return;
+    }
+
+    let mut rng = rand::rng();
+    let half_world = WORLD_SIZE as f32 * TILE_SIZE / 2.0;
+    for _ in 0..RAIN_PER_FRAME {
+        let position = Vec2::new(
+            rng.random_range(-half_world..half_world),
+            rng.random_range(-half_world..half_world),
+        );
+        spawn_particle(
+            &mut commands,
+            position,
+            current_z.0,
+            sprites::particles::RAIN,
+            sprites::particles::RAIN_SIZE,
+            Vec2::new(-20.0, -300.0),
+            rng.random_range(0.2..0.4),
+            0.0,
+        );
+    }
+}
+
+/// Move, grow and fade particles, show only those on the current z-level, and
+/// clear away the ones that have run their course
+fn update_particles(
+    mut commands: Commands,
+    time: Res<Time>,
+    current_z: Res<CurrentZLevel>,
+    mut query: Query<(
+        Entity,
+        &mut Particle,
+        &mut Sprite,
+        &mut Transform,
+        &mut Visibility,
+    )>,
+) {
+    let dt = time.delta_secs();
+    for (entity, mut particle, mut sprite, mut transform, mut visibility) in &mut query {
+        particle.age += dt;
+        if particle.age >= particle.lifetime {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        transform.translation += particle.velocity.extend(0.0) * dt;
+        transform.scale += Vec3::splat(particle.growth * dt);
+        let remaining = 1.0 - particle.age / particle.lifetime;
+        sprite.color.set_alpha(particle.alpha * remaining);
+
+        *visibility = if particle.z == current_z.0 {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        };
+    }
+}
@@ -0,0 +1,170 @@
+//! Grid pathfinding through the passable parts of the world.
+//!
+//! Ants follow the shortest path to their target, found once and kept on the
+//! ant as a `Route` while it still leads there, so a colony on the move
+//! doesn't search the grid afresh for every ant on every tick. Moving between
+//! z-levels takes a ramp (or a tree to climb): an ant can't simply drop down a
+//! hole or scale a sheer tunnel wall.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::prelude::*;
+
+use crate::world::{TileKind, WORLD_SIZE, WorldGrid};
+
+/// Upper bound on tiles explored per search so a single unreachable target
+/// can't stall the simulation
+const MAX_SEARCH_NODES: usize = 8192;
+
+/// A tile coordinate in the world grid
+pub type Coord = (usize, usize, usize);
+
+/// Neighbor offsets: the four cardinal directions plus up and down
+const NEIGHBORS: [(i32, i32, i32); 6] = [
+    (1, 0, 0),
+    (-1, 0, 0),
+    (0, 1, 0),
+    (0, -1, 0),
+    (0, 0, 1),
+    (0, 0, -1),
+];
+
+/// Calls to wait after failing to find a path before searching again
+const RETRY_CALLS: u32 = 20;
+
+/// The path an ant is following, kept between ticks
+#[derive(Component, Default)]
+pub struct Route {
+    /// Where the path leads
+    target: Option<Coord>,
+    /// Tiles still to step onto, the next one last
+    steps: Vec<Coord>,
+    /// Where the ant should be standing if it's kept to the path
+    at: Option<Coord>,
+    /// Calls left before a target found unreachable is searched for again
+    retry_in: u32,
+}
+
+impl Route {
+    /// The next step from `from` toward `to`, following the path found before
+    /// while it's for the same target, the ant hasn't been moved off it and
+    /// the next tile on it can still be stepped onto, and searching again
+    /// otherwise.
+    ///
+    /// Returns `None` if the target is unreachable within the search budget or
+    /// the ant is already there.
+    pub fn next_step(&mut self, world_grid: &WorldGrid, from: Coord, to: Coord) -> Option<Coord> {
+        if from == to {
+            *self = Self::default();
+            return None;
+        }
+
+        let same_target = self.target == Some(to);
+        if same_target && self.steps.is_empty() && self.retry_in > 0 {
+            self.retry_in -= 1;
+            return None;
+        }
+        let on_route = same_target
+            && self.at == Some(from)
+            && self
+                .steps
+                .last()
+                .is_some_and(|&next| can_step(world_grid, from, next));
+        if !on_route {
+            self.target = Some(to);
+            self.steps = find_path(world_grid, from, to).unwrap_or_default();
+            self.retry_in = if self.steps.is_empty() {
+                RETRY_CALLS
+            } else {
+                0
+            };
+        }
+
+        let next = self.steps.pop()?;
+        self.at = Some(next);
+        Some(next)
+    }
+}
+
+/// Find the shortest passable path from `from` to `to`, as the tiles to step
+/// onto with the first one last.
+///
+/// Returns `None` if the target is unreachable within the search budget or
+/// the ant is already there.
+pub fn find_path(world_grid: &WorldGrid, from: Coord, to: Coord) -> Option<Vec<Coord>> {
+    if from == to {
+        return None;
+    }
+
+    let mut came_from: HashMap<Coord, Coord> = HashMap::new();
+    let mut frontier = VecDeque::new();
+    came_from.insert(from, from);
+    frontier.push_back(from);
+
+    while let Some(current) = frontier.pop_front() {
+        if current == to {
+            // Walk back to the start, collecting the steps from the last
+            let mut steps = Vec::new();
+            let mut step = current;
+            while step != from {
+                steps.push(step);
+                step = came_from[&step];
+            }
+            return Some(steps);
+        }
+
+        if came_from.len() >= MAX_SEARCH_NODES {
+            return None;
+        }
+
+        for (dx, dy, dz) in NEIGHBORS {
+            let Some(next) = offset(current, dx, dy, dz) else {
+                continue;
+            };
+            if came_from.contains_key(&next) {
+                continue;
+            }
+            if !can_step(world_grid, current, next) {
+                continue;
+            }
+            came_from.insert(next, current);
+            frontier.push_back(next);
+        }
+    }
+
+    None
+}
+
+/// Check if an ant can step from one tile to a neighboring one: the
+/// destination must be passable, and moving up or down also needs a ramp or
+/// something to climb joining the two levels
+pub fn can_step(world_grid: &WorldGrid, from: Coord, to: Coord) -> bool {
+    let (tx, ty, tz) = to;
+    let destination = world_grid.tiles[tz][ty][tx];
+    if !destination.is_passable() {
+        return false;
+    }
+    if tz == from.2 {
+        return true;
+    }
+
+    let origin = world_grid.tiles[from.2][from.1][from.0];
+    if tz < from.2 {
+        TileKind::joins_vertically(destination, origin)
+    } else {
+        TileKind::joins_vertically(origin, destination)
+    }
+}
+
+/// Offset a coordinate, returning `None` if it leaves the world
+fn offset(coord: Coord, dx: i32, dy: i32, dz: i32) -> Option<Coord> {
+    let x = coord.0 as i32 + dx;
+    let y = coord.1 as i32 + dy;
+    let z = coord.2 as i32 + dz;
+    let range = 0..WORLD_SIZE as i32;
+    if range.contains(&x) && range.contains(&y) && range.contains(&z) {
+        Some((x as usize, y as usize, z as usize))
+    } else {
+        None
+    }
+}
@@ -0,0 +1,97 @@
+//! Detects when the simulation can't keep up with its target tick rate.
+//!
+//! Bevy quietly drops fixed-timestep ticks when frames take too long, so an
+//! overloaded colony just runs in slow motion. This watches the achieved tick
+//! rate and warns the player with suggestions when it stays low.
+
+use bevy::prelude::*;
+
+use crate::notifications::Notification;
+use crate::time_controls::{SimulationSpeed, SimulationTick};
+use crate::{GameState, ResetRun, reset_resource};
+
+pub struct PerformancePlugin;
+
+impl Plugin for PerformancePlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TickRateMonitor>()
+            .add_systems(Update, monitor_tick_rate)
+            .add_systems(ResetRun, reset_resource::<TickRateMonitor>);
+    }
+}
+
+/// Fraction of the target tick rate below which a second counts as slow
+const SLOW_TICK_RATIO: f32 = 0.8;
+/// Consecutive slow seconds before the player is warned
+const STALL_SECONDS: u32 = 5;
+
+/// Tracks the tick rate actually achieved over the last second
+#[derive(Resource)]
+pub struct TickRateMonitor {
+    /// Ticks per second achieved over the last measurement window
+    pub achieved_tps: f32,
+    /// Whether the simulation is currently considered stalled
+    pub stalled: bool,
+    window: Timer,
+    window_start_tick: u64,
+    slow_seconds: u32,
+}
+
+impl Default for TickRateMonitor {
+    fn default() -> Self {
+        Self {
+            achieved_tps: 0.0,
+            stalled: false,
+            window: Timer::from_seconds(1.0, TimerMode::Repeating),
+            window_start_tick: 0,
+            slow_seconds: 0,
+        }
+    }
+}
+
+/// Once a second, compare ticks completed against the target rate
+fn monitor_tick_rate(
+    time: Res<Time<Real>>,
+    tick: Res<SimulationTick>,
+    speed: Res<SimulationSpeed>,
+    game_state: Res<State<GameState>>,
+    mut monitor: ResMut<TickRateMonitor>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    // Paused time isn't a stall; start measuring fresh when resumed
+    if *game_state.get() != GameState::Playing {
+        monitor.window.reset();
+        monitor.window_start_tick = tick.0;
+        monitor.slow_seconds = 0;
+        return;
+    }
+
+    if !monitor.window.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let elapsed = monitor.window.duration().as_secs_f32();
+    monitor.achieved_tps = (tick.0 - monitor.window_start_tick) as f32 / elapsed;
+    monitor.window_start_tick = tick.0;
+
+    // Ultra mode finds its own speed, so falling short there isn't a stall
+    let target_tps = speed.ticks_per_second() as f32;
+    if monitor.achieved_tps < target_tps * SLOW_TICK_RATIO && !speed.ultra {
+        monitor.slow_seconds += 1;
+    } else {
+        monitor.slow_seconds = 0;
+        if monitor.stalled {
+            monitor.stalled = false;
+            notifications.write(Notification::info("Simulation is back up to speed"));
+        }
+    }
+
+    if monitor.slow_seconds >= STALL_SECONDS && !monitor.stalled {
+        monitor.stalled = true;
+        notifications.write(Notification::warning(format!(
+            "Simulation is running slow ({:.1} of {:.0} ticks/s). \
+             Try lowering the speed (-) or hiding the pheromone overlay (O).",
+            monitor.achieved_tps, target_tps
+        )));
+    }
+}
@@ -5,9 +5,13 @@
 
 use bevy::prelude::*;
 
-use crate::GameState;
-use crate::sprites;
-use crate::world::{CurrentZLevel, TILE_SIZE, WORLD_SIZE};
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::config::SimulationConfig;
+use crate::low_spec::{LowSpecMode, cosmetic_frame};
+use crate::sprites::{self, Palette};
+use crate::time_controls::SimulationSet;
+use crate::world::{CurrentZLevel, TILE_SIZE, WORLD_SIZE, WorldGrid, filled_grid};
+use crate::{ResetRun, reset_resource};
 
 pub struct PheromonePlugin;
 
@@ -15,19 +19,18 @@ impl Plugin for PheromonePlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<PheromoneGrids>()
             .init_resource::<SelectedPheromoneType>()
+            .init_resource::<PheromoneOverlaySettings>()
+            .init_resource::<TrailPruning>()
             .add_systems(Startup, spawn_pheromone_overlay)
             .add_systems(
                 Update,
                 (
-                    pheromone_input,
-                    update_pheromone_overlay,
-                    cycle_pheromone_type,
+                    apply_pheromone_actions.in_set(ActionSystems::Apply),
+                    update_pheromone_overlay.run_if(cosmetic_frame),
                 ),
             )
-            .add_systems(
-                FixedUpdate,
-                pheromone_decay.run_if(in_state(GameState::Running)),
-            );
+            .add_systems(FixedUpdate, pheromone_decay.in_set(SimulationSet))
+            .add_systems(ResetRun, reset_resource::<PheromoneGrids>);
     }
 }
 
@@ -45,12 +48,22 @@ pub enum PheromoneType {
 }
 
 impl PheromoneType {
-    pub fn color(&self) -> Color {
+    /// Every pheromone, in `PheromoneGrids::totals` order
+    pub const ALL: [PheromoneType; 4] = [
+        PheromoneType::Dig,
+        PheromoneType::Forage,
+        PheromoneType::Home,
+        PheromoneType::Avoid,
+    ];
+
+    /// The pheromone's overlay color in a palette
+    pub fn color(&self, palette: Palette) -> Color {
+        let [dig, forage, home, avoid] = sprites::pheromones::colors(palette);
         match self {
-            PheromoneType::Dig => sprites::pheromones::DIG,
-            PheromoneType::Forage => sprites::pheromones::FORAGE,
-            PheromoneType::Home => sprites::pheromones::HOME,
-            PheromoneType::Avoid => sprites::pheromones::AVOID,
+            PheromoneType::Dig => dig,
+            PheromoneType::Forage => forage,
+            PheromoneType::Home => home,
+            PheromoneType::Avoid => avoid,
         }
     }
 
@@ -62,8 +75,26 @@ impl PheromoneType {
             PheromoneType::Avoid => "Avoid",
         }
     }
+
+    /// Look up a pheromone type by its display name
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL.into_iter().find(|ptype| ptype.name() == name)
+    }
+
+    /// The next type in the list, wrapping around
+    pub fn next(&self) -> Self {
+        match self {
+            PheromoneType::Dig => PheromoneType::Forage,
+            PheromoneType::Forage => PheromoneType::Home,
+            PheromoneType::Home => PheromoneType::Avoid,
+            PheromoneType::Avoid => PheromoneType::Dig,
+        }
+    }
 }
 
+/// Pheromone laid on a tile each frame the player paints it
+pub const PLACE_AMOUNT: f32 = 0.1;
+
 // ============================================================================
 // Resources
 // ============================================================================
@@ -80,15 +111,28 @@ pub struct PheromoneGrids {
 impl Default for PheromoneGrids {
     fn default() -> Self {
         Self {
-            dig: Box::new([[[0.0; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]),
-            forage: Box::new([[[0.0; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]),
-            home: Box::new([[[0.0; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]),
-            avoid: Box::new([[[0.0; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]),
+            dig: filled_grid(0.0),
+            forage: filled_grid(0.0),
+            home: filled_grid(0.0),
+            avoid: filled_grid(0.0),
         }
     }
 }
 
 impl PheromoneGrids {
+    /// Summed intensity over each whole field: dig, forage, home, avoid
+    pub fn totals(&self) -> [f32; 4] {
+        let total = |grid: &[[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]| -> f32 {
+            grid.iter().flatten().flatten().sum()
+        };
+        [
+            total(&self.dig),
+            total(&self.forage),
+            total(&self.home),
+            total(&self.avoid),
+        ]
+    }
+
     /// Get the intensity of a pheromone type at a position
     pub fn get(&self, ptype: PheromoneType, x: usize, y: usize, z: usize) -> f32 {
         match ptype {
@@ -121,6 +165,36 @@ impl PheromoneGrids {
 #[derive(Resource, Default)]
 pub struct SelectedPheromoneType(pub PheromoneType);
 
+/// Trail hygiene: speeds up decay of pheromone over tiles ants can't walk on,
+/// such as trails buried by a collapse or paint that landed on solid soil
+#[derive(Resource)]
+pub struct TrailPruning {
+    pub enabled: bool,
+    /// How many times faster stale pheromone decays
+    pub multiplier: f32,
+}
+
+impl Default for TrailPruning {
+    fn default() -> Self {
+        Self {
+            enabled: true,
+            multiplier: 10.0,
+        }
+    }
+}
+
+/// Whether the pheromone overlay is drawn
+#[derive(Resource)]
+pub struct PheromoneOverlaySettings {
+    pub visible: bool,
+}
+
+impl Default for PheromoneOverlaySettings {
+    fn default() -> Self {
+        Self { visible: true }
+    }
+}
+
 // ============================================================================
 // Components
 // ============================================================================
@@ -161,8 +235,20 @@ fn spawn_pheromone_overlay(mut commands: Commands) {
 fn update_pheromone_overlay(
     pheromones: Res<PheromoneGrids>,
     current_z: Res<CurrentZLevel>,
+    settings: Res<PheromoneOverlaySettings>,
+    low_spec: Res<LowSpecMode>,
+    palette: Res<Palette>,
     mut query: Query<(&PheromoneOverlay, &mut Sprite, &mut Visibility)>,
 ) {
+    if !settings.visible {
+        if settings.is_changed() {
+            for (_, _, mut visibility) in &mut query {
+                *visibility = Visibility::Hidden;
+            }
+        }
+        return;
+    }
+
     let z = current_z.0;
 
     for (overlay, mut sprite, mut visibility) in &mut query {
@@ -181,13 +267,26 @@ fn update_pheromone_overlay(
         if max_value > 0.01 {
             *visibility = Visibility::Visible;
 
+            // Low-spec mode skips blending and shows only the strongest signal
+            if low_spec.enabled {
+                let strongest = if max_value == dig {
+                    PheromoneType::Dig
+                } else if max_value == forage {
+                    PheromoneType::Forage
+                } else if max_value == home {
+                    PheromoneType::Home
+                } else {
+                    PheromoneType::Avoid
+                };
+                sprite.color = strongest.color(*palette).with_alpha(max_value * 0.6);
+                continue;
+            }
+
             // Blend colors based on relative intensities
             let total = dig + forage + home + avoid;
             if total > 0.0 {
-                let dig_color = sprites::pheromones::DIG;
-                let forage_color = sprites::pheromones::FORAGE;
-                let home_color = sprites::pheromones::HOME;
-                let avoid_color = sprites::pheromones::AVOID;
+                let [dig_color, forage_color, home_color, avoid_color] =
+                    sprites::pheromones::colors(*palette);
 
                 // Weighted blend
                 let r = (color_r(dig_color) * dig
@@ -236,89 +335,71 @@ fn color_b(c: Color) -> f32 {
     }
 }
 
-/// Decay all pheromones over time
-fn pheromone_decay(mut pheromones: ResMut<PheromoneGrids>) {
-    const DECAY_RATE: f32 = 0.0005; // Per tick - slow decay for persistent trails
+/// Decay all pheromones over time. With trail pruning enabled, signals left
+/// over tiles ants can't use (solid soil, open air) fade much faster.
+fn pheromone_decay(
+    mut pheromones: ResMut<PheromoneGrids>,
+    world_grid: Res<WorldGrid>,
+    pruning: Res<TrailPruning>,
+    config: Res<SimulationConfig>,
+) {
+    // Per tick - slow decay for persistent trails
+    let decay_rate = config.pheromone_decay;
+    let stale_rate = if pruning.enabled {
+        decay_rate * pruning.multiplier
+    } else {
+        decay_rate
+    };
 
     for z in 0..WORLD_SIZE {
         for y in 0..WORLD_SIZE {
             for x in 0..WORLD_SIZE {
+                let tile = world_grid.tiles[z][y][x];
+                // Trails only mean something where ants can walk; dig marks
+                // also belong on soil waiting to be dug
+                let trail_rate = if tile.is_passable() {
+                    decay_rate
+                } else {
+                    stale_rate
+                };
+                let dig_rate = if tile.is_passable() || tile.is_diggable() {
+                    decay_rate
+                } else {
+                    stale_rate
+                };
+
                 if pheromones.dig[z][y][x] > 0.0 {
-                    pheromones.dig[z][y][x] = (pheromones.dig[z][y][x] - DECAY_RATE).max(0.0);
+                    pheromones.dig[z][y][x] = (pheromones.dig[z][y][x] - dig_rate).max(0.0);
                 }
                 if pheromones.forage[z][y][x] > 0.0 {
-                    pheromones.forage[z][y][x] = (pheromones.forage[z][y][x] - DECAY_RATE).max(0.0);
+                    pheromones.forage[z][y][x] = (pheromones.forage[z][y][x] - trail_rate).max(0.0);
                 }
                 if pheromones.home[z][y][x] > 0.0 {
-                    pheromones.home[z][y][x] = (pheromones.home[z][y][x] - DECAY_RATE).max(0.0);
+                    pheromones.home[z][y][x] = (pheromones.home[z][y][x] - trail_rate).max(0.0);
                 }
                 if pheromones.avoid[z][y][x] > 0.0 {
-                    pheromones.avoid[z][y][x] = (pheromones.avoid[z][y][x] - DECAY_RATE).max(0.0);
+                    pheromones.avoid[z][y][x] = (pheromones.avoid[z][y][x] - trail_rate).max(0.0);
                 }
             }
         }
     }
 }
 
-/// Handle player pheromone placement via mouse click
-fn pheromone_input(
-    mouse_button: Res<ButtonInput<MouseButton>>,
-    windows: Query<&Window>,
-    camera_query: Query<(&Camera, &GlobalTransform)>,
-    current_z: Res<CurrentZLevel>,
-    selected_type: Res<SelectedPheromoneType>,
+/// Apply pheromone placements from live or replayed input
+fn apply_pheromone_actions(
+    mut actions: MessageReader<PlayerAction>,
     mut pheromones: ResMut<PheromoneGrids>,
 ) {
-    if !mouse_button.pressed(MouseButton::Left) {
-        return;
-    }
-
-    let Ok(window) = windows.single() else {
-        return;
-    };
-
-    let Ok((camera, camera_transform)) = camera_query.single() else {
-        return;
-    };
-
-    let Some(cursor_pos) = window.cursor_position() else {
-        return;
-    };
-
-    // Convert screen position to world position
-    let Ok(world_pos) = camera.viewport_to_world_2d(camera_transform, cursor_pos) else {
-        return;
-    };
-
-    // Convert world position to grid position
-    let grid_x = ((world_pos.x / TILE_SIZE) + (WORLD_SIZE as f32 / 2.0)).floor() as i32;
-    let grid_y = ((world_pos.y / TILE_SIZE) + (WORLD_SIZE as f32 / 2.0)).floor() as i32;
-
-    // Bounds check
-    if grid_x < 0 || grid_x >= WORLD_SIZE as i32 || grid_y < 0 || grid_y >= WORLD_SIZE as i32 {
-        return;
-    }
-
-    let x = grid_x as usize;
-    let y = grid_y as usize;
-    let z = current_z.0;
-
-    // Add pheromone at this location
-    pheromones.add(selected_type.0, x, y, z, 0.1);
-}
-
-/// Cycle through pheromone types with Tab key
-fn cycle_pheromone_type(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    mut selected: ResMut<SelectedPheromoneType>,
-) {
-    if keyboard.just_pressed(KeyCode::Tab) {
-        selected.0 = match selected.0 {
-            PheromoneType::Dig => PheromoneType::Forage,
-            PheromoneType::Forage => PheromoneType::Home,
-            PheromoneType::Home => PheromoneType::Avoid,
-            PheromoneType::Avoid => PheromoneType::Dig,
-        };
-        info!("Selected pheromone: {}", selected.0.name());
+    for action in actions.read() {
+        if let PlayerAction::PlacePheromone {
+            ptype,
+            x,
+            y,
+            z,
+            amount,
+        } = *action
+        {
+            pheromones.add(ptype, x, y, z, amount);
+        }
     }
 }
@@ -0,0 +1,223 @@
+//! Nest poster export.
+//!
+//! F10 draws every z-level of the world, top to bottom, side by side into one
+//! PNG poster under `posters/`. Shift+F10 instead cuts a vertical slice
+//! through the row under the cursor, showing the nest from the side. Both
+//! are drawn straight from the world grid, so they don't depend on where the
+//! camera is or how far it's zoomed, and show tiles the way the game does,
+//! keeping unexplored ground hidden.
+
+use std::path::Path;
+
+use bevy::asset::RenderAssetUsages;
+use bevy::color::ColorToPacked;
+use bevy::prelude::*;
+use bevy::render::render_resource::{Extent3d, TextureDimension, TextureFormat};
+
+use crate::biome::Biome;
+use crate::content::Content;
+use crate::cursor::CursorTile;
+use crate::exploration::Explored;
+use crate::notifications::Notification;
+use crate::screenshots::timestamped_png;
+use crate::sprites;
+use crate::world::{TileKind, WORLD_SIZE, WorldGrid};
+
+pub struct PosterPlugin;
+
+impl Plugin for PosterPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, export_poster);
+    }
+}
+
+/// Directory posters are written to
+const POSTERS_DIR: &str = "posters";
+/// Pixels per tile on the all-levels poster
+const LEVEL_TILE_PX: usize = 4;
+/// Pixels per tile on a vertical slice
+const SLICE_TILE_PX: usize = 8;
+/// Levels per row of the all-levels poster
+const LEVELS_PER_ROW: usize = 8;
+/// Pixels between levels on the all-levels poster
+const LEVEL_GAP_PX: usize = 4;
+/// Color of the gaps between levels
+const GAP_COLOR: [u8; 4] = [16, 16, 16, 255];
+
+/// An RGBA canvas to paint tiles on
+struct Canvas {
+    width: usize,
+    height: usize,
+    pixels: Vec<u8>,
+}
+
+impl Canvas {
+    fn new(width: usize, height: usize, fill: [u8; 4]) -> Self {
+        Self {
+            width,
+            height,
+            pixels: fill.repeat(width * height),
+        }
+    }
+
+    /// Fill a `size`-pixel square with its top-left corner at (`left`, `top`)
+    fn square(&mut self, left: usize, top: usize, size: usize, color: [u8; 4]) {
+        for y in top..top + size {
+            let row = y * self.width;
+            for x in left..left + size {
+                self.pixels[(row + x) * 4..(row + x + 1) * 4].copy_from_slice(&color);
+            }
+        }
+    }
+
+    fn save(self, path: &Path) -> Result<(), String> {
+        let image = Image::new(
+            Extent3d {
+                width: self.width as u32,
+                height: self.height as u32,
+                depth_or_array_layers: 1,
+            },
+            TextureDimension::D2,
+            self.pixels,
+            TextureFormat::Rgba8UnormSrgb,
+            RenderAssetUsages::default(),
+        );
+        image
+            .try_into_dynamic()
+            .map_err(|e| e.to_string())?
+            .to_rgba8()
+            .save(path)
+            .map_err(|e| e.to_string())
+    }
+}
+
+/// The color a tile shows in, as the game would draw it
+fn tile_color(
+    world_grid: &WorldGrid,
+    explored: &Explored,
+    content: &Content,
+    biome: Biome,
+    x: usize,
+    y: usize,
+    z: usize,
+) -> [u8; 4] {
+    let tile_kind = world_grid.tiles[z][y][x];
+    let color = if !explored.shows(tile_kind, x, y, z) {
+        sprites::tiles::UNEXPLORED
+    } else if tile_kind == TileKind::Dirt {
+        world_grid.soil[z][y][x].color(biome)
+    } else {
+        content.biome_color(tile_kind, biome)
+    };
+    color.to_srgba().to_u8_array()
+}
+
+/// Every z-level, highest first, in rows of `LEVELS_PER_ROW`
+fn all_levels(
+    world_grid: &WorldGrid,
+    explored: &Explored,
+    content: &Content,
+    biome: Biome,
+) -> Canvas {
+    let level_px = WORLD_SIZE * LEVEL_TILE_PX;
+    let rows = WORLD_SIZE.div_ceil(LEVELS_PER_ROW);
+    let mut canvas = Canvas::new(
+        LEVELS_PER_ROW * (level_px + LEVEL_GAP_PX) + LEVEL_GAP_PX,
+        rows * (level_px + LEVEL_GAP_PX) + LEVEL_GAP_PX,
+        GAP_COLOR,
+    );
+
+    for (slot, z) in (0..WORLD_SIZE).rev().enumerate() {
+        let left = LEVEL_GAP_PX + (slot % LEVELS_PER_ROW) * (level_px + LEVEL_GAP_PX);
+        let top = LEVEL_GAP_PX + (slot / LEVELS_PER_ROW) * (level_px + LEVEL_GAP_PX);
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                // North up, as in the game view
+                let color = tile_color(world_grid, explored, content, biome, x, y, z);
+                canvas.square(
+                    left + x * LEVEL_TILE_PX,
+                    top + (WORLD_SIZE - 1 - y) * LEVEL_TILE_PX,
+                    LEVEL_TILE_PX,
+                    color,
+                );
+            }
+        }
+    }
+
+    canvas
+}
+
+/// A side view of row `y`, from the sky down to bedrock
+fn slice(
+    world_grid: &WorldGrid,
+    explored: &Explored,
+    content: &Content,
+    biome: Biome,
+    y: usize,
+) -> Canvas {
+    let size = WORLD_SIZE * SLICE_TILE_PX;
+    let mut canvas = Canvas::new(size, size, GAP_COLOR);
+
+    for z in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let color = tile_color(world_grid, explored, content, biome, x, y, z);
+            canvas.square(
+                x * SLICE_TILE_PX,
+                (WORLD_SIZE - 1 - z) * SLICE_TILE_PX,
+                SLICE_TILE_PX,
+                color,
+            );
+        }
+    }
+
+    canvas
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Export every level with F10, or a slice through the cursor with Shift+F10
+fn export_poster(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    world_grid: Res<WorldGrid>,
+    explored: Res<Explored>,
+    content: Res<Content>,
+    biome: Res<Biome>,
+    cursor: Res<CursorTile>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::F10) {
+        return;
+    }
+
+    // There's no filesystem in a web build
+    if cfg!(target_arch = "wasm32") {
+        notifications.write(Notification::warning(
+            "Posters can't be saved in a web build",
+        ));
+        return;
+    }
+
+    let (kind, canvas) = if keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]) {
+        // Through the middle of the world when the cursor is off it
+        let y = cursor.0.map_or(WORLD_SIZE / 2, |(_, y, _)| y);
+        ("slice", slice(&world_grid, &explored, &content, *biome, y))
+    } else {
+        ("nest", all_levels(&world_grid, &explored, &content, *biome))
+    };
+
+    let saved = timestamped_png(POSTERS_DIR, kind)
+        .map_err(|e| e.to_string())
+        .and_then(|path| canvas.save(&path).map(|()| path));
+    match saved {
+        Ok(path) => notifications.write(Notification::info(format!(
+            "Poster saved to {}",
+            path.display()
+        ))),
+        Err(e) => {
+            error!("Failed to save poster: {}", e);
+            notifications.write(Notification::warning("Couldn't save the poster"))
+        }
+    };
+}
@@ -0,0 +1,81 @@
+//! The player's profile: what carries over from one game to the next.
+//!
+//! The profile is kept in `profile.ron` in the working directory, read at
+//! startup and written again whenever something in it changes. It holds the
+//! achievements unlocked (see `achievements.rs`) and the lifetime statistics
+//! gathered over every game (see `statistics.rs`). A web build has nowhere
+//! to keep it, so there the profile only lasts as long as the page.
+
+use std::fs;
+use std::path::Path;
+
+use bevy::prelude::*;
+use serde::{Deserialize, Serialize};
+
+use crate::achievements::Achievement;
+
+pub struct ProfilePlugin;
+
+impl Plugin for ProfilePlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_profile());
+    }
+}
+
+/// Where the profile is kept
+const PROFILE_PATH: &str = "profile.ron";
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Everything the player has done across all their games
+#[derive(Resource, Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct Profile {
+    /// Achievements unlocked, in the order they were
+    pub achievements: Vec<Achievement>,
+    /// Colonies started from the setup screen
+    pub colonies_founded: u32,
+    /// Adults raised from brood in every colony together
+    pub ants_raised: u64,
+    /// Longest any colony has lasted, in ticks
+    pub longest_survival: u64,
+}
+
+impl Profile {
+    /// Write the profile out, warning if it can't be
+    pub fn save(&self) {
+        // There's no filesystem in a web build
+        if cfg!(target_arch = "wasm32") {
+            return;
+        }
+        let result = ron::ser::to_string_pretty(self, ron::ser::PrettyConfig::default())
+            .map_err(|e| e.to_string())
+            .and_then(|text| fs::write(PROFILE_PATH, text).map_err(|e| e.to_string()));
+        if let Err(e) = result {
+            warn!("Failed to save the profile: {}", e);
+        }
+    }
+}
+
+/// Read the profile, starting a fresh one if there isn't one yet
+fn load_profile() -> Profile {
+    let path = Path::new(PROFILE_PATH);
+    if cfg!(target_arch = "wasm32") || !path.is_file() {
+        return Profile::default();
+    }
+    let parsed = fs::read_to_string(path)
+        .map_err(|e| e.to_string())
+        .and_then(|text| ron::from_str(&text).map_err(|e| e.to_string()));
+    match parsed {
+        Ok(profile) => profile,
+        Err(e) => {
+            warn!(
+                "Starting a fresh profile, {} couldn't be read: {}",
+                PROFILE_PATH, e
+            );
+            Profile::default()
+        }
+    }
+}
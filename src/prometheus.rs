@@ -0,0 +1,321 @@
+//! Prometheus metrics for monitoring long runs.
+//!
+//! `--prometheus <port>` (in builds with the `prometheus` feature) serves the
+//! simulation's numbers at `http://<host>:<port>/metrics` in Prometheus' text
+//! format, so a headless soak can be watched from standard dashboards and
+//! alerted on: how long ticks take and how many run each second, the
+//! population by caste, the stores, deaths by cause, pheromone totals, entity
+//! counts by kind, and resident memory. The numbers are refreshed once a
+//! second, whatever the scrape interval.
+
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::Instant;
+
+use bevy::prelude::*;
+use tiny_http::{Header, Response, Server};
+
+use crate::ants::{Ant, Caste, DeathCause};
+use crate::brood::Brood;
+use crate::cli::CliArgs;
+use crate::history::{CASTES, HistorySample};
+use crate::performance::TickRateMonitor;
+use crate::pheromones::{PheromoneGrids, PheromoneType};
+use crate::soak::{self, CreatureQuery, ItemQuery};
+use crate::summary::RunStats;
+use crate::time_controls::{SimulationSet, SimulationTick};
+use crate::world::{FoodStores, FungusGarden, Plant};
+
+pub struct PrometheusPlugin;
+
+impl Plugin for PrometheusPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_exporter)
+            .add_systems(
+                FixedFirst,
+                start_tick_timer
+                    .before(SimulationSet)
+                    .run_if(resource_exists::<PrometheusExport>),
+            )
+            .add_systems(
+                FixedLast,
+                stop_tick_timer
+                    .after(SimulationSet)
+                    .run_if(resource_exists::<PrometheusExport>),
+            )
+            .add_systems(
+                Update,
+                refresh_metrics.run_if(resource_exists::<PrometheusExport>),
+            );
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// The running exporter and the tick timings gathered for it
+#[derive(Resource)]
+struct PrometheusExport {
+    /// The latest metrics text, as served to scrapers
+    page: Arc<Mutex<String>>,
+    refresh: Timer,
+    /// When the current fixed-timestep pass started, and at which tick
+    tick_started: Option<(Instant, u64)>,
+    /// Seconds spent on ticks, and how many, over the whole run
+    tick_seconds: f64,
+    ticks_timed: u64,
+}
+
+// ============================================================================
+// Server
+// ============================================================================
+
+/// Serve the latest metrics to every scrape
+fn serve_scrapes(server: Server, page: Arc<Mutex<String>>) {
+    for request in server.incoming_requests() {
+        let response = if request.url() == "/metrics" {
+            let body = page.lock().map(|page| page.clone()).unwrap_or_default();
+            let mut response = Response::from_string(body);
+            if let Ok(header) = "Content-Type: text/plain; version=0.0.4".parse::<Header>() {
+                response = response.with_header(header);
+            }
+            response
+        } else {
+            Response::from_string("Metrics are at /metrics").with_status_code(404)
+        };
+        if let Err(e) = request.respond(response) {
+            warn!("Failed to answer a metrics scrape: {}", e);
+        }
+    }
+}
+
+/// Add a metric's help and type lines, then a line for each sample, each
+/// with whatever follows the name: labels, or a suffix like `_sum`
+fn metric(page: &mut String, name: &str, kind: &str, help: &str, samples: &[(String, f64)]) {
+    page.push_str(&format!("# HELP {} {}\n", name, help));
+    page.push_str(&format!("# TYPE {} {}\n", name, kind));
+    for (labels, value) in samples {
+        page.push_str(&format!("{}{} {}\n", name, labels, value));
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start serving metrics when `--prometheus` was passed
+fn start_exporter(mut commands: Commands, cli: Option<Res<CliArgs>>) {
+    let Some(port) = cli.and_then(|cli| cli.prometheus) else {
+        return;
+    };
+
+    // There are no sockets to listen on in a web build
+    if cfg!(target_arch = "wasm32") {
+        warn!("Prometheus metrics can't be served from a web build");
+        return;
+    }
+
+    let server = match Server::http(("0.0.0.0", port)) {
+        Ok(server) => server,
+        Err(e) => {
+            error!(
+                "Failed to start the metrics endpoint on port {}: {}",
+                port, e
+            );
+            return;
+        }
+    };
+    let page = Arc::new(Mutex::new(String::new()));
+    let thread_page = page.clone();
+    thread::spawn(move || serve_scrapes(server, thread_page));
+
+    info!(
+        "Serving Prometheus metrics on http://0.0.0.0:{}/metrics",
+        port
+    );
+    commands.insert_resource(PrometheusExport {
+        page,
+        refresh: Timer::from_seconds(1.0, TimerMode::Repeating),
+        tick_started: None,
+        tick_seconds: 0.0,
+        ticks_timed: 0,
+    });
+}
+
+/// Note the time before each fixed-timestep pass
+fn start_tick_timer(tick: Res<SimulationTick>, mut export: ResMut<PrometheusExport>) {
+    export.tick_started = Some((Instant::now(), tick.0));
+}
+
+/// Count the pass's time if it ran a tick, rather than waiting on a pause
+fn stop_tick_timer(tick: Res<SimulationTick>, mut export: ResMut<PrometheusExport>) {
+    let Some((started, start_tick)) = export.tick_started.take() else {
+        return;
+    };
+    if tick.0 > start_tick {
+        export.tick_seconds += started.elapsed().as_secs_f64();
+        export.ticks_timed += tick.0 - start_tick;
+    }
+}
+
+/// Rebuild the metrics page once a second
+#[allow(clippy::too_many_arguments)]
+fn refresh_metrics(
+    time: Res<Time<Real>>,
+    mut export: ResMut<PrometheusExport>,
+    tick: Res<SimulationTick>,
+    tick_rate: Option<Res<TickRateMonitor>>,
+    food_stores: Res<FoodStores>,
+    fungus_garden: Res<FungusGarden>,
+    stats: Res<RunStats>,
+    pheromones: Res<PheromoneGrids>,
+    entity_query: Query<Entity>,
+    ant_query: Query<&Caste, With<Ant>>,
+    brood_query: Query<(), With<Brood>>,
+    plant_query: Query<(), With<Plant>>,
+    item_query: ItemQuery,
+    creature_query: CreatureQuery,
+) {
+    if !export.refresh.tick(time.delta()).just_finished() {
+        return;
+    }
+
+    let sample = HistorySample::take(
+        tick.0,
+        ant_query.iter(),
+        &food_stores,
+        &fungus_garden,
+        &stats,
+        &pheromones,
+    );
+    let plain = |value: f64| vec![(String::new(), value)];
+    let labeled = |label: &str, values: Vec<(&str, f64)>| -> Vec<(String, f64)> {
+        values
+            .into_iter()
+            .map(|(name, value)| (format!("{{{}=\"{}\"}}", label, name.to_lowercase()), value))
+            .collect()
+    };
+
+    let mut page = String::new();
+    metric(
+        &mut page,
+        "acre_ticks_total",
+        "counter",
+        "Simulation ticks run",
+        &plain(tick.0 as f64),
+    );
+    metric(
+        &mut page,
+        "acre_tick_duration_seconds",
+        "summary",
+        "Time spent running simulation ticks",
+        &[
+            (String::from("_sum"), export.tick_seconds),
+            (String::from("_count"), export.ticks_timed as f64),
+        ],
+    );
+    if let Some(tick_rate) = tick_rate {
+        metric(
+            &mut page,
+            "acre_ticks_per_second",
+            "gauge",
+            "Ticks achieved over the last second",
+            &plain(tick_rate.achieved_tps as f64),
+        );
+    }
+    metric(
+        &mut page,
+        "acre_population",
+        "gauge",
+        "Adult ants by caste",
+        &labeled(
+            "caste",
+            CASTES
+                .iter()
+                .zip(sample.castes)
+                .map(|(caste, count)| (caste.name(), count as f64))
+                .collect(),
+        ),
+    );
+    metric(
+        &mut page,
+        "acre_stores",
+        "gauge",
+        "Food and materials held by the colony",
+        &labeled(
+            "resource",
+            vec![
+                ("food", sample.food as f64),
+                ("honeydew", sample.honeydew as f64),
+                ("seeds", sample.seeds as f64),
+                ("leaves", sample.leaves as f64),
+                ("mulch", sample.mulch as f64),
+            ],
+        ),
+    );
+    metric(
+        &mut page,
+        "acre_deaths_total",
+        "counter",
+        "Adult ant deaths by cause",
+        &labeled(
+            "cause",
+            DeathCause::ALL
+                .iter()
+                .zip(sample.deaths)
+                .map(|(cause, count)| (cause.name(), count as f64))
+                .collect(),
+        ),
+    );
+    metric(
+        &mut page,
+        "acre_pheromone",
+        "gauge",
+        "Summed intensity of each pheromone field",
+        &labeled(
+            "pheromone",
+            PheromoneType::ALL
+                .iter()
+                .zip(sample.pheromones)
+                .map(|(ptype, total)| (ptype.name(), total as f64))
+                .collect(),
+        ),
+    );
+    metric(
+        &mut page,
+        "acre_entities",
+        "gauge",
+        "Entities in the world",
+        &plain(entity_query.iter().count() as f64),
+    );
+    metric(
+        &mut page,
+        "acre_entities_by_kind",
+        "gauge",
+        "Entities in the world of the kinds that come and go",
+        &labeled(
+            "kind",
+            vec![
+                ("ants", ant_query.iter().count() as f64),
+                ("brood", brood_query.iter().count() as f64),
+                ("plants", plant_query.iter().count() as f64),
+                ("items", item_query.iter().count() as f64),
+                ("creatures", creature_query.iter().count() as f64),
+            ],
+        ),
+    );
+    if let Some(kb) = soak::resident_memory_kb() {
+        metric(
+            &mut page,
+            "acre_resident_memory_bytes",
+            "gauge",
+            "Resident memory of the process",
+            &plain(kb as f64 * 1024.0),
+        );
+    }
+
+    if let Ok(mut served) = export.page.lock() {
+        *served = page;
+    }
+}
@@ -0,0 +1,349 @@
+//! Input recording and replay for reproducing bug reports.
+//!
+//! Press F9 to start recording and F9 again to write the recording to
+//! `recordings/`, headed by the seed, biome, difficulty and world options the
+//! run started with and the tick recording started on. A replay has to play
+//! the run from the start to reach the same world, so every `PlayerAction`
+//! is captured with the simulation tick it happened on from the moment the
+//! colony is founded, and the file holds all of them, not only those since
+//! F9 was pressed. Launch with `--replay <file>` to start that same world and
+//! feed the actions back in; the simulation holds each tick until the actions
+//! due before it are in.
+//!
+//! A replay only matches if `acre.ron` is tuned the same way, and can't
+//! bring back a scenario's starting stores or drought.
+
+use std::collections::VecDeque;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::biome::Biome;
+use crate::cli::CliArgs;
+use crate::difficulty::Difficulty;
+use crate::rng::SimRng;
+use crate::scenarios::ActiveScenario;
+use crate::time_controls::{SimulationSet, SimulationTick};
+use crate::world::WorldOptions;
+use crate::{ResetRun, reset_resource};
+
+pub struct RecordingPlugin;
+
+impl Plugin for RecordingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<InputRecorder>()
+            .init_resource::<InputReplay>()
+            .configure_sets(FixedFirst, SimulationSet.run_if(replay_caught_up))
+            .configure_sets(FixedUpdate, SimulationSet.run_if(replay_caught_up))
+            .configure_sets(FixedLast, SimulationSet.run_if(replay_caught_up))
+            .add_systems(Startup, load_replay)
+            .add_systems(
+                Update,
+                (
+                    toggle_recording,
+                    replay_actions.in_set(ActionSystems::Input),
+                    record_actions.in_set(ActionSystems::Apply),
+                )
+                    .chain(),
+            )
+            .add_systems(
+                ResetRun,
+                (
+                    reset_resource::<InputRecorder>,
+                    reset_resource::<InputReplay>,
+                ),
+            );
+    }
+}
+
+/// Directory recordings are written to
+const RECORDINGS_DIR: &str = "recordings";
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Captures every player action since the colony was founded.
+///
+/// The list is kept whole for the run rather than streamed to disk, since a
+/// rewind can drop its tail and F9 writes it out in one go. It only grows
+/// with what the player does, a handful of small entries a second at the
+/// very most, so even a long session stays within a few megabytes
+#[derive(Resource, Default)]
+pub struct InputRecorder {
+    /// Recorded actions with the tick they happened on
+    pub actions: Vec<(u64, PlayerAction)>,
+    /// Difficulty the colony was founded on
+    pub difficulty: Difficulty,
+    /// Tick the player started recording on, while recording
+    pub started: Option<u64>,
+}
+
+/// Actions waiting to be replayed, in tick order
+#[derive(Resource, Default)]
+pub struct InputReplay {
+    pub pending: VecDeque<(u64, PlayerAction)>,
+}
+
+/// The world a recording was made in, read from its header
+pub struct RecordingStart {
+    pub seed: u64,
+    pub biome: Biome,
+    pub difficulty: Difficulty,
+    pub options: WorldOptions,
+}
+
+// ============================================================================
+// File Format
+// ============================================================================
+
+/// Write a recording as a `# <key> <value>` header describing the world and
+/// when recording started, then one `<tick> <action>` line per action
+fn write_recording(
+    path: &Path,
+    start: &RecordingStart,
+    started: u64,
+    actions: &[(u64, PlayerAction)],
+) -> std::io::Result<()> {
+    if let Some(dir) = path.parent() {
+        fs::create_dir_all(dir)?;
+    }
+
+    let options = &start.options;
+    let mut contents = String::from("# acre input recording\n");
+    contents.push_str(&format!("# seed {}\n", start.seed));
+    contents.push_str(&format!("# biome {}\n", start.biome.name()));
+    contents.push_str(&format!("# difficulty {}\n", start.difficulty.name()));
+    if let Some(trees) = options.trees {
+        contents.push_str(&format!("# trees {}\n", trees));
+    }
+    contents.push_str(&format!("# workers {}\n", options.starting_workers));
+    contents.push_str(&format!("# water_table {}\n", options.water_table_depth));
+    contents.push_str(&format!("# founding {}\n", options.founding));
+    if let Some(map) = &options.map {
+        contents.push_str(&format!("# map {}\n", map.display()));
+    }
+    contents.push_str(&format!("# started {}\n", started));
+    for (tick, action) in actions {
+        contents.push_str(&format!("{} {}\n", tick, action.to_line()));
+    }
+    fs::write(path, contents)
+}
+
+/// Read the world a recording was made in from its header
+pub fn read_start(path: &Path) -> Result<RecordingStart, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut seed: Option<u64> = None;
+    let mut start = RecordingStart {
+        seed: 0,
+        biome: Biome::default(),
+        difficulty: Difficulty::default(),
+        options: WorldOptions::default(),
+    };
+
+    for line in contents.lines() {
+        let Some((key, value)) = line
+            .trim()
+            .strip_prefix('#')
+            .and_then(|header| header.trim().split_once(' '))
+        else {
+            continue;
+        };
+        let value = value.trim();
+        let bad = |e: String| format!("bad {} '{}': {}", key, value, e);
+        match key {
+            "seed" => seed = Some(value.parse().map_err(|e| bad(format!("{}", e)))?),
+            "biome" => {
+                start.biome =
+                    Biome::from_name(value).ok_or_else(|| bad(String::from("unknown biome")))?
+            }
+            "difficulty" => {
+                start.difficulty = Difficulty::from_name(value)
+                    .ok_or_else(|| bad(String::from("unknown difficulty")))?
+            }
+            "trees" => {
+                start.options.trees = Some(value.parse().map_err(|e| bad(format!("{}", e)))?)
+            }
+            "workers" => {
+                start.options.starting_workers = value.parse().map_err(|e| bad(format!("{}", e)))?
+            }
+            "water_table" => {
+                start.options.water_table_depth =
+                    value.parse().map_err(|e| bad(format!("{}", e)))?
+            }
+            "founding" => {
+                start.options.founding = value.parse().map_err(|e| bad(format!("{}", e)))?
+            }
+            "map" => start.options.map = Some(PathBuf::from(value)),
+            _ => {}
+        }
+    }
+
+    start.seed = seed.ok_or("no seed in the header; it was recorded before seeds were saved")?;
+    Ok(start)
+}
+
+/// Read the actions in a recording written by `write_recording`
+fn read_recording(path: &Path) -> Result<Vec<(u64, PlayerAction)>, String> {
+    let contents = fs::read_to_string(path).map_err(|e| e.to_string())?;
+    let mut actions = Vec::new();
+
+    for line in contents.lines() {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        let (tick, action) = line
+            .split_once(' ')
+            .ok_or_else(|| format!("malformed line '{}'", line))?;
+        let tick = tick
+            .parse()
+            .map_err(|e| format!("bad tick in '{}': {}", line, e))?;
+        actions.push((tick, PlayerAction::from_line(action)?));
+    }
+
+    actions.sort_by_key(|(tick, _)| *tick);
+    Ok(actions)
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Run condition for `SimulationSet`: hold the coming tick until every
+/// replayed action due before it has been applied, however many ticks a
+/// frame runs
+fn replay_caught_up(replay: Res<InputReplay>, tick: Res<SimulationTick>) -> bool {
+    replay
+        .pending
+        .front()
+        .is_none_or(|(action_tick, _)| *action_tick > tick.0)
+}
+
+/// Load the actions of the recording passed with `--replay`, if any; its
+/// world was set up from the header before the app was built
+fn load_replay(cli: Option<Res<CliArgs>>, mut replay: ResMut<InputReplay>) {
+    let Some(path) = cli.and_then(|cli| cli.replay.clone()) else {
+        return;
+    };
+
+    match read_recording(&path) {
+        Ok(actions) => {
+            info!(
+                "Replaying {} recorded actions from {}",
+                actions.len(),
+                path.display()
+            );
+            replay.pending = actions.into();
+        }
+        Err(e) => error!("Failed to load recording {}: {}", path.display(), e),
+    }
+}
+
+/// Re-issue recorded actions once the simulation reaches their tick. The
+/// simulation holds at that tick (see `replay_caught_up`) until they've been
+/// written here and applied, so they land before the next tick runs just as
+/// they did when recorded
+fn replay_actions(
+    tick: Res<SimulationTick>,
+    mut replay: ResMut<InputReplay>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    while replay
+        .pending
+        .front()
+        .is_some_and(|(action_tick, _)| *action_tick <= tick.0)
+    {
+        if let Some((_, action)) = replay.pending.pop_front() {
+            actions.write(action);
+        }
+    }
+}
+
+/// Start recording with F9, and save the recording with F9 again
+#[allow(clippy::too_many_arguments)]
+fn toggle_recording(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    tick: Res<SimulationTick>,
+    mut recorder: ResMut<InputRecorder>,
+    rng: Res<SimRng>,
+    biome: Res<Biome>,
+    difficulty: Res<Difficulty>,
+    options: Res<WorldOptions>,
+    scenario: Res<ActiveScenario>,
+) {
+    if !keyboard.just_pressed(KeyCode::F9) {
+        return;
+    }
+    if cfg!(target_arch = "wasm32") {
+        warn!("Recordings can't be saved in a web build");
+        return;
+    }
+
+    let Some(started) = recorder.started.take() else {
+        recorder.started = Some(tick.0);
+        info!("Recording inputs (F9 to stop)");
+        return;
+    };
+
+    if *difficulty != recorder.difficulty {
+        warn!(
+            "Replays start on {} and don't change the difficulty mid-run",
+            recorder.difficulty.name()
+        );
+    }
+    if let Some(scenario) = &scenario.0 {
+        warn!(
+            "Replays start without the '{}' scenario's stores and weather",
+            scenario.name
+        );
+    }
+    let start = RecordingStart {
+        seed: rng.seed,
+        biome: *biome,
+        difficulty: recorder.difficulty,
+        options: options.clone(),
+    };
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = PathBuf::from(RECORDINGS_DIR).join(format!("input-{}.txt", timestamp));
+
+    match write_recording(&path, &start, started, &recorder.actions) {
+        Ok(()) => info!(
+            "Saved {} recorded actions to {}",
+            recorder.actions.len(),
+            path.display()
+        ),
+        Err(e) => error!("Failed to save recording {}: {}", path.display(), e),
+    }
+}
+
+/// Capture every action, forgetting those undone by rewinding, and the
+/// difficulty the colony starts on
+fn record_actions(
+    tick: Res<SimulationTick>,
+    difficulty: Res<Difficulty>,
+    mut recorder: ResMut<InputRecorder>,
+    mut actions: MessageReader<PlayerAction>,
+) {
+    if tick.0 == 0 {
+        recorder.difficulty = *difficulty;
+    }
+    if recorder
+        .actions
+        .last()
+        .is_some_and(|(action_tick, _)| *action_tick > tick.0)
+    {
+        recorder
+            .actions
+            .retain(|(action_tick, _)| *action_tick <= tick.0);
+    }
+    for action in actions.read() {
+        recorder.actions.push((tick.0, action.clone()));
+    }
+}
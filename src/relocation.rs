@@ -0,0 +1,316 @@
+//! Moving the nest.
+//!
+//! When a flood or a cave-in ruins the founding site, the player can pick
+//! another entrance as the new nest (L over it). Workers escort the queen
+//! across, nurses carry the brood to the nursery chamber nearest the new
+//! site, and haulers carry the fungus stores over a load at a time. Once
+//! everything has arrived, the new site becomes the nest. Picking the old nest
+//! again while a move is under way calls it off and sends everyone back.
+
+use bevy::prelude::*;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::ants::{Ant, Carrying, Caste, GridPosition, Inventory, NestLocation, Task, step_toward};
+use crate::brood::{Brood, Nursery};
+use crate::content::Content;
+use crate::notifications::Notification;
+use crate::pathfinding::{Coord, Route};
+use crate::time_controls::{SimulationSet, SimulationTick};
+use crate::world::{FoodStores, TileKind, WorldGrid};
+use crate::{ResetRun, reset_resource};
+
+pub struct RelocationPlugin;
+
+impl Plugin for RelocationPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<NestMove>()
+            .add_systems(Update, apply_move_actions.in_set(ActionSystems::Apply))
+            .add_systems(
+                FixedUpdate,
+                (assign_movers, escort_queen, haul_stores, finish_move)
+                    .chain()
+                    .run_if(nest_moving)
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, reset_resource::<NestMove>);
+    }
+}
+
+/// Workers walking with the queen
+const ESCORTS: usize = 2;
+/// Workers carrying the stores over at once
+const HAULERS: usize = 3;
+/// The queen takes a step only once every this many ticks
+const QUEEN_STEP_TICKS: u64 = 3;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// A move to a new nest site under way
+#[derive(Resource, Default)]
+pub struct NestMove {
+    /// Surface tile of the new nest, while the colony is moving
+    pub target: Option<Coord>,
+    /// Food still waiting at the old nest to be carried over
+    pub stores_left: u32,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+fn nest_moving(nest_move: Res<NestMove>) -> bool {
+    nest_move.target.is_some()
+}
+
+/// Start a move from live or replayed input. Only entrances can become the
+/// nest, and picking the current nest calls off a move under way.
+fn apply_move_actions(
+    mut actions: MessageReader<PlayerAction>,
+    world_grid: Res<WorldGrid>,
+    nest_location: Res<NestLocation>,
+    food_stores: Res<FoodStores>,
+    mut nest_move: ResMut<NestMove>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    for action in actions.read() {
+        let PlayerAction::MoveNest { x, y, z } = *action else {
+            continue;
+        };
+
+        if (x, y, z) == (nest_location.x, nest_location.y, nest_location.z) {
+            if nest_move.target.is_some_and(|target| target != (x, y, z)) {
+                nest_move.target = Some((x, y, z));
+                nest_move.stores_left = 0;
+                notifications.write(Notification::info(
+                    "The move is off; the colony is heading back to its nest",
+                ));
+            }
+            continue;
+        }
+
+        if world_grid.tiles[z][y][x] != TileKind::Entrance {
+            notifications.write(Notification::warning(
+                "The nest can only be moved to an entrance",
+            ));
+            continue;
+        }
+
+        nest_move.target = Some((x, y, z));
+        nest_move.stores_left = food_stores.food;
+        info!(
+            "Moving the nest to ({}, {}, {}) with {} food to carry",
+            x, y, z, food_stores.food
+        );
+        notifications.write(Notification::info(format!(
+            "The colony is moving to the entrance at ({}, {})",
+            x, y
+        )));
+    }
+}
+
+/// Put idle workers to escorting the queen and hauling the stores, asking
+/// soldiers first
+fn assign_movers(
+    mut ant_query: Query<(&Caste, &mut Task, &Inventory), With<Ant>>,
+    nest_move: Res<NestMove>,
+) {
+    let mut escorts = 0;
+    let mut haulers = 0;
+    for (_, task, inventory) in &ant_query {
+        match *task {
+            Task::Escorting => escorts += 1,
+            Task::HaulingStores => haulers += 1,
+            _ if matches!(inventory.load, Carrying::FungusFood) => haulers += 1,
+            _ => {}
+        }
+    }
+
+    let mut free: Vec<_> = ant_query
+        .iter_mut()
+        .filter(|(caste, task, inventory)| {
+            !matches!(caste, Caste::Queen | Caste::Nurse | Caste::Alate)
+                && matches!(**task, Task::Idle | Task::Wandering)
+                && inventory.is_empty()
+        })
+        .collect();
+    free.sort_by_key(|(caste, ..)| **caste != Caste::Soldier);
+
+    for (_, mut task, _) in free {
+        if escorts < ESCORTS {
+            *task = Task::Escorting;
+            escorts += 1;
+        } else if haulers < HAULERS && nest_move.stores_left > 0 {
+            *task = Task::HaulingStores;
+            haulers += 1;
+        } else {
+            break;
+        }
+    }
+}
+
+/// Escorts keep beside the queen, and she walks on toward the new nest
+/// whenever one of them is with her. A hungry queen goes back to eat first.
+fn escort_queen(
+    mut ant_query: Query<(&mut GridPosition, &mut Route, &Caste, &Task), With<Ant>>,
+    world_grid: Res<WorldGrid>,
+    tick: Res<SimulationTick>,
+    nest_move: Res<NestMove>,
+) {
+    let Some(target) = nest_move.target else {
+        return;
+    };
+    let Some(queen) = ant_query
+        .iter()
+        .find(|(_, _, caste, _)| **caste == Caste::Queen)
+        .map(|(pos, ..)| *pos)
+    else {
+        return;
+    };
+
+    let mut escorted = false;
+    for (mut pos, mut route, _, task) in &mut ant_query {
+        if !matches!(task, Task::Escorting) {
+            continue;
+        }
+        if pos.z == queen.z && pos.x.abs_diff(queen.x) <= 1 && pos.y.abs_diff(queen.y) <= 1 {
+            escorted = true;
+        } else {
+            step_toward(
+                &mut pos,
+                &mut route,
+                (queen.x, queen.y, queen.z),
+                &world_grid,
+            );
+        }
+    }
+
+    if !escorted || !tick.0.is_multiple_of(QUEEN_STEP_TICKS) {
+        return;
+    }
+    for (mut pos, mut route, caste, task) in &mut ant_query {
+        if *caste == Caste::Queen
+            && !matches!(task, Task::SeekingFood)
+            && (pos.x, pos.y, pos.z) != target
+        {
+            step_toward(&mut pos, &mut route, target, &world_grid);
+        }
+    }
+}
+
+/// Haulers pick up a load of food at the old nest and carry it to the new one
+fn haul_stores(
+    mut ant_query: Query<
+        (
+            &mut GridPosition,
+            &mut Route,
+            &Caste,
+            &mut Task,
+            &mut Inventory,
+        ),
+        With<Ant>,
+    >,
+    world_grid: Res<WorldGrid>,
+    nest_location: Res<NestLocation>,
+    mut nest_move: ResMut<NestMove>,
+    mut food_stores: ResMut<FoodStores>,
+    content: Res<Content>,
+) {
+    let Some((home_x, home_y, home_z)) = nest_move.target else {
+        return;
+    };
+    let old_nest = (nest_location.x, nest_location.y, nest_location.z);
+
+    for (mut pos, mut route, caste, mut task, mut inventory) in &mut ant_query {
+        if !matches!(*task, Task::HaulingStores) {
+            continue;
+        }
+        if (pos.x, pos.y, pos.z) != old_nest {
+            step_toward(&mut pos, &mut route, old_nest, &world_grid);
+            continue;
+        }
+
+        // The colony may have eaten some of it in the meantime
+        let load = content
+            .caste(*caste)
+            .load_capacity
+            .min(nest_move.stores_left)
+            .min(food_stores.food);
+        if load == 0 {
+            nest_move.stores_left = 0;
+            *task = Task::Idle;
+            continue;
+        }
+
+        food_stores.food -= load;
+        nest_move.stores_left -= load;
+        inventory.hold(Carrying::FungusFood, load);
+        *task = Task::CarryingHome {
+            home_x,
+            home_y,
+            home_z,
+        };
+    }
+}
+
+/// Settle into the new nest once the queen, the brood and the stores have
+/// all arrived, or head back if the new site's entrance is filled in
+fn finish_move(
+    mut ant_query: Query<(&GridPosition, &Caste, &mut Task, &Inventory), With<Ant>>,
+    brood_query: Query<&GridPosition, (With<Brood>, Without<Ant>)>,
+    world_grid: Res<WorldGrid>,
+    nursery: Res<Nursery>,
+    mut nest_location: ResMut<NestLocation>,
+    mut nest_move: ResMut<NestMove>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let Some(target) = nest_move.target else {
+        return;
+    };
+    let old_nest = (nest_location.x, nest_location.y, nest_location.z);
+    let (x, y, z) = target;
+
+    if target != old_nest && world_grid.tiles[z][y][x] != TileKind::Entrance {
+        nest_move.target = Some(old_nest);
+        nest_move.stores_left = 0;
+        notifications.write(Notification::warning(
+            "The new nest site was filled in; the colony is heading back",
+        ));
+        return;
+    }
+
+    let at = |pos: &GridPosition, tile: Coord| (pos.x, pos.y, pos.z) == tile;
+    let queen_arrived = ant_query
+        .iter()
+        .filter(|(_, caste, ..)| **caste == Caste::Queen)
+        .all(|(pos, ..)| at(pos, target));
+    let stores_moved = nest_move.stores_left == 0
+        && !ant_query.iter().any(|(_, _, task, inventory)| {
+            matches!(task, Task::HaulingStores) || matches!(inventory.load, Carrying::FungusFood)
+        });
+    let brood_moved = nursery
+        .tile
+        .is_none_or(|tile| brood_query.iter().all(|pos| at(pos, tile)));
+    if !(queen_arrived && stores_moved && brood_moved) {
+        return;
+    }
+
+    nest_location.x = x;
+    nest_location.y = y;
+    nest_location.z = z;
+    nest_move.target = None;
+    for (_, _, mut task, _) in &mut ant_query {
+        if matches!(*task, Task::Escorting) {
+            *task = Task::Idle;
+        }
+    }
+
+    if target != old_nest {
+        info!("The nest moved to ({}, {}, {})", x, y, z);
+        notifications.write(Notification::info(format!(
+            "The colony has settled into its new nest at ({}, {})",
+            x, y
+        )));
+    }
+}
@@ -0,0 +1,268 @@
+//! Remote control over HTTP, for driving the simulation from scripts.
+//!
+//! `--remote <port>` (in builds with the `remote` feature) serves a small JSON
+//! API on localhost, so experiments can be run from Python or anything else
+//! that speaks HTTP. With `--headless` the game starts paused, and only moves
+//! when told to:
+//!
+//! - `GET /get_stats`: the colony's numbers, as `--export-metrics json` writes
+//!   them
+//! - `POST /step_n_ticks` `{"ticks": 100}`: run the simulation forward, then
+//!   return the stats
+//! - `POST /place_pheromone` `{"pheromone": "forage", "x": 10, "y": 12, "z":
+//!   40, "amount": 0.1}`: lay pheromone on a tile (`amount` is optional)
+//! - `POST /action` `{"action": "designate Chamber 10 12 38"}`: any player
+//!   action, written as in input recordings
+//!
+//! Errors come back as `{"error": "..."}` with a 400 status. Actions go the
+//! same way as the player's own, so they're recorded like any other, and take
+//! effect before the next tick.
+
+use std::collections::VecDeque;
+use std::sync::mpsc::{self, Sender};
+use std::sync::{Arc, Mutex};
+use std::thread;
+
+use bevy::prelude::*;
+use serde::Deserialize;
+use serde::de::DeserializeOwned;
+use tiny_http::{Header, Method, Request, Response, Server};
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::ants::{Ant, Caste};
+use crate::cli::CliArgs;
+use crate::history::HistorySample;
+use crate::metrics;
+use crate::pheromones::{PLACE_AMOUNT, PheromoneGrids, PheromoneType};
+use crate::step;
+use crate::summary::RunStats;
+use crate::time_controls::SimulationTick;
+use crate::world::{FoodStores, FungusGarden, WORLD_SIZE};
+
+pub struct RemotePlugin;
+
+impl Plugin for RemotePlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_server).add_systems(
+            Update,
+            serve_commands
+                .in_set(ActionSystems::Input)
+                .run_if(resource_exists::<RemoteQueue>),
+        );
+    }
+}
+
+/// Most ticks a single request can run
+const MAX_TICKS: u64 = 100_000;
+
+// ============================================================================
+// Requests
+// ============================================================================
+
+/// Something a script asked the game to do
+enum Command {
+    GetStats,
+    Step(u64),
+    Act(PlayerAction),
+}
+
+/// A command waiting for the game, and where to send its JSON reply
+struct RemoteRequest {
+    command: Command,
+    reply: Sender<Result<String, String>>,
+}
+
+/// Commands passed from the server thread to the game
+#[derive(Resource, Clone, Default)]
+struct RemoteQueue(Arc<Mutex<VecDeque<RemoteRequest>>>);
+
+#[derive(Deserialize)]
+struct StepBody {
+    ticks: u64,
+}
+
+#[derive(Deserialize)]
+struct PheromoneBody {
+    pheromone: String,
+    x: usize,
+    y: usize,
+    z: usize,
+    amount: Option<f32>,
+}
+
+#[derive(Deserialize)]
+struct ActionBody {
+    action: String,
+}
+
+/// Answer requests one at a time, each once the game has carried it out
+fn serve_requests(server: Server, queue: RemoteQueue) {
+    for mut request in server.incoming_requests() {
+        let reply = parse_command(&mut request).and_then(|command| run_in_game(&queue, command));
+        let (status, body) = match reply {
+            Ok(body) => (200, body),
+            Err(e) => (400, serde_json::json!({ "error": e }).to_string()),
+        };
+
+        let mut response = Response::from_string(body).with_status_code(status);
+        if let Ok(header) = "Content-Type: application/json".parse::<Header>() {
+            response = response.with_header(header);
+        }
+        if let Err(e) = request.respond(response) {
+            warn!("Failed to answer a remote request: {}", e);
+        }
+    }
+}
+
+/// The command a request asks for
+fn parse_command(request: &mut Request) -> Result<Command, String> {
+    let mut body = String::new();
+    request
+        .as_reader()
+        .read_to_string(&mut body)
+        .map_err(|e| format!("couldn't read the request: {}", e))?;
+
+    let command = match (request.method(), request.url()) {
+        (&Method::Get, "/get_stats") => Command::GetStats,
+        (&Method::Post, "/step_n_ticks") => {
+            let step: StepBody = parse_body(&body, "{\"ticks\": n}")?;
+            if step.ticks > MAX_TICKS {
+                return Err(format!("at most {} ticks at a time", MAX_TICKS));
+            }
+            Command::Step(step.ticks)
+        }
+        (&Method::Post, "/place_pheromone") => {
+            let place: PheromoneBody = parse_body(&body, "a pheromone, x, y and z")?;
+            let ptype = PheromoneType::ALL
+                .into_iter()
+                .find(|ptype| ptype.name().eq_ignore_ascii_case(&place.pheromone))
+                .ok_or_else(|| {
+                    format!(
+                        "unknown pheromone '{}', expected dig, forage, home or avoid",
+                        place.pheromone
+                    )
+                })?;
+            Command::Act(PlayerAction::PlacePheromone {
+                ptype,
+                x: place.x,
+                y: place.y,
+                z: place.z,
+                amount: place.amount.unwrap_or(PLACE_AMOUNT),
+            })
+        }
+        (&Method::Post, "/action") => {
+            let action: ActionBody = parse_body(&body, "{\"action\": line}")?;
+            Command::Act(PlayerAction::from_line(&action.action)?)
+        }
+        (method, url) => return Err(format!("no endpoint {} {}", method, url)),
+    };
+
+    if let Command::Act(action) = &command {
+        let (x, y, z) = action.position();
+        if x >= WORLD_SIZE || y >= WORLD_SIZE || z >= WORLD_SIZE {
+            return Err(format!("({}, {}, {}) is outside the world", x, y, z));
+        }
+    }
+    Ok(command)
+}
+
+/// A request's JSON body, or what was expected instead
+fn parse_body<T: DeserializeOwned>(body: &str, expected: &str) -> Result<T, String> {
+    serde_json::from_str(body).map_err(|e| format!("expected {}: {}", expected, e))
+}
+
+/// Hand a command to the game and wait for it to be carried out
+fn run_in_game(queue: &RemoteQueue, command: Command) -> Result<String, String> {
+    let (reply, response) = mpsc::channel();
+    queue
+        .0
+        .lock()
+        .map_err(|_| String::from("the game has stopped"))?
+        .push_back(RemoteRequest { command, reply });
+    response
+        .recv()
+        .map_err(|_| String::from("the game has stopped"))?
+}
+
+/// The colony's numbers as JSON
+fn stats_json(world: &mut World) -> String {
+    let mut ant_query = world.query_filtered::<&Caste, With<Ant>>();
+    let world = &*world;
+    let sample = HistorySample::take(
+        world.resource::<SimulationTick>().0,
+        ant_query.iter(world),
+        world.resource::<FoodStores>(),
+        world.resource::<FungusGarden>(),
+        world.resource::<RunStats>(),
+        world.resource::<PheromoneGrids>(),
+    );
+    metrics::to_json(metrics::fields(&sample))
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start the API when `--remote` was passed
+fn start_server(mut commands: Commands, cli: Option<Res<CliArgs>>) {
+    let Some(port) = cli.and_then(|cli| cli.remote) else {
+        return;
+    };
+
+    // There are no sockets to listen on in a web build
+    if cfg!(target_arch = "wasm32") {
+        warn!("Remote control can't be served from a web build");
+        return;
+    }
+
+    // Only scripts on this machine can reach it
+    let server = match Server::http(("127.0.0.1", port)) {
+        Ok(server) => server,
+        Err(e) => {
+            error!("Failed to start the remote API on port {}: {}", port, e);
+            return;
+        }
+    };
+    let queue = RemoteQueue::default();
+    let thread_queue = queue.clone();
+    thread::spawn(move || serve_requests(server, thread_queue));
+
+    info!("Serving the remote API on http://127.0.0.1:{}", port);
+    commands.insert_resource(queue);
+}
+
+/// Carry out the requests that have come in, in order: actions join the
+/// player's own, and ticks run on the spot
+fn serve_commands(world: &mut World) {
+    let queue = world.resource::<RemoteQueue>().clone();
+    let Ok(mut pending) = queue.0.lock() else {
+        return;
+    };
+
+    let mut acted = false;
+    while let Some(request) = pending.pop_front() {
+        // Actions are applied later this frame, so ticks that should follow
+        // them wait for the next
+        if acted && matches!(request.command, Command::Step(_)) {
+            pending.push_front(request);
+            break;
+        }
+
+        let result = match request.command {
+            Command::GetStats => Ok(stats_json(world)),
+            Command::Step(ticks) => {
+                for _ in 0..ticks {
+                    step(world);
+                }
+                Ok(stats_json(world))
+            }
+            Command::Act(action) => {
+                world.write_message(action);
+                acted = true;
+                Ok(String::from("{}"))
+            }
+        };
+        // A script that gave up waiting has nothing to tell
+        let _ = request.reply.send(result);
+    }
+}
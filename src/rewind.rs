@@ -0,0 +1,521 @@
+//! Rewinding recent history.
+//!
+//! Every five seconds of play the colony's state is copied into a ring buffer
+//! holding the last two minutes. Backspace pauses the game and steps back to
+//! the previous copy, and again steps further back; Shift+Backspace steps
+//! forward again, up to the moment rewinding started. A banner shows how far
+//! back the world is. Resuming plays on from whatever point is showing, and
+//! the later history is dropped, so a flooded nursery or a careless dig can be
+//! undone.
+//!
+//! A copy holds the ground, water, stores, designations, the garden, the ants
+//! and brood, the pheromone trails, the run's statistics, the random number
+//! generator, and how many leaves each plant has. Plants that have died since
+//! stay dead; visitors like beetles, spiders and aphids, along with fallen
+//! seeds and wild mushrooms, come and go on their own; and air and moisture
+//! are left as they are and settle back within a few ticks. Because of those,
+//! a recording made across a rewind won't replay the same way.
+
+use std::collections::{HashMap, VecDeque};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::ants::{
+    Age, Ant, Caste, Crop, Falling, GridPosition, Hunger, Inventory, NestLocation,
+    PreviousPosition, Task, spawn_ant,
+};
+use crate::brood::{Brood, spawn_brood};
+use crate::designations::Designations;
+use crate::excavation::DigProgress;
+use crate::exploration::Explored;
+use crate::founding::FoundingQueen;
+use crate::notifications::Notification;
+use crate::pheromones::PheromoneGrids;
+use crate::rng::SimRng;
+use crate::summary::RunStats;
+use crate::time_controls::{SimulationTick, toggle_paused};
+use crate::water::Groundwater;
+use crate::world::{FoodStores, FungusGarden, LeafSource, WORLD_SIZE, WorldGrid};
+use crate::{GameState, ResetRun, in_game, reset_resource};
+
+pub struct RewindPlugin;
+
+impl Plugin for RewindPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RewindHistory>()
+            .add_systems(
+                Update,
+                (
+                    take_snapshot.run_if(in_state(GameState::Playing)),
+                    rewind_input.run_if(in_game),
+                    update_rewind_banner,
+                )
+                    .chain(),
+            )
+            .add_systems(OnExit(GameState::Paused), play_on_from_here)
+            .add_systems(ResetRun, reset_resource::<RewindHistory>);
+    }
+}
+
+/// Real seconds of play between snapshots
+const SNAPSHOT_SECS: f32 = 5.0;
+/// Snapshots kept: two minutes' worth
+const MAX_SNAPSHOTS: usize = 24;
+
+// ============================================================================
+// Snapshots
+// ============================================================================
+
+/// An ant as it was when a snapshot was taken
+#[derive(Clone)]
+struct AntSnapshot {
+    position: GridPosition,
+    caste: Caste,
+    hunger: Hunger,
+    crop: Crop,
+    age: u32,
+    inventory: Inventory,
+    task: Task,
+    falling: Option<Falling>,
+    founding: Option<FoundingQueen>,
+}
+
+/// The colony's state at one moment
+#[derive(Clone)]
+struct Snapshot {
+    /// Real seconds of play when it was taken
+    taken: f32,
+    tick: u64,
+    world_grid: WorldGrid,
+    explored: Explored,
+    water: Box<Grid>,
+    water_struck: bool,
+    fungus_garden: FungusGarden,
+    food_stores: FoodStores,
+    designations: Designations,
+    dig_progress: DigProgress,
+    nest: NestLocation,
+    /// The tiles with any of each pheromone: dig, forage, home, avoid
+    pheromones: [Vec<(usize, f32)>; 4],
+    rng: SimRng,
+    run_stats: RunStats,
+    ants: Vec<(Entity, AntSnapshot)>,
+    brood: Vec<(Entity, Brood, GridPosition)>,
+    leaves: Vec<(Entity, LeafSource)>,
+}
+
+/// The colony state a snapshot copies and a rewind restores
+#[derive(SystemParam)]
+struct ColonyState<'w> {
+    tick: ResMut<'w, SimulationTick>,
+    world_grid: ResMut<'w, WorldGrid>,
+    explored: ResMut<'w, Explored>,
+    groundwater: ResMut<'w, Groundwater>,
+    fungus_garden: ResMut<'w, FungusGarden>,
+    food_stores: ResMut<'w, FoodStores>,
+    designations: ResMut<'w, Designations>,
+    dig_progress: ResMut<'w, DigProgress>,
+    nest: ResMut<'w, NestLocation>,
+    pheromones: ResMut<'w, PheromoneGrids>,
+    rng: ResMut<'w, SimRng>,
+    run_stats: ResMut<'w, RunStats>,
+}
+
+type AntQuery<'w, 's> = Query<
+    'w,
+    's,
+    (
+        Entity,
+        &'static GridPosition,
+        &'static Caste,
+        &'static Hunger,
+        &'static Crop,
+        &'static Age,
+        &'static Inventory,
+        &'static Task,
+        Option<&'static Falling>,
+        Option<&'static FoundingQueen>,
+    ),
+    With<Ant>,
+>;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Recent snapshots, oldest first, and which one is showing while rewound
+#[derive(Resource)]
+pub struct RewindHistory {
+    snapshots: VecDeque<Snapshot>,
+    /// Index of the snapshot showing, while rewound
+    viewing: Option<usize>,
+    /// Real seconds played, to time snapshots
+    played: f32,
+    next_snapshot: f32,
+}
+
+impl Default for RewindHistory {
+    fn default() -> Self {
+        Self {
+            snapshots: VecDeque::with_capacity(MAX_SNAPSHOTS + 1),
+            viewing: None,
+            played: 0.0,
+            next_snapshot: SNAPSHOT_SECS,
+        }
+    }
+}
+
+impl RewindHistory {
+    /// Whether an earlier moment is showing
+    pub fn rewound(&self) -> bool {
+        self.viewing.is_some()
+    }
+
+    /// Seconds of play between the moment showing and the newest snapshot
+    fn seconds_back(&self) -> Option<f32> {
+        let index = self.viewing?;
+        let newest = self.snapshots.back()?;
+        Some(newest.taken - self.snapshots[index].taken)
+    }
+}
+
+/// Copy the colony's current state
+fn snapshot(
+    taken: f32,
+    colony: &ColonyState,
+    ant_query: &AntQuery,
+    brood_query: &Query<(Entity, &Brood, &GridPosition)>,
+    leaf_query: &Query<(Entity, &LeafSource)>,
+) -> Snapshot {
+    Snapshot {
+        taken,
+        tick: colony.tick.0,
+        world_grid: colony.world_grid.clone(),
+        explored: colony.explored.clone(),
+        water: colony.groundwater.level.clone(),
+        water_struck: colony.groundwater.struck,
+        fungus_garden: colony.fungus_garden.clone(),
+        food_stores: colony.food_stores.clone(),
+        designations: colony.designations.clone(),
+        dig_progress: colony.dig_progress.clone(),
+        nest: colony.nest.clone(),
+        pheromones: pheromone_grids(&colony.pheromones).map(|grid| {
+            grid.as_flattened()
+                .as_flattened()
+                .iter()
+                .copied()
+                .enumerate()
+                .filter(|(_, value)| *value > 0.0)
+                .collect()
+        }),
+        rng: colony.rng.clone(),
+        run_stats: colony.run_stats.clone(),
+        ants: ant_query
+            .iter()
+            .map(
+                |(entity, pos, caste, hunger, crop, age, inventory, task, falling, founding)| {
+                    let ant = AntSnapshot {
+                        position: *pos,
+                        caste: *caste,
+                        hunger: hunger.clone(),
+                        crop: crop.clone(),
+                        age: age.0,
+                        inventory: inventory.clone(),
+                        task: task.clone(),
+                        falling: falling.cloned(),
+                        founding: founding.cloned(),
+                    };
+                    (entity, ant)
+                },
+            )
+            .collect(),
+        brood: brood_query
+            .iter()
+            .map(|(entity, brood, pos)| (entity, brood.clone(), *pos))
+            .collect(),
+        leaves: leaf_query
+            .iter()
+            .map(|(entity, leaves)| (entity, leaves.clone()))
+            .collect(),
+    }
+}
+
+type Grid = [[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE];
+
+/// Each pheromone's field, in `PheromoneType::ALL` order
+fn pheromone_grids(pheromones: &PheromoneGrids) -> [&Grid; 4] {
+    [
+        &pheromones.dig,
+        &pheromones.forage,
+        &pheromones.home,
+        &pheromones.avoid,
+    ]
+}
+
+fn pheromone_grids_mut(pheromones: &mut PheromoneGrids) -> [&mut Grid; 4] {
+    [
+        &mut pheromones.dig,
+        &mut pheromones.forage,
+        &mut pheromones.home,
+        &mut pheromones.avoid,
+    ]
+}
+
+/// Put the colony back the way a snapshot found it. Ants and brood still
+/// alive are reset in place, those since lost come back, and those since
+/// born are undone. Brood that comes back is a new entity, so the tasks of
+/// nurses that were seeing to it are pointed at the new one.
+fn restore(
+    commands: &mut Commands,
+    snapshot: &Snapshot,
+    colony: &mut ColonyState,
+    ant_query: &AntQuery,
+    brood_query: &Query<(Entity, &Brood, &GridPosition)>,
+) {
+    colony.tick.0 = snapshot.tick;
+    *colony.world_grid = snapshot.world_grid.clone();
+    *colony.explored = snapshot.explored.clone();
+    colony.groundwater.level = snapshot.water.clone();
+    colony.groundwater.struck = snapshot.water_struck;
+    *colony.fungus_garden = snapshot.fungus_garden.clone();
+    *colony.food_stores = snapshot.food_stores.clone();
+    *colony.designations = snapshot.designations.clone();
+    *colony.dig_progress = snapshot.dig_progress.clone();
+    *colony.nest = snapshot.nest.clone();
+    *colony.rng = snapshot.rng.clone();
+    *colony.run_stats = snapshot.run_stats.clone();
+    for (grid, cells) in pheromone_grids_mut(&mut colony.pheromones)
+        .into_iter()
+        .zip(&snapshot.pheromones)
+    {
+        let grid = grid.as_flattened_mut().as_flattened_mut();
+        grid.fill(0.0);
+        for &(index, value) in cells {
+            grid[index] = value;
+        }
+    }
+
+    let mut respawned = HashMap::new();
+    for (entity, ..) in brood_query.iter() {
+        if !snapshot.brood.iter().any(|(kept, ..)| *kept == entity) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (entity, brood, pos) in &snapshot.brood {
+        let kept = if brood_query.contains(*entity) {
+            *entity
+        } else {
+            let new = spawn_brood(commands, pos, brood.caste);
+            respawned.insert(*entity, new);
+            new
+        };
+        commands.entity(kept).insert((brood.clone(), *pos));
+    }
+
+    for (entity, ..) in ant_query.iter() {
+        if !snapshot.ants.iter().any(|(kept, _)| *kept == entity) {
+            commands.entity(entity).despawn();
+        }
+    }
+    for (entity, ant) in &snapshot.ants {
+        let pos = ant.position;
+        let entity = if ant_query.contains(*entity) {
+            *entity
+        } else {
+            spawn_ant(commands, pos.x, pos.y, pos.z, ant.caste, ant.age)
+        };
+        let mut ant_commands = commands.entity(entity);
+        ant_commands.insert((
+            pos,
+            PreviousPosition(pos),
+            ant.hunger.clone(),
+            ant.crop.clone(),
+            Age(ant.age),
+            ant.inventory.clone(),
+            brood_task(&ant.task, &respawned),
+        ));
+        match &ant.falling {
+            Some(falling) => ant_commands.insert(falling.clone()),
+            None => ant_commands.remove::<Falling>(),
+        };
+        match &ant.founding {
+            Some(founding) => ant_commands.insert(founding.clone()),
+            None => ant_commands.remove::<FoundingQueen>(),
+        };
+    }
+
+    // Plants that have died since can't come back, so only live ones regrow
+    for (entity, leaves) in &snapshot.leaves {
+        if let Ok(mut plant) = commands.get_entity(*entity) {
+            plant.insert(leaves.clone());
+        }
+    }
+}
+
+/// A task as it was, but seeing to the new entity for any brood that has
+/// come back as one
+fn brood_task(task: &Task, respawned: &HashMap<Entity, Entity>) -> Task {
+    let new = |brood: &Entity| respawned.get(brood).copied().unwrap_or(*brood);
+    match task {
+        Task::FetchingBrood { brood } => Task::FetchingBrood { brood: new(brood) },
+        Task::CarryingBrood { brood } => Task::CarryingBrood { brood: new(brood) },
+        Task::Nursing { brood } => Task::Nursing { brood: new(brood) },
+        other => other.clone(),
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Copy the colony into the history every few seconds of play
+fn take_snapshot(
+    time: Res<Time<Real>>,
+    colony: ColonyState,
+    ant_query: AntQuery,
+    brood_query: Query<(Entity, &Brood, &GridPosition)>,
+    leaf_query: Query<(Entity, &LeafSource)>,
+    mut history: ResMut<RewindHistory>,
+) {
+    history.played += time.delta_secs();
+    if history.played < history.next_snapshot {
+        return;
+    }
+    history.next_snapshot = history.played + SNAPSHOT_SECS;
+
+    let snapshot = snapshot(
+        history.played,
+        &colony,
+        &ant_query,
+        &brood_query,
+        &leaf_query,
+    );
+    history.snapshots.push_back(snapshot);
+    if history.snapshots.len() > MAX_SNAPSHOTS {
+        history.snapshots.pop_front();
+    }
+}
+
+/// Step back through history with Backspace, forward with Shift+Backspace
+#[allow(clippy::too_many_arguments)]
+fn rewind_input(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut colony: ColonyState,
+    ant_query: AntQuery,
+    brood_query: Query<(Entity, &Brood, &GridPosition)>,
+    leaf_query: Query<(Entity, &LeafSource)>,
+    mut history: ResMut<RewindHistory>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::Backspace) {
+        return;
+    }
+    let forward = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    let index = match history.viewing {
+        Some(index) if forward => index + 1,
+        Some(index) => index.saturating_sub(1),
+        // Only going back starts a rewind
+        None if forward => return,
+        None => {
+            if history.snapshots.is_empty() {
+                notifications.write(Notification::info("Nothing to rewind to yet"));
+                return;
+            }
+            if *state.get() == GameState::Playing {
+                toggle_paused(state.get(), &mut next_state, &mut time);
+            }
+            // Keep the present as the newest snapshot to come back to
+            let present = snapshot(
+                history.played,
+                &colony,
+                &ant_query,
+                &brood_query,
+                &leaf_query,
+            );
+            history.snapshots.push_back(present);
+            history.snapshots.len() - 2
+        }
+    };
+    if index >= history.snapshots.len() || Some(index) == history.viewing {
+        return;
+    }
+
+    history.viewing = Some(index);
+    restore(
+        &mut commands,
+        &history.snapshots[index],
+        &mut colony,
+        &ant_query,
+        &brood_query,
+    );
+}
+
+/// Resuming from a rewound moment drops the history after it
+fn play_on_from_here(mut history: ResMut<RewindHistory>) {
+    let Some(seconds) = history.seconds_back() else {
+        return;
+    };
+    let Some(index) = history.viewing.take() else {
+        return;
+    };
+    history.snapshots.truncate(index + 1);
+    let taken = history.snapshots[index].taken;
+    history.played = taken;
+    history.next_snapshot = taken + SNAPSHOT_SECS;
+    info!("Playing on from {:.0}s back", seconds);
+}
+
+/// Marker for the banner shown while rewound
+#[derive(Component)]
+struct RewindBanner;
+
+/// Show how far back the world is while rewound
+fn update_rewind_banner(
+    mut commands: Commands,
+    history: Res<RewindHistory>,
+    mut banner_query: Query<(Entity, &mut Text), With<RewindBanner>>,
+) {
+    if !history.is_changed() {
+        return;
+    }
+
+    let Some(seconds) = history.seconds_back() else {
+        for (entity, _) in &banner_query {
+            commands.entity(entity).despawn();
+        }
+        return;
+    };
+    let label = format!(
+        "<< REWOUND {}:{:02}  |  Backspace: further back  Shift+Backspace: forward  Space: play on from here\nDead plants, visitors, seeds and mushrooms aren't rewound",
+        seconds as u32 / 60,
+        seconds as u32 % 60
+    );
+
+    if let Ok((_, mut text)) = banner_query.single_mut() {
+        **text = label;
+        return;
+    }
+    commands.spawn((
+        RewindBanner,
+        Text::new(label),
+        TextFont {
+            font_size: 16.0,
+            ..default()
+        },
+        TextColor(Color::srgb(1.0, 0.85, 0.4)),
+        Node {
+            position_type: PositionType::Absolute,
+            left: Val::Percent(25.0),
+            top: Val::Px(10.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.2, 0.1, 0.0, 0.85)),
+    ));
+}
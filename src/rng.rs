@@ -0,0 +1,63 @@
+//! The simulation's random number generator.
+//!
+//! Everything random in the simulation, from world generation to ant
+//! decisions, draws from one seeded `SimRng` resource instead of the thread
+//! RNG, so a run can be reproduced from its seed (`--seed <n>`).
+//!
+//! The schedules that generate and advance the world run one system at a
+//! time (see `SimulationPlugin`), since systems sharing the generator have no
+//! order between them and would otherwise draw from it in a different order
+//! each run.
+//!
+//! Keeping runs identical also means never letting hash order decide
+//! anything: simulation state that gets iterated (garden plots, designations,
+//! dig progress, known plants) lives in `BTreeMap`s and `BTreeSet`s, so ties
+//! between equally near tiles always break the same way. Cosmetic effects
+//! like particles use the thread RNG, so they never shift the simulation's
+//! draws.
+
+use bevy::prelude::*;
+use rand::rngs::StdRng;
+use rand::{RngCore, SeedableRng};
+
+/// Seeded RNG shared by all simulation systems
+#[derive(Resource, Clone)]
+pub struct SimRng {
+    /// The seed the generator was last started from
+    pub seed: u64,
+    rng: StdRng,
+}
+
+impl SimRng {
+    pub fn new(seed: u64) -> Self {
+        Self {
+            seed,
+            rng: StdRng::seed_from_u64(seed),
+        }
+    }
+
+    /// Restart the generator from a new seed
+    pub fn reseed(&mut self, seed: u64) {
+        *self = Self::new(seed);
+    }
+
+    /// The next value the generator would give, without drawing it, so two
+    /// runs can tell whether they've drawn the same amount
+    pub fn fingerprint(&self) -> u64 {
+        self.rng.clone().next_u64()
+    }
+}
+
+impl RngCore for SimRng {
+    fn next_u32(&mut self) -> u32 {
+        self.rng.next_u32()
+    }
+
+    fn next_u64(&mut self) -> u64 {
+        self.rng.next_u64()
+    }
+
+    fn fill_bytes(&mut self, dst: &mut [u8]) {
+        self.rng.fill_bytes(dst);
+    }
+}
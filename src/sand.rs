@@ -0,0 +1,148 @@
+//! Falling sand.
+//!
+//! Sand has nothing holding it up: when a tunnel is dug beneath a sand tile,
+//! the sand pours down into it over the next few ticks, refilling the passage
+//! and burying anything caught there. The hole it leaves can let the sand
+//! above it fall in turn, so digging under a deep sand layer can open a
+//! sinkhole all the way up to the surface.
+//!
+//! Rather than scanning the whole grid, only tiles that were just dug (and
+//! the spaces falling sand opens up) are watched, as a small cellular
+//! automaton over the regions that changed.
+
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ants::{Ant, AntDied, Caste, DeathCause, GridPosition};
+use crate::brood::Brood;
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::time_controls::SimulationSet;
+use crate::world::{SURFACE_LEVEL, Soil, TileDug, TileKind, WorldGrid};
+use crate::{ResetRun, reset_resource};
+
+pub struct SandPlugin;
+
+impl Plugin for SandPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ShiftingSand>()
+            .add_systems(
+                FixedUpdate,
+                (watch_dug_tiles, falling_sand)
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, reset_resource::<ShiftingSand>);
+    }
+}
+
+/// Per-tick chance that loose sand above an open tile drops into it
+const SAND_FALL_CHANCE: f64 = 0.2;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Open tiles that may have loose sand hanging over them. Ordered so the
+/// automaton visits them in the same order every run.
+#[derive(Resource, Default)]
+pub struct ShiftingSand {
+    pub watched: BTreeSet<(usize, usize, usize)>,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start watching every freshly dug tile
+fn watch_dug_tiles(mut dug_tiles: MessageReader<TileDug>, mut sand: ResMut<ShiftingSand>) {
+    for dug in dug_tiles.read() {
+        sand.watched.insert((dug.x, dug.y, dug.z));
+    }
+}
+
+/// Let sand fall into the watched tiles, burying ants and brood in its way
+#[allow(clippy::too_many_arguments)]
+fn falling_sand(
+    mut commands: Commands,
+    mut sand: ResMut<ShiftingSand>,
+    mut world_grid: ResMut<WorldGrid>,
+    ant_query: Query<(Entity, &GridPosition, &Caste), With<Ant>>,
+    brood_query: Query<(Entity, &GridPosition), With<Brood>>,
+    mut deaths: MessageWriter<AntDied>,
+    mut notifications: MessageWriter<Notification>,
+    mut rng: ResMut<SimRng>,
+) {
+    if sand.watched.is_empty() {
+        return;
+    }
+
+    let mut filled = Vec::new();
+    let mut still_watched = BTreeSet::new();
+
+    for (x, y, z) in std::mem::take(&mut sand.watched) {
+        // Only tunnels, ramps and chambers fill in; the surface layer has no sand
+        // above it and gardens are packed too firmly with mulch
+        let open = matches!(
+            world_grid.tiles[z][y][x],
+            TileKind::Tunnel | TileKind::Ramp | TileKind::Chamber
+        );
+        if !open || z + 1 >= SURFACE_LEVEL {
+            continue;
+        }
+        let loose_above = world_grid.tiles[z + 1][y][x] == TileKind::Dirt
+            && world_grid.soil[z + 1][y][x] == Soil::Sand;
+        if !loose_above {
+            continue;
+        }
+        if !rng.random_bool(SAND_FALL_CHANCE) {
+            still_watched.insert((x, y, z));
+            continue;
+        }
+
+        // The sand drops a tile, leaving a hole where it was
+        world_grid.tiles[z][y][x] = TileKind::Dirt;
+        world_grid.soil[z][y][x] = Soil::Sand;
+        world_grid.tiles[z + 1][y][x] = TileKind::Tunnel;
+        filled.push((x, y, z));
+
+        // The hole may let more sand down, and the sand may keep falling
+        still_watched.insert((x, y, z + 1));
+        if z > 0 {
+            still_watched.insert((x, y, z - 1));
+        }
+    }
+    sand.watched = still_watched;
+
+    if filled.is_empty() {
+        return;
+    }
+
+    let buried = |pos: &GridPosition| filled.contains(&(pos.x, pos.y, pos.z));
+    let mut crushed = 0;
+    for (entity, pos, caste) in &ant_query {
+        if buried(pos) {
+            commands.entity(entity).despawn();
+            deaths.write(AntDied {
+                caste: *caste,
+                cause: DeathCause::Crushed,
+            });
+            crushed += 1;
+        }
+    }
+    for (entity, pos) in &brood_query {
+        if buried(pos) {
+            commands.entity(entity).despawn();
+            crushed += 1;
+        }
+    }
+
+    if crushed > 0 {
+        notifications.write(Notification::warning(format!(
+            "Sand poured into a tunnel, burying {} of the colony",
+            crushed
+        )));
+    }
+}
@@ -0,0 +1,437 @@
+//! Sandbox mode: creative tools for trying out nest designs.
+//!
+//! F6 turns sandbox mode on or off (not during a scenario), bringing up a
+//! toolbar. Its tools take over the left click from pheromones: Paint turns
+//! the tile under the cursor into the chosen kind (click the button again for
+//! the next kind), Spawn hatches an ant of the chosen caste, and Delete takes
+//! out an ant. Paints and deletions go through `PlayerAction` like the rest of
+//! the player's input, so recordings replay them. Plants own their tiles, so
+//! those aren't painted over.
+//!
+//! The toolbar also sets the stores (each click adds 10, Shift+click empties
+//! them) and sets off any random event on the next tick. Stores and events
+//! are changed directly, so recordings don't capture them.
+
+use bevy::prelude::*;
+
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::ants::{Ant, Caste, GridPosition};
+use crate::cursor::CursorTile;
+use crate::events::RandomEvents;
+use crate::history::CASTES;
+use crate::notifications::Notification;
+use crate::scenarios::ActiveScenario;
+use crate::world::{FoodStores, FungusGarden, TileKind, WorldGrid};
+use crate::{ResetRun, in_game, reset_resource};
+
+pub struct SandboxPlugin;
+
+impl Plugin for SandboxPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Sandbox>()
+            .add_systems(Startup, setup_toolbar)
+            .add_systems(
+                Update,
+                (
+                    toggle_sandbox.run_if(in_game),
+                    toolbar_input.run_if(in_game.and(sandbox_enabled)),
+                    sandbox_tool_input
+                        .run_if(in_game.and(sandbox_tool_active))
+                        .in_set(ActionSystems::Input),
+                    apply_sandbox_actions.in_set(ActionSystems::Apply),
+                    update_toolbar,
+                )
+                    .chain(),
+            )
+            .add_systems(ResetRun, reset_resource::<Sandbox>);
+    }
+}
+
+/// Tile kinds the paint tool cycles through
+const PAINTS: [TileKind; 8] = [
+    TileKind::Dirt,
+    TileKind::Tunnel,
+    TileKind::Chamber,
+    TileKind::Ramp,
+    TileKind::Entrance,
+    TileKind::Rock,
+    TileKind::Surface,
+    TileKind::Air,
+];
+/// Amount each click on a store adds
+const STORE_STEP: u32 = 10;
+
+/// What a left click does in sandbox mode
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum SandboxTool {
+    /// Nothing: clicks place pheromones as usual
+    #[default]
+    Pheromones,
+    Paint(TileKind),
+    Spawn(Caste),
+    Delete,
+}
+
+/// A store the toolbar can set
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Store {
+    Food,
+    Honeydew,
+    Seeds,
+    Leaves,
+}
+
+impl Store {
+    const ALL: [Store; 4] = [Store::Food, Store::Honeydew, Store::Seeds, Store::Leaves];
+
+    fn name(&self) -> &'static str {
+        match self {
+            Store::Food => "Food",
+            Store::Honeydew => "Honeydew",
+            Store::Seeds => "Seeds",
+            Store::Leaves => "Leaves",
+        }
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Whether sandbox mode is on, and the tool in hand
+#[derive(Resource, Default)]
+pub struct Sandbox {
+    pub enabled: bool,
+    pub tool: SandboxTool,
+}
+
+/// Run condition: sandbox mode is on
+pub fn sandbox_enabled(sandbox: Res<Sandbox>) -> bool {
+    sandbox.enabled
+}
+
+/// Run condition: a sandbox tool has the left click instead of pheromones
+pub fn sandbox_tool_active(sandbox: Res<Sandbox>) -> bool {
+    sandbox.enabled && sandbox.tool != SandboxTool::Pheromones
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the toolbar root
+#[derive(Component)]
+struct Toolbar;
+
+/// Buttons on the toolbar
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ToolbarButton {
+    Pheromones,
+    Paint,
+    Spawn,
+    Delete,
+    Store(Store),
+    /// A random event, by its place in the list
+    Event(usize),
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Spawn the (initially hidden) toolbar: tools, stores and events in rows
+fn setup_toolbar(mut commands: Commands, events: Res<RandomEvents>) {
+    let tools = [
+        ToolbarButton::Pheromones,
+        ToolbarButton::Paint,
+        ToolbarButton::Spawn,
+        ToolbarButton::Delete,
+    ];
+    let stores = Store::ALL.map(ToolbarButton::Store);
+    let events: Vec<(ToolbarButton, String)> = events
+        .names()
+        .into_iter()
+        .enumerate()
+        .map(|(index, name)| (ToolbarButton::Event(index), name.to_string()))
+        .collect();
+
+    commands
+        .spawn((
+            Toolbar,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(25.0),
+                bottom: Val::Px(100.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(4.0),
+                padding: UiRect::all(Val::Px(8.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            let rows: [Vec<(ToolbarButton, String)>; 3] = [
+                tools.iter().map(|b| (*b, String::new())).collect(),
+                stores.iter().map(|b| (*b, String::new())).collect(),
+                events,
+            ];
+            for row in rows {
+                parent
+                    .spawn(Node {
+                        column_gap: Val::Px(4.0),
+                        ..default()
+                    })
+                    .with_children(|row_parent| {
+                        for (button, label) in row {
+                            row_parent
+                                .spawn((
+                                    button,
+                                    Button,
+                                    Node {
+                                        padding: UiRect::axes(Val::Px(8.0), Val::Px(4.0)),
+                                        ..default()
+                                    },
+                                    BackgroundColor(Color::srgb(0.25, 0.3, 0.25)),
+                                ))
+                                .with_child((
+                                    Text::new(label),
+                                    TextFont {
+                                        font_size: 12.0,
+                                        ..default()
+                                    },
+                                    TextColor(Color::WHITE),
+                                ));
+                        }
+                    });
+            }
+        });
+}
+
+/// Turn sandbox mode on or off with F6
+fn toggle_sandbox(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    scenario: Res<ActiveScenario>,
+    mut sandbox: ResMut<Sandbox>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::F6) {
+        return;
+    }
+
+    if !sandbox.enabled && scenario.0.is_some() {
+        notifications.write(Notification::warning(
+            "Sandbox tools aren't available in a scenario",
+        ));
+        return;
+    }
+    sandbox.enabled = !sandbox.enabled;
+    sandbox.tool = SandboxTool::Pheromones;
+    notifications.write(Notification::info(if sandbox.enabled {
+        "Sandbox mode on"
+    } else {
+        "Sandbox mode off"
+    }));
+}
+
+/// Pick tools, set the stores and trigger events from the toolbar
+#[allow(clippy::too_many_arguments)]
+fn toolbar_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    button_query: Query<(&Interaction, &ToolbarButton), Changed<Interaction>>,
+    mut sandbox: ResMut<Sandbox>,
+    mut food_stores: ResMut<FoodStores>,
+    mut fungus_garden: ResMut<FungusGarden>,
+    mut events: ResMut<RandomEvents>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match *button {
+            ToolbarButton::Pheromones => sandbox.tool = SandboxTool::Pheromones,
+            ToolbarButton::Paint => {
+                sandbox.tool = match sandbox.tool {
+                    SandboxTool::Paint(kind) => {
+                        let index = PAINTS.iter().position(|k| *k == kind).unwrap_or(0);
+                        SandboxTool::Paint(PAINTS[(index + 1) % PAINTS.len()])
+                    }
+                    _ => SandboxTool::Paint(PAINTS[0]),
+                };
+            }
+            ToolbarButton::Spawn => {
+                sandbox.tool = match sandbox.tool {
+                    SandboxTool::Spawn(caste) => {
+                        let index = CASTES.iter().position(|c| *c == caste).unwrap_or(0);
+                        SandboxTool::Spawn(CASTES[(index + 1) % CASTES.len()])
+                    }
+                    _ => SandboxTool::Spawn(Caste::Forager),
+                };
+            }
+            ToolbarButton::Delete => sandbox.tool = SandboxTool::Delete,
+            ToolbarButton::Store(store) => {
+                let amount = match store {
+                    Store::Food => &mut food_stores.food,
+                    Store::Honeydew => &mut food_stores.honeydew,
+                    Store::Seeds => &mut food_stores.seeds,
+                    Store::Leaves => &mut fungus_garden.leaves,
+                };
+                *amount = if shift { 0 } else { *amount + STORE_STEP };
+                info!("Sandbox: {} set to {}", store.name(), *amount);
+            }
+            ToolbarButton::Event(index) => {
+                events.trigger(index);
+                if let Some(name) = events.names().get(index) {
+                    notifications.write(Notification::info(format!("Triggering {}", name)));
+                }
+            }
+        }
+    }
+}
+
+/// Use the tool in hand on the tile under the cursor: paint while the button
+/// is held, spawn or delete once per click
+fn sandbox_tool_input(
+    mouse_button: Res<ButtonInput<MouseButton>>,
+    cursor_tile: Res<CursorTile>,
+    world_grid: Res<WorldGrid>,
+    sandbox: Res<Sandbox>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    let Some((x, y, z)) = cursor_tile.0 else {
+        return;
+    };
+
+    match sandbox.tool {
+        // Only paint what would change, so a held button isn't recorded
+        // every frame
+        SandboxTool::Paint(kind)
+            if mouse_button.pressed(MouseButton::Left) && world_grid.tiles[z][y][x] != kind =>
+        {
+            actions.write(PlayerAction::PaintTile { kind, x, y, z });
+        }
+        SandboxTool::Spawn(caste) if mouse_button.just_pressed(MouseButton::Left) => {
+            actions.write(PlayerAction::SpawnAnt { caste, x, y, z });
+        }
+        SandboxTool::Delete if mouse_button.just_pressed(MouseButton::Left) => {
+            actions.write(PlayerAction::RemoveAnt { x, y, z });
+        }
+        _ => {}
+    }
+}
+
+/// Apply paints and deletions from live or replayed input
+fn apply_sandbox_actions(
+    mut commands: Commands,
+    mut actions: MessageReader<PlayerAction>,
+    ant_query: Query<(Entity, &GridPosition, &Caste), With<Ant>>,
+    mut world_grid: ResMut<WorldGrid>,
+    mut fungus_garden: ResMut<FungusGarden>,
+) {
+    for action in actions.read() {
+        match *action {
+            PlayerAction::PaintTile { kind, x, y, z } => {
+                let tile = world_grid.tiles[z][y][x];
+                if tile == kind || tile.is_plant() {
+                    continue;
+                }
+                world_grid.tiles[z][y][x] = kind;
+                fungus_garden.plots.remove(&(x, y, z));
+            }
+            PlayerAction::RemoveAnt { x, y, z } => {
+                let ant = ant_query
+                    .iter()
+                    .find(|(_, pos, _)| (pos.x, pos.y, pos.z) == (x, y, z));
+                if let Some((entity, _, caste)) = ant {
+                    commands.entity(entity).despawn();
+                    info!(
+                        "Sandbox: removed a {} at ({}, {}, {})",
+                        caste.name(),
+                        x,
+                        y,
+                        z
+                    );
+                }
+            }
+            _ => {}
+        }
+    }
+}
+
+/// Show the toolbar while sandbox mode is on, with the current tool lit up
+/// and the stores' counts
+fn update_toolbar(
+    sandbox: Res<Sandbox>,
+    food_stores: Res<FoodStores>,
+    fungus_garden: Res<FungusGarden>,
+    events: Res<RandomEvents>,
+    mut toolbar_query: Query<&mut Visibility, With<Toolbar>>,
+    mut button_query: Query<(&ToolbarButton, &Children, &mut BackgroundColor)>,
+    mut text_query: Query<&mut Text>,
+) {
+    if let Ok(mut visibility) = toolbar_query.single_mut() {
+        visibility.set_if_neq(if sandbox.enabled {
+            Visibility::Visible
+        } else {
+            Visibility::Hidden
+        });
+    }
+    if !sandbox.enabled
+        || !(sandbox.is_changed() || food_stores.is_changed() || fungus_garden.is_changed())
+    {
+        return;
+    }
+
+    let names = events.names();
+    for (button, children, mut background) in &mut button_query {
+        let (label, selected) = match *button {
+            ToolbarButton::Pheromones => (
+                String::from("Pheromones"),
+                sandbox.tool == SandboxTool::Pheromones,
+            ),
+            ToolbarButton::Paint => match sandbox.tool {
+                SandboxTool::Paint(kind) => (format!("Paint: {}", kind.name()), true),
+                _ => (String::from("Paint"), false),
+            },
+            ToolbarButton::Spawn => match sandbox.tool {
+                SandboxTool::Spawn(caste) => (format!("Spawn: {}", caste.name()), true),
+                _ => (String::from("Spawn"), false),
+            },
+            ToolbarButton::Delete => (
+                String::from("Delete ant"),
+                sandbox.tool == SandboxTool::Delete,
+            ),
+            ToolbarButton::Store(store) => {
+                let amount = match store {
+                    Store::Food => food_stores.food,
+                    Store::Honeydew => food_stores.honeydew,
+                    Store::Seeds => food_stores.seeds,
+                    Store::Leaves => fungus_garden.leaves,
+                };
+                (
+                    format!("{}: {} (+{})", store.name(), amount, STORE_STEP),
+                    false,
+                )
+            }
+            ToolbarButton::Event(index) => (
+                names.get(index).copied().unwrap_or_default().to_string(),
+                false,
+            ),
+        };
+
+        background.0 = if selected {
+            Color::srgb(0.45, 0.5, 0.3)
+        } else {
+            Color::srgb(0.25, 0.3, 0.25)
+        };
+        if let Some(mut text) = children
+            .first()
+            .and_then(|child| text_query.get_mut(*child).ok())
+        {
+            **text = label;
+        }
+    }
+}
@@ -0,0 +1,553 @@
+//! Scenarios: challenges with their own start and goals.
+//!
+//! A scenario is a RON file giving a name and description, start conditions
+//! that take the place of the setup screen's (biome, trees, founding workers,
+//! a lone queen, difficulty, starting food, seed, a drought with no rain, and
+//! a map file from the world editor to play on),
+//! and the rules it's won and lost by. Scenarios (S) on the title screen lists
+//! them; picking one (or pressing its number) generates its world and goes on
+//! to the setup screen. In play a panel lists the goals and how the colony is
+//! doing on each. The scenario is won once every win rule holds at the same
+//! time, and lost as soon as any lose rule does, or the colony collapses.
+//!
+//! The scenarios that ship with the game are built in, so the web build has
+//! them too. Files in `scenarios/` beside the game add to them, replacing a
+//! built-in one of the same name:
+//!
+//! ```ron
+//! #![enable(implicit_some)]
+//! Scenario(
+//!     name: "Siege",
+//!     description: "Drive off 5 raids before winter comes.",
+//!     start: (difficulty: Hard, workers: 8),
+//!     win: [RaidsRepelled(5)],
+//!     lose: [Days(20)],
+//! )
+//! ```
+//!
+//! The rules are `Days(n)`, `Population(n)`, `PopulationBelow(n)`,
+//! `Stores(n)`, `RaidsRepelled(n)` and `QueenDead`.
+
+use std::fs;
+use std::path::{Path, PathBuf};
+
+use bevy::prelude::*;
+use serde::Deserialize;
+
+use crate::GameState;
+use crate::ants::{Ant, Caste};
+use crate::biome::Biome;
+use crate::difficulty::Difficulty;
+use crate::menu::GameSession;
+use crate::moisture::Weather;
+use crate::notifications::Notification;
+use crate::sprites;
+use crate::summary::{RunEnder, RunStats};
+use crate::time_controls::SimulationTick;
+use crate::world::{FoodStores, WorldOptions};
+
+pub struct ScenarioPlugin;
+
+impl Plugin for ScenarioPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_scenarios())
+            .init_resource::<ActiveScenario>()
+            .add_systems(Startup, setup_scenario_panel)
+            .add_systems(OnEnter(GameState::ScenarioSelect), enter_scenario_select)
+            .add_systems(OnExit(GameState::ScenarioSelect), exit_scenario_select)
+            .add_systems(OnExit(GameState::Setup), apply_start)
+            .add_systems(
+                Update,
+                scenario_select_input.run_if(in_state(GameState::ScenarioSelect)),
+            )
+            .add_systems(
+                Update,
+                (
+                    check_scenario.run_if(in_state(GameState::Playing)),
+                    update_scenario_panel,
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Directory scenario files are read from
+const SCENARIOS_DIR: &str = "scenarios";
+/// Scenarios that ship with the game
+const BUILT_IN: [&str; 3] = [
+    include_str!("../scenarios/drought.ron"),
+    include_str!("../scenarios/siege.ron"),
+    include_str!("../scenarios/boom.ron"),
+];
+
+/// A challenge with its own start conditions, and rules to win and lose by
+#[derive(Debug, Clone, Deserialize)]
+pub struct Scenario {
+    pub name: String,
+    pub description: String,
+    #[serde(default)]
+    pub start: StartConditions,
+    /// Rules that must all hold at once to win
+    pub win: Vec<Rule>,
+    /// Rules that each lose the scenario
+    #[serde(default)]
+    pub lose: Vec<Rule>,
+}
+
+/// Settings a scenario starts with; anything left out keeps the setup
+/// screen's
+#[derive(Debug, Clone, Default, Deserialize)]
+#[serde(default)]
+pub struct StartConditions {
+    pub biome: Option<Biome>,
+    pub trees: Option<usize>,
+    pub workers: Option<usize>,
+    /// Start with a lone queen who founds the nest herself
+    pub founding: Option<bool>,
+    pub difficulty: Option<Difficulty>,
+    /// Food in the stores, instead of the difficulty's
+    pub food: Option<u32>,
+    pub seed: Option<u64>,
+    /// No rain for the whole game
+    pub drought: bool,
+    /// Map file to play on instead of a generated world
+    pub map: Option<PathBuf>,
+}
+
+/// Something a scenario is won or lost by
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Rule {
+    /// This many in-game days have passed
+    Days(u64),
+    /// At least this many ants
+    Population(usize),
+    /// Fewer than this many ants
+    PopulationBelow(usize),
+    /// At least this much food, honeydew and seeds in store together
+    Stores(u32),
+    /// Soldiers have driven this many spiders away from the nest
+    RaidsRepelled(u32),
+    /// The queen has died
+    QueenDead,
+}
+
+/// How the colony stands, for checking rules against
+struct Standing {
+    day: u64,
+    population: usize,
+    stores: u32,
+    raids_repelled: u32,
+    queen_alive: bool,
+}
+
+impl Rule {
+    fn holds(&self, standing: &Standing) -> bool {
+        match *self {
+            Rule::Days(days) => standing.day >= days,
+            Rule::Population(ants) => standing.population >= ants,
+            Rule::PopulationBelow(ants) => standing.population < ants,
+            Rule::Stores(food) => standing.stores >= food,
+            Rule::RaidsRepelled(raids) => standing.raids_repelled >= raids,
+            Rule::QueenDead => !standing.queen_alive,
+        }
+    }
+
+    /// The rule, and where the colony is against it
+    fn describe(&self, standing: &Standing) -> String {
+        match *self {
+            Rule::Days(days) => format!("day {} (now day {})", days, standing.day),
+            Rule::Population(ants) => format!("{}+ ants (now {})", ants, standing.population),
+            Rule::PopulationBelow(ants) => {
+                format!("under {} ants (now {})", ants, standing.population)
+            }
+            Rule::Stores(food) => format!("{}+ food in store (now {})", food, standing.stores),
+            Rule::RaidsRepelled(raids) => format!(
+                "{} spiders driven off (now {})",
+                raids, standing.raids_repelled
+            ),
+            Rule::QueenDead => String::from("the queen dies"),
+        }
+    }
+}
+
+/// Parse the built-in scenarios and any in `scenarios/`, warning about files
+/// that can't be read
+fn load_scenarios() -> Scenarios {
+    let mut list: Vec<Scenario> = BUILT_IN
+        .iter()
+        .filter_map(|text| match ron::from_str(text) {
+            Ok(scenario) => Some(scenario),
+            Err(e) => {
+                error!("Failed to parse a built-in scenario: {}", e);
+                None
+            }
+        })
+        .collect();
+
+    // There's no filesystem in a web build
+    let dir = Path::new(SCENARIOS_DIR);
+    if cfg!(target_arch = "wasm32") || !dir.is_dir() {
+        return Scenarios { list };
+    }
+    let Ok(entries) = fs::read_dir(dir) else {
+        return Scenarios { list };
+    };
+    let mut paths: Vec<_> = entries
+        .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+        .filter(|path| path.extension().is_some_and(|ext| ext == "ron"))
+        .collect();
+    paths.sort();
+
+    for path in paths {
+        let parsed = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| ron::from_str::<Scenario>(&text).map_err(|e| e.to_string()));
+        match parsed {
+            Ok(scenario) => {
+                list.retain(|existing| existing.name != scenario.name);
+                list.push(scenario);
+            }
+            Err(e) => warn!("Skipping scenario {}: {}", path.display(), e),
+        }
+    }
+    Scenarios { list }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Scenarios to choose from
+#[derive(Resource, Default)]
+pub struct Scenarios {
+    pub list: Vec<Scenario>,
+}
+
+/// The scenario being played, if any
+#[derive(Resource, Default)]
+pub struct ActiveScenario(pub Option<Scenario>);
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the scenario select screen
+#[derive(Component)]
+struct ScenarioScreen;
+
+/// A button choosing a scenario, by its place in the list
+#[derive(Component)]
+struct ScenarioButton(usize);
+
+/// Marker for the button back to the title screen
+#[derive(Component)]
+struct BackButton;
+
+/// Marker for the in-game goals panel
+#[derive(Component)]
+struct ScenarioPanel;
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// List the scenarios, each with its description
+fn enter_scenario_select(mut commands: Commands, scenarios: Res<Scenarios>) {
+    commands
+        .spawn((
+            ScenarioScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(30.0),
+                top: Val::Percent(15.0),
+                max_width: Val::Px(520.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Scenarios"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            if scenarios.list.is_empty() {
+                parent.spawn((
+                    Text::new("No scenarios could be loaded"),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(sprites::ui::HIGHLIGHT),
+                ));
+            }
+            for (index, scenario) in scenarios.list.iter().enumerate() {
+                parent
+                    .spawn((
+                        ScenarioButton(index),
+                        Button,
+                        Node {
+                            flex_direction: FlexDirection::Column,
+                            row_gap: Val::Px(2.0),
+                            padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgb(0.25, 0.3, 0.25)),
+                    ))
+                    .with_children(|button| {
+                        button.spawn((
+                            Text::new(format!("{}. {}", index + 1, scenario.name)),
+                            TextFont {
+                                font_size: 16.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+                        button.spawn((
+                            Text::new(scenario.description.clone()),
+                            TextFont {
+                                font_size: 12.0,
+                                ..default()
+                            },
+                            TextColor(Color::srgba(0.8, 0.9, 0.8, 1.0)),
+                        ));
+                    });
+            }
+            parent
+                .spawn((
+                    BackButton,
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.25, 0.3, 0.25)),
+                ))
+                .with_child((
+                    Text::new("Back (Esc)"),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+        });
+}
+
+/// Tear down the scenario select screen
+fn exit_scenario_select(mut commands: Commands, screen_query: Query<Entity, With<ScenarioScreen>>) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Pick a scenario by button or number key, setting the world up for it, or
+/// go back with Esc
+#[allow(clippy::too_many_arguments)]
+fn scenario_select_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    button_query: Query<(&Interaction, &ScenarioButton), Changed<Interaction>>,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    scenarios: Res<Scenarios>,
+    mut active: ResMut<ActiveScenario>,
+    mut biome: ResMut<Biome>,
+    mut options: ResMut<WorldOptions>,
+    mut difficulty: ResMut<Difficulty>,
+    mut session: ResMut<GameSession>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape)
+        || back_query.iter().any(|i| *i == Interaction::Pressed)
+    {
+        next_state.set(GameState::MainMenu);
+        return;
+    }
+
+    let number_keys = [
+        KeyCode::Digit1,
+        KeyCode::Digit2,
+        KeyCode::Digit3,
+        KeyCode::Digit4,
+        KeyCode::Digit5,
+        KeyCode::Digit6,
+        KeyCode::Digit7,
+        KeyCode::Digit8,
+        KeyCode::Digit9,
+    ];
+    let chosen = button_query
+        .iter()
+        .find(|(interaction, _)| **interaction == Interaction::Pressed)
+        .map(|(_, button)| button.0)
+        .or_else(|| {
+            number_keys
+                .iter()
+                .position(|key| keyboard.just_pressed(*key))
+        });
+    let Some(scenario) = chosen.and_then(|index| scenarios.list.get(index)) else {
+        return;
+    };
+
+    let start = &scenario.start;
+    if let Some(chosen) = start.biome {
+        *biome = chosen;
+    }
+    if start.trees.is_some() {
+        options.trees = start.trees;
+    }
+    if let Some(workers) = start.workers {
+        options.starting_workers = workers;
+    }
+    if let Some(founding) = start.founding {
+        options.founding = founding;
+    }
+    if let Some(chosen) = start.difficulty {
+        *difficulty = chosen;
+    }
+    options.map = start.map.clone();
+
+    // Generate the scenario's world afresh, even before the first game
+    session.resume = None;
+    session.played = true;
+    session.restart_seed = start.seed;
+    active.0 = Some(scenario.clone());
+    next_state.set(GameState::Setup);
+    info!("Scenario: {}", scenario.name);
+}
+
+/// Apply the start conditions the world's generation doesn't cover as the
+/// game begins
+fn apply_start(
+    active: Res<ActiveScenario>,
+    mut food_stores: ResMut<FoodStores>,
+    mut weather: ResMut<Weather>,
+) {
+    let Some(scenario) = &active.0 else {
+        return;
+    };
+
+    if let Some(food) = scenario.start.food {
+        food_stores.food = food;
+    }
+    weather.drought = scenario.start.drought;
+}
+
+/// How the colony stands now
+fn standing(
+    tick: &SimulationTick,
+    caste_query: &Query<&Caste, With<Ant>>,
+    food_stores: &FoodStores,
+    stats: &RunStats,
+) -> Standing {
+    Standing {
+        day: tick.day(),
+        population: caste_query.iter().count(),
+        stores: food_stores.food + food_stores.honeydew + food_stores.seeds,
+        raids_repelled: stats.raids_repelled,
+        queen_alive: caste_query.iter().any(|caste| *caste == Caste::Queen),
+    }
+}
+
+/// End the game once the scenario's won or lost
+fn check_scenario(
+    active: Res<ActiveScenario>,
+    caste_query: Query<&Caste, With<Ant>>,
+    tick: Res<SimulationTick>,
+    food_stores: Res<FoodStores>,
+    mut stats: ResMut<RunStats>,
+    mut ender: RunEnder,
+) {
+    let Some(scenario) = &active.0 else {
+        return;
+    };
+    // Wait for the founding colony, and leave a collapse to the summary
+    if stats.collapsed || tick.0 == 0 {
+        return;
+    }
+
+    let standing = standing(&tick, &caste_query, &food_stores, &stats);
+    let lost = scenario.lose.iter().find(|rule| rule.holds(&standing));
+    let won = !scenario.win.is_empty() && scenario.win.iter().all(|rule| rule.holds(&standing));
+    let result = match (lost, won) {
+        (Some(rule), _) => format!("Lost: {}", rule.describe(&standing)),
+        (None, true) => String::from("Won"),
+        (None, false) => return,
+    };
+    stats.collapsed = true;
+
+    ender.end_run(
+        &stats,
+        "scenario",
+        &format!("scenario: {}", scenario.name),
+        &result,
+        Notification::info(format!("Scenario {}: {}.", scenario.name, result)),
+    );
+}
+
+/// Spawn the (initially hidden) goals panel on the right
+fn setup_scenario_panel(mut commands: Commands) {
+    commands.spawn((
+        ScenarioPanel,
+        Text::new(""),
+        TextFont {
+            font_size: 12.0,
+            ..default()
+        },
+        TextColor(Color::WHITE),
+        Node {
+            position_type: PositionType::Absolute,
+            right: Val::Px(10.0),
+            top: Val::Percent(35.0),
+            max_width: Val::Px(280.0),
+            padding: UiRect::all(Val::Px(8.0)),
+            ..default()
+        },
+        BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+        Visibility::Hidden,
+    ));
+}
+
+/// Show the goals and progress while a scenario is in play
+fn update_scenario_panel(
+    active: Res<ActiveScenario>,
+    state: Res<State<GameState>>,
+    caste_query: Query<&Caste, With<Ant>>,
+    tick: Res<SimulationTick>,
+    food_stores: Res<FoodStores>,
+    stats: Res<RunStats>,
+    mut panel_query: Query<(&mut Text, &mut Visibility), With<ScenarioPanel>>,
+) {
+    let Ok((mut text, mut visibility)) = panel_query.single_mut() else {
+        return;
+    };
+    let scenario = match &active.0 {
+        Some(scenario) if state.get().in_game() => scenario,
+        _ => {
+            visibility.set_if_neq(Visibility::Hidden);
+            return;
+        }
+    };
+    visibility.set_if_neq(Visibility::Visible);
+    if !tick.is_changed() && !active.is_changed() {
+        return;
+    }
+
+    let standing = standing(&tick, &caste_query, &food_stores, &stats);
+    let mut lines = vec![format!("Scenario: {}", scenario.name)];
+    lines.push(String::from("Win once all of:"));
+    for rule in &scenario.win {
+        lines.push(format!("  {}", rule.describe(&standing)));
+    }
+    if !scenario.lose.is_empty() {
+        lines.push(String::from("Lose if any of:"));
+        for rule in &scenario.lose {
+            lines.push(format!("  {}", rule.describe(&standing)));
+        }
+    }
+    **text = lines.join("\n");
+}
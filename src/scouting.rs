@@ -0,0 +1,80 @@
+//! What the colony knows about the surface.
+//!
+//! Foragers only go for plants some ant has seen. Any ant out on the surface
+//! spots the plants around it, and when the colony knows of nothing worth
+//! cutting, idle foragers head out to scout a far corner of the map instead.
+
+use std::collections::BTreeSet;
+
+use bevy::prelude::*;
+
+use crate::ants::{Ant, GridPosition};
+use crate::time_controls::SimulationSet;
+use crate::world::{Plant, SURFACE_LEVEL};
+use crate::{ResetRun, reset_resource};
+
+pub struct ScoutingPlugin;
+
+impl Plugin for ScoutingPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<ColonyKnowledge>()
+            .add_systems(FixedUpdate, spot_plants.in_set(SimulationSet))
+            .add_systems(ResetRun, reset_resource::<ColonyKnowledge>);
+    }
+}
+
+/// How far an ant on the surface spots plants
+const SIGHT: usize = 6;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Plants the colony has found
+#[derive(Resource, Default)]
+pub struct ColonyKnowledge {
+    pub plants: BTreeSet<Entity>,
+}
+
+impl ColonyKnowledge {
+    pub fn knows(&self, plant: Entity) -> bool {
+        self.plants.contains(&plant)
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Remember every plant an ant on the surface comes within sight of, and
+/// forget the ones that are gone
+fn spot_plants(
+    ant_query: Query<&GridPosition, With<Ant>>,
+    plant_query: Query<(Entity, &Plant)>,
+    mut knowledge: ResMut<ColonyKnowledge>,
+) {
+    for (entity, plant) in &plant_query {
+        if knowledge.knows(entity) {
+            continue;
+        }
+        let seen = ant_query.iter().any(|pos| {
+            pos.z >= SURFACE_LEVEL && pos.x.abs_diff(plant.x) + pos.y.abs_diff(plant.y) <= SIGHT
+        });
+        if seen {
+            knowledge.plants.insert(entity);
+            info!(
+                "Scouts found a {} at ({}, {})",
+                plant.kind.name(),
+                plant.x,
+                plant.y
+            );
+        }
+    }
+
+    // Plants die and get eaten; drop them once more are remembered than exist
+    if knowledge.plants.len() > plant_query.iter().len() {
+        knowledge
+            .plants
+            .retain(|&entity| plant_query.contains(entity));
+    }
+}
@@ -0,0 +1,130 @@
+//! Screenshots.
+//!
+//! F12 saves the current frame as a PNG under `screenshots/`, named by the
+//! time it was taken. Shift+F12 hides the interface for that frame first, for
+//! a clean shot of the colony. The web build has nowhere to save them.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+use bevy::render::view::screenshot::{Screenshot, ScreenshotCaptured, save_to_disk};
+
+use crate::notifications::Notification;
+
+pub struct ScreenshotPlugin;
+
+impl Plugin for ScreenshotPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<HiddenUi>()
+            .add_systems(Update, take_screenshot);
+    }
+}
+
+/// Directory screenshots are written to
+pub const SCREENSHOTS_DIR: &str = "screenshots";
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Interface nodes hidden for a clean screenshot, with how they were shown
+#[derive(Resource, Default)]
+pub struct HiddenUi(Vec<(Entity, Visibility)>);
+
+/// The root interface nodes, which hide everything beneath them
+pub type RootNodes<'w, 's> =
+    Query<'w, 's, (Entity, &'static mut Visibility), (With<Node>, Without<ChildOf>)>;
+
+/// A new timestamped PNG path under `dir`, creating the directory
+pub fn timestamped_png(dir: &str, kind: &str) -> std::io::Result<PathBuf> {
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_millis())
+        .unwrap_or_default();
+    fs::create_dir_all(dir)?;
+    Ok(PathBuf::from(dir).join(format!("{}-{}.png", kind, timestamp)))
+}
+
+/// Capture the window to a PNG at `path`, hiding the interface for that frame
+/// if `clean`
+pub fn capture_window(
+    commands: &mut Commands,
+    path: PathBuf,
+    clean: bool,
+    ui_query: &mut RootNodes,
+    hidden: &mut HiddenUi,
+) {
+    // Hide every root node this frame; the capture puts them back
+    if clean && hidden.0.is_empty() {
+        for (entity, mut visibility) in ui_query.iter_mut() {
+            hidden.0.push((entity, *visibility));
+            *visibility = Visibility::Hidden;
+        }
+    }
+
+    commands
+        .spawn(Screenshot::primary_window())
+        .observe(save_to_disk(path))
+        .observe(restore_ui);
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Capture the window with F12, without the interface with Shift+F12
+fn take_screenshot(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut ui_query: RootNodes,
+    mut hidden: ResMut<HiddenUi>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::F12) {
+        return;
+    }
+
+    // There's no filesystem in a web build
+    if cfg!(target_arch = "wasm32") {
+        notifications.write(Notification::warning(
+            "Screenshots can't be saved in a web build",
+        ));
+        return;
+    }
+    let path = match timestamped_png(SCREENSHOTS_DIR, "acre") {
+        Ok(path) => path,
+        Err(e) => {
+            error!("Failed to create {}: {}", SCREENSHOTS_DIR, e);
+            notifications.write(Notification::warning("Couldn't save the screenshot"));
+            return;
+        }
+    };
+
+    let clean = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+    capture_window(
+        &mut commands,
+        path.clone(),
+        clean,
+        &mut ui_query,
+        &mut hidden,
+    );
+    notifications.write(Notification::info(format!(
+        "Screenshot saved to {}",
+        path.display()
+    )));
+}
+
+/// Show the interface again once a clean screenshot has been captured
+fn restore_ui(
+    _captured: On<ScreenshotCaptured>,
+    mut hidden: ResMut<HiddenUi>,
+    mut query: Query<&mut Visibility>,
+) {
+    for (entity, visibility) in hidden.0.drain(..) {
+        if let Ok(mut current) = query.get_mut(entity) {
+            *current = visibility;
+        }
+    }
+}
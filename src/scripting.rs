@@ -0,0 +1,508 @@
+//! Scripting: custom events, goals and behaviors in Rhai.
+//!
+//! Every `.rhai` file in `scripts/` beside the game is loaded at startup, in
+//! name order, and hooked into the simulation. A script defines whichever of
+//! these functions it wants:
+//!
+//! - `on_tick(colony)` every simulation tick
+//! - `on_ant_spawned(colony, caste)` for each ant hatched, by caste name
+//! - `on_raid(colony, spiders)` as a pack of spiders arrives
+//!
+//! `colony` is a map of how the colony stands: `tick`, `day`, `season`,
+//! `food`, `honeydew`, `seeds`, `leaves`, `harvested`, `population`,
+//! `queen_alive`, and `castes` (a map from caste name to count). Functions
+//! can't see a script's top-level variables, so anything a script needs to
+//! remember between calls goes on `this`, a map kept for it.
+//!
+//! Scripts act on the game through:
+//!
+//! - `notify(text)` and `warn(text)` to tell the player something
+//! - `spawn(caste, count)` to hatch ants at the nest
+//! - `add_food(n)` and `add_leaves(n)` to fill (or, negative, empty) stores
+//! - `raid(spiders)` to send in a pack of spiders
+//! - `rain(ticks)` to start a shower
+//! - `win(reason)` and `lose(reason)` to end the game
+//!
+//! ```rhai
+//! // Every fifth spider raid brings a gift
+//! fn on_raid(colony, spiders) {
+//!     this.raids = (this.raids ?? 0) + 1;
+//!     if this.raids % 5 == 0 {
+//!         add_food(200);
+//!         notify("The raiders left their stores behind");
+//!     }
+//! }
+//!
+//! fn on_tick(colony) {
+//!     if colony.castes.Soldier >= 20 {
+//!         win("Raised an army of 20 soldiers");
+//!     }
+//! }
+//! ```
+//!
+//! Hooks run as part of the simulation, so recordings replay what scripts do
+//! as long as they only go by the colony they're given. A script that errors
+//! is switched off for the rest of the run with a warning, and each call is
+//! limited in how much work it can do so a runaway loop can't hang the game.
+//! Every new colony starts each script afresh, switched on with an empty
+//! `this`.
+
+use std::fs;
+use std::path::Path;
+use std::sync::{Arc, Mutex};
+
+use bevy::ecs::system::SystemState;
+use bevy::prelude::*;
+use rhai::{AST, CallFnOptions, Dynamic, Engine, EvalAltResult, FuncArgs, Map, Scope};
+
+use crate::ResetRun;
+use crate::ants::{Ant, Caste, NestLocation, spawn_ant};
+use crate::console::ConsoleAppExt;
+use crate::history::CASTES;
+use crate::migrations::{
+    Migrant, MigrantKind, random_edge_tile, random_interior_tile, spawn_migrant,
+};
+use crate::moisture::Weather;
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::summary::{RunEnder, RunStats};
+use crate::time_controls::{SimulationSet, SimulationTick};
+use crate::world::{FoodStores, FungusGarden};
+
+pub struct ScriptingPlugin;
+
+impl Plugin for ScriptingPlugin {
+    fn build(&self, app: &mut App) {
+        app.insert_resource(load_scripts())
+            .add_console_command(
+                "scripts",
+                "scripts",
+                "List the loaded scripts",
+                list_scripts,
+            )
+            .add_systems(
+                FixedUpdate,
+                (run_script_hooks, apply_script_actions)
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, reset_scripts);
+    }
+}
+
+/// Directory scripts are loaded from
+const SCRIPTS_DIR: &str = "scripts";
+/// Most operations one hook call may take before it's stopped
+const MAX_OPERATIONS: u64 = 100_000;
+/// Most ants one `spawn` may hatch
+const MAX_SPAWN: i64 = 100;
+/// Most spiders one `raid` may send
+const MAX_RAID: i64 = 20;
+
+/// Something a script asked for, applied after its hooks have run
+#[derive(Debug, Clone)]
+enum ScriptAction {
+    Notify { text: String, warning: bool },
+    Spawn { caste: Caste, count: usize },
+    AddFood(i64),
+    AddLeaves(i64),
+    Raid(usize),
+    Rain(u32),
+    End { won: bool, reason: String },
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// A loaded script
+pub struct Script {
+    pub name: String,
+    ast: AST,
+    /// Hook functions the script defines
+    hooks: Vec<String>,
+    /// The script's `this`, kept between calls
+    state: Dynamic,
+    /// Switched off after an error
+    pub failed: bool,
+}
+
+/// The scripting engine and every script loaded into it
+#[derive(Resource)]
+pub struct Scripts {
+    engine: Engine,
+    pub list: Vec<Script>,
+    /// What the scripts have asked for since it was last applied, and by
+    /// which script
+    actions: Arc<Mutex<Vec<(String, ScriptAction)>>>,
+    /// The script whose hook is running, so its actions can be credited
+    running: Arc<Mutex<String>>,
+}
+
+impl Scripts {
+    /// Call a hook on every script that defines it
+    fn call(&mut self, hook: &str, args: impl FuncArgs + Clone) {
+        for script in self.list.iter_mut() {
+            if script.failed || !script.hooks.iter().any(|name| name == hook) {
+                continue;
+            }
+            if let Ok(mut running) = self.running.lock() {
+                running.clone_from(&script.name);
+            }
+
+            let options = CallFnOptions::new()
+                .eval_ast(false)
+                .bind_this_ptr(&mut script.state);
+            let result = self.engine.call_fn_with_options::<Dynamic>(
+                options,
+                &mut Scope::new(),
+                &script.ast,
+                hook,
+                args.clone(),
+            );
+            if let Err(e) = result {
+                warn!("Script {} failed in {}: {}", script.name, hook, e);
+                script.failed = true;
+                queue(
+                    &self.actions,
+                    &script.name,
+                    ScriptAction::Notify {
+                        text: format!("Script {} failed and was switched off", script.name),
+                        warning: true,
+                    },
+                );
+            }
+        }
+    }
+
+    /// Give every script a fresh start for a new colony: an empty `this`,
+    /// switched back on, and nothing left over to apply
+    pub fn reset(&mut self) {
+        for script in self.list.iter_mut() {
+            script.state = Dynamic::from_map(Map::new());
+            script.failed = false;
+        }
+        if let Ok(mut actions) = self.actions.lock() {
+            actions.clear();
+        }
+    }
+}
+
+/// Add an action to the queue
+fn queue(actions: &Mutex<Vec<(String, ScriptAction)>>, script: &str, action: ScriptAction) {
+    if let Ok(mut actions) = actions.lock() {
+        actions.push((script.to_string(), action));
+    }
+}
+
+/// An engine with the game's functions registered, each queuing its action
+/// under the script that's running
+fn engine(
+    actions: &Arc<Mutex<Vec<(String, ScriptAction)>>>,
+    running: &Arc<Mutex<String>>,
+) -> Engine {
+    let mut engine = Engine::new();
+    engine.set_max_operations(MAX_OPERATIONS);
+
+    let act = {
+        let actions = Arc::clone(actions);
+        let running = Arc::clone(running);
+        move |action: ScriptAction| {
+            let script = running.lock().map(|name| name.clone()).unwrap_or_default();
+            queue(&actions, &script, action);
+        }
+    };
+
+    let run = act.clone();
+    engine.register_fn("notify", move |text: &str| {
+        run(ScriptAction::Notify {
+            text: text.to_string(),
+            warning: false,
+        })
+    });
+    let run = act.clone();
+    engine.register_fn("warn", move |text: &str| {
+        run(ScriptAction::Notify {
+            text: text.to_string(),
+            warning: true,
+        })
+    });
+    let run = act.clone();
+    engine.register_fn(
+        "spawn",
+        move |name: &str, count: i64| -> Result<(), Box<EvalAltResult>> {
+            let caste = CASTES
+                .into_iter()
+                .find(|caste| caste.name().eq_ignore_ascii_case(name))
+                .ok_or_else(|| format!("unknown caste '{}'", name))?;
+            run(ScriptAction::Spawn {
+                caste,
+                count: count.clamp(0, MAX_SPAWN) as usize,
+            });
+            Ok(())
+        },
+    );
+    let run = act.clone();
+    engine.register_fn("add_food", move |amount: i64| {
+        run(ScriptAction::AddFood(amount))
+    });
+    let run = act.clone();
+    engine.register_fn("add_leaves", move |amount: i64| {
+        run(ScriptAction::AddLeaves(amount))
+    });
+    let run = act.clone();
+    engine.register_fn("raid", move |spiders: i64| {
+        run(ScriptAction::Raid(spiders.clamp(0, MAX_RAID) as usize))
+    });
+    let run = act.clone();
+    engine.register_fn("rain", move |ticks: i64| {
+        run(ScriptAction::Rain(ticks.clamp(0, u32::MAX as i64) as u32))
+    });
+    let run = act.clone();
+    engine.register_fn("win", move |reason: &str| {
+        run(ScriptAction::End {
+            won: true,
+            reason: reason.to_string(),
+        })
+    });
+    engine.register_fn("lose", move |reason: &str| {
+        act(ScriptAction::End {
+            won: false,
+            reason: reason.to_string(),
+        })
+    });
+
+    engine
+}
+
+/// Compile every script in `scripts/`, warning about any that don't
+fn load_scripts() -> Scripts {
+    let actions = Arc::new(Mutex::new(Vec::new()));
+    let running = Arc::new(Mutex::new(String::new()));
+    let engine = engine(&actions, &running);
+    let mut list = Vec::new();
+
+    // There's no filesystem in a web build
+    let dir = Path::new(SCRIPTS_DIR);
+    let paths = if cfg!(target_arch = "wasm32") || !dir.is_dir() {
+        Vec::new()
+    } else {
+        let mut paths: Vec<_> = fs::read_dir(dir)
+            .into_iter()
+            .flatten()
+            .filter_map(|entry| entry.ok().map(|entry| entry.path()))
+            .filter(|path| path.extension().is_some_and(|ext| ext == "rhai"))
+            .collect();
+        paths.sort();
+        paths
+    };
+
+    for path in paths {
+        let compiled = fs::read_to_string(&path)
+            .map_err(|e| e.to_string())
+            .and_then(|text| engine.compile(&text).map_err(|e| e.to_string()));
+        let ast = match compiled {
+            Ok(ast) => ast,
+            Err(e) => {
+                warn!("Skipping script {}: {}", path.display(), e);
+                continue;
+            }
+        };
+        let hooks = ast
+            .iter_functions()
+            .map(|function| function.name.to_string())
+            .collect();
+        let name = path
+            .file_stem()
+            .map(|stem| stem.to_string_lossy().into_owned())
+            .unwrap_or_default();
+        info!("Loaded script {}", name);
+        list.push(Script {
+            name,
+            ast,
+            hooks,
+            state: Dynamic::from_map(Map::new()),
+            failed: false,
+        });
+    }
+
+    Scripts {
+        engine,
+        list,
+        actions,
+        running,
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// How the colony stands, for the scripts
+fn colony(
+    tick: &SimulationTick,
+    food_stores: &FoodStores,
+    fungus_garden: &FungusGarden,
+    caste_query: &Query<&Caste, With<Ant>>,
+) -> Map {
+    let mut castes = Map::new();
+    for caste in CASTES {
+        let count = caste_query.iter().filter(|c| **c == caste).count();
+        castes.insert(caste.name().into(), Dynamic::from(count as i64));
+    }
+
+    let mut colony = Map::new();
+    let mut set = |key: &str, value: Dynamic| {
+        colony.insert(key.into(), value);
+    };
+    set("tick", Dynamic::from(tick.0 as i64));
+    set("day", Dynamic::from(tick.day() as i64));
+    set("season", Dynamic::from(tick.season().name().to_string()));
+    set("food", Dynamic::from(food_stores.food as i64));
+    set("honeydew", Dynamic::from(food_stores.honeydew as i64));
+    set("seeds", Dynamic::from(food_stores.seeds as i64));
+    set("leaves", Dynamic::from(fungus_garden.leaves as i64));
+    set("harvested", Dynamic::from(fungus_garden.harvested as i64));
+    set(
+        "population",
+        Dynamic::from(caste_query.iter().count() as i64),
+    );
+    set(
+        "queen_alive",
+        Dynamic::from(caste_query.iter().any(|caste| *caste == Caste::Queen)),
+    );
+    set("castes", Dynamic::from_map(castes));
+    colony
+}
+
+/// Call each script's hooks for what's happened this tick
+fn run_script_hooks(
+    mut scripts: ResMut<Scripts>,
+    tick: Res<SimulationTick>,
+    food_stores: Res<FoodStores>,
+    fungus_garden: Res<FungusGarden>,
+    caste_query: Query<&Caste, With<Ant>>,
+    new_ant_query: Query<&Caste, Added<Ant>>,
+    new_migrant_query: Query<&Migrant, Added<Migrant>>,
+) {
+    if scripts.list.iter().all(|script| script.failed) {
+        return;
+    }
+    let colony = Dynamic::from_map(colony(&tick, &food_stores, &fungus_garden, &caste_query));
+
+    for caste in &new_ant_query {
+        scripts.call("on_ant_spawned", (colony.clone(), caste.name().to_string()));
+    }
+    let spiders = new_migrant_query
+        .iter()
+        .filter(|migrant| migrant.kind == MigrantKind::Spider)
+        .count();
+    if spiders > 0 {
+        scripts.call("on_raid", (colony.clone(), spiders as i64));
+    }
+    scripts.call("on_tick", (colony,));
+}
+
+/// Start every script's state afresh for a new run
+fn reset_scripts(mut scripts: ResMut<Scripts>) {
+    scripts.reset();
+}
+
+/// Carry out what the scripts asked for
+fn apply_script_actions(world: &mut World) {
+    let actions = {
+        let scripts = world.resource::<Scripts>();
+        let Ok(mut actions) = scripts.actions.lock() else {
+            return;
+        };
+        std::mem::take(&mut *actions)
+    };
+
+    for (script, action) in actions {
+        match action {
+            ScriptAction::Notify { text, warning } => {
+                world.write_message(if warning {
+                    Notification::warning(text)
+                } else {
+                    Notification::info(text)
+                });
+            }
+            ScriptAction::Spawn { caste, count } => {
+                let nest = world.resource::<NestLocation>().clone();
+                let mut commands = world.commands();
+                for _ in 0..count {
+                    spawn_ant(&mut commands, nest.x, nest.y, nest.z, caste, 0);
+                }
+            }
+            ScriptAction::AddFood(amount) => {
+                adjust(&mut world.resource_mut::<FoodStores>().food, amount);
+            }
+            ScriptAction::AddLeaves(amount) => {
+                adjust(&mut world.resource_mut::<FungusGarden>().leaves, amount);
+            }
+            ScriptAction::Raid(spiders) => {
+                let mut rng = world.resource_mut::<SimRng>();
+                let (x, y) = random_edge_tile(&mut *rng);
+                let target = random_interior_tile(&mut *rng);
+                let mut commands = world.commands();
+                for _ in 0..spiders {
+                    spawn_migrant(&mut commands, MigrantKind::Spider, x, y, target);
+                }
+            }
+            ScriptAction::Rain(ticks) => {
+                let mut weather = world.resource_mut::<Weather>();
+                weather.rain_ticks = weather.rain_ticks.max(ticks);
+            }
+            ScriptAction::End { won, reason } => end_game(world, &script, won, &reason),
+        }
+    }
+}
+
+/// Add a (possibly negative) amount to a store, keeping it in range
+fn adjust(store: &mut u32, amount: i64) {
+    *store = (*store as i64 + amount).clamp(0, u32::MAX as i64) as u32;
+}
+
+/// End the game as a script won or lost it, with a summary card
+fn end_game(world: &mut World, script: &str, won: bool, reason: &str) {
+    let mut state: SystemState<(ResMut<RunStats>, RunEnder)> = SystemState::new(world);
+    let (mut stats, mut ender) = state.get_mut(world);
+    if stats.collapsed {
+        return;
+    }
+    stats.collapsed = true;
+
+    let result = if won {
+        format!("Won: {}", reason)
+    } else {
+        format!("Lost: {}", reason)
+    };
+    ender.end_run(
+        &stats,
+        "script",
+        &format!("script: {}", script),
+        &result,
+        Notification::info(format!("{}: {}.", script, result)),
+    );
+    state.apply(world);
+}
+
+// ============================================================================
+// Console
+// ============================================================================
+
+fn list_scripts(world: &mut World, _args: &[&str]) -> Result<String, String> {
+    let scripts = world.resource::<Scripts>();
+    if scripts.list.is_empty() {
+        return Ok(format!("No scripts in {}/", SCRIPTS_DIR));
+    }
+    let lines: Vec<String> = scripts
+        .list
+        .iter()
+        .map(|script| {
+            let status = if script.failed { " (failed)" } else { "" };
+            format!("{}{}: {}", script.name, status, script.hooks.join(", "))
+        })
+        .collect();
+    Ok(lines.join("\n"))
+}
@@ -0,0 +1,165 @@
+//! Seasonal tree seeds: an emergency ration lying on the ground.
+//!
+//! Every autumn trees drop seeds beneath their canopies. Foragers gather them
+//! into the food stores when food runs low; a stored seed feeds an ant or
+//! larva directly, but is far less nourishing than fungus. Seeds nobody
+//! collects may sprout into saplings come spring, and otherwise rot away.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::atlas::AtlasSprite;
+use crate::low_spec::cosmetic_frame;
+use crate::rng::SimRng;
+use crate::sprites;
+use crate::time_controls::{Season, SimulationSet, SimulationTick, TICKS_PER_DAY};
+use crate::world::{
+    CurrentZLevel, MAX_TREES, SURFACE_LEVEL, Sapling, TILE_SIZE, TileKind, Tree, WORLD_SIZE,
+    WorldGrid, try_plant_sapling,
+};
+use crate::{ResetRun, despawn_all};
+
+pub struct SeedPlugin;
+
+impl Plugin for SeedPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Update, update_seed_sprites.run_if(cosmetic_frame))
+            .add_systems(
+                FixedUpdate,
+                (seed_drop, seed_sprouting).chain().in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, despawn_all::<SeedItem>);
+    }
+}
+
+/// Per-tick chance, in autumn, that a tree drops a seed
+const SEED_ITEM_DROP_CHANCE: f64 = 1.0 / 600.0;
+/// How far from the trunk dropped seeds land (under the canopy)
+const SEED_ITEM_RADIUS: i32 = 1;
+/// Seeds a single tree can have lying around it
+const MAX_SEEDS_PER_TREE: usize = 6;
+/// Per-tick chance, in spring, that a seed on the ground tries to sprout
+const SEED_SPROUT_CHANCE: f64 = 1.0 / 1200.0;
+/// Ticks an uncollected seed lasts before rotting
+const SEED_LIFETIME: u32 = 12 * TICKS_PER_DAY;
+
+/// Fraction of an ant's hunger a seed satisfies
+pub const SEED_NUTRITION: f32 = 0.5;
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// A seed lying on the surface, waiting to be gathered
+#[derive(Component)]
+pub struct SeedItem {
+    pub x: usize,
+    pub y: usize,
+    /// The tree it fell from
+    pub tree: Entity,
+    /// Ticks spent on the ground
+    pub age: u32,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Trees drop seeds beneath their canopies in autumn
+fn seed_drop(
+    mut commands: Commands,
+    tick: Res<SimulationTick>,
+    world_grid: Res<WorldGrid>,
+    tree_query: Query<(Entity, &Tree)>,
+    seed_query: Query<&SeedItem>,
+    mut rng: ResMut<SimRng>,
+) {
+    if tick.season() != Season::Autumn {
+        return;
+    }
+
+    for (entity, tree) in &tree_query {
+        if !rng.random_bool(SEED_ITEM_DROP_CHANCE) {
+            continue;
+        }
+        if seed_query.iter().filter(|seed| seed.tree == entity).count() >= MAX_SEEDS_PER_TREE {
+            continue;
+        }
+
+        let x = tree.x as i32 + rng.random_range(-SEED_ITEM_RADIUS..=SEED_ITEM_RADIUS);
+        let y = tree.y as i32 + rng.random_range(-SEED_ITEM_RADIUS..=SEED_ITEM_RADIUS);
+        let range = 0..WORLD_SIZE as i32;
+        if !range.contains(&x) || !range.contains(&y) {
+            continue;
+        }
+
+        let (x, y) = (x as usize, y as usize);
+        if world_grid.tiles[SURFACE_LEVEL][y][x] != TileKind::Surface {
+            continue;
+        }
+
+        commands.spawn((
+            SeedItem {
+                x,
+                y,
+                tree: entity,
+                age: 0,
+            },
+            Sprite {
+                color: sprites::objects::SEED,
+                custom_size: Some(Vec2::splat(sprites::objects::SEED_SIZE)),
+                ..default()
+            },
+            AtlasSprite(sprites::objects::SEED_INDEX),
+            Transform::from_xyz(
+                (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE,
+                (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE,
+                0.7,
+            ),
+        ));
+    }
+}
+
+/// Uncollected seeds sprout in spring if there's room, and rot with age
+fn seed_sprouting(
+    mut commands: Commands,
+    tick: Res<SimulationTick>,
+    mut world_grid: ResMut<WorldGrid>,
+    mut seed_query: Query<(Entity, &mut SeedItem)>,
+    tree_query: Query<(), With<Tree>>,
+    sapling_query: Query<(), With<Sapling>>,
+    mut rng: ResMut<SimRng>,
+) {
+    let spring = tick.season() == Season::Spring;
+    let mut population = tree_query.iter().count() + sapling_query.iter().count();
+
+    for (entity, mut seed) in &mut seed_query {
+        seed.age += 1;
+        if seed.age >= SEED_LIFETIME {
+            commands.entity(entity).despawn();
+            continue;
+        }
+
+        if spring && population < MAX_TREES && rng.random_bool(SEED_SPROUT_CHANCE) {
+            commands.entity(entity).despawn();
+            if try_plant_sapling(&mut commands, &mut world_grid, seed.x as i32, seed.y as i32) {
+                population += 1;
+            }
+        }
+    }
+}
+
+/// Seeds lie on the surface, so they're only shown on the surface level
+fn update_seed_sprites(
+    current_z: Res<CurrentZLevel>,
+    mut query: Query<&mut Visibility, With<SeedItem>>,
+) {
+    let visibility = if current_z.0 == SURFACE_LEVEL {
+        Visibility::Visible
+    } else {
+        Visibility::Hidden
+    };
+    for mut seed_visibility in &mut query {
+        *seed_visibility = visibility;
+    }
+}
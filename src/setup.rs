@@ -0,0 +1,436 @@
+//! New-game setup screen.
+//!
+//! The game opens paused on a preview of the generated surface so a bad start
+//! (say, no trees anywhere near the nest) can be rerolled before any time is
+//! invested in it. R or the Regenerate button replants the surface from a new
+//! seed, or type a seed's digits and press Enter to use that one. G switches
+//! biome, T and C step the number of trees and founding workers (Shift steps
+//! down), H cycles difficulty, and Q switches between an established nest and a
+//! lone queen founding one; the buttons do the same. Space or the Start
+//! button begins the simulation once loading finishes. A world loaded from a
+//! map file (see `maps.rs`) keeps its own nest, and generating a world
+//! instead leaves the map behind.
+//!
+//! The world size is shown but fixed: the grids are sized at compile time by
+//! `WORLD_SIZE`.
+
+use std::ops::RangeInclusive;
+
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::ants::{Ant, NestLocation, spawn_start};
+use crate::biome::Biome;
+use crate::content::Content;
+use crate::difficulty::Difficulty;
+use crate::rng::SimRng;
+use crate::summary::minimap;
+use crate::world::{
+    FoodStores, FungusGarden, MAX_TREES, Plant, Sapling, WORLD_SIZE, WorldGrid, WorldOptions,
+    clear_surface, dig_founding_chamber, fill_founding_chamber, generate_surface,
+    generate_underground,
+};
+
+pub struct SetupPlugin;
+
+impl Plugin for SetupPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<SeedEntry>()
+            .add_systems(OnEnter(GameState::Setup), enter_setup)
+            .add_systems(OnExit(GameState::Setup), exit_setup)
+            .add_systems(
+                Update,
+                (setup_input, update_preview)
+                    .chain()
+                    .run_if(in_state(GameState::Setup)),
+            );
+    }
+}
+
+/// Trees that can be asked for at generation
+const TREE_RANGE: RangeInclusive<usize> = 0..=MAX_TREES;
+/// Founding workers that can be asked for
+const WORKER_RANGE: RangeInclusive<usize> = 1..=12;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Digits of a seed being typed in
+#[derive(Resource, Default)]
+struct SeedEntry(String);
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the setup screen root
+#[derive(Component)]
+struct SetupScreen;
+
+/// Marker for the world settings line
+#[derive(Component)]
+struct SettingsText;
+
+/// Marker for the minimap preview text
+#[derive(Component)]
+struct PreviewText;
+
+/// Buttons on the setup screen
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum SetupButton {
+    Regenerate,
+    Biome,
+    Trees,
+    Workers,
+    Difficulty,
+    Founding,
+    Start,
+}
+
+impl SetupButton {
+    const ALL: [SetupButton; 7] = [
+        SetupButton::Regenerate,
+        SetupButton::Biome,
+        SetupButton::Trees,
+        SetupButton::Workers,
+        SetupButton::Difficulty,
+        SetupButton::Founding,
+        SetupButton::Start,
+    ];
+
+    fn label(&self) -> &'static str {
+        match self {
+            SetupButton::Regenerate => "Regenerate (R)",
+            SetupButton::Biome => "Biome (G)",
+            SetupButton::Trees => "Trees (T)",
+            SetupButton::Workers => "Workers (C)",
+            SetupButton::Difficulty => "Difficulty (H)",
+            SetupButton::Founding => "Founding (Q)",
+            SetupButton::Start => "Start (Space)",
+        }
+    }
+}
+
+/// Step a setting up or down within its range, wrapping at either end
+fn step(value: usize, up: bool, range: RangeInclusive<usize>) -> usize {
+    let (min, max) = (*range.start(), *range.end());
+    match (up, value) {
+        (true, v) if v >= max => min,
+        (true, v) => v + 1,
+        (false, v) if v <= min => max,
+        (false, v) => v - 1,
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Hold the simulation and show the preview
+fn enter_setup(mut commands: Commands, mut time: ResMut<Time<Virtual>>) {
+    time.pause();
+
+    commands
+        .spawn((
+            SetupScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(30.0),
+                top: Val::Percent(8.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("=== New colony ==="),
+                TextFont {
+                    font_size: 18.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent.spawn((
+                SettingsText,
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.8, 0.9, 0.8, 1.0)),
+            ));
+            parent.spawn((
+                PreviewText,
+                Text::new(""),
+                TextFont {
+                    font_size: 10.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
+            parent
+                .spawn(Node {
+                    column_gap: Val::Px(8.0),
+                    row_gap: Val::Px(8.0),
+                    flex_wrap: FlexWrap::Wrap,
+                    max_width: Val::Px(480.0),
+                    ..default()
+                })
+                .with_children(|row| {
+                    for button in SetupButton::ALL {
+                        row.spawn((
+                            button,
+                            Button,
+                            Node {
+                                padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                                ..default()
+                            },
+                            BackgroundColor(Color::srgb(0.25, 0.3, 0.25)),
+                        ))
+                        .with_child((
+                            Text::new(button.label()),
+                            TextFont {
+                                font_size: 14.0,
+                                ..default()
+                            },
+                            TextColor(Color::WHITE),
+                        ));
+                    }
+                });
+        });
+}
+
+/// Tear down the preview; the simulation starts once loading finishes
+fn exit_setup(mut commands: Commands, screen_query: Query<Entity, With<SetupScreen>>) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Trees and saplings, cleared away when the world is regenerated
+type PlantQuery<'w, 's> = Query<'w, 's, Entity, Or<(With<Plant>, With<Sapling>)>>;
+
+/// Apply setting changes, regenerate, or start the game from keys or buttons
+#[allow(clippy::too_many_arguments)]
+fn setup_input(
+    mut commands: Commands,
+    keyboard: Res<ButtonInput<KeyCode>>,
+    button_query: Query<(&Interaction, &SetupButton), Changed<Interaction>>,
+    plant_query: PlantQuery,
+    ant_query: Query<Entity, With<Ant>>,
+    // Paired to stay within Bevy's sixteen system parameters
+    (mut world_grid, content): (ResMut<WorldGrid>, Res<Content>),
+    mut fungus_garden: ResMut<FungusGarden>,
+    mut biome: ResMut<Biome>,
+    mut options: ResMut<WorldOptions>,
+    mut difficulty: ResMut<Difficulty>,
+    mut food_stores: ResMut<FoodStores>,
+    mut seed_entry: ResMut<SeedEntry>,
+    mut rng: ResMut<SimRng>,
+    mut nest: ResMut<NestLocation>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    let pressed = |target: SetupButton| {
+        button_query
+            .iter()
+            .any(|(interaction, button)| *interaction == Interaction::Pressed && *button == target)
+    };
+    let shift = keyboard.any_pressed([KeyCode::ShiftLeft, KeyCode::ShiftRight]);
+
+    // Typed seed
+    for key in keyboard.get_just_pressed() {
+        if let Some(digit) = digit(*key) {
+            seed_entry.0.push(digit);
+        }
+    }
+    if keyboard.just_pressed(KeyCode::Backspace) {
+        seed_entry.0.pop();
+    }
+    let mut typed_seed = None;
+    if keyboard.just_pressed(KeyCode::Enter) && !seed_entry.0.is_empty() {
+        match seed_entry.0.parse::<u64>() {
+            Ok(seed) => typed_seed = Some(seed),
+            Err(_) => warn!("'{}' is not a valid seed", seed_entry.0),
+        }
+        seed_entry.0.clear();
+    }
+
+    let change_biome = keyboard.just_pressed(KeyCode::KeyG) || pressed(SetupButton::Biome);
+    if change_biome {
+        *biome = biome.next();
+        // A new biome brings its own usual tree count
+        options.trees = None;
+        info!("Biome: {}", biome.name());
+    }
+
+    let change_trees = keyboard.just_pressed(KeyCode::KeyT) || pressed(SetupButton::Trees);
+    if change_trees {
+        let up = !(shift && keyboard.just_pressed(KeyCode::KeyT));
+        options.trees = Some(step(options.tree_count(*biome), up, TREE_RANGE));
+        info!("Trees: {}", options.tree_count(*biome));
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyC) || pressed(SetupButton::Workers) {
+        let up = !(shift && keyboard.just_pressed(KeyCode::KeyC));
+        options.starting_workers = step(options.starting_workers, up, WORKER_RANGE);
+        for entity in &ant_query {
+            commands.entity(entity).despawn();
+        }
+        spawn_start(&mut commands, &options, (nest.x, nest.y));
+    }
+
+    if keyboard.just_pressed(KeyCode::KeyQ) || pressed(SetupButton::Founding) {
+        options.founding = !options.founding;
+        for entity in &ant_query {
+            commands.entity(entity).despawn();
+        }
+        // A map's nest stays however it was drawn
+        if options.map.is_none() {
+            if options.founding {
+                fill_founding_chamber(&mut world_grid, &mut fungus_garden);
+            } else {
+                dig_founding_chamber(&mut world_grid, &mut fungus_garden);
+            }
+        }
+        spawn_start(&mut commands, &options, (nest.x, nest.y));
+        info!("Founding start: {}", options.founding);
+    }
+
+    let change_difficulty =
+        keyboard.just_pressed(KeyCode::KeyH) || pressed(SetupButton::Difficulty);
+    if change_difficulty {
+        *difficulty = difficulty.next();
+        info!("Difficulty: {}", difficulty.name());
+    }
+    if change_difficulty || keyboard.just_pressed(KeyCode::KeyQ) || pressed(SetupButton::Founding) {
+        // A founding queen starts with nothing in the stores
+        food_stores.food = if options.founding {
+            0
+        } else {
+            difficulty.starting_food()
+        };
+    }
+
+    let reroll = keyboard.just_pressed(KeyCode::KeyR) || pressed(SetupButton::Regenerate);
+    if change_biome || change_trees || reroll || typed_seed.is_some() {
+        // Replay generation from the start of a seed so the shown seed
+        // reproduces this exact map
+        let seed = match typed_seed {
+            Some(seed) => seed,
+            None if reroll => rand::random(),
+            None => rng.seed,
+        };
+        rng.reseed(seed);
+
+        for entity in &plant_query {
+            commands.entity(entity).despawn();
+        }
+        if options.map.take().is_some() {
+            // Leave the map behind entirely, nest and all
+            *world_grid = WorldGrid::default();
+            fungus_garden.plots.clear();
+            *nest = NestLocation::default();
+            if !options.founding {
+                dig_founding_chamber(&mut world_grid, &mut fungus_garden);
+            }
+            for entity in &ant_query {
+                commands.entity(entity).despawn();
+            }
+            spawn_start(&mut commands, &options, (nest.x, nest.y));
+        }
+        clear_surface(&mut world_grid);
+        generate_surface(
+            &mut commands,
+            &mut world_grid,
+            &mut *rng,
+            *biome,
+            &content,
+            options.tree_count(*biome),
+        );
+        generate_underground(&mut world_grid, &mut *rng, *biome);
+        info!("Regenerated the surface");
+    }
+
+    if keyboard.just_pressed(KeyCode::Space) || pressed(SetupButton::Start) {
+        next_state.set(GameState::Loading);
+    }
+}
+
+/// The digit a number-row or keypad key types
+fn digit(key: KeyCode) -> Option<char> {
+    let digit = match key {
+        KeyCode::Digit0 | KeyCode::Numpad0 => '0',
+        KeyCode::Digit1 | KeyCode::Numpad1 => '1',
+        KeyCode::Digit2 | KeyCode::Numpad2 => '2',
+        KeyCode::Digit3 | KeyCode::Numpad3 => '3',
+        KeyCode::Digit4 | KeyCode::Numpad4 => '4',
+        KeyCode::Digit5 | KeyCode::Numpad5 => '5',
+        KeyCode::Digit6 | KeyCode::Numpad6 => '6',
+        KeyCode::Digit7 | KeyCode::Numpad7 => '7',
+        KeyCode::Digit8 | KeyCode::Numpad8 => '8',
+        KeyCode::Digit9 | KeyCode::Numpad9 => '9',
+        _ => return None,
+    };
+    Some(digit)
+}
+
+/// Redraw the settings and minimap whenever the world or a setting changes
+#[allow(clippy::too_many_arguments)]
+fn update_preview(
+    world_grid: Res<WorldGrid>,
+    biome: Res<Biome>,
+    options: Res<WorldOptions>,
+    difficulty: Res<Difficulty>,
+    seed_entry: Res<SeedEntry>,
+    rng: Res<SimRng>,
+    mut settings_query: Query<&mut Text, (With<SettingsText>, Without<PreviewText>)>,
+    mut preview_query: Query<&mut Text, (With<PreviewText>, Without<SettingsText>)>,
+) {
+    let settings_changed = biome.is_changed()
+        || options.is_changed()
+        || difficulty.is_changed()
+        || seed_entry.is_changed();
+    if !world_grid.is_changed() && !settings_changed {
+        return;
+    }
+
+    let seed = if seed_entry.0.is_empty() {
+        rng.seed.to_string()
+    } else {
+        format!("{}_ (Enter to use)", seed_entry.0)
+    };
+    for mut text in &mut settings_query {
+        **text = format!(
+            "Seed: {} | Size: {}x{} (fixed) | Biome: {} | Water table: {} deep\nTrees: {} | Workers: {} | Difficulty: {} | Start: {}",
+            seed,
+            WORLD_SIZE,
+            WORLD_SIZE,
+            biome.name(),
+            options.water_table_depth,
+            options.tree_count(*biome),
+            options.starting_workers,
+            difficulty.name(),
+            if options.founding {
+                "founding queen"
+            } else {
+                "established nest"
+            }
+        );
+        if let Some(path) = &options.map {
+            text.push_str(&format!(
+                "\nMap: {} (R, G, T or a seed generates a world instead)",
+                path.display()
+            ));
+        }
+    }
+    if world_grid.is_changed() {
+        for mut text in &mut preview_query {
+            **text = minimap(&world_grid);
+        }
+    }
+}
@@ -0,0 +1,251 @@
+//! Long-run soak test for leaks and slow growth.
+//!
+//! `--soak <ticks>` skips the setup screen and runs the game for that many
+//! ticks, logging a sample once per in-game day: resident memory, the number
+//! of entities (in total and by kind), and the total of each pheromone field.
+//! Add `--headless` to run without a window, stepping one tick per frame as
+//! fast as the machine allows. At the end it prints how each measure grew
+//! over the run, so unbounded accumulation of items, corpses, or sprites
+//! shows up long before a week-long colony runs out of memory.
+
+use std::time::Duration;
+
+use bevy::prelude::*;
+use bevy::time::TimeUpdateStrategy;
+
+use crate::ants::Ant;
+use crate::aphids::AphidColony;
+use crate::brood::Brood;
+use crate::cli::CliArgs;
+use crate::migrations::Migrant;
+use crate::mushrooms::WildMushroom;
+use crate::pheromones::PheromoneGrids;
+use crate::rng::SimRng;
+use crate::seeds::SeedItem;
+use crate::summary::RunStats;
+use crate::time_controls::{BASE_TICKS_PER_SECOND, SimulationTick, TICKS_PER_DAY};
+use crate::world::Plant;
+
+pub struct SoakPlugin;
+
+impl Plugin for SoakPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_soak).add_systems(
+            Update,
+            (sample_soak, finish_soak)
+                .chain()
+                .run_if(resource_exists::<SoakMonitor>),
+        );
+    }
+}
+
+/// Ticks between samples
+const SAMPLE_INTERVAL: u64 = TICKS_PER_DAY as u64;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// One periodic measurement of the running game
+#[derive(Debug, Clone, Default)]
+struct SoakSample {
+    tick: u64,
+    /// Resident memory in kilobytes, where the platform reports it
+    rss_kb: Option<u64>,
+    entities: usize,
+    ants: usize,
+    brood: usize,
+    plants: usize,
+    items: usize,
+    creatures: usize,
+    /// Summed intensity of each pheromone: dig, forage, home, avoid
+    pheromones: [f32; 4],
+}
+
+/// Samples taken during a soak run
+#[derive(Resource)]
+pub struct SoakMonitor {
+    /// Tick the soak stops at
+    pub target: u64,
+    samples: Vec<SoakSample>,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Set up the soak when `--soak` was passed, stepping time one tick per frame
+/// when headless
+fn start_soak(mut commands: Commands, cli: Option<Res<CliArgs>>) {
+    let Some(cli) = cli else {
+        return;
+    };
+    let Some(target) = cli.soak else {
+        return;
+    };
+
+    commands.insert_resource(SoakMonitor {
+        target,
+        samples: Vec::new(),
+    });
+    if cli.headless {
+        commands.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs_f64(
+            1.0 / BASE_TICKS_PER_SECOND,
+        )));
+    }
+    info!("Soaking for {} ticks", target);
+}
+
+/// Seeds and mushrooms lying on the ground
+pub type ItemQuery<'w, 's> = Query<'w, 's, (), Or<(With<SeedItem>, With<WildMushroom>)>>;
+
+/// Creatures that visit or settle near the colony
+pub type CreatureQuery<'w, 's> = Query<'w, 's, (), Or<(With<Migrant>, With<AphidColony>)>>;
+
+/// Take a sample once per interval
+#[allow(clippy::too_many_arguments)]
+fn sample_soak(
+    tick: Res<SimulationTick>,
+    pheromones: Res<PheromoneGrids>,
+    mut monitor: ResMut<SoakMonitor>,
+    entity_query: Query<Entity>,
+    ant_query: Query<(), With<Ant>>,
+    brood_query: Query<(), With<Brood>>,
+    plant_query: Query<(), With<Plant>>,
+    item_query: ItemQuery,
+    creature_query: CreatureQuery,
+) {
+    let due = monitor
+        .samples
+        .last()
+        .is_none_or(|last| tick.0 >= last.tick + SAMPLE_INTERVAL);
+    if !due {
+        return;
+    }
+
+    let sample = SoakSample {
+        tick: tick.0,
+        rss_kb: resident_memory_kb(),
+        entities: entity_query.iter().count(),
+        ants: ant_query.iter().count(),
+        brood: brood_query.iter().count(),
+        plants: plant_query.iter().count(),
+        items: item_query.iter().count(),
+        creatures: creature_query.iter().count(),
+        pheromones: pheromones.totals(),
+    };
+
+    info!(
+        "Soak day {:.0}: memory {} | entities {} (ants {}, brood {}, plants {}, items {}, creatures {}) | pheromones dig {:.0} forage {:.0} home {:.0} avoid {:.0}",
+        sample.tick as f64 / TICKS_PER_DAY as f64,
+        format_memory(sample.rss_kb),
+        sample.entities,
+        sample.ants,
+        sample.brood,
+        sample.plants,
+        sample.items,
+        sample.creatures,
+        sample.pheromones[0],
+        sample.pheromones[1],
+        sample.pheromones[2],
+        sample.pheromones[3],
+    );
+    monitor.samples.push(sample);
+}
+
+/// Print the growth report and quit once the target tick is reached, or early
+/// if the colony collapses (which pauses the simulation)
+fn finish_soak(
+    tick: Res<SimulationTick>,
+    monitor: Res<SoakMonitor>,
+    stats: Res<RunStats>,
+    rng: Res<SimRng>,
+    mut exit: MessageWriter<AppExit>,
+) {
+    if tick.0 < monitor.target && !stats.collapsed {
+        return;
+    }
+
+    let mut report = soak_report(&monitor.samples);
+    report.push_str(&format!("Seed: {}\n", rng.seed));
+    if stats.collapsed {
+        report.push_str(&format!("Colony collapsed at tick {}\n", tick.0));
+    }
+    println!("{}", report);
+    exit.write(AppExit::Success);
+}
+
+/// Format how each measure changed between the first and last samples
+fn soak_report(samples: &[SoakSample]) -> String {
+    let (Some(first), Some(last)) = (samples.first(), samples.last()) else {
+        return String::from("=== ACRE soak: no samples taken ===\n");
+    };
+
+    let days = (last.tick - first.tick) as f64 / TICKS_PER_DAY as f64;
+    let mut report = format!(
+        "=== ACRE soak: {} ticks ({:.1} days), {} samples ===\n",
+        last.tick,
+        last.tick as f64 / TICKS_PER_DAY as f64,
+        samples.len()
+    );
+    report.push_str("Measure         first      last      peak   change/day\n");
+
+    let mut row = |name: &str, value: &dyn Fn(&SoakSample) -> f64| {
+        let peak = samples.iter().map(value).fold(f64::MIN, f64::max);
+        let change = (value(last) - value(first)) / days.max(f64::EPSILON);
+        report.push_str(&format!(
+            "{:<12} {:>9.0} {:>9.0} {:>9.0} {:>+12.1}\n",
+            name,
+            value(first),
+            value(last),
+            peak,
+            change
+        ));
+    };
+
+    if first.rss_kb.is_some() {
+        row("memory (KB)", &|s| s.rss_kb.unwrap_or_default() as f64);
+    }
+    row("entities", &|s| s.entities as f64);
+    row("ants", &|s| s.ants as f64);
+    row("brood", &|s| s.brood as f64);
+    row("plants", &|s| s.plants as f64);
+    row("items", &|s| s.items as f64);
+    row("creatures", &|s| s.creatures as f64);
+    row("dig", &|s| s.pheromones[0] as f64);
+    row("forage", &|s| s.pheromones[1] as f64);
+    row("home", &|s| s.pheromones[2] as f64);
+    row("avoid", &|s| s.pheromones[3] as f64);
+
+    // Entities that aren't ants, brood, or anything else counted but still
+    // climb steadily are the usual sign of a leak
+    let counted = |s: &SoakSample| s.ants + s.brood + s.plants + s.items + s.creatures;
+    let other_growth = (last.entities as i64 - counted(last) as i64)
+        - (first.entities as i64 - counted(first) as i64);
+    if other_growth > 0 {
+        report.push_str(&format!(
+            "\nWarning: {} uncategorized entities accumulated over the run\n",
+            other_growth
+        ));
+    }
+
+    report
+}
+
+/// Resident memory of this process in kilobytes (Linux only)
+pub fn resident_memory_kb() -> Option<u64> {
+    let status = std::fs::read_to_string("/proc/self/status").ok()?;
+    status
+        .lines()
+        .find_map(|line| line.strip_prefix("VmRSS:"))
+        .and_then(|value| value.split_whitespace().next())
+        .and_then(|kb| kb.parse().ok())
+}
+
+/// Memory for the log line
+fn format_memory(rss_kb: Option<u64>) -> String {
+    match rss_kb {
+        Some(kb) => format!("{:.1} MB", kb as f64 / 1024.0),
+        None => String::from("n/a"),
+    }
+}
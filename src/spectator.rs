@@ -0,0 +1,349 @@
+//! Live spectating over WebSocket.
+//!
+//! `--spectate <port>` (in builds with the `spectator` feature) serves the
+//! colony to remote viewers, so a web dashboard or another machine can watch a
+//! run, headless soaks included, without rendering it locally. Viewers connect
+//! to `ws://<host>:<port>` and receive binary messages, each a zlib-compressed
+//! JSON object:
+//!
+//! - `world`: the tile grid, as `[kind, count]` runs through it in z, y, x
+//!   order. Sent when a viewer joins and whenever the tiles change, at most
+//!   once a second.
+//! - `state`: the tick, the stores, and every ant and plant. Sent up to ten
+//!   times a second while the simulation is running, and when a viewer joins.
+//!
+//! Messages are compressed and sent on background threads, one per viewer, so
+//! a slow viewer never holds the game or the other viewers up; once they fall
+//! a few messages behind they're dropped instead. If the server itself falls
+//! behind, the game skips `state` messages until it catches up.
+
+use std::io::Write;
+use std::net::{TcpListener, TcpStream};
+use std::sync::mpsc::{self, Receiver, RecvTimeoutError, Sender, SyncSender, TrySendError};
+use std::thread;
+use std::time::Duration;
+
+use bevy::prelude::*;
+use flate2::Compression;
+use flate2::write::ZlibEncoder;
+use tungstenite::{Bytes, Message, WebSocket};
+
+use crate::ants::{Ant, Caste, GridPosition, Task};
+use crate::cli::CliArgs;
+use crate::time_controls::SimulationTick;
+use crate::world::{FoodStores, FungusGarden, Plant, TileKind, WORLD_SIZE, WorldGrid};
+
+pub struct SpectatorPlugin;
+
+impl Plugin for SpectatorPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(Startup, start_server).add_systems(
+            Update,
+            broadcast_colony.run_if(resource_exists::<SpectatorServer>),
+        );
+    }
+}
+
+/// Seconds between `state` messages
+const STATE_INTERVAL: f64 = 0.1;
+/// Seconds between checks for changed tiles
+const WORLD_INTERVAL: f64 = 1.0;
+/// How often viewers who've just joined are looked for
+const JOIN_POLL: Duration = Duration::from_millis(100);
+/// How long a viewer may take to accept a message before it's dropped
+const WRITE_TIMEOUT: Duration = Duration::from_secs(1);
+/// How long a viewer may take over the WebSocket handshake
+const HANDSHAKE_TIMEOUT: Duration = Duration::from_secs(5);
+/// Messages the game can get ahead of the server before state is skipped
+const FRAME_QUEUE: usize = 8;
+/// Messages a viewer can fall behind before they're dropped
+const VIEWER_QUEUE: usize = 4;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// The running spectator server, fed messages for its viewers
+#[derive(Resource)]
+struct SpectatorServer {
+    frames: SyncSender<Frame>,
+    /// Real time the last `state` message was sent at
+    last_state: f64,
+    /// Real time the tiles were last checked at
+    last_world: f64,
+    /// Tick the last `state` message was for
+    last_tick: Option<u64>,
+    /// The tiles as last sent, to tell when they've changed
+    last_runs: Vec<(TileKind, usize)>,
+}
+
+/// A message for every viewer
+struct Frame {
+    json: String,
+    /// Whether this is the tile grid, which viewers who join later need too
+    world: bool,
+}
+
+// ============================================================================
+// Server
+// ============================================================================
+
+/// Accept viewers as they connect, giving each its own thread for the
+/// WebSocket handshake so one that never finishes it can't hold up the rest
+fn accept_viewers(listener: TcpListener, joined: Sender<WebSocket<TcpStream>>) {
+    for stream in listener.incoming() {
+        let stream = match stream {
+            Ok(stream) => stream,
+            Err(e) => {
+                warn!("Spectator failed to connect: {}", e);
+                continue;
+            }
+        };
+        let joined = joined.clone();
+        thread::spawn(move || handshake(stream, joined));
+    }
+}
+
+/// Complete a viewer's handshake and pass them on, giving up on one that
+/// stalls
+fn handshake(stream: TcpStream, joined: Sender<WebSocket<TcpStream>>) {
+    let address = stream
+        .peer_addr()
+        .map_or_else(|_| String::from("unknown"), |address| address.to_string());
+    if let Err(e) = stream
+        .set_read_timeout(Some(HANDSHAKE_TIMEOUT))
+        .and_then(|_| stream.set_write_timeout(Some(WRITE_TIMEOUT)))
+    {
+        warn!("Couldn't set up spectator {}: {}", address, e);
+        return;
+    }
+    match tungstenite::accept(stream) {
+        Ok(socket) => {
+            info!("Spectator {} is watching", address);
+            // Fails only once the server has stopped
+            let _ = joined.send(socket);
+        }
+        Err(e) => warn!("Spectator {} failed the handshake: {}", address, e),
+    }
+}
+
+/// Compress each message and queue it for every viewer, and catch new viewers
+/// up with the latest tiles and state as they join. A viewer whose queue is
+/// full has fallen too far behind and is dropped, so the others never wait
+/// on them
+fn broadcast_frames(frames: Receiver<Frame>, joined: Receiver<WebSocket<TcpStream>>) {
+    let mut viewers: Vec<SyncSender<Bytes>> = Vec::new();
+    let mut world: Option<Bytes> = None;
+    let mut state: Option<Bytes> = None;
+
+    loop {
+        match frames.recv_timeout(JOIN_POLL) {
+            Ok(frame) => match compress(&frame.json) {
+                Ok(bytes) => {
+                    let bytes = Bytes::from(bytes);
+                    viewers.retain(|viewer| match viewer.try_send(bytes.clone()) {
+                        Ok(()) => true,
+                        Err(TrySendError::Full(_)) => {
+                            info!("Dropped a spectator who fell behind");
+                            false
+                        }
+                        Err(TrySendError::Disconnected(_)) => false,
+                    });
+                    if frame.world {
+                        world = Some(bytes);
+                    } else {
+                        state = Some(bytes);
+                    }
+                }
+                Err(e) => warn!("Failed to compress a spectator message: {}", e),
+            },
+            Err(RecvTimeoutError::Timeout) => {}
+            Err(RecvTimeoutError::Disconnected) => return,
+        }
+
+        // Even while the game is paused, a new viewer sees the colony
+        for socket in joined.try_iter() {
+            let (viewer, queue) = mpsc::sync_channel(VIEWER_QUEUE);
+            for bytes in [&world, &state].into_iter().flatten() {
+                let _ = viewer.try_send(bytes.clone());
+            }
+            thread::spawn(move || serve_viewer(socket, queue));
+            viewers.push(viewer);
+        }
+    }
+}
+
+/// Send a viewer their queued messages until they leave or are dropped
+fn serve_viewer(mut socket: WebSocket<TcpStream>, queue: Receiver<Bytes>) {
+    for bytes in queue {
+        if let Err(e) = socket.send(Message::binary(bytes)) {
+            info!("Spectator left: {}", e);
+            return;
+        }
+    }
+}
+
+fn compress(json: &str) -> std::io::Result<Vec<u8>> {
+    let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+    encoder.write_all(json.as_bytes())?;
+    encoder.finish()
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start serving viewers when `--spectate` was passed
+fn start_server(mut commands: Commands, cli: Option<Res<CliArgs>>) {
+    let Some(port) = cli.and_then(|cli| cli.spectate) else {
+        return;
+    };
+
+    // There are no sockets to listen on in a web build
+    if cfg!(target_arch = "wasm32") {
+        warn!("Spectating can't be served from a web build");
+        return;
+    }
+
+    let listener = match TcpListener::bind(("0.0.0.0", port)) {
+        Ok(listener) => listener,
+        Err(e) => {
+            error!(
+                "Failed to start the spectator server on port {}: {}",
+                port, e
+            );
+            return;
+        }
+    };
+    let (frames, frame_receiver) = mpsc::sync_channel(FRAME_QUEUE);
+    let (joined, joined_receiver) = mpsc::channel();
+    thread::spawn(move || accept_viewers(listener, joined));
+    thread::spawn(move || broadcast_frames(frame_receiver, joined_receiver));
+
+    info!("Serving spectators on ws://0.0.0.0:{}", port);
+    commands.insert_resource(SpectatorServer {
+        frames,
+        last_state: f64::NEG_INFINITY,
+        last_world: f64::NEG_INFINITY,
+        last_tick: None,
+        last_runs: Vec::new(),
+    });
+}
+
+/// Send viewers the tiles when they've changed, and the colony's state as it
+/// runs
+#[allow(clippy::too_many_arguments)]
+fn broadcast_colony(
+    time: Res<Time<Real>>,
+    mut server: ResMut<SpectatorServer>,
+    tick: Res<SimulationTick>,
+    world_grid: Res<WorldGrid>,
+    food_stores: Res<FoodStores>,
+    fungus_garden: Res<FungusGarden>,
+    ant_query: Query<(&GridPosition, &Caste, &Task), With<Ant>>,
+    plant_query: Query<&Plant>,
+) {
+    let now = time.elapsed_secs_f64();
+    let mut frames = Vec::new();
+
+    if now - server.last_world >= WORLD_INTERVAL {
+        server.last_world = now;
+        let runs = tile_runs(&world_grid);
+        if runs != server.last_runs {
+            frames.push(Frame {
+                json: world_json(tick.0, &runs),
+                world: true,
+            });
+            server.last_runs = runs;
+        }
+    }
+
+    if now - server.last_state >= STATE_INTERVAL && server.last_tick != Some(tick.0) {
+        server.last_state = now;
+        server.last_tick = Some(tick.0);
+
+        let ants: Vec<String> = ant_query
+            .iter()
+            .map(|(pos, caste, task)| {
+                format!(
+                    "{{\"x\":{},\"y\":{},\"z\":{},\"caste\":\"{}\",\"task\":\"{}\"}}",
+                    pos.x,
+                    pos.y,
+                    pos.z,
+                    caste.name(),
+                    task.name()
+                )
+            })
+            .collect();
+        let plants: Vec<String> = plant_query
+            .iter()
+            .map(|plant| {
+                format!(
+                    "{{\"x\":{},\"y\":{},\"kind\":\"{}\"}}",
+                    plant.x,
+                    plant.y,
+                    plant.kind.name()
+                )
+            })
+            .collect();
+        frames.push(Frame {
+            json: format!(
+                "{{\"type\":\"state\",\"tick\":{},\"food\":{},\"honeydew\":{},\"seeds\":{},\"leaves\":{},\"ants\":[{}],\"plants\":[{}]}}",
+                tick.0,
+                food_stores.food,
+                food_stores.honeydew,
+                food_stores.seeds,
+                fungus_garden.leaves,
+                ants.join(","),
+                plants.join(",")
+            ),
+            world: false,
+        });
+    }
+
+    for frame in frames {
+        match server.frames.try_send(frame) {
+            Ok(()) => {}
+            // The server is behind, so this state is skipped for a newer one;
+            // the tiles are sent again at the next check
+            Err(TrySendError::Full(frame)) => {
+                if frame.world {
+                    server.last_runs.clear();
+                }
+            }
+            Err(TrySendError::Disconnected(_)) => {
+                warn!("The spectator server has stopped");
+                return;
+            }
+        }
+    }
+}
+
+/// The tile grid as runs of one kind, in z, y, x order
+fn tile_runs(world_grid: &WorldGrid) -> Vec<(TileKind, usize)> {
+    let mut runs: Vec<(TileKind, usize)> = Vec::new();
+    for z in 0..WORLD_SIZE {
+        for y in 0..WORLD_SIZE {
+            for &tile in &world_grid.tiles[z][y] {
+                match runs.last_mut() {
+                    Some((kind, count)) if *kind == tile => *count += 1,
+                    _ => runs.push((tile, 1)),
+                }
+            }
+        }
+    }
+    runs
+}
+
+fn world_json(tick: u64, runs: &[(TileKind, usize)]) -> String {
+    let runs: Vec<String> = runs
+        .iter()
+        .map(|(kind, count)| format!("[\"{}\",{}]", kind.name(), count))
+        .collect();
+    format!(
+        "{{\"type\":\"world\",\"tick\":{},\"size\":{},\"tiles\":[{}]}}",
+        tick,
+        WORLD_SIZE,
+        runs.join(",")
+    )
+}
@@ -1,27 +1,106 @@
-//! Placeholder sprite colors and definitions.
+//! Sprite colors, sizes and atlas cells.
 //!
-//! This module centralizes all visual definitions so they can easily be
-//! swapped out for real sprite assets later.
+//! This module centralizes all visual definitions. Every sprite has a color
+//! and a size; tiles, ants, brood and items also have a cell in the sprite
+//! atlas (see `atlas.rs`), which is drawn tinted by the color when the atlas is
+//! available and left out when it isn't.
 
 #![allow(dead_code)]
 
 use bevy::prelude::*;
 
+/// Color palette for the colors that tell things apart: castes and pheromone
+/// types. Everything else keeps its natural color.
+#[derive(Resource, Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Palette {
+    #[default]
+    Standard,
+    /// For red-green color blindness with weak green cones
+    Deuteranopia,
+    /// For red-green color blindness with weak red cones
+    Protanopia,
+    /// For blue-yellow color blindness
+    Tritanopia,
+}
+
+impl Palette {
+    pub const ALL: [Palette; 4] = [
+        Palette::Standard,
+        Palette::Deuteranopia,
+        Palette::Protanopia,
+        Palette::Tritanopia,
+    ];
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Palette::Standard => "Standard",
+            Palette::Deuteranopia => "Deuteranopia",
+            Palette::Protanopia => "Protanopia",
+            Palette::Tritanopia => "Tritanopia",
+        }
+    }
+
+    pub fn from_name(name: &str) -> Option<Self> {
+        Self::ALL
+            .into_iter()
+            .find(|palette| palette.name().eq_ignore_ascii_case(name))
+    }
+
+    /// The next palette in the list, wrapping around
+    pub fn next(&self) -> Self {
+        match self {
+            Palette::Standard => Palette::Deuteranopia,
+            Palette::Deuteranopia => Palette::Protanopia,
+            Palette::Protanopia => Palette::Tritanopia,
+            Palette::Tritanopia => Palette::Standard,
+        }
+    }
+}
+
+/// Layout of the sprite atlas image
+pub mod atlas {
+    /// Path of the atlas image under `assets/`
+    pub const PATH: &str = "sprites/atlas.png";
+    /// Width and height of each cell, in pixels
+    pub const CELL_SIZE: u32 = 16;
+    pub const COLUMNS: u32 = 16;
+    /// Row 0 tiles, row 1 soils and unexplored ground, row 2 brood, row 3
+    /// items, then a row per ant caste
+    pub const ROWS: u32 = 10;
+}
+
 /// Tile colors (16x16 tiles)
 pub mod tiles {
     use super::*;
 
-    pub const AIR: Color = Color::srgb(0.529, 0.808, 0.922); // Sky blue
+    // The ground before a biome tints it; every other tile kind's color is
+    // in assets/content.ron
     pub const SURFACE: Color = Color::srgb(0.133, 0.545, 0.133); // Forest green
     pub const DIRT: Color = Color::srgb(0.545, 0.271, 0.075); // Saddle brown
-    pub const TUNNEL: Color = Color::srgb(0.3, 0.3, 0.3); // Dark gray
-    pub const CHAMBER: Color = Color::srgb(0.4, 0.35, 0.3); // Tan
-    pub const FUNGUS_GARDEN: Color = Color::srgb(0.35, 0.35, 0.3); // Gray with hint of green
-    pub const TREE_TRUNK: Color = Color::srgb(0.4, 0.26, 0.13); // Dark brown bark
-    pub const TREE_CANOPY: Color = Color::srgb(0.18, 0.42, 0.18); // Dark green leaves
+
+    // Soils and ground not yet explored
+    pub const CLAY: Color = Color::srgb(0.62, 0.33, 0.2); // Brick red-brown
+    pub const SAND: Color = Color::srgb(0.78, 0.64, 0.4); // Pale buff
+    pub const WATER: Color = Color::srgb(0.2, 0.45, 0.8); // Murky blue
+    pub const CRACK: Color = Color::srgb(0.12, 0.08, 0.05); // Near-black fissure
+    pub const UNEXPLORED: Color = Color::srgb(0.08, 0.07, 0.07); // Unlit earth
+
+    // Biome ground palettes (the forest uses SURFACE and DIRT)
+    pub const GRASSLAND_SURFACE: Color = Color::srgb(0.42, 0.62, 0.2); // Sunlit meadow
+    pub const GRASSLAND_DIRT: Color = Color::srgb(0.45, 0.3, 0.15); // Loam
+    pub const DESERT_SURFACE: Color = Color::srgb(0.86, 0.76, 0.5); // Sand
+    pub const DESERT_DIRT: Color = Color::srgb(0.72, 0.52, 0.3); // Sandy clay
+
+    // Atlas cells. Row 0 has one per tile kind, placed by assets/content.ron;
+    // loam is drawn as plain dirt
+    pub const DIRT_INDEX: usize = 3;
+    // Row 1: dug-through soils and ground not yet explored
+    pub const UNEXPLORED_INDEX: usize = 16;
+    pub const CLAY_INDEX: usize = 17;
+    pub const SAND_INDEX: usize = 18;
 }
 
-/// Ant colors and sizes
+/// Ant colors
 pub mod ants {
     use super::*;
 
@@ -29,13 +108,91 @@ pub mod ants {
     pub const QUEEN: Color = Color::srgb(0.15, 0.1, 0.05); // Very dark brown
     pub const FORAGER: Color = Color::srgb(0.6, 0.3, 0.15); // Reddish brown
     pub const GARDENER: Color = Color::srgb(0.5, 0.35, 0.2); // Light brown
+    pub const NURSE: Color = Color::srgb(0.62, 0.5, 0.35); // Pale tan
     pub const SOLDIER: Color = Color::srgb(0.25, 0.15, 0.08); // Dark brown
+    pub const ALATE: Color = Color::srgb(0.55, 0.4, 0.1); // Amber, with wings
+
+    /// Queen, forager, gardener, nurse, soldier and alate colors in a palette.
+    /// The colorblind palettes trade the browns for hues and shades that stay
+    /// apart (after Okabe and Ito).
+    pub fn colors(palette: Palette) -> [Color; 6] {
+        match palette {
+            Palette::Standard => [QUEEN, FORAGER, GARDENER, NURSE, SOLDIER, ALATE],
+            Palette::Deuteranopia => [
+                Color::srgb(0.1, 0.1, 0.1),   // Black
+                Color::srgb(0.9, 0.6, 0.0),   // Orange
+                Color::srgb(0.35, 0.7, 0.9),  // Sky blue
+                Color::srgb(0.95, 0.9, 0.55), // Pale yellow
+                Color::srgb(0.0, 0.3, 0.6),   // Dark blue
+                Color::srgb(0.8, 0.6, 0.7),   // Reddish purple
+            ],
+            // Reds look dark without red cones, so lean on yellows and blues
+            Palette::Protanopia => [
+                Color::srgb(0.1, 0.1, 0.1),    // Black
+                Color::srgb(0.95, 0.75, 0.1),  // Golden yellow
+                Color::srgb(0.35, 0.7, 0.9),   // Sky blue
+                Color::srgb(0.9, 0.9, 0.8),    // Off-white
+                Color::srgb(0.0, 0.3, 0.6),    // Dark blue
+                Color::srgb(0.55, 0.55, 0.55), // Mid gray
+            ],
+            Palette::Tritanopia => [
+                Color::srgb(0.1, 0.1, 0.1),   // Black
+                Color::srgb(0.85, 0.25, 0.2), // Red
+                Color::srgb(0.1, 0.6, 0.6),   // Teal
+                Color::srgb(0.95, 0.75, 0.8), // Light pink
+                Color::srgb(0.45, 0.1, 0.1),  // Dark red
+                Color::srgb(0.6, 0.6, 0.6),   // Gray
+            ],
+        }
+    }
+
+    // Atlas cells: each caste has its own row, facing east, starting with
+    // the standing pose and followed by its animation frames
+    pub const QUEEN_INDEX: usize = 64;
+    pub const FORAGER_INDEX: usize = 80;
+    pub const GARDENER_INDEX: usize = 96;
+    pub const NURSE_INDEX: usize = 112;
+    pub const SOLDIER_INDEX: usize = 128;
+    pub const ALATE_INDEX: usize = 144;
+
+    // Animation frames, as (first column, frame count) along a caste's row
+    pub const WALK_FRAMES: (usize, usize) = (1, 4);
+    pub const WORK_FRAMES: (usize, usize) = (5, 2); // Digging, cutting, scrubbing
+    pub const CARRY_FRAMES: (usize, usize) = (7, 4); // Walking with a load held up
+    pub const HOLD_FRAME: usize = 11; // Standing with a load held up
+
+    // Seconds each frame shows for
+    pub const WALK_FRAME_SECS: f32 = 0.12;
+    pub const WORK_FRAME_SECS: f32 = 0.25;
+
+    // Newly hatched (callow) ants are paler and smaller until their cuticle hardens
+    pub const CALLOW_LIGHTEN: f32 = 0.5; // Fraction blended toward white at hatching
+    pub const CALLOW_SCALE: f32 = 0.8; // Size multiplier at hatching
 
-    // Sizes (in pixels)
-    pub const QUEEN_SIZE: f32 = 12.0;
-    pub const FORAGER_SIZE: f32 = 8.0;
-    pub const GARDENER_SIZE: f32 = 6.0;
-    pub const SOLDIER_SIZE: f32 = 10.0;
+    // Ants are drawn longer than they are wide so their heading shows
+    pub const BODY_ASPECT: f32 = 0.55; // Width as a fraction of length
+
+    /// Sprite size for an ant of the given length, lying along the x axis
+    pub fn body_size(length: f32) -> Vec2 {
+        Vec2::new(length, length * BODY_ASPECT)
+    }
+
+    /// Blend a caste color toward its pale callow shade. `maturity` runs from
+    /// 0.0 (just hatched) to 1.0 (fully darkened adult).
+    pub fn callow_color(adult: Color, maturity: f32) -> Color {
+        let adult = adult.to_srgba();
+        let lighten = CALLOW_LIGHTEN * (1.0 - maturity.clamp(0.0, 1.0));
+        Color::srgb(
+            adult.red + (1.0 - adult.red) * lighten,
+            adult.green + (1.0 - adult.green) * lighten,
+            adult.blue + (1.0 - adult.blue) * lighten,
+        )
+    }
+
+    /// Size multiplier for an ant at the given maturity (0.0 - 1.0)
+    pub fn callow_scale(maturity: f32) -> f32 {
+        CALLOW_SCALE + (1.0 - CALLOW_SCALE) * maturity.clamp(0.0, 1.0)
+    }
 }
 
 /// Egg/larva/pupa colors and sizes
@@ -49,6 +206,11 @@ pub mod brood {
     pub const EGG_SIZE: f32 = 4.0;
     pub const LARVA_SIZE: f32 = 5.0;
     pub const PUPA_SIZE: f32 = 6.0;
+
+    // Atlas cells along row 2
+    pub const EGG_INDEX: usize = 32;
+    pub const LARVA_INDEX: usize = 33;
+    pub const PUPA_INDEX: usize = 34;
 }
 
 /// Resource/object colors and sizes
@@ -58,10 +220,48 @@ pub mod objects {
     pub const LEAF_FRAGMENT: Color = Color::srgb(0.3, 0.7, 0.2); // Bright green
     pub const MULCH: Color = Color::srgb(0.25, 0.35, 0.15); // Dark green-brown
     pub const FUNGUS: Color = Color::srgb(0.9, 0.85, 0.7); // Pale yellow-white
+    pub const APHID: Color = Color::srgb(0.6, 0.85, 0.4); // Soft lime green
+    pub const LADYBUG: Color = Color::srgb(0.85, 0.1, 0.1); // Bright red
+    pub const HONEYDEW: Color = Color::srgb(0.95, 0.8, 0.4); // Amber droplet
+    pub const SEED: Color = Color::srgb(0.55, 0.4, 0.2); // Nut brown
+    pub const MUSHROOM: Color = Color::srgb(0.85, 0.75, 0.6); // Buff cap
+    pub const BEETLE: Color = Color::srgb(0.2, 0.45, 0.35); // Iridescent teal
+    pub const SPIDER: Color = Color::srgb(0.25, 0.2, 0.18); // Dusky brown-black
 
     pub const LEAF_SIZE: f32 = 6.0;
     pub const MULCH_SIZE: f32 = 8.0;
     pub const FUNGUS_SIZE: f32 = 6.0;
+    pub const APHID_SIZE: f32 = 5.0;
+    pub const LADYBUG_SIZE: f32 = 6.0;
+    pub const SEED_SIZE: f32 = 4.0;
+    pub const MUSHROOM_SIZE: f32 = 7.0;
+    pub const BEETLE_SIZE: f32 = 7.0;
+    pub const SPIDER_SIZE: f32 = 10.0;
+
+    // Atlas cells along row 3
+    pub const LEAF_INDEX: usize = 48;
+    pub const MULCH_INDEX: usize = 49;
+    pub const FUNGUS_INDEX: usize = 50;
+    pub const APHID_INDEX: usize = 51;
+    pub const LADYBUG_INDEX: usize = 52;
+    pub const HONEYDEW_INDEX: usize = 53;
+    pub const SEED_INDEX: usize = 54;
+    pub const MUSHROOM_INDEX: usize = 55;
+    pub const BEETLE_INDEX: usize = 56;
+    pub const SPIDER_INDEX: usize = 57;
+}
+
+/// Particle colors and sizes
+pub mod particles {
+    use super::*;
+
+    pub const DIRT: Color = Color::srgba(0.5, 0.35, 0.2, 0.9); // Dusty brown
+    pub const RAIN: Color = Color::srgba(0.7, 0.8, 0.95, 0.5); // Pale blue, 50% opacity
+    pub const HIT: Color = Color::srgba(1.0, 0.95, 0.8, 0.9); // Hot white
+
+    pub const DIRT_SIZE: f32 = 3.0;
+    pub const RAIN_SIZE: Vec2 = Vec2::new(1.0, 7.0); // Thin streak
+    pub const HIT_SIZE: f32 = 6.0;
 }
 
 /// Pheromone overlay colors (semi-transparent)
@@ -72,6 +272,64 @@ pub mod pheromones {
     pub const FORAGE: Color = Color::srgba(0.2, 0.8, 0.2, 0.4); // Green, 40% opacity
     pub const HOME: Color = Color::srgba(0.4, 0.3, 0.8, 0.4); // Purple-blue, 40% opacity
     pub const AVOID: Color = Color::srgba(0.8, 0.2, 0.2, 0.4); // Red, 40% opacity
+
+    /// Dig, forage, home and avoid colors in a palette, all at 40% opacity
+    pub fn colors(palette: Palette) -> [Color; 4] {
+        match palette {
+            Palette::Standard => [DIG, FORAGE, HOME, AVOID],
+            Palette::Deuteranopia => [
+                Color::srgba(0.9, 0.6, 0.0, 0.4),   // Orange
+                Color::srgba(0.35, 0.7, 0.9, 0.4),  // Sky blue
+                Color::srgba(0.0, 0.3, 0.6, 0.4),   // Dark blue
+                Color::srgba(0.95, 0.9, 0.25, 0.4), // Yellow
+            ],
+            Palette::Protanopia => [
+                Color::srgba(0.95, 0.7, 0.1, 0.4),  // Golden yellow
+                Color::srgba(0.35, 0.7, 0.9, 0.4),  // Sky blue
+                Color::srgba(0.0, 0.3, 0.6, 0.4),   // Dark blue
+                Color::srgba(0.95, 0.95, 0.9, 0.4), // White
+            ],
+            Palette::Tritanopia => [
+                Color::srgba(0.85, 0.25, 0.2, 0.4), // Red
+                Color::srgba(0.1, 0.65, 0.65, 0.4), // Teal
+                Color::srgba(0.3, 0.3, 0.3, 0.4),   // Dark gray
+                Color::srgba(0.95, 0.75, 0.8, 0.4), // Light pink
+            ],
+        }
+    }
+}
+
+/// Air quality overlay colors (semi-transparent)
+pub mod air {
+    use super::*;
+
+    pub const FRESH: Color = Color::srgba(0.3, 0.7, 0.9, 0.0); // Clear blue, invisible
+    pub const STALE: Color = Color::srgba(0.6, 0.55, 0.1, 0.6); // Murky olive, 60% opacity
+}
+
+/// Soil moisture overlay colors (semi-transparent)
+pub mod moisture {
+    use super::*;
+
+    pub const DRY: Color = Color::srgba(0.9, 0.75, 0.4, 0.3); // Parched tan, 30% opacity
+    pub const WET: Color = Color::srgba(0.1, 0.35, 0.9, 0.6); // Deep blue, 60% opacity
+}
+
+/// Cave-in risk overlay colors
+pub mod cave_ins {
+    use super::*;
+
+    pub const STRAINED: Color = Color::srgba(1.0, 0.6, 0.1, 0.35); // Amber, 35% opacity
+    pub const FAILING: Color = Color::srgba(0.9, 0.1, 0.1, 0.6); // Red, 60% opacity
+}
+
+/// Designation overlay colors (semi-transparent)
+pub mod designations {
+    use super::*;
+
+    pub const TUNNEL: Color = Color::srgba(0.9, 0.9, 0.3, 0.5); // Yellow, 50% opacity
+    pub const CHAMBER: Color = Color::srgba(0.3, 0.8, 0.9, 0.5); // Cyan, 50% opacity
+    pub const RAMP: Color = Color::srgba(0.9, 0.5, 0.9, 0.5); // Magenta, 50% opacity
 }
 
 /// UI colors
@@ -81,4 +339,5 @@ pub mod ui {
     pub const TEXT: Color = Color::srgb(0.9, 0.9, 0.9); // Light gray
     pub const BACKGROUND: Color = Color::srgba(0.1, 0.1, 0.1, 0.8); // Dark, semi-transparent
     pub const HIGHLIGHT: Color = Color::srgb(1.0, 0.8, 0.2); // Gold/yellow
+    pub const CURSOR: Color = Color::srgba(1.0, 1.0, 1.0, 0.25); // Faint white tile highlight
 }
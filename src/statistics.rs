@@ -0,0 +1,194 @@
+//! Lifetime statistics across every game.
+//!
+//! Each colony started from the setup screen counts towards the player's
+//! profile (see `profile.rs`): how many colonies have been founded, how many
+//! ants they've raised from brood between them, and the longest any of them
+//! has lasted. A run's numbers are added in at the start of each in-game day
+//! and again when it ends, so a game abandoned partway still counts up to its
+//! last day. Audits, benchmarks and soaks skip the setup screen and don't
+//! count at all.
+//!
+//! Statistics (T) on the title screen shows them.
+
+use bevy::prelude::*;
+
+use crate::profile::Profile;
+use crate::summary::RunStats;
+use crate::time_controls::{SimulationTick, TICKS_PER_DAY};
+use crate::{GameState, in_game};
+
+pub struct StatisticsPlugin;
+
+impl Plugin for StatisticsPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<BankedRun>()
+            .add_systems(OnExit(GameState::Loading), found_colony)
+            .add_systems(OnEnter(GameState::GameOver), bank_run)
+            .add_systems(Update, bank_daily.run_if(in_game))
+            .add_systems(OnEnter(GameState::Statistics), enter_statistics)
+            .add_systems(OnExit(GameState::Statistics), exit_statistics)
+            .add_systems(
+                Update,
+                statistics_input.run_if(in_state(GameState::Statistics)),
+            );
+    }
+}
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// How much of the current run has been added to the profile
+#[derive(Resource, Default)]
+pub struct BankedRun {
+    /// Whether this run counts towards the lifetime statistics
+    pub counting: bool,
+    /// Ants raised that have been added already
+    pub raised: u32,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the statistics screen root
+#[derive(Component)]
+struct StatisticsScreen;
+
+/// Marker for the button back to the title screen
+#[derive(Component)]
+struct BackButton;
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Count a new colony as it leaves the setup screen for play
+fn found_colony(mut profile: ResMut<Profile>, mut banked: ResMut<BankedRun>) {
+    *banked = BankedRun {
+        counting: true,
+        raised: 0,
+    };
+    profile.colonies_founded += 1;
+    profile.save();
+}
+
+/// Add what the run has done since it was last banked to the profile
+fn bank(profile: &mut Profile, banked: &mut BankedRun, stats: &RunStats, tick: &SimulationTick) {
+    profile.ants_raised += u64::from(stats.raised.saturating_sub(banked.raised));
+    banked.raised = stats.raised;
+    profile.longest_survival = profile.longest_survival.max(tick.0);
+    profile.save();
+}
+
+/// Bank the run at the start of each in-game day
+fn bank_daily(
+    stats: Res<RunStats>,
+    tick: Res<SimulationTick>,
+    mut profile: ResMut<Profile>,
+    mut banked: ResMut<BankedRun>,
+    mut last_day: Local<u64>,
+) {
+    if !banked.counting || tick.day() == *last_day {
+        return;
+    }
+    *last_day = tick.day();
+    bank(&mut profile, &mut banked, &stats, &tick);
+}
+
+/// Bank the run as it ends
+fn bank_run(
+    stats: Res<RunStats>,
+    tick: Res<SimulationTick>,
+    mut profile: ResMut<Profile>,
+    mut banked: ResMut<BankedRun>,
+) {
+    if banked.counting {
+        bank(&mut profile, &mut banked, &stats, &tick);
+        banked.counting = false;
+    }
+}
+
+/// Show the lifetime statistics
+fn enter_statistics(mut commands: Commands, profile: Res<Profile>) {
+    let days = profile.longest_survival as f64 / TICKS_PER_DAY as f64;
+    let lines = [
+        format!("Colonies founded: {}", profile.colonies_founded),
+        format!("Ants raised: {}", profile.ants_raised),
+        format!("Longest survival: {:.1} days", days),
+        format!("Achievements unlocked: {}", profile.achievements.len()),
+    ];
+
+    commands
+        .spawn((
+            StatisticsScreen,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(35.0),
+                top: Val::Percent(20.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(8.0),
+                padding: UiRect::all(Val::Px(24.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Statistics"),
+                TextFont {
+                    font_size: 24.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            for line in lines {
+                parent.spawn((
+                    Text::new(line),
+                    TextFont {
+                        font_size: 14.0,
+                        ..default()
+                    },
+                    TextColor(Color::srgba(0.8, 0.9, 0.8, 1.0)),
+                ));
+            }
+            parent
+                .spawn((
+                    BackButton,
+                    Button,
+                    Node {
+                        padding: UiRect::axes(Val::Px(12.0), Val::Px(6.0)),
+                        ..default()
+                    },
+                    BackgroundColor(Color::srgb(0.25, 0.3, 0.25)),
+                ))
+                .with_child((
+                    Text::new("Back (Esc)"),
+                    TextFont {
+                        font_size: 16.0,
+                        ..default()
+                    },
+                    TextColor(Color::WHITE),
+                ));
+        });
+}
+
+/// Tear down the statistics screen
+fn exit_statistics(mut commands: Commands, screen_query: Query<Entity, With<StatisticsScreen>>) {
+    for entity in &screen_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Go back to the title screen with Esc or the Back button
+fn statistics_input(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    back_query: Query<&Interaction, (Changed<Interaction>, With<BackButton>)>,
+    mut next_state: ResMut<NextState<GameState>>,
+) {
+    if keyboard.just_pressed(KeyCode::Escape)
+        || back_query.iter().any(|i| *i == Interaction::Pressed)
+    {
+        next_state.set(GameState::MainMenu);
+    }
+}
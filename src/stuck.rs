@@ -0,0 +1,157 @@
+//! Stuck-ant detection and recovery.
+//!
+//! An ant that sits on the same tile for too long with a task that should be
+//! taking it somewhere (a target it can't path to, a tunnel that caved in
+//! around it) would otherwise hold that task forever, and enough of them
+//! quietly stall the colony's economy. Once an ant has gone `stuck_ticks`
+//! without moving, its situation is logged, it drops the task to pick a new
+//! one, and an Avoid pheromone is laid on the spot (`stuck_avoid`, zero to
+//! turn it off) to steer the next ant away from the same trap.
+//!
+//! Tasks that keep an ant in one place by design, like guarding an entrance
+//! or waiting at the nest for food, are left alone. So is work done on the
+//! spot, like digging through hard soil, cutting a leaf or cleaning a garden
+//! tile, for as long as it keeps making progress.
+
+use bevy::prelude::*;
+
+use crate::ants::{Ant, Caste, GridPosition, PreviousPosition, Task};
+use crate::config::SimulationConfig;
+use crate::excavation::DigProgress;
+use crate::pheromones::{PheromoneGrids, PheromoneType};
+use crate::time_controls::SimulationSet;
+use crate::world::{FungusGarden, WORLD_SIZE, WorldGrid};
+
+pub struct StuckPlugin;
+
+impl Plugin for StuckPlugin {
+    fn build(&self, app: &mut App) {
+        app.add_systems(FixedLast, recover_stuck_ants.in_set(SimulationSet));
+    }
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// How long an ant has stood on one tile at its current task
+#[derive(Component, Default)]
+pub struct Stillness {
+    pub ticks: u32,
+    /// The task it was counted against, so a new task starts the count over
+    task: &'static str,
+    /// The task's progress when last counted, so work done on the spot starts
+    /// the count over too
+    work: Option<f32>,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Count how long each busy ant has gone without moving, and shake loose any
+/// that have been still too long
+fn recover_stuck_ants(
+    mut query: Query<
+        (
+            &GridPosition,
+            &PreviousPosition,
+            &Caste,
+            &mut Task,
+            &mut Stillness,
+        ),
+        With<Ant>,
+    >,
+    config: Res<SimulationConfig>,
+    world_grid: Res<WorldGrid>,
+    dig_progress: Res<DigProgress>,
+    fungus_garden: Res<FungusGarden>,
+    mut pheromones: ResMut<PheromoneGrids>,
+) {
+    for (pos, previous, caste, mut task, mut stillness) in &mut query {
+        let moved = (pos.x, pos.y, pos.z) != (previous.0.x, previous.0.y, previous.0.z);
+        let work = work_done(&task, &dig_progress, &fungus_garden);
+        if moved || task.stays_put() || stillness.task != task.name() || stillness.work != work {
+            stillness.ticks = 0;
+            stillness.task = task.name();
+            stillness.work = work;
+            continue;
+        }
+
+        stillness.ticks += 1;
+        if stillness.ticks < config.stuck_ticks {
+            continue;
+        }
+
+        warn!(
+            "{} stuck at ({}, {}, {}) for {} ticks while {}, on {} with {} open neighbors; dropping the task",
+            caste.name(),
+            pos.x,
+            pos.y,
+            pos.z,
+            stillness.ticks,
+            task.name(),
+            world_grid.tiles[pos.z][pos.y][pos.x].name(),
+            open_neighbors(&world_grid, pos)
+        );
+        if config.stuck_avoid > 0.0 {
+            pheromones.add(
+                PheromoneType::Avoid,
+                pos.x,
+                pos.y,
+                pos.z,
+                config.stuck_avoid,
+            );
+        }
+        *task = Task::Idle;
+        stillness.ticks = 0;
+    }
+}
+
+/// How far along a task done on the spot is, for the tasks that keep an ant
+/// on one tile while they make progress
+fn work_done(task: &Task, dig_progress: &DigProgress, fungus_garden: &FungusGarden) -> Option<f32> {
+    match *task {
+        Task::Digging {
+            target_x,
+            target_y,
+            target_z,
+        } => Some(dig_progress.get(target_x, target_y, target_z)),
+        Task::Cutting { progress, .. } => Some(progress as f32),
+        Task::Cleaning {
+            target_x,
+            target_y,
+            target_z,
+        } => fungus_garden
+            .plots
+            .get(&(target_x, target_y, target_z))
+            .map(|plot| plot.contamination),
+        _ => None,
+    }
+}
+
+/// Passable tiles sharing a face with a position
+fn open_neighbors(world_grid: &WorldGrid, pos: &GridPosition) -> usize {
+    const FACES: [(i32, i32, i32); 6] = [
+        (1, 0, 0),
+        (-1, 0, 0),
+        (0, 1, 0),
+        (0, -1, 0),
+        (0, 0, 1),
+        (0, 0, -1),
+    ];
+
+    FACES
+        .iter()
+        .filter(|&&(dx, dy, dz)| {
+            let x = pos.x as i32 + dx;
+            let y = pos.y as i32 + dy;
+            let z = pos.z as i32 + dz;
+            let range = 0..WORLD_SIZE as i32;
+            range.contains(&x)
+                && range.contains(&y)
+                && range.contains(&z)
+                && world_grid.tiles[z as usize][y as usize][x as usize].is_passable()
+        })
+        .count()
+}
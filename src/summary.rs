@@ -0,0 +1,373 @@
+//! End-of-run summary card.
+//!
+//! Tracks a few run statistics and, when the colony collapses (the queen is
+//! dead with no brood left, or every ant is) or wins with a nuptial flight,
+//! ends the game and produces a compact text card with the run's highlights
+//! and an ASCII minimap of the nest. A flight's card also gives the run a
+//! score. The card is shown on screen until leaving the finished game, and
+//! saved under `summaries/` so it can be shared.
+
+use std::collections::HashMap;
+use std::fs;
+use std::io;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::ecs::system::SystemParam;
+use bevy::prelude::*;
+
+use crate::ants::{Ant, AntDied, Caste, DeathCause};
+use crate::biome::Biome;
+use crate::brood::Brood;
+use crate::notifications::Notification;
+use crate::nuptial::FlightSucceeded;
+use crate::rng::SimRng;
+use crate::time_controls::{SimulationTick, TICKS_PER_DAY};
+use crate::world::{FoodStores, FungusGarden, SURFACE_LEVEL, TileKind, WORLD_SIZE, WorldGrid};
+use crate::{GameState, ResetRun, reset_resource};
+
+pub struct SummaryPlugin;
+
+impl Plugin for SummaryPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<RunStats>()
+            .add_systems(
+                Update,
+                (
+                    record_deaths,
+                    track_population,
+                    detect_collapse,
+                    celebrate_flight,
+                )
+                    .chain(),
+            )
+            .add_systems(OnExit(GameState::GameOver), hide_summary_card)
+            .add_systems(ResetRun, reset_resource::<RunStats>);
+    }
+}
+
+/// Directory summary cards are written to
+const SUMMARIES_DIR: &str = "summaries";
+/// World tiles per minimap character along each axis
+const MINIMAP_SCALE: usize = 2;
+/// Score for each alate that got away on the flight
+const SCORE_PER_SURVIVOR: u32 = 100;
+/// Score for each ant at the colony's peak
+const SCORE_PER_ANT: u32 = 10;
+/// Days a flight is expected to take
+const PAR_DAYS: u32 = 30;
+/// Score for each day a flight beats par by
+const SCORE_PER_DAY_UNDER_PAR: u32 = 20;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Statistics gathered over the course of a run
+#[derive(Resource, Default, Clone)]
+pub struct RunStats {
+    pub peak_population: usize,
+    /// Adult deaths by cause
+    pub deaths: HashMap<DeathCause, u32>,
+    /// How the queen died, once she has
+    pub queen_death: Option<DeathCause>,
+    /// Spiders soldiers have driven away from the nest
+    pub raids_repelled: u32,
+    /// Adults raised from brood
+    pub raised: u32,
+    /// Set once the colony has collapsed and the card has been produced
+    pub collapsed: bool,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the on-screen summary card
+#[derive(Component)]
+struct SummaryCard;
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Tally deaths, noting how the queen died
+fn record_deaths(mut deaths: MessageReader<AntDied>, mut stats: ResMut<RunStats>) {
+    for death in deaths.read() {
+        *stats.deaths.entry(death.cause).or_default() += 1;
+        if death.caste == Caste::Queen {
+            stats.queen_death = Some(death.cause);
+        }
+    }
+}
+
+/// Remember the largest the colony ever got
+fn track_population(ant_query: Query<(), With<Ant>>, mut stats: ResMut<RunStats>) {
+    let population = ant_query.iter().count();
+    if population > stats.peak_population {
+        stats.peak_population = population;
+    }
+}
+
+/// Once the queen is gone with no brood left to raise, or every ant is dead,
+/// end the game and produce the summary card
+fn detect_collapse(
+    caste_query: Query<&Caste, With<Ant>>,
+    brood_query: Query<(), With<Brood>>,
+    tick: Res<SimulationTick>,
+    mut stats: ResMut<RunStats>,
+    mut ender: RunEnder,
+) {
+    // Wait until the simulation has run so the founding colony has spawned
+    if stats.collapsed || tick.0 == 0 {
+        return;
+    }
+    // Workers can still raise what brood is left without a queen
+    let queen = caste_query.iter().any(|c| *c == Caste::Queen);
+    let failed = caste_query.is_empty() || (!queen && brood_query.is_empty());
+    if !failed {
+        return;
+    }
+    stats.collapsed = true;
+
+    let result = match stats.queen_death {
+        Some(cause) => format!("Collapsed, queen died of {}", cause.name()),
+        None => String::from("Collapsed, every ant lost"),
+    };
+    ender.end_run(
+        &stats,
+        "run",
+        "colony summary",
+        &result,
+        Notification::warning("The colony has collapsed."),
+    );
+}
+
+/// Once a nuptial flight gets away, end the game and produce a scored
+/// summary card
+fn celebrate_flight(
+    mut flights: MessageReader<FlightSucceeded>,
+    tick: Res<SimulationTick>,
+    stats: Res<RunStats>,
+    mut ender: RunEnder,
+) {
+    let Some(flight) = flights.read().last().copied() else {
+        return;
+    };
+
+    let score = flight_score(&flight, stats.peak_population, tick.day() as u32);
+    let result = format!(
+        "Flew, {} of {} alates away, score {}",
+        flight.survivors, flight.alates, score
+    );
+    ender.end_run(
+        &stats,
+        "flight",
+        "nuptial flight",
+        &result,
+        Notification::info("The nuptial flight got away!"),
+    );
+}
+
+// ============================================================================
+// Ending a Run
+// ============================================================================
+
+/// Everything it takes to end a run on a summary card
+#[derive(SystemParam)]
+pub struct RunEnder<'w, 's> {
+    commands: Commands<'w, 's>,
+    tick: Res<'w, SimulationTick>,
+    food_stores: Res<'w, FoodStores>,
+    fungus_garden: Res<'w, FungusGarden>,
+    world_grid: Res<'w, WorldGrid>,
+    biome: Res<'w, Biome>,
+    rng: Res<'w, SimRng>,
+    next_state: ResMut<'w, NextState<GameState>>,
+    time: ResMut<'w, Time<Virtual>>,
+    notifications: MessageWriter<'w, Notification>,
+}
+
+impl RunEnder<'_, '_> {
+    /// End the game: build the card under `title` with the run's `result`,
+    /// save it as a `kind` summary, tell the player the `headline` and where
+    /// the card went, show it, and stop the clock
+    pub fn end_run(
+        &mut self,
+        stats: &RunStats,
+        kind: &str,
+        title: &str,
+        result: &str,
+        mut headline: Notification,
+    ) {
+        let production = (self.fungus_garden.harvested, self.food_stores.produced);
+        let card = summary_card(
+            title,
+            result,
+            stats,
+            production,
+            self.tick.0,
+            self.rng.seed,
+            *self.biome,
+            &self.world_grid,
+        );
+        info!("Run over:\n{}", card);
+
+        match save_card(kind, &card) {
+            Ok(path) => {
+                headline.text = format!("{} Summary saved to {}", headline.text, path.display())
+            }
+            Err(e) => error!("Failed to save summary: {}", e),
+        }
+        self.notifications.write(headline);
+
+        spawn_summary_card(&mut self.commands, card);
+        self.next_state.set(GameState::GameOver);
+        self.time.pause();
+    }
+}
+
+/// Write a card under `summaries/`, named by kind and time
+fn save_card(kind: &str, card: &str) -> io::Result<PathBuf> {
+    // There's no filesystem (or wall clock) in a web build
+    if cfg!(target_arch = "wasm32") {
+        return Err(io::Error::new(
+            io::ErrorKind::Unsupported,
+            "can't save files in a web build",
+        ));
+    }
+    let timestamp = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_secs())
+        .unwrap_or_default();
+    let path = PathBuf::from(SUMMARIES_DIR).join(format!("{}-{}.txt", kind, timestamp));
+    fs::create_dir_all(SUMMARIES_DIR)?;
+    fs::write(&path, card)?;
+    Ok(path)
+}
+
+/// Score a successful flight: alates away, the colony's peak size, and a
+/// bonus for getting there quickly
+fn flight_score(flight: &FlightSucceeded, peak_population: usize, days: u32) -> u32 {
+    flight.survivors as u32 * SCORE_PER_SURVIVOR
+        + peak_population as u32 * SCORE_PER_ANT
+        + PAR_DAYS.saturating_sub(days) * SCORE_PER_DAY_UNDER_PAR
+}
+
+/// Build the shareable text card, headed by its title
+#[allow(clippy::too_many_arguments)]
+pub fn summary_card(
+    title: &str,
+    result: &str,
+    stats: &RunStats,
+    (leaves, food): (u32, u32),
+    tick: u64,
+    seed: u64,
+    biome: Biome,
+    world_grid: &WorldGrid,
+) -> String {
+    let days = tick as f64 / TICKS_PER_DAY as f64;
+    let total_deaths: u32 = stats.deaths.values().sum();
+
+    let mut card = String::new();
+    card.push_str(&format!("=== ACRE {} ===\n", title));
+    card.push_str(&format!("Seed: {}\n", seed));
+    card.push_str(&format!("Biome: {}\n", biome.name()));
+    card.push_str(&format!("Result: {}\n", result));
+    card.push_str(&format!("Days played: {:.1}\n", days));
+    card.push_str(&format!("Peak population: {}\n", stats.peak_population));
+    card.push_str(&format!("Leaves harvested: {}\n", leaves));
+    card.push_str(&format!("Food produced: {}\n", food));
+    card.push_str(&format!("Adult deaths: {}\n", total_deaths));
+    card.push('\n');
+    card.push_str(&minimap(world_grid));
+    card
+}
+
+/// Top-down ASCII map of the world: `#` excavated nest, `g` garden,
+/// `T` trees, `"` undergrowth, `.` open ground
+pub fn minimap(world_grid: &WorldGrid) -> String {
+    let mut map = String::new();
+
+    // Rows top to bottom so north is up, matching the game view
+    for cy in (0..WORLD_SIZE / MINIMAP_SCALE).rev() {
+        for cx in 0..WORLD_SIZE / MINIMAP_SCALE {
+            let mut symbol = '.';
+            for y in cy * MINIMAP_SCALE..(cy + 1) * MINIMAP_SCALE {
+                for x in cx * MINIMAP_SCALE..(cx + 1) * MINIMAP_SCALE {
+                    let tile_symbol = column_symbol(world_grid, x, y);
+                    if symbol_rank(tile_symbol) > symbol_rank(symbol) {
+                        symbol = tile_symbol;
+                    }
+                }
+            }
+            map.push(symbol);
+        }
+        map.push('\n');
+    }
+
+    map
+}
+
+/// The most notable thing in a single world column
+fn column_symbol(world_grid: &WorldGrid, x: usize, y: usize) -> char {
+    let mut symbol = match world_grid.tiles[SURFACE_LEVEL + 1][y][x] {
+        TileKind::TreeTrunk | TileKind::Deadwood | TileKind::Sapling => 'T',
+        TileKind::Bush | TileKind::Grass => '"',
+        _ => '.',
+    };
+
+    for z in 0..SURFACE_LEVEL {
+        match world_grid.tiles[z][y][x] {
+            TileKind::FungusGarden => return 'g',
+            TileKind::Tunnel | TileKind::Ramp | TileKind::Chamber => symbol = '#',
+            _ => {}
+        }
+    }
+
+    symbol
+}
+
+/// Which symbol wins when several share a minimap cell
+fn symbol_rank(symbol: char) -> u8 {
+    match symbol {
+        'g' => 4,
+        '#' => 3,
+        'T' => 2,
+        '"' => 1,
+        _ => 0,
+    }
+}
+
+/// Take the card down when leaving the finished game
+fn hide_summary_card(mut commands: Commands, card_query: Query<Entity, With<SummaryCard>>) {
+    for entity in &card_query {
+        commands.entity(entity).despawn();
+    }
+}
+
+/// Show the card in the middle of the screen
+fn spawn_summary_card(commands: &mut Commands, card: String) {
+    commands
+        .spawn((
+            SummaryCard,
+            Node {
+                position_type: PositionType::Absolute,
+                left: Val::Percent(30.0),
+                top: Val::Percent(10.0),
+                padding: UiRect::all(Val::Px(16.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.85)),
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new(card),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+        });
+}
@@ -1,80 +1,267 @@
 use bevy::prelude::*;
+use serde::Deserialize;
 
-use crate::GameState;
+use crate::{GameState, ResetRun, reset_resource};
 
 pub struct TimeControlsPlugin;
 
 impl Plugin for TimeControlsPlugin {
     fn build(&self, app: &mut App) {
         app.init_resource::<SimulationSpeed>()
+            .init_resource::<SimulationTick>()
+            .init_resource::<UltraGovernor>()
+            .init_resource::<SingleStep>()
+            .configure_sets(FixedFirst, SimulationSet.run_if(simulation_running))
+            .configure_sets(FixedUpdate, SimulationSet.run_if(simulation_running))
+            .configure_sets(FixedLast, SimulationSet.run_if(simulation_running))
             .add_systems(Startup, setup_fixed_timestep)
-            .add_systems(Update, (toggle_pause, change_speed, apply_speed));
+            .add_systems(Update, (tune_ultra, apply_speed).chain())
+            .add_systems(FixedLast, advance_tick.in_set(SimulationSet))
+            .add_systems(ResetRun, reset_resource::<SimulationTick>);
     }
 }
 
 /// Base ticks per second for the simulation
-const BASE_TICKS_PER_SECOND: f64 = 10.0;
+pub const BASE_TICKS_PER_SECOND: f64 = 10.0;
+
+/// Simulation ticks in one in-game day (4 minutes at 1x speed)
+pub const TICKS_PER_DAY: u32 = 2400;
+
+/// Speeds picked directly with Shift+1 to Shift+5
+pub const SPEED_PRESETS: [f32; 5] = [1.0, 2.0, 4.0, 8.0, 16.0];
+/// Fastest ultra mode will push the simulation
+const ULTRA_MAX: f32 = 256.0;
+/// Frame time past which a frame counts as struggling (30fps)
+const ULTRA_SLOW_FRAME_SECS: f32 = 1.0 / 30.0;
+/// Share of struggling frames in a second that makes ultra mode back off
+const ULTRA_BACK_OFF: f32 = 0.1;
 
 #[derive(Resource)]
 pub struct SimulationSpeed {
     pub multiplier: f32,
+    /// Whether ultra mode is finding the fastest speed the machine can keep
+    /// up with, rather than holding `multiplier` steady
+    pub ultra: bool,
 }
 
 impl Default for SimulationSpeed {
     fn default() -> Self {
-        Self { multiplier: 1.0 }
+        Self {
+            multiplier: 1.0,
+            ultra: false,
+        }
+    }
+}
+
+impl SimulationSpeed {
+    /// Target simulation ticks per second at the current speed
+    pub fn ticks_per_second(&self) -> f64 {
+        BASE_TICKS_PER_SECOND * self.multiplier as f64
+    }
+
+    /// Step the speed down a notch: halving above 1x, by quarters below
+    pub fn slower(&mut self) {
+        self.ultra = false;
+        self.multiplier = if self.multiplier > 1.0 {
+            (self.multiplier / 2.0).max(1.0)
+        } else {
+            (self.multiplier - 0.25).max(0.25)
+        };
+    }
+
+    /// Step the speed up a notch: by quarters up to 1x, doubling past it
+    pub fn faster(&mut self) {
+        self.ultra = false;
+        self.multiplier = if self.multiplier >= 1.0 {
+            (self.multiplier * 2.0).min(SPEED_PRESETS[SPEED_PRESETS.len() - 1])
+        } else {
+            self.multiplier + 0.25
+        };
+    }
+
+    /// Label for the status line, like "4.00x" or "ultra 64x"
+    pub fn label(&self) -> String {
+        if self.ultra {
+            format!("ultra {:.0}x", self.multiplier)
+        } else {
+            format!("{:.2}x", self.multiplier)
+        }
+    }
+}
+
+/// Watches frame times in ultra mode to find how fast the simulation can run
+#[derive(Resource)]
+pub struct UltraGovernor {
+    window: Timer,
+    frames: u32,
+    slow_frames: u32,
+}
+
+impl Default for UltraGovernor {
+    fn default() -> Self {
+        Self {
+            window: Timer::from_seconds(1.0, TimerMode::Repeating),
+            frames: 0,
+            slow_frames: 0,
+        }
+    }
+}
+
+/// Every system that advances the simulation. They only run while the game
+/// is playing, or for a single step while paused, so pausing holds the whole
+/// simulation still rather than relying on virtual time standing still
+#[derive(SystemSet, Debug, Clone, PartialEq, Eq, Hash)]
+pub struct SimulationSet;
+
+/// Whether a single tick has been asked for while paused and not yet run
+#[derive(Resource, Default)]
+pub struct SingleStep(pub bool);
+
+/// Run condition for `SimulationSet`
+pub fn simulation_running(state: Res<State<GameState>>, step: Res<SingleStep>) -> bool {
+    *state.get() == GameState::Playing || step.0
+}
+
+/// Number of simulation ticks elapsed since the game started
+#[derive(Resource, Default)]
+pub struct SimulationTick(pub u64);
+
+impl SimulationTick {
+    /// Whole in-game days elapsed
+    pub fn day(&self) -> u64 {
+        self.0 / TICKS_PER_DAY as u64
+    }
+
+    /// The season the current day falls in
+    pub fn season(&self) -> Season {
+        match (self.day() / DAYS_PER_SEASON) % 4 {
+            0 => Season::Spring,
+            1 => Season::Summer,
+            2 => Season::Autumn,
+            _ => Season::Winter,
+        }
+    }
+}
+
+/// In-game days in each season
+pub const DAYS_PER_SEASON: u64 = 5;
+
+/// Seasons of the in-game year
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum Season {
+    Spring,
+    Summer,
+    Autumn,
+    Winter,
+}
+
+impl Season {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Season::Spring => "Spring",
+            Season::Summer => "Summer",
+            Season::Autumn => "Autumn",
+            Season::Winter => "Winter",
+        }
+    }
+
+    /// How wet the season is, scaling how often it rains and how hard the
+    /// water table pushes into deep tunnels
+    pub fn wetness(&self) -> f32 {
+        match self {
+            Season::Spring => 1.5,
+            Season::Summer => 0.75,
+            Season::Autumn => 1.0,
+            Season::Winter => 0.5,
+        }
     }
 }
 
+/// Count each completed simulation tick, which finishes any single step
+pub fn advance_tick(mut tick: ResMut<SimulationTick>, mut step: ResMut<SingleStep>) {
+    tick.0 += 1;
+    step.0 = false;
+}
+
 /// Set up the initial fixed timestep
 fn setup_fixed_timestep(mut time: ResMut<Time<Fixed>>) {
     time.set_timestep_hz(BASE_TICKS_PER_SECOND);
 }
 
-fn toggle_pause(
-    keyboard: Res<ButtonInput<KeyCode>>,
-    current_state: Res<State<GameState>>,
-    mut next_state: ResMut<NextState<GameState>>,
-    mut time: ResMut<Time<Virtual>>,
+/// Pause a running game or resume a paused one
+pub fn toggle_paused(
+    current_state: &GameState,
+    next_state: &mut NextState<GameState>,
+    time: &mut Time<Virtual>,
 ) {
-    if keyboard.just_pressed(KeyCode::Space) {
-        match current_state.get() {
-            GameState::Running => {
-                next_state.set(GameState::Paused);
-                time.pause();
-                info!("Paused");
-            }
-            GameState::Paused => {
-                next_state.set(GameState::Running);
-                time.unpause();
-                info!("Resumed");
-            }
+    match current_state {
+        GameState::Playing => {
+            next_state.set(GameState::Paused);
+            time.pause();
+            info!("Paused");
+        }
+        GameState::Paused => {
+            next_state.set(GameState::Playing);
+            time.unpause();
+            info!("Resumed");
         }
+        // Menus and the setup screen start the game themselves, and a game
+        // that's over stays over
+        GameState::MainMenu
+        | GameState::ScenarioSelect
+        | GameState::Editor
+        | GameState::Achievements
+        | GameState::Statistics
+        | GameState::Setup
+        | GameState::Loading
+        | GameState::GameOver => {}
     }
 }
 
-fn change_speed(keyboard: Res<ButtonInput<KeyCode>>, mut speed: ResMut<SimulationSpeed>) {
-    let old_speed = speed.multiplier;
-
-    // Minus key (-) to slow down
-    if keyboard.just_pressed(KeyCode::Minus) {
-        speed.multiplier = (speed.multiplier - 0.25).max(0.25);
+/// In ultra mode, double the speed each second the frame rate holds up and
+/// halve it when frames start to struggle, so the simulation runs as many
+/// fixed steps per frame as the machine can manage
+pub fn tune_ultra(
+    time: Res<Time<Real>>,
+    virtual_time: Res<Time<Virtual>>,
+    mut governor: ResMut<UltraGovernor>,
+    mut speed: ResMut<SimulationSpeed>,
+) {
+    // Paused frames say nothing about how fast the simulation can run
+    if !speed.ultra || virtual_time.is_paused() {
+        governor.window.reset();
+        governor.frames = 0;
+        governor.slow_frames = 0;
+        return;
     }
 
-    // Equals key (=) to speed up
-    if keyboard.just_pressed(KeyCode::Equal) {
-        speed.multiplier = (speed.multiplier + 0.25).min(4.0);
+    governor.frames += 1;
+    if time.delta_secs() > ULTRA_SLOW_FRAME_SECS {
+        governor.slow_frames += 1;
+    }
+    if !governor.window.tick(time.delta()).just_finished() {
+        return;
     }
 
-    if speed.multiplier != old_speed {
-        info!("Speed: {:.2}x", speed.multiplier);
+    let struggling = governor.slow_frames as f32 > governor.frames as f32 * ULTRA_BACK_OFF;
+    let floor = SPEED_PRESETS[SPEED_PRESETS.len() - 1];
+    let multiplier = if struggling {
+        (speed.multiplier / 2.0).max(floor)
+    } else if governor.slow_frames == 0 {
+        (speed.multiplier * 2.0).min(ULTRA_MAX)
+    } else {
+        speed.multiplier
+    };
+    if multiplier != speed.multiplier {
+        speed.multiplier = multiplier;
     }
+    governor.frames = 0;
+    governor.slow_frames = 0;
 }
 
 /// Apply the speed multiplier to the fixed timestep
 fn apply_speed(speed: Res<SimulationSpeed>, mut time: ResMut<Time<Fixed>>) {
     if speed.is_changed() {
-        let hz = BASE_TICKS_PER_SECOND * speed.multiplier as f64;
-        time.set_timestep_hz(hz);
+        time.set_timestep_hz(speed.ticks_per_second());
     }
 }
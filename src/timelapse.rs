@@ -0,0 +1,146 @@
+//! Timelapse capture.
+//!
+//! F11 starts and stops a timelapse: every so many simulation ticks the window
+//! is captured, without the interface, as the next numbered PNG in a fresh
+//! folder under `timelapses/`. Frames only come while the simulation runs, so
+//! pausing or speeding up the game pauses or speeds up the timelapse, and any
+//! video tool can string the sequence together. Launch with
+//! `--timelapse <ticks>` to record from the start, a frame every `ticks`.
+
+use std::fs;
+use std::path::PathBuf;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+use bevy::prelude::*;
+
+use crate::cli::CliArgs;
+use crate::notifications::Notification;
+use crate::screenshots::{HiddenUi, RootNodes, capture_window};
+use crate::time_controls::SimulationTick;
+
+pub struct TimelapsePlugin;
+
+impl Plugin for TimelapsePlugin {
+    fn build(&self, app: &mut App) {
+        let interval = app
+            .world()
+            .get_resource::<CliArgs>()
+            .and_then(|cli| cli.timelapse);
+
+        app.insert_resource(Timelapse {
+            interval: interval.unwrap_or(DEFAULT_INTERVAL).max(1),
+            start_on_launch: interval.is_some(),
+            ..default()
+        })
+        .add_systems(Update, (toggle_timelapse, capture_timelapse).chain());
+    }
+}
+
+/// Directory each timelapse gets its own folder in
+const TIMELAPSES_DIR: &str = "timelapses";
+/// Ticks between frames unless set with `--timelapse`: ten seconds of game
+/// time at normal speed, 24 frames a day
+const DEFAULT_INTERVAL: u64 = 100;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// The timelapse being recorded, if any
+#[derive(Resource, Default)]
+pub struct Timelapse {
+    /// Ticks between frames
+    pub interval: u64,
+    /// Whether to start recording without waiting for F11
+    start_on_launch: bool,
+    /// Folder the current timelapse is writing to, while recording
+    pub dir: Option<PathBuf>,
+    /// Frames written so far
+    pub frames: u32,
+    /// Tick the next frame is due on
+    next_tick: u64,
+}
+
+impl Timelapse {
+    /// Start writing frames to a new folder
+    fn start(&mut self, tick: u64) -> std::io::Result<PathBuf> {
+        let timestamp = SystemTime::now()
+            .duration_since(UNIX_EPOCH)
+            .map(|d| d.as_secs())
+            .unwrap_or_default();
+        let dir = PathBuf::from(TIMELAPSES_DIR).join(format!("timelapse-{}", timestamp));
+        fs::create_dir_all(&dir)?;
+        self.dir = Some(dir.clone());
+        self.frames = 0;
+        self.next_tick = tick;
+        Ok(dir)
+    }
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Start or stop a timelapse with F11, or straight away with `--timelapse`
+fn toggle_timelapse(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    tick: Res<SimulationTick>,
+    mut timelapse: ResMut<Timelapse>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    let launch = std::mem::take(&mut timelapse.start_on_launch);
+    if !keyboard.just_pressed(KeyCode::F11) && !launch {
+        return;
+    }
+
+    // There's no filesystem in a web build
+    if cfg!(target_arch = "wasm32") {
+        notifications.write(Notification::warning(
+            "Timelapses can't be saved in a web build",
+        ));
+        return;
+    }
+
+    if let Some(dir) = timelapse.dir.take() {
+        notifications.write(Notification::info(format!(
+            "Timelapse stopped: {} frames in {}",
+            timelapse.frames,
+            dir.display()
+        )));
+        return;
+    }
+
+    match timelapse.start(tick.0) {
+        Ok(dir) => notifications.write(Notification::info(format!(
+            "Recording a timelapse to {} (F11 to stop)",
+            dir.display()
+        ))),
+        Err(e) => {
+            error!("Failed to create a timelapse folder: {}", e);
+            notifications.write(Notification::warning("Couldn't start the timelapse"))
+        }
+    };
+}
+
+/// Capture the next frame once enough ticks have passed
+fn capture_timelapse(
+    mut commands: Commands,
+    tick: Res<SimulationTick>,
+    mut timelapse: ResMut<Timelapse>,
+    mut ui_query: RootNodes,
+    mut hidden: ResMut<HiddenUi>,
+) {
+    let Some(dir) = timelapse.dir.clone() else {
+        return;
+    };
+    // A new game starts the tick count over, so don't wait on the old one
+    let waiting = tick.0 < timelapse.next_tick;
+    if waiting && timelapse.next_tick <= tick.0 + timelapse.interval {
+        return;
+    }
+
+    timelapse.frames += 1;
+    timelapse.next_tick = tick.0 + timelapse.interval;
+    let path = dir.join(format!("frame-{:05}.png", timelapse.frames));
+    capture_window(&mut commands, path, true, &mut ui_query, &mut hidden);
+}
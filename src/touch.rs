@@ -0,0 +1,321 @@
+//! Touch controls, so the game can be played on a tablet or in a browser
+//! without a keyboard.
+//!
+//! Drag with one finger to pan and pinch with two to zoom. Touch and hold a
+//! tile to start painting: keep the finger down and drag to paint a stroke of
+//! the selected pheromone, or of tunnel designations in dig mode. The first
+//! touch also brings up a toolbar with the keyboard-only controls: pause,
+//! speed, z-level, pheromone type, and the paint mode.
+
+use bevy::prelude::*;
+
+use crate::GameState;
+use crate::actions::{ActionSystems, PlayerAction};
+use crate::camera::{MAX_SCALE, MIN_SCALE, MainCamera, step_z_level};
+use crate::cursor::screen_to_tile;
+use crate::designations::DesignationKind;
+use crate::pheromones::{PLACE_AMOUNT, SelectedPheromoneType};
+use crate::time_controls::{SimulationSpeed, toggle_paused};
+use crate::world::CurrentZLevel;
+
+pub struct TouchPlugin;
+
+impl Plugin for TouchPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<TouchPaint>()
+            .add_systems(Startup, spawn_touch_toolbar)
+            .add_systems(
+                Update,
+                (
+                    show_touch_toolbar,
+                    touch_toolbar,
+                    update_toolbar_labels,
+                    touch_gestures.in_set(ActionSystems::Input),
+                )
+                    .chain(),
+            );
+    }
+}
+
+/// Seconds a finger must rest on a tile before it starts painting
+const HOLD_SECS: f32 = 0.4;
+/// Pixels a finger can wander and still count as resting
+const HOLD_SLOP: f32 = 12.0;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// What a touch-and-hold paints
+#[derive(Resource, Default, Clone, Copy, PartialEq, Eq)]
+pub enum TouchPaint {
+    /// The selected pheromone
+    #[default]
+    Pheromone,
+    /// Tunnel designations
+    Dig,
+}
+
+/// The one-finger gesture in progress
+#[derive(Default)]
+struct Gesture {
+    /// The finger being tracked
+    finger: Option<u64>,
+    /// Seconds it has been down
+    held: f32,
+    /// Pixels it has moved since touching down
+    travelled: f32,
+    /// Whether it has rested long enough to paint
+    painting: bool,
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the toolbar root, hidden until the screen is touched
+#[derive(Component)]
+struct TouchToolbar;
+
+/// Buttons on the touch toolbar
+#[derive(Component, Clone, Copy, PartialEq, Eq)]
+enum ToolbarButton {
+    Pause,
+    Slower,
+    Faster,
+    Down,
+    Up,
+    Pheromone,
+    Mode,
+}
+
+impl ToolbarButton {
+    const ALL: [ToolbarButton; 7] = [
+        ToolbarButton::Pause,
+        ToolbarButton::Slower,
+        ToolbarButton::Faster,
+        ToolbarButton::Down,
+        ToolbarButton::Up,
+        ToolbarButton::Pheromone,
+        ToolbarButton::Mode,
+    ];
+}
+
+/// Marker for a toolbar button's label
+#[derive(Component)]
+struct ToolbarLabel(ToolbarButton);
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Spawn the toolbar along the bottom of the screen, hidden
+fn spawn_touch_toolbar(mut commands: Commands) {
+    commands
+        .spawn((
+            TouchToolbar,
+            Node {
+                position_type: PositionType::Absolute,
+                bottom: Val::Px(8.0),
+                left: Val::Px(8.0),
+                column_gap: Val::Px(8.0),
+                display: Display::None,
+                ..default()
+            },
+        ))
+        .with_children(|parent| {
+            for button in ToolbarButton::ALL {
+                parent
+                    .spawn((
+                        button,
+                        Button,
+                        Node {
+                            padding: UiRect::axes(Val::Px(16.0), Val::Px(12.0)),
+                            ..default()
+                        },
+                        BackgroundColor(Color::srgba(0.25, 0.3, 0.25, 0.85)),
+                    ))
+                    .with_child((
+                        ToolbarLabel(button),
+                        Text::new(""),
+                        TextFont {
+                            font_size: 18.0,
+                            ..default()
+                        },
+                        TextColor(Color::WHITE),
+                    ));
+            }
+        });
+}
+
+/// Bring up the toolbar the first time the screen is touched
+fn show_touch_toolbar(
+    touches: Res<Touches>,
+    mut toolbar_query: Query<&mut Node, With<TouchToolbar>>,
+) {
+    if !touches.any_just_pressed() {
+        return;
+    }
+    for mut node in &mut toolbar_query {
+        node.display = Display::Flex;
+    }
+}
+
+/// Do what a pressed toolbar button says
+#[allow(clippy::too_many_arguments)]
+fn touch_toolbar(
+    button_query: Query<(&Interaction, &ToolbarButton), Changed<Interaction>>,
+    current_state: Res<State<GameState>>,
+    mut next_state: ResMut<NextState<GameState>>,
+    mut time: ResMut<Time<Virtual>>,
+    mut speed: ResMut<SimulationSpeed>,
+    mut current_z: ResMut<CurrentZLevel>,
+    mut selected: ResMut<SelectedPheromoneType>,
+    mut paint: ResMut<TouchPaint>,
+) {
+    for (interaction, button) in &button_query {
+        if *interaction != Interaction::Pressed {
+            continue;
+        }
+        match button {
+            ToolbarButton::Pause => toggle_paused(current_state.get(), &mut next_state, &mut time),
+            ToolbarButton::Slower => speed.slower(),
+            ToolbarButton::Faster => speed.faster(),
+            ToolbarButton::Down => step_z_level(&mut current_z, false),
+            ToolbarButton::Up => step_z_level(&mut current_z, true),
+            ToolbarButton::Pheromone => selected.0 = selected.0.next(),
+            ToolbarButton::Mode => {
+                *paint = match *paint {
+                    TouchPaint::Pheromone => TouchPaint::Dig,
+                    TouchPaint::Dig => TouchPaint::Pheromone,
+                }
+            }
+        }
+    }
+}
+
+/// Keep the toolbar labels in step with the settings they show
+fn update_toolbar_labels(
+    current_state: Res<State<GameState>>,
+    selected: Res<SelectedPheromoneType>,
+    paint: Res<TouchPaint>,
+    mut label_query: Query<(&mut Text, &ToolbarLabel)>,
+) {
+    if !current_state.is_changed() && !selected.is_changed() && !paint.is_changed() {
+        return;
+    }
+
+    for (mut text, label) in &mut label_query {
+        **text = match label.0 {
+            ToolbarButton::Pause => match current_state.get() {
+                GameState::Paused => "Resume".to_string(),
+                _ => "Pause".to_string(),
+            },
+            ToolbarButton::Slower => "Slower".to_string(),
+            ToolbarButton::Faster => "Faster".to_string(),
+            ToolbarButton::Down => "Z-".to_string(),
+            ToolbarButton::Up => "Z+".to_string(),
+            ToolbarButton::Pheromone => selected.0.name().to_string(),
+            ToolbarButton::Mode => match *paint {
+                TouchPaint::Pheromone => "Paint: pheromone".to_string(),
+                TouchPaint::Dig => "Paint: dig".to_string(),
+            },
+        };
+    }
+}
+
+/// Pan with one finger, pinch to zoom with two, and touch and hold to paint
+#[allow(clippy::too_many_arguments)]
+fn touch_gestures(
+    time: Res<Time<Real>>,
+    touches: Res<Touches>,
+    button_query: Query<&Interaction, With<ToolbarButton>>,
+    mut camera_query: Query<
+        (&Camera, &GlobalTransform, &mut Transform, &mut Projection),
+        With<MainCamera>,
+    >,
+    current_state: Res<State<GameState>>,
+    current_z: Res<CurrentZLevel>,
+    selected: Res<SelectedPheromoneType>,
+    paint: Res<TouchPaint>,
+    mut gesture: Local<Gesture>,
+    mut actions: MessageWriter<PlayerAction>,
+) {
+    let Ok((camera, camera_transform, mut transform, mut projection)) = camera_query.single_mut()
+    else {
+        return;
+    };
+    let Projection::Orthographic(ref mut ortho) = *projection else {
+        return;
+    };
+
+    // Fingers on the toolbar are pressing buttons, not the world
+    if button_query
+        .iter()
+        .any(|interaction| *interaction != Interaction::None)
+    {
+        *gesture = Gesture::default();
+        return;
+    }
+
+    let fingers: Vec<_> = touches.iter().collect();
+    match fingers.as_slice() {
+        [finger] => {
+            if gesture.finger != Some(finger.id()) {
+                *gesture = Gesture {
+                    finger: Some(finger.id()),
+                    ..default()
+                };
+            }
+            gesture.held += time.delta_secs();
+            gesture.travelled += finger.delta().length();
+            if !gesture.painting && gesture.held >= HOLD_SECS && gesture.travelled < HOLD_SLOP {
+                gesture.painting = true;
+            }
+
+            if gesture.painting {
+                // Painting changes the world, which waits for the game to start
+                if !current_state.get().in_game() {
+                    return;
+                }
+                let Some((x, y)) = screen_to_tile(camera, camera_transform, finger.position())
+                else {
+                    return;
+                };
+                let z = current_z.0;
+                actions.write(match *paint {
+                    TouchPaint::Pheromone => PlayerAction::PlacePheromone {
+                        ptype: selected.0,
+                        x,
+                        y,
+                        z,
+                        amount: PLACE_AMOUNT,
+                    },
+                    TouchPaint::Dig => PlayerAction::Designate {
+                        kind: DesignationKind::Tunnel,
+                        x,
+                        y,
+                        z,
+                    },
+                });
+            } else if gesture.travelled >= HOLD_SLOP {
+                // Screen y runs down, world y runs up
+                let delta = finger.delta() * ortho.scale;
+                transform.translation.x -= delta.x;
+                transform.translation.y += delta.y;
+            }
+        }
+        [first, second] => {
+            // A second finger ends any pan or paint and starts a pinch
+            *gesture = Gesture::default();
+            let before = first
+                .previous_position()
+                .distance(second.previous_position());
+            let after = first.position().distance(second.position());
+            if before > 0.0 && after > 0.0 {
+                ortho.scale = (ortho.scale * before / after).clamp(MIN_SCALE, MAX_SCALE);
+            }
+        }
+        _ => *gesture = Gesture::default(),
+    }
+}
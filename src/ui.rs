@@ -1,19 +1,41 @@
 //! Minimal UI for displaying game state and colony stats.
+//!
+//! All of the UI can be scaled from 75% to 200% for high-DPI displays: U steps
+//! through the sizes, or launch with `--ui-scale <percent>`.
+
+use std::time::Duration;
 
 use bevy::prelude::*;
+use bevy::time::common_conditions::on_timer;
 
 use crate::GameState;
 use crate::ants::{Ant, Caste};
+use crate::brood::{Brood, BroodStage, population_cap};
+use crate::cli::CliArgs;
+use crate::cursor::CursorTile;
+use crate::exploration::Explored;
+use crate::moisture::{SoilMoisture, Weather};
+use crate::notifications::{Notification, NotificationFeed, NotificationLevel};
 use crate::pheromones::SelectedPheromoneType;
-use crate::time_controls::SimulationSpeed;
-use crate::world::{CurrentZLevel, FungusGarden, SURFACE_LEVEL};
+use crate::sprites;
+use crate::time_controls::{SimulationSpeed, SimulationTick};
+use crate::world::{CurrentZLevel, FoodStores, FungusGarden, SURFACE_LEVEL, WorldGrid};
 
 pub struct UiPlugin;
 
 impl Plugin for UiPlugin {
     fn build(&self, app: &mut App) {
-        app.add_systems(Startup, setup_ui)
-            .add_systems(Update, update_ui);
+        app.add_systems(Startup, (setup_ui, setup_nursery_panel, apply_cli_ui_scale))
+            .add_systems(
+                Update,
+                (
+                    cycle_ui_scale,
+                    update_ui,
+                    update_notification_text,
+                    toggle_nursery_panel,
+                    update_nursery_panel.run_if(on_timer(Duration::from_millis(250))),
+                ),
+            );
     }
 }
 
@@ -37,10 +59,65 @@ struct ColonyStatsText;
 #[derive(Component)]
 struct ControlsText;
 
+/// Marker for the latest notification
+#[derive(Component)]
+struct NotificationText;
+
+/// Marker for the nursery panel root
+#[derive(Component)]
+struct NurseryPanel;
+
+/// Marker for the nursery panel's summary line
+#[derive(Component)]
+struct NurserySummaryText;
+
+/// Marker for the container holding one row per brood item
+#[derive(Component)]
+struct NurseryRows;
+
+/// Most brood rows listed before the rest are summarized
+const MAX_NURSERY_ROWS: usize = 12;
+/// UI sizes U steps through, as multiples of the normal size
+pub const UI_SCALES: [f32; 6] = [0.75, 1.0, 1.25, 1.5, 1.75, 2.0];
+
+/// The next UI size after `scale`, wrapping around to the smallest
+pub fn next_ui_scale(scale: f32) -> f32 {
+    UI_SCALES
+        .into_iter()
+        .find(|&step| step > scale + f32::EPSILON)
+        .unwrap_or(UI_SCALES[0])
+}
+
 // ============================================================================
 // Systems
 // ============================================================================
 
+/// Scale the UI as asked with `--ui-scale`
+fn apply_cli_ui_scale(cli: Option<Res<CliArgs>>, mut ui_scale: ResMut<UiScale>) {
+    if let Some(percent) = cli.and_then(|cli| cli.ui_scale) {
+        let (min, max) = (UI_SCALES[0], UI_SCALES[UI_SCALES.len() - 1]);
+        ui_scale.0 = (percent / 100.0).clamp(min, max);
+        info!("UI scale: {:.0}%", ui_scale.0 * 100.0);
+    }
+}
+
+/// Step through the UI sizes with U
+fn cycle_ui_scale(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut ui_scale: ResMut<UiScale>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyU) {
+        return;
+    }
+
+    ui_scale.0 = next_ui_scale(ui_scale.0);
+    notifications.write(Notification::info(format!(
+        "UI scale: {:.0}%",
+        ui_scale.0 * 100.0
+    )));
+}
+
 fn setup_ui(mut commands: Commands) {
     // Root container - top-left corner
     commands
@@ -80,6 +157,17 @@ fn setup_ui(mut commands: Commands) {
                 TextColor(Color::srgba(0.8, 0.9, 0.8, 1.0)),
             ));
 
+            // Latest notification (warnings and colony news)
+            parent.spawn((
+                NotificationText,
+                Text::new(""),
+                TextFont {
+                    font_size: 14.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+
             // Controls help
             parent.spawn((
                 ControlsText,
@@ -93,54 +181,82 @@ fn setup_ui(mut commands: Commands) {
         });
 }
 
+/// The status line, apart from the other panel texts
+type StatusTextQuery<'w, 's> = Query<
+    'w,
+    's,
+    &'static mut Text,
+    (
+        With<StatusText>,
+        Without<ColonyStatsText>,
+        Without<ControlsText>,
+    ),
+>;
+
+/// The colony stats, apart from the other panel texts
+type ColonyTextQuery<'w, 's> = Query<
+    'w,
+    's,
+    &'static mut Text,
+    (
+        With<ColonyStatsText>,
+        Without<StatusText>,
+        Without<ControlsText>,
+    ),
+>;
+
+/// The controls help, apart from the other panel texts
+type ControlsTextQuery<'w, 's> = Query<
+    'w,
+    's,
+    &'static mut Text,
+    (
+        With<ControlsText>,
+        Without<StatusText>,
+        Without<ColonyStatsText>,
+    ),
+>;
+
+#[allow(clippy::too_many_arguments)]
 fn update_ui(
     game_state: Res<State<GameState>>,
     speed: Res<SimulationSpeed>,
+    tick: Res<SimulationTick>,
     current_z: Res<CurrentZLevel>,
+    cursor_tile: Res<CursorTile>,
+    world_grid: Res<WorldGrid>,
+    explored: Res<Explored>,
+    moisture: Res<SoilMoisture>,
+    weather: Res<Weather>,
     selected_pheromone: Res<SelectedPheromoneType>,
     fungus_garden: Res<FungusGarden>,
+    food_stores: Res<FoodStores>,
     ant_query: Query<&Caste, With<Ant>>,
-    mut status_query: Query<
-        &mut Text,
-        (
-            With<StatusText>,
-            Without<ColonyStatsText>,
-            Without<ControlsText>,
-        ),
-    >,
-    mut colony_query: Query<
-        &mut Text,
-        (
-            With<ColonyStatsText>,
-            Without<StatusText>,
-            Without<ControlsText>,
-        ),
-    >,
-    mut controls_query: Query<
-        &mut Text,
-        (
-            With<ControlsText>,
-            Without<StatusText>,
-            Without<ColonyStatsText>,
-        ),
-    >,
+    mut status_query: StatusTextQuery,
+    mut colony_query: ColonyTextQuery,
+    mut controls_query: ControlsTextQuery,
 ) {
     // Count ants by caste
     let mut queen_count = 0;
     let mut forager_count = 0;
     let mut gardener_count = 0;
+    let mut nurse_count = 0;
     let mut soldier_count = 0;
+    let mut alate_count = 0;
 
     for caste in &ant_query {
         match caste {
             Caste::Queen => queen_count += 1,
             Caste::Forager => forager_count += 1,
             Caste::Gardener => gardener_count += 1,
+            Caste::Nurse => nurse_count += 1,
             Caste::Soldier => soldier_count += 1,
+            Caste::Alate => alate_count += 1,
         }
     }
 
-    let total_ants = queen_count + forager_count + gardener_count + soldier_count;
+    let total_ants =
+        queen_count + forager_count + gardener_count + nurse_count + soldier_count + alate_count;
 
     // Calculate z-level relative to surface
     let z_relative = current_z.0 as i32 - SURFACE_LEVEL as i32;
@@ -155,35 +271,302 @@ fn update_ui(
     // Update status text
     if let Ok(mut text) = status_query.single_mut() {
         let pause_state = match game_state.get() {
-            GameState::Running => "",
+            GameState::Playing => "",
             GameState::Paused => " [PAUSED]",
+            GameState::Setup => " [SETUP]",
+            GameState::Editor => " [EDITOR]",
+            GameState::MainMenu
+            | GameState::ScenarioSelect
+            | GameState::Achievements
+            | GameState::Statistics => " [MENU]",
+            GameState::Loading => " [LOADING]",
+            GameState::GameOver => " [GAME OVER]",
+        };
+
+        // Soil under the cursor, to help route tunnels through easy ground
+        let soil = match cursor_tile.0 {
+            Some((x, y, z))
+                if z < SURFACE_LEVEL && !explored.shows(world_grid.tiles[z][y][x], x, y, z) =>
+            {
+                String::from("  |  Soil: unexplored")
+            }
+            Some((x, y, z)) if z < SURFACE_LEVEL => {
+                let soil = world_grid.soil[z][y][x];
+                format!(
+                    "  |  Soil: {} (dig {:.0}%, stability {:.0}%, moisture {:.0}%)",
+                    soil.name(),
+                    moisture.dig_chance(soil, x, y, z) * 100.0,
+                    moisture.stability(soil, x, y, z) * 100.0,
+                    moisture.level[z][y][x] * 100.0
+                )
+            }
+            _ => String::new(),
         };
 
         **text = format!(
-            "Speed: {:.2}x{}  |  Z: {}  |  Pheromone: {}",
-            speed.multiplier,
+            "Day {} ({}{})  |  Speed: {}{}  |  Z: {}  |  Pheromone: {}{}",
+            tick.day() + 1,
+            tick.season().name(),
+            if weather.raining() { ", raining" } else { "" },
+            speed.label(),
             pause_state,
             z_display,
-            selected_pheromone.0.name()
+            selected_pheromone.0.name(),
+            soil
         );
     }
 
     // Update colony stats
     if let Ok(mut text) = colony_query.single_mut() {
         **text = format!(
-            "Colony: {} ants (Q:{} F:{} G:{})\nGarden: {} food | {} mulch | {} leaves",
+            "Colony: {} ants (Q:{} F:{} G:{} N:{} S:{} A:{})\nGarden: {} tiles | {} mulch | {} leaves | {:.0}% contaminated\nStores: {}/{} ({} food, {} honeydew, {} seeds)",
             total_ants,
             queen_count,
             forager_count,
             gardener_count,
-            fungus_garden.food,
-            fungus_garden.mulch,
-            fungus_garden.leaves
+            nurse_count,
+            soldier_count,
+            alate_count,
+            fungus_garden.plots.len(),
+            fungus_garden.total_mulch(),
+            fungus_garden.leaves,
+            fungus_garden.max_contamination() * 100.0,
+            food_stores.total(),
+            food_stores.capacity,
+            food_stores.food,
+            food_stores.honeydew,
+            food_stores.seeds
         );
     }
 
     // Update controls help
     if let Ok(mut text) = controls_query.single_mut() {
-        **text = "Space:Pause  -/=:Speed  []:Z-Level  Tab:Pheromone  Click:Place".to_string();
+        **text =
+            "Space:Pause  Esc:Menu  -/=:Speed  Shift+1-5:Preset  Shift+0:Ultra  Shift+.:Step  Bksp:Rewind  []:Z-Level  WASD:Cursor  Tab:Pheromone  Click/Enter:Place  RClick/X:Designate (Alt:Ramp)  L:Move nest  H:Difficulty  E:Edge scroll  Home:Nest  1-4:Bookmark  B:Blueprint  F1-4:Macro  N:Nursery  G:Graphs  J:Log  O:Overlay  V:Air  I:Cave-ins  M:Moisture  K:Music  Y:Palette  U:UI size  P:Prune  F6:Sandbox  ~:Console  F10:Poster  F11:Timelapse  F12:Screenshot"
+                .to_string();
+    }
+}
+
+fn update_notification_text(
+    feed: Res<NotificationFeed>,
+    mut query: Query<(&mut Text, &mut TextColor), With<NotificationText>>,
+) {
+    if !feed.is_changed() {
+        return;
+    }
+
+    let Ok((mut text, mut color)) = query.single_mut() else {
+        return;
+    };
+
+    match &feed.current {
+        Some((notification, _)) => {
+            **text = notification.text.clone();
+            color.0 = match notification.level {
+                NotificationLevel::Info => Color::WHITE,
+                NotificationLevel::Warning => sprites::ui::HIGHLIGHT,
+            };
+        }
+        None => text.clear(),
     }
 }
+
+// ============================================================================
+// Nursery Panel
+// ============================================================================
+
+/// Spawn the (initially hidden) nursery panel in the top-right corner
+fn setup_nursery_panel(mut commands: Commands) {
+    commands
+        .spawn((
+            NurseryPanel,
+            Node {
+                position_type: PositionType::Absolute,
+                right: Val::Px(10.0),
+                top: Val::Px(10.0),
+                width: Val::Px(300.0),
+                flex_direction: FlexDirection::Column,
+                row_gap: Val::Px(6.0),
+                padding: UiRect::all(Val::Px(10.0)),
+                ..default()
+            },
+            BackgroundColor(Color::srgba(0.0, 0.0, 0.0, 0.7)),
+            Visibility::Hidden,
+        ))
+        .with_children(|parent| {
+            parent.spawn((
+                Text::new("Nursery"),
+                TextFont {
+                    font_size: 16.0,
+                    ..default()
+                },
+                TextColor(Color::WHITE),
+            ));
+            parent.spawn((
+                NurserySummaryText,
+                Text::new(""),
+                TextFont {
+                    font_size: 13.0,
+                    ..default()
+                },
+                TextColor(Color::srgba(0.8, 0.9, 0.8, 1.0)),
+            ));
+            parent.spawn((
+                NurseryRows,
+                Node {
+                    flex_direction: FlexDirection::Column,
+                    row_gap: Val::Px(4.0),
+                    ..default()
+                },
+            ));
+        });
+}
+
+/// Show or hide the nursery panel with the N key
+fn toggle_nursery_panel(
+    keyboard: Res<ButtonInput<KeyCode>>,
+    mut query: Query<&mut Visibility, With<NurseryPanel>>,
+) {
+    if !keyboard.just_pressed(KeyCode::KeyN) {
+        return;
+    }
+
+    if let Ok(mut visibility) = query.single_mut() {
+        *visibility = match *visibility {
+            Visibility::Hidden => Visibility::Visible,
+            _ => Visibility::Hidden,
+        };
+    }
+}
+
+/// Rebuild the nursery listing: stage counts, a progress bar per brood item,
+/// and its projected emergence time
+#[allow(clippy::too_many_arguments)]
+fn update_nursery_panel(
+    mut commands: Commands,
+    speed: Res<SimulationSpeed>,
+    fungus_garden: Res<FungusGarden>,
+    panel_query: Query<&Visibility, With<NurseryPanel>>,
+    brood_query: Query<&Brood>,
+    ant_query: Query<(), With<Ant>>,
+    mut summary_query: Query<&mut Text, With<NurserySummaryText>>,
+    rows_query: Query<Entity, With<NurseryRows>>,
+) {
+    if !matches!(panel_query.single(), Ok(Visibility::Visible)) {
+        return;
+    }
+
+    let mut brood: Vec<&Brood> = brood_query.iter().collect();
+    brood.sort_by_key(|b| b.ticks_to_emerge());
+
+    let count = |stage: BroodStage| brood.iter().filter(|b| b.stage == stage).count();
+    let unfed = brood.iter().filter(|b| b.is_unfed()).count();
+    let cold = brood.iter().filter(|b| b.is_cold()).count();
+    let population = ant_query.iter().count() + brood.len();
+
+    if let Ok(mut text) = summary_query.single_mut() {
+        **text = format!(
+            "Eggs: {}  Larvae: {}  Pupae: {}\nPopulation: {}/{}{}{}",
+            count(BroodStage::Egg),
+            count(BroodStage::Larva),
+            count(BroodStage::Pupa),
+            population,
+            population_cap(&fungus_garden),
+            if unfed > 0 {
+                format!("\n{} larvae unfed!", unfed)
+            } else {
+                String::new()
+            },
+            if cold > 0 {
+                format!("\n{} brood cold - not enough nurses", cold)
+            } else {
+                String::new()
+            }
+        );
+    }
+
+    let Ok(rows) = rows_query.single() else {
+        return;
+    };
+
+    let ticks_per_second = speed.ticks_per_second();
+    commands
+        .entity(rows)
+        .despawn_related::<Children>()
+        .with_children(|parent| {
+            for item in brood.iter().take(MAX_NURSERY_ROWS) {
+                spawn_nursery_row(parent, item, ticks_per_second);
+            }
+            if brood.len() > MAX_NURSERY_ROWS {
+                parent.spawn((
+                    Text::new(format!("...and {} more", brood.len() - MAX_NURSERY_ROWS)),
+                    TextFont {
+                        font_size: 12.0,
+                        ..default()
+                    },
+                    TextColor(sprites::ui::TEXT),
+                ));
+            }
+        });
+}
+
+/// One brood item: label, stage progress bar, and projected emergence
+fn spawn_nursery_row(parent: &mut ChildSpawnerCommands, brood: &Brood, ticks_per_second: f64) {
+    let (status, color) = if brood.is_unfed() {
+        ("UNFED".to_string(), sprites::ui::HIGHLIGHT)
+    } else {
+        let seconds = brood.ticks_to_emerge() as f64 / ticks_per_second;
+        (format!("emerges in ~{:.0}s", seconds), sprites::ui::TEXT)
+    };
+
+    parent
+        .spawn(Node {
+            flex_direction: FlexDirection::Row,
+            align_items: AlignItems::Center,
+            column_gap: Val::Px(6.0),
+            ..default()
+        })
+        .with_children(|row| {
+            row.spawn((
+                Text::new(format!("{} {}", brood.caste.name(), brood.stage.name())),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(color),
+                Node {
+                    width: Val::Px(110.0),
+                    ..default()
+                },
+            ));
+
+            // Progress through the current stage
+            row.spawn((
+                Node {
+                    width: Val::Px(60.0),
+                    height: Val::Px(8.0),
+                    ..default()
+                },
+                BackgroundColor(sprites::ui::BACKGROUND),
+            ))
+            .with_children(|bar| {
+                bar.spawn((
+                    Node {
+                        width: Val::Percent(brood.stage_progress() * 100.0),
+                        height: Val::Percent(100.0),
+                        ..default()
+                    },
+                    BackgroundColor(brood.stage.color()),
+                ));
+            });
+
+            row.spawn((
+                Text::new(status),
+                TextFont {
+                    font_size: 12.0,
+                    ..default()
+                },
+                TextColor(color),
+            ));
+        });
+}
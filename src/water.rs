@@ -0,0 +1,320 @@
+//! Groundwater.
+//!
+//! Below the water table (set per world with `--water-table <depth>`) the
+//! soil is saturated. Tunnels dug down into it fill with water seeping out of
+//! the surrounding loam and sand, settling to the bottom and rising from
+//! there. Clay and rock hold the water back, so a tunnel walled in by them
+//! stays dry. Ants bail flooded tunnels out a mouthful at a time, but ants and
+//! brood left in deep water drown. The table pushes hardest in a wet spring
+//! and barely at all through winter.
+
+use bevy::prelude::*;
+use rand::Rng;
+
+use crate::ants::{Ant, AntDied, Caste, DeathCause, GridPosition};
+use crate::biome::Biome;
+use crate::brood::Brood;
+use crate::low_spec::cosmetic_frame;
+use crate::notifications::Notification;
+use crate::rng::SimRng;
+use crate::sprites;
+use crate::time_controls::{SimulationSet, SimulationTick};
+use crate::world::{
+    CurrentZLevel, Soil, TILE_SIZE, TileDug, TileKind, WORLD_SIZE, WorldGrid, WorldOptions,
+    filled_grid,
+};
+use crate::{ResetRun, reset_resource};
+
+pub struct WaterPlugin;
+
+impl Plugin for WaterPlugin {
+    fn build(&self, app: &mut App) {
+        app.init_resource::<Groundwater>()
+            .add_systems(Startup, spawn_water_sprites)
+            .add_systems(Update, update_water_sprites.run_if(cosmetic_frame))
+            .add_systems(
+                FixedUpdate,
+                (
+                    watch_for_groundwater,
+                    (water_flow, drowning)
+                        .chain()
+                        .run_if(|water: Res<Groundwater>| water.struck),
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(ResetRun, reset_resource::<Groundwater>);
+    }
+}
+
+/// Water seeping in through each permeable face of a tunnel per tick
+const SEEP_RATE: f32 = 0.002;
+/// Share of the difference with each neighbor that spreads sideways per tick
+const SPREAD_RATE: f32 = 0.2;
+/// Water level at which a tile is worth bailing
+pub const BAIL_THRESHOLD: f32 = 0.3;
+/// Water level deep enough to drown in
+const DROWN_LEVEL: f32 = 0.75;
+/// Per-tick chance an ant or brood in deep water drowns
+const DROWN_CHANCE: f64 = 0.01;
+
+// ============================================================================
+// Resources
+// ============================================================================
+
+/// Water standing in the tunnels, as a fill level (0.0 - 1.0) per tile
+#[derive(Resource)]
+pub struct Groundwater {
+    pub level: Box<[[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+    /// Whether the colony has dug below the water table yet; until then
+    /// there's nothing to simulate
+    pub struck: bool,
+    /// Tiles holding enough water to be worth bailing
+    pub flooded: Vec<(usize, usize, usize)>,
+    /// Next-step buffer reused by each spreading pass
+    scratch: Box<[[[f32; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+}
+
+impl Default for Groundwater {
+    fn default() -> Self {
+        Self {
+            level: filled_grid(0.0),
+            struck: false,
+            flooded: Vec::new(),
+            scratch: filled_grid(0.0),
+        }
+    }
+}
+
+impl Groundwater {
+    /// The flooded tile a bailer should work on: the highest one, where the
+    /// water is shallowest, nearest to `from` among those
+    pub fn bail_target(&self, from: (usize, usize, usize)) -> Option<(usize, usize, usize)> {
+        let top = self.flooded.iter().map(|&(_, _, z)| z).max()?;
+        self.flooded
+            .iter()
+            .filter(|&&(_, _, z)| z == top)
+            .min_by_key(|&&(x, y, z)| x.abs_diff(from.0) + y.abs_diff(from.1) + z.abs_diff(from.2))
+            .copied()
+    }
+
+    /// Scoop up to `amount` of water from a tile, returning how much was taken
+    pub fn bail(&mut self, x: usize, y: usize, z: usize, amount: f32) -> f32 {
+        let taken = self.level[z][y][x].min(amount);
+        self.level[z][y][x] -= taken;
+        taken
+    }
+}
+
+// ============================================================================
+// Components
+// ============================================================================
+
+/// Marker for the water sprite drawn over a tile
+#[derive(Component)]
+pub struct WaterSprite {
+    pub x: usize,
+    pub y: usize,
+}
+
+// ============================================================================
+// Systems
+// ============================================================================
+
+/// Tiles water can stand in
+fn holds_water(tile: TileKind) -> bool {
+    tile.is_excavated()
+}
+
+/// Saturated soil that lets water through: loam and sand, not clay or rock
+fn is_permeable(world_grid: &WorldGrid, x: usize, y: usize, z: usize) -> bool {
+    matches!(world_grid.tiles[z][y][x], TileKind::Dirt | TileKind::Root)
+        && world_grid.soil[z][y][x] != Soil::Clay
+}
+
+/// Start the simulation the first time a tunnel reaches the water table
+fn watch_for_groundwater(
+    mut dug_tiles: MessageReader<TileDug>,
+    options: Res<WorldOptions>,
+    mut water: ResMut<Groundwater>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    if water.struck {
+        dug_tiles.clear();
+        return;
+    }
+
+    let table_z = options.water_table_z();
+    if dug_tiles.read().any(|dug| dug.z <= table_z) {
+        water.struck = true;
+        notifications.write(Notification::warning(format!(
+            "The colony has struck groundwater {} tiles down: deep tunnels will flood",
+            options.water_table_depth
+        )));
+    }
+}
+
+/// Seep water into tunnels below the table, let it settle, and spread it out
+fn water_flow(
+    tick: Res<SimulationTick>,
+    world_grid: Res<WorldGrid>,
+    options: Res<WorldOptions>,
+    biome: Res<Biome>,
+    mut water: ResMut<Groundwater>,
+) {
+    let water = &mut *water;
+    let tiles = &world_grid.tiles;
+    let table_z = options.water_table_z();
+    let seep = SEEP_RATE * tick.season().wetness() * biome.moisture();
+
+    // Seep in through permeable walls, floors, and ceilings
+    for z in 0..=table_z {
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                if !holds_water(tiles[z][y][x]) {
+                    water.level[z][y][x] = 0.0;
+                    continue;
+                }
+
+                let neighbors = [
+                    (x.wrapping_sub(1), y, z),
+                    (x + 1, y, z),
+                    (x, y.wrapping_sub(1), z),
+                    (x, y + 1, z),
+                    (x, y, z.wrapping_sub(1)),
+                    (x, y, z + 1),
+                ];
+                let faces = neighbors
+                    .into_iter()
+                    .filter(|&(nx, ny, nz)| {
+                        nx < WORLD_SIZE
+                            && ny < WORLD_SIZE
+                            && nz <= table_z
+                            && is_permeable(&world_grid, nx, ny, nz)
+                    })
+                    .count();
+                water.level[z][y][x] = (water.level[z][y][x] + seep * faces as f32).min(1.0);
+            }
+        }
+    }
+
+    // Settle: water pours down into any open space below it
+    for z in 1..=table_z {
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                if !holds_water(tiles[z][y][x]) || !holds_water(tiles[z - 1][y][x]) {
+                    continue;
+                }
+                let moved = water.level[z][y][x].min(1.0 - water.level[z - 1][y][x]);
+                water.level[z][y][x] -= moved;
+                water.level[z - 1][y][x] += moved;
+            }
+        }
+    }
+
+    // Spread sideways toward level
+    water.flooded.clear();
+    for z in 0..=table_z {
+        for y in 0..WORLD_SIZE {
+            for x in 0..WORLD_SIZE {
+                let here = water.level[z][y][x];
+                let mut next = here;
+                if holds_water(tiles[z][y][x]) {
+                    for (nx, ny) in [
+                        (x.wrapping_sub(1), y),
+                        (x + 1, y),
+                        (x, y.wrapping_sub(1)),
+                        (x, y + 1),
+                    ] {
+                        if nx < WORLD_SIZE && ny < WORLD_SIZE && holds_water(tiles[z][ny][nx]) {
+                            next += (water.level[z][ny][nx] - here) * SPREAD_RATE / 4.0;
+                        }
+                    }
+                }
+                water.scratch[z][y][x] = next;
+                if next >= BAIL_THRESHOLD {
+                    water.flooded.push((x, y, z));
+                }
+            }
+        }
+    }
+    for z in 0..=table_z {
+        water.level[z] = water.scratch[z];
+    }
+}
+
+/// Ants and brood caught in deep water may drown
+fn drowning(
+    mut commands: Commands,
+    water: Res<Groundwater>,
+    ant_query: Query<(Entity, &GridPosition, &Caste), With<Ant>>,
+    brood_query: Query<(Entity, &GridPosition), With<Brood>>,
+    mut deaths: MessageWriter<AntDied>,
+    mut notifications: MessageWriter<Notification>,
+    mut rng: ResMut<SimRng>,
+) {
+    let deep = |pos: &GridPosition| water.level[pos.z][pos.y][pos.x] >= DROWN_LEVEL;
+
+    for (entity, pos, caste) in &ant_query {
+        if deep(pos) && rng.random_bool(DROWN_CHANCE) {
+            commands.entity(entity).despawn();
+            deaths.write(AntDied {
+                caste: *caste,
+                cause: DeathCause::Drowned,
+            });
+            notifications.write(Notification::warning(format!(
+                "A {} drowned in a flooded tunnel",
+                caste.name().to_lowercase()
+            )));
+        }
+    }
+    for (entity, pos) in &brood_query {
+        if deep(pos) && rng.random_bool(DROWN_CHANCE) {
+            commands.entity(entity).despawn();
+            notifications.write(Notification::warning("Brood drowned in a flooded chamber"));
+        }
+    }
+}
+
+/// Spawn a water sprite over every tile of the view
+fn spawn_water_sprites(mut commands: Commands) {
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            let world_x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+            let world_y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+
+            commands.spawn((
+                Sprite {
+                    color: sprites::tiles::WATER,
+                    custom_size: Some(Vec2::splat(TILE_SIZE)),
+                    ..default()
+                },
+                Transform::from_xyz(world_x, world_y, 0.4), // Over tiles, under overlays
+                WaterSprite { x, y },
+                Visibility::Hidden,
+            ));
+        }
+    }
+}
+
+/// Show standing water on the current z-level, deeper water more opaque
+fn update_water_sprites(
+    water: Res<Groundwater>,
+    current_z: Res<CurrentZLevel>,
+    mut query: Query<(&WaterSprite, &mut Sprite, &mut Visibility)>,
+) {
+    if !water.struck {
+        return;
+    }
+
+    let z = current_z.0;
+    for (water_sprite, mut sprite, mut visibility) in &mut query {
+        let level = water.level[z][water_sprite.y][water_sprite.x];
+        if level > 0.05 {
+            *visibility = Visibility::Visible;
+            sprite.color = sprites::tiles::WATER.with_alpha(0.3 + level * 0.5);
+        } else {
+            *visibility = Visibility::Hidden;
+        }
+    }
+}
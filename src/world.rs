@@ -1,62 +1,357 @@
+use std::collections::BTreeMap;
+use std::path::PathBuf;
+
 use bevy::prelude::*;
 use rand::Rng;
+use serde::Deserialize;
 
+use crate::atlas::AtlasSprite;
+use crate::biome::Biome;
+use crate::config::SimulationConfig;
+use crate::content::Content;
+use crate::exploration::Explored;
+use crate::moisture::SoilMoisture;
+use crate::notifications::Notification;
+use crate::rng::SimRng;
 use crate::sprites;
+use crate::time_controls::{SimulationSet, TICKS_PER_DAY};
+use crate::{ResetRun, despawn_all, reset_resource};
 
 pub const WORLD_SIZE: usize = 64;
 pub const SURFACE_LEVEL: usize = 48;
 pub const TILE_SIZE: f32 = 16.0;
 pub const TREE_HEIGHT: usize = 6; // Trunk + canopy
 
+/// World position of the middle of a tile
+pub fn tile_to_world(x: usize, y: usize) -> Vec2 {
+    Vec2::new(
+        (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE,
+        (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE,
+    )
+}
+
+/// A world-sized grid with every cell set to one value, built straight on the
+/// heap. `Box::new` of an array this size goes through the stack first in
+/// debug builds, which overflows threads with smaller stacks (like tests')
+pub fn filled_grid<T: Copy>(value: T) -> Box<[[[T; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]> {
+    match vec![[[value; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]
+        .into_boxed_slice()
+        .try_into()
+    {
+        Ok(grid) => grid,
+        Err(_) => unreachable!("the grid has WORLD_SIZE layers"),
+    }
+}
+
 pub struct WorldPlugin;
 
 impl Plugin for WorldPlugin {
     fn build(&self, app: &mut App) {
-        app.init_resource::<WorldGrid>()
+        app.add_message::<TileDug>()
+            .init_resource::<WorldGrid>()
+            .init_resource::<Biome>()
+            .init_resource::<WorldOptions>()
             .init_resource::<CurrentZLevel>()
             .init_resource::<FungusGarden>()
-            .add_systems(Startup, (init_world_with_trees, spawn_tile_sprites).chain())
+            .init_resource::<FoodStores>()
+            .add_systems(
+                Startup,
+                (
+                    init_world_with_trees,
+                    init_undergrowth,
+                    init_underground,
+                    dig_founding_nest,
+                    spawn_tile_sprites,
+                )
+                    .chain(),
+            )
             .add_systems(Update, update_tile_sprites)
-            .add_systems(FixedUpdate, fungus_growth);
+            .add_systems(
+                FixedUpdate,
+                (
+                    update_food_capacity,
+                    fungus_growth,
+                    garden_contamination,
+                    food_spoilage,
+                    leaf_regrowth,
+                    root_damage,
+                    tree_withering,
+                    tree_aging,
+                    tree_seeding,
+                    sapling_growth,
+                )
+                    .chain()
+                    .in_set(SimulationSet),
+            )
+            .add_systems(
+                ResetRun,
+                (
+                    reset_resource::<WorldGrid>,
+                    reset_resource::<FungusGarden>,
+                    reset_resource::<FoodStores>,
+                    reset_resource::<CurrentZLevel>,
+                    despawn_all::<Plant>,
+                    despawn_all::<Sapling>,
+                ),
+            );
     }
 }
 
-#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Deserialize)]
 pub enum TileKind {
     #[default]
     Air,
     Surface,
+    /// A hole in the surface over a ramp down into the nest
+    Entrance,
     Dirt,
     Tunnel,
+    /// A sloped tunnel joining its z-level to the ones above and below
+    Ramp,
     Chamber,
     FungusGarden,
     TreeTrunk,
     TreeCanopy,
+    Sapling,
+    Deadwood,
+    Bush,
+    Grass,
+    /// Tree roots reaching down into the soil beneath a trunk
+    Root,
+    /// Bedrock veins and boulders that no ant can dig through
+    Rock,
 }
 
 impl TileKind {
-    pub fn color(&self) -> Color {
+    /// Every tile kind, in the order they're declared
+    pub const ALL: [TileKind; 16] = [
+        TileKind::Air,
+        TileKind::Surface,
+        TileKind::Entrance,
+        TileKind::Dirt,
+        TileKind::Tunnel,
+        TileKind::Ramp,
+        TileKind::Chamber,
+        TileKind::FungusGarden,
+        TileKind::TreeTrunk,
+        TileKind::TreeCanopy,
+        TileKind::Sapling,
+        TileKind::Deadwood,
+        TileKind::Bush,
+        TileKind::Grass,
+        TileKind::Root,
+        TileKind::Rock,
+    ];
+
+    pub fn name(&self) -> &'static str {
         match self {
-            TileKind::Air => sprites::tiles::AIR,
-            TileKind::Surface => sprites::tiles::SURFACE,
-            TileKind::Dirt => sprites::tiles::DIRT,
-            TileKind::Tunnel => sprites::tiles::TUNNEL,
-            TileKind::Chamber => sprites::tiles::CHAMBER,
-            TileKind::FungusGarden => sprites::tiles::FUNGUS_GARDEN,
-            TileKind::TreeTrunk => sprites::tiles::TREE_TRUNK,
-            TileKind::TreeCanopy => sprites::tiles::TREE_CANOPY,
+            TileKind::Air => "Air",
+            TileKind::Surface => "Surface",
+            TileKind::Entrance => "Entrance",
+            TileKind::Dirt => "Dirt",
+            TileKind::Tunnel => "Tunnel",
+            TileKind::Ramp => "Ramp",
+            TileKind::Chamber => "Chamber",
+            TileKind::FungusGarden => "FungusGarden",
+            TileKind::TreeTrunk => "TreeTrunk",
+            TileKind::TreeCanopy => "TreeCanopy",
+            TileKind::Sapling => "Sapling",
+            TileKind::Deadwood => "Deadwood",
+            TileKind::Bush => "Bush",
+            TileKind::Grass => "Grass",
+            TileKind::Root => "Root",
+            TileKind::Rock => "Rock",
         }
     }
+
+    /// Look up a tile kind by its name
+    pub fn from_name(name: &str) -> Option<Self> {
+        TileKind::ALL.into_iter().find(|kind| kind.name() == name)
+    }
+
+    /// Whether the tile belongs to a plant, which owns it
+    pub fn is_plant(&self) -> bool {
+        matches!(
+            self,
+            TileKind::TreeTrunk
+                | TileKind::TreeCanopy
+                | TileKind::Sapling
+                | TileKind::Deadwood
+                | TileKind::Bush
+                | TileKind::Grass
+                | TileKind::Root
+        )
+    }
+
+    /// Check if ants can dig through a tile (roots only slowly, rock never)
+    pub fn is_diggable(&self) -> bool {
+        matches!(self, TileKind::Dirt | TileKind::Root)
+    }
+
+    /// Check if a tile has nothing in it to hold an ant up: open sky, or
+    /// foliage too flimsy to stand on
+    pub fn is_open_air(&self) -> bool {
+        matches!(
+            self,
+            TileKind::Air | TileKind::Sapling | TileKind::Bush | TileKind::Grass
+        )
+    }
+
+    /// Check if a tile can be walked (or climbed) on
+    pub fn is_passable(&self) -> bool {
+        self.is_climbable()
+            || self.is_excavated()
+            || matches!(self, TileKind::Surface | TileKind::Entrance)
+    }
+
+    /// Check if a tile is part of the dug-out nest
+    pub fn is_excavated(&self) -> bool {
+        matches!(
+            self,
+            TileKind::Tunnel | TileKind::Ramp | TileKind::Chamber | TileKind::FungusGarden
+        )
+    }
+
+    /// Check if an ant can move straight up or down between two stacked
+    /// tiles: only by a ramp or by climbing
+    pub fn joins_vertically(lower: TileKind, upper: TileKind) -> bool {
+        lower == TileKind::Ramp
+            || upper == TileKind::Ramp
+            || lower.is_climbable()
+            || upper.is_climbable()
+    }
+
+    /// Check if ants can climb a tile: tree trunks, living or dead, and the
+    /// canopy's branches
+    pub fn is_climbable(&self) -> bool {
+        matches!(
+            self,
+            TileKind::TreeTrunk | TileKind::TreeCanopy | TileKind::Deadwood
+        )
+    }
 }
 
-#[derive(Resource)]
+/// What the soil is made of, stored for every underground tile whether it's
+/// still solid or has been dug out
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum Soil {
+    /// Crumbly topsoil: the baseline for digging and stability
+    #[default]
+    Loam,
+    /// Dense and slow to dig, but holds its shape
+    Clay,
+    /// Quick to dig and quick to give way
+    Sand,
+}
+
+impl Soil {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Soil::Loam => "Loam",
+            Soil::Clay => "Clay",
+            Soil::Sand => "Sand",
+        }
+    }
+
+    /// Chance per tick that a digging ant breaks through a tile of this soil
+    pub fn dig_chance(&self) -> f64 {
+        match self {
+            Soil::Loam => 0.6,
+            Soil::Clay => 0.25,
+            Soil::Sand => 1.0,
+        }
+    }
+
+    /// How well tunnels dug through this soil hold up (0.0 - 1.0)
+    pub fn stability(&self) -> f32 {
+        match self {
+            Soil::Loam => 0.7,
+            Soil::Clay => 1.0,
+            Soil::Sand => 0.3,
+        }
+    }
+
+    /// Color of solid soil of this kind, loam taking the biome's dirt color
+    pub fn color(&self, biome: Biome) -> Color {
+        match self {
+            Soil::Loam => biome.soil_colors().1,
+            Soil::Clay => sprites::tiles::CLAY,
+            Soil::Sand => sprites::tiles::SAND,
+        }
+    }
+
+    /// Cell of solid ground of this soil in the sprite atlas
+    pub fn atlas_index(&self) -> usize {
+        match self {
+            Soil::Loam => sprites::tiles::DIRT_INDEX,
+            Soil::Clay => sprites::tiles::CLAY_INDEX,
+            Soil::Sand => sprites::tiles::SAND_INDEX,
+        }
+    }
+}
+
+/// Sent whenever a solid tile is dug out
+#[derive(Message, Debug, Clone, Copy)]
+pub struct TileDug {
+    pub x: usize,
+    pub y: usize,
+    pub z: usize,
+}
+
+/// Workers founding the colony unless changed on the setup screen
+pub const DEFAULT_STARTING_WORKERS: usize = 5;
+/// Depth below the surface where the soil is saturated with groundwater
+pub const DEFAULT_WATER_TABLE_DEPTH: usize = 28;
+
+/// Starting conditions picked on the setup screen
+#[derive(Resource, Clone)]
+pub struct WorldOptions {
+    /// Trees planted at generation; `None` uses the biome's usual count
+    pub trees: Option<usize>,
+    /// Workers that found the colony alongside the queen
+    pub starting_workers: usize,
+    /// Depth below the surface of the water table (`--water-table <depth>`)
+    pub water_table_depth: usize,
+    /// Start with a lone queen who founds the nest herself (`--founding`)
+    pub founding: bool,
+    /// Map file to play on instead of a generated world (`--map <file>`)
+    pub map: Option<PathBuf>,
+}
+
+impl Default for WorldOptions {
+    fn default() -> Self {
+        Self {
+            trees: None,
+            starting_workers: DEFAULT_STARTING_WORKERS,
+            water_table_depth: DEFAULT_WATER_TABLE_DEPTH,
+            founding: false,
+            map: None,
+        }
+    }
+}
+
+impl WorldOptions {
+    /// Trees to plant in the given biome
+    pub fn tree_count(&self, biome: Biome) -> usize {
+        self.trees.unwrap_or_else(|| biome.tree_count())
+    }
+
+    /// The highest z-level inside the water table
+    pub fn water_table_z(&self) -> usize {
+        SURFACE_LEVEL.saturating_sub(self.water_table_depth)
+    }
+}
+
+#[derive(Resource, Clone)]
 pub struct WorldGrid {
     pub tiles: Box<[[[TileKind; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
+    /// Soil layer of each tile; only meaningful below the surface
+    pub soil: Box<[[[Soil; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]>,
 }
 
 impl Default for WorldGrid {
     fn default() -> Self {
-        let mut tiles = Box::new([[[TileKind::Air; WORLD_SIZE]; WORLD_SIZE]; WORLD_SIZE]);
+        let mut tiles = filled_grid(TileKind::Air);
 
         for z in 0..WORLD_SIZE {
             for y in 0..WORLD_SIZE {
@@ -72,7 +367,10 @@ impl Default for WorldGrid {
             }
         }
 
-        Self { tiles }
+        Self {
+            tiles,
+            soil: filled_grid(Soil::Loam),
+        }
     }
 }
 
@@ -85,22 +383,119 @@ impl Default for WorldGrid {
 pub struct Tree {
     pub x: usize,
     pub y: usize,
+    /// Age in simulation ticks
+    pub age: u32,
+    /// Age after which the tree may die of old age
+    pub lifespan: u32,
 }
 
-/// A leaf source that can be harvested
+/// The kinds of plants foragers can cut leaves from
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize)]
+pub enum PlantKind {
+    Tree,
+    Bush,
+    Grass,
+}
+
+impl PlantKind {
+    pub const ALL: [PlantKind; 3] = [PlantKind::Tree, PlantKind::Bush, PlantKind::Grass];
+
+    /// The tile this plant occupies just above the surface
+    pub fn tile(&self) -> TileKind {
+        match self {
+            PlantKind::Tree => TileKind::TreeTrunk,
+            PlantKind::Bush => TileKind::Bush,
+            PlantKind::Grass => TileKind::Grass,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            PlantKind::Tree => "tree",
+            PlantKind::Bush => "bush",
+            PlantKind::Grass => "grass",
+        }
+    }
+}
+
+/// A plant foragers can harvest, standing on the surface at (x, y)
 #[derive(Component)]
+pub struct Plant {
+    pub kind: PlantKind,
+    pub x: usize,
+    pub y: usize,
+}
+
+impl Plant {
+    /// Where a forager cuts leaves: up in the canopy for trees, which means
+    /// climbing the trunk, and on the ground beside smaller plants
+    pub fn forage_spot(&self) -> (usize, usize, usize) {
+        match self.kind {
+            PlantKind::Tree => (self.x, self.y, SURFACE_LEVEL + 1 + TRUNK_HEIGHT),
+            PlantKind::Bush | PlantKind::Grass => (self.x, self.y, SURFACE_LEVEL),
+        }
+    }
+}
+
+/// A young tree growing from a dropped seed
+#[derive(Component)]
+pub struct Sapling {
+    pub x: usize,
+    pub y: usize,
+    /// Ticks spent growing so far
+    pub growth: u32,
+}
+
+/// Default ticks it takes a tree to regrow a single leaf
+pub const DEFAULT_REGROW_TICKS: f32 = 150.0;
+
+/// A leaf source that can be harvested
+#[derive(Component, Clone)]
 pub struct LeafSource {
     pub leaves_remaining: u32,
+    /// Leaf units a single cut fragment adds to the garden
+    pub leaf_value: u32,
     pub max_leaves: u32,
+    /// Ticks accumulated toward the next regrown leaf
     pub regrow_timer: f32,
+    /// Ticks needed to regrow one leaf
+    pub regrow_ticks: f32,
+    /// Ticks spent stripped bare without recovering
+    pub depleted_ticks: u32,
 }
 
 impl Default for LeafSource {
     fn default() -> Self {
         Self {
             leaves_remaining: 20,
+            leaf_value: 1,
             max_leaves: 20,
             regrow_timer: 0.0,
+            regrow_ticks: DEFAULT_REGROW_TICKS,
+            depleted_ticks: 0,
+        }
+    }
+}
+
+/// Harvested plants slowly regrow their leaves up to `max_leaves`. Plants
+/// kept bare build up stress until they recover a quarter of their leaves.
+fn leaf_regrowth(mut query: Query<&mut LeafSource>) {
+    for mut leaf_source in &mut query {
+        if leaf_source.leaves_remaining == 0 {
+            leaf_source.depleted_ticks += 1;
+        } else if leaf_source.leaves_remaining * 4 >= leaf_source.max_leaves {
+            leaf_source.depleted_ticks = 0;
+        }
+
+        if leaf_source.leaves_remaining >= leaf_source.max_leaves {
+            leaf_source.regrow_timer = 0.0;
+            continue;
+        }
+
+        leaf_source.regrow_timer += 1.0;
+        if leaf_source.regrow_timer >= leaf_source.regrow_ticks {
+            leaf_source.regrow_timer -= leaf_source.regrow_ticks;
+            leaf_source.leaves_remaining += 1;
         }
     }
 }
@@ -109,94 +504,726 @@ impl Default for LeafSource {
 // Fungus Garden Resource
 // ============================================================================
 
-/// The colony's fungus garden - stores leaves, mulch, and food
-#[derive(Resource)]
-pub struct FungusGarden {
-    /// Raw leaves waiting to be processed
-    pub leaves: u32,
+/// Maximum mulch a single garden tile can hold. Since every tile grows food
+/// independently, the dug garden area caps how much food the colony can
+/// produce and therefore how large it can grow.
+pub const MAX_PLOT_MULCH: u32 = 8;
+
+/// A single fungus garden tile and the mulch growing on it
+#[derive(Debug, Clone, Default)]
+pub struct GardenPlot {
     /// Mulch (processed leaves) that fungus grows on
     pub mulch: u32,
-    /// Food available for ants to eat
-    pub food: u32,
     /// Progress toward next food unit (0.0 - 1.0)
     pub growth_progress: f32,
+    /// Mold and bacteria buildup (0.0 - 1.0); above the blight threshold it
+    /// destroys mulch
+    pub contamination: f32,
 }
 
-impl Default for FungusGarden {
-    fn default() -> Self {
-        Self {
-            leaves: 0,
-            mulch: 0,
-            food: 10, // Start with some food so colony doesn't immediately starve
-            growth_progress: 0.0,
-        }
+impl GardenPlot {
+    /// Add contamination, up to the most a tile can hold
+    pub fn contaminate(&mut self, amount: f32) {
+        self.contamination = (self.contamination + amount).min(MAX_CONTAMINATION);
     }
 }
 
+/// The colony's fungus garden - leaves awaiting processing and the garden tiles
+/// gardeners have planted in chambers
+#[derive(Resource, Clone, Default)]
+pub struct FungusGarden {
+    /// Raw leaves waiting at the nest to be processed
+    pub leaves: u32,
+    /// Planted `TileKind::FungusGarden` tiles, keyed by position
+    pub plots: BTreeMap<(usize, usize, usize), GardenPlot>,
+    /// Leaves foragers have brought in over the whole run
+    pub harvested: u32,
+}
+
 impl FungusGarden {
-    /// Add a leaf to the garden (called when forager delivers)
-    pub fn add_leaf(&mut self) {
+    /// Add leaves to the garden (called when forager delivers)
+    pub fn add_leaves(&mut self, amount: u32) {
+        self.leaves += amount;
+        self.harvested += amount;
+    }
+
+    /// Put back a leaf a gardener couldn't use
+    pub fn return_leaf(&mut self) {
         self.leaves += 1;
     }
 
-    /// Gardener processes a leaf into mulch
-    pub fn process_leaf(&mut self) -> bool {
+    /// Gardener picks up a leaf to chew into mulch
+    pub fn take_leaf(&mut self) -> bool {
         if self.leaves > 0 {
             self.leaves -= 1;
-            self.mulch += 1;
             true
         } else {
             false
         }
     }
 
-    /// Try to consume food (returns true if food was available)
+    /// Total mulch across all garden tiles
+    pub fn total_mulch(&self) -> u32 {
+        self.plots.values().map(|plot| plot.mulch).sum()
+    }
+
+    /// Turn a chamber tile into a new garden tile (returns false if not a chamber)
+    pub fn plant(&mut self, world_grid: &mut WorldGrid, x: usize, y: usize, z: usize) -> bool {
+        if world_grid.tiles[z][y][x] != TileKind::Chamber {
+            return false;
+        }
+        world_grid.tiles[z][y][x] = TileKind::FungusGarden;
+        self.plots.insert((x, y, z), GardenPlot::default());
+        true
+    }
+
+    /// Spread mulch on a garden tile (returns false if missing or full)
+    pub fn add_mulch(&mut self, x: usize, y: usize, z: usize) -> bool {
+        match self.plots.get_mut(&(x, y, z)) {
+            Some(plot) if plot.mulch < MAX_PLOT_MULCH => {
+                plot.mulch += 1;
+                true
+            }
+            _ => false,
+        }
+    }
+
+    /// Add contamination to every garden tile within `radius` of a position
+    /// (e.g. a corpse left rotting near the garden)
+    pub fn contaminate_near(&mut self, pos: (usize, usize, usize), radius: usize, amount: f32) {
+        for (plot_pos, plot) in self.plots.iter_mut() {
+            if plot_pos.0.abs_diff(pos.0) <= radius
+                && plot_pos.1.abs_diff(pos.1) <= radius
+                && plot_pos.2.abs_diff(pos.2) <= radius
+            {
+                plot.contaminate(amount);
+            }
+        }
+    }
+
+    /// Clean a garden tile, returning its remaining contamination
+    pub fn clean(&mut self, x: usize, y: usize, z: usize, amount: f32) -> f32 {
+        match self.plots.get_mut(&(x, y, z)) {
+            Some(plot) => {
+                plot.contamination = (plot.contamination - amount).max(0.0);
+                plot.contamination
+            }
+            None => 0.0,
+        }
+    }
+
+    /// The most contaminated garden tile above `threshold`, if any
+    pub fn dirtiest_plot(&self, threshold: f32) -> Option<(usize, usize, usize)> {
+        self.plots
+            .iter()
+            .filter(|(_, plot)| plot.contamination > threshold)
+            .max_by(|a, b| a.1.contamination.total_cmp(&b.1.contamination))
+            .map(|(pos, _)| *pos)
+    }
+
+    /// Highest contamination across all garden tiles
+    pub fn max_contamination(&self) -> f32 {
+        self.plots
+            .values()
+            .map(|plot| plot.contamination)
+            .fold(0.0, f32::max)
+    }
+
+    /// Find the best place to spread mulch near `from`: the nearest garden tile
+    /// with room, otherwise the nearest chamber tile that could be planted
+    pub fn find_site(
+        &self,
+        world_grid: &WorldGrid,
+        from: (usize, usize, usize),
+    ) -> Option<(usize, usize, usize)> {
+        let distance = |pos: &(usize, usize, usize)| {
+            pos.0.abs_diff(from.0) + pos.1.abs_diff(from.1) + pos.2.abs_diff(from.2)
+        };
+
+        let open_plot = self
+            .plots
+            .iter()
+            .filter(|(_, plot)| plot.mulch < MAX_PLOT_MULCH)
+            .map(|(pos, _)| *pos)
+            .min_by_key(distance);
+        if open_plot.is_some() {
+            return open_plot;
+        }
+
+        // Search a box around the nest for an unplanted chamber
+        let min_x = from.0.saturating_sub(GARDEN_SEARCH_RADIUS);
+        let max_x = (from.0 + GARDEN_SEARCH_RADIUS).min(WORLD_SIZE - 1);
+        let min_y = from.1.saturating_sub(GARDEN_SEARCH_RADIUS);
+        let max_y = (from.1 + GARDEN_SEARCH_RADIUS).min(WORLD_SIZE - 1);
+        let min_z = from.2.saturating_sub(GARDEN_SEARCH_RADIUS);
+
+        let mut best = None;
+        for z in min_z..=from.2.min(SURFACE_LEVEL) {
+            for y in min_y..=max_y {
+                for x in min_x..=max_x {
+                    if world_grid.tiles[z][y][x] == TileKind::Chamber
+                        && best.is_none_or(|b| distance(&(x, y, z)) < distance(&b))
+                    {
+                        best = Some((x, y, z));
+                    }
+                }
+            }
+        }
+        best
+    }
+}
+
+/// How far from the nest gardeners look for chambers to plant
+const GARDEN_SEARCH_RADIUS: usize = 12;
+
+/// Fungus grows on each garden tile's mulch and produces food over time
+fn fungus_growth(
+    mut garden: ResMut<FungusGarden>,
+    mut stores: ResMut<FoodStores>,
+    moisture: Res<SoilMoisture>,
+    config: Res<SimulationConfig>,
+) {
+    for (&(x, y, z), plot) in garden.plots.iter_mut() {
+        // No mulch = no growth
+        if plot.mulch == 0 {
+            continue;
+        }
+
+        // Growth rate scales with amount of mulch (diminishing returns)
+        // Base rate from the config, boosted by sqrt(mulch), and slowed when
+        // the chamber is too dry or too wet, then scaled by the difficulty
+        let growth_rate = config.fungus_growth_rate
+            * (plot.mulch as f32).sqrt()
+            * moisture.garden_growth(x, y, z)
+            * config.fungus_yield;
+        plot.growth_progress = (plot.growth_progress + growth_rate).min(1.0);
+
+        // When progress reaches 1.0, harvest food into the stores and consume some mulch.
+        // If the stores are full the ripe fungus waits until there's room.
+        if plot.growth_progress >= 1.0 && stores.store_food() {
+            plot.growth_progress -= 1.0;
+            stores.produced += 1;
+            // Mulch slowly depletes as fungus consumes it, leaving waste behind
+            plot.mulch -= 1;
+            plot.contaminate(WASTE_CONTAMINATION);
+            info!(
+                "Fungus produced food! Stores: {}/{} food",
+                stores.food, stores.capacity
+            );
+        }
+    }
+}
+
+/// Contamination left behind by each unit of food the fungus produces
+const WASTE_CONTAMINATION: f32 = 0.02;
+/// Contamination per tick on a garden tile at full humidity
+const HUMIDITY_CONTAMINATION: f32 = 0.0002;
+/// Contamination above which blight starts destroying a garden tile
+pub const BLIGHT_THRESHOLD: f32 = 0.5;
+/// Most contamination a garden tile can build up, where blight is likeliest
+pub const MAX_CONTAMINATION: f32 = 1.0;
+/// Per-tick chance of losing mulch to blight, scaled by contamination
+const BLIGHT_CHANCE: f32 = 0.01;
+
+/// Relative humidity of a tile (0.0 - 1.0); deeper soil holds more moisture
+pub fn humidity(z: usize) -> f32 {
+    if z >= SURFACE_LEVEL {
+        0.0
+    } else {
+        (SURFACE_LEVEL - z) as f32 / SURFACE_LEVEL as f32
+    }
+}
+
+/// Humidity breeds mold on garden tiles, and badly contaminated tiles lose
+/// mulch and ripening fungus to blight unless gardeners keep them clean
+fn garden_contamination(
+    mut garden: ResMut<FungusGarden>,
+    biome: Res<Biome>,
+    mut rng: ResMut<SimRng>,
+) {
+    for (&(x, y, z), plot) in garden.plots.iter_mut() {
+        plot.contaminate(HUMIDITY_CONTAMINATION * humidity(z) * biome.moisture());
+
+        if plot.contamination < BLIGHT_THRESHOLD || plot.mulch == 0 {
+            continue;
+        }
+
+        if rng.random::<f32>() < BLIGHT_CHANCE * plot.contamination {
+            plot.mulch -= 1;
+            plot.growth_progress = 0.0;
+            info!(
+                "Blight destroyed mulch on garden tile ({}, {}, {}). {} mulch left.",
+                x, y, z, plot.mulch
+            );
+        }
+    }
+}
+
+// ============================================================================
+// Food Stores Resource
+// ============================================================================
+
+/// Food storage capacity available without any dug chambers
+const BASE_FOOD_CAPACITY: u32 = 20;
+/// Extra food capacity provided by each chamber tile
+const FOOD_CAPACITY_PER_CHAMBER: u32 = 5;
+/// Fraction of stored food that spoils per tick
+const SPOILAGE_RATE: f32 = 0.0002;
+
+/// The colony's granary - harvested fungus waiting to be eaten
+#[derive(Resource, Clone)]
+pub struct FoodStores {
+    /// Food available for ants to eat
+    pub food: u32,
+    /// Honeydew collected from aphids; shares storage space but never spoils
+    pub honeydew: u32,
+    /// Seeds gathered as an emergency ration; less nourishing than food
+    pub seeds: u32,
+    /// Maximum food that can be stored, grows with dug chambers
+    pub capacity: u32,
+    /// Progress toward the next unit of food spoiling (0.0 - 1.0)
+    pub spoilage_progress: f32,
+    /// Food the fungus garden has produced over the whole run
+    pub produced: u32,
+}
+
+impl Default for FoodStores {
+    fn default() -> Self {
+        Self {
+            food: 10, // Start with some food so colony doesn't immediately starve
+            honeydew: 0,
+            seeds: 0,
+            capacity: BASE_FOOD_CAPACITY,
+            spoilage_progress: 0.0,
+            produced: 0,
+        }
+    }
+}
+
+impl FoodStores {
+    /// Food, honeydew, and seeds currently stored
+    pub fn total(&self) -> u32 {
+        self.food + self.honeydew + self.seeds
+    }
+
+    /// Try to store a unit of food (returns false if the stores are full)
+    pub fn store_food(&mut self) -> bool {
+        if self.total() < self.capacity {
+            self.food += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Try to store a unit of honeydew (returns false if the stores are full)
+    pub fn store_honeydew(&mut self) -> bool {
+        if self.total() < self.capacity {
+            self.honeydew += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Try to store a seed (returns false if the stores are full)
+    pub fn store_seed(&mut self) -> bool {
+        if self.total() < self.capacity {
+            self.seeds += 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Try to eat a stored seed once the real food has run out
+    pub fn consume_seed(&mut self) -> bool {
+        if self.seeds > 0 {
+            self.seeds -= 1;
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Try to consume food, eating perishable fungus before honeydew
+    /// (returns true if food was available)
     pub fn consume_food(&mut self) -> bool {
         if self.food > 0 {
             self.food -= 1;
             true
+        } else if self.honeydew > 0 {
+            self.honeydew -= 1;
+            true
         } else {
             false
         }
     }
 }
 
-/// Fungus grows on mulch and produces food over time
-fn fungus_growth(mut garden: ResMut<FungusGarden>) {
-    // No mulch = no growth
-    if garden.mulch == 0 {
+/// Recompute storage capacity from the number of chamber tiles in the nest
+fn update_food_capacity(world_grid: Res<WorldGrid>, mut stores: ResMut<FoodStores>) {
+    if !world_grid.is_changed() {
         return;
     }
 
-    // Growth rate scales with amount of mulch (diminishing returns)
-    // Base rate: 0.01 per tick, boosted by sqrt(mulch)
-    let growth_rate = 0.005 * (garden.mulch as f32).sqrt();
-    garden.growth_progress += growth_rate;
+    let chamber_tiles = world_grid.tiles[..SURFACE_LEVEL]
+        .iter()
+        .flatten()
+        .flatten()
+        .filter(|tile| **tile == TileKind::Chamber)
+        .count() as u32;
+
+    let capacity = BASE_FOOD_CAPACITY + chamber_tiles * FOOD_CAPACITY_PER_CHAMBER;
+    if capacity != stores.capacity {
+        stores.capacity = capacity;
+        info!("Food storage capacity is now {}", capacity);
+    }
+}
+
+/// Stored food slowly spoils, and anything over capacity rots right away
+fn food_spoilage(mut stores: ResMut<FoodStores>) {
+    if stores.total() > stores.capacity {
+        let lost = (stores.total() - stores.capacity).min(stores.food);
+        stores.food -= lost;
+        info!("{} food spoiled for lack of storage space", lost);
+    }
+
+    if stores.food == 0 {
+        stores.spoilage_progress = 0.0;
+        return;
+    }
 
-    // When progress reaches 1.0, produce food and consume some mulch
-    if garden.growth_progress >= 1.0 {
-        garden.growth_progress -= 1.0;
-        garden.food += 1;
-        // Mulch slowly depletes as fungus consumes it
-        if garden.mulch > 0 {
-            garden.mulch -= 1;
+    stores.spoilage_progress += stores.food as f32 * SPOILAGE_RATE;
+    if stores.spoilage_progress >= 1.0 {
+        stores.spoilage_progress -= 1.0;
+        stores.food -= 1;
+        info!("Some stored food spoiled. {} food remaining.", stores.food);
+    }
+}
+
+// ============================================================================
+// Tree Lifecycle
+// ============================================================================
+
+/// Shortest and longest natural tree lifespans, in ticks
+const TREE_LIFESPAN: std::ops::Range<u32> = 20 * TICKS_PER_DAY..40 * TICKS_PER_DAY;
+/// Per-tick chance that a tree past its lifespan dies
+const OLD_TREE_DEATH_CHANCE: f64 = 1.0 / 2000.0;
+/// Per-tick chance that a tree's canopy drops a seed
+const SEED_DROP_CHANCE: f64 = 1.0 / 3000.0;
+/// How far from the trunk a dropped seed can land
+const SEED_RADIUS: i32 = 5;
+/// Trees and saplings the surface can hold before seeds stop taking root
+pub const MAX_TREES: usize = 24;
+/// Ticks for a sapling to grow into a full tree
+const SAPLING_GROWTH_TICKS: u32 = 3 * TICKS_PER_DAY;
+
+/// Ticks a tree can stay stripped bare before it withers
+const WITHER_TICKS: u32 = TICKS_PER_DAY / 2;
+
+/// How far roots reach below a trunk
+const ROOT_DEPTH: usize = 3;
+/// Depth below the surface at which roots spread sideways
+const ROOT_SPREAD_DEPTH: usize = 2;
+
+/// Stripping a tree bare kills off part of its roots, and a tree with damaged
+/// roots (from harvesting or ants tunnelling through them) regrows leaves more
+/// slowly. A tree with no roots left withers.
+fn root_damage(
+    mut world_grid: ResMut<WorldGrid>,
+    mut query: Query<(&Tree, &mut LeafSource)>,
+    biome: Res<Biome>,
+    content: Res<Content>,
+    mut rng: ResMut<SimRng>,
+) {
+    let base_regrow_ticks = content.leaf_source(PlantKind::Tree, *biome).regrow_ticks;
+
+    for (tree, mut leaf_source) in &mut query {
+        let roots = root_tiles(tree.x, tree.y);
+        let mut living: Vec<_> = roots
+            .iter()
+            .copied()
+            .filter(|&(x, y, z)| world_grid.tiles[z][y][x] == TileKind::Root)
+            .collect();
+
+        // Just stripped bare
+        if leaf_source.depleted_ticks == 1 && !living.is_empty() {
+            let (x, y, z) = living.swap_remove(rng.random_range(0..living.len()));
+            world_grid.tiles[z][y][x] = TileKind::Dirt;
+            info!(
+                "Over-harvesting damaged the roots of the tree at ({}, {})",
+                tree.x, tree.y
+            );
         }
+
+        if living.is_empty() {
+            leaf_source.depleted_ticks = leaf_source.depleted_ticks.max(WITHER_TICKS);
+            continue;
+        }
+
+        let health = living.len() as f32 / roots.len() as f32;
+        leaf_source.regrow_ticks = base_regrow_ticks / health;
+    }
+}
+
+/// Trees that are over-harvested for too long wither and die
+fn tree_withering(
+    mut commands: Commands,
+    mut world_grid: ResMut<WorldGrid>,
+    query: Query<(Entity, &Tree, &LeafSource)>,
+    mut notifications: MessageWriter<Notification>,
+) {
+    for (entity, tree, leaf_source) in &query {
+        if leaf_source.depleted_ticks >= WITHER_TICKS {
+            kill_tree(&mut commands, &mut world_grid, entity, tree);
+            info!("Over-harvested tree at ({}, {}) withered", tree.x, tree.y);
+            notifications.write(Notification::warning(
+                "A tree withered from over-harvesting - spread foraging across more trees",
+            ));
+        }
+    }
+}
+
+/// Old trees eventually die and leave deadwood behind
+fn tree_aging(
+    mut commands: Commands,
+    mut world_grid: ResMut<WorldGrid>,
+    mut query: Query<(Entity, &mut Tree, &LeafSource)>,
+    mut rng: ResMut<SimRng>,
+) {
+    for (entity, mut tree, leaf_source) in &mut query {
+        tree.age = tree.age.saturating_add(1);
+        // Already withering this tick
+        if leaf_source.depleted_ticks >= WITHER_TICKS {
+            continue;
+        }
+        if tree.age > tree.lifespan && rng.random_bool(OLD_TREE_DEATH_CHANCE) {
+            kill_tree(&mut commands, &mut world_grid, entity, &tree);
+            info!("An old tree at ({}, {}) died", tree.x, tree.y);
+        }
+    }
+}
+
+/// Canopies occasionally drop seeds that sprout into saplings nearby
+fn tree_seeding(
+    mut commands: Commands,
+    mut world_grid: ResMut<WorldGrid>,
+    tree_query: Query<&Tree>,
+    sapling_query: Query<&Sapling>,
+    mut rng: ResMut<SimRng>,
+) {
+    let mut population = tree_query.iter().count() + sapling_query.iter().count();
+
+    for tree in &tree_query {
+        if population >= MAX_TREES {
+            return;
+        }
+        if !rng.random_bool(SEED_DROP_CHANCE) {
+            continue;
+        }
+
+        let x = tree.x as i32 + rng.random_range(-SEED_RADIUS..=SEED_RADIUS);
+        let y = tree.y as i32 + rng.random_range(-SEED_RADIUS..=SEED_RADIUS);
+        if try_plant_sapling(&mut commands, &mut world_grid, x, y) {
+            population += 1;
+        }
+    }
+}
+
+/// Sprout a sapling at a surface position if a seed can take root there
+pub fn try_plant_sapling(
+    commands: &mut Commands,
+    world_grid: &mut WorldGrid,
+    x: i32,
+    y: i32,
+) -> bool {
+    if x < 1 || y < 1 || x >= WORLD_SIZE as i32 - 1 || y >= WORLD_SIZE as i32 - 1 {
+        return false;
+    }
+
+    let (x, y) = (x as usize, y as usize);
+    if !can_sprout(world_grid, x, y) {
+        return false;
+    }
+
+    world_grid.tiles[SURFACE_LEVEL + 1][y][x] = TileKind::Sapling;
+    commands.spawn(Sapling { x, y, growth: 0 });
+    info!("A seed sprouted into a sapling at ({}, {})", x, y);
+    true
+}
+
+/// Saplings grow into full trees once they're old enough
+fn sapling_growth(
+    mut commands: Commands,
+    mut world_grid: ResMut<WorldGrid>,
+    mut query: Query<(Entity, &mut Sapling)>,
+    biome: Res<Biome>,
+    content: Res<Content>,
+    mut rng: ResMut<SimRng>,
+) {
+    for (entity, mut sapling) in &mut query {
+        sapling.growth += 1;
+        if sapling.growth < SAPLING_GROWTH_TICKS {
+            continue;
+        }
+
+        commands.entity(entity).despawn();
+        world_grid.tiles[SURFACE_LEVEL + 1][sapling.y][sapling.x] = TileKind::Air;
+        spawn_tree(
+            &mut commands,
+            &mut world_grid,
+            &mut *rng,
+            sapling.x,
+            sapling.y,
+            0,
+            content.leaf_source(PlantKind::Tree, *biome),
+        );
         info!(
-            "Fungus produced food! Garden: {} leaves, {} mulch, {} food",
-            garden.leaves, garden.mulch, garden.food
+            "A sapling at ({}, {}) grew into a tree",
+            sapling.x, sapling.y
         );
     }
 }
 
+/// Check whether a seed can take root at a surface position: open ground with
+/// no other tree or sapling crowding it
+fn can_sprout(world_grid: &WorldGrid, x: usize, y: usize) -> bool {
+    if world_grid.tiles[SURFACE_LEVEL][y][x] != TileKind::Surface {
+        return false;
+    }
+
+    for ny in y - 1..=y + 1 {
+        for nx in x - 1..=x + 1 {
+            if world_grid.tiles[SURFACE_LEVEL + 1][ny][nx] != TileKind::Air {
+                return false;
+            }
+        }
+    }
+    true
+}
+
+/// Kill a tree: its canopy falls away, the trunk is left as deadwood, and it
+/// stops being a leaf source
+pub fn kill_tree(commands: &mut Commands, world_grid: &mut WorldGrid, entity: Entity, tree: &Tree) {
+    for (x, y, z) in canopy_tiles(tree.x, tree.y) {
+        if world_grid.tiles[z][y][x] == TileKind::TreeCanopy {
+            world_grid.tiles[z][y][x] = TileKind::Air;
+        }
+    }
+    for (x, y, z) in trunk_tiles(tree.x, tree.y) {
+        if world_grid.tiles[z][y][x] == TileKind::TreeTrunk {
+            world_grid.tiles[z][y][x] = TileKind::Deadwood;
+        }
+    }
+    commands.entity(entity).despawn();
+}
+
+/// Plant a young tree standing on the surface at (x, y), in full leaf
+pub fn plant_tree(
+    commands: &mut Commands,
+    world_grid: &mut WorldGrid,
+    rng: &mut impl Rng,
+    x: usize,
+    y: usize,
+    leaves: LeafSource,
+) {
+    spawn_tree(commands, world_grid, rng, x, y, 0, leaves);
+}
+
+/// Clear a tree's trunk, canopy and roots away entirely, leaving open air and
+/// soil. Callers are responsible for despawning the tree entity.
+pub fn clear_tree(world_grid: &mut WorldGrid, x: usize, y: usize) {
+    for (cx, cy, cz) in canopy_tiles(x, y) {
+        if world_grid.tiles[cz][cy][cx] == TileKind::TreeCanopy {
+            world_grid.tiles[cz][cy][cx] = TileKind::Air;
+        }
+    }
+    for (tx, ty, tz) in trunk_tiles(x, y) {
+        world_grid.tiles[tz][ty][tx] = TileKind::Air;
+    }
+    for (rx, ry, rz) in root_tiles(x, y) {
+        if world_grid.tiles[rz][ry][rx] == TileKind::Root {
+            world_grid.tiles[rz][ry][rx] = TileKind::Dirt;
+        }
+    }
+}
+
 // ============================================================================
 // Systems
 // ============================================================================
 
 /// Initialize the world with trees
-fn init_world_with_trees(mut commands: Commands, mut world_grid: ResMut<WorldGrid>) {
-    let mut rng = rand::rng();
-    let num_trees = 8; // Start with a few trees
+fn init_world_with_trees(
+    mut commands: Commands,
+    mut world_grid: ResMut<WorldGrid>,
+    biome: Res<Biome>,
+    content: Res<Content>,
+    options: Res<WorldOptions>,
+    mut rng: ResMut<SimRng>,
+) {
+    let trees = options.tree_count(*biome);
+    let leaves = content.leaf_source(PlantKind::Tree, *biome);
+    plant_trees(&mut commands, &mut world_grid, &mut *rng, leaves, trees);
+}
+
+/// Scatter bushes and grass tufts across open ground as quick-regrowing,
+/// low-value forage
+fn init_undergrowth(
+    mut commands: Commands,
+    mut world_grid: ResMut<WorldGrid>,
+    biome: Res<Biome>,
+    content: Res<Content>,
+    mut rng: ResMut<SimRng>,
+) {
+    plant_undergrowth(&mut commands, &mut world_grid, &mut *rng, *biome, &content);
+}
+
+/// Lay the soil layers and the rock running through them
+fn init_underground(mut world_grid: ResMut<WorldGrid>, biome: Res<Biome>, mut rng: ResMut<SimRng>) {
+    generate_underground(&mut world_grid, &mut *rng, *biome);
+}
+
+/// Clear everything above the surface, and the roots and rock beneath it, so
+/// it can be generated again. Callers are responsible for despawning the plant
+/// entities that stood there.
+pub fn clear_surface(world_grid: &mut WorldGrid) {
+    for z in SURFACE_LEVEL + 1..WORLD_SIZE {
+        for row in world_grid.tiles[z].iter_mut() {
+            row.fill(TileKind::Air);
+        }
+    }
+    for level in world_grid.tiles[..SURFACE_LEVEL].iter_mut() {
+        for tile in level.iter_mut().flatten() {
+            if matches!(tile, TileKind::Root | TileKind::Rock) {
+                *tile = TileKind::Dirt;
+            }
+        }
+    }
+}
 
+/// Generate a fresh set of trees and undergrowth on the surface
+pub fn generate_surface(
+    commands: &mut Commands,
+    world_grid: &mut WorldGrid,
+    rng: &mut impl Rng,
+    biome: Biome,
+    content: &Content,
+    trees: usize,
+) {
+    let leaves = content.leaf_source(PlantKind::Tree, biome);
+    plant_trees(commands, world_grid, rng, leaves, trees);
+    plant_undergrowth(commands, world_grid, rng, biome, content);
+}
+
+/// Plant the starting trees, keeping clear of the nest
+fn plant_trees(
+    commands: &mut Commands,
+    world_grid: &mut WorldGrid,
+    rng: &mut impl Rng,
+    leaves: LeafSource,
+    num_trees: usize,
+) {
     for _ in 0..num_trees {
         // Random position, but not too close to center (where queen spawns)
         let x = rng.random_range(5..WORLD_SIZE - 5);
@@ -208,56 +1235,333 @@ fn init_world_with_trees(mut commands: Commands, mut world_grid: ResMut<WorldGri
             continue;
         }
 
-        spawn_tree(&mut commands, &mut world_grid, x, y);
+        // Start trees at staggered ages so they don't all die together
+        let age = rng.random_range(0..TREE_LIFESPAN.start);
+        spawn_tree(commands, world_grid, rng, x, y, age, leaves.clone());
     }
 
     info!("Spawned trees in the world");
 }
 
-/// Spawn a tree at the given surface position
-fn spawn_tree(commands: &mut Commands, world_grid: &mut WorldGrid, x: usize, y: usize) {
-    let base_z = SURFACE_LEVEL + 1;
+/// Plant bushes and grass tufts on open ground, keeping the entrance clear
+fn plant_undergrowth(
+    commands: &mut Commands,
+    world_grid: &mut WorldGrid,
+    rng: &mut impl Rng,
+    biome: Biome,
+    content: &Content,
+) {
+    let center = WORLD_SIZE / 2;
+    let (bushes, grass) = biome.undergrowth_counts();
+
+    for (kind, count) in [(PlantKind::Bush, bushes), (PlantKind::Grass, grass)] {
+        for _ in 0..count {
+            let x = rng.random_range(2..WORLD_SIZE - 2);
+            let y = rng.random_range(2..WORLD_SIZE - 2);
+
+            // Keep the nest entrance clear
+            if (x as i32 - center as i32).abs() < 3 && (y as i32 - center as i32).abs() < 3 {
+                continue;
+            }
+            if world_grid.tiles[SURFACE_LEVEL + 1][y][x] != TileKind::Air {
+                continue;
+            }
 
-    // Create trunk (3 tiles high)
-    for z_offset in 0..3 {
-        let z = base_z + z_offset;
-        if z < WORLD_SIZE {
-            world_grid.tiles[z][y][x] = TileKind::TreeTrunk;
+            world_grid.tiles[SURFACE_LEVEL + 1][y][x] = kind.tile();
+            commands.spawn((Plant { kind, x, y }, content.leaf_source(kind, biome)));
+        }
+    }
+
+    info!("Spawned bushes and grass in the world");
+}
+
+/// Generate what lies beneath the surface: rock, then the soil layers
+pub fn generate_underground(world_grid: &mut WorldGrid, rng: &mut impl Rng, biome: Biome) {
+    generate_rock(world_grid, rng);
+    generate_soil(world_grid, rng, biome);
+}
+
+/// Depth of the topsoil before the first layer change
+const TOPSOIL_DEPTH: std::ops::Range<usize> = 4..8;
+/// Thickness of each deeper soil layer
+const LAYER_THICKNESS: std::ops::Range<usize> = 3..9;
+/// Most a layer boundary rises or sinks across the map
+const LAYER_UNDULATION: f32 = 2.0;
+
+/// Fill the underground with stratified soil: a band of topsoil (sand in the
+/// desert, loam elsewhere) over alternating layers of clay, loam, and sand
+/// whose boundaries gently rise and fall across the map
+fn generate_soil(world_grid: &mut WorldGrid, rng: &mut impl Rng, biome: Biome) {
+    let topsoil = if biome == Biome::Desert {
+        Soil::Sand
+    } else {
+        Soil::Loam
+    };
+
+    // Layers from the surface down, each with its base depth and a wave
+    // (frequency and phases) bending the boundary beneath it
+    let mut layers = Vec::new();
+    let mut depth = rng.random_range(TOPSOIL_DEPTH);
+    let mut kind = topsoil;
+    while depth < SURFACE_LEVEL {
+        let wave = (
+            rng.random_range(0.05..0.2_f32),
+            rng.random_range(0.0..std::f32::consts::TAU),
+            rng.random_range(0.0..std::f32::consts::TAU),
+        );
+        layers.push((kind, depth, wave));
+
+        kind = match (kind, rng.random_range(0..2)) {
+            (Soil::Loam, 0) | (Soil::Sand, 0) => Soil::Clay,
+            (Soil::Clay, 0) | (Soil::Sand, _) => Soil::Loam,
+            (Soil::Loam, _) | (Soil::Clay, _) => Soil::Sand,
+        };
+        depth += rng.random_range(LAYER_THICKNESS);
+    }
+
+    for y in 0..WORLD_SIZE {
+        for x in 0..WORLD_SIZE {
+            for z in 0..SURFACE_LEVEL {
+                let tile_depth = (SURFACE_LEVEL - z) as f32;
+                let soil = layers
+                    .iter()
+                    .find(|(_, base, (frequency, phase_x, phase_y))| {
+                        let bend = ((x as f32 * frequency + phase_x).sin()
+                            + (y as f32 * frequency + phase_y).sin())
+                            * LAYER_UNDULATION
+                            / 2.0;
+                        tile_depth <= *base as f32 + bend
+                    })
+                    .map_or(kind, |(soil, _, _)| *soil);
+                world_grid.soil[z][y][x] = soil;
+            }
+        }
+    }
+
+    info!("Laid {} soil layers", layers.len() + 1);
+}
+
+/// Rock veins winding through the soil
+const ROCK_VEINS: usize = 10;
+/// Tiles each vein runs for
+const VEIN_LENGTH: std::ops::Range<usize> = 20..40;
+/// Round boulders buried near the surface
+const BOULDERS: usize = 8;
+/// Shallowest depth rock appears at, leaving the topsoil free to dig
+const ROCK_MIN_DEPTH: usize = 2;
+/// Deepest a boulder sits
+const BOULDER_MAX_DEPTH: usize = 10;
+
+/// Lay undiggable rock into the soil: long meandering veins at any depth and
+/// boulders in the shallows. Only plain dirt is turned to rock, so roots and
+/// the nest are left alone, and the random draws don't depend on what's
+/// already there, so the same seed always lays the same rock.
+fn generate_rock(world_grid: &mut WorldGrid, rng: &mut impl Rng) {
+    for _ in 0..ROCK_VEINS {
+        let mut x = rng.random_range(0..WORLD_SIZE) as i32;
+        let mut y = rng.random_range(0..WORLD_SIZE) as i32;
+        let mut z = rng.random_range(0..SURFACE_LEVEL - ROCK_MIN_DEPTH) as i32;
+        let length = rng.random_range(VEIN_LENGTH);
+
+        for _ in 0..length {
+            place_rock(world_grid, x, y, z);
+            // Veins are two tiles thick in a random direction
+            match rng.random_range(0..3) {
+                0 => place_rock(world_grid, x + 1, y, z),
+                1 => place_rock(world_grid, x, y + 1, z),
+                _ => place_rock(world_grid, x, y, z + 1),
+            }
+
+            // Wander mostly sideways, drifting up or down now and then
+            match rng.random_range(0..10) {
+                0..=3 => x += if rng.random_bool(0.5) { 1 } else { -1 },
+                4..=7 => y += if rng.random_bool(0.5) { 1 } else { -1 },
+                _ => z += if rng.random_bool(0.5) { 1 } else { -1 },
+            }
         }
     }
 
-    // Create canopy (3 tiles high, with some spread)
-    let canopy_base = base_z + 3;
-    for z_offset in 0..3 {
+    for _ in 0..BOULDERS {
+        let x = rng.random_range(0..WORLD_SIZE) as i32;
+        let y = rng.random_range(0..WORLD_SIZE) as i32;
+        let depth = rng.random_range(ROCK_MIN_DEPTH..=BOULDER_MAX_DEPTH);
+        let z = (SURFACE_LEVEL - depth) as i32;
+        for (dx, dy, dz) in [(0, 0, 0), (1, 0, 0), (0, 1, 0), (1, 1, 0), (0, 0, -1)] {
+            place_rock(world_grid, x + dx, y + dy, z + dz);
+        }
+    }
+
+    info!("Laid rock veins and boulders");
+}
+
+/// Turn a dirt tile to rock if it's on the map and deep enough
+fn place_rock(world_grid: &mut WorldGrid, x: i32, y: i32, z: i32) {
+    let range = 0..WORLD_SIZE as i32;
+    if !range.contains(&x) || !range.contains(&y) || z < 0 {
+        return;
+    }
+    let (x, y, z) = (x as usize, y as usize, z as usize);
+    if z + ROCK_MIN_DEPTH <= SURFACE_LEVEL && world_grid.tiles[z][y][x] == TileKind::Dirt {
+        world_grid.tiles[z][y][x] = TileKind::Rock;
+    }
+}
+
+/// Depth of the shaft leading from the nest entrance down to the founding chamber
+pub const FOUNDING_SHAFT_DEPTH: usize = 4;
+
+/// Dig out the established nest to start in, unless the queen is founding
+/// it herself
+fn dig_founding_nest(
+    mut world_grid: ResMut<WorldGrid>,
+    mut garden: ResMut<FungusGarden>,
+    options: Res<WorldOptions>,
+) {
+    if !options.founding {
+        dig_founding_chamber(&mut world_grid, &mut garden);
+    }
+}
+
+/// Dig the founding queen's shaft and first chamber beneath the nest, with a
+/// single garden tile already planted so the colony can start farming
+pub fn dig_founding_chamber(world_grid: &mut WorldGrid, garden: &mut FungusGarden) {
+    let center = WORLD_SIZE / 2;
+
+    for depth in 1..=FOUNDING_SHAFT_DEPTH {
+        world_grid.tiles[SURFACE_LEVEL - depth][center][center] = TileKind::Ramp;
+    }
+
+    let chamber_z = SURFACE_LEVEL - FOUNDING_SHAFT_DEPTH - 1;
+    for y in center - 1..=center + 1 {
+        for x in center - 1..=center + 1 {
+            world_grid.tiles[chamber_z][y][x] = TileKind::Chamber;
+        }
+    }
+    garden.plant(world_grid, center, center, chamber_z);
+
+    info!("Dug founding chamber at depth {}", chamber_z);
+}
+
+/// Fill the founding shaft and chamber back in, for a start where the queen
+/// digs her own
+pub fn fill_founding_chamber(world_grid: &mut WorldGrid, garden: &mut FungusGarden) {
+    let center = WORLD_SIZE / 2;
+
+    for depth in 1..=FOUNDING_SHAFT_DEPTH {
+        world_grid.tiles[SURFACE_LEVEL - depth][center][center] = TileKind::Dirt;
+    }
+
+    let chamber_z = SURFACE_LEVEL - FOUNDING_SHAFT_DEPTH - 1;
+    for y in center - 1..=center + 1 {
+        for x in center - 1..=center + 1 {
+            world_grid.tiles[chamber_z][y][x] = TileKind::Dirt;
+            garden.plots.remove(&(x, y, chamber_z));
+        }
+    }
+}
+
+/// Height of a tree trunk in tiles
+const TRUNK_HEIGHT: usize = 3;
+
+/// Tile positions of a tree's trunk
+fn trunk_tiles(x: usize, y: usize) -> Vec<(usize, usize, usize)> {
+    let base_z = SURFACE_LEVEL + 1;
+    (base_z..base_z + TRUNK_HEIGHT)
+        .filter(|z| *z < WORLD_SIZE)
+        .map(|z| (x, y, z))
+        .collect()
+}
+
+/// Tile positions of a tree's canopy (3 tiles high, spreading in the middle)
+fn canopy_tiles(x: usize, y: usize) -> Vec<(usize, usize, usize)> {
+    let canopy_base = SURFACE_LEVEL + 1 + TRUNK_HEIGHT;
+    let mut tiles = Vec::new();
+
+    for z_offset in 0..TREE_HEIGHT - TRUNK_HEIGHT {
         let z = canopy_base + z_offset;
         if z >= WORLD_SIZE {
             continue;
         }
 
         // Canopy spreads out
-        let spread = if z_offset == 1 { 1 } else { 0 };
-        for dy in -(spread as i32)..=(spread as i32) {
-            for dx in -(spread as i32)..=(spread as i32) {
+        let spread: i32 = if z_offset == 1 { 1 } else { 0 };
+        for dy in -spread..=spread {
+            for dx in -spread..=spread {
                 let nx = (x as i32 + dx).clamp(0, WORLD_SIZE as i32 - 1) as usize;
                 let ny = (y as i32 + dy).clamp(0, WORLD_SIZE as i32 - 1) as usize;
-                world_grid.tiles[z][ny][nx] = TileKind::TreeCanopy;
+                tiles.push((nx, ny, z));
             }
         }
     }
+    tiles
+}
+
+/// Tile positions of a tree's roots: straight down under the trunk, spreading
+/// out partway down
+fn root_tiles(x: usize, y: usize) -> Vec<(usize, usize, usize)> {
+    let mut tiles: Vec<_> = (1..=ROOT_DEPTH)
+        .map(|d| (x, y, SURFACE_LEVEL - d))
+        .collect();
+
+    let z = SURFACE_LEVEL - ROOT_SPREAD_DEPTH;
+    for (dx, dy) in [(-1, 0), (1, 0), (0, -1), (0, 1)] {
+        let nx = x as i32 + dx;
+        let ny = y as i32 + dy;
+        if (0..WORLD_SIZE as i32).contains(&nx) && (0..WORLD_SIZE as i32).contains(&ny) {
+            tiles.push((nx as usize, ny as usize, z));
+        }
+    }
+    tiles
+}
+
+/// Spawn a tree at the given surface position, `age` ticks old, with its
+/// leaves
+fn spawn_tree(
+    commands: &mut Commands,
+    world_grid: &mut WorldGrid,
+    rng: &mut impl Rng,
+    x: usize,
+    y: usize,
+    age: u32,
+    leaves: LeafSource,
+) {
+    for (tx, ty, tz) in trunk_tiles(x, y) {
+        world_grid.tiles[tz][ty][tx] = TileKind::TreeTrunk;
+    }
+    // Roots only grow into untouched soil, never into the nest
+    for (rx, ry, rz) in root_tiles(x, y) {
+        if world_grid.tiles[rz][ry][rx] == TileKind::Dirt {
+            world_grid.tiles[rz][ry][rx] = TileKind::Root;
+        }
+    }
+    for (cx, cy, cz) in canopy_tiles(x, y) {
+        world_grid.tiles[cz][cy][cx] = TileKind::TreeCanopy;
+    }
 
     // Spawn tree entity with leaf source at canopy level
-    let canopy_z = canopy_base + 1;
+    let canopy_z = SURFACE_LEVEL + 1 + TRUNK_HEIGHT + 1;
     let world_x = (x as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
     let world_y = (y as f32 - WORLD_SIZE as f32 / 2.0) * TILE_SIZE;
+    let lifespan = rng.random_range(TREE_LIFESPAN);
 
     commands.spawn((
-        Tree { x, y },
-        LeafSource::default(),
+        Tree {
+            x,
+            y,
+            age,
+            lifespan,
+        },
+        Plant {
+            kind: PlantKind::Tree,
+            x,
+            y,
+        },
+        leaves,
         Sprite {
             color: sprites::objects::LEAF_FRAGMENT,
             custom_size: Some(Vec2::splat(TILE_SIZE * 0.5)),
             ..default()
         },
+        AtlasSprite(sprites::objects::LEAF_INDEX),
         Transform::from_xyz(world_x, world_y, 0.8),
         TreeCanopyMarker { z: canopy_z },
     ));
@@ -284,7 +1588,7 @@ pub struct TileSprite {
     pub y: usize,
 }
 
-fn spawn_tile_sprites(mut commands: Commands) {
+fn spawn_tile_sprites(mut commands: Commands, content: Res<Content>) {
     // Spawn a sprite for each tile position in the current view
     for y in 0..WORLD_SIZE {
         for x in 0..WORLD_SIZE {
@@ -299,6 +1603,7 @@ fn spawn_tile_sprites(mut commands: Commands) {
                 },
                 Transform::from_xyz(world_x, world_y, 0.0),
                 TileSprite { x, y },
+                AtlasSprite(content.tile(TileKind::default()).atlas_index),
             ));
         }
     }
@@ -307,15 +1612,38 @@ fn spawn_tile_sprites(mut commands: Commands) {
 fn update_tile_sprites(
     world_grid: Res<WorldGrid>,
     current_z: Res<CurrentZLevel>,
-    mut query: Query<(&TileSprite, &mut Sprite)>,
+    biome: Res<Biome>,
+    explored: Res<Explored>,
+    content: Res<Content>,
+    mut query: Query<(&TileSprite, &mut Sprite, &mut AtlasSprite)>,
 ) {
-    if !current_z.is_changed() && !world_grid.is_changed() {
+    if !current_z.is_changed()
+        && !world_grid.is_changed()
+        && !biome.is_changed()
+        && !explored.is_changed()
+        && !content.is_changed()
+    {
         return;
     }
 
     let z = current_z.0;
-    for (tile_sprite, mut sprite) in &mut query {
-        let tile_kind = world_grid.tiles[z][tile_sprite.y][tile_sprite.x];
-        sprite.color = tile_kind.color();
+    for (tile_sprite, mut sprite, mut cell) in &mut query {
+        let (x, y) = (tile_sprite.x, tile_sprite.y);
+        let tile_kind = world_grid.tiles[z][y][x];
+        let (color, index) = if !explored.shows(tile_kind, x, y, z) {
+            (sprites::tiles::UNEXPLORED, sprites::tiles::UNEXPLORED_INDEX)
+        } else if tile_kind == TileKind::Dirt {
+            let soil = world_grid.soil[z][y][x];
+            (soil.color(*biome), soil.atlas_index())
+        } else {
+            (
+                content.biome_color(tile_kind, *biome),
+                content.tile(tile_kind).atlas_index,
+            )
+        };
+        sprite.color = color;
+        // Only touch the cell when it moves, so the atlas isn't reapplied to
+        // every tile each time anything changes
+        cell.set_if_neq(AtlasSprite(index));
     }
 }
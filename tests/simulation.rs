@@ -0,0 +1,276 @@
+//! Regression tests for the colony's behavior, run headless against the
+//! simulation library.
+//!
+//! Each test builds an app with `MinimalPlugins` and a fixed seed, so the
+//! same world is generated every run, and advances it with `acre::step()`
+//! rather than the clock. Virtual time is held still so no extra ticks sneak
+//! in between steps. Otherwise the app runs the simulation exactly as the game
+//! does, executors included, so a nondeterministic schedule shows up here.
+
+use std::time::Duration;
+
+use bevy::app::Plugins;
+use bevy::prelude::*;
+use bevy::state::app::StatesPlugin;
+use bevy::time::TimeUpdateStrategy;
+
+use acre::aphids::AphidColony;
+use acre::designations::{DesignationKind, Designations, dig_approach};
+use acre::invariants::InvariantChecks;
+use acre::mushrooms::WildMushroom;
+use acre::seeds::SeedItem;
+use acre::world::{FoodStores, FungusGarden, Plant, Sapling, WorldOptions};
+use acre::{
+    Ant, Caste, GameState, NestLocation, ResetRun, SimulationPlugin, SimulationTick, TileKind,
+    WORLD_SIZE, WorldGrid,
+};
+
+/// Seed every test generates its world from
+const SEED: u64 = 1;
+/// Longer than an unfed queen can last, at the default hunger rate
+const STARVATION_TICKS: u64 = 2500;
+/// Plenty of time for idle workers to take up a designation and dig it
+const DIG_TICKS: u64 = 2000;
+
+/// A headless app with the world generated and the colony founded, ready to
+/// step
+fn simulation(seed: u64, options: WorldOptions) -> App {
+    simulation_with(seed, options, ())
+}
+
+/// A headless simulation with some plugins from outside the simulation too
+fn simulation_with<M>(seed: u64, options: WorldOptions, plugins: impl Plugins<M>) -> App {
+    let mut app = App::new();
+    app.add_plugins((MinimalPlugins, StatesPlugin))
+        .insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO))
+        .insert_state(GameState::Playing)
+        .add_plugins(SimulationPlugin { seed })
+        .add_plugins(plugins)
+        .insert_resource(options)
+        // Any broken invariant fails the test, in debug builds
+        .insert_resource(InvariantChecks { panic: true });
+    app.finish();
+    app.cleanup();
+    // Startup generates the world and spawns the colony
+    app.update();
+    app
+}
+
+/// Run the simulation for a number of ticks
+fn run(app: &mut App, ticks: u64) {
+    for _ in 0..ticks {
+        acre::step(app.world_mut());
+    }
+}
+
+fn count_castes(app: &mut App, caste: Caste) -> usize {
+    app.world_mut()
+        .query_filtered::<&Caste, With<Ant>>()
+        .iter(app.world())
+        .filter(|c| **c == caste)
+        .count()
+}
+
+/// Take every source of food out of the world: plants, aphids, seeds and
+/// mushrooms on the surface, and the stores and garden in the nest
+fn strip_food(app: &mut App) {
+    let world = app.world_mut();
+    let sources: Vec<Entity> = world
+        .query_filtered::<Entity, Or<(
+            With<Plant>,
+            With<Sapling>,
+            With<AphidColony>,
+            With<SeedItem>,
+            With<WildMushroom>,
+        )>>()
+        .iter(world)
+        .collect();
+    for entity in sources {
+        world.despawn(entity);
+    }
+
+    let mut stores = world.resource_mut::<FoodStores>();
+    stores.food = 0;
+    stores.honeydew = 0;
+    stores.seeds = 0;
+
+    let mut garden = world.resource_mut::<FungusGarden>();
+    garden.leaves = 0;
+    garden.plots.clear();
+}
+
+#[test]
+fn colony_survives_with_trees() {
+    let mut app = simulation(SEED, WorldOptions::default());
+    let workers = app.world_mut().query::<&Ant>().iter(app.world()).count() - 1;
+    assert!(workers > 0, "the colony should start with workers");
+
+    run(&mut app, STARVATION_TICKS);
+
+    assert_eq!(
+        count_castes(&mut app, Caste::Queen),
+        1,
+        "the queen should be fed from the garden"
+    );
+    let foragers = count_castes(&mut app, Caste::Forager);
+    assert!(foragers > 0, "some foragers should still be alive");
+}
+
+#[test]
+fn colony_starves_without_food() {
+    let mut app = simulation(
+        SEED,
+        WorldOptions {
+            trees: Some(0),
+            ..default()
+        },
+    );
+    assert_eq!(count_castes(&mut app, Caste::Queen), 1);
+
+    // Mushrooms keep popping up, so keep the surface bare as it runs
+    for _ in 0..STARVATION_TICKS / 100 {
+        strip_food(&mut app);
+        run(&mut app, 100);
+    }
+
+    assert_eq!(
+        count_castes(&mut app, Caste::Queen),
+        0,
+        "the queen should starve with nothing to eat"
+    );
+}
+
+#[test]
+fn dig_designations_complete() {
+    let mut app = simulation(SEED, WorldOptions::default());
+
+    // The closest soil to the nest that a digger can reach
+    let nest = app.world().resource::<NestLocation>();
+    let nest = (nest.x, nest.y, nest.z);
+    let world_grid = app.world().resource::<WorldGrid>();
+    let target = (0..WORLD_SIZE)
+        .flat_map(|z| (0..WORLD_SIZE).flat_map(move |y| (0..WORLD_SIZE).map(move |x| (x, y, z))))
+        .filter(|&(x, y, z)| world_grid.tiles[z][y][x] == TileKind::Dirt)
+        .filter(|&coord| dig_approach(world_grid, coord).is_some())
+        .min_by_key(|&(x, y, z)| x.abs_diff(nest.0) + y.abs_diff(nest.1) + z.abs_diff(nest.2))
+        .expect("there should be soil next to the nest");
+
+    app.world_mut()
+        .resource_mut::<Designations>()
+        .tiles
+        .insert(target, DesignationKind::Chamber);
+
+    let mut ticks = 0;
+    while app
+        .world()
+        .resource::<Designations>()
+        .get(target.0, target.1, target.2)
+        .is_some()
+    {
+        assert!(
+            ticks < DIG_TICKS,
+            "the designation at {:?} should be dug within {} ticks",
+            target,
+            DIG_TICKS
+        );
+        run(&mut app, 10);
+        ticks += 10;
+    }
+
+    let (x, y, z) = target;
+    assert_eq!(
+        app.world().resource::<WorldGrid>().tiles[z][y][x],
+        TileKind::Chamber
+    );
+}
+
+#[test]
+fn same_seed_same_colony() {
+    let positions = |seed| {
+        let mut app = simulation(seed, WorldOptions::default());
+        run(&mut app, 200);
+        let mut positions: Vec<_> = app
+            .world_mut()
+            .query_filtered::<&acre::GridPosition, With<Ant>>()
+            .iter(app.world())
+            .map(|pos| (pos.x, pos.y, pos.z))
+            .collect();
+        positions.sort_unstable();
+        positions
+    };
+
+    assert_eq!(positions(SEED), positions(SEED));
+}
+
+#[test]
+fn reset_run_clears_the_colony() {
+    let mut app = simulation(SEED, WorldOptions::default());
+    run(&mut app, 100);
+
+    app.world_mut().run_schedule(ResetRun);
+
+    let world = app.world_mut();
+    assert_eq!(world.query::<&Ant>().iter(world).count(), 0);
+    assert_eq!(world.query::<&Plant>().iter(world).count(), 0);
+    assert_eq!(world.resource::<SimulationTick>().0, 0);
+    assert!(world.resource::<FungusGarden>().plots.is_empty());
+}
+
+#[cfg(feature = "ui")]
+#[test]
+fn rewind_mid_task_keeps_stepping() {
+    use acre::brood::{Brood, spawn_brood};
+    use acre::rewind::RewindPlugin;
+    use acre::{GridPosition, Task};
+
+    // Keys are pressed by hand, without the input plugin clearing them
+    let keyboard = |app: &mut App| {
+        app.init_resource::<ButtonInput<KeyCode>>();
+    };
+    let mut app = simulation_with(SEED, WorldOptions::default(), (keyboard, RewindPlugin));
+    run(&mut app, 100);
+
+    // Set a nurse to tend a fresh egg beside the queen
+    let world = app.world_mut();
+    let queen_pos = world
+        .query::<(&GridPosition, &Caste)>()
+        .iter(world)
+        .find(|(_, caste)| **caste == Caste::Queen)
+        .map(|(pos, _)| *pos)
+        .expect("the colony has a queen");
+    let brood = spawn_brood(&mut world.commands(), &queen_pos, Caste::Forager);
+    world.flush();
+    let nurse = world
+        .query::<(Entity, &Caste)>()
+        .iter(world)
+        .find(|(_, caste)| **caste == Caste::Nurse)
+        .map(|(entity, _)| entity)
+        .expect("the colony has a nurse");
+    world.entity_mut(nurse).insert(Task::Nursing { brood });
+
+    // Let enough real time pass for a snapshot, without the simulation moving
+    app.world_mut().resource_mut::<Time<Virtual>>().pause();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::from_secs(6)));
+    app.update();
+    app.insert_resource(TimeUpdateStrategy::ManualDuration(Duration::ZERO));
+
+    // The egg goes, then the colony is rewound to before it did
+    app.world_mut().despawn(brood);
+    run(&mut app, 50);
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .press(KeyCode::Backspace);
+    app.update();
+    app.world_mut()
+        .resource_mut::<ButtonInput<KeyCode>>()
+        .reset(KeyCode::Backspace);
+
+    let world = app.world_mut();
+    let Some(Task::Nursing { brood: restored }) = world.get::<Task>(nurse).cloned() else {
+        panic!("the nurse is back to tending the egg");
+    };
+    assert_ne!(restored, brood);
+    assert!(world.get::<Brood>(restored).is_some());
+
+    run(&mut app, 200);
+}